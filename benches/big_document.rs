@@ -0,0 +1,60 @@
+//! Benchmark for parsing and diffing large documents
+//!
+//! Measures the stages `rjd` runs on big inputs: parsing the text, estimating
+//! the memory of the parsed document (the `--max-memory` check) and diffing
+//! two versions that differ in one record out of a hundred.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rjd::{diff, estimated_memory};
+use serde_json::{json, Value};
+
+/// A document of `records` user records, with every hundredth one edited if `edited`
+fn create_document(records: usize, edited: bool) -> Value {
+    let users: Vec<Value> = (0..records)
+        .map(|i| {
+            let active = !(edited && i % 100 == 0);
+            json!({
+                "id": i,
+                "name": format!("user{}", i),
+                "email": format!("user{}@example.com", i),
+                "active": active,
+                "roles": ["reader", "writer"],
+                "profile": {"age": 20 + i % 50, "score": i as f64 / 7.0}
+            })
+        })
+        .collect();
+    json!({"version": 1, "users": users})
+}
+
+fn bench_big_documents(c: &mut Criterion) {
+    let mut group = c.benchmark_group("big_document");
+    group.sample_size(10);
+
+    for records in [1_000, 10_000, 100_000].iter() {
+        let old = create_document(*records, false);
+        let new = create_document(*records, true);
+        let text = old.to_string();
+        group.throughput(Throughput::Bytes(text.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("parse", records), &text, |b, text| {
+            b.iter(|| serde_json::from_str::<Value>(std::hint::black_box(text)).unwrap())
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("estimated_memory", records),
+            &old,
+            |b, old| b.iter(|| estimated_memory(std::hint::black_box(old))),
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("diff", records),
+            &(&old, &new),
+            |b, (old, new)| b.iter(|| diff(std::hint::black_box(old), std::hint::black_box(new))),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_big_documents);
+criterion_main!(benches);
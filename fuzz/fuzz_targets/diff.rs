@@ -0,0 +1,31 @@
+//! Diffing and formatting arbitrary document pairs must never panic
+//!
+//! Inputs are two JSON documents separated by a `---` line, the format written
+//! by `rjd fuzz-corpus export`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rjd::{create_formatter, diff, split_json_pair};
+use serde_json::Value;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok((old, new)) = split_json_pair(text, Some("---")) else {
+        return;
+    };
+    let (Ok(old), Ok(new)) = (
+        serde_json::from_str::<Value>(old),
+        serde_json::from_str::<Value>(new),
+    ) else {
+        return;
+    };
+
+    let changes = diff(&old, &new);
+    for format in ["changes", "after", "rfc6902", "summary"] {
+        let formatter = create_formatter(format, true).unwrap();
+        formatter.format(&changes).unwrap();
+    }
+});
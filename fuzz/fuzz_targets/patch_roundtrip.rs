@@ -0,0 +1,32 @@
+//! Applying the rfc6902 output to the old document must yield the new one
+//!
+//! Inputs are two JSON documents separated by a `---` line, the format written
+//! by `rjd fuzz-corpus export`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rjd::{apply_patch, create_formatter, diff, split_json_pair};
+use serde_json::Value;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok((old, new)) = split_json_pair(text, Some("---")) else {
+        return;
+    };
+    let (Ok(old), Ok(new)) = (
+        serde_json::from_str::<Value>(old),
+        serde_json::from_str::<Value>(new),
+    ) else {
+        return;
+    };
+
+    let patch = create_formatter("rfc6902", false)
+        .unwrap()
+        .format(&diff(&old, &new))
+        .unwrap();
+    let patch: Value = serde_json::from_str(&patch).unwrap();
+    assert_eq!(apply_patch(&old, &patch).as_ref(), Ok(&new));
+});
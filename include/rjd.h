@@ -0,0 +1,60 @@
+/*
+ * rjd C interface
+ *
+ * Build with `cargo build --release --features ffi` and link against the
+ * resulting librjd shared library. All strings are NUL-terminated UTF-8.
+ *
+ * No function lets a Rust panic unwind into the caller: a panic is caught
+ * inside the library and reported as RJD_ERR_PANIC, or as a NULL result
+ * for functions that return a pointer.
+ */
+#ifndef RJD_H
+#define RJD_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Error codes returned by rjd_last_error_code() */
+#define RJD_OK 0
+#define RJD_ERR_NULL_ARGUMENT 1
+#define RJD_ERR_INVALID_UTF8 2
+#define RJD_ERR_INVALID_JSON 3
+#define RJD_ERR_INVALID_OPTIONS 4
+#define RJD_ERR_FORMAT 5
+#define RJD_ERR_PANIC 6
+
+/*
+ * Diff two JSON documents and return the formatted report.
+ *
+ * options_json is a JSON object described by rjd_options_schema(), or NULL
+ * for the defaults (the "changes" format). Returns NULL on failure; see
+ * rjd_last_error_code() and rjd_last_error_message(). Free the result with
+ * rjd_string_free().
+ */
+char *rjd_diff(const char *old_json, const char *new_json, const char *options_json);
+
+/* Free a string returned by rjd_diff(). NULL is ignored. */
+void rjd_string_free(char *text);
+
+/* Error code of the last rjd_diff() call on this thread (RJD_OK on success). */
+int rjd_last_error_code(void);
+
+/*
+ * Description of the last error on this thread, or NULL after a successful
+ * call. Owned by the library; valid until the next rjd_diff() call on the
+ * same thread.
+ */
+const char *rjd_last_error_message(void);
+
+/*
+ * JSON Schema of the options document. Static; do not free. NULL only if
+ * the library panicked while building it.
+ */
+const char *rjd_options_schema(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RJD_H */
@@ -0,0 +1,134 @@
+//! Base64-encoded binary values
+//!
+//! Blobs such as certificates, images or compressed payloads are often
+//! stored in JSON as base64 strings. Printing both versions of a changed blob
+//! says little, so the diff can report a [`summary`] of each instead: the
+//! decoded size and SHA-256 hash.
+
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD};
+use base64::Engine;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+/// Shortest string the heuristic of [`looks_like_base64`] treats as a blob
+pub const MIN_DETECTED_LEN: usize = 64;
+
+/// Decode standard or URL-safe base64, with or without padding
+///
+/// Line breaks and other ASCII whitespace are skipped, and a
+/// `data:<type>;base64,` prefix is removed.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::binary::decode_base64;
+///
+/// assert_eq!(decode_base64("aGVsbG8=").unwrap(), b"hello");
+/// assert_eq!(decode_base64("data:text/plain;base64,aGVsbG8").unwrap(), b"hello");
+/// assert!(decode_base64("not base64!").is_none());
+/// ```
+pub fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let payload = match text.strip_prefix("data:") {
+        Some(rest) => rest.split_once(";base64,")?.1,
+        None => text,
+    };
+    let compact: String = payload
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+    if compact.is_empty() {
+        return None;
+    }
+    [STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD]
+        .iter()
+        .find_map(|engine| engine.decode(&compact).ok())
+}
+
+/// Guess whether a string is a base64-encoded blob rather than text
+///
+/// The string must be at least [`MIN_DETECTED_LEN`] characters of the base64
+/// alphabet that decode, and mix upper and lower case letters with digits or
+/// symbols, which words, identifiers and hex digests do not.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::binary::looks_like_base64;
+///
+/// assert!(looks_like_base64(&"iVBORw0KGgoAAAANSUhEUgAAAAEAAAAB".repeat(2)));
+/// assert!(!looks_like_base64(&"0123456789abcdef".repeat(4)));
+/// assert!(!looks_like_base64("short"));
+/// ```
+pub fn looks_like_base64(text: &str) -> bool {
+    let payload = text
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"))
+        .map_or(text, |(_, payload)| payload);
+    let trimmed = payload.trim_end_matches('=');
+    if trimmed.len() < MIN_DETECTED_LEN {
+        return false;
+    }
+    let alphabet =
+        |c: char| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '-' | '_' | '\n' | '\r');
+    let has = |test: fn(&char) -> bool| trimmed.chars().any(|c| test(&c));
+    trimmed.chars().all(alphabet)
+        && has(char::is_ascii_uppercase)
+        && has(char::is_ascii_lowercase)
+        && (has(char::is_ascii_digit) || has(|c| matches!(c, '+' | '/' | '-' | '_')))
+        && decode_base64(payload).is_some()
+}
+
+/// The value reported instead of a blob: its size in bytes and SHA-256 hash
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::binary::summary;
+/// use serde_json::json;
+///
+/// assert_eq!(
+///     summary(b"hello"),
+///     json!({"$binary": {
+///         "bytes": 5,
+///         "sha256": "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+///     }})
+/// );
+/// ```
+pub fn summary(bytes: &[u8]) -> Value {
+    let hash: String = Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    json!({"$binary": {"bytes": bytes.len(), "sha256": hash}})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_variants() {
+        assert_eq!(decode_base64("+/8=").unwrap(), [0xfb, 0xff]);
+        assert_eq!(decode_base64("-_8").unwrap(), [0xfb, 0xff]);
+        assert_eq!(decode_base64("aGVs\nbG8=").unwrap(), b"hello");
+        assert!(decode_base64("").is_none());
+        assert!(decode_base64("data:image/png,raw").is_none());
+    }
+
+    #[test]
+    fn test_detection_heuristic() {
+        let blob = STANDARD.encode((0..=255u8).collect::<Vec<_>>());
+        assert!(looks_like_base64(&blob));
+        assert!(looks_like_base64(&format!(
+            "data:application/octet-stream;base64,{}",
+            blob
+        )));
+
+        // Long text, identifiers and hex digests are not blobs
+        assert!(!looks_like_base64(&"lorem ipsum dolor sit amet ".repeat(4)));
+        assert!(!looks_like_base64(&"CamelCaseIdentifier".repeat(4)));
+        assert!(!looks_like_base64(
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        ));
+    }
+}
@@ -0,0 +1,257 @@
+//! Options and diff entry point shared by the C and JavaScript interfaces
+//!
+//! Both interfaces take the diff options as one JSON document, described by
+//! [`OPTIONS_SCHEMA`], and return the report of a built-in formatter.
+
+use crate::{
+    create_formatter_with_options, diff_with_options, ArrayDiff, DiffOptions, FormatterOptions,
+    SimilarityThreshold,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::time::Duration;
+
+/// JSON Schema of the options document
+pub(crate) const OPTIONS_SCHEMA: &str = r#"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "rjd diff options",
+  "type": "object",
+  "additionalProperties": false,
+  "properties": {
+    "format": {"type": "string", "default": "changes", "description": "Output format, as rjd --format"},
+    "sort": {"type": "boolean", "default": false, "description": "Sort keys in JSON output"},
+    "maxDepth": {"type": ["integer", "null"], "minimum": 0, "description": "Report differences below this depth as one change of the subtree"},
+    "maxChanges": {"type": ["integer", "null"], "minimum": 0, "description": "Stop after this many changes"},
+    "ignore": {"type": "array", "items": {"type": "string"}, "description": "Paths to leave out of the report: JSON Pointers (/user/id), dot notation (user.id) or JSONPath queries ($..token), as for --ignore"},
+    "ignoreKeyCase": {"type": "boolean", "default": false},
+    "sortKeys": {"type": "boolean", "default": false, "description": "Compare object keys in alphabetical order instead of document order"},
+    "normalizeDates": {"type": "boolean", "default": false},
+    "dateToleranceMs": {"type": "integer", "minimum": 0, "default": 0},
+    "coerceNumbers": {"type": "boolean", "default": false},
+    "coerceNumberStrings": {"type": "boolean", "default": false},
+    "nullAsAbsent": {"type": "boolean", "default": false},
+    "emptyAsAbsent": {"type": "boolean", "default": false},
+    "structureOnly": {"type": "boolean", "default": false},
+    "arrayDiff": {"enum": ["positional", "similarity"], "default": "positional", "description": "Pair array elements by index or by structural similarity"},
+    "arrayStrategies": {"type": "object", "additionalProperties": {"type": "string"}, "description": "Pairing of the arrays matching each pattern: positional, unordered, similarity[:THRESHOLD] or keyed:KEY"},
+    "similarityThreshold": {"type": "number", "minimum": 0, "maximum": 1, "default": 0.7, "description": "Least similarity of elements paired with arrayDiff similarity"},
+    "arraySets": {"type": "boolean", "default": false, "description": "Compare arrays of scalars as sets, reporting added, removed and common elements"},
+    "parseEmbeddedJson": {"type": "boolean", "default": false},
+    "binaryPaths": {"type": "array", "items": {"type": "string"}},
+    "detectBinary": {"type": "boolean", "default": false},
+    "decodeBinaryJson": {"type": "boolean", "default": false}
+  }
+}"#;
+
+/// The options document, see [`OPTIONS_SCHEMA`]
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub(crate) struct BindingOptions {
+    format: Option<String>,
+    sort: bool,
+    max_depth: Option<usize>,
+    max_changes: Option<usize>,
+    ignore: Vec<String>,
+    ignore_key_case: bool,
+    sort_keys: bool,
+    normalize_dates: bool,
+    date_tolerance_ms: u64,
+    coerce_numbers: bool,
+    coerce_number_strings: bool,
+    null_as_absent: bool,
+    empty_as_absent: bool,
+    structure_only: bool,
+    array_diff: ArrayMode,
+    array_strategies: Map<String, Value>,
+    similarity_threshold: Option<f64>,
+    array_sets: bool,
+    parse_embedded_json: bool,
+    binary_paths: Vec<String>,
+    detect_binary: bool,
+    decode_binary_json: bool,
+}
+
+impl BindingOptions {
+    fn diff_options(&self) -> DiffOptions {
+        DiffOptions {
+            max_depth: self.max_depth,
+            max_changes: self.max_changes,
+            ignore_key_case: self.ignore_key_case,
+            sort_keys: self.sort_keys,
+            normalize_dates: self.normalize_dates,
+            date_tolerance: Duration::from_millis(self.date_tolerance_ms),
+            coerce_numbers: self.coerce_numbers,
+            coerce_number_strings: self.coerce_number_strings,
+            null_as_absent: self.null_as_absent,
+            empty_as_absent: self.empty_as_absent,
+            structure_only: self.structure_only,
+            array_diff: match self.array_diff {
+                ArrayMode::Positional => ArrayDiff::Positional,
+                ArrayMode::Similarity => ArrayDiff::Similarity(
+                    self.similarity_threshold
+                        .and_then(SimilarityThreshold::new)
+                        .unwrap_or_default(),
+                ),
+            },
+            array_strategies: self
+                .array_strategies
+                .iter()
+                .filter_map(|(pattern, strategy)| {
+                    Some((pattern.clone(), strategy.as_str()?.parse().ok()?))
+                })
+                .collect(),
+            array_sets: self.array_sets,
+            parse_embedded_json: self.parse_embedded_json,
+            binary_paths: self.binary_paths.clone(),
+            detect_binary: self.detect_binary,
+            decode_binary_json: self.decode_binary_json,
+            parallel: false,
+            comparator: None,
+        }
+    }
+}
+
+/// How array elements are paired, see [`ArrayDiff`]
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ArrayMode {
+    #[default]
+    Positional,
+    Similarity,
+}
+
+/// Why a diff requested through a binding failed
+#[derive(Debug)]
+pub(crate) enum BindingError {
+    /// The old or new document was not valid JSON
+    InvalidJson(String),
+    /// The options document was invalid, or named an unknown format or a bad pattern
+    InvalidOptions(String),
+    /// The formatter failed
+    Format(String),
+}
+
+impl fmt::Display for BindingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingError::InvalidJson(message)
+            | BindingError::InvalidOptions(message)
+            | BindingError::Format(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for BindingError {}
+
+/// Parse one of the compared documents; `name` is "old" or "new"
+pub(crate) fn parse_document(text: &str, name: &str) -> Result<Value, BindingError> {
+    serde_json::from_str(text)
+        .map_err(|e| BindingError::InvalidJson(format!("{} is not valid JSON: {}", name, e)))
+}
+
+/// Parse the options document, or use the defaults without one
+pub(crate) fn parse_options(text: Option<&str>) -> Result<BindingOptions, BindingError> {
+    let Some(text) = text else {
+        return Ok(BindingOptions::default());
+    };
+    let options: BindingOptions = serde_json::from_str(text)
+        .map_err(|e| BindingError::InvalidOptions(format!("invalid options: {}", e)))?;
+    for pattern in &options.ignore {
+        crate::validate_pattern(pattern)
+            .map_err(|e| BindingError::InvalidOptions(e.to_string()))?;
+    }
+    for (pattern, strategy) in &options.array_strategies {
+        crate::validate_pattern(pattern)
+            .map_err(|e| BindingError::InvalidOptions(e.to_string()))?;
+        strategy
+            .as_str()
+            .ok_or_else(|| "expected a string".to_string())
+            .and_then(|text| text.parse::<ArrayDiff>().map(drop))
+            .map_err(|e| {
+                BindingError::InvalidOptions(format!(
+                    "invalid options: arrayStrategies.{}: {}",
+                    pattern, e
+                ))
+            })?;
+    }
+    if let Some(threshold) = options.similarity_threshold {
+        if SimilarityThreshold::new(threshold).is_none() {
+            return Err(BindingError::InvalidOptions(format!(
+                "invalid options: similarityThreshold must be between 0 and 1, got {}",
+                threshold
+            )));
+        }
+    }
+    Ok(options)
+}
+
+/// Diff two documents and format the report as the options ask
+pub(crate) fn diff_report(
+    old: &Value,
+    new: &Value,
+    options: &BindingOptions,
+) -> Result<String, BindingError> {
+    let mut changes = diff_with_options(old, new, &options.diff_options());
+    if !options.ignore.is_empty() {
+        changes = changes.filter_ignore_patterns(&options.ignore);
+    }
+    let format = options.format.as_deref().unwrap_or("changes");
+    let formatter = create_formatter_with_options(format, &FormatterOptions::new(options.sort))
+        .map_err(|e| BindingError::InvalidOptions(e.to_string()))?;
+    if formatter.uses_before() {
+        changes.before = Some(old.clone());
+    }
+    formatter
+        .format(&changes)
+        .map_err(|e| BindingError::Format(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_schema_lists_every_option() {
+        let schema: Value = serde_json::from_str(OPTIONS_SCHEMA).unwrap();
+        let documented: Vec<&String> = schema["properties"].as_object().unwrap().keys().collect();
+        let defaults = serde_json::to_value(BindingOptions::default()).unwrap();
+        let accepted: Vec<&String> = defaults.as_object().unwrap().keys().collect();
+        assert_eq!(documented, accepted);
+    }
+
+    #[test]
+    fn test_diff_report() {
+        let options = parse_options(Some(r#"{"format": "rfc6902", "ignore": ["/ts"]}"#)).unwrap();
+        let old = parse_document(r#"{"a": 1, "ts": 1}"#, "old").unwrap();
+        let new = parse_document(r#"{"a": 2, "ts": 2}"#, "new").unwrap();
+        let report: Value =
+            serde_json::from_str(&diff_report(&old, &new, &options).unwrap()).unwrap();
+        assert_eq!(
+            report,
+            serde_json::json!([{"op": "replace", "path": "/a", "value": 2}])
+        );
+        assert!(matches!(
+            parse_options(Some(r#"{"ignore": ["items[x"]}"#)),
+            Err(BindingError::InvalidOptions(_))
+        ));
+        assert!(matches!(
+            parse_options(Some(
+                r#"{"arrayDiff": "similarity", "similarityThreshold": 2}"#
+            )),
+            Err(BindingError::InvalidOptions(_))
+        ));
+        assert!(matches!(
+            parse_options(Some(r#"{"arrayStrategies": {"/tags": "sorted"}}"#)),
+            Err(BindingError::InvalidOptions(_))
+        ));
+
+        let options =
+            parse_options(Some(r#"{"arrayStrategies": {"/tags": "unordered"}}"#)).unwrap();
+        let old = parse_document(r#"{"tags": ["a", "b"]}"#, "old").unwrap();
+        let new = parse_document(r#"{"tags": ["b", "a"]}"#, "new").unwrap();
+        let report: Value =
+            serde_json::from_str(&diff_report(&old, &new, &options).unwrap()).unwrap();
+        assert!(report["modified"].as_array().unwrap().is_empty());
+    }
+}
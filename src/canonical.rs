@@ -0,0 +1,281 @@
+//! Canonical forms of JSON documents
+//!
+//! Storing documents in a canonical form keeps later diffs free of key order
+//! and number spelling noise. [`canonicalize`] sorts keys and normalizes
+//! numbers; [`to_jcs_string`] writes the RFC 8785 JSON Canonicalization
+//! Scheme form, suitable for hashing and signing; [`jcs_normalize`] prepares
+//! documents for a comparison that ignores how they were serialized.
+
+use crate::decimal::Decimal;
+use serde_json::{Map, Number, Value};
+use std::cmp::Ordering;
+use unicode_normalization::UnicodeNormalization;
+
+/// Sort object keys and normalize numbers, recursively
+///
+/// Keys are sorted by code point. Whole floats that can be represented exactly
+/// become integers (`1.0` and `1e2` become `1` and `100`), and `-0.0` becomes
+/// `0`. With the `arbitrary-precision` feature, numbers are instead respelled
+/// without changing their value, as ECMAScript would write them but with
+/// every digit kept: `2.50` becomes `2.5` and `1e21` becomes `1e+21`, while
+/// integers written in full are kept as they are, however long.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::canonicalize;
+/// use serde_json::json;
+///
+/// let value: serde_json::Value = serde_json::from_str(r#"{"b": 1.0, "a": [2e2, 0.5]}"#).unwrap();
+/// assert_eq!(
+///     serde_json::to_string(&canonicalize(&value)).unwrap(),
+///     r#"{"a":[200,0.5],"b":1}"#
+/// );
+/// ```
+pub fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            let sorted: Map<String, Value> = entries
+                .into_iter()
+                .map(|(key, value)| (key.clone(), canonicalize(value)))
+                .collect();
+            Value::Object(sorted)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        Value::Number(number) => Value::Number(normalize_number(number)),
+        _ => value.clone(),
+    }
+}
+
+/// Serialize a value in the RFC 8785 canonical form
+///
+/// The output has no whitespace, object keys sorted by their UTF-16 code
+/// units, numbers written as ECMAScript does (all numbers are IEEE 754
+/// doubles, so integers beyond 2^53 lose precision) and strings with only
+/// the required escapes.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::to_jcs_string;
+/// use serde_json::json;
+///
+/// let value = json!({"b": [1.5e-7, 1e21, 100.0], "a": "\u{20ac}\n"});
+/// assert_eq!(
+///     to_jcs_string(&value),
+///     "{\"a\":\"\u{20ac}\\n\",\"b\":[1.5e-7,1e+21,100]}"
+/// );
+/// ```
+pub fn to_jcs_string(value: &Value) -> String {
+    let mut out = String::new();
+    write_jcs(value, &mut out);
+    out
+}
+
+/// Normalize numbers as RFC 8785 does and strings to Unicode NFC, recursively
+///
+/// Every number becomes the IEEE 754 double it denotes, so `1`, `1.0` and
+/// `1e0` are the same value (and integers beyond 2^53 are rounded, as any
+/// JCS implementation would). RFC 8785 leaves strings as they are; keys and
+/// strings are additionally normalized to NFC here, so a precomposed `é` and
+/// `e` followed by a combining accent compare equal. Key order is kept.
+///
+/// Two documents that serialize to the same canonical form are equal after
+/// normalization, so diffing them reports no changes.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::{diff, jcs_normalize};
+///
+/// let old: serde_json::Value = serde_json::from_str(r#"{"caf\u00e9": 1.50, "n": 100}"#).unwrap();
+/// let new: serde_json::Value = serde_json::from_str(r#"{"n": 1e2, "cafe\u0301": 1.5}"#).unwrap();
+///
+/// assert!(!diff(&old, &new).is_empty());
+/// assert!(diff(&jcs_normalize(&old), &jcs_normalize(&new)).is_empty());
+/// ```
+pub fn jcs_normalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.nfc().collect(), jcs_normalize(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(jcs_normalize).collect()),
+        Value::Number(number) => Value::Number(double_number(number)),
+        Value::String(text) => Value::String(text.nfc().collect()),
+        Value::Bool(_) | Value::Null => value.clone(),
+    }
+}
+
+fn write_jcs(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| utf16_cmp(a, b));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(key, out);
+                out.push(':');
+                write_jcs(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_jcs(item, out);
+            }
+            out.push(']');
+        }
+        Value::Number(number) => out.push_str(&ecmascript_number(number.as_f64().unwrap_or(0.0))),
+        Value::String(text) => write_string(text, out),
+        Value::Bool(_) | Value::Null => out.push_str(&value.to_string()),
+    }
+}
+
+/// Quote a string; serde_json escapes exactly the characters RFC 8785 requires
+fn write_string(text: &str, out: &mut String) {
+    out.push_str(&Value::from(text).to_string());
+}
+
+fn utf16_cmp(a: &str, b: &str) -> Ordering {
+    a.encode_utf16().cmp(b.encode_utf16())
+}
+
+/// Floats with a magnitude below this are written as integers when whole (2^53)
+#[cfg(not(feature = "arbitrary-precision"))]
+const EXACT_INTEGER_LIMIT: f64 = 9_007_199_254_740_992.0;
+
+#[cfg(not(feature = "arbitrary-precision"))]
+fn normalize_number(number: &Number) -> Number {
+    match number.as_f64() {
+        Some(float) if number.is_f64() && float.fract() == 0.0 => {
+            if float.abs() < EXACT_INTEGER_LIMIT {
+                Number::from(float as i64)
+            } else {
+                number.clone()
+            }
+        }
+        _ => number.clone(),
+    }
+}
+
+/// A number in its shortest exact spelling (see [`Decimal::to_ecmascript`]);
+/// integers written without a fraction or exponent are kept as they are
+#[cfg(feature = "arbitrary-precision")]
+fn normalize_number(number: &Number) -> Number {
+    let text = number.to_string();
+    let plain_integer = text
+        .trim_start_matches('-')
+        .bytes()
+        .all(|b| b.is_ascii_digit());
+    if plain_integer && text != "-0" {
+        return number.clone();
+    }
+    Decimal::parse(&text)
+        .and_then(|decimal| decimal.to_ecmascript().parse().ok())
+        .unwrap_or_else(|| number.clone())
+}
+
+/// The double a number denotes, spelled as ECMAScript writes it
+fn double_number(number: &Number) -> Number {
+    ecmascript_number(number.as_f64().unwrap_or(0.0))
+        .parse()
+        .unwrap_or_else(|_| number.clone())
+}
+
+/// Format a double as ECMAScript's `Number.prototype.toString` does
+fn ecmascript_number(value: f64) -> String {
+    // Rust's `{:e}` gives the shortest round-tripping digits, e.g. "1.5e-7"
+    Decimal::parse(&format!("{:e}", value))
+        .map_or_else(|| value.to_string(), |decimal| decimal.to_ecmascript())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_ecmascript_numbers() {
+        let cases = [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1.0, "1"),
+            (-1.5, "-1.5"),
+            (123456789.0, "123456789"),
+            (1e20, "100000000000000000000"),
+            (1e21, "1e+21"),
+            (1.5e300, "1.5e+300"),
+            (0.000001, "0.000001"),
+            (1e-7, "1e-7"),
+            (-1.2345e-10, "-1.2345e-10"),
+            (0.1 + 0.2, "0.30000000000000004"),
+            (9007199254740993.0, "9007199254740992"),
+            (f64::MAX, "1.7976931348623157e+308"),
+            (f64::MIN_POSITIVE, "2.2250738585072014e-308"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(ecmascript_number(value), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_jcs_key_order_and_strings() {
+        // U+1F600 sorts after U+E000 by code point, but its UTF-16 surrogates come first
+        let value = json!({"\u{1f600}": 1, "\u{e000}": 2, "a": {"z": null, "b": true}});
+        assert_eq!(
+            to_jcs_string(&value),
+            "{\"a\":{\"b\":true,\"z\":null},\"\u{1f600}\":1,\"\u{e000}\":2}"
+        );
+        assert_eq!(
+            to_jcs_string(&json!("tab\t quote\" \u{1} slash/")),
+            r#""tab\t quote\" \u0001 slash/""#
+        );
+    }
+
+    #[test]
+    fn test_jcs_normalize() {
+        let old: Value = serde_json::from_str(
+            r#"{"a": [1.0, 18446744073709551615, "\u00c5"], "e\u0301": 0.10}"#,
+        )
+        .unwrap();
+        let new: Value =
+            serde_json::from_str(r#"{"a": [1, 1.8446744073709552e19, "A\u030a"], "\u00e9": 1e-1}"#)
+                .unwrap();
+        assert_eq!(jcs_normalize(&old), jcs_normalize(&new));
+        assert_ne!(jcs_normalize(&json!([1])), jcs_normalize(&json!([1.5])));
+    }
+
+    #[test]
+    #[cfg(not(feature = "arbitrary-precision"))]
+    fn test_canonicalize_numbers() {
+        let value: Value = serde_json::from_str("[1.0, -0.0, 1e2, 0.5, 1e300, 7]").unwrap();
+        assert_eq!(
+            serde_json::to_string(&canonicalize(&value)).unwrap(),
+            "[1,0,100,0.5,1e300,7]"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary-precision")]
+    fn test_canonicalize_numbers_exactly() {
+        let value: Value = serde_json::from_str(
+            "[1.0, -0.0, 1e2, 0.50, 1e300, 7, 123456789012345678901234567890]",
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_string(&canonicalize(&value)).unwrap(),
+            "[1,0,100,0.5,1e+300,7,123456789012345678901234567890]"
+        );
+    }
+}
@@ -0,0 +1,569 @@
+//! Saved change sets
+//!
+//! A change set is a self-contained JSON document holding a diff result, so an
+//! expensive diff can be computed once and rendered in several formats later
+//! (`rjd --save-changes` and `rjd render`). Paths are stored as arrays of
+//! segments, keys as strings and array indices as numbers, so that they
+//! round-trip exactly.
+//!
+//! The same layout can be encoded as MessagePack ([`Changes::to_msgpack`]),
+//! which is several times smaller and faster to read than JSON when change
+//! sets are stored in bulk.
+
+use crate::json_path::{JsonPath, PathSegment};
+use crate::types::{Change, Changes, Truncation, TruncationReason, Warning, WarningKind};
+use serde::de::Error as _;
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+/// Current version of the change set layout
+pub const CHANGE_SET_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangeSet {
+    change_set_version: u32,
+    added: Vec<SavedChange>,
+    removed: Vec<SavedChange>,
+    modified: Vec<SavedChange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    truncated: Vec<SavedTruncation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<SavedWarning>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    before: Option<Value>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    after: Option<Value>,
+    /// Saved with the documents, which the pairs index into
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    paired_arrays: Vec<SavedPairing>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedChange {
+    path: Vec<Value>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    value: Option<Value>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    old_value: Option<Value>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    new_value: Option<Value>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    annotations: Map<String, Value>,
+}
+
+/// Deserialize a field that is present, keeping `null` as `Some(Value::Null)`
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedTruncation {
+    path: Vec<Value>,
+    reason: SavedReason,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum SavedReason {
+    MaxDepth(usize),
+    MaxChanges(usize),
+    Cancelled,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedPairing {
+    path: Vec<Value>,
+    pairs: Vec<(usize, usize)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedWarning {
+    path: Vec<Value>,
+    kind: SavedWarningKind,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum SavedWarningKind {
+    PositionalArray {
+        #[serde(rename = "oldLen")]
+        old_len: usize,
+        #[serde(rename = "newLen")]
+        new_len: usize,
+    },
+    PrecisionLoss,
+    AmbiguousKey {
+        key: String,
+    },
+    DuplicateKey {
+        input: String,
+    },
+}
+
+fn save_path(path: &JsonPath) -> Vec<Value> {
+    path.segments()
+        .iter()
+        .map(|segment| match segment {
+            PathSegment::Key(key) => Value::String(key.clone()),
+            PathSegment::Index(index) => Value::from(*index),
+        })
+        .collect()
+}
+
+fn load_path(segments: Vec<Value>) -> Result<JsonPath, serde_json::Error> {
+    segments
+        .into_iter()
+        .map(|segment| match segment {
+            Value::String(key) => Ok(PathSegment::Key(key)),
+            Value::Number(n) if n.is_u64() => Ok(PathSegment::Index(n.as_u64().unwrap() as usize)),
+            other => Err(serde_json::Error::custom(format!(
+                "invalid path segment {} (expected a key or an array index)",
+                other
+            ))),
+        })
+        .collect::<Result<_, _>>()
+        .map(JsonPath::from_segments)
+}
+
+fn save_change(change: &Change, changes: &Changes) -> SavedChange {
+    let (value, old_value, new_value) = match change {
+        Change::Added { value, .. } => (Some(value.clone()), None, None),
+        Change::Removed { value, .. } => (None, Some(value.clone()), None),
+        Change::Modified {
+            old_value,
+            new_value,
+            ..
+        } => (None, Some(old_value.clone()), Some(new_value.clone())),
+    };
+    SavedChange {
+        path: save_path(change.path()),
+        value,
+        old_value,
+        new_value,
+        annotations: changes
+            .annotations
+            .get(change.path())
+            .cloned()
+            .unwrap_or_default(),
+    }
+}
+
+fn load_change(saved: SavedChange, changes: &mut Changes) -> Result<Change, serde_json::Error> {
+    let path = load_path(saved.path)?;
+    if !saved.annotations.is_empty() {
+        changes.annotations.insert(path.clone(), saved.annotations);
+    }
+    match (saved.value, saved.old_value, saved.new_value) {
+        (Some(value), None, None) => Ok(Change::Added { path, value }),
+        (None, Some(value), None) => Ok(Change::Removed { path, value }),
+        (None, Some(old_value), Some(new_value)) => Ok(Change::Modified {
+            path,
+            old_value,
+            new_value,
+        }),
+        _ => Err(serde_json::Error::custom(format!(
+            "change at '{}' needs either value, oldValue, or oldValue and newValue",
+            path
+        ))),
+    }
+}
+
+impl Changes {
+    /// Serialize the changes as a change set that [`Changes::from_json`] reads back
+    ///
+    /// Truncations, warnings and annotations are kept. With `include_documents`, the
+    /// `before` and `after` documents are saved too, which the formats that
+    /// render a document (e.g. "after" or "annotated") need.
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::{diff, Changes};
+    /// use serde_json::json;
+    ///
+    /// let changes = diff(&json!({"a.b": 1, "0": [1]}), &json!({"a.b": 2, "0": []}));
+    /// let saved = changes.to_json(true);
+    /// assert_eq!(Changes::from_json(&saved).unwrap(), changes);
+    /// ```
+    pub fn to_json(&self, include_documents: bool) -> String {
+        // A change set holds only strings, numbers and JSON values
+        serde_json::to_string_pretty(&self.change_set(include_documents))
+            .expect("change sets serialize")
+    }
+
+    /// Encode the changes as a MessagePack change set that
+    /// [`Changes::from_msgpack`] reads back
+    ///
+    /// The layout is that of [`Changes::to_json`]. Numbers are stored as
+    /// 64-bit integers or doubles; a number beyond the range of a double is
+    /// kept as its decimal text.
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::{diff, Changes};
+    /// use serde_json::json;
+    ///
+    /// let changes = diff(&json!({"port": 80, "tags": ["a"]}), &json!({"port": 8080}));
+    /// let packed = changes.to_msgpack(false);
+    /// assert!(packed.len() < changes.to_json(false).len());
+    /// assert_eq!(Changes::from_msgpack(&packed).unwrap().modified, changes.modified);
+    /// ```
+    pub fn to_msgpack(&self, include_documents: bool) -> Vec<u8> {
+        let change_set = serde_json::to_value(self.change_set(include_documents))
+            .expect("change sets serialize");
+        rmp_serde::to_vec(&Packed(&change_set)).expect("change sets serialize")
+    }
+
+    fn change_set(&self, include_documents: bool) -> ChangeSet {
+        ChangeSet {
+            change_set_version: CHANGE_SET_VERSION,
+            added: self.added.iter().map(|c| save_change(c, self)).collect(),
+            removed: self.removed.iter().map(|c| save_change(c, self)).collect(),
+            modified: self.modified.iter().map(|c| save_change(c, self)).collect(),
+            truncated: self
+                .truncated
+                .iter()
+                .map(|truncation| SavedTruncation {
+                    path: save_path(&truncation.path),
+                    reason: match truncation.reason {
+                        TruncationReason::MaxDepth(depth) => SavedReason::MaxDepth(depth),
+                        TruncationReason::MaxChanges(limit) => SavedReason::MaxChanges(limit),
+                        TruncationReason::Cancelled => SavedReason::Cancelled,
+                    },
+                })
+                .collect(),
+            warnings: self
+                .warnings
+                .iter()
+                .map(|warning| SavedWarning {
+                    path: save_path(&warning.path),
+                    kind: match &warning.kind {
+                        WarningKind::PositionalArray { old_len, new_len } => {
+                            SavedWarningKind::PositionalArray {
+                                old_len: *old_len,
+                                new_len: *new_len,
+                            }
+                        }
+                        WarningKind::PrecisionLoss => SavedWarningKind::PrecisionLoss,
+                        WarningKind::AmbiguousKey(key) => {
+                            SavedWarningKind::AmbiguousKey { key: key.clone() }
+                        }
+                        WarningKind::DuplicateKey(input) => SavedWarningKind::DuplicateKey {
+                            input: input.clone(),
+                        },
+                    },
+                })
+                .collect(),
+            before: self.before.clone().filter(|_| include_documents),
+            after: self.after.clone().filter(|_| include_documents),
+            paired_arrays: self.saved_pairings(include_documents),
+        }
+    }
+
+    /// The paired arrays in path order, or none without the documents
+    fn saved_pairings(&self, include_documents: bool) -> Vec<SavedPairing> {
+        if !include_documents {
+            return Vec::new();
+        }
+        let mut paths: Vec<&JsonPath> = self.paired_arrays.keys().collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| SavedPairing {
+                path: save_path(path),
+                pairs: self.paired_arrays[path].clone(),
+            })
+            .collect()
+    }
+
+    /// Read a change set written by [`Changes::to_json`]
+    ///
+    /// # Errors
+    /// Returns an error if the text is not a change set of a supported version.
+    pub fn from_json(text: &str) -> Result<Self, serde_json::Error> {
+        Self::from_change_set(serde_json::from_str(text)?)
+    }
+
+    /// Read a change set written by [`Changes::to_msgpack`]
+    ///
+    /// # Errors
+    /// Returns an error if the bytes are not MessagePack, or not a change set
+    /// of a supported version.
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, serde_json::Error> {
+        let value: Value = rmp_serde::from_slice(bytes)
+            .map_err(|e| serde_json::Error::custom(format!("invalid MessagePack: {}", e)))?;
+        Self::from_change_set(value)
+    }
+
+    fn from_change_set(value: Value) -> Result<Self, serde_json::Error> {
+        match value.get("changeSetVersion").and_then(Value::as_u64) {
+            Some(version) if version == u64::from(CHANGE_SET_VERSION) => {}
+            Some(version) => {
+                return Err(serde_json::Error::custom(format!(
+                    "unsupported change set version {} (expected {})",
+                    version, CHANGE_SET_VERSION
+                )))
+            }
+            None => {
+                return Err(serde_json::Error::custom(
+                    "not a change set: missing changeSetVersion",
+                ))
+            }
+        }
+        let change_set: ChangeSet = serde_json::from_value(value)?;
+
+        let mut changes = Changes::new();
+        let saved = change_set
+            .added
+            .into_iter()
+            .chain(change_set.removed)
+            .chain(change_set.modified);
+        for change in saved {
+            let change = load_change(change, &mut changes)?;
+            changes.push(change);
+        }
+        for truncation in change_set.truncated {
+            changes.truncated.push(Truncation {
+                path: load_path(truncation.path)?,
+                reason: match truncation.reason {
+                    SavedReason::MaxDepth(depth) => TruncationReason::MaxDepth(depth),
+                    SavedReason::MaxChanges(limit) => TruncationReason::MaxChanges(limit),
+                    SavedReason::Cancelled => TruncationReason::Cancelled,
+                },
+            });
+        }
+        for warning in change_set.warnings {
+            changes.warnings.push(Warning {
+                path: load_path(warning.path)?,
+                kind: match warning.kind {
+                    SavedWarningKind::PositionalArray { old_len, new_len } => {
+                        WarningKind::PositionalArray { old_len, new_len }
+                    }
+                    SavedWarningKind::PrecisionLoss => WarningKind::PrecisionLoss,
+                    SavedWarningKind::AmbiguousKey { key } => WarningKind::AmbiguousKey(key),
+                    SavedWarningKind::DuplicateKey { input } => WarningKind::DuplicateKey(input),
+                },
+            });
+        }
+        for pairing in change_set.paired_arrays {
+            changes
+                .paired_arrays
+                .insert(load_path(pairing.path)?, pairing.pairs);
+        }
+        changes.before = change_set.before;
+        changes.after = change_set.after;
+        Ok(changes)
+    }
+}
+
+/// A JSON value in the shape MessagePack stores it
+///
+/// With the `arbitrary-precision` feature, serde_json serializes numbers as a private
+/// struct holding their text, so they are unpacked here first.
+struct Packed<'a>(&'a Value);
+
+impl Serialize for Packed<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Number(n) => {
+                if let Some(n) = n.as_u64() {
+                    serializer.serialize_u64(n)
+                } else if let Some(n) = n.as_i64() {
+                    serializer.serialize_i64(n)
+                } else if let Some(n) = n.as_f64() {
+                    serializer.serialize_f64(n)
+                } else {
+                    serializer.serialize_str(&n.to_string())
+                }
+            }
+            Value::String(text) => serializer.serialize_str(text),
+            Value::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(&Packed(item))?;
+                }
+                seq.end()
+            }
+            Value::Object(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (key, value) in fields {
+                    map.serialize_entry(key, &Packed(value))?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff, diff_with_options, DiffOptions};
+    use serde_json::json;
+
+    #[test]
+    fn test_round_trip_with_documents() {
+        let old = json!({"a": {"b": [1, 2, 3]}, "x.y": true, "gone": null});
+        let new = json!({"a": {"b": [1, 5]}, "x.y": false, "new": {"k": 1}});
+        let mut changes = diff(&old, &new);
+        changes.before = Some(old.clone());
+
+        let loaded = Changes::from_json(&changes.to_json(true)).unwrap();
+        assert_eq!(loaded, changes);
+        assert_eq!(loaded.before.as_ref(), Some(&old));
+        assert_eq!(loaded.after.as_ref(), Some(&new));
+    }
+
+    #[test]
+    fn test_round_trip_paired_arrays() {
+        let options = DiffOptions {
+            array_diff: crate::ArrayDiff::Keyed("id".to_string()),
+            ..DiffOptions::default()
+        };
+        let old = json!([{"id": 1, "n": 1}, {"id": 2, "n": 2}]);
+        let new = json!([{"id": 2, "n": 3}, {"id": 1, "n": 1}]);
+        let changes = diff_with_options(&old, &new, &options);
+        assert_eq!(
+            changes.paired_arrays[&JsonPath::new()],
+            vec![(1, 0), (0, 1)]
+        );
+
+        assert_eq!(Changes::from_json(&changes.to_json(true)).unwrap(), changes);
+        let loaded = Changes::from_json(&changes.to_json(false)).unwrap();
+        assert!(loaded.paired_arrays.is_empty());
+    }
+
+    #[test]
+    fn test_round_trip_without_documents() {
+        let changes = diff(&json!([1]), &json!([2]));
+        let saved = changes.to_json(false);
+        let value: Value = serde_json::from_str(&saved).unwrap();
+        assert_eq!(value["changeSetVersion"], 1);
+        assert_eq!(value["modified"][0]["path"], json!([0]));
+        assert!(value.get("after").is_none());
+
+        let loaded = Changes::from_json(&saved).unwrap();
+        assert_eq!(loaded.modified, changes.modified);
+        assert_eq!(loaded.after, None);
+    }
+
+    #[test]
+    fn test_round_trip_truncations_and_warnings() {
+        let options = DiffOptions {
+            max_depth: Some(1),
+            ..DiffOptions::default()
+        };
+        let mut changes = diff_with_options(
+            &json!({"a": {"b": 1}, "k": 1}),
+            &json!({"a": {"b": 2}, "k": 2}),
+            &options,
+        );
+        changes.warnings.push(Warning {
+            path: JsonPath::from_segments(vec![PathSegment::Key("a.b".to_string())]),
+            kind: WarningKind::AmbiguousKey("a.b".to_string()),
+        });
+        assert!(changes.is_truncated());
+
+        let loaded = Changes::from_json(&changes.to_json(false)).unwrap();
+        assert_eq!(loaded.truncated, changes.truncated);
+        assert_eq!(loaded.warnings, changes.warnings);
+    }
+
+    #[test]
+    fn test_msgpack_round_trip() {
+        let old = json!({"n": -3, "f": 1.5, "s": "x", "list": [null, true], "o": {"k": 1}});
+        let new = json!({"n": 18446744073709551615u64, "f": 2.25, "s": "y", "list": [null]});
+        let mut changes = diff(&old, &new);
+        changes.before = Some(old.clone());
+        changes
+            .annotations
+            .entry("s".parse().unwrap())
+            .or_default()
+            .insert("severity".to_string(), json!("warn"));
+        changes.warnings.push(Warning {
+            path: "list".parse().unwrap(),
+            kind: WarningKind::PositionalArray {
+                old_len: 2,
+                new_len: 1,
+            },
+        });
+
+        let packed = changes.to_msgpack(true);
+        assert!(packed.len() < changes.to_json(true).len());
+        let loaded = Changes::from_msgpack(&packed).unwrap();
+        assert_eq!(loaded, changes);
+        assert_eq!(loaded.before.as_ref(), Some(&old));
+        assert_eq!(
+            Changes::from_msgpack(&changes.to_msgpack(false))
+                .unwrap()
+                .after,
+            None
+        );
+
+        // Numbers beyond a double are kept as text
+        #[cfg(feature = "arbitrary-precision")]
+        {
+            let huge: Value = serde_json::from_str("1e400").unwrap();
+            let changes = diff(&json!(null), &huge);
+            let loaded = Changes::from_msgpack(&changes.to_msgpack(false)).unwrap();
+            assert!(matches!(
+                &loaded.modified[0],
+                Change::Modified { new_value, .. } if new_value == "1e400"
+            ));
+        }
+
+        assert!(Changes::from_msgpack(b"not msgpack").is_err());
+        assert!(Changes::from_msgpack(&rmp_serde::to_vec(&[1, 2]).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_from_json_rejects_invalid_change_sets() {
+        let unsupported =
+            json!({"changeSetVersion": 9, "added": [], "removed": [], "modified": []});
+        let err = Changes::from_json(&unsupported.to_string()).unwrap_err();
+        assert!(err.to_string().contains("unsupported change set version 9"));
+
+        let bad_path = json!({
+            "changeSetVersion": 1,
+            "added": [{"path": [true], "value": 1}],
+            "removed": [],
+            "modified": []
+        });
+        assert!(Changes::from_json(&bad_path.to_string()).is_err());
+
+        let no_value = json!({
+            "changeSetVersion": 1,
+            "added": [{"path": ["a"]}],
+            "removed": [],
+            "modified": []
+        });
+        assert!(Changes::from_json(&no_value.to_string()).is_err());
+
+        // The "changes" output format is not a change set
+        assert!(Changes::from_json(r#"{"added": [], "removed": [], "modified": []}"#).is_err());
+    }
+}
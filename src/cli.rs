@@ -1,20 +1,70 @@
-use clap::Parser;
+use crate::config;
+use crate::help;
+use crate::output::Destination;
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::ffi::OsString;
 use std::path::PathBuf;
+use std::time::Duration;
 
 // Import from library crate for error type
-use rjd::RjdError;
+use rjd::rules::Severity;
+use rjd::string_diff::Granularity;
+use rjd::{
+    ArrayDiff, DescriptionTemplate, FormatterRegistry, InputFormat, PathSubstitution, RjdError,
+    SimilarityThreshold,
+};
 
 /// Output format options
-#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+///
+/// The built-in formats have their own variants; any other format registered
+/// in the [`FormatterRegistry`] is `Other`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum OutputFormat {
-    #[value(name = "changes")]
-    Changes, // Default: {added, removed, modified}
+    Changes,   // Default: {added, removed, modified}
+    After,     // Output the "after" state with only changed properties
+    Before,    // Output the "before" state of removed and modified properties
+    FullAfter, // The whole "after" document, keeping the first input's layout
+    Annotated, // The whole "after" document with changes marked inline
+    Rfc6902,   // RFC 6902 compliant JSON Patch format
+    Sarif,     // SARIF 2.1.0 log for code-scanning annotations
+    Junit,     // JUnit XML report with one failed test case per change
+    Gcc,       // Compiler-style `file:line:column: kind: message` lines
+    Summary,   // Change counts and the largest changes
+    Jsonl,     // One JSON object per change per line
+    Msgpack,   // Binary MessagePack change set
+    Other(String),
+}
 
-    #[value(name = "after")]
-    After, // Output the "after" state with only changed properties
+impl OutputFormat {
+    /// The format registered under `name`
+    fn from_name(name: String) -> Self {
+        match name.as_str() {
+            "changes" => OutputFormat::Changes,
+            "after" => OutputFormat::After,
+            "before" => OutputFormat::Before,
+            "full-after" => OutputFormat::FullAfter,
+            "annotated" => OutputFormat::Annotated,
+            "rfc6902" => OutputFormat::Rfc6902,
+            "sarif" => OutputFormat::Sarif,
+            "junit" => OutputFormat::Junit,
+            "gcc" => OutputFormat::Gcc,
+            "summary" => OutputFormat::Summary,
+            "jsonl" => OutputFormat::Jsonl,
+            "msgpack" => OutputFormat::Msgpack,
+            _ => OutputFormat::Other(name),
+        }
+    }
 
-    #[value(name = "rfc6902")]
-    Rfc6902, // RFC 6902 compliant JSON Patch format
+    /// Value parser accepting the names of the registered formats
+    fn parser() -> impl TypedValueParser<Value = Self> {
+        let names = FormatterRegistry::new()
+            .names()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        PossibleValuesParser::new(names).map(Self::from_name)
+    }
 }
 
 impl std::fmt::Display for OutputFormat {
@@ -22,18 +72,156 @@ impl std::fmt::Display for OutputFormat {
         match self {
             OutputFormat::Changes => write!(f, "changes"),
             OutputFormat::After => write!(f, "after"),
+            OutputFormat::Before => write!(f, "before"),
+            OutputFormat::FullAfter => write!(f, "full-after"),
+            OutputFormat::Annotated => write!(f, "annotated"),
             OutputFormat::Rfc6902 => write!(f, "rfc6902"),
+            OutputFormat::Sarif => write!(f, "sarif"),
+            OutputFormat::Junit => write!(f, "junit"),
+            OutputFormat::Gcc => write!(f, "gcc"),
+            OutputFormat::Summary => write!(f, "summary"),
+            OutputFormat::Jsonl => write!(f, "jsonl"),
+            OutputFormat::Msgpack => write!(f, "msgpack"),
+            OutputFormat::Other(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// When to color output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorWhen {
+    /// Color when writing to a terminal, honoring NO_COLOR/FORCE_COLOR/CLICOLOR
+    Auto,
+    /// Always color
+    Always,
+    /// Never color
+    Never,
+}
+
+impl From<ColorWhen> for rjd::color::ColorChoice {
+    fn from(when: ColorWhen) -> Self {
+        match when {
+            ColorWhen::Auto => Self::Auto,
+            ColorWhen::Always => Self::Always,
+            ColorWhen::Never => Self::Never,
         }
     }
 }
 
+/// How `--order` sorts the entries of each change kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum EntryOrder {
+    /// By path, with array indices compared as numbers
+    Path,
+    /// By the JSON type of the changed value, then by path
+    Type,
+    /// Type changes first, then the largest changed subtrees, then by path
+    Severity,
+}
+
+impl From<EntryOrder> for rjd::ChangeOrder {
+    fn from(order: EntryOrder) -> Self {
+        match order {
+            EntryOrder::Path => rjd::ChangeOrder::Path,
+            EntryOrder::Type => rjd::ChangeOrder::Type,
+            EntryOrder::Severity => rjd::ChangeOrder::Severity,
+        }
+    }
+}
+
+/// How `--array-diff` pairs array elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ArrayMode {
+    /// Compare the elements at the same index
+    Positional,
+    /// Pair elements by structural similarity (see --similarity-threshold)
+    Similarity,
+}
+
+/// What `--strict-parse` does with repeated object keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DuplicateKeyPolicy {
+    /// Refuse to compare the inputs
+    Error,
+    /// Compare the last values and report each repeated key as a warning
+    Warn,
+}
+
+/// A `--unwrap` target: which input to descend into and where
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unwrap {
+    /// Unwrap the first input
+    pub old: bool,
+    /// Unwrap the second input
+    pub new: bool,
+    /// JSON Pointer of the value to compare instead of the whole document
+    ///
+    /// A dot-notation path given on the command line is stored as a pointer.
+    pub pointer: String,
+}
+
+impl std::str::FromStr for Unwrap {
+    type Err = String;
+
+    /// Parse `old:PATH`, `new:PATH`, or a bare `PATH` applying to both inputs
+    ///
+    /// `PATH` is a JSON Pointer (`/data`) or dot notation (`data.items`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (old, new, path) = match s.split_once(':') {
+            Some(("old", path)) => (true, false, path),
+            Some(("new", path)) => (false, true, path),
+            _ if !s.is_empty() => (true, true, s),
+            _ => return Err("expected old:PATH, new:PATH or PATH".to_string()),
+        };
+        let path = rjd::JsonPath::parse_any(path).map_err(|e| e.to_string())?;
+        Ok(Self {
+            old,
+            new,
+            pointer: path.to_json_pointer(),
+        })
+    }
+}
+
+/// An `rjd assert --expect` check: the value a path must have changed to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expectation {
+    /// JSON Pointer of the value (dot notation on the command line is converted)
+    pub pointer: String,
+    /// The value expected in the second input
+    pub value: serde_json::Value,
+}
+
+impl std::str::FromStr for Expectation {
+    type Err = String;
+
+    /// Parse `PATH=VALUE`, where PATH is a JSON Pointer (`/version`) or dot
+    /// notation (`version`) and VALUE is JSON (`3`, `true`, `"3"`) or else a
+    /// plain string
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((path, value)) = s.split_once('=') else {
+            return Err("expected PATH=VALUE, e.g. /version=2.0.0".to_string());
+        };
+        let path = rjd::JsonPath::parse_any(path).map_err(|e| e.to_string())?;
+        let value = serde_json::from_str(value)
+            .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+        Ok(Self {
+            pointer: path.to_json_pointer(),
+            value,
+        })
+    }
+}
+
 /// Command-line arguments for rjd
 #[derive(Parser, Debug)]
 #[command(name = "rjd")]
 #[command(about = "Compare two JSON files or inline JSON strings")]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
-    /// First JSON file or inline JSON string
-    pub file1: String,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// First JSON file or inline JSON string (omit with --stdin to read both inputs from stdin)
+    pub file1: Option<String>,
 
     /// Second JSON file or inline JSON string (not required when using --stdin)
     #[arg(required = false)]
@@ -43,22 +231,229 @@ pub struct Args {
     #[arg(long)]
     pub stdin: bool,
 
+    /// Line separating the two documents when both are read from stdin
+    #[arg(long, value_name = "SEP", allow_hyphen_values = true)]
+    pub stdin_separator: Option<String>,
+
     /// Output format (default: changes)
-    #[arg(short, long, default_value_t = OutputFormat::Changes, hide_default_value = true)]
+    #[arg(
+        short,
+        long,
+        default_value_t = OutputFormat::Changes,
+        hide_default_value = true,
+        value_parser = OutputFormat::parser()
+    )]
     pub format: OutputFormat,
 
+    /// Tera template the template format renders the changes with (implies --format template)
+    ///
+    /// The template sees the added, removed and modified arrays of the
+    /// changes format, all entries with a kind field as changes, and the
+    /// number of changes as total.
+    #[arg(long, value_name = "FILE")]
+    pub template: Option<PathBuf>,
+
     /// Sort keys in output
     #[arg(long)]
     pub sort: bool,
 
+    /// Order the entries of each change kind by path, value type, or severity
+    /// (default: document order)
+    #[arg(long, value_name = "ORDER")]
+    pub order: Option<EntryOrder>,
+
+    /// Report the changes from the second input back to the first, e.g. for a rollback
+    ///
+    /// The diff is inverted rather than rerun: added values are reported as
+    /// removed, removed ones as added and modified values with old and new
+    /// swapped. Everything after the diff (--ignore, --rules, locations and
+    /// the output format) sees the reversed direction.
+    #[arg(long)]
+    pub reverse: bool,
+
+    /// Check that the RFC 6902 patch of the changes turns the first input into the second
+    ///
+    /// The patch is applied to the first input in memory, before --ignore,
+    /// --map-path and other filters, and the run fails (exit code 1) naming
+    /// the first paths where the result differs. Options that leave
+    /// differences unreported, such as --structure-only or --coerce-numbers,
+    /// make the check fail too.
+    #[arg(long)]
+    pub verify: bool,
+
+    /// Print a "No differences found." message instead of an empty result
+    #[arg(long, conflicts_with = "empty_output")]
+    pub print_empty_message: bool,
+
+    /// Print TEXT instead of the format's empty result when there are no changes ('' prints nothing)
+    #[arg(long, value_name = "TEXT", allow_hyphen_values = true)]
+    pub empty_output: Option<String>,
+
+    /// Color output: auto (default), always, or never
+    #[arg(long, value_name = "WHEN", default_value_t = ColorWhen::Auto, hide_default_value = true, value_enum)]
+    pub color: ColorWhen,
+
+    /// Number of largest changes listed by the summary format (default: 5)
+    #[arg(long, default_value_t = rjd::formatter::DEFAULT_TOP_K, hide_default_value = true)]
+    pub top: usize,
+
+    /// Write the report to DEST: a file path, file:PATH, http(s)://URL (POST) or s3://BUCKET/KEY
+    ///
+    /// `-` is stdout (the default). Files are written to a temporary file and
+    /// renamed over the target, so readers never see a partial report.
+    /// Without --format, a .sarif file gets sarif output and a .xml file junit.
+    #[arg(short, long, value_name = "DEST", default_value = "-")]
+    pub output: Destination,
+
+    /// Also save the changes (after --ignore) as a change set for `rjd render`
+    ///
+    /// The change set holds the changes and both documents, so it can later
+    /// be rendered in any format without diffing again. DEST takes the same
+    /// forms as --output.
+    #[arg(long, value_name = "DEST")]
+    pub save_changes: Option<Destination>,
+
+    /// Report a pseudo-random sample of at most N changes
+    #[arg(long, value_name = "N")]
+    pub sample: Option<usize>,
+
+    /// Seed for --sample; the seed used is printed to stderr so reports can be reproduced
+    #[arg(long, value_name = "SEED", requires = "sample")]
+    pub seed: Option<u64>,
+
+    /// Show N levels of unchanged surrounding keys with each change (changes, after and before formats)
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub context: usize,
+
+    /// Group changes under their ancestors N path segments deep (changes format)
+    ///
+    /// Changes below the root are listed in nested `groups` objects, so that,
+    /// e.g., every change under users[3] appears together. Ancestors holding a
+    /// single group and no changes of their own are merged into it.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub group_by_path_depth: Option<u32>,
+
+    /// Truncate output values longer than N characters, noting their original size
+    #[arg(long, value_name = "N")]
+    pub max_value_length: Option<usize>,
+
+    /// Version of the changes output format (1 omits the formatVersion field)
+    #[arg(long, default_value_t = 2, hide_default_value = true, value_parser = clap::value_parser!(u32).range(1..=2))]
+    pub format_version: u32,
+
+    /// Ignore .rjd.toml and .rjdrc.json config files
+    #[arg(long)]
+    pub no_config: bool,
+
+    /// Wrap JSON output in an envelope with the tool version, inputs, time, options and change counts
+    #[arg(long)]
+    pub with_metadata: bool,
+
+    /// Report the line and column of each changed value in the input files
+    /// (as oldLocation/newLocation in the changes format, and in SARIF regions)
+    #[arg(long)]
+    pub with_locations: bool,
+
+    /// Rewrite reported paths with a sed-style substitution on their JSON Pointer (repeatable)
+    ///
+    /// Written s#PATTERN#REPLACEMENT#, with any delimiter after the s and an
+    /// optional g flag to replace every match. PATTERN is a regular
+    /// expression; REPLACEMENT refers to groups as $1. Substitutions run in
+    /// order, before --ignore patterns are matched. Example: --map-path
+    /// 's#^/env/[^/]+##' reports /env/prod/db/port as /db/port.
+    #[arg(long, value_name = "EXPR")]
+    pub map_path: Vec<PathSubstitution>,
+
+    /// Path to ignore, as a JSON Pointer, dot notation or JSONPath query like '$.users[*].token' (repeatable)
+    ///
+    /// A pattern starting with / is a JSON Pointer, one starting with $ a
+    /// JSONPath query and anything else dot notation (users[0].token). Every
+    /// pattern matches exactly the values it selects; end it with /** (or .**)
+    /// to also match everything below them. Examples: --ignore
+    /// /metadata/generated (or metadata.generated) ignores that value only;
+    /// --ignore '/metadata/**' ignores all of metadata; --ignore
+    /// '$.users[*].token' ignores the token of every user; --ignore
+    /// '$..etag.**' ignores etag keys at any depth, with their contents.
+    #[arg(long, value_name = "PATTERN")]
+    pub ignore: Vec<String>,
+
     /// JSON file containing paths to ignore (can be specified multiple times)
     #[arg(long)]
     pub ignore_json: Vec<String>,
 
+    /// JSON Schema of the inputs, used to match array items by key and classify changes
+    ///
+    /// Arrays whose items have an identity key (the `x-rjd-key` keyword on
+    /// the array or items schema, or a required `id` property) are matched by
+    /// that key instead of by position. Each change is reported with its
+    /// `compatibility` (breaking or compatible), the `reason` it is breaking
+    /// and the `schemaPath` of the changed value.
+    #[arg(long, value_name = "FILE")]
+    pub schema: Option<PathBuf>,
+
+    /// JSON Schema whose default values are filled into both inputs before diffing
+    ///
+    /// Missing properties whose schema has a `default` are added (recursively),
+    /// so documents are compared by their effective configuration.
+    #[arg(long, value_name = "SCHEMA")]
+    pub fill_defaults: Option<PathBuf>,
+
+    /// JSON file of rules assigning a severity (info, warn, error) to each change
+    ///
+    /// Each change is reported with its `severity`. Rules match paths like
+    /// --ignore patterns and can be limited to kinds of change, e.g.
+    /// {"default": "info", "rules": [{"path": "/database/**", "kinds": ["removed"], "severity": "error"}]}.
+    /// The first matching rule wins.
+    #[arg(long, value_name = "FILE")]
+    pub rules: Option<PathBuf>,
+
+    /// Exit with code 2 if a change has this severity or higher: info, warn or error
+    #[arg(long, value_name = "SEVERITY", requires = "rules")]
+    pub fail_on: Option<Severity>,
+
+    /// JSON file with the only paths allowed to change (can be specified multiple times)
+    #[arg(long)]
+    pub require_paths: Vec<String>,
+
+    /// JSON file listing the paths expected to change, in the ignore file format (repeatable)
+    ///
+    /// Changes outside the list are marked `"unexpected": true` in the output
+    /// (SARIF `error` results) and listed on stderr, and rjd exits with code 2.
+    #[arg(long, value_name = "FILE")]
+    pub expect_json: Vec<String>,
+
+    /// JSON file with paths that must have changed (can be specified multiple times)
+    #[arg(long)]
+    pub must_change: Vec<String>,
+
+    /// Fail (exit code 2) when more than this share of leaf values changed, e.g. 5%
+    ///
+    /// Leaves are scalars and empty containers; the share is the number of
+    /// changes out of the leaves present in either document. The percent sign
+    /// is optional.
+    #[arg(long, value_name = "PERCENT", value_parser = parse_percent)]
+    pub max_drift: Option<f64>,
+
     /// Maximum file size in bytes (default: 104857600, env: RJD_MAX_FILE_SIZE)
     #[arg(long)]
     pub max_file_size: Option<u64>,
 
+    /// Refuse inputs that would need more than SIZE of memory, e.g. 512M or 2G
+    ///
+    /// The inputs are checked before they are read (the text alone must fit)
+    /// and again once parsed, before diffing, so an unexpectedly huge input
+    /// fails fast instead of exhausting the machine. K, M and G are powers
+    /// of 1024; a bare number is bytes.
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_memory: Option<u64>,
+
+    /// Give up on a diff still running after DURATION, e.g. 30s or 2m
+    ///
+    /// An abandoned diff prints no result and exits with code 3, so CI can
+    /// tell a pathological input from a difference or an error.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub timeout: Option<Duration>,
+
     /// Maximum JSON nesting depth (default: 1000, env: RJD_MAX_JSON_DEPTH)
     #[arg(long)]
     pub max_depth: Option<usize>,
@@ -70,18 +465,797 @@ pub struct Args {
     /// Force input to be treated as inline JSON
     #[arg(long)]
     pub inline: bool,
+
+    /// Encoding of the inputs: json, msgpack, cbor, bson or xml (default: from
+    /// each file's extension, otherwise json)
+    ///
+    /// MessagePack, CBOR, BSON and XML documents are converted to JSON before
+    /// they are compared: byte strings become base64 strings, BSON-specific
+    /// values such as object ids and dates use MongoDB Extended JSON
+    /// ({"$oid": "..."}), and XML elements become objects keyed by child
+    /// element name (see --xml-attribute-prefix and --xml-text-key). A
+    /// non-JSON second input read with --stdin needs this option. With the
+    /// analytics feature, avro and parquet files are read as arrays of their
+    /// records.
+    #[arg(long, value_name = "FORMAT")]
+    pub input_format: Option<InputFormat>,
+
+    /// Prefix of XML attribute names in the converted JSON (default: @)
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        default_value = "@",
+        hide_default_value = true
+    )]
+    pub xml_attribute_prefix: String,
+
+    /// Key of XML element text in the converted JSON, used when the element
+    /// also has attributes or child elements (default: #text)
+    #[arg(
+        long,
+        value_name = "KEY",
+        default_value = "#text",
+        hide_default_value = true
+    )]
+    pub xml_text_key: String,
+
+    /// Detect object keys repeated in either input: error (default) or warn
+    ///
+    /// JSON parsers disagree on which value of a repeated key wins; rjd
+    /// compares the last one. With `error` such inputs are rejected; with
+    /// `warn` each repeated key is listed in the output's warnings. Give the
+    /// policy with an equals sign: --strict-parse=warn.
+    #[arg(
+        long,
+        value_name = "POLICY",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "error",
+        value_enum
+    )]
+    pub strict_parse: Option<DuplicateKeyPolicy>,
+
+    /// Stop diffing below this depth and report deeper differences as one changed subtree
+    #[arg(long, value_name = "N")]
+    pub max_diff_depth: Option<usize>,
+
+    /// Treat object keys that differ only in case as the same key
+    #[arg(long)]
+    pub ignore_key_case: bool,
+
+    /// Compare object keys in alphabetical order instead of document order
+    #[arg(long)]
+    pub sort_keys: bool,
+
+    /// Compare ISO-8601 timestamp strings as instants ('...T00:00:00Z' equals '...T00:00:00+00:00')
+    #[arg(long)]
+    pub normalize_dates: bool,
+
+    /// With --normalize-dates, treat timestamps at most this far apart as equal (e.g. 5s, 250ms, 2m)
+    ///
+    /// Units are ms, s, m and h; a bare number is seconds.
+    #[arg(long, value_name = "DURATION", requires = "normalize_dates", value_parser = parse_duration)]
+    pub date_tolerance: Option<Duration>,
+
+    /// Compare numbers by value, so 1 and 1.0 are equal
+    ///
+    /// Numbers are compared as 64-bit integers or floats. Built with the
+    /// arbitrary-precision feature, rjd compares numbers exactly: by default
+    /// they must also be spelled the same (1.10 and 1.1 differ), and with
+    /// this flag only their exact values count.
+    #[arg(long)]
+    pub coerce_numbers: bool,
+
+    /// Treat strings holding a number as that number, so "1", 1 and 1.0 are equal
+    #[arg(long)]
+    pub coerce_number_strings: bool,
+
+    /// Treat a member set to null as equal to a missing member
+    #[arg(long)]
+    pub null_as_absent: bool,
+
+    /// Treat a member holding {} or [] as equal to a missing member
+    #[arg(long)]
+    pub empty_as_absent: bool,
+
+    /// Compare only the shape of the inputs: key presence, value types and array lengths
+    ///
+    /// Strings, numbers and booleans that keep their type are not reported
+    /// when their values change, so schema drift stands out from data churn.
+    #[arg(long)]
+    pub structure_only: bool,
+
+    /// Compare arrays of scalars (tag lists, permissions) as sets
+    ///
+    /// Order and repeated elements are ignored. Arrays with different
+    /// elements are reported as one change of the whole array, annotated
+    /// with addedElements, removedElements and common, instead of one
+    /// change per index.
+    #[arg(long, conflicts_with = "structure_only")]
+    pub array_sets: bool,
+
+    /// How to pair array elements: by index (default) or by similarity
+    ///
+    /// With similarity, each element is compared with the most similar
+    /// element of the other array, so items that moved and changed slightly
+    /// are reported as modified rather than as a cascade of index changes.
+    /// Paired elements are reported under their index in the second input,
+    /// and unmatched elements of the first input after its end.
+    #[arg(
+        long,
+        value_name = "MODE",
+        default_value = "positional",
+        hide_default_value = true
+    )]
+    pub array_diff: ArrayMode,
+
+    /// Pair the elements of arrays matching PATTERN by STRATEGY (repeatable; the first match applies)
+    ///
+    /// PATTERN is an ignore pattern (`/users`, `$..tags`). STRATEGY is
+    /// positional, unordered (equal elements match wherever they are),
+    /// similarity[:THRESHOLD] or keyed:KEY (objects with the same KEY value
+    /// match). Overrides --array-diff and --array-sets for those arrays.
+    /// Example: --array-strategy /users=keyed:id --array-strategy /tags=unordered.
+    /// In a config file, a table of patterns: `[array-strategy]` with
+    /// `"/users" = { strategy = "keyed", key = "id" }` and `"/tags" = "unordered"`.
+    #[arg(long, value_name = "PATTERN=STRATEGY", value_parser = parse_array_strategy)]
+    pub array_strategy: Vec<(String, ArrayDiff)>,
+
+    /// Least similarity (0 to 1) of elements paired by --array-diff similarity (default: 0.7)
+    ///
+    /// Equal values score 1; objects score the average similarity of their
+    /// members, a member on one side only scoring 0.
+    #[arg(long, value_name = "SCORE", value_parser = parse_threshold)]
+    pub similarity_threshold: Option<SimilarityThreshold>,
+
+    /// Normalize both inputs per RFC 8785 (JCS) before diffing
+    ///
+    /// Numbers are compared as the doubles they denote (1, 1.0 and 1e0 are
+    /// equal) and keys and strings are compared in Unicode NFC form, so two
+    /// serializations of the same document produce an empty diff. Reported
+    /// values are the normalized ones.
+    #[arg(long)]
+    pub canonical: bool,
+
+    /// Report only values whose JSON type changed (e.g. string to number, object to array)
+    ///
+    /// Each reported change gets `oldType` and `newType` fields.
+    #[arg(long)]
+    pub types_only: bool,
+
+    /// Add the type and size of the changed values to each change
+    ///
+    /// Changes get `oldType` (removed and modified values), `newType` (added
+    /// and modified values) and `valueSize`, the length in bytes of the
+    /// value's compact JSON text (for a modification, of the larger value).
+    /// Only the changes format reports them.
+    #[arg(long)]
+    pub verbose_changes: bool,
+
+    /// Add a human-readable description to each change, e.g. "`user.age` changed from 30 to 31"
+    ///
+    /// The changes format reports it as a description field; SARIF, JUnit
+    /// and gcc output use it as the message of each change.
+    #[arg(long)]
+    pub describe: bool,
+
+    /// Lay descriptions out with TEMPLATE (implies --describe)
+    ///
+    /// Placeholders: {path} (dot notation), {pointer} (JSON Pointer), {kind}
+    /// (added, removed or modified), {value} (the added or removed value, or
+    /// the new value), {old} and {new} (empty for a missing side); {{ and }}
+    /// are literal braces. Example: --describe-template '{kind} {pointer}:
+    /// {old} -> {new}'.
+    #[arg(long, value_name = "TEMPLATE")]
+    pub describe_template: Option<DescriptionTemplate>,
+
+    /// Diff strings holding a JSON object or array on both sides as embedded documents
+    ///
+    /// Changes inside the embedded document are reported below the string's
+    /// path, e.g. settings.port for {"settings": "{\"port\": 80}"}. Such paths
+    /// do not exist in the input, so rfc6902 output for them cannot be applied.
+    #[arg(long)]
+    pub parse_embedded_json: bool,
+
+    /// Path of a base64 blob, reported as its size and SHA-256 hash (repeatable)
+    ///
+    /// Uses the --ignore pattern syntax, e.g. --binary-paths '$..certificate'.
+    /// A changed blob is reported as {"$binary": {"bytes": N, "sha256": "..."}}
+    /// on each side instead of the full string.
+    #[arg(long, value_name = "PATTERN")]
+    pub binary_paths: Vec<String>,
+
+    /// Also report strings that look like base64 blobs (long, mixed-case base64) as blobs
+    #[arg(long)]
+    pub detect_binary: bool,
+
+    /// Diff blobs that decode to a JSON object or array on both sides as documents
+    #[arg(long)]
+    pub decode_binary_json: bool,
+
+    /// Show what changed inside modified strings, by word (default) or by character
+    ///
+    /// Each modified string gets a `stringDiff` list of the runs of text kept,
+    /// deleted and inserted; the annotated format shows it as `__diff`. Pass
+    /// the granularity with an equals sign: --string-diff=char.
+    #[arg(
+        long,
+        value_name = "GRANULARITY",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "word"
+    )]
+    pub string_diff: Option<Granularity>,
+
+    /// Stop diffing after N changes and report the partial result
+    #[arg(long, value_name = "N")]
+    pub max_changes: Option<usize>,
+
+    /// Diff top-level object keys in parallel (useful for large documents; needs the `parallel` feature)
+    #[arg(long)]
+    pub parallel: bool,
+
+    /// When the inputs are structurally equal, report formatting-only differences on stderr
+    ///
+    /// Numbers spelled differently (1 and 1.0) are still reported as changes,
+    /// but do not keep the inputs from counting as formatted differently.
+    #[arg(long)]
+    pub report_format_only: bool,
+
+    /// Compare the value at a path instead of the whole input (repeatable)
+    ///
+    /// old:/data unwraps the first input, new:/result/payload the second and a
+    /// bare /data both. Paths are JSON Pointers or dot notation
+    /// (new:result.payload). Repeated unwraps are applied in order, each to
+    /// the result of the previous one.
+    #[arg(long, value_name = "[SIDE:]PATH")]
+    pub unwrap: Vec<Unwrap>,
+
+    /// Inline `$ref` references to other JSON files before diffing
+    ///
+    /// Referenced files must lie under the directory of the input that
+    /// refers to them (the working directory for inline JSON).
+    #[arg(long)]
+    pub resolve_refs: bool,
+
+    /// Extract the JSON object or array embedded in each input's surrounding text
+    #[arg(long)]
+    pub extract_json: bool,
+
+    /// Which embedded JSON document to extract, counting from 1 (default: 1)
+    #[arg(long, default_value_t = 1, hide_default_value = true, requires = "extract_json", value_parser = clap::value_parser!(u32).range(1..))]
+    pub nth: u32,
+}
+
+/// Subcommands for rjd
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Print the JSON Schema of an output format
+    Schema {
+        /// Output format to describe (default: changes)
+        #[arg(
+            short,
+            long,
+            default_value_t = OutputFormat::Changes,
+            hide_default_value = true,
+            value_parser = OutputFormat::parser()
+        )]
+        format: OutputFormat,
+
+        /// Version of the changes output format (default: 2)
+        #[arg(long, default_value_t = 2, hide_default_value = true, value_parser = clap::value_parser!(u32).range(1..=2))]
+        format_version: u32,
+    },
+    /// Diff consecutive versions (v1 → v2 → v3 ...) and summarize the history
+    Chain {
+        /// JSON files in chronological order (at least two)
+        #[arg(required = true, num_args = 2..)]
+        files: Vec<PathBuf>,
+
+        /// Format of each step's changes (default: changes)
+        #[arg(
+            short,
+            long,
+            default_value_t = OutputFormat::Changes,
+            hide_default_value = true,
+            value_parser = OutputFormat::parser()
+        )]
+        format: OutputFormat,
+
+        /// Sort keys alphabetically
+        #[arg(short, long)]
+        sort: bool,
+    },
+    /// Diff one baseline against many targets and report widespread changes
+    ///
+    /// Prints the changes from the baseline to each target, and the paths
+    /// changed in more than --threshold targets together with those targets.
+    /// Useful for auditing many environment configs against a reference.
+    Fanout {
+        /// Reference JSON file
+        baseline: PathBuf,
+
+        /// JSON files to compare against the baseline
+        #[arg(required = true, num_args = 1..)]
+        targets: Vec<PathBuf>,
+
+        /// List only paths changed in more than K targets (default: 0, every changed path)
+        #[arg(long, value_name = "K", default_value_t = 0, hide_default_value = true)]
+        threshold: usize,
+
+        /// Also save each target's changes as a change set, DIR/<target name>.changes.json (see `rjd render`)
+        #[arg(long, value_name = "DIR")]
+        save_changes: Option<PathBuf>,
+
+        /// Format of each target's changes (default: changes)
+        #[arg(
+            short,
+            long,
+            default_value_t = OutputFormat::Changes,
+            hide_default_value = true,
+            value_parser = OutputFormat::parser()
+        )]
+        format: OutputFormat,
+
+        /// Sort keys alphabetically
+        #[arg(short, long)]
+        sort: bool,
+    },
+    /// Diff every pair of documents and print a matrix of change counts
+    ///
+    /// Useful to find which of many environment configs have drifted from
+    /// each other. Prints the documents, an N×N matrix of the number of
+    /// changes between each pair and every pair's counts by kind.
+    Matrix {
+        /// JSON files to compare (at least two)
+        #[arg(required = true, num_args = 2..)]
+        files: Vec<PathBuf>,
+
+        /// Also write the changes of each pair to DIR, named I-J.json after the documents' positions
+        #[arg(long, value_name = "DIR")]
+        details: Option<PathBuf>,
+
+        /// Format of the pair details (default: changes)
+        #[arg(
+            short,
+            long,
+            default_value_t = OutputFormat::Changes,
+            hide_default_value = true,
+            value_parser = OutputFormat::parser()
+        )]
+        format: OutputFormat,
+
+        /// Sort keys alphabetically
+        #[arg(short, long)]
+        sort: bool,
+    },
+    /// Diff a JSON file at two git revisions, e.g. `rjd rev HEAD~1:config.json HEAD:config.json`
+    Rev {
+        /// Old version as REV:PATH (PATH relative to the repository root, or ./PATH), or a file
+        old: String,
+
+        /// New version as REV:PATH, or a file in the working tree
+        new: String,
+
+        /// Output format (default: changes)
+        #[arg(
+            short,
+            long,
+            default_value_t = OutputFormat::Changes,
+            hide_default_value = true,
+            value_parser = OutputFormat::parser()
+        )]
+        format: OutputFormat,
+
+        /// Sort keys alphabetically
+        #[arg(short, long)]
+        sort: bool,
+    },
+    /// Render a change set saved with --save-changes
+    Render {
+        /// Change set file
+        file: PathBuf,
+
+        /// Render the changes back from the second document to the first
+        #[arg(long)]
+        reverse: bool,
+
+        /// Output format (default: changes)
+        #[arg(
+            short,
+            long,
+            default_value_t = OutputFormat::Changes,
+            hide_default_value = true,
+            value_parser = OutputFormat::parser()
+        )]
+        format: OutputFormat,
+
+        /// Sort keys alphabetically
+        #[arg(short, long)]
+        sort: bool,
+
+        /// Write the report to DEST instead of stdout (see `rjd --help`)
+        #[arg(short, long, value_name = "DEST", default_value = "-")]
+        output: Destination,
+    },
+    /// Report which changes two saved change sets have in common and which are unique to each
+    CompareChanges {
+        /// First change set, e.g. last week's deployment
+        first: PathBuf,
+
+        /// Second change set
+        second: PathBuf,
+
+        /// Sort keys alphabetically
+        #[arg(short, long)]
+        sort: bool,
+    },
+    /// Check that paths changed to expected values and nothing else changed
+    ///
+    /// Prints a JSON report with the outcome of each expectation and the
+    /// unexpected changes, and exits with code 2 if the assertion fails.
+    Assert {
+        /// First JSON file, e.g. the previous release's artifact
+        old: PathBuf,
+
+        /// Second JSON file
+        new: PathBuf,
+
+        /// Expected new value of a path, e.g. /version=2.0.0 or version=2.0.0 (repeatable)
+        ///
+        /// The value is read as JSON when it parses (`/replicas=3`,
+        /// `/enabled=true`, `/tag="3"`), otherwise as a string. Changes at or
+        /// below an expected path are allowed; any other change fails.
+        #[arg(long, value_name = "PATH=VALUE", required = true)]
+        expect: Vec<Expectation>,
+    },
+    /// Compare a live document against a stored golden file (snapshot testing)
+    ///
+    /// Prints the changes from the golden file to the live document and exits
+    /// with code 2 if there are any. With --update the golden file is
+    /// rewritten with the live document instead, and created if missing, e.g.
+    /// `rjd snapshot --update golden/response.json -- live.json`.
+    Snapshot {
+        /// Stored golden file
+        golden: PathBuf,
+
+        /// Live document to check
+        live: PathBuf,
+
+        /// Accept the live document: create or rewrite the golden file
+        #[arg(long)]
+        update: bool,
+
+        /// Output format (default: changes)
+        #[arg(
+            short,
+            long,
+            default_value_t = OutputFormat::Changes,
+            hide_default_value = true,
+            value_parser = OutputFormat::parser()
+        )]
+        format: OutputFormat,
+
+        /// Sort keys alphabetically
+        #[arg(short, long)]
+        sort: bool,
+    },
+    /// Profile a document: value and key counts, depths, types and largest subtrees
+    ///
+    /// Helps choose --max-diff-depth, --max-depth or ignore patterns before
+    /// diffing a huge document. `depths` lists the number of values at each
+    /// depth, starting with the root.
+    Stats {
+        /// JSON file to profile
+        file: PathBuf,
+
+        /// Number of largest subtrees to list
+        #[arg(long, value_name = "K", default_value_t = 10)]
+        top: usize,
+    },
+    /// Print a document in canonical form, for storing files before diffing
+    ///
+    /// Keys are sorted and whole floats such as `1.0` are written as integers.
+    /// With --jcs the output is the compact RFC 8785 JSON Canonicalization
+    /// Scheme form, suitable for hashing and signing.
+    #[command(alias = "canonicalize")]
+    Fmt {
+        /// JSON file to canonicalize
+        file: PathBuf,
+
+        /// Write the RFC 8785 (JCS) canonical form instead of pretty-printed JSON
+        #[arg(long)]
+        jcs: bool,
+
+        /// Write the document to DEST instead of stdout (see `rjd --help`)
+        #[arg(short, long, value_name = "DEST", default_value = "-")]
+        output: Destination,
+    },
+    /// Invert, compose or minimize RFC 6902 patches
+    Patch {
+        #[command(subcommand)]
+        command: PatchCommand,
+    },
+    /// Apply an RFC 6902 patch to a JSON file, replacing it atomically
+    PatchInPlace {
+        /// JSON file to modify
+        target: PathBuf,
+
+        /// JSON Patch file (e.g. `rjd --format rfc6902` output)
+        #[arg(long, value_name = "PATCH")]
+        with: PathBuf,
+
+        /// Keep the original file as TARGET.bak
+        #[arg(long)]
+        backup: bool,
+    },
+    /// Print the long help of every command, or a man page
+    HelpFull {
+        /// Output a roff man page instead, e.g. `rjd help-full --manpage > rjd.1`
+        #[arg(long)]
+        manpage: bool,
+    },
+    /// Manage fuzzing inputs
+    FuzzCorpus {
+        #[command(subcommand)]
+        command: FuzzCorpusCommand,
+    },
+}
+
+/// `rjd patch` subcommands
+///
+/// Each prints the resulting patch to stdout.
+#[derive(Subcommand, Debug)]
+pub enum PatchCommand {
+    /// Print the patch that undoes PATCH, e.g. to roll back a deployment
+    ///
+    /// A patch does not record the values it removes or replaces, so the
+    /// document it was (or will be) applied to is needed to restore them.
+    Invert {
+        /// JSON Patch file
+        patch: PathBuf,
+
+        /// JSON file the patch applies to
+        document: PathBuf,
+    },
+    /// Combine patches applied one after another into a single patch
+    ///
+    /// Operations later patches override or undo are merged away, e.g.
+    /// `rjd patch compose p1.json p2.json > combined.json`.
+    Compose {
+        /// JSON Patch files in the order they are applied (at least two)
+        #[arg(required = true, num_args = 2..)]
+        patches: Vec<PathBuf>,
+    },
+    /// Print the shortest patch with the same effect as PATCH on DOCUMENT
+    ///
+    /// Compares the simplified patch with the changes between DOCUMENT and
+    /// its patched result, and prints the one with fewer operations.
+    Minimize {
+        /// JSON Patch file
+        patch: PathBuf,
+
+        /// JSON file the patch applies to
+        document: PathBuf,
+    },
+}
+
+/// `rjd fuzz-corpus` subcommands
+#[derive(Subcommand, Debug)]
+pub enum FuzzCorpusCommand {
+    /// Save two inputs as a fuzz corpus entry, e.g. to contribute a failing case
+    Export {
+        /// First JSON file or inline JSON
+        old: String,
+
+        /// Second JSON file or inline JSON
+        new: String,
+
+        /// Corpus directory to write to
+        #[arg(
+            long,
+            value_name = "DIR",
+            default_value = "fuzz/corpus/patch_roundtrip"
+        )]
+        dir: PathBuf,
+    },
+}
+
+/// Parse a duration such as `5s`, `250ms`, `1.5m` or `2h` (a bare number is seconds)
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let scale = match unit {
+        "ms" => 0.001,
+        "" | "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        _ => return Err(format!("unknown unit '{}' (use ms, s, m or h)", unit)),
+    };
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", text))?;
+    Duration::try_from_secs_f64(value * scale).map_err(|e| e.to_string())
+}
+
+/// Parse a size such as `512M`, `2G`, `64KiB` or `1048576` (a bare number is bytes)
+fn parse_size(text: &str) -> Result<u64, String> {
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let unit = unit.to_ascii_uppercase();
+    let shift = match unit.trim_end_matches("IB").trim_end_matches('B') {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return Err(format!("unknown unit '{}' (use K, M, G or T)", unit)),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|value| value.checked_mul(1 << shift))
+        .ok_or_else(|| format!("invalid size '{}'", text))
+}
+
+/// Parse a percentage such as `5%`, `0.5%` or `5`
+fn parse_percent(text: &str) -> Result<f64, String> {
+    let number = text.strip_suffix('%').unwrap_or(text);
+    match number.parse::<f64>() {
+        Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(percent),
+        _ => Err(format!(
+            "expected a percentage between 0% and 100%, got '{}'",
+            text
+        )),
+    }
+}
+
+fn parse_array_strategy(text: &str) -> Result<(String, ArrayDiff), String> {
+    let (pattern, strategy) = text
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected PATTERN=STRATEGY, got '{}'", text))?;
+    Ok((pattern.to_string(), strategy.parse()?))
+}
+
+fn parse_threshold(text: &str) -> Result<SimilarityThreshold, String> {
+    text.parse()
+        .ok()
+        .and_then(SimilarityThreshold::new)
+        .ok_or_else(|| format!("expected a number between 0 and 1, got '{}'", text))
 }
 
 impl Args {
+    /// The array pairing the diff options ask for
+    pub fn array_diff(&self) -> ArrayDiff {
+        match self.array_diff {
+            ArrayMode::Positional => ArrayDiff::Positional,
+            ArrayMode::Similarity => {
+                ArrayDiff::Similarity(self.similarity_threshold.unwrap_or_default())
+            }
+        }
+    }
+
+    /// The clap command, with the usage examples appended to `--help`
+    pub fn full_command() -> clap::Command {
+        Self::command().after_long_help(help::examples_text())
+    }
+
+    /// Parse the process arguments, exiting with a usage error if they are invalid
+    ///
+    /// Options missing from the command line are taken from the nearest
+    /// config file (see [`crate::config`]) unless `--no-config` is given.
+    /// Without `--format`, `--template` selects the template format, and
+    /// otherwise the format is inferred from the extension of an `--output`
+    /// file.
+    pub fn parse_command_line() -> Result<Self, RjdError> {
+        let command = Self::full_command();
+        let argv: Vec<OsString> = std::env::args_os().collect();
+        let mut matches = command.clone().get_matches_from(&argv);
+
+        let config_path = match std::env::current_dir() {
+            Ok(cwd) if matches.subcommand().is_none() && !matches.get_flag("no_config") => {
+                config::discover(&cwd)
+            }
+            _ => None,
+        };
+        if let Some(path) = config_path {
+            let options = config::load(&path)?;
+            let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+            let defaults = config::to_args(&options, &command, given, &path)?;
+            let merged = argv
+                .iter()
+                .take(1)
+                .chain(&defaults)
+                .chain(argv.iter().skip(1));
+            matches = command.get_matches_from(merged);
+        }
+
+        let mut args = Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        if matches.value_source("format") != Some(ValueSource::CommandLine) {
+            if args.template.is_some() {
+                args.format = OutputFormat::Other("template".to_string());
+            } else if let Some(format) = args.output.inferred_format() {
+                args.format = format;
+            }
+        }
+        Ok(args)
+    }
+
     /// Validate command-line arguments
     pub fn validate(&self) -> Result<(), RjdError> {
+        // Subcommands validate their own arguments
+        if self.command.is_some() {
+            return Ok(());
+        }
+
+        if self.stdin_separator.is_some() && (!self.stdin || self.file1.is_some()) {
+            return Err(RjdError::InvalidArgs {
+                message: "--stdin-separator requires --stdin without file arguments".to_string(),
+            });
+        }
+
+        if self.parallel && !cfg!(feature = "parallel") {
+            return Err(RjdError::InvalidArgs {
+                message:
+                    "--parallel is not available: rjd was built without the `parallel` feature"
+                        .to_string(),
+            });
+        }
+
+        let template_format = self.format == OutputFormat::Other("template".to_string());
+        if self.template.is_some() != template_format {
+            return Err(RjdError::InvalidArgs {
+                message: "--format template and --template FILE go together".to_string(),
+            });
+        }
+
+        if let Some(format) = self.input_format.filter(|&f| f != InputFormat::Json) {
+            let conflict = if self.inline {
+                Some("--inline")
+            } else if self.extract_json {
+                Some("--extract-json")
+            } else if self.file1.is_none() {
+                Some("reading both inputs from stdin")
+            } else {
+                None
+            };
+            if let Some(conflict) = conflict {
+                return Err(RjdError::InvalidArgs {
+                    message: format!("--input-format {} cannot be used with {}", format, conflict),
+                });
+            }
+        }
+
+        if self.extract_json && self.file1.is_none() {
+            return Err(RjdError::InvalidArgs {
+                message: "--extract-json cannot be used when both inputs come from stdin"
+                    .to_string(),
+            });
+        }
+
+        if self.file1.is_none() && !self.stdin {
+            return Err(RjdError::InvalidArgs {
+                message: "Missing first file argument".to_string(),
+            });
+        }
+
         // If not using stdin, file2 must be provided
         if !self.stdin && self.file2.is_none() {
             return Err(RjdError::MissingFile2);
         }
 
-        // Validate ignore files exist
-        for ignore_path in &self.ignore_json {
+        // Validate ignore and path policy files exist
+        let pattern_files = self
+            .ignore_json
+            .iter()
+            .chain(&self.require_paths)
+            .chain(&self.expect_json)
+            .chain(&self.must_change);
+        for ignore_path in pattern_files {
             let path = PathBuf::from(ignore_path);
             if !path.exists() {
                 return Err(RjdError::FileRead {
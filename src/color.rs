@@ -0,0 +1,285 @@
+//! Colored terminal output
+//!
+//! [`ColorWriter`] highlights JSON as it is written, so every formatter gets
+//! the same coloring without knowing about it. [`should_colorize`] applies the
+//! `--color` choice together with the `NO_COLOR`, `FORCE_COLOR`, `CLICOLOR`
+//! and `CLICOLOR_FORCE` environment conventions.
+
+use std::io::{self, Write};
+
+const KEY: &[u8] = b"\x1b[34m";
+const STRING: &[u8] = b"\x1b[32m";
+const NUMBER: &[u8] = b"\x1b[36m";
+const LITERAL: &[u8] = b"\x1b[35m";
+const RESET: &[u8] = b"\x1b[0m";
+
+/// When to use colored output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal, subject to environment variables
+    #[default]
+    Auto,
+    /// Always color
+    Always,
+    /// Never color
+    Never,
+}
+
+/// Decide whether output should be colored
+///
+/// An explicit `Always`/`Never` wins. For `Auto`, in order of precedence:
+/// a non-empty `NO_COLOR` disables color; `FORCE_COLOR` or `CLICOLOR_FORCE`
+/// set to anything but `0` enables it; `CLICOLOR=0` disables it; otherwise
+/// color is used only when writing to a terminal.
+///
+/// `env` looks up environment variables, which keeps the decision testable.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::color::{should_colorize, ColorChoice};
+///
+/// let env = |name: &str| (name == "NO_COLOR").then(|| "1".to_string());
+/// assert!(!should_colorize(ColorChoice::Auto, true, env));
+/// assert!(should_colorize(ColorChoice::Always, false, env));
+/// ```
+pub fn should_colorize<F>(choice: ColorChoice, is_terminal: bool, env: F) -> bool
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let set = |name: &str| env(name).filter(|value| !value.is_empty());
+    let enabled = |name: &str| set(name).is_some_and(|value| value != "0");
+
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            if set("NO_COLOR").is_some() {
+                false
+            } else if enabled("FORCE_COLOR") || enabled("CLICOLOR_FORCE") {
+                true
+            } else if set("CLICOLOR").as_deref() == Some("0") {
+                false
+            } else {
+                is_terminal
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Normal,
+    InString {
+        escaped: bool,
+    },
+    /// A string just ended; whether it is a key depends on the next token
+    AfterString,
+    Scalar,
+}
+
+/// Writer that adds ANSI colors to JSON text passing through it
+///
+/// Keys, string values, numbers and literals get distinct colors. Input can
+/// arrive in arbitrary chunks; a string is held back only until the next
+/// non-whitespace byte shows whether it was an object key.
+pub struct ColorWriter<W: Write> {
+    inner: W,
+    state: State,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> ColorWriter<W> {
+    /// Wrap a writer
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            state: State::Normal,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Emit the held-back string, colored as a key or a value
+    fn emit_pending(&mut self, is_key: bool) -> io::Result<()> {
+        let end = self
+            .pending
+            .iter()
+            .rposition(|&b| b == b'"')
+            .map_or(0, |i| i + 1);
+        let (string, whitespace) = self.pending.split_at(end);
+        self.inner.write_all(if is_key { KEY } else { STRING })?;
+        self.inner.write_all(string)?;
+        self.inner.write_all(RESET)?;
+        self.inner.write_all(whitespace)?;
+        self.pending.clear();
+        Ok(())
+    }
+
+    fn process(&mut self, byte: u8) -> io::Result<()> {
+        match self.state {
+            State::Normal => match byte {
+                b'"' => {
+                    self.pending.push(byte);
+                    self.state = State::InString { escaped: false };
+                }
+                b'-' | b'0'..=b'9' => {
+                    self.inner.write_all(NUMBER)?;
+                    self.inner.write_all(&[byte])?;
+                    self.state = State::Scalar;
+                }
+                b't' | b'f' | b'n' => {
+                    self.inner.write_all(LITERAL)?;
+                    self.inner.write_all(&[byte])?;
+                    self.state = State::Scalar;
+                }
+                _ => self.inner.write_all(&[byte])?,
+            },
+            State::InString { escaped } => {
+                self.pending.push(byte);
+                self.state = match byte {
+                    _ if escaped => State::InString { escaped: false },
+                    b'\\' => State::InString { escaped: true },
+                    b'"' => State::AfterString,
+                    _ => State::InString { escaped: false },
+                };
+            }
+            State::AfterString => {
+                if byte.is_ascii_whitespace() {
+                    self.pending.push(byte);
+                } else {
+                    self.emit_pending(byte == b':')?;
+                    self.state = State::Normal;
+                    self.process(byte)?;
+                }
+            }
+            State::Scalar => {
+                if byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'+' | b'-') {
+                    self.inner.write_all(&[byte])?;
+                } else {
+                    self.inner.write_all(RESET)?;
+                    self.state = State::Normal;
+                    self.process(byte)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ColorWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            self.process(byte)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.state {
+            State::AfterString => {
+                self.emit_pending(false)?;
+                self.state = State::Normal;
+            }
+            State::Scalar => {
+                self.inner.write_all(RESET)?;
+                self.state = State::Normal;
+            }
+            _ => {}
+        }
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colorize(text: &str, chunk: usize) -> String {
+        let mut writer = ColorWriter::new(Vec::new());
+        for part in text.as_bytes().chunks(chunk) {
+            writer.write_all(part).unwrap();
+        }
+        writer.flush().unwrap();
+        String::from_utf8(writer.inner).unwrap()
+    }
+
+    #[test]
+    fn test_color_writer_highlights_tokens() {
+        let colored = colorize("{\"k\": \"v\", \"n\": -1.5, \"b\": true}\n", 3);
+        assert_eq!(
+            colored,
+            "{\x1b[34m\"k\"\x1b[0m: \x1b[32m\"v\"\x1b[0m, \x1b[34m\"n\"\x1b[0m: \
+             \x1b[36m-1.5\x1b[0m, \x1b[34m\"b\"\x1b[0m: \x1b[35mtrue\x1b[0m}\n"
+        );
+    }
+
+    #[test]
+    fn test_color_writer_handles_escapes_and_chunking() {
+        let text = "[\"a\\\"b: c\", null]\n";
+        let stripped = colorize(text, 1)
+            .replace("\x1b[32m", "")
+            .replace("\x1b[35m", "")
+            .replace("\x1b[0m", "");
+        assert_eq!(stripped, text);
+        assert!(colorize(text, 1).contains("\x1b[32m\"a\\\"b: c\"\x1b[0m"));
+    }
+
+    #[test]
+    fn test_should_colorize_env_conventions() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        assert!(should_colorize(ColorChoice::Auto, true, env(&[])));
+        assert!(!should_colorize(ColorChoice::Auto, false, env(&[])));
+        assert!(!should_colorize(
+            ColorChoice::Auto,
+            true,
+            env(&[("NO_COLOR", "1")])
+        ));
+        assert!(should_colorize(
+            ColorChoice::Auto,
+            true,
+            env(&[("NO_COLOR", "")])
+        ));
+        assert!(should_colorize(
+            ColorChoice::Auto,
+            false,
+            env(&[("FORCE_COLOR", "1")])
+        ));
+        assert!(!should_colorize(
+            ColorChoice::Auto,
+            false,
+            env(&[("FORCE_COLOR", "0")])
+        ));
+        assert!(should_colorize(
+            ColorChoice::Auto,
+            false,
+            env(&[("CLICOLOR_FORCE", "1")])
+        ));
+        assert!(!should_colorize(
+            ColorChoice::Auto,
+            true,
+            env(&[("CLICOLOR", "0")])
+        ));
+        assert!(!should_colorize(
+            ColorChoice::Auto,
+            false,
+            env(&[("NO_COLOR", "1"), ("FORCE_COLOR", "1")])
+        ));
+        assert!(should_colorize(
+            ColorChoice::Always,
+            false,
+            env(&[("NO_COLOR", "1")])
+        ));
+        assert!(!should_colorize(
+            ColorChoice::Never,
+            true,
+            env(&[("FORCE_COLOR", "1")])
+        ));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::cli::Expectation;
+use rjd::{diff, load_json_file_with_config, JsonPath, LoadConfig, RjdError};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+/// Check that each expected path changed to its value and nothing else changed
+///
+/// The report lists every expectation with the old and new value found and,
+/// if it failed, why; `unexpected` lists the JSON Pointers of changes that are
+/// neither at nor below an expected path. The report is printed before a
+/// failed assertion is returned as a policy violation.
+pub fn run(old: &PathBuf, new: &PathBuf, expectations: &[Expectation]) -> Result<(), RjdError> {
+    let config = LoadConfig::from_env();
+    let old = load_json_file_with_config(old, &config)?;
+    let new = load_json_file_with_config(new, &config)?;
+
+    let results: Vec<Value> = expectations
+        .iter()
+        .map(|expectation| check(expectation, &old, &new))
+        .collect();
+    let failed = results.iter().filter(|r| r["passed"] == false).count();
+
+    let allowed: Vec<String> = expectations
+        .iter()
+        .map(|expectation| format!("{}/**", expectation.pointer))
+        .collect();
+    let unexpected: Vec<String> = diff(&old, &new)
+        .mark_unexpected(&allowed)
+        .iter()
+        .map(JsonPath::to_json_pointer)
+        .collect();
+
+    let passed = failed == 0 && unexpected.is_empty();
+    let report = json!({
+        "passed": passed,
+        "expectations": results,
+        "unexpected": unexpected,
+    });
+    let output = serde_json::to_string_pretty(&report).map_err(|e| RjdError::Formatter {
+        message: e.to_string(),
+    })?;
+    println!("{}", output);
+
+    if passed {
+        Ok(())
+    } else {
+        Err(RjdError::PolicyViolation {
+            message: format!(
+                "Assertion failed: {} of {} expectations not met, {} unexpected changes",
+                failed,
+                expectations.len(),
+                unexpected.len()
+            ),
+        })
+    }
+}
+
+/// The outcome of one expectation
+fn check(expectation: &Expectation, old: &Value, new: &Value) -> Value {
+    let resolve = |document| {
+        JsonPath::from_pointer(&expectation.pointer)
+            .ok()
+            .and_then(|path| path.resolve(document).cloned())
+    };
+    let (old_value, new_value) = (resolve(old), resolve(new));
+
+    let failure = match &new_value {
+        None => Some("path not found in the new document"),
+        Some(value) if *value != expectation.value => {
+            Some("new value differs from the expected value")
+        }
+        Some(_) if old_value == new_value => Some("value did not change"),
+        Some(_) => None,
+    };
+
+    let mut result = json!({
+        "path": expectation.pointer,
+        "expected": expectation.value,
+        "oldValue": old_value,
+        "newValue": new_value,
+        "passed": failure.is_none(),
+    });
+    if let Some(reason) = failure {
+        result["reason"] = json!(reason);
+    }
+    result
+}
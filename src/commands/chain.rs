@@ -0,0 +1,76 @@
+use crate::cli::OutputFormat;
+use rjd::formatter::FormatError;
+use rjd::{
+    create_formatter_with_options, diff, load_json_file_with_config, FormatterOptions, LoadConfig,
+    RjdError,
+};
+use serde_json::{json, Map, Value};
+use std::path::PathBuf;
+
+/// Print the changes between each consecutive pair of versions plus a summary
+///
+/// Each step holds the formatted diff of two neighbouring files. The summary
+/// totals the changes and lists every changed path with the number of steps
+/// that changed it, in order of first change.
+pub fn run(files: &[PathBuf], format: &OutputFormat, sort: bool) -> Result<(), RjdError> {
+    let config = LoadConfig::from_env();
+    let formatter =
+        create_formatter_with_options(&format.to_string(), &FormatterOptions::new(sort)).map_err(
+            |e| RjdError::Formatter {
+                message: e.to_string(),
+            },
+        )?;
+    let formatter_error = |e: FormatError| RjdError::Formatter {
+        message: e.to_string(),
+    };
+
+    let mut steps = Vec::new();
+    let (mut added, mut removed, mut modified) = (0, 0, 0);
+    let mut path_counts: Map<String, Value> = Map::new();
+
+    let mut previous = load_json_file_with_config(&files[0], &config)?;
+    for pair in files.windows(2) {
+        let current = load_json_file_with_config(&pair[1], &config)?;
+        let mut changes = diff(&previous, &current);
+        if formatter.uses_before() {
+            changes.before = Some(previous.clone());
+        }
+
+        added += changes.added.len();
+        removed += changes.removed.len();
+        modified += changes.modified.len();
+        for path in changes.paths() {
+            let count = path_counts.entry(path.to_string()).or_insert(json!(0));
+            *count = json!(count.as_u64().unwrap_or(0) + 1);
+        }
+
+        let output = formatter.format(&changes).map_err(formatter_error)?;
+        let output: Value = serde_json::from_str(&output).map_err(|e| formatter_error(e.into()))?;
+        steps.push(json!({
+            "from": pair[0].display().to_string(),
+            "to": pair[1].display().to_string(),
+            "changes": output,
+        }));
+        previous = current;
+    }
+
+    let paths: Vec<Value> = path_counts
+        .into_iter()
+        .map(|(path, steps)| json!({"path": path, "steps": steps}))
+        .collect();
+    let report = json!({
+        "steps": steps,
+        "summary": {
+            "versions": files.len(),
+            "added": added,
+            "removed": removed,
+            "modified": modified,
+            "total": added + removed + modified,
+            "paths": paths,
+        }
+    });
+
+    let output = serde_json::to_string_pretty(&report).map_err(|e| formatter_error(e.into()))?;
+    println!("{}", output);
+    Ok(())
+}
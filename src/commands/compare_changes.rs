@@ -0,0 +1,48 @@
+use crate::commands::render;
+use rjd::formatter::sort_json_value;
+use rjd::{Changes, RjdError};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+/// Print the changes common to two saved change sets and those unique to each
+///
+/// Changes are matched by path and must have the same kind and values to be
+/// common, so a path changed differently in the two sets is listed as unique
+/// to both.
+pub fn run(first: &PathBuf, second: &PathBuf, sort: bool) -> Result<(), RjdError> {
+    let first = render::load(first)?;
+    let second = render::load(second)?;
+
+    let common = first.intersection(&second);
+    let only_first = first.difference(&second);
+    let only_second = second.difference(&first);
+    let report = json!({
+        "common": changes_value(&common)?,
+        "onlyInFirst": changes_value(&only_first)?,
+        "onlyInSecond": changes_value(&only_second)?,
+        "summary": {
+            "common": common.len(),
+            "onlyInFirst": only_first.len(),
+            "onlyInSecond": only_second.len(),
+        }
+    });
+    let report = if sort {
+        sort_json_value(&report)
+    } else {
+        report
+    };
+
+    let output = serde_json::to_string_pretty(&report).map_err(formatter_error)?;
+    println!("{}", output);
+    Ok(())
+}
+
+fn changes_value(changes: &Changes) -> Result<Value, RjdError> {
+    serde_json::to_value(changes).map_err(formatter_error)
+}
+
+fn formatter_error(e: serde_json::Error) -> RjdError {
+    RjdError::Formatter {
+        message: e.to_string(),
+    }
+}
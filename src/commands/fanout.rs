@@ -0,0 +1,131 @@
+use crate::cli::OutputFormat;
+use rjd::formatter::FormatError;
+use rjd::{
+    create_formatter_with_options, diff, load_json_file_with_config, FormatterOptions, LoadConfig,
+    RjdError,
+};
+use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Diff a baseline against each target and report the paths many targets change
+///
+/// Each target gets the formatted changes from the baseline to it, and with
+/// `save_changes` a change set `DIR/<target name>.changes.json` for `rjd
+/// render`. The summary
+/// lists every path changed in more than `threshold` targets, with the
+/// targets that change it, most widespread first (ties in order of first
+/// change).
+pub fn run(
+    baseline: &PathBuf,
+    targets: &[PathBuf],
+    threshold: usize,
+    save_changes: Option<&Path>,
+    format: &OutputFormat,
+    sort: bool,
+) -> Result<(), RjdError> {
+    let config = LoadConfig::from_env();
+    let formatter =
+        create_formatter_with_options(&format.to_string(), &FormatterOptions::new(sort)).map_err(
+            |e| RjdError::Formatter {
+                message: e.to_string(),
+            },
+        )?;
+    let formatter_error = |e: FormatError| RjdError::Formatter {
+        message: e.to_string(),
+    };
+
+    let write_error = |path: &Path, e: std::io::Error| RjdError::Output {
+        destination: path.display().to_string(),
+        message: e.to_string(),
+    };
+    let change_set_paths = match save_changes {
+        Some(dir) => {
+            fs::create_dir_all(dir).map_err(|e| write_error(dir, e))?;
+            change_set_paths(dir, targets)?
+        }
+        None => Vec::new(),
+    };
+
+    let base = load_json_file_with_config(baseline, &config)?;
+    let mut reports = Vec::new();
+    // Changed paths in order of first change, with the targets changing them
+    let mut paths: Vec<(String, Vec<String>)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for (i, target) in targets.iter().enumerate() {
+        let mut changes = diff(&base, &load_json_file_with_config(target, &config)?);
+        if formatter.uses_before() || save_changes.is_some() {
+            changes.before = Some(base.clone());
+        }
+        let name = target.display().to_string();
+
+        for path in changes.paths() {
+            let path = path.to_string();
+            match positions.get(&path) {
+                Some(&position) => paths[position].1.push(name.clone()),
+                None => {
+                    positions.insert(path.clone(), paths.len());
+                    paths.push((path, vec![name.clone()]));
+                }
+            }
+        }
+
+        let output = formatter.format(&changes).map_err(formatter_error)?;
+        let output: Value = serde_json::from_str(&output).map_err(|e| formatter_error(e.into()))?;
+        let mut report = json!({
+            "target": name,
+            "total": changes.len(),
+            "changes": output,
+        });
+        if let Some(path) = change_set_paths.get(i) {
+            let mut file = fs::File::create(path).map_err(|e| write_error(path, e))?;
+            writeln!(file, "{}", changes.to_json(true)).map_err(|e| write_error(path, e))?;
+            report["changeSet"] = json!(path.display().to_string());
+        }
+        reports.push(report);
+    }
+
+    paths.retain(|(_, changed_in)| changed_in.len() > threshold);
+    paths.sort_by_key(|(_, changed_in)| Reverse(changed_in.len()));
+    let paths: Vec<Value> = paths
+        .into_iter()
+        .map(|(path, changed_in)| {
+            json!({"path": path, "count": changed_in.len(), "targets": changed_in})
+        })
+        .collect();
+    let report = json!({
+        "baseline": baseline.display().to_string(),
+        "targets": reports,
+        "summary": {
+            "targets": targets.len(),
+            "threshold": threshold,
+            "paths": paths,
+        }
+    });
+
+    let output = serde_json::to_string_pretty(&report).map_err(|e| formatter_error(e.into()))?;
+    println!("{}", output);
+    Ok(())
+}
+
+/// Where to save each target's change set: `dir/<file stem>.changes.json`
+fn change_set_paths(dir: &Path, targets: &[PathBuf]) -> Result<Vec<PathBuf>, RjdError> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for target in targets {
+        let stem = target.file_stem().unwrap_or(target.as_os_str());
+        let path = dir.join(format!("{}.changes.json", stem.to_string_lossy()));
+        if paths.contains(&path) {
+            return Err(RjdError::InvalidArgs {
+                message: format!(
+                    "two targets would both save their changes to {}; give targets distinct file names",
+                    path.display()
+                ),
+            });
+        }
+        paths.push(path);
+    }
+    Ok(paths)
+}
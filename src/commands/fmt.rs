@@ -0,0 +1,24 @@
+use crate::output::{Destination, JSON};
+use rjd::{canonicalize, load_json_file_with_config, to_jcs_string, LoadConfig, RjdError};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Print a document in canonical form: sorted keys and normalized numbers,
+/// pretty-printed, or the compact RFC 8785 form with `jcs`
+pub fn run(file: &PathBuf, jcs: bool, output: &Destination) -> Result<(), RjdError> {
+    let document = load_json_file_with_config(file, &LoadConfig::from_env())?;
+    let text = if jcs {
+        to_jcs_string(&document)
+    } else {
+        serde_json::to_string_pretty(&canonicalize(&document)).map_err(|e| RjdError::Formatter {
+            message: e.to_string(),
+        })?
+    };
+
+    let mut sink = output.open(JSON)?;
+    writeln!(sink, "{}", text).map_err(|e| RjdError::Output {
+        destination: output.to_string(),
+        message: e.to_string(),
+    })?;
+    sink.finish()
+}
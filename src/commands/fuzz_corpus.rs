@@ -0,0 +1,32 @@
+use rjd::{load_json_input_with_config, LoadConfig, RjdError};
+use std::path::Path;
+
+/// Save an input pair as a fuzz corpus entry and print its path
+///
+/// The entry holds both documents as compact JSON separated by a `---` line,
+/// the input format of the fuzz targets in `fuzz/`. It is named after a hash
+/// of its contents, so exporting the same pair twice yields the same file.
+pub fn export(old: &str, new: &str, dir: &Path) -> Result<(), RjdError> {
+    let config = LoadConfig::from_env();
+    let old = load_json_input_with_config(old, &config)?;
+    let new = load_json_input_with_config(new, &config)?;
+    let content = format!("{}\n---\n{}\n", old, new);
+
+    let write_error = |source| RjdError::FileRead {
+        path: dir.to_path_buf(),
+        source,
+    };
+    std::fs::create_dir_all(dir).map_err(write_error)?;
+    let path = dir.join(format!("{:016x}", fnv1a(content.as_bytes())));
+    std::fs::write(&path, content).map_err(write_error)?;
+
+    println!("{}", path.display());
+    Ok(())
+}
+
+/// 64-bit FNV-1a, stable across platforms and releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
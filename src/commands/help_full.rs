@@ -0,0 +1,39 @@
+use crate::cli::Args;
+use crate::help::examples_roff;
+use clap::CommandFactory;
+use rjd::RjdError;
+use std::io::{self, Write};
+
+/// Print the long help of rjd and every subcommand, or a roff man page
+pub fn run(manpage: bool) -> Result<(), RjdError> {
+    let mut output = Vec::new();
+    let result = if manpage {
+        // The examples get their own section instead of clap_mangen's EXTRA
+        clap_mangen::Man::new(Args::command())
+            .render(&mut output)
+            .map(|()| output.extend_from_slice(examples_roff().as_bytes()))
+    } else {
+        let mut command = Args::full_command();
+        command.build();
+        let mut help = command.render_long_help().to_string();
+        for subcommand in command.get_subcommands_mut() {
+            let name = subcommand.get_name().to_string();
+            if name == "help" {
+                continue;
+            }
+            help.push_str(&format!(
+                "\n\n\u{2500}\u{2500} rjd {} \u{2500}\u{2500}\n\n{}",
+                name,
+                subcommand.render_long_help()
+            ));
+        }
+        writeln!(output, "{}", help.trim_end())
+    };
+
+    result
+        .and_then(|()| io::stdout().lock().write_all(&output))
+        .map_err(|e| RjdError::Output {
+            destination: "stdout".to_string(),
+            message: e.to_string(),
+        })
+}
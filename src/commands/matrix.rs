@@ -0,0 +1,98 @@
+use crate::cli::OutputFormat;
+use rjd::formatter::FormatError;
+use rjd::{
+    create_formatter_with_options, diff, load_json_file_with_config, FormatterOptions, LoadConfig,
+    RjdError,
+};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Diff every pair of documents and print a matrix of change counts
+///
+/// `matrix[i][j]` is the number of changes between documents `i` and `j`
+/// (the same either way round, and 0 on the diagonal). Each pair is also
+/// listed with its counts by kind. With `details`, the changes of each pair
+/// (from the earlier document to the later one) are written to `DIR/I-J.EXT`,
+/// numbered by position in `documents`.
+pub fn run(
+    files: &[PathBuf],
+    details: Option<&Path>,
+    format: &OutputFormat,
+    sort: bool,
+) -> Result<(), RjdError> {
+    let config = LoadConfig::from_env();
+    let formatter =
+        create_formatter_with_options(&format.to_string(), &FormatterOptions::new(sort)).map_err(
+            |e| RjdError::Formatter {
+                message: e.to_string(),
+            },
+        )?;
+    let formatter_error = |e: FormatError| RjdError::Formatter {
+        message: e.to_string(),
+    };
+    let write_error = |path: &Path, e: std::io::Error| RjdError::Output {
+        destination: path.display().to_string(),
+        message: e.to_string(),
+    };
+    if let Some(dir) = details {
+        fs::create_dir_all(dir).map_err(|e| write_error(dir, e))?;
+    }
+
+    let documents = files
+        .iter()
+        .map(|file| load_json_file_with_config(file, &config))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut matrix = vec![vec![0; files.len()]; files.len()];
+    let mut pairs = Vec::new();
+    for i in 0..documents.len() {
+        for j in i + 1..documents.len() {
+            let mut changes = diff(&documents[i], &documents[j]);
+            if details.is_some() && formatter.uses_before() {
+                changes.before = Some(documents[i].clone());
+            }
+            matrix[i][j] = changes.len();
+            matrix[j][i] = changes.len();
+
+            let mut pair = json!({
+                "from": files[i].display().to_string(),
+                "to": files[j].display().to_string(),
+                "added": changes.added.len(),
+                "removed": changes.removed.len(),
+                "modified": changes.modified.len(),
+                "total": changes.len(),
+            });
+            if let Some(dir) = details {
+                let path = dir.join(format!("{}-{}.{}", i, j, extension(format)));
+                let mut file = fs::File::create(&path).map_err(|e| write_error(&path, e))?;
+                formatter
+                    .write_to(&changes, &mut file)
+                    .map_err(formatter_error)?;
+                pair["details"] = json!(path.display().to_string());
+            }
+            pairs.push(pair);
+        }
+    }
+
+    let report = json!({
+        "documents": files.iter().map(|file| file.display().to_string()).collect::<Vec<_>>(),
+        "matrix": matrix,
+        "pairs": pairs,
+    });
+    let output = serde_json::to_string_pretty(&report).map_err(|e| formatter_error(e.into()))?;
+    println!("{}", output);
+    Ok(())
+}
+
+/// File extension for pair details in `format`
+fn extension(format: &OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Sarif => "sarif",
+        OutputFormat::Junit => "xml",
+        OutputFormat::Gcc => "txt",
+        OutputFormat::Jsonl => "jsonl",
+        OutputFormat::Msgpack => "msgpack",
+        _ => "json",
+    }
+}
@@ -0,0 +1,98 @@
+//! Subcommand handlers for the rjd binary
+
+mod assert;
+mod chain;
+mod compare_changes;
+mod fanout;
+mod fmt;
+mod fuzz_corpus;
+mod help_full;
+mod matrix;
+mod patch;
+mod patch_in_place;
+mod render;
+mod rev;
+mod schema;
+mod snapshot;
+mod stats;
+
+use crate::cli::{Command, FuzzCorpusCommand, PatchCommand};
+use rjd::RjdError;
+
+/// Run a subcommand
+pub fn run(command: &Command) -> Result<(), RjdError> {
+    match command {
+        Command::Schema {
+            format,
+            format_version,
+        } => schema::run(format, *format_version),
+        Command::Chain {
+            files,
+            format,
+            sort,
+        } => chain::run(files, format, *sort),
+        Command::Fanout {
+            baseline,
+            targets,
+            threshold,
+            save_changes,
+            format,
+            sort,
+        } => fanout::run(
+            baseline,
+            targets,
+            *threshold,
+            save_changes.as_deref(),
+            format,
+            *sort,
+        ),
+        Command::Matrix {
+            files,
+            details,
+            format,
+            sort,
+        } => matrix::run(files, details.as_deref(), format, *sort),
+        Command::FuzzCorpus {
+            command: FuzzCorpusCommand::Export { old, new, dir },
+        } => fuzz_corpus::export(old, new, dir),
+        Command::Patch { command } => match command {
+            PatchCommand::Invert { patch, document } => patch::invert(patch, document),
+            PatchCommand::Compose { patches } => patch::compose(patches),
+            PatchCommand::Minimize { patch, document } => patch::minimize(patch, document),
+        },
+        Command::PatchInPlace {
+            target,
+            with,
+            backup,
+        } => patch_in_place::run(target, with, *backup),
+        Command::Rev {
+            old,
+            new,
+            format,
+            sort,
+        } => rev::run(old, new, format, *sort),
+        Command::Render {
+            file,
+            reverse,
+            format,
+            sort,
+            output,
+        } => render::run(file, *reverse, format, *sort, output),
+        Command::CompareChanges {
+            first,
+            second,
+            sort,
+        } => compare_changes::run(first, second, *sort),
+        Command::Assert { old, new, expect } => assert::run(old, new, expect),
+        Command::Stats { file, top } => stats::run(file, *top),
+        Command::Snapshot {
+            golden,
+            live,
+            update,
+            format,
+            sort,
+        } => snapshot::run(golden, live, *update, format, *sort),
+        Command::Fmt { file, jcs, output } => fmt::run(file, *jcs, output),
+        Command::HelpFull { manpage } => help_full::run(*manpage),
+    }
+}
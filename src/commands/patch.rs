@@ -0,0 +1,56 @@
+use rjd::{
+    compose_patches, invert_patch, load_json_file_with_config, minimize_patch, LoadConfig,
+    PatchError, RjdError,
+};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Print the patch undoing `patch` on `document`
+pub fn invert(patch: &Path, document: &Path) -> Result<(), RjdError> {
+    let config = LoadConfig::from_env();
+    let document = load_json_file_with_config(&document.to_path_buf(), &config)?;
+    let patch_value = load_json_file_with_config(&patch.to_path_buf(), &config)?;
+    let inverse = invert_patch(&document, &patch_value).map_err(|e| failed("invert", patch, e))?;
+    print(&inverse)
+}
+
+/// Print one patch with the effect of `patches` applied in order
+pub fn compose(patches: &[PathBuf]) -> Result<(), RjdError> {
+    let config = LoadConfig::from_env();
+    // Simplifying each patch on its own first names the file of an invalid operation
+    let simplified = patches
+        .iter()
+        .map(|patch| {
+            let value = load_json_file_with_config(patch, &config)?;
+            compose_patches(std::slice::from_ref(&value)).map_err(|e| failed("compose", patch, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let composed = compose_patches(&simplified).map_err(|e| RjdError::Internal {
+        message: format!("Failed to compose patches: {}", e),
+    })?;
+    print(&composed)
+}
+
+/// Print the shortest patch with the effect of `patch` on `document`
+pub fn minimize(patch: &Path, document: &Path) -> Result<(), RjdError> {
+    let config = LoadConfig::from_env();
+    let document = load_json_file_with_config(&document.to_path_buf(), &config)?;
+    let patch_value = load_json_file_with_config(&patch.to_path_buf(), &config)?;
+    let minimized =
+        minimize_patch(&document, &patch_value).map_err(|e| failed("minimize", patch, e))?;
+    print(&minimized)
+}
+
+fn failed(action: &str, patch: &Path, error: PatchError) -> RjdError {
+    RjdError::Internal {
+        message: format!("Failed to {} {}: {}", action, patch.display(), error),
+    }
+}
+
+fn print(patch: &Value) -> Result<(), RjdError> {
+    let output = serde_json::to_string_pretty(patch).map_err(|e| RjdError::Formatter {
+        message: e.to_string(),
+    })?;
+    println!("{}", output);
+    Ok(())
+}
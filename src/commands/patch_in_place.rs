@@ -0,0 +1,71 @@
+use crate::output::temp_path;
+use rjd::formatter::{Formatter, FullAfterFormatter};
+use rjd::{
+    apply_patch, diff, load_json_file_with_config, parse_json_with_config,
+    read_file_with_config_and_policy,
+};
+use rjd::{LoadConfig, RjdError, SymlinkPolicy};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Apply an RFC 6902 patch to a JSON file on disk
+///
+/// The result is written to a temporary file next to the target and renamed
+/// over it, so readers never see a half-written file. Untouched parts of the
+/// file keep their text; see [`FullAfterFormatter`] for how changed values
+/// are laid out. With `backup`, the original is first copied to `<target>.bak`.
+pub fn run(target: &Path, patch: &Path, backup: bool) -> Result<(), RjdError> {
+    let config = LoadConfig::from_env();
+    // Renaming over a symlink would replace the link, not the file it points to
+    let text =
+        read_file_with_config_and_policy(&target.to_path_buf(), &config, SymlinkPolicy::Reject)?;
+    let document = parse_json_with_config(&text, &config)?;
+    let patch_value = load_json_file_with_config(&patch.to_path_buf(), &config)?;
+
+    let patched = apply_patch(&document, &patch_value).map_err(|e| RjdError::Internal {
+        message: format!("Failed to apply {}: {}", patch.display(), e),
+    })?;
+    let mut output = FullAfterFormatter::new(Some(text.clone()))
+        .format(&diff(&document, &patched))
+        .map_err(|e| write_error(target, e))?;
+    if text.ends_with('\n') {
+        output.push('\n');
+    }
+
+    if backup {
+        let backup_path = backup_path(target);
+        fs::copy(target, &backup_path).map_err(|e| write_error(&backup_path, e))?;
+    }
+    replace_file(target, output.as_bytes()).map_err(|e| write_error(target, e))
+}
+
+/// Write `contents` to a temporary file beside `target`, then rename it over `target`
+fn replace_file(target: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let temp = temp_path(target);
+
+    let result = (|| {
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::set_permissions(&temp, fs::metadata(target)?.permissions())?;
+        fs::rename(&temp, target)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+fn backup_path(target: &Path) -> PathBuf {
+    let mut path = target.as_os_str().to_owned();
+    path.push(".bak");
+    PathBuf::from(path)
+}
+
+fn write_error(path: &Path, error: impl std::fmt::Display) -> RjdError {
+    RjdError::Output {
+        destination: path.display().to_string(),
+        message: error.to_string(),
+    }
+}
@@ -0,0 +1,46 @@
+use crate::cli::OutputFormat;
+use crate::output::Destination;
+use rjd::{
+    create_formatter_with_options, read_file_with_config_and_policy, Changes, FormatterOptions,
+    LoadConfig, RjdError, SymlinkPolicy,
+};
+use std::path::PathBuf;
+
+/// Render a change set saved with `--save-changes` in any output format
+///
+/// With `reverse`, the changes are inverted first (see [`Changes::invert`]).
+pub fn run(
+    file: &PathBuf,
+    reverse: bool,
+    format: &OutputFormat,
+    sort: bool,
+    output: &Destination,
+) -> Result<(), RjdError> {
+    let mut changes = load(file)?;
+    if reverse {
+        changes.invert();
+    }
+    let formatter =
+        create_formatter_with_options(&format.to_string(), &FormatterOptions::new(sort)).map_err(
+            |e| RjdError::Formatter {
+                message: e.to_string(),
+            },
+        )?;
+    let mut sink = output.open(formatter.content_type())?;
+    formatter
+        .write_to(&changes, &mut sink)
+        .map_err(|e| RjdError::Formatter {
+            message: e.to_string(),
+        })?;
+    sink.finish()
+}
+
+/// Read a change set saved with `--save-changes`
+pub(super) fn load(file: &PathBuf) -> Result<Changes, RjdError> {
+    let config = LoadConfig::from_env();
+    let text = read_file_with_config_and_policy(file, &config, SymlinkPolicy::Reject)?;
+    Changes::from_json(&text).map_err(|source| RjdError::JsonParse {
+        path: file.clone(),
+        source,
+    })
+}
@@ -0,0 +1,68 @@
+use crate::cli::OutputFormat;
+use rjd::{
+    create_formatter_with_options, diff, load_json_file_with_config, parse_json_with_config,
+    FormatterOptions, LoadConfig, RjdError,
+};
+use serde_json::Value;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Diff two versions of a JSON file stored in git
+///
+/// A `REV:PATH` spec is read from git's object storage with `git cat-file`,
+/// so no temporary files are needed; as in git, `PATH` is relative to the
+/// repository root unless it starts with `./`. A spec without a colon is a
+/// file in the working tree.
+pub fn run(old: &str, new: &str, format: &OutputFormat, sort: bool) -> Result<(), RjdError> {
+    let config = LoadConfig::from_env();
+    let old = load(old, &config)?;
+    let new = load(new, &config)?;
+
+    let formatter =
+        create_formatter_with_options(&format.to_string(), &FormatterOptions::new(sort)).map_err(
+            |e| RjdError::Formatter {
+                message: e.to_string(),
+            },
+        )?;
+    let mut changes = diff(&old, &new);
+    if formatter.uses_before() {
+        changes.before = Some(old);
+    }
+    let mut stdout = io::stdout().lock();
+    formatter
+        .write_to(&changes, &mut stdout)
+        .and_then(|()| Ok(stdout.flush()?))
+        .map_err(|e| RjdError::Formatter {
+            message: e.to_string(),
+        })
+}
+
+fn load(spec: &str, config: &LoadConfig) -> Result<Value, RjdError> {
+    if !spec.contains(':') {
+        return load_json_file_with_config(&PathBuf::from(spec), config);
+    }
+
+    let error = |message: String| RjdError::Internal {
+        message: format!("Failed to read '{}' from git: {}", spec, message),
+    };
+    let output = Command::new("git")
+        .args(["cat-file", "blob", spec])
+        .output()
+        .map_err(|e| error(format!("failed to run git: {}", e)))?;
+    if !output.status.success() {
+        return Err(error(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    let size = output.stdout.len() as u64;
+    if size > config.max_file_size {
+        return Err(RjdError::FileTooLarge {
+            path: PathBuf::from(spec),
+            size,
+            limit: config.max_file_size,
+        });
+    }
+    let text = String::from_utf8(output.stdout).map_err(|e| error(e.to_string()))?;
+    parse_json_with_config(&text, config)
+}
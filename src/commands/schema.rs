@@ -0,0 +1,17 @@
+use crate::cli::OutputFormat;
+use rjd::formatter::schema::output_schema;
+use rjd::RjdError;
+
+/// Print the JSON Schema of an output format
+pub fn run(format: &OutputFormat, format_version: u32) -> Result<(), RjdError> {
+    let schema =
+        output_schema(&format.to_string(), format_version).map_err(|e| RjdError::Formatter {
+            message: e.to_string(),
+        })?;
+    let output = serde_json::to_string_pretty(&schema).map_err(|e| RjdError::Formatter {
+        message: e.to_string(),
+    })?;
+
+    println!("{}", output);
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use crate::cli::OutputFormat;
+use crate::output::{Destination, JSON};
+use rjd::{
+    create_formatter_with_options, diff, load_json_file_with_config, FormatterOptions, LoadConfig,
+    RjdError,
+};
+use serde_json::Value;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Compare a live document against a stored golden file
+///
+/// The changes from the golden file to the live document are printed. A
+/// mismatch is a policy violation unless `update` is set, in which case the
+/// golden file is rewritten (pretty-printed) with the live document; a
+/// missing golden file is created the same way.
+pub fn run(
+    golden: &Path,
+    live: &Path,
+    update: bool,
+    format: &OutputFormat,
+    sort: bool,
+) -> Result<(), RjdError> {
+    let config = LoadConfig::from_env();
+    let live_value = load_json_file_with_config(&live.to_path_buf(), &config)?;
+
+    if !golden.exists() {
+        if !update {
+            return Err(RjdError::PolicyViolation {
+                message: format!(
+                    "Snapshot {} does not exist; run with --update to create it",
+                    golden.display()
+                ),
+            });
+        }
+        write_golden(golden, &live_value)?;
+        eprintln!("Created snapshot {}", golden.display());
+        return Ok(());
+    }
+
+    let golden_value = load_json_file_with_config(&golden.to_path_buf(), &config)?;
+    let mut changes = diff(&golden_value, &live_value);
+
+    let formatter =
+        create_formatter_with_options(&format.to_string(), &FormatterOptions::new(sort)).map_err(
+            |e| RjdError::Formatter {
+                message: e.to_string(),
+            },
+        )?;
+    if formatter.uses_before() {
+        changes.before = Some(golden_value);
+    }
+    let mut stdout = io::stdout().lock();
+    formatter
+        .write_to(&changes, &mut stdout)
+        .and_then(|()| Ok(stdout.flush()?))
+        .map_err(|e| RjdError::Formatter {
+            message: e.to_string(),
+        })?;
+
+    if changes.is_empty() {
+        return Ok(());
+    }
+    if update {
+        write_golden(golden, &live_value)?;
+        eprintln!(
+            "Updated snapshot {} ({} changes accepted)",
+            golden.display(),
+            changes.len()
+        );
+        return Ok(());
+    }
+    Err(RjdError::PolicyViolation {
+        message: format!(
+            "Snapshot {} does not match {}: {} changes; run with --update to accept them",
+            golden.display(),
+            live.display(),
+            changes.len()
+        ),
+    })
+}
+
+/// Write the live document over the golden file, creating its directory
+fn write_golden(golden: &Path, value: &Value) -> Result<(), RjdError> {
+    let destination = Destination::File(golden.to_path_buf());
+    let write_error = |message: String| RjdError::Output {
+        destination: destination.to_string(),
+        message,
+    };
+    if let Some(parent) = golden.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| write_error(e.to_string()))?;
+    }
+    let text = serde_json::to_string_pretty(value).map_err(|e| write_error(e.to_string()))?;
+
+    let mut sink = destination.open(JSON)?;
+    writeln!(sink, "{}", text).map_err(|e| write_error(e.to_string()))?;
+    sink.finish()
+}
@@ -0,0 +1,37 @@
+use rjd::{load_json_file_with_config, profile, JsonPath, LoadConfig, RjdError};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+/// Print the size and shape of a document: value and key counts, values per
+/// depth, a type histogram, the widest object, the longest array and the
+/// `top` largest subtrees
+pub fn run(file: &PathBuf, top: usize) -> Result<(), RjdError> {
+    let document = load_json_file_with_config(file, &LoadConfig::from_env())?;
+    let profile = profile(&document, top);
+
+    let located = |found: &Option<(JsonPath, usize)>, size: &str| match found {
+        Some((path, len)) => json!({"path": path.to_json_pointer(), size: len}),
+        None => Value::Null,
+    };
+    let largest: Vec<Value> = profile
+        .largest
+        .iter()
+        .map(|(path, values)| json!({"path": path.to_json_pointer(), "values": values}))
+        .collect();
+    let report = json!({
+        "values": profile.values,
+        "keys": profile.keys,
+        "maxDepth": profile.max_depth(),
+        "depths": profile.depths,
+        "types": profile.types,
+        "widestObject": located(&profile.widest_object, "keys"),
+        "longestArray": located(&profile.longest_array, "length"),
+        "largestSubtrees": largest,
+    });
+
+    let output = serde_json::to_string_pretty(&report).map_err(|e| RjdError::Formatter {
+        message: e.to_string(),
+    })?;
+    println!("{}", output);
+    Ok(())
+}
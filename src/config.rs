@@ -0,0 +1,204 @@
+//! Project-level defaults from `.rjd.toml` or `.rjdrc.json`
+//!
+//! The nearest config file in the current directory or one of its parents
+//! supplies default options. Its keys are long option names and its values
+//! what the option would be given on the command line:
+//!
+//! ```toml
+//! format = "rfc6902"
+//! ignore = ["/metadata/generated", "$.users[*].token"]
+//! normalize-dates = true
+//! date-tolerance = "5s"
+//! ```
+//!
+//! Options taking `NAME=VALUE` pairs may also be given as a table, with a
+//! table value written as its `strategy` and the other field joined by `:`:
+//!
+//! ```toml
+//! [array-strategy]
+//! "/users" = { strategy = "keyed", key = "id" }
+//! "/tags" = "unordered"
+//! ```
+//!
+//! Options given on the command line take precedence: a config value is only
+//! used for options the command line leaves out.
+//!
+//! Since the file may come from any parent directory, only options that shape
+//! the diff and its output can be set (see [`OPTIONS`]). Options naming files
+//! to read or write, resource limits and exit-code gates are rejected.
+
+use rjd::RjdError;
+use serde_json::{Map, Value};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Config file names, in order of preference within a directory
+pub const CONFIG_FILES: [&str; 2] = [".rjd.toml", ".rjdrc.json"];
+
+/// The long options a config file may set
+pub const OPTIONS: &[&str] = &[
+    // Output
+    "format",
+    "sort",
+    "order",
+    "print-empty-message",
+    "empty-output",
+    "color",
+    "top",
+    "context",
+    "group-by-path-depth",
+    "max-value-length",
+    "format-version",
+    "with-metadata",
+    "with-locations",
+    "verbose-changes",
+    "describe",
+    "describe-template",
+    "string-diff",
+    // Diff
+    "map-path",
+    "ignore",
+    "input-format",
+    "xml-attribute-prefix",
+    "xml-text-key",
+    "strict-parse",
+    "max-diff-depth",
+    "ignore-key-case",
+    "sort-keys",
+    "normalize-dates",
+    "date-tolerance",
+    "coerce-numbers",
+    "coerce-number-strings",
+    "null-as-absent",
+    "empty-as-absent",
+    "structure-only",
+    "array-sets",
+    "array-diff",
+    "array-strategy",
+    "similarity-threshold",
+    "canonical",
+    "types-only",
+    "parse-embedded-json",
+    "binary-paths",
+    "detect-binary",
+    "decode-binary-json",
+    "max-changes",
+    "parallel",
+    "unwrap",
+    "extract-json",
+    "nth",
+];
+
+/// Find the nearest config file in `start` or its ancestors
+pub fn discover(start: &Path) -> Option<PathBuf> {
+    start.ancestors().find_map(|dir| {
+        CONFIG_FILES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+    })
+}
+
+/// Read the options of a config file
+pub fn load(path: &Path) -> Result<Map<String, Value>, RjdError> {
+    let error = |message: String| RjdError::Config {
+        path: path.to_path_buf(),
+        message,
+    };
+    let text = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+    let options = if path.extension().is_some_and(|ext| ext == "toml") {
+        let table: toml::Table = toml::from_str(&text).map_err(|e| error(e.to_string()))?;
+        serde_json::to_value(table).map_err(|e| error(e.to_string()))?
+    } else {
+        serde_json::from_str(&text).map_err(|e| error(e.to_string()))?
+    };
+    match options {
+        Value::Object(options) => Ok(options),
+        _ => Err(error("expected a table of options".to_string())),
+    }
+}
+
+/// Turn config options into command-line arguments for `command`
+///
+/// Options for which `given` returns true (by argument id) are skipped, so
+/// the command line takes precedence. Options missing from [`OPTIONS`] are
+/// an error.
+pub fn to_args(
+    options: &Map<String, Value>,
+    command: &clap::Command,
+    given: impl Fn(&str) -> bool,
+    path: &Path,
+) -> Result<Vec<OsString>, RjdError> {
+    let error = |message: String| RjdError::Config {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    let mut args = Vec::new();
+    for (key, value) in options {
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(key.as_str()))
+            .filter(|_| !matches!(key.as_str(), "help" | "version"))
+            .ok_or_else(|| error(format!("unknown option '{}'", key)))?;
+        if !OPTIONS.contains(&key.as_str()) {
+            return Err(error(format!(
+                "option '{}' cannot be set in a config file",
+                key
+            )));
+        }
+        if given(arg.get_id().as_str()) {
+            continue;
+        }
+
+        if let Value::Object(entries) = value {
+            for (name, entry) in entries {
+                let text = entry_text(entry)
+                    .ok_or_else(|| error(format!("unsupported value for '{}.{}'", key, name)))?;
+                args.push(OsString::from(format!("--{}={}={}", key, name, text)));
+            }
+            continue;
+        }
+        let values = match value {
+            Value::Array(items) => items.iter().collect(),
+            _ => vec![value],
+        };
+        for value in values {
+            let text = match value {
+                Value::Bool(flag) if !arg.get_action().takes_values() => {
+                    if *flag {
+                        args.push(OsString::from(format!("--{}", key)));
+                    }
+                    continue;
+                }
+                Value::String(text) => text.clone(),
+                Value::Number(number) => number.to_string(),
+                Value::Bool(flag) => flag.to_string(),
+                _ => return Err(error(format!("unsupported value for '{}'", key))),
+            };
+            args.push(OsString::from(format!("--{}={}", key, text)));
+        }
+    }
+    Ok(args)
+}
+
+/// The value of a table entry: a string or number as is, or a table
+/// `{strategy = "keyed", key = "id"}` as `keyed:id`
+fn entry_text(entry: &Value) -> Option<String> {
+    let scalar = |value: &Value| match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    };
+    let Value::Object(fields) = entry else {
+        return scalar(entry);
+    };
+    let strategy = fields.get("strategy")?.as_str()?;
+    let mut others = fields.iter().filter(|(field, _)| *field != "strategy");
+    match (others.next(), others.next()) {
+        (None, _) => Some(strategy.to_string()),
+        (Some((_, value)), None) => Some(format!("{}:{}", strategy, scalar(value)?)),
+        _ => None,
+    }
+}
@@ -0,0 +1,235 @@
+//! ISO-8601 timestamp parsing for `--normalize-dates`, and formatting for
+//! the `--with-metadata` envelope
+//!
+//! Only the common RFC 3339 shape is recognized: a calendar date and a time
+//! of day, separated by `T` (or a space), with optional fractional seconds
+//! and an optional UTC offset (`Z`, `+HH:MM`, `+HHMM` or `+HH`). A timestamp
+//! without an offset is taken to be UTC. Strings that merely start like a
+//! date (plain dates, free text) are not timestamps and compare as strings.
+
+use std::time::Duration;
+
+/// Parse an ISO-8601 timestamp into nanoseconds since the Unix epoch
+pub(crate) fn parse_timestamp(text: &str) -> Option<i128> {
+    let bytes = text.as_bytes();
+    if bytes.len() < 16 || bytes[4] != b'-' || bytes[7] != b'-' || bytes[13] != b':' {
+        return None;
+    }
+    if !matches!(bytes[10], b'T' | b't' | b' ') {
+        return None;
+    }
+
+    let year = digits(bytes, 0, 4)?;
+    let month = digits(bytes, 5, 2)?;
+    let day = digits(bytes, 8, 2)?;
+    let hour = digits(bytes, 11, 2)?;
+    let minute = digits(bytes, 14, 2)?;
+    if !(1..=12).contains(&month)
+        || day == 0
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+    {
+        return None;
+    }
+
+    let mut pos = 16;
+    let mut second = 0;
+    let mut nanos = 0;
+    if bytes.get(pos) == Some(&b':') {
+        second = digits(bytes, pos + 1, 2)?;
+        // Allow a leap second, which lands on the next second's instant
+        if second > 60 {
+            return None;
+        }
+        pos += 3;
+        if matches!(bytes.get(pos), Some(b'.' | b',')) {
+            let start = pos + 1;
+            let end = start
+                + bytes[start..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit())
+                    .count();
+            if end == start {
+                return None;
+            }
+            // Digits beyond nanosecond precision are dropped
+            for (i, b) in bytes[start..end].iter().take(9).enumerate() {
+                nanos += i128::from(b - b'0') * 10i128.pow(8 - i as u32);
+            }
+            pos = end;
+        }
+    }
+
+    let offset_minutes = match &bytes[pos..] {
+        [] | [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), rest @ ..] => {
+            let (hours, minutes) = match rest {
+                [_, _] => (digits(rest, 0, 2)?, 0),
+                [_, _, b':', _, _] => (digits(rest, 0, 2)?, digits(rest, 3, 2)?),
+                [_, _, _, _] => (digits(rest, 0, 2)?, digits(rest, 2, 2)?),
+                _ => return None,
+            };
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = hours * 60 + minutes;
+            if *sign == b'-' {
+                -offset
+            } else {
+                offset
+            }
+        }
+        _ => return None,
+    };
+
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60
+        - offset_minutes * 60
+        + second;
+    Some(i128::from(seconds) * 1_000_000_000 + nanos)
+}
+
+/// Whether two strings are timestamps no further apart than `tolerance`
+pub(crate) fn same_instant(old: &str, new: &str, tolerance: Duration) -> bool {
+    match (parse_timestamp(old), parse_timestamp(new)) {
+        (Some(old), Some(new)) => old.abs_diff(new) <= tolerance.as_nanos(),
+        _ => false,
+    }
+}
+
+/// Format seconds since the Unix epoch as an RFC 3339 UTC timestamp
+pub(crate) fn format_timestamp(seconds: i64) -> String {
+    let (days, time) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
+
+/// Read `len` ASCII digits starting at `start`
+fn digits(bytes: &[u8], start: usize, len: usize) -> Option<i64> {
+    let field = bytes.get(start..start + len)?;
+    field.iter().try_fold(0i64, |acc, b| {
+        b.is_ascii_digit().then(|| acc * 10 + i64::from(b - b'0'))
+    })
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Calendar date of a day count since 1970-01-01, the inverse of [`days_from_civil`]
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_timestamp(-1), "1969-12-31T23:59:59Z");
+        for text in [
+            "2024-12-31T23:59:59Z",
+            "2100-03-01T12:30:05Z",
+            "1900-01-01T00:00:00Z",
+        ] {
+            let seconds = parse_timestamp(text).unwrap() / 1_000_000_000;
+            assert_eq!(format_timestamp(seconds as i64), text);
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_timestamp("2024-01-01T00:00:00Z"),
+            Some(1_704_067_200 * 1_000_000_000)
+        );
+        assert_eq!(
+            parse_timestamp("2024-02-29 12:30:15.25+02:00"),
+            Some(1_709_202_615 * 1_000_000_000 + 250_000_000)
+        );
+        assert_eq!(
+            parse_timestamp("2024-01-01T01:00-0100"),
+            parse_timestamp("2024-01-01T02:00:00Z")
+        );
+
+        for text in [
+            "2024-01-01",
+            "2023-02-29T00:00:00Z",
+            "2024-01-01T24:00:00Z",
+            "2024-01-01T00:00:00.Z",
+            "2024-01-01T00:00:00 UTC",
+            "2024-01-01T00:00:00+5",
+            "not a date at all",
+        ] {
+            assert_eq!(parse_timestamp(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_same_instant() {
+        let zero = Duration::ZERO;
+        assert!(same_instant(
+            "2024-01-01T00:00:00Z",
+            "2024-01-01T00:00:00+00:00",
+            zero
+        ));
+        assert!(same_instant(
+            "2024-01-01T00:00:00Z",
+            "2023-12-31T19:00:00.000-05:00",
+            zero
+        ));
+        assert!(!same_instant(
+            "2024-01-01T00:00:00Z",
+            "2024-01-01T00:00:03Z",
+            zero
+        ));
+        assert!(same_instant(
+            "2024-01-01T00:00:00Z",
+            "2024-01-01T00:00:03Z",
+            Duration::from_secs(5)
+        ));
+        assert!(!same_instant("soon", "soon", zero));
+    }
+}
@@ -0,0 +1,177 @@
+//! Exact decimal values of JSON numbers
+//!
+//! With the `arbitrary-precision` feature, numbers keep the text they were
+//! parsed from (serde_json's `arbitrary_precision`), so `1.10` and `1.1` or
+//! two integers beyond the 64-bit range stay distinct. [`Decimal`] reads that
+//! text as an exact value to compare numbers numerically without rounding
+//! them to floats. It also spells numbers as ECMAScript does for the JSON
+//! Canonicalization Scheme.
+
+#[cfg(feature = "arbitrary-precision")]
+use serde_json::Number;
+
+/// The exact value of a JSON number: `digits × 10^exponent`
+///
+/// `digits` has no leading or trailing zeros, so equal values have equal
+/// decimals (zero has no digits and is never negative).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Decimal {
+    pub negative: bool,
+    pub digits: String,
+    pub exponent: i64,
+}
+
+impl Decimal {
+    /// Read a number written in JSON syntax, e.g. `-1.50e3`
+    pub fn parse(text: &str) -> Option<Self> {
+        let (negative, unsigned) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+            Some(i) => (&unsigned[..i], unsigned[i + 1..].parse::<i64>().ok()?),
+            None => (unsigned, 0),
+        };
+        let (integer, fraction) = match mantissa.split_once('.') {
+            Some((_, "")) => return None,
+            Some(parts) => parts,
+            None => (mantissa, ""),
+        };
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if integer.is_empty() || !all_digits(integer) || !all_digits(fraction) {
+            return None;
+        }
+
+        let digits = format!("{}{}", integer, fraction);
+        let significant = digits.trim_start_matches('0').trim_end_matches('0');
+        if significant.is_empty() {
+            return Some(Self {
+                negative: false,
+                digits: String::new(),
+                exponent: 0,
+            });
+        }
+        let trailing_zeros = digits.len() - digits.trim_end_matches('0').len();
+        let exponent = exponent
+            .checked_sub(fraction.len() as i64)?
+            .checked_add(trailing_zeros as i64)?;
+        Some(Self {
+            negative,
+            digits: significant.to_string(),
+            exponent,
+        })
+    }
+
+    /// The exact value of a number as it was written
+    #[cfg(feature = "arbitrary-precision")]
+    pub fn of(number: &Number) -> Option<Self> {
+        Self::parse(&number.to_string())
+    }
+
+    /// Write the value as ECMAScript writes numbers, with every digit kept
+    ///
+    /// Integers are written in full up to 21 digits, fractions down to
+    /// `0.000001` with a decimal point and anything else in exponent form,
+    /// e.g. `1.5e-7` or `1e+21`.
+    pub fn to_ecmascript(&self) -> String {
+        if self.digits.is_empty() {
+            return "0".to_string();
+        }
+        let digits = self.digits.as_str();
+        let k = digits.len() as i64;
+        // The decimal point goes after `n` digits
+        let n = k.saturating_add(self.exponent);
+
+        let body = if k <= n && n <= 21 {
+            format!("{}{}", digits, "0".repeat((n - k) as usize))
+        } else if 0 < n && n <= 21 {
+            format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+        } else if -6 < n && n <= 0 {
+            format!("0.{}{}", "0".repeat(-n as usize), digits)
+        } else {
+            let fraction = if k > 1 {
+                format!(".{}", &digits[1..])
+            } else {
+                String::new()
+            };
+            let sign = if n > 0 { "+" } else { "-" };
+            format!("{}{}e{}{}", &digits[..1], fraction, sign, (n - 1).abs())
+        };
+        if self.negative {
+            format!("-{}", body)
+        } else {
+            body
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(text: &str) -> Decimal {
+        Decimal::parse(text).unwrap()
+    }
+
+    #[test]
+    fn test_equal_values_have_equal_decimals() {
+        assert_eq!(decimal("1.10"), decimal("1.1"));
+        assert_eq!(decimal("100"), decimal("1e2"));
+        assert_eq!(decimal("1.5E+3"), decimal("1500.000"));
+        assert_eq!(decimal("-0.0"), decimal("0"));
+        assert_eq!(decimal("0.001"), decimal("1e-3"));
+        assert_ne!(
+            decimal("123456789012345678901234567890"),
+            decimal("123456789012345678901234567891")
+        );
+        assert_ne!(decimal("1"), decimal("-1"));
+        assert_eq!(
+            decimal("-12.50"),
+            Decimal {
+                negative: true,
+                digits: "125".to_string(),
+                exponent: -1
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_numbers() {
+        for text in [
+            "",
+            "-",
+            "1.",
+            "x",
+            "1e",
+            "1e1.5",
+            " 1",
+            "1e99999999999999999999",
+        ] {
+            assert!(Decimal::parse(text).is_none(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_to_ecmascript() {
+        let cases = [
+            ("0.0", "0"),
+            ("2.50", "2.5"),
+            ("1e2", "100"),
+            ("-1.5e-7", "-1.5e-7"),
+            ("0.000001", "0.000001"),
+            ("1e21", "1e+21"),
+            ("123.456e1", "1234.56"),
+            (
+                "123456789012345678901234567890",
+                "1.2345678901234567890123456789e+29",
+            ),
+            (
+                "1.2345678901234567890123e-30",
+                "1.2345678901234567890123e-30",
+            ),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(decimal(text).to_ecmascript(), expected, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,836 @@
+//! Binary document formats
+//!
+//! MessagePack, CBOR and BSON documents are converted to JSON values by
+//! [`decode_document`], so binary-serialized data from databases and services
+//! can be diffed like JSON. Values JSON has no type for are mapped as follows:
+//!
+//! - byte strings become base64 strings (see [`binary`](crate::binary))
+//! - MessagePack extension values become `[type, base64 data]` arrays
+//! - CBOR tags are dropped, keeping the tagged value; big integers become
+//!   numbers
+//! - BSON types beyond JSON use MongoDB Extended JSON, e.g. `{"$oid": "..."}`
+//!   for object ids and `{"$date": {"$numberLong": "..."}}` for dates
+//! - map keys that are not strings are written as JSON text, e.g. `"1"`
+//!
+//! XML documents are converted as described in [`decode_xml`](crate::decode_xml).
+//! With the `analytics` feature, Avro and Parquet record files are read too,
+//! as arrays of records (see [`InputFormat::Avro`]).
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{json, Map, Number, Value};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::RjdError;
+use crate::loader::{check_json_depth, parse_json_with_depth_limit, LoadConfig};
+
+/// Encoding of an input document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputFormat {
+    /// JSON text
+    Json,
+    /// MessagePack
+    Msgpack,
+    /// CBOR (RFC 8949)
+    Cbor,
+    /// BSON, as stored by MongoDB
+    Bson,
+    /// XML, converted with the default [`XmlConvention`](crate::XmlConvention)
+    Xml,
+    /// An Avro object container file, read as an array of its records
+    #[cfg(feature = "analytics")]
+    Avro,
+    /// A Parquet file, read as an array of its rows
+    #[cfg(feature = "analytics")]
+    Parquet,
+}
+
+impl InputFormat {
+    const NAMES: &'static [(&'static str, InputFormat)] = &[
+        ("json", InputFormat::Json),
+        ("msgpack", InputFormat::Msgpack),
+        ("cbor", InputFormat::Cbor),
+        ("bson", InputFormat::Bson),
+        ("xml", InputFormat::Xml),
+        #[cfg(feature = "analytics")]
+        ("avro", InputFormat::Avro),
+        #[cfg(feature = "analytics")]
+        ("parquet", InputFormat::Parquet),
+    ];
+
+    /// The format a file's extension names, if any
+    ///
+    /// `.json`, `.msgpack` (or `.mpk`), `.cbor`, `.bson` and `.xml` are recognized,
+    /// in any case, and with the `analytics` feature `.avro` and `.parquet`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rjd::InputFormat;
+    /// use std::path::Path;
+    ///
+    /// assert_eq!(InputFormat::from_path(Path::new("dump.BSON")), Some(InputFormat::Bson));
+    /// assert_eq!(InputFormat::from_path(Path::new("config.yaml")), None);
+    /// ```
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "mpk" => Some(InputFormat::Msgpack),
+            name => name.parse().ok(),
+        }
+    }
+}
+
+impl fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, _) = Self::NAMES
+            .iter()
+            .find(|(_, format)| format == self)
+            .expect("every format has a name");
+        f.write_str(name)
+    }
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::NAMES
+            .iter()
+            .find(|(name, _)| *name == text)
+            .map(|(_, format)| *format)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::NAMES.iter().map(|(name, _)| *name).collect();
+                let (last, rest) = names.split_last().expect("formats have names");
+                format!(
+                    "unknown input format '{}' (expected {} or {})",
+                    text,
+                    rest.join(", "),
+                    last
+                )
+            })
+    }
+}
+
+/// Decode a document in `format` to a JSON value
+///
+/// `source_name` names the input in error messages. The document must fill
+/// `bytes` exactly and be no deeper than the configured JSON depth limit.
+///
+/// # Errors
+/// Returns an error if the bytes are not a single valid document in `format`,
+/// or if the document is nested too deeply.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::{decode_document, InputFormat, LoadConfig};
+/// use serde_json::json;
+///
+/// // {"id": 7, "tags": ["a"]} in MessagePack
+/// let bytes = b"\x82\xa2id\x07\xa4tags\x91\xa1a";
+/// let value = decode_document(bytes, InputFormat::Msgpack, "inline", &LoadConfig::default()).unwrap();
+/// assert_eq!(value, json!({"id": 7, "tags": ["a"]}));
+/// ```
+pub fn decode_document(
+    bytes: &[u8],
+    format: InputFormat,
+    source_name: &str,
+    config: &LoadConfig,
+) -> Result<Value, RjdError> {
+    let decoded = match format {
+        InputFormat::Json => std::str::from_utf8(bytes)
+            .map_err(|e| e.to_string())
+            .and_then(|text| parse_json_with_depth_limit(text, config.max_json_depth))
+            .map_err(Into::into),
+        InputFormat::Msgpack => decode_msgpack(bytes).map_err(Into::into),
+        InputFormat::Cbor => decode_cbor(bytes).map_err(Into::into),
+        InputFormat::Bson => decode_bson(bytes, config.max_json_depth),
+        InputFormat::Xml => crate::xml::xml_to_json(
+            bytes,
+            &crate::XmlConvention::default(),
+            config.max_json_depth,
+        ),
+        #[cfg(feature = "analytics")]
+        InputFormat::Avro => crate::records::decode_avro(bytes, config),
+        #[cfg(feature = "analytics")]
+        InputFormat::Parquet => crate::records::decode_parquet(bytes).map_err(Into::into),
+    };
+    checked(decoded, format, source_name, config)
+}
+
+/// Why a document could not be decoded
+#[derive(Debug, PartialEq)]
+pub(crate) enum DecodeError {
+    /// The bytes are not a valid document; the message says why
+    Invalid(String),
+    /// Decoding stopped at this depth, past the configured limit
+    TooDeep(usize),
+}
+
+impl From<String> for DecodeError {
+    fn from(message: String) -> Self {
+        DecodeError::Invalid(message)
+    }
+}
+
+/// A decoded document, or the error reporting why it could not be decoded or
+/// is nested too deeply
+pub(crate) fn checked(
+    decoded: Result<Value, DecodeError>,
+    format: InputFormat,
+    source_name: &str,
+    config: &LoadConfig,
+) -> Result<Value, RjdError> {
+    let value = decoded.map_err(|error| match error {
+        DecodeError::Invalid(message) => RjdError::Decode {
+            format,
+            source_name: source_name.to_string(),
+            message,
+        },
+        DecodeError::TooDeep(depth) => RjdError::JsonDepthExceeded {
+            depth,
+            limit: config.max_json_depth,
+        },
+    })?;
+    check_json_depth(&value, config.max_json_depth).map_err(|depth| {
+        RjdError::JsonDepthExceeded {
+            depth,
+            limit: config.max_json_depth,
+        }
+    })?;
+    Ok(value)
+}
+
+fn decode_msgpack(bytes: &[u8]) -> Result<Value, String> {
+    let mut rest = bytes;
+    let value = Document
+        .deserialize(&mut rmp_serde::Deserializer::new(&mut rest))
+        .map_err(|e| e.to_string())?;
+    trailing(bytes.len() - rest.len(), rest)?;
+    Ok(value)
+}
+
+fn decode_cbor(bytes: &[u8]) -> Result<Value, String> {
+    let mut rest = bytes;
+    let value: ciborium::Value = ciborium::from_reader(&mut rest).map_err(|e| e.to_string())?;
+    trailing(bytes.len() - rest.len(), rest)?;
+    cbor_to_json(value)
+}
+
+/// Reject data after the end of the document
+fn trailing(offset: usize, rest: &[u8]) -> Result<(), String> {
+    if rest.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} unexpected byte(s) after the document at offset {}",
+            rest.len(),
+            offset
+        ))
+    }
+}
+
+/// A non-string map key as JSON text
+fn key_text(key: Value) -> String {
+    match key {
+        Value::String(key) => key,
+        other => other.to_string(),
+    }
+}
+
+/// A number of any size as a JSON number
+fn big_number(n: impl fmt::Display) -> Value {
+    Value::Number(n.to_string().parse().expect("integers are JSON numbers"))
+}
+
+fn float(n: f64) -> Result<Value, String> {
+    Number::from_f64(n)
+        .map(Value::Number)
+        .ok_or_else(|| format!("{} has no JSON representation", n))
+}
+
+/// Builds a JSON value from any self-describing serde format
+struct Document;
+
+impl<'de> DeserializeSeed<'de> for Document {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Document {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a document value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<Value, E> {
+        Ok(big_number(v))
+    }
+
+    fn visit_u128<E>(self, v: u128) -> Result<Value, E> {
+        Ok(big_number(v))
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Value, E> {
+        float(v).map_err(E::custom)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::String(STANDARD.encode(v)))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element_seed(Document)? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut fields = Map::new();
+        while let Some(key) = map.next_key_seed(Document)? {
+            fields.insert(key_text(key), map.next_value_seed(Document)?);
+        }
+        Ok(Value::Object(fields))
+    }
+}
+
+fn cbor_to_json(value: ciborium::Value) -> Result<Value, String> {
+    use ciborium::Value as Cbor;
+
+    Ok(match value {
+        Cbor::Null => Value::Null,
+        Cbor::Bool(b) => Value::Bool(b),
+        Cbor::Integer(n) => big_number(i128::from(n)),
+        Cbor::Float(n) => float(n)?,
+        Cbor::Text(text) => Value::String(text),
+        Cbor::Bytes(bytes) => Value::String(STANDARD.encode(bytes)),
+        Cbor::Tag(_, value) => cbor_to_json(*value)?,
+        Cbor::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(cbor_to_json)
+                .collect::<Result<_, _>>()?,
+        ),
+        Cbor::Map(entries) => {
+            let mut fields = Map::new();
+            for (key, value) in entries {
+                fields.insert(key_text(cbor_to_json(key)?), cbor_to_json(value)?);
+            }
+            Value::Object(fields)
+        }
+        other => return Err(format!("unsupported CBOR value {:?}", other)),
+    })
+}
+
+/// Decode a BSON document, failing as soon as it nests deeper than `max_depth`
+fn decode_bson(bytes: &[u8], max_depth: usize) -> Result<Value, DecodeError> {
+    let mut reader = BsonReader {
+        bytes,
+        pos: 0,
+        depth: 0,
+        max_depth,
+    };
+    let value = reader.document(false)?;
+    trailing(reader.pos, &bytes[reader.pos..])?;
+    Ok(value)
+}
+
+/// Reader for the BSON binary layout (<https://bsonspec.org/spec.html>)
+struct BsonReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Number of documents being read, the current one included
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<'a> BsonReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| format!("unexpected end of data at offset {}", self.pos))?;
+        let taken = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn int32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn length(&mut self) -> Result<usize, String> {
+        let offset = self.pos;
+        usize::try_from(self.int32()?).map_err(|_| format!("negative length at offset {}", offset))
+    }
+
+    fn cstring(&mut self) -> Result<String, String> {
+        let offset = self.pos;
+        let len = self.bytes[offset..]
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| format!("unterminated name at offset {}", offset))?;
+        let text = self.take(len)?;
+        self.pos += 1;
+        utf8(text, offset)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let offset = self.pos;
+        let len = self.length()?;
+        match self.take(len)? {
+            [text @ .., 0] => utf8(text, offset),
+            _ => Err(format!("invalid string at offset {}", offset)),
+        }
+    }
+
+    fn object_id(&mut self) -> Result<String, String> {
+        Ok(self
+            .take(12)?
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect())
+    }
+
+    /// An embedded document or array, with its length prefix
+    fn document(&mut self, array: bool) -> Result<Value, DecodeError> {
+        self.depth += 1;
+        if self.depth > self.max_depth {
+            return Err(DecodeError::TooDeep(self.depth));
+        }
+        let value = self.document_fields(array)?;
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    fn document_fields(&mut self, array: bool) -> Result<Value, DecodeError> {
+        let start = self.pos;
+        let end = start + self.length()?;
+        let mut fields = Map::new();
+        let mut items = Vec::new();
+        loop {
+            let kind = self.byte()?;
+            if kind == 0 {
+                break;
+            }
+            let name = self.cstring()?;
+            let value = self.value(kind)?;
+            if array {
+                items.push(value);
+            } else {
+                fields.insert(name, value);
+            }
+        }
+        if self.pos != end {
+            return Err(format!(
+                "document at offset {} is {} bytes long, not the {} its header gives",
+                start,
+                self.pos - start,
+                end - start
+            )
+            .into());
+        }
+        Ok(if array {
+            Value::Array(items)
+        } else {
+            Value::Object(fields)
+        })
+    }
+
+    /// The value of an element of type `kind`
+    ///
+    /// Nested documents are read here rather than in [`Self::element`], whose
+    /// stack frame is large, so that deep nesting recurses through small
+    /// frames only.
+    fn value(&mut self, kind: u8) -> Result<Value, DecodeError> {
+        match kind {
+            0x03 => self.document(false),
+            0x04 => self.document(true),
+            0x0F => {
+                self.int32()?;
+                let code = self.string()?;
+                let scope = self.document(false)?;
+                Ok(json!({"$code": code, "$scope": scope}))
+            }
+            kind => self.element(kind),
+        }
+    }
+
+    /// The value of an element of a type other than a document
+    fn element(&mut self, kind: u8) -> Result<Value, DecodeError> {
+        Ok(match kind {
+            0x01 => {
+                let n = f64::from_le_bytes(self.array()?);
+                float(n).unwrap_or_else(|_| {
+                    let text = match n {
+                        _ if n.is_nan() => "NaN",
+                        _ if n > 0.0 => "Infinity",
+                        _ => "-Infinity",
+                    };
+                    json!({"$numberDouble": text})
+                })
+            }
+            0x02 => Value::String(self.string()?),
+            0x05 => {
+                let len = self.length()?;
+                let subtype = self.byte()?;
+                let data = self.take(len)?;
+                json!({"$binary": {"base64": STANDARD.encode(data), "subType": format!("{:02x}", subtype)}})
+            }
+            0x06 => json!({"$undefined": true}),
+            0x07 => json!({"$oid": self.object_id()?}),
+            0x08 => match self.byte()? {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                other => return Err(format!("invalid boolean {}", other).into()),
+            },
+            0x09 => {
+                let millis = i64::from_le_bytes(self.array()?);
+                json!({"$date": {"$numberLong": millis.to_string()}})
+            }
+            0x0A => Value::Null,
+            0x0B => {
+                let pattern = self.cstring()?;
+                let options = self.cstring()?;
+                json!({"$regularExpression": {"pattern": pattern, "options": options}})
+            }
+            0x0C => {
+                let collection = self.string()?;
+                json!({"$dbPointer": {"$ref": collection, "$id": {"$oid": self.object_id()?}}})
+            }
+            0x0D => json!({"$code": self.string()?}),
+            0x0E => json!({"$symbol": self.string()?}),
+            0x10 => Value::from(self.int32()?),
+            0x11 => {
+                let increment = u32::from_le_bytes(self.array()?);
+                let time = u32::from_le_bytes(self.array()?);
+                json!({"$timestamp": {"t": time, "i": increment}})
+            }
+            0x12 => Value::from(i64::from_le_bytes(self.array()?)),
+            0x13 => json!({"$numberDecimal": decimal128(u128::from_le_bytes(self.array()?))}),
+            0xFF => json!({"$minKey": 1}),
+            0x7F => json!({"$maxKey": 1}),
+            other => {
+                return Err(format!(
+                    "unknown element type 0x{:02x} at offset {}",
+                    other,
+                    self.pos - 1
+                )
+                .into())
+            }
+        })
+    }
+}
+
+fn utf8(bytes: &[u8], offset: usize) -> Result<String, String> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| format!("invalid UTF-8 at offset {}", offset))
+}
+
+/// An IEEE 754 decimal128 value (BID encoding) as a decimal string
+///
+/// Follows the to-scientific-string conversion of the BSON decimal128 spec.
+fn decimal128(bits: u128) -> String {
+    const EXPONENT_BIAS: i32 = 6176;
+    let sign = if bits >> 127 == 1 { "-" } else { "" };
+    let combination = (bits >> 122) & 0x1F;
+    if combination == 0x1F {
+        return "NaN".to_string();
+    }
+    if combination == 0x1E {
+        return format!("{}Infinity", sign);
+    }
+    let (biased, coefficient) = if (bits >> 125) & 0b11 == 0b11 {
+        // A coefficient too large to be canonical counts as zero
+        ((bits >> 111) & 0x3FFF, 0)
+    } else {
+        ((bits >> 113) & 0x3FFF, bits & ((1 << 113) - 1))
+    };
+    let coefficient = if coefficient > 9_999_999_999_999_999_999_999_999_999_999 {
+        0
+    } else {
+        coefficient
+    };
+    let exponent = biased as i32 - EXPONENT_BIAS;
+    let digits = coefficient.to_string();
+    let adjusted = exponent + digits.len() as i32 - 1;
+
+    let text = if exponent <= 0 && adjusted >= -6 {
+        let point = digits.len() as i32 + exponent;
+        if exponent == 0 {
+            digits
+        } else if point > 0 {
+            let (whole, fraction) = digits.split_at(point as usize);
+            format!("{}.{}", whole, fraction)
+        } else {
+            format!("0.{}{}", "0".repeat(-point as usize), digits)
+        }
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let mantissa = if rest.is_empty() {
+            first.to_string()
+        } else {
+            format!("{}.{}", first, rest)
+        };
+        format!(
+            "{}E{}{}",
+            mantissa,
+            if adjusted >= 0 { "+" } else { "" },
+            adjusted
+        )
+    };
+    format!("{}{}", sign, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A BSON document from its elements' bytes
+    fn bson(elements: &[u8]) -> Vec<u8> {
+        let len = (elements.len() + 5) as i32;
+        let mut document = len.to_le_bytes().to_vec();
+        document.extend_from_slice(elements);
+        document.push(0);
+        document
+    }
+
+    fn decode(bytes: &[u8], format: InputFormat) -> Result<Value, RjdError> {
+        decode_document(bytes, format, "test", &LoadConfig::default())
+    }
+
+    #[test]
+    fn test_input_format_names() {
+        for name in ["json", "msgpack", "cbor", "bson"] {
+            assert_eq!(name.parse::<InputFormat>().unwrap().to_string(), name);
+        }
+        assert!("yaml".parse::<InputFormat>().is_err());
+        assert_eq!(
+            InputFormat::from_path(Path::new("a/b.mpk")),
+            Some(InputFormat::Msgpack)
+        );
+        assert_eq!(
+            InputFormat::from_path(Path::new("b.Json")),
+            Some(InputFormat::Json)
+        );
+        assert_eq!(InputFormat::from_path(Path::new("cbor")), None);
+    }
+
+    #[test]
+    fn test_decode_msgpack() {
+        // {1: bin "hi", "f": 1.5, "n": nil, "e": ext 5 "x"}
+        let bytes =
+            b"\x84\x01\xc4\x02hi\xa1f\xcb\x3f\xf8\x00\x00\x00\x00\x00\x00\xa1n\xc0\xa1e\xd4\x05x";
+        assert_eq!(
+            decode(bytes, InputFormat::Msgpack).unwrap(),
+            json!({"1": "aGk=", "f": 1.5, "n": null, "e": [5, "eA=="]})
+        );
+
+        let error = decode(b"\x91\x01\x02", InputFormat::Msgpack).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to decode msgpack from test: 1 unexpected byte(s) after the document at offset 2"
+        );
+        assert!(decode(b"\x92\x01", InputFormat::Msgpack).is_err());
+    }
+
+    #[test]
+    fn test_decode_cbor() {
+        use ciborium::Value as Cbor;
+
+        let document = Cbor::Map(vec![
+            (Cbor::Text("big".into()), Cbor::Integer(u64::MAX.into())),
+            (Cbor::Integer((-2).into()), Cbor::Bytes(b"hi".to_vec())),
+            (
+                Cbor::Text("when".into()),
+                Cbor::Tag(0, Box::new(Cbor::Text("2024-01-01T00:00:00Z".into()))),
+            ),
+            (
+                Cbor::Text("list".into()),
+                Cbor::Array(vec![Cbor::Bool(true), Cbor::Null, Cbor::Float(0.5)]),
+            ),
+        ]);
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&document, &mut bytes).unwrap();
+        assert_eq!(
+            decode(&bytes, InputFormat::Cbor).unwrap(),
+            json!({
+                "big": 18446744073709551615u64,
+                "-2": "aGk=",
+                "when": "2024-01-01T00:00:00Z",
+                "list": [true, null, 0.5]
+            })
+        );
+
+        bytes.push(0);
+        assert!(decode(&bytes, InputFormat::Cbor).is_err());
+        // NaN has no JSON counterpart
+        assert!(decode(b"\xf9\x7e\x00", InputFormat::Cbor).is_err());
+    }
+
+    #[test]
+    fn test_decode_bson() {
+        let mut elements = Vec::new();
+        elements.extend(b"\x02name\x00\x04\x00\x00\x00ann\x00");
+        elements.extend(b"\x10n\x00\x07\x00\x00\x00");
+        elements.extend(b"\x12big\x00\x00\x00\x00\x00\x01\x00\x00\x00");
+        elements.extend(b"\x01d\x00\x00\x00\x00\x00\x00\x00\xf8\x3f");
+        elements.extend(b"\x08ok\x00\x01\x0anone\x00");
+        elements.extend(b"\x07_id\x00\x65\x0a\x1b\x2c\x3d\x4e\x5f\x60\x71\x82\x93\xa4");
+        elements.extend(b"\x09at\x00\xe8\x03\x00\x00\x00\x00\x00\x00");
+        elements.extend(b"\x05blob\x00\x02\x00\x00\x00\x00hi");
+        elements.extend(b"\x04list\x00");
+        elements.extend(bson(
+            b"\x100\x00\x01\x00\x00\x00\x021\x00\x02\x00\x00\x00x\x00",
+        ));
+        elements.extend(b"\x03sub\x00");
+        elements.extend(bson(b"\x0bre\x00a.*\x00i\x00"));
+        // 1.50 as decimal128: coefficient 150, exponent -2
+        elements.extend(b"\x13price\x00");
+        elements.extend((150u128 | ((6176u128 - 2) << 113)).to_le_bytes());
+
+        assert_eq!(
+            decode(&bson(&elements), InputFormat::Bson).unwrap(),
+            json!({
+                "name": "ann",
+                "n": 7,
+                "big": 4294967296u64,
+                "d": 1.5,
+                "ok": true,
+                "none": null,
+                "_id": {"$oid": "650a1b2c3d4e5f60718293a4"},
+                "at": {"$date": {"$numberLong": "1000"}},
+                "blob": {"$binary": {"base64": "aGk=", "subType": "00"}},
+                "list": [1, "x"],
+                "sub": {"re": {"$regularExpression": {"pattern": "a.*", "options": "i"}}},
+                "price": {"$numberDecimal": "1.50"}
+            })
+        );
+
+        // A header length that disagrees with the elements
+        let mut bytes = bson(b"\x0aa\x00");
+        bytes[0] += 1;
+        assert!(decode(&bytes, InputFormat::Bson).is_err());
+        assert!(decode(&bson(b"\x20a\x00"), InputFormat::Bson).is_err());
+        assert!(decode(&bson(b"\x02a\x00\xff\xff\xff\xff"), InputFormat::Bson).is_err());
+    }
+
+    #[test]
+    fn test_decimal128() {
+        let decimal = |coefficient: u128, exponent: i32| {
+            decimal128(coefficient | (((6176 + exponent) as u128) << 113))
+        };
+        assert_eq!(decimal(0, 0), "0");
+        assert_eq!(decimal(12345, -2), "123.45");
+        assert_eq!(decimal(5, -3), "0.005");
+        assert_eq!(decimal(1, 3), "1E+3");
+        assert_eq!(decimal(12, -10), "1.2E-9");
+        assert_eq!(decimal(0, -6176), "0E-6176");
+        assert_eq!(decimal128((1 << 127) | (7 | (6176 << 113))), "-7");
+        assert_eq!(decimal128(0x1F << 122), "NaN");
+        assert_eq!(decimal128((1 << 127) | (0x1E << 122)), "-Infinity");
+    }
+
+    #[test]
+    fn test_decode_json_and_depth_limit() {
+        assert_eq!(
+            decode(br#"{"a": [1]}"#, InputFormat::Json).unwrap(),
+            json!({"a": [1]})
+        );
+        let config = LoadConfig::with_limits(1024, 2);
+        let error = decode_document(b"\x91\x91\x91\x01", InputFormat::Msgpack, "t", &config);
+        assert!(matches!(
+            error,
+            Err(RjdError::JsonDepthExceeded { limit: 2, .. })
+        ));
+
+        let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        assert!(decode(deep.as_bytes(), InputFormat::Json).is_err());
+    }
+
+    #[test]
+    fn test_decode_deeply_nested_bson() {
+        // {"a": {"a": ... {}}}, nested far deeper than the stack allows recursing
+        let depth = 200_000;
+        let mut bytes = Vec::new();
+        for level in 0..depth {
+            let len = 5 + 8 * (depth - level) as i32;
+            bytes.extend(len.to_le_bytes());
+            bytes.extend(b"\x03a\x00");
+        }
+        bytes.extend(bson(&[]));
+        bytes.extend(std::iter::repeat_n(0, depth));
+
+        let config = LoadConfig::with_limits(u64::MAX, 100);
+        assert!(matches!(
+            decode_document(&bytes, InputFormat::Bson, "t", &config),
+            Err(RjdError::JsonDepthExceeded {
+                depth: 101,
+                limit: 100
+            })
+        ));
+        let config = LoadConfig::with_limits(u64::MAX, usize::MAX);
+        let shallow = &bytes[7 * (depth - 3)..bytes.len() - (depth - 3)];
+        assert_eq!(
+            decode_document(shallow, InputFormat::Bson, "t", &config).unwrap(),
+            json!({"a": {"a": {"a": {}}}})
+        );
+    }
+}
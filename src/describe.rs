@@ -0,0 +1,304 @@
+//! Human-readable descriptions of changes
+//!
+//! [`describe`] phrases a change as a sentence, e.g. `` `user.age` changed
+//! from 30 to 31``; a [`DescriptionTemplate`] lays descriptions out any other
+//! way. [`Changes::annotate_descriptions`] records them as `description`
+//! annotations, which the changes format reports and the SARIF, JUnit and
+//! GCC formats use as their messages.
+
+use crate::types::{Change, ChangeKind, Changes};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+/// Describe a change in a sentence
+///
+/// Strings are quoted with single quotes; other values are written as JSON.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::{describe, diff};
+/// use serde_json::json;
+///
+/// let changes = diff(
+///     &json!({"user": {"age": 30}, "tags": ["a", "b", "beta"]}),
+///     &json!({"user": {"age": 31}, "tags": ["a", "b"]}),
+/// );
+/// assert_eq!(describe(&changes.modified[0]), "`user.age` changed from 30 to 31");
+/// assert_eq!(describe(&changes.removed[0]), "`tags[2]` 'beta' removed");
+/// ```
+pub fn describe(change: &Change) -> String {
+    match change {
+        Change::Added { path, value } => format!("`{}` {} added", path, show(value)),
+        Change::Removed { path, value } => format!("`{}` {} removed", path, show(value)),
+        Change::Modified {
+            path,
+            old_value,
+            new_value,
+        } => format!(
+            "`{}` changed from {} to {}",
+            path,
+            show(old_value),
+            show(new_value)
+        ),
+    }
+}
+
+/// A value as descriptions show it
+fn show(value: &Value) -> String {
+    match value {
+        Value::String(text) => format!("'{}'", text),
+        _ => value.to_string(),
+    }
+}
+
+/// A placeholder of a [`DescriptionTemplate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Path,
+    Pointer,
+    Kind,
+    Value,
+    Old,
+    New,
+}
+
+impl Field {
+    const NAMES: &'static [(&'static str, Field)] = &[
+        ("path", Field::Path),
+        ("pointer", Field::Pointer),
+        ("kind", Field::Kind),
+        ("value", Field::Value),
+        ("old", Field::Old),
+        ("new", Field::New),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field(Field),
+}
+
+/// A layout for change descriptions
+///
+/// Text is copied as it is, with `{{` and `}}` for literal braces, and these
+/// placeholders are filled in:
+///
+/// - `{path}`: the path in dot notation (`user.tags[0]`)
+/// - `{pointer}`: the path as a JSON Pointer (`/user/tags/0`)
+/// - `{kind}`: `added`, `removed` or `modified`
+/// - `{value}`: the added or removed value, or the new value of a modification
+/// - `{old}`, `{new}`: the old and new values, empty for the side a value is
+///   missing from
+///
+/// Values are shown as in [`describe`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::{diff, DescriptionTemplate};
+/// use serde_json::json;
+///
+/// let template: DescriptionTemplate = "{kind} {pointer}: {old} -> {new}".parse().unwrap();
+/// let changes = diff(&json!({"port": 80}), &json!({"port": 8080}));
+/// assert_eq!(template.describe(&changes.modified[0]), "modified /port: 80 -> 8080");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptionTemplate {
+    text: String,
+    parts: Vec<Part>,
+}
+
+impl DescriptionTemplate {
+    /// Describe `change` with this template
+    pub fn describe(&self, change: &Change) -> String {
+        let (old, new) = match change {
+            Change::Added { value, .. } => (None, Some(value)),
+            Change::Removed { value, .. } => (Some(value), None),
+            Change::Modified {
+                old_value,
+                new_value,
+                ..
+            } => (Some(old_value), Some(new_value)),
+        };
+        let mut description = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => description.push_str(text),
+                Part::Field(Field::Path) => description.push_str(&change.path().to_string()),
+                Part::Field(Field::Pointer) => {
+                    description.push_str(&change.path().to_json_pointer())
+                }
+                Part::Field(Field::Kind) => description.push_str(match change.kind() {
+                    ChangeKind::Added => "added",
+                    ChangeKind::Removed => "removed",
+                    ChangeKind::Modified => "modified",
+                }),
+                Part::Field(Field::Value) => {
+                    description.push_str(&new.or(old).map(show).unwrap_or_default())
+                }
+                Part::Field(Field::Old) => description.push_str(&old.map(show).unwrap_or_default()),
+                Part::Field(Field::New) => description.push_str(&new.map(show).unwrap_or_default()),
+            }
+        }
+        description
+    }
+}
+
+impl fmt::Display for DescriptionTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl FromStr for DescriptionTemplate {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("unclosed '{{' in '{}'", text)),
+                        }
+                    }
+                    let field = Field::NAMES
+                        .iter()
+                        .find(|(known, _)| *known == name)
+                        .map(|(_, field)| *field)
+                        .ok_or_else(|| {
+                            let known: Vec<String> = Field::NAMES
+                                .iter()
+                                .map(|(known, _)| format!("{{{}}}", known))
+                                .collect();
+                            format!(
+                                "unknown placeholder '{{{}}}' in '{}' (expected one of {})",
+                                name,
+                                text,
+                                known.join(", ")
+                            )
+                        })?;
+                    if !literal.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(field));
+                }
+                '}' => return Err(format!("unmatched '}}' in '{}' (write '}}}}')", text)),
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Text(literal));
+        }
+        Ok(Self {
+            text: text.to_string(),
+            parts,
+        })
+    }
+}
+
+impl Changes {
+    /// Annotate every change with a `description`
+    ///
+    /// Descriptions come from `template`, or from [`describe`] without one.
+    pub fn annotate_descriptions(&mut self, template: Option<&DescriptionTemplate>) {
+        for change in self.added.iter().chain(&self.removed).chain(&self.modified) {
+            let description = match template {
+                Some(template) => template.describe(change),
+                None => describe(change),
+            };
+            self.annotations
+                .entry(change.path().clone())
+                .or_default()
+                .insert("description".to_string(), Value::String(description));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    #[test]
+    fn test_describe() {
+        let changes = diff(
+            &json!({"a": {"b": null}, "s": "x", "l": [1]}),
+            &json!({"a": {}, "s": "y", "l": [1, {"k": true}]}),
+        );
+        let descriptions: Vec<String> = changes.iter().map(describe).collect();
+        assert_eq!(
+            descriptions,
+            [
+                "`l[1].k` true added",
+                "`a.b` null removed",
+                "`s` changed from 'x' to 'y'"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_template() {
+        let changes = diff(&json!({"a": "x", "b": 1}), &json!({"a": "y", "c": [2]}));
+        let template: DescriptionTemplate = "{{{kind}}} {path} ({pointer}): {old}|{new}|{value}"
+            .parse()
+            .unwrap();
+        let descriptions: Vec<String> = changes.iter().map(|c| template.describe(c)).collect();
+        assert_eq!(
+            descriptions,
+            [
+                "{added} c[0] (/c/0): |2|2",
+                "{removed} b (/b): 1||1",
+                "{modified} a (/a): 'x'|'y'|'y'"
+            ]
+        );
+        assert_eq!(
+            template.to_string(),
+            "{{{kind}}} {path} ({pointer}): {old}|{new}|{value}"
+        );
+
+        for invalid in ["{size}", "{path", "a } b", "{}"] {
+            assert!(
+                invalid.parse::<DescriptionTemplate>().is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_annotate_descriptions() {
+        let mut changes = diff(&json!({"port": 80}), &json!({"port": 8080}));
+        changes.annotate_descriptions(None);
+        let path = changes.modified[0].path().clone();
+        assert_eq!(
+            changes.annotations[&path]["description"],
+            "`port` changed from 80 to 8080"
+        );
+
+        let template: DescriptionTemplate = "{path} is now {new}".parse().unwrap();
+        changes.annotate_descriptions(Some(&template));
+        assert_eq!(
+            changes.annotations[&path]["description"],
+            "port is now 8080"
+        );
+    }
+}
@@ -0,0 +1,342 @@
+//! Zero-copy diff over borrowed values
+//!
+//! [`diff_borrowed`] produces the same changes as [`crate::diff`], but each
+//! change refers to the values inside the inputs instead of cloning them. This
+//! avoids deep copies of large subtrees when callers only inspect or count
+//! changes. [`ChangesRef::to_owned_changes`] converts to the owned [`Changes`].
+//!
+//! The borrowed diff only compares values the default way: options that
+//! change the comparison are not supported, and
+//! [`diff_borrowed_with_options`] declines them.
+
+use super::options::DiffOptions;
+use crate::json_path::JsonPath;
+use crate::path::{join_array_path, join_path};
+use crate::types::{Change, Changes};
+use serde_json::{Map, Value};
+
+/// A single change that borrows its values from the diffed documents
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeRef<'a> {
+    /// A value present only in the new document
+    Added { path: JsonPath, value: &'a Value },
+    /// A value present only in the old document
+    Removed { path: JsonPath, value: &'a Value },
+    /// A value that differs between the documents
+    Modified {
+        path: JsonPath,
+        old_value: &'a Value,
+        new_value: &'a Value,
+    },
+}
+
+impl ChangeRef<'_> {
+    /// Get the path of this change
+    pub fn path(&self) -> &JsonPath {
+        match self {
+            ChangeRef::Added { path, .. } => path,
+            ChangeRef::Removed { path, .. } => path,
+            ChangeRef::Modified { path, .. } => path,
+        }
+    }
+
+    /// Clone the referenced values into an owned [`Change`]
+    pub fn to_owned_change(&self) -> Change {
+        match self {
+            ChangeRef::Added { path, value } => Change::Added {
+                path: path.clone(),
+                value: (*value).clone(),
+            },
+            ChangeRef::Removed { path, value } => Change::Removed {
+                path: path.clone(),
+                value: (*value).clone(),
+            },
+            ChangeRef::Modified {
+                path,
+                old_value,
+                new_value,
+            } => Change::Modified {
+                path: path.clone(),
+                old_value: (*old_value).clone(),
+                new_value: (*new_value).clone(),
+            },
+        }
+    }
+}
+
+/// Container for borrowed changes, mirroring [`Changes`]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChangesRef<'a> {
+    pub added: Vec<ChangeRef<'a>>,
+    pub removed: Vec<ChangeRef<'a>>,
+    pub modified: Vec<ChangeRef<'a>>,
+    /// The old document, used by formatters that render the "before" state
+    pub before: Option<&'a Value>,
+    /// The new document, used by formatters that render the "after" state
+    pub after: Option<&'a Value>,
+}
+
+impl<'a> ChangesRef<'a> {
+    /// Create a new empty container
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a change to the appropriate category
+    pub fn push(&mut self, change: ChangeRef<'a>) {
+        match change {
+            ChangeRef::Added { .. } => self.added.push(change),
+            ChangeRef::Removed { .. } => self.removed.push(change),
+            ChangeRef::Modified { .. } => self.modified.push(change),
+        }
+    }
+
+    /// Check if there are any changes
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    /// Total number of changes
+    pub fn len(&self) -> usize {
+        self.added.len() + self.removed.len() + self.modified.len()
+    }
+
+    /// Clone all referenced values into an owned [`Changes`]
+    ///
+    /// As with [`crate::diff`], the first document is left out (see
+    /// [`Changes::before`]).
+    pub fn to_owned_changes(&self) -> Changes {
+        let mut changes = Changes::new();
+        changes.added = self.added.iter().map(ChangeRef::to_owned_change).collect();
+        changes.removed = self
+            .removed
+            .iter()
+            .map(ChangeRef::to_owned_change)
+            .collect();
+        changes.modified = self
+            .modified
+            .iter()
+            .map(ChangeRef::to_owned_change)
+            .collect();
+        changes.after = self.after.cloned();
+        changes
+    }
+}
+
+impl From<ChangesRef<'_>> for Changes {
+    fn from(changes: ChangesRef<'_>) -> Self {
+        changes.to_owned_changes()
+    }
+}
+
+/// Compare two JSON values without cloning them
+///
+/// The result lists the same changes, in the same order, as [`crate::diff`].
+/// Warnings are not collected.
+///
+/// # Examples
+///
+/// ```
+/// use rjd::{diff, diff_borrowed, Changes};
+/// use serde_json::json;
+///
+/// let old = json!({"blob": [1, 2, 3], "name": "a"});
+/// let new = json!({"blob": [1, 2, 4], "name": "a"});
+///
+/// let changes = diff_borrowed(&old, &new);
+/// assert_eq!(changes.len(), 1);
+/// assert_eq!(Changes::from(changes), diff(&old, &new));
+/// ```
+pub fn diff_borrowed<'a>(old: &'a Value, new: &'a Value) -> ChangesRef<'a> {
+    let mut changes = ChangesRef::new();
+    changes.before = Some(old);
+    changes.after = Some(new);
+    walk(Some(old), Some(new), &JsonPath::new(), &mut changes);
+    changes
+}
+
+/// Compare two JSON values without cloning them, if `options` allow it
+///
+/// Returns `None` for options that change how values are compared, which
+/// only [`diff_with_options`](crate::diff_with_options) supports. Otherwise
+/// the result is that of [`diff_borrowed`]; `parallel` is allowed, as it does
+/// not change the result.
+///
+/// # Examples
+///
+/// ```
+/// use rjd::{diff_borrowed_with_options, DiffOptions};
+/// use serde_json::json;
+///
+/// let (old, new) = (json!({"a": 1}), json!({"A": 2}));
+/// let changes = diff_borrowed_with_options(&old, &new, &DiffOptions::default());
+/// assert_eq!(changes.map(|changes| changes.len()), Some(2));
+///
+/// let options = DiffOptions {
+///     ignore_key_case: true,
+///     ..DiffOptions::default()
+/// };
+/// assert!(diff_borrowed_with_options(&old, &new, &options).is_none());
+/// ```
+pub fn diff_borrowed_with_options<'a>(
+    old: &'a Value,
+    new: &'a Value,
+    options: &DiffOptions,
+) -> Option<ChangesRef<'a>> {
+    let supported = DiffOptions {
+        parallel: options.parallel,
+        ..DiffOptions::default()
+    };
+    (*options == supported).then(|| diff_borrowed(old, new))
+}
+
+fn walk<'a>(
+    old: Option<&'a Value>,
+    new: Option<&'a Value>,
+    path: &JsonPath,
+    changes: &mut ChangesRef<'a>,
+) {
+    match (old, new) {
+        (Some(old), Some(new)) if old == new => {}
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            walk_object(Some(old), Some(new), path, changes)
+        }
+        (Some(Value::Object(old)), None) if !old.is_empty() => {
+            walk_object(Some(old), None, path, changes)
+        }
+        (None, Some(Value::Object(new))) if !new.is_empty() => {
+            walk_object(None, Some(new), path, changes)
+        }
+        (Some(Value::Array(old)), Some(Value::Array(new))) => {
+            walk_array(Some(old), Some(new), path, changes)
+        }
+        (Some(Value::Array(old)), None) if !old.is_empty() => {
+            walk_array(Some(old), None, path, changes)
+        }
+        (None, Some(Value::Array(new))) if !new.is_empty() => {
+            walk_array(None, Some(new), path, changes)
+        }
+        (None, Some(value)) => changes.push(ChangeRef::Added {
+            path: path.clone(),
+            value,
+        }),
+        (Some(value), None) => changes.push(ChangeRef::Removed {
+            path: path.clone(),
+            value,
+        }),
+        (Some(old_value), Some(new_value)) => changes.push(ChangeRef::Modified {
+            path: path.clone(),
+            old_value,
+            new_value,
+        }),
+        (None, None) => {}
+    }
+}
+
+fn walk_object<'a>(
+    old: Option<&'a Map<String, Value>>,
+    new: Option<&'a Map<String, Value>>,
+    path: &JsonPath,
+    changes: &mut ChangesRef<'a>,
+) {
+    // Keys of the old object first, then keys only in the new one, as in `diff`
+    let old_keys = old.into_iter().flat_map(|m| m.keys());
+    let added_keys = new
+        .into_iter()
+        .flat_map(|m| m.keys())
+        .filter(|key| old.map(|m| !m.contains_key(*key)).unwrap_or(true));
+
+    for key in old_keys.chain(added_keys) {
+        walk(
+            old.and_then(|m| m.get(key)),
+            new.and_then(|m| m.get(key)),
+            &join_path(path, key),
+            changes,
+        );
+    }
+}
+
+fn walk_array<'a>(
+    old: Option<&'a Vec<Value>>,
+    new: Option<&'a Vec<Value>>,
+    path: &JsonPath,
+    changes: &mut ChangesRef<'a>,
+) {
+    let len = old.map_or(0, Vec::len).max(new.map_or(0, Vec::len));
+    for i in 0..len {
+        walk(
+            old.and_then(|v| v.get(i)),
+            new.and_then(|v| v.get(i)),
+            &join_array_path(path, i),
+            changes,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_borrowed_matches_owned_diff() {
+        let cases = [
+            (
+                json!({"a": 1, "b": [1, 2]}),
+                json!({"a": 2, "b": [1], "c": {"d": null}}),
+            ),
+            (json!([{"x": 1}, 2]), json!([{"x": 1, "y": true}])),
+            (json!({"obj": {"k": "v"}}), json!({"obj": [1]})),
+            (json!("same"), json!("same")),
+            (json!(1), json!("1")),
+            (
+                json!({"gone": {}, "list": [[]]}),
+                json!({"new": [], "list": []}),
+            ),
+        ];
+
+        for (old, new) in &cases {
+            assert_eq!(diff_borrowed(old, new).to_owned_changes(), diff(old, new));
+        }
+
+        // Added and removed empty containers are whole-value changes
+        let changes = diff_borrowed(&cases[5].0, &cases[5].1);
+        assert_eq!((changes.added.len(), changes.removed.len()), (1, 2));
+    }
+
+    #[test]
+    fn test_diff_borrowed_declines_comparison_options() {
+        let (old, new) = (json!({"a": [1, 2]}), json!({"a": [2, 1]}));
+        let parallel = DiffOptions {
+            parallel: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            diff_borrowed_with_options(&old, &new, &parallel),
+            Some(diff_borrowed(&old, &new))
+        );
+
+        let unordered = DiffOptions {
+            array_diff: crate::ArrayDiff::Unordered,
+            ..DiffOptions::default()
+        };
+        assert!(diff_borrowed_with_options(&old, &new, &unordered).is_none());
+    }
+
+    #[test]
+    fn test_diff_borrowed_references_input() {
+        let old = json!({"big": {"nested": [1, 2, 3]}});
+        let new = json!({});
+
+        let changes = diff_borrowed(&old, &new);
+        // Removed containers are reported leaf by leaf, as in `diff`
+        assert_eq!(changes.removed.len(), 3);
+        match &changes.removed[0] {
+            ChangeRef::Removed { value, .. } => {
+                assert!(std::ptr::eq(*value, &old["big"]["nested"][0]));
+            }
+            other => panic!("unexpected change: {:?}", other),
+        }
+    }
+}
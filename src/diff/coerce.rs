@@ -0,0 +1,97 @@
+//! Loose equality rules for `--coerce-numbers` and `--coerce-number-strings`
+
+#[cfg(feature = "arbitrary-precision")]
+use crate::decimal::Decimal;
+use serde_json::{Number, Value};
+
+/// Whether two numbers have the same numeric value (`1 == 1.0`)
+///
+/// Integers are compared exactly; anything else is compared as `f64`.
+#[cfg(not(feature = "arbitrary-precision"))]
+pub(crate) fn numbers_equal(a: &Number, b: &Number) -> bool {
+    if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+        return a == b;
+    }
+    if let (Some(a), Some(b)) = (a.as_u64(), b.as_u64()) {
+        return a == b;
+    }
+    match (a.as_f64(), b.as_f64()) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Whether two numbers have the same numeric value (`1 == 1.0`, `1.10 == 1.1`)
+///
+/// Numbers are compared as the exact decimals they were written as, so
+/// integers beyond 64 bits and long fractions are never rounded.
+#[cfg(feature = "arbitrary-precision")]
+pub(crate) fn numbers_equal(a: &Number, b: &Number) -> bool {
+    match (Decimal::of(a), Decimal::of(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a.as_f64().is_some_and(|a| b.as_f64() == Some(a)),
+    }
+}
+
+/// Whether a number and a string holding a JSON number are numerically
+/// equal, in either order (`1 == "1"`, `"2.50" == 2.5`)
+pub(crate) fn number_matches_string(a: &Value, b: &Value) -> bool {
+    let (number, text) = match (a, b) {
+        (Value::Number(n), Value::String(s)) | (Value::String(s), Value::Number(n)) => (n, s),
+        _ => return false,
+    };
+    text.parse::<Number>()
+        .is_ok_and(|parsed| numbers_equal(number, &parsed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn number(value: Value) -> Number {
+        match value {
+            Value::Number(n) => n,
+            other => panic!("not a number: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_numbers_equal() {
+        assert!(numbers_equal(&number(json!(1)), &number(json!(1.0))));
+        assert!(numbers_equal(&number(json!(-3)), &number(json!(-3.0))));
+        assert!(numbers_equal(
+            &number(json!(u64::MAX)),
+            &number(json!(u64::MAX))
+        ));
+        assert!(!numbers_equal(&number(json!(1)), &number(json!(1.5))));
+        assert!(!numbers_equal(&number(json!(-1)), &number(json!(u64::MAX))));
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary-precision")]
+    fn test_numbers_equal_exactly() {
+        let parse = |text: &str| text.parse::<Number>().unwrap();
+        assert!(numbers_equal(&parse("1.10"), &parse("1.1")));
+        assert!(numbers_equal(&parse("1e2"), &parse("100.00")));
+        assert!(!numbers_equal(
+            &parse("123456789012345678901234567890"),
+            &parse("123456789012345678901234567891")
+        ));
+        assert!(!numbers_equal(
+            &parse("0.10000000000000000001"),
+            &parse("0.1")
+        ));
+    }
+
+    #[test]
+    fn test_number_matches_string() {
+        assert!(number_matches_string(&json!(1), &json!("1")));
+        assert!(number_matches_string(&json!("2.50"), &json!(2.5)));
+        assert!(number_matches_string(&json!(100), &json!("1e2")));
+        assert!(!number_matches_string(&json!(1), &json!(" 1")));
+        assert!(!number_matches_string(&json!(1), &json!("one")));
+        assert!(!number_matches_string(&json!("1"), &json!("1")));
+        assert!(!number_matches_string(&json!(1), &json!(true)));
+    }
+}
@@ -0,0 +1,88 @@
+//! Custom equality for the diff engine
+
+use crate::json_path::JsonPath;
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+
+/// Decides whether two values are equal in place of the built-in comparison
+///
+/// The engine asks the comparator about every pair of values found at the
+/// same path in both documents, before comparing them itself:
+///
+/// - `Some(true)`: the values are equal; nothing below the path is compared
+/// - `Some(false)`: the values differ and are reported as one modification
+/// - `None`: the engine compares them as usual, asking again for their members
+///
+/// Comparators must be `Send + Sync`, as a parallel diff shares them between
+/// worker threads.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::{diff_with_options, Comparator, DiffOptions, JsonPath, ValueComparator};
+/// use serde_json::{json, Value};
+///
+/// /// Compare URLs ignoring the order of their query parameters
+/// struct QueryOrder;
+///
+/// impl ValueComparator for QueryOrder {
+///     fn equals(&self, _path: &JsonPath, old: &Value, new: &Value) -> Option<bool> {
+///         let (old, new) = (old.as_str()?, new.as_str()?);
+///         let split = |url: &str| {
+///             let (base, query) = url.split_once('?').unwrap_or((url, ""));
+///             let mut params: Vec<String> = query.split('&').map(String::from).collect();
+///             params.sort();
+///             (base.to_string(), params)
+///         };
+///         Some(split(old) == split(new))
+///     }
+/// }
+///
+/// let options = DiffOptions {
+///     comparator: Some(Comparator::new(QueryOrder)),
+///     ..DiffOptions::default()
+/// };
+/// let old = json!({"url": "https://a/?x=1&y=2", "n": 1});
+/// let new = json!({"url": "https://a/?y=2&x=1", "n": 2});
+/// let changes = diff_with_options(&old, &new, &options);
+/// assert_eq!(changes.len(), 1);
+/// assert_eq!(changes.modified[0].path().to_string(), "n");
+/// ```
+pub trait ValueComparator: Send + Sync {
+    /// Whether `old` and `new`, both found at `path`, are equal, or `None`
+    /// to leave the decision to the engine
+    fn equals(&self, path: &JsonPath, old: &Value, new: &Value) -> Option<bool>;
+}
+
+/// A [`ValueComparator`] set in [`DiffOptions`](crate::DiffOptions)
+///
+/// Clones share the comparator; two `Comparator`s are equal only if they
+/// share one.
+#[derive(Clone)]
+pub struct Comparator(Arc<dyn ValueComparator>);
+
+impl Comparator {
+    /// Wrap a comparator for use in the diff options
+    pub fn new(comparator: impl ValueComparator + 'static) -> Self {
+        Self(Arc::new(comparator))
+    }
+
+    pub(crate) fn equals(&self, path: &JsonPath, old: &Value, new: &Value) -> Option<bool> {
+        self.0.equals(path, old, new)
+    }
+}
+
+impl fmt::Debug for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Comparator(..)")
+    }
+}
+
+impl PartialEq for Comparator {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Comparator {}
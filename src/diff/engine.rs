@@ -1,8 +1,17 @@
-use crate::diff::visitor::{traverse, ValueVisitor, ValueVisitorExt};
+use crate::binary::{decode_base64, looks_like_base64, summary};
+use crate::datetime::same_instant;
+use crate::diff::cancel::CancellationToken;
+use crate::diff::coerce::{number_matches_string, numbers_equal};
+use crate::diff::options::{ArrayDiff, DiffOptions};
+use crate::diff::pairing::{pair_by_key, pair_by_similarity, pair_equal, MAX_SIMILARITY_PAIRS};
+use crate::diff::visitor::{traverse, ValueVisitor};
 use crate::json_path::JsonPath;
 use crate::path::{join_array_path, join_path};
-use crate::types::{Change, Changes};
+use crate::types::{
+    Change, Changes, PatternMatcher, Truncation, TruncationReason, Warning, WarningKind,
+};
 use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 /// Main diff function - compares two JSON values and returns all changes
 ///
@@ -58,20 +67,361 @@ use serde_json::Value;
 /// assert_eq!(changes.modified.len(), 1);
 /// ```
 pub fn diff(old: &Value, new: &Value) -> Changes {
+    diff_with_options(old, new, &DiffOptions::default())
+}
+
+/// Compare two JSON values with explicit [`DiffOptions`]
+///
+/// With default options this is the same as [`diff`].
+pub fn diff_with_options(old: &Value, new: &Value, options: &DiffOptions) -> Changes {
+    run_diff(old, new, options, None)
+}
+
+/// Compare two JSON values, stopping early once `token` is cancelled
+///
+/// The token is checked before each array element and object member, so
+/// another thread can abandon a long diff. A cancelled diff returns the
+/// changes found so far with a [`TruncationReason::Cancelled`] entry in
+/// [`Changes::truncated`] at the path where it stopped; a diff that finishes
+/// first is the same as [`diff_with_options`].
+///
+/// # Examples
+///
+/// ```
+/// use rjd::{diff_with_cancellation, CancellationToken, DiffOptions, TruncationReason};
+/// use serde_json::json;
+///
+/// let old = json!({"a": 1, "b": 2});
+/// let new = json!({"a": 10, "b": 20});
+///
+/// let token = CancellationToken::new();
+/// let changes = diff_with_cancellation(&old, &new, &DiffOptions::default(), &token);
+/// assert_eq!(changes.modified.len(), 2);
+///
+/// token.cancel();
+/// let changes = diff_with_cancellation(&old, &new, &DiffOptions::default(), &token);
+/// assert!(changes.modified.is_empty());
+/// assert_eq!(changes.truncated[0].reason, TruncationReason::Cancelled);
+/// ```
+pub fn diff_with_cancellation(
+    old: &Value,
+    new: &Value,
+    options: &DiffOptions,
+    token: &CancellationToken,
+) -> Changes {
+    run_diff(old, new, options, Some(token))
+}
+
+fn run_diff(
+    old: &Value,
+    new: &Value,
+    options: &DiffOptions,
+    cancel: Option<&CancellationToken>,
+) -> Changes {
+    #[cfg(feature = "parallel")]
+    if options.parallel {
+        return parallel_diff(old, new, options, cancel);
+    }
+
     let mut changes = Changes::new();
     changes.after = Some(new.clone());
-    let mut visitor = DiffVisitor {
-        changes: &mut changes,
+    let mut visitor = DiffVisitor::new(&mut changes, options, cancel);
+
+    visitor.compare(Some(old), Some(new), &JsonPath::new());
+
+    changes
+}
+
+/// Diff two JSON values, comparing top-level object keys in parallel
+///
+/// When both values are objects, each top-level key's subtree is diffed on a
+/// rayon worker thread and the per-key results are merged in key order, so the
+/// output is identical to [`diff`]. Other values fall back to [`diff`].
+///
+/// # Examples
+///
+/// ```
+/// use rjd::{diff, diff_parallel};
+/// use serde_json::json;
+///
+/// let old = json!({"a": {"x": 1}, "b": [1, 2], "c": "same"});
+/// let new = json!({"a": {"x": 2}, "b": [1], "d": true});
+///
+/// assert_eq!(diff_parallel(&old, &new), diff(&old, &new));
+/// ```
+#[cfg(feature = "parallel")]
+pub fn diff_parallel(old: &Value, new: &Value) -> Changes {
+    parallel_diff(old, new, &DiffOptions::default(), None)
+}
+
+#[cfg(feature = "parallel")]
+fn parallel_diff(
+    old: &Value,
+    new: &Value,
+    options: &DiffOptions,
+    cancel: Option<&CancellationToken>,
+) -> Changes {
+    use rayon::prelude::*;
+
+    let sequential = DiffOptions {
+        parallel: false,
+        ..options.clone()
+    };
+    let (Value::Object(old_map), Value::Object(new_map)) = (old, new) else {
+        return run_diff(old, new, &sequential, cancel);
     };
+    // A change limit depends on traversal order, so it needs the sequential diff;
+    // a comparator may claim the root, so it does too
+    if old == new
+        || options.max_depth == Some(0)
+        || options.max_changes.is_some()
+        || options.comparator.is_some()
+    {
+        return run_diff(old, new, &sequential, cancel);
+    }
+
+    let root = JsonPath::new();
+    let keys = paired_keys(Some(old_map), Some(new_map), options);
+    let per_key: Vec<Changes> = keys
+        .par_iter()
+        .filter(|(_, old_val, new_val)| !matches_absent(options, *old_val, *new_val))
+        .map(|(key, old_val, new_val)| {
+            let mut changes = Changes::new();
+            let mut visitor = DiffVisitor::new(&mut changes, &sequential, cancel);
+            let key_path = join_path(&root, key);
+            if !visitor.cancelled(&key_path) {
+                visitor.compare(*old_val, *new_val, &key_path);
+            }
+            changes
+        })
+        .collect();
 
-    traverse(Some(old), Some(new), &JsonPath::new(), &mut visitor);
+    // Merge in key order so the output matches the sequential diff
+    let mut changes = Changes::new();
+    changes.after = Some(new.clone());
+    for part in per_key {
+        changes.added.extend(part.added);
+        changes.removed.extend(part.removed);
+        changes.modified.extend(part.modified);
+        for truncation in part.truncated {
+            // Every worker still running notices a cancellation; report it once
+            if truncation.reason != TruncationReason::Cancelled || !changes.is_cancelled() {
+                changes.truncated.push(truncation);
+            }
+        }
+        changes.warnings.extend(part.warnings);
+        changes.annotations.extend(part.annotations);
+        changes.paired_arrays.extend(part.paired_arrays);
+    }
 
     changes
 }
 
+/// Arrays at least this long whose lengths differ get a positional comparison warning
+const LARGE_ARRAY_LEN: usize = 1000;
+
+/// Floats at or beyond this magnitude cannot represent every integer exactly (2^53)
+#[cfg(not(feature = "arbitrary-precision"))]
+const EXACT_FLOAT_LIMIT: f64 = 9_007_199_254_740_992.0;
+
+/// Whether a number is a float too large to hold every integer exactly,
+/// typically a big integer from the source text that did not fit in 64 bits
+///
+/// With the `arbitrary-precision` feature numbers keep their text, so none
+/// lose precision.
+#[cfg(not(feature = "arbitrary-precision"))]
+fn may_have_lost_precision(number: &serde_json::Number) -> bool {
+    number.is_f64()
+        && number
+            .as_f64()
+            .is_some_and(|f| f.fract() == 0.0 && f.abs() >= EXACT_FLOAT_LIMIT)
+}
+
+/// The JSON object or array a string holds, if any
+fn embedded_json(text: &str) -> Option<Value> {
+    let trimmed = text.trim_start();
+    if !trimmed.starts_with(['{', '[']) {
+        return None;
+    }
+    serde_json::from_str(trimmed)
+        .ok()
+        .filter(|value: &Value| value.is_object() || value.is_array())
+}
+
+/// A key of the compared objects with its value on each side
+type KeyPair<'v> = (String, Option<&'v Value>, Option<&'v Value>);
+
+/// Pair up the keys of two objects for comparison
+///
+/// Keys of the old object come first, in order, followed by keys only present
+/// in the new object; with `sort_keys` all keys are in alphabetical order
+/// instead. With `ignore_key_case`, keys that differ only in case are paired
+/// (exact matches are preferred) and reported under the new object's key.
+fn paired_keys<'v>(
+    old_value: Option<&'v serde_json::Map<String, Value>>,
+    new_value: Option<&'v serde_json::Map<String, Value>>,
+    options: &DiffOptions,
+) -> Vec<KeyPair<'v>> {
+    let old_entries = old_value.into_iter().flatten();
+    let new_entries = new_value.into_iter().flatten();
+
+    let mut pairs: Vec<KeyPair<'v>> = if options.ignore_key_case {
+        let old_entries: Vec<(&String, &'v Value)> = old_entries.collect();
+        let new_entries: Vec<(&String, &'v Value)> = new_entries.collect();
+        // Index the new keys once, exactly and by lowercase (in document order)
+        let mut exact: HashMap<&str, usize> = HashMap::with_capacity(new_entries.len());
+        let mut by_lower: HashMap<String, VecDeque<usize>> = HashMap::new();
+        for (i, (key, _)) in new_entries.iter().enumerate() {
+            exact.insert(key.as_str(), i);
+            by_lower.entry(key.to_lowercase()).or_default().push_back(i);
+        }
+
+        let mut taken = vec![false; new_entries.len()];
+        let mut partners: Vec<Option<usize>> = old_entries
+            .iter()
+            .map(|(key, _)| {
+                let partner = exact.get(key.as_str()).copied();
+                if let Some(i) = partner {
+                    taken[i] = true;
+                }
+                partner
+            })
+            .collect();
+        for (partner, (key, _)) in partners.iter_mut().zip(&old_entries) {
+            if partner.is_some() {
+                continue;
+            }
+            let Some(candidates) = by_lower.get_mut(&key.to_lowercase()) else {
+                continue;
+            };
+            // Candidates taken by an exact match or an earlier key are dropped for good
+            while candidates.front().is_some_and(|&i| taken[i]) {
+                candidates.pop_front();
+            }
+            *partner = candidates.pop_front();
+            if let Some(i) = *partner {
+                taken[i] = true;
+            }
+        }
+
+        let mut pairs: Vec<KeyPair<'v>> = old_entries
+            .iter()
+            .zip(partners)
+            .map(|((key, value), partner)| match partner {
+                Some(i) => (
+                    new_entries[i].0.clone(),
+                    Some(*value),
+                    Some(new_entries[i].1),
+                ),
+                None => ((*key).clone(), Some(*value), None),
+            })
+            .collect();
+        pairs.extend(
+            new_entries
+                .iter()
+                .zip(taken)
+                .filter(|(_, taken)| !taken)
+                .map(|((key, value), _)| ((*key).clone(), None, Some(*value))),
+        );
+        pairs
+    } else {
+        let mut pairs: Vec<KeyPair<'v>> = old_entries
+            .map(|(key, value)| (key.clone(), Some(value), new_value.and_then(|m| m.get(key))))
+            .collect();
+        pairs.extend(
+            new_entries
+                .filter(|(key, _)| !old_value.is_some_and(|m| m.contains_key(*key)))
+                .map(|(key, value)| (key.clone(), None, Some(value))),
+        );
+        pairs
+    };
+    if options.sort_keys {
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+    pairs
+}
+
+/// Whether a member is missing on one side and, by `null_as_absent` or
+/// `empty_as_absent`, counts as missing on the other
+fn matches_absent(options: &DiffOptions, old: Option<&Value>, new: Option<&Value>) -> bool {
+    match (old, new) {
+        (Some(value), None) | (None, Some(value)) => counts_as_absent(options, value),
+        _ => false,
+    }
+}
+
+fn counts_as_absent(options: &DiffOptions, value: &Value) -> bool {
+    match value {
+        Value::Null => options.null_as_absent,
+        Value::Array(items) => options.empty_as_absent && items.is_empty(),
+        Value::Object(map) => options.empty_as_absent && map.is_empty(),
+        _ => false,
+    }
+}
+
+/// Whether an array holds only scalars, so `array_sets` compares it as a set
+fn is_scalar_list(items: &[Value]) -> bool {
+    items
+        .iter()
+        .all(|item| !matches!(item, Value::Array(_) | Value::Object(_)))
+}
+
+/// Elements only in `old`, only in `new` and in both, each once and in
+/// document order (`old`'s for the common ones)
+fn set_difference(old: &[Value], new: &[Value]) -> (Vec<Value>, Vec<Value>, Vec<Value>) {
+    // Scalars are equal exactly when their JSON texts are
+    let texts =
+        |items: &[Value]| -> HashSet<String> { items.iter().map(Value::to_string).collect() };
+    let (old_texts, new_texts) = (texts(old), texts(new));
+    let only = |items: &[Value], other: &HashSet<String>, keep: bool| {
+        let mut seen = HashSet::new();
+        items
+            .iter()
+            .filter(|item| {
+                let text = item.to_string();
+                other.contains(&text) == keep && seen.insert(text)
+            })
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    (
+        only(new, &old_texts, false),
+        only(old, &new_texts, false),
+        only(old, &new_texts, true),
+    )
+}
+
 /// Visitor implementation that collects changes during traversal
 struct DiffVisitor<'a> {
     changes: &'a mut Changes,
+    options: &'a DiffOptions,
+    /// Compiled `options.binary_paths`
+    binary_paths: PatternMatcher,
+    /// Compiled patterns of `options.array_strategies`
+    array_strategies: Vec<(PatternMatcher, &'a ArrayDiff)>,
+    cancel: Option<&'a CancellationToken>,
+}
+
+impl<'a> DiffVisitor<'a> {
+    fn new(
+        changes: &'a mut Changes,
+        options: &'a DiffOptions,
+        cancel: Option<&'a CancellationToken>,
+    ) -> Self {
+        Self {
+            changes,
+            options,
+            binary_paths: PatternMatcher::new(&options.binary_paths),
+            array_strategies: options
+                .array_strategies
+                .iter()
+                .map(|(pattern, strategy)| {
+                    (PatternMatcher::new(std::slice::from_ref(pattern)), strategy)
+                })
+                .collect(),
+            cancel,
+        }
+    }
 }
 
 impl<'a> ValueVisitor for DiffVisitor<'a> {
@@ -84,6 +434,9 @@ impl<'a> ValueVisitor for DiffVisitor<'a> {
         new_value: Option<&Value>,
     ) -> Self::Output {
         // Null values don't need special handling - they are treated like any other value
+        if self.ignores_values(old_value, new_value) {
+            return;
+        }
         self.handle_change(path, old_value.cloned(), new_value.cloned())
     }
 
@@ -93,6 +446,9 @@ impl<'a> ValueVisitor for DiffVisitor<'a> {
         old_value: Option<&bool>,
         new_value: Option<&bool>,
     ) -> Self::Output {
+        if self.ignores_values(old_value, new_value) {
+            return;
+        }
         self.handle_change(
             path,
             old_value.cloned().map(Value::Bool),
@@ -106,6 +462,18 @@ impl<'a> ValueVisitor for DiffVisitor<'a> {
         old_value: Option<&Value>,
         new_value: Option<&Value>,
     ) -> Self::Output {
+        #[cfg(not(feature = "arbitrary-precision"))]
+        for value in [old_value, new_value].into_iter().flatten() {
+            self.check_precision(path, value);
+        }
+        if self.ignores_values(old_value, new_value) {
+            return;
+        }
+        if let (Some(Value::Number(old)), Some(Value::Number(new))) = (old_value, new_value) {
+            if self.coerces_numbers() && numbers_equal(old, new) {
+                return;
+            }
+        }
         self.handle_change(path, old_value.cloned(), new_value.cloned())
     }
 
@@ -115,6 +483,26 @@ impl<'a> ValueVisitor for DiffVisitor<'a> {
         old_value: Option<&String>,
         new_value: Option<&String>,
     ) -> Self::Output {
+        if self.ignores_values(old_value, new_value) {
+            return;
+        }
+        if let (Some(old), Some(new), true) =
+            (old_value, new_value, self.options.parse_embedded_json)
+        {
+            if let (Some(old), Some(new)) = (embedded_json(old), embedded_json(new)) {
+                return traverse(Some(&old), Some(&new), path, self);
+            }
+        }
+        let old_blob = old_value.and_then(|text| self.decode_binary(path, text));
+        let new_blob = new_value.and_then(|text| self.decode_binary(path, text));
+        if old_blob.is_some() || new_blob.is_some() {
+            return self.handle_binary(path, (old_value, old_blob), (new_value, new_blob));
+        }
+        if let (Some(old), Some(new), true) = (old_value, new_value, self.options.normalize_dates) {
+            if same_instant(old, new, self.options.date_tolerance) {
+                return;
+            }
+        }
         self.handle_change(
             path,
             old_value.cloned().map(Value::String),
@@ -128,16 +516,74 @@ impl<'a> ValueVisitor for DiffVisitor<'a> {
         old_value: Option<&Vec<Value>>,
         new_value: Option<&Vec<Value>>,
     ) -> Self::Output {
+        if self.at_max_depth(path) {
+            return self.handle_subtree(
+                path,
+                old_value.cloned().map(Value::Array),
+                new_value.cloned().map(Value::Array),
+            );
+        }
+
+        if let (Some(items), None) | (None, Some(items)) = (old_value, new_value) {
+            if items.is_empty() {
+                // Report an added or removed empty array; it has no leaves to list
+                return self.handle_change(
+                    path,
+                    old_value.cloned().map(Value::Array),
+                    new_value.cloned().map(Value::Array),
+                );
+            }
+        }
+
+        if let (Some(old), Some(new)) = (old_value, new_value) {
+            let strategy = self
+                .array_strategies
+                .iter()
+                .find(|(pattern, _)| pattern.matches(path))
+                .map(|(_, strategy)| *strategy);
+            if strategy.is_none()
+                && self.options.array_sets
+                && !self.options.structure_only
+                && is_scalar_list(old)
+                && is_scalar_list(new)
+            {
+                return self.handle_array_set(path, old, new);
+            }
+            let pairs = match strategy.unwrap_or(&self.options.array_diff) {
+                ArrayDiff::Positional => None,
+                ArrayDiff::Similarity(threshold) => (old.len().saturating_mul(new.len())
+                    <= MAX_SIMILARITY_PAIRS)
+                    .then(|| pair_by_similarity(old, new, threshold.get())),
+                ArrayDiff::Keyed(key) => pair_by_key(old, new, key),
+                ArrayDiff::Unordered => Some(pair_equal(old, new)),
+            };
+            if let Some(pairs) = pairs {
+                return self.diff_paired(path, old, new, pairs);
+            }
+        }
+
         let old_len = old_value.map(|v| v.len()).unwrap_or(0);
         let new_len = new_value.map(|v| v.len()).unwrap_or(0);
         let max_len = old_len.max(new_len);
+        if old_value.is_some()
+            && new_value.is_some()
+            && old_len != new_len
+            && max_len >= LARGE_ARRAY_LEN
+        {
+            self.warn(path, WarningKind::PositionalArray { old_len, new_len });
+        }
 
         for i in 0..max_len {
             let element_path = join_array_path(path, i);
             let old_element = old_value.and_then(|v| v.get(i));
             let new_element = new_value.and_then(|v| v.get(i));
 
-            traverse(old_element, new_element, &element_path, self);
+            if self.cancelled(&element_path)
+                || (old_element != new_element && self.at_change_limit(&element_path))
+            {
+                break;
+            }
+            self.compare(old_element, new_element, &element_path);
         }
     }
 
@@ -147,40 +593,284 @@ impl<'a> ValueVisitor for DiffVisitor<'a> {
         old_value: Option<&serde_json::Map<String, Value>>,
         new_value: Option<&serde_json::Map<String, Value>>,
     ) -> Self::Output {
-        // Collect all keys from new_value first (preserves "after" file order)
-        let mut all_keys: Vec<String> = new_value
-            .as_ref()
-            .map(|m| m.keys().cloned().collect())
-            .unwrap_or_default();
-
-        // Add keys only in old_value (removed keys)
-        if let Some(old_map) = old_value {
-            for key in old_map.keys() {
-                if new_value
-                    .as_ref()
-                    .map(|m| !m.contains_key(key))
-                    .unwrap_or(true)
-                {
-                    all_keys.push(key.clone());
-                }
+        if self.at_max_depth(path) {
+            return self.handle_subtree(
+                path,
+                old_value.cloned().map(Value::Object),
+                new_value.cloned().map(Value::Object),
+            );
+        }
+
+        if let (Some(map), None) | (None, Some(map)) = (old_value, new_value) {
+            if map.is_empty() {
+                // Report an added or removed empty object; it has no leaves to list
+                return self.handle_change(
+                    path,
+                    old_value.cloned().map(Value::Object),
+                    new_value.cloned().map(Value::Object),
+                );
             }
         }
 
-        for key in all_keys {
+        for (key, old_val, new_val) in paired_keys(old_value, new_value, self.options) {
+            if matches_absent(self.options, old_val, new_val) {
+                continue;
+            }
             let key_path = join_path(path, &key);
-            let old_val = old_value.and_then(|m| m.get(&key));
-            let new_val = new_value.and_then(|m| m.get(&key));
+            if self.cancelled(&key_path) {
+                break;
+            }
+
+            if old_val != new_val {
+                if self.at_change_limit(&key_path) {
+                    break;
+                }
+                if key.contains(['.', '[', ']']) {
+                    self.warn(&key_path, WarningKind::AmbiguousKey(key.clone()));
+                }
+            }
+            self.compare(old_val, new_val, &key_path);
+        }
+    }
 
-            traverse(old_val, new_val, &key_path, self);
+    fn visit_modified(&mut self, path: &JsonPath, old: &Value, new: &Value) -> Self::Output {
+        // Type mismatch: record the change unless the options equate the values
+        if self.options.empty_as_absent
+            && counts_as_absent(self.options, old)
+            && counts_as_absent(self.options, new)
+        {
+            // `{}` and `[]`
+            return;
+        }
+        if self.options.coerce_number_strings && number_matches_string(old, new) {
+            return;
         }
+        self.handle_change(path, Some(old.clone()), Some(new.clone()))
     }
 
-    fn visit_equal(&mut self, _path: &JsonPath, _value: &Value) -> Self::Output {
-        // Values are equal - no change to record
+    #[cfg(not(feature = "arbitrary-precision"))]
+    fn visit_equal(&mut self, path: &JsonPath, value: &Value) -> Self::Output {
+        // Values are equal - no change to record, but the equality may be an
+        // artifact of lost precision
+        self.check_precision(path, value);
     }
 }
 
 impl<'a> DiffVisitor<'a> {
+    /// Compare the values at `path`, asking the custom comparator first
+    fn compare(&mut self, old_value: Option<&Value>, new_value: Option<&Value>, path: &JsonPath) {
+        if let (Some(comparator), Some(old), Some(new)) =
+            (&self.options.comparator, old_value, new_value)
+        {
+            match comparator.equals(path, old, new) {
+                Some(true) => return,
+                Some(false) => {
+                    return self.handle_change(path, Some(old.clone()), Some(new.clone()));
+                }
+                None => {}
+            }
+        }
+        traverse(old_value, new_value, path, self);
+    }
+
+    /// Whether containers at this path are reported whole instead of recursed into
+    fn at_max_depth(&self, path: &JsonPath) -> bool {
+        self.options
+            .max_depth
+            .is_some_and(|max_depth| path.len() >= max_depth)
+    }
+
+    fn warn(&mut self, path: &JsonPath, kind: WarningKind) {
+        self.changes.warnings.push(Warning {
+            path: path.clone(),
+            kind,
+        });
+    }
+
+    /// Warn about numbers in `value` that may not have been parsed exactly
+    #[cfg(not(feature = "arbitrary-precision"))]
+    fn check_precision(&mut self, path: &JsonPath, value: &Value) {
+        match value {
+            Value::Number(number) if may_have_lost_precision(number) => {
+                self.warn(path, WarningKind::PrecisionLoss);
+            }
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    self.check_precision(&join_array_path(path, i), item);
+                }
+            }
+            Value::Object(map) => {
+                for (key, item) in map {
+                    self.check_precision(&join_path(path, key), item);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether two scalars of the same type count as equal because only the
+    /// structure is compared
+    fn ignores_values<T>(&self, old_value: Option<T>, new_value: Option<T>) -> bool {
+        self.options.structure_only && old_value.is_some() && new_value.is_some()
+    }
+
+    /// The bytes of a string treated as a base64 blob, by path or by the heuristic
+    fn decode_binary(&self, path: &JsonPath, text: &str) -> Option<Vec<u8>> {
+        let is_blob = self.binary_paths.matches(path)
+            || (self.options.detect_binary && looks_like_base64(text));
+        is_blob.then(|| decode_base64(text)).flatten()
+    }
+
+    /// Report a change of strings where at least one side is a blob
+    ///
+    /// Blobs holding JSON documents on both sides are diffed with
+    /// `decode_binary_json`; otherwise each blob is reported as its
+    /// [`summary`], and blobs with the same bytes are equal.
+    fn handle_binary(
+        &mut self,
+        path: &JsonPath,
+        (old_text, old_blob): (Option<&String>, Option<Vec<u8>>),
+        (new_text, new_blob): (Option<&String>, Option<Vec<u8>>),
+    ) {
+        if self.options.decode_binary_json {
+            let document = |blob: &Option<Vec<u8>>| {
+                let text = std::str::from_utf8(blob.as_deref()?).ok()?;
+                embedded_json(text)
+            };
+            if let (Some(old), Some(new)) = (document(&old_blob), document(&new_blob)) {
+                return traverse(Some(&old), Some(&new), path, self);
+            }
+        }
+        let reported = |text: Option<&String>, blob: Option<Vec<u8>>| match blob {
+            Some(bytes) => Some(summary(&bytes)),
+            None => text.cloned().map(Value::String),
+        };
+        let old_value = reported(old_text, old_blob);
+        let new_value = reported(new_text, new_blob);
+        if old_value != new_value {
+            self.handle_change(path, old_value, new_value);
+        }
+    }
+
+    /// Report two arrays of scalars as one change annotated with the elements
+    /// added, removed and kept; arrays holding the same elements are equal
+    fn handle_array_set(&mut self, path: &JsonPath, old: &[Value], new: &[Value]) {
+        let (added, removed, common) = set_difference(old, new);
+        if added.is_empty() && removed.is_empty() {
+            return;
+        }
+        self.handle_change(
+            path,
+            Some(Value::Array(old.to_vec())),
+            Some(Value::Array(new.to_vec())),
+        );
+        let annotations = self.changes.annotations.entry(path.clone()).or_default();
+        annotations.insert("addedElements".to_string(), Value::Array(added));
+        annotations.insert("removedElements".to_string(), Value::Array(removed));
+        annotations.insert("common".to_string(), Value::Array(common));
+    }
+
+    /// Compare array elements paired by an [`ArrayDiff`] strategy
+    ///
+    /// Paired elements are reported under their index in the new array and
+    /// unpaired old elements after its end, in order, so that every change
+    /// has its own path. When anything is reported, the pairs are recorded
+    /// in [`Changes::paired_arrays`].
+    fn diff_paired(
+        &mut self,
+        path: &JsonPath,
+        old: &[Value],
+        new: &[Value],
+        pairs: Vec<Option<usize>>,
+    ) {
+        let reported = self.changes.len();
+        let mut paired = vec![false; old.len()];
+        let mut index_pairs = Vec::new();
+        for (j, pair) in pairs.iter().enumerate() {
+            if let Some(i) = *pair {
+                paired[i] = true;
+                index_pairs.push((i, j));
+            }
+        }
+
+        let kept = new
+            .iter()
+            .enumerate()
+            .map(|(j, item)| (j, pairs[j].map(|i| &old[i]), Some(item)));
+        let removed = old
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| !paired[i])
+            .enumerate()
+            .map(|(k, (_, item))| (new.len() + k, Some(item), None));
+        for (index, old_element, new_element) in kept.chain(removed) {
+            let element_path = join_array_path(path, index);
+            if self.cancelled(&element_path)
+                || (old_element != new_element && self.at_change_limit(&element_path))
+            {
+                break;
+            }
+            self.compare(old_element, new_element, &element_path);
+        }
+
+        if self.changes.len() > reported {
+            self.changes.paired_arrays.insert(path.clone(), index_pairs);
+        }
+    }
+
+    /// Whether numbers are compared by value rather than representation
+    fn coerces_numbers(&self) -> bool {
+        self.options.coerce_numbers || self.options.coerce_number_strings
+    }
+
+    /// Check the change limit before visiting `path`, recording where the diff stopped
+    fn at_change_limit(&mut self, path: &JsonPath) -> bool {
+        let Some(limit) = self.options.max_changes else {
+            return false;
+        };
+        if self.changes.len() < limit {
+            return false;
+        }
+        if !self.changes.is_truncated() {
+            self.changes.truncated.push(Truncation {
+                path: path.clone(),
+                reason: TruncationReason::MaxChanges(limit),
+            });
+        }
+        true
+    }
+
+    /// Check for cancellation before visiting `path`, recording where the diff stopped
+    fn cancelled(&mut self, path: &JsonPath) -> bool {
+        if !self.cancel.is_some_and(CancellationToken::is_cancelled) {
+            return false;
+        }
+        if !self.changes.is_cancelled() {
+            self.changes.truncated.push(Truncation {
+                path: path.clone(),
+                reason: TruncationReason::Cancelled,
+            });
+        }
+        true
+    }
+
+    /// Report a container at the maximum depth as a single change
+    fn handle_subtree(
+        &mut self,
+        path: &JsonPath,
+        old_value: Option<Value>,
+        new_value: Option<Value>,
+    ) {
+        if let (Some(_), Some(_), Some(depth)) = (&old_value, &new_value, self.options.max_depth) {
+            // Only a modified subtree loses detail; added/removed ones are complete
+            self.changes.truncated.push(Truncation {
+                path: path.clone(),
+                reason: TruncationReason::MaxDepth(depth),
+            });
+        }
+        self.handle_change(path, old_value, new_value);
+    }
+
     fn handle_change(
         &mut self,
         path: &JsonPath,
@@ -214,23 +904,638 @@ impl<'a> DiffVisitor<'a> {
     }
 }
 
-impl<'a> ValueVisitorExt for DiffVisitor<'a> {
-    fn visit_modified(
-        &mut self,
-        path: &JsonPath,
-        old_value: Option<&Value>,
-        new_value: Option<&Value>,
-    ) -> Self::Output {
-        // For type mismatches or primitive modifications, just record the change
-        self.handle_change(path, old_value.cloned(), new_value.cloned())
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_max_depth_reports_subtrees() {
+        let old = json!({"a": {"b": {"c": 1}}, "list": [[1]], "gone": {"x": 1}});
+        let new = json!({"a": {"b": {"c": 2}}, "list": [[2]], "new": {"y": 1}});
+        let options = DiffOptions {
+            max_depth: Some(1),
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &options);
+
+        let modified: Vec<String> = changes
+            .modified
+            .iter()
+            .map(|c| c.path().to_string())
+            .collect();
+        assert_eq!(modified, vec!["a", "list"]);
+        assert_eq!(changes.added.len(), 1);
+        assert_eq!(changes.added[0].path().to_string(), "new");
+        assert_eq!(changes.removed[0].path().to_string(), "gone");
+
+        // Only the modified subtrees lost detail
+        let truncated: Vec<String> = changes
+            .truncated
+            .iter()
+            .map(|t| t.path.to_string())
+            .collect();
+        assert_eq!(truncated, vec!["a", "list"]);
+        assert_eq!(changes.truncated[0].reason, TruncationReason::MaxDepth(1));
+
+        let root = diff_with_options(
+            &old,
+            &new,
+            &DiffOptions {
+                max_depth: Some(0),
+                ..DiffOptions::default()
+            },
+        );
+        assert_eq!(root.modified.len(), 1);
+        assert!(root.modified[0].path().is_empty());
+
+        // Unlimited depth matches the default diff
+        assert_eq!(
+            diff_with_options(&old, &new, &DiffOptions::default()),
+            diff(&old, &new)
+        );
+    }
+
+    #[test]
+    fn test_ignore_key_case() {
+        let old = json!({"Name": "x", "Age": 1, "name": "exact", "Gone": true});
+        let new = json!({"name": "exact", "NAME": "x", "age": 2, "New": false});
+        let options = DiffOptions {
+            ignore_key_case: true,
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &options);
+
+        // "name" pairs with its exact match, "NAME" with the remaining "Name"
+        let modified: Vec<String> = changes
+            .modified
+            .iter()
+            .map(|c| c.path().to_string())
+            .collect();
+        assert_eq!(modified, vec!["age"]);
+        assert_eq!(changes.added[0].path().to_string(), "New");
+        assert_eq!(changes.removed[0].path().to_string(), "Gone");
+        assert_eq!(changes.len(), 3);
+
+        let nested = diff_with_options(
+            &json!({"User": {"ID": 1}}),
+            &json!({"user": {"id": 1}}),
+            &options,
+        );
+        assert!(nested.is_empty());
+        // Several keys with the same lowercase pair up in document order
+        let repeated = diff_with_options(
+            &json!({"KEY": 1, "Key": 2, "key": 3}),
+            &json!({"key": 3, "kEy": 1, "keY": 2}),
+            &options,
+        );
+        assert!(repeated.is_empty());
+        assert_eq!(
+            diff(&json!({"User": 1}), &json!({"user": 1})).len(),
+            2,
+            "keys are case-sensitive by default"
+        );
+    }
+
+    #[test]
+    fn test_key_order() {
+        let old = json!({"b": 1, "gone": 1, "a": 1});
+        let new = json!({"new": 1, "a": 2, "b": 2, "c": 1});
+        let paths =
+            |changes: &Changes| -> Vec<String> { changes.paths().map(|p| p.to_string()).collect() };
+
+        // Keys of the old document in order, then keys only in the new one
+        let changes = diff(&old, &new);
+        assert_eq!(paths(&changes), vec!["new", "c", "gone", "b", "a"]);
+
+        let options = DiffOptions {
+            sort_keys: true,
+            ..DiffOptions::default()
+        };
+        let sorted = diff_with_options(&old, &new, &options);
+        assert_eq!(paths(&sorted), vec!["c", "new", "gone", "a", "b"]);
+
+        let options = DiffOptions {
+            sort_keys: true,
+            ignore_key_case: true,
+            ..DiffOptions::default()
+        };
+        let sorted =
+            diff_with_options(&json!({"B": 1, "A": 1}), &json!({"b": 2, "a": 2}), &options);
+        assert_eq!(paths(&sorted), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_array_sets() {
+        let old = json!({"tags": ["a", "b", "c", 1], "ids": [1, 2], "nested": [[1], [2]]});
+        let new =
+            json!({"tags": ["c", "d", "a", "d", "1"], "ids": [2, 1, 1], "nested": [[1], [3]]});
+        let options = DiffOptions {
+            array_sets: true,
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &options);
+
+        // Reordered and repeated elements are equal; arrays of arrays keep
+        // index changes, down to the inner arrays of scalars
+        let paths: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(paths, vec!["tags", "nested[1]"]);
+        let tags = &changes.annotations[&"tags".parse().unwrap()];
+        assert_eq!(tags["addedElements"], json!(["d", "1"]));
+        assert_eq!(tags["removedElements"], json!(["b", 1]));
+        assert_eq!(tags["common"], json!(["a", "c"]));
+
+        let parallel = DiffOptions {
+            parallel: true,
+            ..options.clone()
+        };
+        assert_eq!(diff_with_options(&old, &new, &parallel), changes);
+        assert_eq!(diff(&old, &new).len(), 9);
+    }
+
+    #[test]
+    fn test_array_diff_similarity() {
+        use crate::diff::{ArrayDiff, SimilarityThreshold};
+
+        let old = json!([{"id": 1, "v": "a"}, {"id": 2, "v": "b"}, {"id": 3, "v": "c"}]);
+        let new = json!([{"id": 3, "v": "c"}, {"id": 1, "v": "x"}]);
+        let options = DiffOptions {
+            array_diff: ArrayDiff::Similarity(SimilarityThreshold::new(0.5).unwrap()),
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &options);
+
+        // Paired elements under their new index, the unpaired one after them
+        let paths: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(paths, vec!["[2].id", "[2].v", "[1].v"]);
+        assert_eq!(changes.removed.len(), 2);
+        assert_eq!(changes.modified.len(), 1);
+    }
+
+    #[test]
+    fn test_array_strategies() {
+        use crate::diff::ArrayDiff;
+
+        let old = json!({
+            "users": [{"id": 1, "role": "a"}, {"id": 2, "role": "b"}],
+            "tags": ["x", "y", "x"],
+            "list": [1, 2]
+        });
+        let new = json!({
+            "users": [{"id": 2, "role": "b"}, {"id": 1, "role": "c"}],
+            "tags": ["y", "x", "z"],
+            "list": [2, 1]
+        });
+        let options = DiffOptions {
+            array_strategies: vec![
+                ("/users".to_string(), ArrayDiff::Keyed("id".to_string())),
+                ("$..tags".to_string(), ArrayDiff::Unordered),
+                ("/tags".to_string(), ArrayDiff::Positional),
+            ],
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &options);
+        let paths: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        // The second "x" became "z"; only the unlisted array is positional
+        assert_eq!(
+            paths,
+            vec!["tags[2]", "tags[3]", "users[1].role", "list[0]", "list[1]"]
+        );
+
+        assert_eq!("keyed:id".parse(), Ok(ArrayDiff::Keyed("id".to_string())));
+        assert_eq!(
+            "similarity:0.5".parse::<ArrayDiff>().unwrap().to_string(),
+            "similarity:0.5"
+        );
+        for invalid in ["keyed", "keyed:", "similarity:2", "sorted", "unordered:x"] {
+            assert!(invalid.parse::<ArrayDiff>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_value_comparator() {
+        use crate::diff::{Comparator, ValueComparator};
+
+        /// Versions equal up to a leading "v"; whole objects under "meta" differ
+        struct Versions;
+
+        impl ValueComparator for Versions {
+            fn equals(&self, path: &JsonPath, old: &Value, new: &Value) -> Option<bool> {
+                if path.to_string() == "meta" {
+                    return Some(false);
+                }
+                let (old, new) = (old.as_str()?, new.as_str()?);
+                Some(old.trim_start_matches('v') == new.trim_start_matches('v'))
+            }
+        }
+
+        let options = DiffOptions {
+            comparator: Some(Comparator::new(Versions)),
+            ..DiffOptions::default()
+        };
+        let old = json!({"version": "v1.2", "deps": ["1.0", "2.0"], "meta": {"a": 1}, "n": 1});
+        let new = json!({"version": "1.2", "deps": ["v1.0", "3.0"], "meta": {"a": 2}, "n": 1});
+        let changes = diff_with_options(&old, &new, &options);
+        let paths: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(paths, vec!["deps[1]", "meta"]);
+        assert!(matches!(
+            &changes.modified[1],
+            Change::Modified { new_value, .. } if *new_value == json!({"a": 2})
+        ));
+
+        // The comparator is consulted at the root too, also in a parallel diff
+        let parallel = DiffOptions {
+            parallel: true,
+            ..options.clone()
+        };
+        assert!(diff_with_options(&json!("v1"), &json!("1"), &options).is_empty());
+        assert_eq!(diff_with_options(&old, &new, &parallel), changes);
+        assert_eq!(options, options.clone());
+        assert_ne!(options, DiffOptions::default());
+    }
+
+    #[test]
+    fn test_normalize_dates() {
+        let old = json!({"at": "2024-01-01T00:00:00Z", "seen": "2024-01-01T00:00:00Z", "id": "a"});
+        let new =
+            json!({"at": "2024-01-01T00:00:00+00:00", "seen": "2024-01-01T00:00:04Z", "id": "b"});
+        assert_eq!(
+            diff(&old, &new).len(),
+            3,
+            "timestamps compare as strings by default"
+        );
+
+        let mut options = DiffOptions {
+            normalize_dates: true,
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &options);
+        let modified: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(modified, vec!["seen", "id"]);
+
+        options.date_tolerance = std::time::Duration::from_secs(5);
+        let changes = diff_with_options(&old, &new, &options);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes.modified[0].path().to_string(), "id");
+    }
+
+    #[test]
+    fn test_coerce_numbers() {
+        let old = json!({"int": 1, "float": 2.5, "text": "3", "other": 4});
+        let new = json!({"int": 1.0, "float": "2.50", "text": 3, "other": 5});
+        assert_eq!(diff(&old, &new).len(), 4);
+
+        let mut options = DiffOptions {
+            coerce_numbers: true,
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &options);
+        let paths: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(paths, vec!["float", "text", "other"]);
+
+        options.coerce_number_strings = true;
+        let changes = diff_with_options(&old, &new, &options);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes.modified[0].path().to_string(), "other");
+
+        options.coerce_numbers = false;
+        assert_eq!(diff_with_options(&old, &new, &options), changes);
+    }
+
+    #[test]
+    fn test_null_as_absent() {
+        let old = json!({"a": null, "b": 1, "c": null, "list": [null], "nested": {"x": null}});
+        let new = json!({"b": null, "c": null, "d": null, "list": [], "nested": {}});
+        assert_eq!(diff(&old, &new).len(), 5);
+
+        let options = DiffOptions {
+            null_as_absent: true,
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &options);
+        // A value becoming null and array elements are still changes
+        let paths: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(paths, vec!["list[0]", "b"]);
+
+        #[cfg(feature = "parallel")]
+        assert_eq!(
+            diff_with_options(
+                &old,
+                &new,
+                &DiffOptions {
+                    parallel: true,
+                    ..options.clone()
+                }
+            ),
+            changes
+        );
+    }
+
+    #[test]
+    fn test_warnings() {
+        let long: Vec<u32> = (0..LARGE_ARRAY_LEN as u32).collect();
+        let short = &long[1..];
+        let big: Value = serde_json::from_str("123456789012345678901234567890").unwrap();
+        let old = json!({"items": long, "same": {"id": big}, "a.b": 1, "c[0]": 1, "plain": 1});
+        let new = json!({"items": short, "same": {"id": big}, "a.b": 2, "c[0]": 1, "plain": 2});
+        let changes = diff(&old, &new);
+
+        let warnings: Vec<(String, WarningKind)> = changes
+            .warnings
+            .iter()
+            .map(|w| (w.path.to_string(), w.kind.clone()))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                (
+                    "items".to_string(),
+                    WarningKind::PositionalArray {
+                        old_len: 1000,
+                        new_len: 999
+                    }
+                ),
+                #[cfg(not(feature = "arbitrary-precision"))]
+                ("same.id".to_string(), WarningKind::PrecisionLoss),
+                (
+                    "a.b".to_string(),
+                    WarningKind::AmbiguousKey("a.b".to_string())
+                ),
+            ]
+        );
+
+        // Small arrays, exact numbers and plain keys are not suspicious
+        let quiet = diff(
+            &json!({"a": [1, 2], "n": 1.5}),
+            &json!({"a": [2], "n": 2.5}),
+        );
+        assert!(quiet.warnings.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary-precision")]
+    fn test_numbers_compared_exactly() {
+        let parse = |text: &str| -> Value { serde_json::from_str(text).unwrap() };
+        let old = parse(r#"{"big": 123456789012345678901234567890, "price": 1.10, "n": 1}"#);
+        let new = parse(r#"{"big": 123456789012345678901234567891, "price": 1.1, "n": 1.0}"#);
+
+        let changes = diff(&old, &new);
+        let paths: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(paths, ["big", "price", "n"]);
+        assert_eq!(
+            changes.modified[1],
+            Change::Modified {
+                path: "price".parse().unwrap(),
+                old_value: parse("1.10"),
+                new_value: parse("1.1"),
+            }
+        );
+        assert!(changes.warnings.is_empty());
+
+        // By value, only the big integers differ
+        let options = DiffOptions {
+            coerce_numbers: true,
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &options);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes.modified[0].path().to_string(), "big");
+    }
+
+    #[test]
+    fn test_empty_as_absent() {
+        let old = json!({"a": {}, "b": [], "c": {}, "d": [1], "e": {"x": 1}});
+        let new = json!({"c": [], "d": [], "e": {}, "f": []});
+        let paths =
+            |changes: &Changes| -> Vec<String> { changes.paths().map(|p| p.to_string()).collect() };
+
+        // Empty containers are reported as whole values by default
+        let changes = diff(&old, &new);
+        assert_eq!(paths(&changes), vec!["f", "a", "b", "d[0]", "e.x", "c"]);
+        assert_eq!(
+            changes.added[0],
+            Change::Added {
+                path: "f".parse().unwrap(),
+                value: json!([])
+            }
+        );
+
+        let options = DiffOptions {
+            empty_as_absent: true,
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &options);
+        // Emptying a container still removes its contents
+        assert_eq!(paths(&changes), vec!["d[0]", "e.x"]);
+    }
+
+    #[test]
+    fn test_structure_only_ignores_values() {
+        let old = json!({"name": "a", "port": 80, "tags": ["x"], "db": {"host": "h"}, "on": true});
+        let new = json!({"name": "b", "port": "80", "tags": ["y", "z"], "db": {"user": "u"}, "on": false});
+        let options = DiffOptions {
+            structure_only: true,
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &options);
+
+        // Type changes, array length changes and key presence remain
+        let paths: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(paths, vec!["tags[1]", "db.user", "db.host", "port"]);
+        assert!(diff_with_options(&json!(1), &json!(2), &options).is_empty());
+        assert_eq!(
+            diff_with_options(&json!(1), &json!(null), &options).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_parse_embedded_json() {
+        let old = json!({"config": "{\"port\": 80, \"hosts\": [\"a\"]}", "n": "1", "s": "[1"});
+        let new =
+            json!({"config": "{\"port\": 81, \"hosts\": [\"a\", \"b\"]}", "n": "2", "s": "[2"});
+        let options = DiffOptions {
+            parse_embedded_json: true,
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &options);
+
+        let paths: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(paths, vec!["config.hosts[1]", "config.port", "n", "s"]);
+        assert_eq!(
+            changes.modified[0],
+            Change::Modified {
+                path: "config.port".parse().unwrap(),
+                old_value: json!(80),
+                new_value: json!(81),
+            }
+        );
+
+        // Without the option the string is one opaque value
+        assert_eq!(diff(&old, &new).modified[0].path().to_string(), "config");
+    }
+
+    #[test]
+    fn test_binary_values() {
+        // "hello", "hellO", "hello" unpadded and {"a": 1} / {"a": 2} in base64
+        let old =
+            json!({"blob": "aGVsbG8=", "same": "aGVsbG8=", "doc": "eyJhIjogMX0=", "text": "x"});
+        let new =
+            json!({"blob": "aGVsbE8=", "same": "aGVsbG8", "doc": "eyJhIjogMn0=", "text": "y"});
+        let options = DiffOptions {
+            binary_paths: vec!["/blob".to_string(), "/same".to_string(), "/doc".to_string()],
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &options);
+
+        let paths: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(paths, vec!["blob", "doc", "text"]);
+        assert_eq!(
+            changes.modified[0],
+            Change::Modified {
+                path: "blob".parse().unwrap(),
+                old_value: summary(b"hello"),
+                new_value: summary(b"hellO"),
+            }
+        );
+
+        let decoding = DiffOptions {
+            decode_binary_json: true,
+            ..options
+        };
+        let changes = diff_with_options(&old, &new, &decoding);
+        assert_eq!(changes.modified[1].path().to_string(), "doc.a");
+
+        // The heuristic needs no paths, and leaves short strings alone
+        let bytes = |step: u8| (0..64u8).map(|i| i.wrapping_mul(step)).collect::<Vec<_>>();
+        let blob = |step: u8| {
+            use base64::Engine;
+            base64::engine::general_purpose::STANDARD.encode(bytes(step))
+        };
+        let detecting = DiffOptions {
+            detect_binary: true,
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(
+            &json!({"a": blob(1), "b": "aGVsbG8="}),
+            &json!({"a": blob(3), "b": "aGVsbE8="}),
+            &detecting,
+        );
+        let new_values: Vec<&Value> = changes
+            .modified
+            .iter()
+            .map(|change| match change {
+                Change::Modified { new_value, .. } => new_value,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(new_values, [&summary(&bytes(3)), &json!("aGVsbE8=")]);
+    }
+
+    #[test]
+    fn test_max_changes_returns_partial_result() {
+        let old = json!({"a": 1, "b": {"c": 1, "d": 1}, "e": 1, "same": 0});
+        let new = json!({"a": 2, "b": {"c": 2, "d": 2}, "e": 2, "same": 0});
+        let options = DiffOptions {
+            max_changes: Some(2),
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &options);
+
+        let modified: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(modified, vec!["a", "b.c"]);
+        assert_eq!(
+            changes.truncated,
+            vec![Truncation {
+                path: "b.d".parse().unwrap(),
+                reason: TruncationReason::MaxChanges(2),
+            }]
+        );
+
+        // Reaching the limit exactly with nothing left to compare is not a truncation
+        let exact = DiffOptions {
+            max_changes: Some(4),
+            ..DiffOptions::default()
+        };
+        let changes = diff_with_options(&old, &new, &exact);
+        assert_eq!(changes.len(), 4);
+        assert!(!changes.is_truncated());
+    }
+
+    #[test]
+    fn test_cancelled_diff_returns_partial_result() {
+        let old = json!({"a": [1, 2], "b": 1});
+        let new = json!({"a": [1, 3], "b": 2});
+
+        let token = CancellationToken::new();
+        let options = DiffOptions::default();
+        assert_eq!(
+            diff_with_cancellation(&old, &new, &options, &token),
+            diff(&old, &new)
+        );
+
+        token.cancel();
+        let changes = diff_with_cancellation(&old, &new, &options, &token);
+        assert!(changes.is_empty());
+        assert!(changes.is_cancelled());
+        assert_eq!(
+            changes.truncated,
+            vec![Truncation {
+                path: "a".parse().unwrap(),
+                reason: TruncationReason::Cancelled,
+            }]
+        );
+
+        #[cfg(feature = "parallel")]
+        {
+            let parallel = DiffOptions {
+                parallel: true,
+                ..DiffOptions::default()
+            };
+            let changes = diff_with_cancellation(&old, &new, &parallel, &token);
+            assert!(changes.is_empty());
+            assert_eq!(changes.truncated.len(), 1);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_diff_parallel_matches_sequential() {
+        let mut old = serde_json::Map::new();
+        let mut new = serde_json::Map::new();
+        for i in 0..200 {
+            old.insert(format!("k{}", i), json!({"n": i, "tags": [i, i + 1]}));
+            if i % 7 != 0 {
+                let n = if i % 3 == 0 { i + 1 } else { i };
+                new.insert(format!("k{}", i), json!({"n": n, "tags": [i]}));
+            }
+        }
+        new.insert("extra".to_string(), json!(true));
+        let (old, new) = (Value::Object(old), Value::Object(new));
+
+        assert_eq!(diff_parallel(&old, &new), diff(&old, &new));
+        let depth_limited = DiffOptions {
+            max_depth: Some(1),
+            parallel: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            diff_with_options(&old, &new, &depth_limited),
+            diff_with_options(
+                &old,
+                &new,
+                &DiffOptions {
+                    parallel: false,
+                    ..depth_limited.clone()
+                }
+            )
+        );
+        assert_eq!(
+            diff_parallel(&json!([1]), &json!([2])),
+            diff(&json!([1]), &json!([2]))
+        );
+    }
+
     #[test]
     fn test_empty_path_handling() {
         let old = json!(1);
@@ -4,7 +4,23 @@
 //! It uses a recursive tree traversal approach to identify added, removed,
 //! and modified values between two JSON documents.
 
+mod borrowed;
+mod cancel;
+mod coerce;
+mod comparator;
 mod engine;
+mod options;
+mod pairing;
+mod serialize;
 mod visitor;
 
-pub use engine::diff;
+pub use borrowed::{diff_borrowed, diff_borrowed_with_options, ChangeRef, ChangesRef};
+pub use cancel::CancellationToken;
+pub(crate) use coerce::numbers_equal;
+pub use comparator::{Comparator, ValueComparator};
+#[cfg(feature = "parallel")]
+pub use engine::diff_parallel;
+pub use engine::{diff, diff_with_cancellation, diff_with_options};
+pub use options::{ArrayDiff, DiffOptions, SimilarityThreshold};
+pub use serialize::{describe_changes, diff_serialize};
+pub use visitor::{traverse, ValueVisitor};
@@ -0,0 +1,221 @@
+use super::comparator::Comparator;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Options controlling how two JSON values are compared
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::{diff_with_options, DiffOptions};
+/// use serde_json::json;
+///
+/// let old = json!({"config": {"db": {"host": "a", "port": 1}}});
+/// let new = json!({"config": {"db": {"host": "b", "port": 2}}});
+///
+/// // Report everything below "config" as a single modified subtree
+/// let options = DiffOptions {
+///     max_depth: Some(1),
+///     ..DiffOptions::default()
+/// };
+/// let changes = diff_with_options(&old, &new, &options);
+/// assert_eq!(changes.modified.len(), 1);
+/// assert_eq!(changes.modified[0].path().to_string(), "config");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffOptions {
+    /// Stop recursing at this depth and report deeper differences as one
+    /// change of the whole subtree (`None` means unlimited)
+    pub max_depth: Option<usize>,
+    /// Stop after recording this many changes (`None` means unlimited)
+    ///
+    /// A diff cut short by either limit lists where it stopped in
+    /// [`Changes::truncated`](crate::Changes::truncated).
+    pub max_changes: Option<usize>,
+    /// Treat object keys that differ only in case as the same key, reported
+    /// under the key used in the new document
+    pub ignore_key_case: bool,
+    /// Visit object keys in alphabetical order; by default they are visited
+    /// in document order, the old document's keys first and then the keys
+    /// only found in the new one
+    pub sort_keys: bool,
+    /// Compare strings that are ISO-8601 timestamps as instants, so
+    /// `2024-01-01T00:00:00Z` and `2024-01-01T00:00:00+00:00` are equal
+    pub normalize_dates: bool,
+    /// With `normalize_dates`, treat timestamps at most this far apart as equal
+    pub date_tolerance: Duration,
+    /// Compare numbers by value, so `1` and `1.0` are equal
+    ///
+    /// With the `arbitrary-precision` feature values are compared exactly
+    /// (`1.10` and `1.1` are equal too, while by default numbers are equal
+    /// only if spelled the same)
+    pub coerce_numbers: bool,
+    /// Also treat a string holding a JSON number as equal to that number
+    /// (`"1"` and `1`); implies `coerce_numbers`
+    pub coerce_number_strings: bool,
+    /// Treat an object member set to `null` as equal to a missing member
+    pub null_as_absent: bool,
+    /// Treat an object member holding `{}` or `[]` as equal to a missing
+    /// member, and `{}` as equal to `[]`
+    pub empty_as_absent: bool,
+    /// Compare only the shape of the documents: key presence, value types
+    /// and array lengths. Scalars of the same type are equal whatever their
+    /// values.
+    pub structure_only: bool,
+    /// How the elements of two arrays are paired for comparison
+    pub array_diff: ArrayDiff,
+    /// Pairing for the arrays at paths matching ignore-style patterns,
+    /// overriding `array_diff` and `array_sets`; the first matching pattern
+    /// applies
+    pub array_strategies: Vec<(String, ArrayDiff)>,
+    /// Compare arrays holding only scalars as sets: order and repeats are
+    /// ignored, and differing arrays are reported as one change annotated
+    /// with `addedElements`, `removedElements` and `common` (ignored with
+    /// `structure_only`)
+    pub array_sets: bool,
+    /// Diff strings that hold a JSON object or array on both sides as
+    /// documents, reporting changes below the string's path
+    pub parse_embedded_json: bool,
+    /// Ignore-style patterns of strings holding base64 blobs, reported as
+    /// their size and SHA-256 hash (see [`crate::binary`])
+    pub binary_paths: Vec<String>,
+    /// Also treat strings that look like base64 blobs as blobs
+    pub detect_binary: bool,
+    /// Diff blobs that decode to a JSON object or array on both sides as
+    /// documents, reporting changes below the blob's path
+    pub decode_binary_json: bool,
+    /// Diff top-level object keys on worker threads (requires the `parallel`
+    /// feature; without it the keys are diffed in order and `rjd --parallel`
+    /// is rejected)
+    pub parallel: bool,
+    /// Custom equality consulted before the built-in comparison of values
+    /// present in both documents (see [`ValueComparator`](crate::ValueComparator))
+    pub comparator: Option<Comparator>,
+}
+
+impl DiffOptions {
+    /// Create options with default behavior
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// How the elements of two arrays are paired for comparison
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::{diff_with_options, ArrayDiff, DiffOptions, SimilarityThreshold};
+/// use serde_json::json;
+///
+/// let old = json!([{"name": "a", "port": 80}, {"name": "b", "port": 81}]);
+/// let new = json!([{"name": "new", "port": 9}, {"name": "a", "port": 80}, {"name": "b", "port": 82}]);
+///
+/// // Positionally, every element changed
+/// assert_eq!(diff_with_options(&old, &new, &DiffOptions::default()).len(), 6);
+///
+/// let options = DiffOptions {
+///     array_diff: ArrayDiff::Similarity(SimilarityThreshold::new(0.5).unwrap()),
+///     ..DiffOptions::default()
+/// };
+/// let changes = diff_with_options(&old, &new, &options);
+/// assert_eq!(changes.added[0].path().to_string(), "[0].name");
+/// assert_eq!(changes.modified[0].path().to_string(), "[2].port");
+/// assert_eq!(changes.len(), 3);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ArrayDiff {
+    /// Compare the elements at the same index
+    #[default]
+    Positional,
+    /// Pair each element with the most similar element of the other array,
+    /// if they are at least this similar, so that elements that moved and
+    /// changed a little are still compared with each other
+    ///
+    /// Similarity runs from 0 to 1: equal scalars score 1, and objects and
+    /// arrays score the average similarity of their members (a member on one
+    /// side only scores 0). Paired elements are reported under their index in
+    /// the new array, unpaired new elements as added and unpaired old ones as
+    /// removed after its end. Arrays with more than a million element pairs
+    /// are compared by position.
+    Similarity(SimilarityThreshold),
+    /// Pair objects with the same value of this member, like the `x-rjd-key`
+    /// schema keyword; arrays whose elements are not all objects with
+    /// distinct values of the member are compared by position
+    Keyed(String),
+    /// Pair equal elements wherever they are, so a change of order is not
+    /// a difference; elements without an equal partner are added or removed
+    Unordered,
+}
+
+impl fmt::Display for ArrayDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArrayDiff::Positional => write!(f, "positional"),
+            ArrayDiff::Similarity(threshold) => write!(f, "similarity:{}", threshold.get()),
+            ArrayDiff::Keyed(key) => write!(f, "keyed:{}", key),
+            ArrayDiff::Unordered => write!(f, "unordered"),
+        }
+    }
+}
+
+impl FromStr for ArrayDiff {
+    type Err = String;
+
+    /// Parse `positional`, `unordered`, `similarity` (with the default
+    /// threshold), `similarity:THRESHOLD` or `keyed:KEY`
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (name, argument) = match text.split_once(':') {
+            Some((name, argument)) => (name, Some(argument)),
+            None => (text, None),
+        };
+        match (name, argument) {
+            ("positional", None) => Ok(ArrayDiff::Positional),
+            ("unordered", None) => Ok(ArrayDiff::Unordered),
+            ("similarity", None) => Ok(ArrayDiff::Similarity(SimilarityThreshold::default())),
+            ("similarity", Some(threshold)) => threshold
+                .parse()
+                .ok()
+                .and_then(SimilarityThreshold::new)
+                .map(ArrayDiff::Similarity)
+                .ok_or_else(|| {
+                    format!(
+                        "invalid similarity threshold '{}' (expected a number between 0 and 1)",
+                        threshold
+                    )
+                }),
+            ("keyed", Some(key)) if !key.is_empty() => Ok(ArrayDiff::Keyed(key.to_string())),
+            _ => Err(format!(
+                "unknown array strategy '{}' (expected positional, unordered, similarity[:THRESHOLD] or keyed:KEY)",
+                text
+            )),
+        }
+    }
+}
+
+/// The least similarity, between 0 and 1, of two paired array elements
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct SimilarityThreshold(f64);
+
+impl SimilarityThreshold {
+    /// A threshold of `value`, or `None` unless it is between 0 and 1
+    pub fn new(value: f64) -> Option<Self> {
+        (0.0..=1.0).contains(&value).then_some(Self(value))
+    }
+
+    /// The threshold as a number between 0 and 1
+    pub fn get(self) -> f64 {
+        self.0
+    }
+}
+
+// The value is never NaN, so equality is total
+impl Eq for SimilarityThreshold {}
+
+impl Default for SimilarityThreshold {
+    /// 0.7
+    fn default() -> Self {
+        Self(0.7)
+    }
+}
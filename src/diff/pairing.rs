@@ -0,0 +1,194 @@
+//! Pairing of array elements for the [`ArrayDiff`](crate::ArrayDiff) strategies
+//!
+//! Each function returns, for each element of the new array, the index of the
+//! element of the old array it is compared with.
+
+use crate::canonical::canonicalize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Arrays whose element pairs exceed this count are compared by position
+pub(crate) const MAX_SIMILARITY_PAIRS: usize = 1_000_000;
+
+/// Structural similarity of two values, from 0 (unrelated) to 1 (equal)
+///
+/// Scalars score 1 if equal and 0 otherwise, and values of different types
+/// 0. Objects average the similarity of their members over all keys of
+/// both (a key on one side only scores 0), arrays that of their elements by
+/// position over the longer array. Two empty objects or arrays score 1.
+pub(crate) fn similarity(old: &Value, new: &Value) -> f64 {
+    match (old, new) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            let keys = old_map.len() + new_map.keys().filter(|k| !old_map.contains_key(*k)).count();
+            if keys == 0 {
+                return 1.0;
+            }
+            let total: f64 = old_map
+                .iter()
+                .filter_map(|(key, old)| new_map.get(key).map(|new| similarity(old, new)))
+                .sum();
+            total / keys as f64
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            let len = old_items.len().max(new_items.len());
+            if len == 0 {
+                return 1.0;
+            }
+            let total: f64 = old_items
+                .iter()
+                .zip(new_items)
+                .map(|(old, new)| similarity(old, new))
+                .sum();
+            total / len as f64
+        }
+        _ if old == new => 1.0,
+        _ => 0.0,
+    }
+}
+
+/// For each element of `new`, the index of the element of `old` it is paired with
+///
+/// Pairs scoring at least `threshold` are taken best first; among equally
+/// similar pairs, the elements that moved least are paired first. Each
+/// element is paired at most once.
+pub(crate) fn pair_by_similarity(
+    old: &[Value],
+    new: &[Value],
+    threshold: f64,
+) -> Vec<Option<usize>> {
+    let mut candidates = Vec::new();
+    for (i, old_item) in old.iter().enumerate() {
+        for (j, new_item) in new.iter().enumerate() {
+            let score = similarity(old_item, new_item);
+            if score >= threshold {
+                candidates.push((score, i.abs_diff(j), i, j));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+    let mut taken = vec![false; old.len()];
+    let mut pairs = vec![None; new.len()];
+    for (_, _, i, j) in candidates {
+        if !taken[i] && pairs[j].is_none() {
+            taken[i] = true;
+            pairs[j] = Some(i);
+        }
+    }
+    pairs
+}
+
+/// Pair objects with the same value of `key`, or `None` unless every element
+/// of both arrays is an object with a distinct value of `key`
+pub(crate) fn pair_by_key(old: &[Value], new: &[Value], key: &str) -> Option<Vec<Option<usize>>> {
+    let keys = |items: &[Value]| -> Option<Vec<String>> {
+        let mut seen = HashSet::new();
+        items
+            .iter()
+            .map(|item| {
+                let value = item.as_object()?.get(key)?.to_string();
+                seen.insert(value.clone()).then_some(value)
+            })
+            .collect()
+    };
+    let old_index: HashMap<String, usize> = keys(old)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| (value, i))
+        .collect();
+    let pairs = keys(new)?
+        .iter()
+        .map(|value| old_index.get(value).copied())
+        .collect();
+    Some(pairs)
+}
+
+/// Pair equal elements wherever they are, repeated elements in order
+///
+/// Objects are equal whatever the order of their keys, as in the diff.
+pub(crate) fn pair_equal(old: &[Value], new: &[Value]) -> Vec<Option<usize>> {
+    let text = |item: &Value| canonicalize(item).to_string();
+    let mut old_index: HashMap<String, VecDeque<usize>> = HashMap::new();
+    for (i, item) in old.iter().enumerate() {
+        old_index.entry(text(item)).or_default().push_back(i);
+    }
+    new.iter()
+        .map(|item| {
+            let candidates = old_index.get_mut(&text(item))?;
+            // Numbers of one value but different spellings share a bucket
+            let position = candidates.iter().position(|&i| old[i] == *item)?;
+            candidates.remove(position)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity(&json!(1), &json!(1)), 1.0);
+        assert_eq!(similarity(&json!(1), &json!("1")), 0.0);
+        assert_eq!(similarity(&json!({}), &json!({})), 1.0);
+        assert_eq!(
+            similarity(&json!({"a": 1, "b": 2}), &json!({"a": 1, "b": 3})),
+            0.5
+        );
+        assert_eq!(similarity(&json!({"a": 1}), &json!({"a": 1, "b": 2})), 0.5);
+        assert_eq!(
+            similarity(&json!({"a": [1, 2], "b": 1}), &json!({"a": [1, 3], "b": 1})),
+            0.75
+        );
+    }
+
+    #[test]
+    fn test_pair_by_similarity() {
+        let old = [
+            json!({"id": 1, "name": "a", "port": 80}),
+            json!({"id": 2, "name": "b", "port": 81}),
+        ];
+        let new = [
+            json!({"id": 3, "name": "c", "port": 82}),
+            json!({"id": 2, "name": "b", "port": 91}),
+            json!({"id": 1, "name": "a", "port": 80}),
+        ];
+        // The changed service scores 2/3
+        assert_eq!(
+            pair_by_similarity(&old, &new, 0.6),
+            vec![None, Some(1), Some(0)]
+        );
+        assert_eq!(
+            pair_by_similarity(&old, &new, 0.7),
+            vec![None, None, Some(0)]
+        );
+
+        assert_eq!(
+            pair_by_key(&old, &new, "id"),
+            Some(vec![None, Some(1), Some(0)])
+        );
+        assert_eq!(pair_by_key(&old, &new, "missing"), None);
+        assert_eq!(pair_by_key(&old, &[json!(1)], "id"), None);
+        assert_eq!(
+            pair_by_key(&old, &[old[0].clone(), old[0].clone()], "id"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_pair_equal() {
+        let old = [json!("a"), json!({"x": 1, "y": 2}), json!("a"), json!(1.0)];
+        let new = [
+            json!({"y": 2, "x": 1}),
+            json!("a"),
+            json!("b"),
+            json!("a"),
+            json!(1),
+        ];
+        assert_eq!(
+            pair_equal(&old, &new),
+            vec![Some(1), Some(0), None, Some(2), None]
+        );
+    }
+}
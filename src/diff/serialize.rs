@@ -0,0 +1,162 @@
+//! Diffing any [`Serialize`] value
+
+use crate::diff::engine::diff;
+use crate::error::RjdError;
+use crate::types::{Change, Changes};
+use serde::Serialize;
+
+/// Compare two serializable values by their JSON form
+///
+/// Both values are serialized with `serde_json` first, so domain structs can
+/// be diffed (in tests, say) without calling `serde_json::to_value` by hand.
+/// They need not have the same type.
+///
+/// # Errors
+///
+/// Returns [`RjdError::Serialize`] if either value cannot be represented as
+/// JSON, e.g. a map with non-string keys.
+///
+/// # Examples
+///
+/// ```
+/// use rjd::diff_serialize;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// let old = Server { host: "a".into(), port: 80 };
+/// let new = Server { host: "a".into(), port: 8080 };
+/// let changes = diff_serialize(&old, &new).unwrap();
+/// assert_eq!(changes.modified[0].path().to_string(), "port");
+/// ```
+pub fn diff_serialize<T, U>(old: &T, new: &U) -> Result<Changes, RjdError>
+where
+    T: Serialize + ?Sized,
+    U: Serialize + ?Sized,
+{
+    let old = serde_json::to_value(old).map_err(|source| RjdError::Serialize { source })?;
+    let new = serde_json::to_value(new).map_err(|source| RjdError::Serialize { source })?;
+    Ok(diff(&old, &new))
+}
+
+/// One line per change, as the assertion macros print them
+#[doc(hidden)]
+pub fn describe_changes(changes: &Changes) -> String {
+    changes
+        .iter()
+        .map(|change| match change {
+            Change::Added { path, value } => format!("  added    {}: {}", path, value),
+            Change::Removed { path, value } => format!("  removed  {}: {}", path, value),
+            Change::Modified {
+                path,
+                old_value,
+                new_value,
+            } => format!("  modified {}: {} -> {}", path, old_value, new_value),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Assert that two serializable values have the same JSON form
+///
+/// On failure the panic message lists every change from the left value to
+/// the right one. The values are passed by reference to
+/// [`diff_serialize`](crate::diff_serialize), so they need not be the same
+/// type. An optional format string and arguments are added to the message,
+/// as with [`assert_eq!`].
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+///
+/// #[derive(serde::Serialize)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// rjd::assert_json_eq!(Point { x: 1, y: 2 }, json!({"y": 2, "x": 1}));
+/// ```
+///
+/// ```should_panic
+/// // panics with "JSON values differ:\n  modified x: 1 -> 2"
+/// rjd::assert_json_eq!(serde_json::json!({"x": 1}), serde_json::json!({"x": 2}));
+/// ```
+#[macro_export]
+macro_rules! assert_json_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_json_eq!($left, $right, "")
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match $crate::diff_serialize(&$left, &$right) {
+            Ok(changes) => {
+                if !changes.is_empty() {
+                    let context = format!($($arg)+);
+                    panic!(
+                        "assertion failed: JSON values differ{}{}:\n{}",
+                        if context.is_empty() { "" } else { ": " },
+                        context,
+                        $crate::describe_changes(&changes)
+                    );
+                }
+            }
+            Err(error) => panic!("assertion failed: {}", error),
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Config {
+        name: &'static str,
+        replicas: u32,
+        tags: Vec<&'static str>,
+    }
+
+    #[test]
+    fn test_diff_serialize() {
+        let old = Config {
+            name: "api",
+            replicas: 2,
+            tags: vec!["a"],
+        };
+        let new = json!({"name": "api", "replicas": 3, "tags": ["a", "b"]});
+        let changes = diff_serialize(&old, &new).unwrap();
+        assert_eq!(
+            describe_changes(&changes),
+            "  added    tags[1]: \"b\"\n  modified replicas: 2 -> 3"
+        );
+
+        let tuple_keys = BTreeMap::from([((1, 2), "x")]);
+        assert!(matches!(
+            diff_serialize(&tuple_keys, &json!({})),
+            Err(RjdError::Serialize { .. })
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "JSON values differ: rollout 3:\n  removed  tags[0]: \"a\"")]
+    fn test_assert_json_eq_reports_changes() {
+        let old = Config {
+            name: "api",
+            replicas: 2,
+            tags: vec!["a"],
+        };
+        crate::assert_json_eq!(
+            old,
+            json!({"name": "api", "replicas": 2, "tags": []}),
+            "rollout {}",
+            3
+        );
+    }
+}
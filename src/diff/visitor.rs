@@ -1,11 +1,81 @@
+//! Paired traversal of two JSON values
+//!
+//! [`traverse`] dispatches a pair of values (either of which may be absent)
+//! to the [`ValueVisitor`] method for their type. The diff engine and
+//! [`profile`](crate::profile) are both built on it, and custom analyses can
+//! be too.
+
 use crate::json_path::JsonPath;
-use serde_json::Value;
+use serde_json::{Map, Value};
 
 /// Visitor trait for traversing JSON values
 ///
-/// This trait allows for flexible traversal of JSON structures.
-/// Different implementations can perform different operations during traversal.
+/// [`traverse`] calls one method per pair of values: with only `new_value`
+/// for an added value, only `old_value` for a removed one, both for two
+/// different values of the same type, [`visit_modified`](Self::visit_modified)
+/// for values of different types and [`visit_equal`](Self::visit_equal) for
+/// equal values. Visitors descend into arrays and objects themselves, by
+/// calling [`traverse`] on the children they care about with a child path
+/// ([`JsonPath::push`]).
+///
+/// # Examples
+///
+/// Count the strings added anywhere in a document:
+///
+/// ```rust
+/// use rjd::{traverse, JsonPath, PathSegment, ValueVisitor};
+/// use serde_json::{json, Map, Value};
+///
+/// #[derive(Default)]
+/// struct AddedStrings(Vec<String>);
+///
+/// impl ValueVisitor for AddedStrings {
+///     type Output = ();
+///
+///     fn visit_null(&mut self, _: &JsonPath, _: Option<&Value>, _: Option<&Value>) {}
+///     fn visit_bool(&mut self, _: &JsonPath, _: Option<&bool>, _: Option<&bool>) {}
+///     fn visit_number(&mut self, _: &JsonPath, _: Option<&Value>, _: Option<&Value>) {}
+///
+///     fn visit_string(&mut self, path: &JsonPath, old: Option<&String>, new: Option<&String>) {
+///         if old.is_none() && new.is_some() {
+///             self.0.push(path.to_string());
+///         }
+///     }
+///
+///     fn visit_array(&mut self, path: &JsonPath, old: Option<&Vec<Value>>, new: Option<&Vec<Value>>) {
+///         let old = old.map(Vec::as_slice).unwrap_or_default();
+///         let new = new.map(Vec::as_slice).unwrap_or_default();
+///         for i in 0..old.len().max(new.len()) {
+///             let mut child = path.clone();
+///             child.push(PathSegment::Index(i));
+///             traverse(old.get(i), new.get(i), &child, self);
+///         }
+///     }
+///
+///     fn visit_object(
+///         &mut self,
+///         path: &JsonPath,
+///         old: Option<&Map<String, Value>>,
+///         new: Option<&Map<String, Value>>,
+///     ) {
+///         let empty = Map::new();
+///         let (old, new) = (old.unwrap_or(&empty), new.unwrap_or(&empty));
+///         for key in old.keys().chain(new.keys().filter(|key| !old.contains_key(*key))) {
+///             let mut child = path.clone();
+///             child.push(PathSegment::Key(key.clone()));
+///             traverse(old.get(key), new.get(key), &child, self);
+///         }
+///     }
+/// }
+///
+/// let old = json!({"name": "ann", "tags": ["a"]});
+/// let new = json!({"name": "ann", "tags": ["a", "b"], "nick": "an"});
+/// let mut visitor = AddedStrings::default();
+/// traverse(Some(&old), Some(&new), &JsonPath::new(), &mut visitor);
+/// assert_eq!(visitor.0, ["tags[1]", "nick"]);
+/// ```
 pub trait ValueVisitor {
+    /// What each visit returns; absent pairs return the default
     type Output: Default;
 
     /// Visit a null value
@@ -52,21 +122,37 @@ pub trait ValueVisitor {
     fn visit_object(
         &mut self,
         path: &JsonPath,
-        old_value: Option<&serde_json::Map<String, Value>>,
-        new_value: Option<&serde_json::Map<String, Value>>,
+        old_value: Option<&Map<String, Value>>,
+        new_value: Option<&Map<String, Value>>,
     ) -> Self::Output;
 
+    /// Called when the two values have different types
+    ///
+    /// The default implementation does nothing.
+    #[allow(unused)]
+    fn visit_modified(
+        &mut self,
+        path: &JsonPath,
+        old_value: &Value,
+        new_value: &Value,
+    ) -> Self::Output {
+        Self::Output::default()
+    }
+
     /// Called when both values are the same (no change)
     ///
     /// Override this method if you need to track equal values.
     /// The default implementation does nothing.
     #[allow(unused)]
-    fn visit_equal(&mut self, _path: &JsonPath, _value: &Value) -> Self::Output {
+    fn visit_equal(&mut self, path: &JsonPath, value: &Value) -> Self::Output {
         Self::Output::default()
     }
 }
 
 /// Traverse two JSON values and call the appropriate visitor methods
+///
+/// Only the values themselves are visited; see [`ValueVisitor`] for how
+/// visitors reach nested values.
 pub fn traverse<V>(
     old: Option<&Value>,
     new: Option<&Value>,
@@ -74,7 +160,7 @@ pub fn traverse<V>(
     visitor: &mut V,
 ) -> V::Output
 where
-    V: ValueVisitor + ValueVisitorExt,
+    V: ValueVisitor + ?Sized,
 {
     match (old, new) {
         (None, Some(new)) => {
@@ -107,65 +193,140 @@ where
                 // Values are different - check types
                 match (old, new) {
                     (Value::Null, Value::Null) => visitor.visit_null(path, Some(old), Some(new)),
-                    (Value::Bool(_), Value::Bool(_)) => {
-                        visitor.visit_bool(path, old.as_bool().as_ref(), new.as_bool().as_ref())
-                    }
+                    (Value::Bool(a), Value::Bool(b)) => visitor.visit_bool(path, Some(a), Some(b)),
                     (Value::Number(_), Value::Number(_)) => {
                         visitor.visit_number(path, Some(old), Some(new))
                     }
-                    (Value::String(_), Value::String(_)) => visitor.visit_string(
-                        path,
-                        old.as_str().map(|s| s.to_string()).as_ref(),
-                        new.as_str().map(|s| s.to_string()).as_ref(),
-                    ),
-                    (Value::Array(_), Value::Array(_)) => {
-                        visitor.visit_array(path, old.as_array(), new.as_array())
+                    (Value::String(a), Value::String(b)) => {
+                        visitor.visit_string(path, Some(a), Some(b))
                     }
-                    (Value::Object(_), Value::Object(_)) => {
-                        visitor.visit_object(path, old.as_object(), new.as_object())
+                    (Value::Array(a), Value::Array(b)) => {
+                        visitor.visit_array(path, Some(a), Some(b))
                     }
-                    (_, _) => {
-                        // Type mismatch - treat as modification
-                        visitor.visit_modified(path, Some(old), Some(new))
+                    (Value::Object(a), Value::Object(b)) => {
+                        visitor.visit_object(path, Some(a), Some(b))
                     }
+                    // Type mismatch
+                    (_, _) => visitor.visit_modified(path, old, new),
                 }
             }
         }
-        (None, None) => {
-            // Both are None - this shouldn't happen in normal traversal
-            // but we handle it gracefully by returning default output
-            <V as ValueVisitor>::Output::default()
-        }
+        (None, None) => V::Output::default(),
     }
 }
 
-/// Extends the ValueVisitor trait with additional methods
-pub trait ValueVisitorExt: ValueVisitor {
-    /// Visit a modified value (type changed or value changed)
-    fn visit_modified(
-        &mut self,
-        path: &JsonPath,
-        old_value: Option<&Value>,
-        new_value: Option<&Value>,
-    ) -> Self::Output
-    where
-        Self: Sized,
-    {
-        // Default implementation treats modified as a change
-        match (old_value, new_value) {
-            (Some(old), Some(new)) => traverse(Some(old), Some(new), path, self),
-            (Some(old), None) => {
-                // This shouldn't happen for modified values
-                self.visit_object(path, old.as_object(), None)
-            }
-            (None, Some(new)) => {
-                // This shouldn't happen for modified values
-                self.visit_object(path, None, new.as_object())
-            }
-            (None, None) => {
-                // Both are None - nothing to do
-                Self::Output::default()
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Records which method each pair was dispatched to
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl Recorder {
+        fn record<T>(&mut self, kind: &str, path: &JsonPath, old: Option<T>, new: Option<T>) {
+            let side = match (old.is_some(), new.is_some()) {
+                (false, true) => "added",
+                (true, false) => "removed",
+                _ => "changed",
+            };
+            self.0.push(format!("{} {} {}", kind, side, path));
+        }
+    }
+
+    impl ValueVisitor for Recorder {
+        type Output = usize;
+
+        fn visit_null(
+            &mut self,
+            path: &JsonPath,
+            old: Option<&Value>,
+            new: Option<&Value>,
+        ) -> usize {
+            self.record("null", path, old, new);
+            1
+        }
+
+        fn visit_bool(&mut self, path: &JsonPath, old: Option<&bool>, new: Option<&bool>) -> usize {
+            self.record("bool", path, old, new);
+            1
         }
+
+        fn visit_number(
+            &mut self,
+            path: &JsonPath,
+            old: Option<&Value>,
+            new: Option<&Value>,
+        ) -> usize {
+            self.record("number", path, old, new);
+            1
+        }
+
+        fn visit_string(
+            &mut self,
+            path: &JsonPath,
+            old: Option<&String>,
+            new: Option<&String>,
+        ) -> usize {
+            self.record("string", path, old, new);
+            1
+        }
+
+        fn visit_array(
+            &mut self,
+            path: &JsonPath,
+            old: Option<&Vec<Value>>,
+            new: Option<&Vec<Value>>,
+        ) -> usize {
+            self.record("array", path, old, new);
+            1
+        }
+
+        fn visit_object(
+            &mut self,
+            path: &JsonPath,
+            old: Option<&Map<String, Value>>,
+            new: Option<&Map<String, Value>>,
+        ) -> usize {
+            self.record("object", path, old, new);
+            1
+        }
+    }
+
+    #[test]
+    fn test_traverse_dispatch() {
+        let path: JsonPath = "a".parse().unwrap();
+        let mut visitor = Recorder::default();
+        let pairs = [
+            (Some(json!(null)), None),
+            (None, Some(json!(true))),
+            (Some(json!(1)), Some(json!(2))),
+            (Some(json!("x")), Some(json!("y"))),
+            (Some(json!([1])), Some(json!([2]))),
+            (Some(json!({"k": 1})), None),
+        ];
+        for (old, new) in &pairs {
+            assert_eq!(traverse(old.as_ref(), new.as_ref(), &path, &mut visitor), 1);
+        }
+        assert_eq!(
+            visitor.0,
+            [
+                "null removed a",
+                "bool added a",
+                "number changed a",
+                "string changed a",
+                "array changed a",
+                "object removed a"
+            ]
+        );
+
+        // Type changes, equal values and absent pairs use the defaults
+        visitor.0.clear();
+        let (one, text) = (json!(1), json!("1"));
+        assert_eq!(traverse(Some(&one), Some(&text), &path, &mut visitor), 0);
+        assert_eq!(traverse(Some(&one), Some(&one), &path, &mut visitor), 0);
+        assert_eq!(traverse(None, None, &path, &mut visitor), 0);
+        assert!(visitor.0.is_empty());
     }
 }
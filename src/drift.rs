@@ -0,0 +1,85 @@
+//! Measuring how much of a document changed
+
+use crate::types::Changes;
+use serde_json::Value;
+
+/// How much of a document changed: changed leaves against all leaves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Drift {
+    /// Number of changed leaves
+    pub changed: usize,
+    /// Number of leaves in either document
+    pub total: usize,
+}
+
+impl Drift {
+    /// Changed leaves as a percentage of all leaves (0 for two empty documents)
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        (self.changed as f64 / self.total as f64 * 100.0).min(100.0)
+    }
+}
+
+impl Changes {
+    /// Measure the drift between `old` and the document these changes lead to
+    ///
+    /// Leaves are scalars and empty containers. The total counts the leaves of
+    /// `old` plus the added ones, i.e. the leaves present in either document;
+    /// each change counts as one changed leaf.
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::diff;
+    /// use serde_json::json;
+    ///
+    /// let old = json!({"a": 1, "b": 2, "c": 3, "d": 4});
+    /// let new = json!({"a": 1, "b": 2, "c": 3, "d": 5, "e": 6});
+    /// let drift = diff(&old, &new).drift(&old);
+    /// assert_eq!((drift.changed, drift.total), (2, 5));
+    /// assert_eq!(drift.percent(), 40.0);
+    /// ```
+    pub fn drift(&self, old: &Value) -> Drift {
+        Drift {
+            changed: self.len(),
+            total: count_leaves(old) + self.added.len(),
+        }
+    }
+}
+
+fn count_leaves(value: &Value) -> usize {
+    match value {
+        Value::Array(items) if !items.is_empty() => items.iter().map(count_leaves).sum(),
+        Value::Object(map) if !map.is_empty() => map.values().map(count_leaves).sum(),
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    #[test]
+    fn test_count_leaves() {
+        assert_eq!(count_leaves(&json!(1)), 1);
+        assert_eq!(count_leaves(&json!({})), 1);
+        assert_eq!(count_leaves(&json!({"a": [1, 2, {}], "b": {"c": null}})), 4);
+    }
+
+    #[test]
+    fn test_drift() {
+        let old = json!({"a": [1, 2, 3], "b": "x"});
+        assert_eq!(diff(&old, &old).drift(&old).percent(), 0.0);
+
+        let new = json!({"a": [1, 2], "c": true});
+        let drift = diff(&old, &new).drift(&old);
+        assert_eq!((drift.changed, drift.total), (3, 5));
+        assert_eq!(drift.percent(), 60.0);
+
+        let empty = json!({});
+        assert_eq!(diff(&empty, &empty).drift(&empty).total, 1);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::decode::InputFormat;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -23,6 +24,15 @@ pub enum RjdError {
         limit: u64,
     },
 
+    #[error(
+        "Memory budget exceeded: about {needed} bytes needed for {what} (budget: {limit} bytes)"
+    )]
+    MemoryBudgetExceeded {
+        what: String,
+        needed: u64,
+        limit: u64,
+    },
+
     #[error("JSON depth exceeded: depth {depth} exceeds limit {limit}")]
     JsonDepthExceeded { depth: usize, limit: usize },
 
@@ -38,14 +48,69 @@ pub enum RjdError {
     #[error("Invalid input: {input}")]
     InvalidInput { input: String },
 
+    #[error("Failed to decode {format} from {source_name}: {message}")]
+    Decode {
+        format: InputFormat,
+        source_name: String,
+        message: String,
+    },
+
+    #[error("Duplicate object keys at: {}", paths.join(", "))]
+    DuplicateKeys { paths: Vec<String> },
+
+    #[error("No embedded JSON document #{nth} found in {source_name}")]
+    EmbeddedJsonNotFound { nth: usize, source_name: String },
+
+    #[error("Failed to resolve $ref '{reference}': {message}")]
+    RefResolution { reference: String, message: String },
+
+    #[error("$ref '{reference}' points outside {root}")]
+    RefOutsideRoot { reference: String, root: PathBuf },
+
     #[error("Invalid arguments: {message}")]
     InvalidArgs { message: String },
 
+    #[error("Invalid config file {path}: {message}")]
+    Config { path: PathBuf, message: String },
+
     #[error("Internal error: {message}")]
     Internal { message: String },
 
     #[error("Formatter error: {message}")]
     Formatter { message: String },
+
+    #[error("Failed to write output to {destination}: {message}")]
+    Output {
+        destination: String,
+        message: String,
+    },
+
+    #[error("Policy check failed:\n{message}")]
+    PolicyViolation { message: String },
+
+    #[error("Failed to serialize value as JSON: {source}")]
+    Serialize { source: serde_json::Error },
+
+    #[error("Diff timed out after {limit:?}")]
+    Timeout { limit: std::time::Duration },
+
+    #[error("Patch verification failed: {message}")]
+    PatchVerification { message: String },
+}
+
+impl RjdError {
+    /// Process exit code the CLI uses for this error
+    ///
+    /// Policy failures (the diff ran but violated a check) exit with 2 so that
+    /// CI scripts can tell them apart from operational errors, which exit with 1.
+    /// A diff abandoned at its time limit exits with 3.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RjdError::PolicyViolation { .. } => 2,
+            RjdError::Timeout { .. } => 3,
+            _ => 1,
+        }
+    }
 }
 
 /// Formatter-specific errors
@@ -53,6 +118,46 @@ pub enum RjdError {
 pub enum FormatterError {
     #[error("Unknown format '{format}'. Valid formats are: {valid}")]
     UnknownFormat { format: String, valid: String },
+
+    #[error("Unsupported format version {version}. Supported versions are: {supported}")]
+    UnsupportedFormatVersion { version: u32, supported: String },
+
+    #[error("Format '{format}' is not JSON and has no JSON Schema")]
+    NoSchema { format: String },
+
+    #[error("The template format needs a template (--template FILE)")]
+    MissingTemplate,
+
+    #[error("Invalid template: {message}")]
+    InvalidTemplate { message: String },
+}
+
+/// Errors raised while a [`Formatter`](crate::formatter::Formatter) renders changes
+#[derive(Debug, Error)]
+pub enum FormatError {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Fmt(#[from] std::fmt::Error),
+
+    /// The changes cannot be rendered in this format
+    #[error("{message}")]
+    Unsupported { message: String },
+
+    /// Error raised by a custom formatter
+    #[error(transparent)]
+    Custom(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl FormatError {
+    /// Check whether the error was caused by the reader closing the pipe
+    pub fn is_broken_pipe(&self) -> bool {
+        matches!(self, FormatError::Io(e) if e.kind() == std::io::ErrorKind::BrokenPipe)
+    }
 }
 
 #[cfg(test)]
@@ -82,6 +187,20 @@ mod tests {
         assert!(msg.contains("after"));
         assert!(msg.contains("rfc6902"));
     }
+
+    #[test]
+    fn test_exit_codes() {
+        let policy = RjdError::PolicyViolation {
+            message: "outside".to_string(),
+        };
+        assert_eq!(policy.exit_code(), 2);
+        let timeout = RjdError::Timeout {
+            limit: std::time::Duration::from_secs(30),
+        };
+        assert_eq!(timeout.exit_code(), 3);
+        assert_eq!(timeout.to_string(), "Diff timed out after 30s");
+        assert_eq!(RjdError::MissingFile2.exit_code(), 1);
+    }
 }
 
 // Note: From implementations for IO/JSON errors are intentionally omitted.
@@ -0,0 +1,119 @@
+//! Extraction of JSON documents embedded in surrounding text
+//!
+//! Log lines, HTML `<script>` tags and similar wrappers often carry a JSON
+//! document in the middle of other text. These helpers scan the text for
+//! JSON objects and arrays and return the requested one.
+
+use serde_json::Value;
+use std::ops::Range;
+
+use crate::error::RjdError;
+use crate::loader::{check_json_depth, LoadConfig};
+
+/// Find the `nth` (1-based) JSON object or array embedded in `text`
+///
+/// Scanning starts at every `{` or `[` and skips over each document once it
+/// has been found, so nested values are never counted separately.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::extract_json;
+/// use serde_json::json;
+///
+/// let log = r#"INFO payload={"id": 1} then {"id": 2} done"#;
+/// assert_eq!(extract_json(log, 1), Some(json!({"id": 1})));
+/// assert_eq!(extract_json(log, 2), Some(json!({"id": 2})));
+/// assert_eq!(extract_json(log, 3), None);
+/// ```
+pub fn extract_json(text: &str, nth: usize) -> Option<Value> {
+    extract_json_span(text, nth).map(|(_, value)| value)
+}
+
+/// Find the `nth` (1-based) embedded JSON document and its byte range in `text`
+pub fn extract_json_span(text: &str, nth: usize) -> Option<(Range<usize>, Value)> {
+    if nth == 0 {
+        return None;
+    }
+
+    let mut pos = 0;
+    let mut found = 0;
+    while let Some(offset) = text[pos..].find(['{', '[']) {
+        let start = pos + offset;
+        let mut stream = serde_json::Deserializer::from_str(&text[start..]).into_iter::<Value>();
+        match stream.next() {
+            Some(Ok(value)) if value.is_object() || value.is_array() => {
+                found += 1;
+                let end = start + stream.byte_offset();
+                if found == nth {
+                    return Some((start..end, value));
+                }
+                pos = end;
+            }
+            // Not a JSON document at this position, e.g. "[INFO]"
+            _ => pos = start + 1,
+        }
+    }
+
+    None
+}
+
+/// Find the `nth` embedded JSON document, applying the depth limit
+///
+/// `source_name` is used in the error message when no document is found.
+pub fn extract_json_with_config(
+    text: &str,
+    nth: usize,
+    source_name: &str,
+    config: &LoadConfig,
+) -> Result<Value, RjdError> {
+    let value = extract_json(text, nth).ok_or_else(|| RjdError::EmbeddedJsonNotFound {
+        nth,
+        source_name: source_name.to_string(),
+    })?;
+
+    check_json_depth(&value, config.max_json_depth).map_err(|depth| {
+        RjdError::JsonDepthExceeded {
+            depth,
+            limit: config.max_json_depth,
+        }
+    })?;
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_extract_from_html_script_tag() {
+        let html = r#"<html><script type="application/json">{"config": [1, 2]}</script></html>"#;
+        assert_eq!(extract_json(html, 1), Some(json!({"config": [1, 2]})));
+
+        let (span, _) = extract_json_span(html, 1).unwrap();
+        assert_eq!(&html[span], r#"{"config": [1, 2]}"#);
+    }
+
+    #[test]
+    fn test_extract_skips_non_json_brackets() {
+        let log = "[INFO] 2024-01-01 started\n[WARN] payload: [1, {\"a\": true}]";
+        assert_eq!(extract_json(log, 1), Some(json!([1, {"a": true}])));
+        assert_eq!(extract_json(log, 2), None);
+    }
+
+    #[test]
+    fn test_extract_with_config_reports_missing_document() {
+        let config = LoadConfig::default();
+        let result = extract_json_with_config("no json here", 1, "stdin", &config);
+        assert!(matches!(
+            result,
+            Err(RjdError::EmbeddedJsonNotFound { nth: 1, .. })
+        ));
+
+        let config = LoadConfig::with_limits(1024, 1);
+        let result = extract_json_with_config(r#"x {"a": {"b": 1}} y"#, 1, "stdin", &config);
+        assert!(matches!(result, Err(RjdError::JsonDepthExceeded { .. })));
+    }
+}
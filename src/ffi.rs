@@ -0,0 +1,290 @@
+//! C interface to the diff engine (`ffi` feature)
+//!
+//! Built as a `cdylib`, the crate exports a small C ABI so applications in
+//! other languages (Python through ctypes, C++, Swift) can diff documents
+//! without spawning the CLI. `include/rjd.h` declares the functions:
+//!
+//! - [`rjd_diff`] diffs two JSON documents and returns the formatted report
+//! - [`rjd_string_free`] frees a string returned by [`rjd_diff`]
+//! - [`rjd_last_error_code`] and [`rjd_last_error_message`] explain a failure
+//! - [`rjd_options_schema`] returns the JSON Schema of the options document
+//!
+//! All strings are NUL-terminated UTF-8. Errors are kept per thread, so each
+//! thread can check the outcome of its own calls. A panic never unwinds into
+//! the caller: it is caught at the boundary and reported as
+//! [`RJD_ERR_PANIC`] (or a NULL result).
+
+use crate::bindings::{diff_report, parse_document, parse_options, BindingError, OPTIONS_SCHEMA};
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::OnceLock;
+
+/// The last call succeeded
+pub const RJD_OK: c_int = 0;
+/// A required pointer argument was NULL
+pub const RJD_ERR_NULL_ARGUMENT: c_int = 1;
+/// An argument was not valid UTF-8
+pub const RJD_ERR_INVALID_UTF8: c_int = 2;
+/// The old or new document was not valid JSON
+pub const RJD_ERR_INVALID_JSON: c_int = 3;
+/// The options document was not valid JSON or did not match the schema
+pub const RJD_ERR_INVALID_OPTIONS: c_int = 4;
+/// The report could not be formatted (or held a NUL byte)
+pub const RJD_ERR_FORMAT: c_int = 5;
+/// The library panicked; the panic was caught before reaching the caller
+pub const RJD_ERR_PANIC: c_int = 6;
+
+/// [`OPTIONS_SCHEMA`] as a C string, built on first use
+static OPTIONS_SCHEMA_C: OnceLock<CString> = OnceLock::new();
+
+/// A failed call: its error code and message
+struct FfiError {
+    code: c_int,
+    message: String,
+}
+
+impl FfiError {
+    fn new(code: c_int, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<BindingError> for FfiError {
+    fn from(error: BindingError) -> Self {
+        let code = match error {
+            BindingError::InvalidJson(_) => RJD_ERR_INVALID_JSON,
+            BindingError::InvalidOptions(_) => RJD_ERR_INVALID_OPTIONS,
+            BindingError::Format(_) => RJD_ERR_FORMAT,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<(c_int, Option<CString>)> = const { RefCell::new((RJD_OK, None)) };
+}
+
+fn set_last_error(code: c_int, message: Option<String>) {
+    // A message is never built from a NUL byte, but drop it rather than panic
+    let message = message.and_then(|text| CString::new(text).ok());
+    LAST_ERROR.with(|last| *last.borrow_mut() = (code, message));
+}
+
+/// Run the body of an exported function, returning `on_panic(payload)` if it
+/// panics, since unwinding across the C ABI is undefined behaviour
+fn catch_panic<T>(body: impl FnOnce() -> T, on_panic: impl FnOnce(Box<dyn Any + Send>) -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(on_panic)
+}
+
+/// The message a panic was raised with
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let detail = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause");
+    format!("internal error: {}", detail)
+}
+
+/// Diff two JSON documents and return the report as a new string
+///
+/// `options_json` is a JSON object described by [`rjd_options_schema`], or
+/// NULL for the defaults (the "changes" format). On failure the result is
+/// NULL and [`rjd_last_error_code`] says why; a panic while diffing gives
+/// [`RJD_ERR_PANIC`].
+///
+/// # Safety
+///
+/// `old` and `new` must be valid pointers to NUL-terminated strings and
+/// `options_json` must be NULL or one. The returned string must be freed with
+/// [`rjd_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn rjd_diff(
+    old: *const c_char,
+    new: *const c_char,
+    options_json: *const c_char,
+) -> *mut c_char {
+    let result = catch_panic(
+        || {
+            diff_strings(
+                read_str(old, "old"),
+                read_str(new, "new"),
+                if options_json.is_null() {
+                    Ok(None)
+                } else {
+                    read_str(options_json, "options_json").map(Some)
+                },
+            )
+        },
+        |payload| Err(FfiError::new(RJD_ERR_PANIC, panic_message(&*payload))),
+    );
+    match result {
+        Ok(report) => {
+            set_last_error(RJD_OK, None);
+            report.into_raw()
+        }
+        Err(error) => {
+            set_last_error(error.code, Some(error.message));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Free a string returned by [`rjd_diff`]; NULL is ignored
+///
+/// # Safety
+///
+/// `text` must be NULL or a string returned by [`rjd_diff`] that has not
+/// been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn rjd_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        catch_panic(|| drop(CString::from_raw(text)), |_| ());
+    }
+}
+
+/// The error code of the last [`rjd_diff`] call on this thread ([`RJD_OK`]
+/// if it succeeded)
+#[no_mangle]
+pub extern "C" fn rjd_last_error_code() -> c_int {
+    catch_panic(
+        || LAST_ERROR.with(|last| last.borrow().0),
+        |_| RJD_ERR_PANIC,
+    )
+}
+
+/// A description of the last error on this thread, or NULL if the last call
+/// succeeded
+///
+/// The string belongs to the library and stays valid until the next
+/// [`rjd_diff`] call on the same thread.
+#[no_mangle]
+pub extern "C" fn rjd_last_error_message() -> *const c_char {
+    catch_panic(
+        || LAST_ERROR.with(|last| last.borrow().1.as_ref().map_or(ptr::null(), |m| m.as_ptr())),
+        |_| ptr::null(),
+    )
+}
+
+/// The JSON Schema of the options document, a static string that must not
+/// be freed (NULL if it could not be built)
+#[no_mangle]
+pub extern "C" fn rjd_options_schema() -> *const c_char {
+    catch_panic(
+        || {
+            OPTIONS_SCHEMA_C
+                .get_or_init(|| CString::new(OPTIONS_SCHEMA).expect("schema has no NUL byte"))
+                .as_ptr()
+        },
+        |_| ptr::null(),
+    )
+}
+
+/// Borrow a C string argument as UTF-8
+unsafe fn read_str<'a>(text: *const c_char, name: &str) -> Result<&'a str, FfiError> {
+    if text.is_null() {
+        return Err(FfiError::new(
+            RJD_ERR_NULL_ARGUMENT,
+            format!("{} is NULL", name),
+        ));
+    }
+    CStr::from_ptr(text).to_str().map_err(|e| {
+        FfiError::new(
+            RJD_ERR_INVALID_UTF8,
+            format!("{} is not valid UTF-8: {}", name, e),
+        )
+    })
+}
+
+fn diff_strings(
+    old: Result<&str, FfiError>,
+    new: Result<&str, FfiError>,
+    options: Result<Option<&str>, FfiError>,
+) -> Result<CString, FfiError> {
+    let old = parse_document(old?, "old")?;
+    let new = parse_document(new?, "new")?;
+    let options = parse_options(options?)?;
+    let report = diff_report(&old, &new, &options)?;
+    CString::new(report).map_err(|e| FfiError::new(RJD_ERR_FORMAT, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn call(old: &str, new: &str, options: Option<&str>) -> Result<Value, (c_int, String)> {
+        let old = CString::new(old).unwrap();
+        let new = CString::new(new).unwrap();
+        let options = options.map(|text| CString::new(text).unwrap());
+        unsafe {
+            let report = rjd_diff(
+                old.as_ptr(),
+                new.as_ptr(),
+                options.as_ref().map_or(ptr::null(), |o| o.as_ptr()),
+            );
+            if report.is_null() {
+                let message = CStr::from_ptr(rjd_last_error_message());
+                return Err((rjd_last_error_code(), message.to_string_lossy().into()));
+            }
+            let text = CStr::from_ptr(report).to_str().unwrap().to_string();
+            rjd_string_free(report);
+            assert_eq!(rjd_last_error_code(), RJD_OK);
+            assert!(rjd_last_error_message().is_null());
+            Ok(serde_json::from_str(&text).unwrap())
+        }
+    }
+
+    #[test]
+    fn test_diff_with_default_and_explicit_options() {
+        let report = call(r#"{"a": 1, "b": 2}"#, r#"{"a": 1, "b": 3}"#, None).unwrap();
+        assert_eq!(report["modified"][0]["path"], "b");
+
+        let report = call(
+            r#"{"a": 1, "ts": 1}"#,
+            r#"{"a": 2, "ts": 2}"#,
+            Some(r#"{"format": "rfc6902", "ignore": ["/ts"]}"#),
+        )
+        .unwrap();
+        assert_eq!(
+            report,
+            serde_json::json!([{"op": "replace", "path": "/a", "value": 2}])
+        );
+    }
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(call("{", "{}", None).unwrap_err().0, RJD_ERR_INVALID_JSON);
+        let (code, message) = call("{}", "{}", Some(r#"{"colour": true}"#)).unwrap_err();
+        assert_eq!(code, RJD_ERR_INVALID_OPTIONS);
+        assert!(message.contains("colour"), "{}", message);
+        assert_eq!(
+            call("{}", "{}", Some(r#"{"format": "nope"}"#))
+                .unwrap_err()
+                .0,
+            RJD_ERR_INVALID_OPTIONS
+        );
+        unsafe {
+            assert!(rjd_diff(ptr::null(), ptr::null(), ptr::null()).is_null());
+        }
+        assert_eq!(rjd_last_error_code(), RJD_ERR_NULL_ARGUMENT);
+    }
+
+    #[test]
+    fn test_panics_do_not_unwind() {
+        let result = catch_panic(
+            || -> Result<(), FfiError> { panic!("boom") },
+            |payload| Err(FfiError::new(RJD_ERR_PANIC, panic_message(&*payload))),
+        );
+        let error = result.unwrap_err();
+        assert_eq!(error.code, RJD_ERR_PANIC);
+        assert_eq!(error.message, "internal error: boom");
+        assert!(!rjd_options_schema().is_null());
+    }
+}
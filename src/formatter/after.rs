@@ -1,142 +1,9 @@
+use crate::error::FormatError;
+use crate::formatter::path_filter::filter_document;
 use crate::formatter::{sort_json_value, Formatter};
-use crate::json_path::{JsonPath, PathSegment};
-use crate::types::{Change, Changes};
+use crate::types::Changes;
 use serde_json::{Map, Value};
 
-// Import PathParser
-use super::path_parser::PathParser;
-use std::collections::HashSet;
-
-/// Check if a path or any of its descendants are in the changed paths set
-///
-/// This function determines whether a JSON node should be included in the filtered
-/// output by checking if either the exact path matches or if any descendant paths
-/// start with this path as a prefix.
-fn path_or_descendants_changed(path: &JsonPath, changed_paths: &HashSet<Vec<PathSegment>>) -> bool {
-    // Check exact match
-    if changed_paths.contains(path.segments()) {
-        return true;
-    }
-
-    // Check if any changed path is a descendant of this path
-    // A path is a descendant if it starts with all segments of this path
-    for changed in changed_paths {
-        if changed.len() >= path.len() && changed[..path.len()] == path.segments()[..] {
-            return true;
-        }
-    }
-
-    false
-}
-
-/// Collect and filter in a single pass through the value tree
-///
-/// This optimized function traverses the JSON value exactly once, building the
-/// filtered output directly during traversal. It includes a node if:
-/// - The node's path is in changed_paths, OR
-/// - Any descendant of the node is in changed_paths
-///
-/// When an object/array is directly in changed_paths, ALL its children are included
-/// (not just changed ones). This matches the old behavior where the entire structure
-/// is preserved when the parent is marked as changed.
-///
-/// This eliminates the need for separate path collection and value building phases.
-fn collect_and_filter_single_pass(
-    value: &Value,
-    current_path: &JsonPath,
-    changed_paths: &HashSet<Vec<PathSegment>>,
-) -> Option<Value> {
-    match value {
-        Value::Object(map) => {
-            // Check if this object or any of its descendants are changed
-            let object_or_descendants_changed =
-                path_or_descendants_changed(current_path, changed_paths);
-            let object_directly_changed = changed_paths.contains(current_path.segments());
-
-            if object_or_descendants_changed {
-                let mut filtered_map = Map::new();
-
-                for (key, child_value) in map {
-                    // Build path for this child
-                    let mut child_path = current_path.clone();
-                    child_path.push(PathSegment::Key(key.clone()));
-
-                    // Check if this child path or any descendants are changed
-                    let child_or_descendants_changed =
-                        path_or_descendants_changed(&child_path, changed_paths);
-
-                    if child_or_descendants_changed || object_directly_changed {
-                        // If parent object is directly changed, include all children without filtering
-                        // Otherwise, recurse normally to filter
-                        if object_directly_changed {
-                            // Include child as-is (don't filter further)
-                            filtered_map.insert(key.clone(), child_value.clone());
-                        } else {
-                            // Recurse into child with filtering
-                            if let Some(filtered_child) = collect_and_filter_single_pass(
-                                child_value,
-                                &child_path,
-                                changed_paths,
-                            ) {
-                                filtered_map.insert(key.clone(), filtered_child);
-                            }
-                        }
-                    }
-                }
-
-                Some(Value::Object(filtered_map))
-            } else {
-                None
-            }
-        }
-        Value::Array(arr) => {
-            // Check if this array or any of its elements/descendants are changed
-            let array_or_descendants_changed =
-                path_or_descendants_changed(current_path, changed_paths);
-            let array_directly_changed = changed_paths.contains(current_path.segments());
-
-            if array_or_descendants_changed {
-                // Include ALL elements of the array
-                // This matches the old behavior where the entire array is shown
-                let mut filtered_arr = Vec::new();
-
-                for (i, child_value) in arr.iter().enumerate() {
-                    if array_directly_changed {
-                        // Include element as-is (don't filter further)
-                        filtered_arr.push(child_value.clone());
-                    } else {
-                        // Recursively filter child elements
-                        let mut child_path = current_path.clone();
-                        child_path.push(PathSegment::Index(i));
-
-                        if let Some(filtered_child) =
-                            collect_and_filter_single_pass(child_value, &child_path, changed_paths)
-                        {
-                            filtered_arr.push(filtered_child);
-                        } else {
-                            // Primitive value not in changed paths, but descendant changed
-                            // Include it anyway since we're in an array that has changed descendants
-                            filtered_arr.push(child_value.clone());
-                        }
-                    }
-                }
-
-                Some(Value::Array(filtered_arr))
-            } else {
-                None
-            }
-        }
-        // Primitive values are included if path is in changed_paths
-        _ => {
-            if changed_paths.contains(current_path.segments()) {
-                Some(value.clone())
-            } else {
-                None
-            }
-        }
-    }
-}
-
 /// Formatter for the "after" output format
 ///
 /// This formatter outputs the "after" state (file2) but only includes
@@ -144,12 +11,26 @@ fn collect_and_filter_single_pass(
 pub struct AfterFormatter {
     pretty: bool,
     sort: bool,
+    context: usize,
 }
 
 impl AfterFormatter {
     /// Create a new AfterFormatter with pretty printing enabled
     pub fn new(sort: bool) -> Self {
-        Self { pretty: true, sort }
+        Self {
+            pretty: true,
+            sort,
+            context: 0,
+        }
+    }
+
+    /// Also include `levels` levels of unchanged keys around each change
+    ///
+    /// Nested containers beyond those levels are collapsed to `"{…}"` or
+    /// `"[…]"`. A value of 0 disables context.
+    pub fn with_context(mut self, levels: usize) -> Self {
+        self.context = levels;
+        self
     }
 }
 
@@ -160,7 +41,7 @@ impl Default for AfterFormatter {
 }
 
 impl Formatter for AfterFormatter {
-    fn format(&self, changes: &Changes) -> Result<String, Box<dyn std::error::Error>> {
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
         // Get the "after" value
         let after_value = match &changes.after {
             Some(value) => value,
@@ -175,31 +56,12 @@ impl Formatter for AfterFormatter {
             }
         };
 
-        // Build a set of all changed paths as strings
-        let mut changed_paths_strings = HashSet::new();
-        for change in &changes.added {
-            if let Change::Added { path, .. } = change {
-                changed_paths_strings.insert(path.to_string());
-            }
-        }
-        for change in &changes.modified {
-            if let Change::Modified { path, .. } = change {
-                changed_paths_strings.insert(path.to_string());
-            }
-        }
-
-        // Pre-parse changed paths into PathSegment vectors for O(1) comparison
-        let changed_paths_segments: HashSet<Vec<PathSegment>> = changed_paths_strings
-            .iter()
-            .filter_map(|p| PathParser::parse(p).ok())
-            .map(|parser| parser.into_segments())
-            .collect();
-
-        // Use single-pass traversal with PathParser integration
-        let root_path = JsonPath::new();
-        let filtered_after =
-            collect_and_filter_single_pass(after_value, &root_path, &changed_paths_segments)
-                .unwrap_or(Value::Object(Map::new()));
+        let filtered_after = filter_document(
+            after_value,
+            changes.added.iter().chain(&changes.modified),
+            changes,
+            self.context,
+        );
 
         // Serialize to JSON
         let json = if self.pretty {
@@ -222,9 +84,54 @@ impl Formatter for AfterFormatter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::json_path::{JsonPath, PathSegment};
     use crate::types::{Change, Changes};
     use serde_json::Value;
 
+    #[test]
+    fn test_format_key_containing_dot() {
+        let mut changes = Changes::new();
+        changes.push(Change::Modified {
+            path: JsonPath::from_segments(vec![PathSegment::Key("a.b".to_string())]),
+            old_value: Value::from(1),
+            new_value: Value::from(2),
+        });
+        changes.after = Some(serde_json::json!({"a.b": 2, "a": {"b": 3}}));
+
+        let formatter = AfterFormatter::new(false);
+        let parsed: Value = serde_json::from_str(&formatter.format(&changes).unwrap()).unwrap();
+        assert_eq!(parsed, serde_json::json!({"a.b": 2}));
+    }
+
+    #[test]
+    fn test_format_with_context() {
+        let mut changes = Changes::new();
+        changes.push(Change::Modified {
+            path: "config.db.port".parse().unwrap(),
+            old_value: Value::from(5432),
+            new_value: Value::from(5433),
+        });
+        changes.push(Change::Removed {
+            path: "extra.flag".parse().unwrap(),
+            value: Value::Bool(true),
+        });
+        changes.after = Some(serde_json::json!({
+            "config": {"db": {"host": "localhost", "port": 5433, "pool": {"size": 5}}, "debug": false},
+            "extra": {"other": 1},
+            "untouched": 1
+        }));
+
+        let formatter = AfterFormatter::new(false).with_context(1);
+        let parsed: Value = serde_json::from_str(&formatter.format(&changes).unwrap()).unwrap();
+        assert_eq!(
+            parsed,
+            serde_json::json!({
+                "config": {"db": {"port": 5433, "host": "localhost", "pool": "{…}"}},
+                "extra": {"other": 1}
+            })
+        );
+    }
+
     #[test]
     fn test_format_empty_changes() {
         let formatter = AfterFormatter::new(false);
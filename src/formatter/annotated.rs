@@ -0,0 +1,267 @@
+use crate::error::FormatError;
+use crate::formatter::{sort_json_value, Formatter};
+use crate::json_path::{JsonPath, PathSegment};
+use crate::types::{Change, Changes};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// Formatter for the "annotated" output format
+///
+/// Outputs the whole second document with the changes marked inline, using
+/// the conventions of the `json-diff` npm tool:
+///
+/// - an added object member's key gets a `__added` suffix, a removed one
+///   (shown with its old value) a `__deleted` suffix
+/// - a modified value becomes `{"__old": ..., "__new": ...}`, plus the
+///   `stringDiff` annotation of a string as `"__diff"` (see
+///   [`annotate_string_diffs`](crate::string_diff::annotate_string_diffs))
+/// - each element of an array with changes becomes a `[marker, value]` pair,
+///   where the marker is `" "` (unchanged), `"+"` (added), `"-"` (removed)
+///   or `"~"` (changed inside, with the value annotated)
+///
+/// Unchanged parts are output as they are. Changes filtered out (e.g. by
+/// ignore patterns) are not marked.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::formatter::{AnnotatedFormatter, Formatter};
+/// use rjd::diff;
+/// use serde_json::json;
+///
+/// let old = json!({"name": "app", "port": 80, "tags": ["a"]});
+/// let new = json!({"name": "app", "port": 8080, "tags": ["a", "b"], "debug": true});
+/// let mut changes = diff(&old, &new);
+/// changes.before = Some(old.clone());
+/// let output = AnnotatedFormatter::new(false).format(&changes).unwrap();
+///
+/// assert_eq!(
+///     serde_json::from_str::<serde_json::Value>(&output).unwrap(),
+///     json!({
+///         "name": "app",
+///         "port": {"__old": 80, "__new": 8080},
+///         "tags": [[" ", "a"], ["+", "b"]],
+///         "debug__added": true
+///     })
+/// );
+/// ```
+pub struct AnnotatedFormatter {
+    sort: bool,
+}
+
+impl AnnotatedFormatter {
+    /// Create a new AnnotatedFormatter with pretty printing enabled
+    pub fn new(sort: bool) -> Self {
+        Self { sort }
+    }
+}
+
+impl Default for AnnotatedFormatter {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+impl Formatter for AnnotatedFormatter {
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+        let (Some(before), Some(after)) = (&changes.before, &changes.after) else {
+            return Err(FormatError::Unsupported {
+                message: "the annotated format needs both documents".to_string(),
+            });
+        };
+        let annotated = Annotator::new(changes).annotate(&JsonPath::new(), before, after);
+
+        if self.sort {
+            Ok(serde_json::to_string_pretty(&sort_json_value(&annotated))?)
+        } else {
+            Ok(serde_json::to_string_pretty(&annotated)?)
+        }
+    }
+
+    fn uses_before(&self) -> bool {
+        true
+    }
+}
+
+struct Annotator<'a> {
+    /// Changes by path
+    changes: HashMap<&'a JsonPath, &'a Change>,
+    /// Paths with a change at or below them
+    touched: HashSet<JsonPath>,
+    /// Annotations of the changes by path
+    annotations: &'a HashMap<JsonPath, Map<String, Value>>,
+}
+
+impl<'a> Annotator<'a> {
+    fn new(changes: &'a Changes) -> Self {
+        let mut touched = HashSet::new();
+        for path in changes.paths() {
+            for n in 0..=path.len() {
+                touched.insert(JsonPath::from_segments(path.segments()[..n].to_vec()));
+            }
+        }
+        Self {
+            changes: changes
+                .iter()
+                .map(|change| (change.path(), change))
+                .collect(),
+            touched,
+            annotations: &changes.annotations,
+        }
+    }
+
+    fn annotate(&self, path: &JsonPath, old: &Value, new: &Value) -> Value {
+        if !self.touched.contains(path) {
+            return new.clone();
+        }
+        let changed_here = self.changes.contains_key(path);
+        match (old, new) {
+            (Value::Object(old_map), Value::Object(new_map)) if !changed_here => {
+                let mut annotated = Map::new();
+                for (key, value) in new_map {
+                    let child = child_path(path, PathSegment::Key(key.clone()));
+                    match old_map.get(key) {
+                        Some(old_value) => {
+                            annotated.insert(key.clone(), self.annotate(&child, old_value, value));
+                        }
+                        None if self.touched.contains(&child) => {
+                            annotated.insert(format!("{}__added", key), value.clone());
+                        }
+                        None => {
+                            annotated.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+                for (key, value) in old_map {
+                    let child = child_path(path, PathSegment::Key(key.clone()));
+                    if !new_map.contains_key(key) && self.touched.contains(&child) {
+                        annotated.insert(format!("{}__deleted", key), value.clone());
+                    }
+                }
+                Value::Object(annotated)
+            }
+            (Value::Array(old_items), Value::Array(new_items)) if !changed_here => {
+                let len = old_items.len().max(new_items.len());
+                let elements = (0..len).filter_map(|i| {
+                    let child = child_path(path, PathSegment::Index(i));
+                    let touched = self.touched.contains(&child);
+                    match (old_items.get(i), new_items.get(i)) {
+                        (Some(old_item), Some(new_item)) if touched => {
+                            Some(json!(["~", self.annotate(&child, old_item, new_item)]))
+                        }
+                        (None, Some(new_item)) if touched => Some(json!(["+", new_item])),
+                        (Some(old_item), None) if touched => Some(json!(["-", old_item])),
+                        (_, Some(new_item)) => Some(json!([" ", new_item])),
+                        (_, None) => None,
+                    }
+                });
+                Value::Array(elements.collect())
+            }
+            _ => {
+                let string_diff = self
+                    .annotations
+                    .get(path)
+                    .and_then(|annotations| annotations.get("stringDiff"));
+                match string_diff {
+                    Some(edits) => json!({"__old": old, "__new": new, "__diff": edits}),
+                    None => json!({"__old": old, "__new": new}),
+                }
+            }
+        }
+    }
+}
+
+fn child_path(path: &JsonPath, segment: PathSegment) -> JsonPath {
+    let mut child = path.clone();
+    child.push(segment);
+    child
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+
+    fn annotated(old: &Value, new: &Value) -> Value {
+        let mut changes = diff(old, new);
+        changes.before = Some(old.clone());
+        let output = AnnotatedFormatter::new(false).format(&changes).unwrap();
+        serde_json::from_str(&output).unwrap()
+    }
+
+    #[test]
+    fn test_annotate_objects() {
+        let old = json!({"keep": {"x": 1}, "gone": {"a": [1]}, "n": {"a": 1, "b": 2}});
+        let new = json!({"keep": {"x": 1}, "n": {"a": 1, "b": 3}, "new": {}});
+        assert_eq!(
+            annotated(&old, &new),
+            json!({
+                "keep": {"x": 1},
+                "n": {"a": 1, "b": {"__old": 2, "__new": 3}},
+                "new__added": {},
+                "gone__deleted": {"a": [1]}
+            })
+        );
+    }
+
+    #[test]
+    fn test_annotate_arrays() {
+        let old = json!([1, {"id": 1, "v": "a"}, 3, 4]);
+        let new = json!([1, {"id": 1, "v": "b"}, "3"]);
+        assert_eq!(
+            annotated(&old, &new),
+            json!([
+                [" ", 1],
+                ["~", {"id": 1, "v": {"__old": "a", "__new": "b"}}],
+                ["~", {"__old": 3, "__new": "3"}],
+                ["-", 4]
+            ])
+        );
+    }
+
+    #[test]
+    fn test_annotate_unchanged_and_root() {
+        let doc = json!({"a": [1, 2]});
+        assert_eq!(annotated(&doc, &doc), doc);
+        assert_eq!(
+            annotated(&json!(1), &json!("1")),
+            json!({"__old": 1, "__new": "1"})
+        );
+        assert!(AnnotatedFormatter::new(false)
+            .format(&Changes::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_annotate_string_diff() {
+        let mut changes = diff(&json!({"s": "a b"}), &json!({"s": "a c"}));
+        changes.before = Some(json!({"s": "a b"}));
+        crate::string_diff::annotate_string_diffs(
+            &mut changes,
+            crate::string_diff::Granularity::Word,
+        );
+        let output = AnnotatedFormatter::new(false).format(&changes).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&output).unwrap()["s"]["__diff"],
+            json!([
+                {"op": "equal", "text": "a "},
+                {"op": "delete", "text": "b"},
+                {"op": "insert", "text": "c"}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_annotate_ignores_filtered_changes() {
+        let old = json!({"a": 1, "b": 1});
+        let new = json!({"a": 2, "b": 2, "c": 3});
+        let mut changes =
+            diff(&old, &new).filter_ignore_patterns(&["/b".to_string(), "/c".to_string()]);
+        changes.before = Some(old.clone());
+        let output = AnnotatedFormatter::new(true).format(&changes).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&output).unwrap(),
+            json!({"a": {"__new": 2, "__old": 1}, "b": 2, "c": 3})
+        );
+    }
+}
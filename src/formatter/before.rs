@@ -0,0 +1,113 @@
+use crate::error::FormatError;
+use crate::formatter::path_filter::filter_document;
+use crate::formatter::{sort_json_value, Formatter};
+use crate::types::Changes;
+use serde_json::{Map, Value};
+
+/// Formatter for the "before" output format
+///
+/// The counterpart of [`AfterFormatter`](crate::formatter::AfterFormatter):
+/// it outputs the "before" state (file1) but only includes properties that
+/// were removed or modified, i.e. what a rollback would restore.
+pub struct BeforeFormatter {
+    sort: bool,
+    context: usize,
+}
+
+impl BeforeFormatter {
+    /// Create a new BeforeFormatter with pretty printing enabled
+    pub fn new(sort: bool) -> Self {
+        Self { sort, context: 0 }
+    }
+
+    /// Also include `levels` levels of unchanged keys around each change
+    ///
+    /// The context is taken from the first input. Nested containers beyond
+    /// those levels are collapsed to `"{…}"` or `"[…]"`. A value of 0
+    /// disables context.
+    pub fn with_context(mut self, levels: usize) -> Self {
+        self.context = levels;
+        self
+    }
+}
+
+impl Default for BeforeFormatter {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+impl Formatter for BeforeFormatter {
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+        let filtered_before = match &changes.before {
+            Some(before) => filter_document(
+                before,
+                changes.removed.iter().chain(&changes.modified),
+                changes,
+                self.context,
+            ),
+            // If no "before" value is available, return empty object
+            None => Value::Object(Map::new()),
+        };
+
+        if self.sort {
+            Ok(serde_json::to_string_pretty(&sort_json_value(
+                &filtered_before,
+            ))?)
+        } else {
+            Ok(serde_json::to_string_pretty(&filtered_before)?)
+        }
+    }
+
+    fn uses_before(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    fn format(formatter: &BeforeFormatter, changes: &Changes) -> Value {
+        serde_json::from_str(&formatter.format(changes).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_format_removed_and_modified() {
+        let old = json!({"name": "Bob", "age": 30, "phone": "555", "tags": ["a", "b"]});
+        let new = json!({"name": "Bob", "age": 31, "email": "x@y.com", "tags": ["a"]});
+        let mut changes = diff(&old, &new);
+        changes.before = Some(old.clone());
+
+        assert_eq!(
+            format(&BeforeFormatter::new(false), &changes),
+            json!({"age": 30, "phone": "555", "tags": ["a", "b"]})
+        );
+    }
+
+    #[test]
+    fn test_format_with_context_and_sort() {
+        let old = json!({"db": {"port": 5432, "host": "localhost", "pool": {"size": 5}}, "z": 1});
+        let new = json!({"db": {"port": 5433, "host": "localhost", "pool": {"size": 5}}, "z": 1});
+        let mut changes = diff(&old, &new);
+        changes.before = Some(old.clone());
+
+        let output = BeforeFormatter::new(true)
+            .with_context(1)
+            .format(&changes)
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&output).unwrap(),
+            json!({"db": {"host": "localhost", "pool": "{…}", "port": 5432}})
+        );
+        assert!(output.find("host").unwrap() < output.find("port").unwrap());
+    }
+
+    #[test]
+    fn test_format_without_before_document() {
+        let changes = Changes::new();
+        assert_eq!(format(&BeforeFormatter::new(false), &changes), json!({}));
+    }
+}
@@ -1,5 +1,10 @@
-use crate::formatter::{sort_json_value, Formatter};
-use crate::types::Changes;
+use crate::error::{FormatError, FormatterError};
+use crate::formatter::context::context_for;
+use crate::formatter::{sort_json_value, write_pretty_array, Formatter, CHANGES_FORMAT_VERSION};
+use crate::grouping::{group_changes, ChangeGroup};
+use crate::types::{Change, ChangeKind, Changes};
+use serde_json::{Map, Value};
+use std::io::Write;
 
 /// Formatter for the "changes" output format
 ///
@@ -7,40 +12,294 @@ use crate::types::Changes;
 /// - added: Items present in the new file but not in the old file
 /// - removed: Items present in the old file but not in the new file
 /// - modified: Items that changed between the two files
+///
+/// Since format version 2 the object also carries a `formatVersion` field.
+/// With [`ChangesFormatter::with_context`], each change also carries a
+/// `context` object with the unchanged surroundings of the change, and the
+/// fields in [`Changes::annotations`] are added to the change at their path.
+/// With [`ChangesFormatter::with_grouping`], changes below the root are
+/// listed in nested `groups` instead.
 pub struct ChangesFormatter {
     pretty: bool,
     sort: bool,
+    version: u32,
+    context: usize,
+    group_depth: Option<usize>,
 }
 
 impl ChangesFormatter {
     /// Create a new ChangesFormatter with pretty printing enabled
     pub fn new(sort: bool) -> Self {
-        Self { pretty: true, sort }
+        Self {
+            pretty: true,
+            sort,
+            version: CHANGES_FORMAT_VERSION,
+            context: 0,
+            group_depth: None,
+        }
+    }
+
+    /// Create a ChangesFormatter that emits the given format version
+    ///
+    /// # Errors
+    /// Returns an error if the version is not 1 or 2.
+    pub fn with_version(sort: bool, version: u32) -> Result<Self, FormatterError> {
+        if !(1..=CHANGES_FORMAT_VERSION).contains(&version) {
+            return Err(FormatterError::UnsupportedFormatVersion {
+                version,
+                supported: "1, 2".to_string(),
+            });
+        }
+        Ok(Self {
+            version,
+            ..Self::new(sort)
+        })
+    }
+
+    /// Include `levels` levels of unchanged surrounding keys with each change
+    ///
+    /// The context is taken from the second input and added to each change as
+    /// `{"path": ..., "value": ...}`, where `path` is the ancestor the context
+    /// starts at. A value of 0 disables context.
+    pub fn with_context(mut self, levels: usize) -> Self {
+        self.context = levels;
+        self
+    }
+
+    /// Group changes under their ancestors `depth` path segments deep
+    ///
+    /// The top-level `added`, `removed` and `modified` arrays then hold only
+    /// changes to the whole document, and a `groups` array holds the rest as
+    /// `{"path", "added", "removed", "modified", "groups"}` objects nested
+    /// by subtree (see [`group_changes`]); empty fields are left out of
+    /// groups. `None` disables grouping.
+    pub fn with_grouping(mut self, depth: Option<usize>) -> Self {
+        self.group_depth = depth;
+        self
+    }
+
+    /// Build the output entry for a single change
+    fn entry(&self, change: &Change, changes: &Changes) -> Result<Value, serde_json::Error> {
+        let mut value = serde_json::to_value(change)?;
+        if let (Value::Object(fields), Some(annotations)) =
+            (&mut value, changes.annotations.get(change.path()))
+        {
+            fields.extend(annotations.clone());
+        }
+        if self.context > 0 {
+            let context = changes
+                .after
+                .as_ref()
+                .and_then(|after| context_for(after, change.path(), self.context));
+            if let (Value::Object(fields), Some((path, context))) = (&mut value, context) {
+                let mut object = Map::new();
+                object.insert("path".to_string(), Value::String(path.to_string()));
+                object.insert("value".to_string(), context);
+                fields.insert("context".to_string(), Value::Object(object));
+            }
+        }
+        Ok(if self.sort {
+            sort_json_value(&value)
+        } else {
+            value
+        })
+    }
+
+    /// Build the output entries for a list of changes
+    fn entries<'c>(
+        &self,
+        items: impl IntoIterator<Item = &'c Change>,
+        changes: &Changes,
+    ) -> Result<Value, serde_json::Error> {
+        items
+            .into_iter()
+            .map(|change| self.entry(change, changes))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array)
+    }
+
+    /// Build the output object for a group of changes and its nested groups
+    fn group(&self, group: &ChangeGroup, changes: &Changes) -> Result<Value, serde_json::Error> {
+        let mut fields = Map::new();
+        fields.insert("path".to_string(), Value::String(group.path.to_string()));
+        for (key, kind) in KINDS {
+            let items = group.changes.iter().copied().filter(|c| c.kind() == kind);
+            if let Value::Array(entries) = self.entries(items, changes)? {
+                if !entries.is_empty() {
+                    fields.insert(key.to_string(), Value::Array(entries));
+                }
+            }
+        }
+        if !group.groups.is_empty() {
+            fields.insert("groups".to_string(), self.groups(group, changes)?);
+        }
+        Ok(Value::Object(fields))
+    }
+
+    /// Build the `groups` array of a group
+    fn groups(&self, group: &ChangeGroup, changes: &Changes) -> Result<Value, serde_json::Error> {
+        group
+            .groups
+            .iter()
+            .map(|nested| self.group(nested, changes))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array)
     }
 }
 
+/// Output field of each kind of change
+const KINDS: [(&str, ChangeKind); 3] = [
+    ("added", ChangeKind::Added),
+    ("removed", ChangeKind::Removed),
+    ("modified", ChangeKind::Modified),
+];
+
 impl Default for ChangesFormatter {
     fn default() -> Self {
         Self::new(false)
     }
 }
 
+/// A top-level field of the streamed output
+enum Section<'a> {
+    Version,
+    Changes(&'a [Change]),
+    Value(Value),
+}
+
+/// The `truncated` field listing where a partial diff stopped, if it did
+fn truncated_entries(changes: &Changes) -> Option<Value> {
+    if !changes.is_truncated() {
+        return None;
+    }
+    let entries = changes
+        .truncated
+        .iter()
+        .map(|truncation| {
+            let mut entry = Map::new();
+            entry.insert(
+                "path".to_string(),
+                Value::String(truncation.path.to_string()),
+            );
+            entry.insert(
+                "reason".to_string(),
+                Value::String(truncation.reason.to_string()),
+            );
+            Value::Object(entry)
+        })
+        .collect();
+    Some(Value::Array(entries))
+}
+
+/// The `warnings` field listing suspicious comparisons, if there were any
+fn warning_entries(changes: &Changes) -> Option<Value> {
+    if changes.warnings.is_empty() {
+        return None;
+    }
+    let entries = changes
+        .warnings
+        .iter()
+        .map(|warning| {
+            let mut entry = Map::new();
+            entry.insert("path".to_string(), Value::String(warning.path.to_string()));
+            entry.insert(
+                "message".to_string(),
+                Value::String(warning.kind.to_string()),
+            );
+            Value::Object(entry)
+        })
+        .collect();
+    Some(Value::Array(entries))
+}
+
 impl Formatter for ChangesFormatter {
-    fn format(&self, changes: &Changes) -> Result<String, Box<dyn std::error::Error>> {
-        let json = serde_json::to_value(changes)?;
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+        let mut fields = Map::new();
+        if self.version >= 2 {
+            fields.insert("formatVersion".to_string(), Value::from(self.version));
+        }
+        if let Some(depth) = self.group_depth {
+            let root = group_changes(changes, depth);
+            for (key, kind) in KINDS {
+                let items = root.changes.iter().copied().filter(|c| c.kind() == kind);
+                fields.insert(key.to_string(), self.entries(items, changes)?);
+            }
+            fields.insert("groups".to_string(), self.groups(&root, changes)?);
+        } else {
+            fields.insert("added".to_string(), self.entries(&changes.added, changes)?);
+            fields.insert(
+                "removed".to_string(),
+                self.entries(&changes.removed, changes)?,
+            );
+            fields.insert(
+                "modified".to_string(),
+                self.entries(&changes.modified, changes)?,
+            );
+        }
+        if let Some(truncated) = truncated_entries(changes) {
+            fields.insert("truncated".to_string(), truncated);
+        }
+        if let Some(warnings) = warning_entries(changes) {
+            fields.insert("warnings".to_string(), warnings);
+        }
+        let json = Value::Object(fields);
+
+        let json = if self.sort {
+            sort_json_value(&json)
+        } else {
+            json
+        };
 
+        if self.pretty {
+            Ok(serde_json::to_string_pretty(&json)?)
+        } else {
+            Ok(serde_json::to_string(&json)?)
+        }
+    }
+
+    fn write_to(&self, changes: &Changes, writer: &mut dyn Write) -> Result<(), FormatError> {
+        // Groups are only complete once every change is placed, so they are not streamed
+        if !self.pretty || self.group_depth.is_some() {
+            writeln!(writer, "{}", self.format(changes)?)?;
+            return Ok(());
+        }
+
+        let mut sections: Vec<(&str, Section)> = Vec::new();
+        if self.version >= 2 {
+            sections.push(("formatVersion", Section::Version));
+        }
+        sections.push(("added", Section::Changes(&changes.added)));
+        sections.push(("removed", Section::Changes(&changes.removed)));
+        sections.push(("modified", Section::Changes(&changes.modified)));
+        if let Some(truncated) = truncated_entries(changes) {
+            sections.push(("truncated", Section::Value(truncated)));
+        }
+        if let Some(warnings) = warning_entries(changes) {
+            sections.push(("warnings", Section::Value(warnings)));
+        }
         if self.sort {
-            let sorted = sort_json_value(&json);
-            if self.pretty {
-                Ok(serde_json::to_string_pretty(&sorted)?)
-            } else {
-                Ok(serde_json::to_string(&sorted)?)
+            sections.sort_by_key(|(key, _)| *key);
+        }
+
+        write!(writer, "{{")?;
+        for (i, (key, section)) in sections.into_iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            write!(writer, "{}\n  {}: ", separator, serde_json::to_string(key)?)?;
+            match section {
+                Section::Version => write!(writer, "{}", self.version)?,
+                Section::Changes(items) => {
+                    let values = items
+                        .iter()
+                        .map(|change| self.entry(change, changes).unwrap_or(Value::Null));
+                    write_pretty_array(writer, values, 1)?;
+                }
+                Section::Value(Value::Array(items)) => write_pretty_array(writer, items, 1)?,
+                Section::Value(value) => write!(writer, "{}", value)?,
             }
-        } else if self.pretty {
-            Ok(serde_json::to_string_pretty(changes)?)
-        } else {
-            Ok(serde_json::to_string(changes)?)
         }
+        writeln!(writer, "\n}}")?;
+
+        Ok(())
     }
 }
 
@@ -50,6 +309,113 @@ mod tests {
     use crate::types::{Change, Changes};
     use serde_json::Value;
 
+    #[test]
+    fn test_format_truncated_diff() {
+        let mut changes = Changes::new();
+        changes.push(Change::Modified {
+            path: "a".parse().unwrap(),
+            old_value: Value::from(1),
+            new_value: Value::from(2),
+        });
+        changes.truncated.push(crate::types::Truncation {
+            path: "b".parse().unwrap(),
+            reason: crate::types::TruncationReason::MaxChanges(1),
+        });
+
+        let formatter = ChangesFormatter::new(false);
+        let parsed: Value = serde_json::from_str(&formatter.format(&changes).unwrap()).unwrap();
+        assert_eq!(parsed["truncated"][0]["path"], "b");
+        assert!(parsed["truncated"][0]["reason"]
+            .as_str()
+            .unwrap()
+            .contains("change limit 1"));
+
+        let mut streamed = Vec::new();
+        formatter.write_to(&changes, &mut streamed).unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&streamed).unwrap(), parsed);
+
+        changes.truncated.clear();
+        let parsed: Value = serde_json::from_str(&formatter.format(&changes).unwrap()).unwrap();
+        assert!(parsed.get("truncated").is_none());
+    }
+
+    #[test]
+    fn test_format_warnings() {
+        let mut changes = Changes::new();
+        let formatter = ChangesFormatter::new(false);
+        let parsed: Value = serde_json::from_str(&formatter.format(&changes).unwrap()).unwrap();
+        assert!(parsed.get("warnings").is_none());
+
+        changes.warnings.push(crate::types::Warning {
+            path: "n".parse().unwrap(),
+            kind: crate::types::WarningKind::PrecisionLoss,
+        });
+        let parsed: Value = serde_json::from_str(&formatter.format(&changes).unwrap()).unwrap();
+        assert_eq!(parsed["warnings"][0]["path"], "n");
+        assert!(parsed["warnings"][0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("precision"));
+    }
+
+    #[test]
+    fn test_format_with_context() {
+        let mut changes = Changes::new();
+        changes.push(Change::Modified {
+            path: "users[0].age".parse().unwrap(),
+            old_value: Value::from(25),
+            new_value: Value::from(26),
+        });
+        changes.after = Some(serde_json::json!({
+            "users": [{"name": "Alice", "age": 26, "tags": ["admin"]}]
+        }));
+
+        let formatter = ChangesFormatter::new(false).with_context(1);
+        let parsed: Value = serde_json::from_str(&formatter.format(&changes).unwrap()).unwrap();
+        assert_eq!(
+            parsed["modified"][0]["context"],
+            serde_json::json!({
+                "path": "users[0]",
+                "value": {"name": "Alice", "age": 26, "tags": "[…]"}
+            })
+        );
+
+        let mut streamed = Vec::new();
+        formatter.write_to(&changes, &mut streamed).unwrap();
+        let streamed: Value = serde_json::from_slice(&streamed).unwrap();
+        assert_eq!(streamed, parsed);
+
+        let plain: Value =
+            serde_json::from_str(&ChangesFormatter::new(false).format(&changes).unwrap()).unwrap();
+        assert!(plain["modified"][0].get("context").is_none());
+    }
+
+    #[test]
+    fn test_format_with_grouping() {
+        let changes = crate::diff(
+            &serde_json::json!({"users": [{"name": "a", "age": 1}, {"name": "b"}], "v": 1}),
+            &serde_json::json!({"users": [{"name": "A", "age": 2}, {"name": "b"}]}),
+        );
+
+        let formatter = ChangesFormatter::new(false).with_grouping(Some(2));
+        let parsed: Value = serde_json::from_str(&formatter.format(&changes).unwrap()).unwrap();
+        assert_eq!(parsed["modified"], serde_json::json!([]));
+        assert_eq!(
+            parsed["groups"],
+            serde_json::json!([
+                {"path": "v", "removed": [{"path": "v", "value": 1}]},
+                {"path": "users[0]", "modified": [
+                    {"path": "users[0].name", "oldValue": "a", "newValue": "A"},
+                    {"path": "users[0].age", "oldValue": 1, "newValue": 2}
+                ]}
+            ])
+        );
+
+        let mut streamed = Vec::new();
+        formatter.write_to(&changes, &mut streamed).unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&streamed).unwrap(), parsed);
+    }
+
     #[test]
     fn test_format_empty_changes() {
         let formatter = ChangesFormatter::new(false);
@@ -128,7 +494,31 @@ mod tests {
 
         // Get the order of keys
         let keys: Vec<&str> = obj.keys().map(|s| s.as_str()).collect();
-        assert_eq!(keys, vec!["added", "modified", "removed"]);
+        assert_eq!(keys, vec!["added", "formatVersion", "modified", "removed"]);
+    }
+
+    #[test]
+    fn test_format_version_field() {
+        let changes = Changes::new();
+
+        let v2 = ChangesFormatter::new(false).format(&changes).unwrap();
+        let parsed: Value = serde_json::from_str(&v2).unwrap();
+        assert_eq!(parsed["formatVersion"], 2);
+        assert_eq!(
+            parsed.as_object().unwrap().keys().next().unwrap(),
+            "formatVersion"
+        );
+
+        let v1 = ChangesFormatter::with_version(false, 1)
+            .unwrap()
+            .format(&changes)
+            .unwrap();
+        let parsed: Value = serde_json::from_str(&v1).unwrap();
+        assert!(parsed.get("formatVersion").is_none());
+        assert!(parsed["added"].is_array());
+
+        assert!(ChangesFormatter::with_version(false, 0).is_err());
+        assert!(ChangesFormatter::with_version(false, 3).is_err());
     }
 
     #[test]
@@ -156,4 +546,29 @@ mod tests {
         let nested_keys: Vec<&str> = nested_obj.keys().map(|s| s.as_str()).collect();
         assert_eq!(nested_keys, vec!["a_key", "z_key"]);
     }
+
+    #[test]
+    fn test_write_to_matches_format() {
+        let mut changes = Changes::new();
+        changes.push(Change::Added {
+            path: "z".parse().unwrap(),
+            value: serde_json::json!({"b": 1, "a": [1, 2]}),
+        });
+        changes.push(Change::Modified {
+            path: "name".parse().unwrap(),
+            old_value: Value::String("John".to_string()),
+            new_value: Value::String("Jane".to_string()),
+        });
+
+        for formatter in [
+            ChangesFormatter::new(false),
+            ChangesFormatter::new(true),
+            ChangesFormatter::with_version(false, 1).unwrap(),
+        ] {
+            let mut out = Vec::new();
+            formatter.write_to(&changes, &mut out).unwrap();
+            let expected = format!("{}\n", formatter.format(&changes).unwrap());
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
+        }
+    }
 }
@@ -0,0 +1,110 @@
+//! Unchanged surroundings of a change, for `--context`
+
+use crate::json_path::JsonPath;
+use serde_json::{Map, Value};
+
+/// Find the surroundings of a change in `document`
+///
+/// Returns the path of the ancestor `levels` levels above `path` together with
+/// its value, pruned to `levels` levels: containers nested deeper than that are
+/// collapsed to `"{…}"` or `"[…]"`. If the ancestor is missing from the
+/// document (e.g. it was removed), the nearest ancestor that still exists is
+/// used instead.
+pub(crate) fn context_for(
+    document: &Value,
+    path: &JsonPath,
+    levels: usize,
+) -> Option<(JsonPath, Value)> {
+    let mut depth = path.len().saturating_sub(levels);
+    loop {
+        let ancestor = path.prefix(depth).unwrap_or_default();
+        if let Some(value) = ancestor.resolve(document) {
+            return Some((ancestor, prune(value, levels)));
+        }
+        if depth == 0 {
+            return None;
+        }
+        depth -= 1;
+    }
+}
+
+/// Copy `value`, collapsing containers below `levels` levels
+fn prune(value: &Value, levels: usize) -> Value {
+    match value {
+        Value::Object(map) if levels == 0 && !map.is_empty() => Value::from("{…}"),
+        Value::Array(items) if levels == 0 && !items.is_empty() => Value::from("[…]"),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, child)| (key.clone(), prune(child, levels - 1)))
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|item| prune(item, levels - 1)).collect())
+        }
+        _ => value.clone(),
+    }
+}
+
+/// Add context entries to `target` without overwriting anything already there
+pub(crate) fn merge_context(target: &mut Value, context: &Value) {
+    match (target, context) {
+        (Value::Object(target), Value::Object(context)) => {
+            for (key, value) in context {
+                match target.get_mut(key) {
+                    Some(existing) => merge_context(existing, value),
+                    None => {
+                        target.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (Value::Array(target), Value::Array(context)) if target.len() == context.len() => {
+            for (existing, value) in target.iter_mut().zip(context) {
+                merge_context(existing, value);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_context_for_prunes_to_levels() {
+        let document = json!({
+            "users": [{"name": "Alice", "age": 26, "tags": ["a"], "meta": {"x": 1}}]
+        });
+        let path: JsonPath = "users[0].age".parse().unwrap();
+
+        let (ancestor, value) = context_for(&document, &path, 1).unwrap();
+        assert_eq!(ancestor.to_string(), "users[0]");
+        assert_eq!(
+            value,
+            json!({"name": "Alice", "age": 26, "tags": "[…]", "meta": "{…}"})
+        );
+
+        let (ancestor, value) = context_for(&document, &path, 5).unwrap();
+        assert!(ancestor.is_empty());
+        assert_eq!(value, document);
+    }
+
+    #[test]
+    fn test_context_for_removed_path_uses_existing_ancestor() {
+        let document = json!({"a": {"b": 1}});
+        let path: JsonPath = "a.gone.deep".parse().unwrap();
+
+        let (ancestor, value) = context_for(&document, &path, 1).unwrap();
+        assert_eq!(ancestor.to_string(), "a");
+        assert_eq!(value, json!({"b": 1}));
+    }
+
+    #[test]
+    fn test_merge_context_keeps_existing_values() {
+        let mut target = json!({"user": {"age": 26}});
+        merge_context(&mut target, &json!({"user": {"name": "Alice", "age": "x"}}));
+        assert_eq!(target, json!({"user": {"age": 26, "name": "Alice"}}));
+    }
+}
@@ -0,0 +1,350 @@
+//! The complete second document, laid out like the first input
+//!
+//! Unchanged regions are copied from the first input's text and only changed
+//! values are rendered, so committing the output back to a repository yields
+//! a diff as small as the change itself.
+
+use super::Formatter;
+use crate::error::FormatError;
+use crate::json_path::JsonPath;
+use crate::location::{Node, Scanner, Want};
+use crate::types::{Change, ChangeKind, Changes};
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+use serde_json::{Map, Value};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// Formatter for the "full-after" format
+///
+/// Outputs the whole second document. Given the text of the first input, the
+/// output keeps that text's layout wherever nothing changed:
+///
+/// - modified values are rendered in place
+/// - object members that were added or removed are spliced in or cut out,
+///   added ones going after the last remaining member
+/// - arrays whose elements were added or removed are rendered whole
+///
+/// Rendered values use the indentation of the original text (or none if it
+/// was compact). Changes that were filtered out (e.g. by ignore patterns)
+/// keep their original text. Without the original text, or if it is not the
+/// document the changes were computed from, the document is pretty-printed.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::formatter::{Formatter, FullAfterFormatter};
+/// use rjd::diff;
+///
+/// let original = "{\n    \"name\": \"app\",\n    \"tags\": [\"a\", \"b\"],\n    \"port\": 80\n}\n";
+/// let old = serde_json::from_str(original).unwrap();
+/// let new = serde_json::json!({"name": "app", "tags": ["a", "b"], "port": 8080});
+///
+/// let formatter = FullAfterFormatter::new(Some(original.to_string()));
+/// let output = formatter.format(&diff(&old, &new)).unwrap();
+/// assert_eq!(output, "{\n    \"name\": \"app\",\n    \"tags\": [\"a\", \"b\"],\n    \"port\": 8080\n}");
+/// ```
+pub struct FullAfterFormatter {
+    original: Option<String>,
+}
+
+impl FullAfterFormatter {
+    /// Create a formatter that lays the output out like `original`, the text
+    /// of the first input
+    pub fn new(original: Option<String>) -> Self {
+        Self { original }
+    }
+}
+
+impl Formatter for FullAfterFormatter {
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+        let after = changes
+            .after
+            .as_ref()
+            .ok_or_else(|| FormatError::Unsupported {
+                message: "the full-after format needs the second document".to_string(),
+            })?;
+        if let Some(original) = &self.original {
+            let before: Value = serde_json::from_str(original)?;
+            if let Some(text) = splice(original, &before, after, changes) {
+                return Ok(text);
+            }
+        }
+        Ok(serde_json::to_string_pretty(after)?)
+    }
+}
+
+/// Apply the changes to the text of `before`, or `None` if the text cannot be
+/// edited in place
+fn splice(original: &str, before: &Value, after: &Value, changes: &Changes) -> Option<String> {
+    // The text must be the document the changes were computed from
+    let consistent = changes.iter().all(|change| match change {
+        Change::Removed { path, value }
+        | Change::Modified {
+            path,
+            old_value: value,
+            ..
+        } => path.resolve(before) == Some(value),
+        Change::Added { .. } => true,
+    });
+    if !consistent {
+        return None;
+    }
+
+    let plan = Plan::new(before, after, changes);
+    let nodes = Scanner::scan(original, &plan.wanted())?;
+    let indent = indent_unit(original);
+
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    for path in &plan.replace {
+        let span = nodes.get(path)?.span.clone();
+        let value = path.resolve(after)?;
+        let text = render(value, indent, line_indent(original, span.start))?;
+        edits.push((span, text));
+    }
+    for path in &plan.members {
+        let (Value::Object(old), Value::Object(new)) =
+            (path.resolve(before)?, path.resolve(after)?)
+        else {
+            return None;
+        };
+        edits.extend(member_edits(original, nodes.get(path)?, old, new, indent)?);
+    }
+
+    // Edit from the end so earlier offsets stay valid; at the same offset a
+    // deletion goes before an insertion
+    edits.sort_by_key(|(range, _)| Reverse((range.start, range.end)));
+    let mut text = original.to_string();
+    for (range, replacement) in edits {
+        text.replace_range(range, &replacement);
+    }
+    Some(text.trim_end().to_string())
+}
+
+/// Which parts of the original text need to change
+struct Plan {
+    /// Values rendered from the second document
+    replace: HashSet<JsonPath>,
+    /// Objects that gained or lost members
+    members: HashSet<JsonPath>,
+}
+
+impl Plan {
+    fn new(before: &Value, after: &Value, changes: &Changes) -> Self {
+        let in_both =
+            |path: &JsonPath| path.resolve(before).is_some() && path.resolve(after).is_some();
+        let mut replace = HashSet::new();
+        let mut members = HashSet::new();
+
+        for change in changes {
+            let path = change.path();
+            if change.kind() == ChangeKind::Modified && in_both(path) {
+                replace.insert(path.clone());
+                continue;
+            }
+
+            // An added or removed value changes the nearest container in both documents
+            let segments = path.segments();
+            let depth = (0..segments.len())
+                .rev()
+                .find(|&n| in_both(&JsonPath::from_segments(segments[..n].to_vec())))
+                .unwrap_or(0);
+            let container = JsonPath::from_segments(segments[..depth].to_vec());
+            match (container.resolve(before), container.resolve(after)) {
+                (Some(Value::Object(old)), Some(Value::Object(new)))
+                    if old.keys().any(|key| new.contains_key(key)) =>
+                {
+                    members.insert(container);
+                }
+                _ => {
+                    replace.insert(container);
+                }
+            }
+        }
+
+        // A rendered value already contains every change below it
+        let covered = |path: &JsonPath, strict: bool| {
+            let limit = if strict { path.len() } else { path.len() + 1 };
+            (0..limit)
+                .any(|n| replace.contains(&JsonPath::from_segments(path.segments()[..n].to_vec())))
+        };
+        let members = members.into_iter().filter(|p| !covered(p, false)).collect();
+        let replace = replace
+            .iter()
+            .filter(|p| !covered(p, true))
+            .cloned()
+            .collect();
+        Self { replace, members }
+    }
+
+    /// The paths the scanner must locate, and the containers leading to them
+    fn wanted(&self) -> HashMap<JsonPath, Want> {
+        let mut wanted = HashMap::new();
+        let targets = self
+            .replace
+            .iter()
+            .map(|path| (path, Want::Span))
+            .chain(self.members.iter().map(|path| (path, Want::Members)));
+        for (path, want) in targets {
+            for n in 0..path.len() {
+                let prefix = JsonPath::from_segments(path.segments()[..n].to_vec());
+                wanted.entry(prefix).or_insert(Want::Members);
+            }
+            wanted.insert(path.clone(), want);
+        }
+        wanted
+    }
+}
+
+/// Cut out removed members and append added ones, keeping the object's layout
+fn member_edits(
+    original: &str,
+    node: &Node,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    indent: Option<&str>,
+) -> Option<Vec<(Range<usize>, String)>> {
+    let members = &node.members;
+    let removed: Vec<bool> = members.iter().map(|m| !new.contains_key(&m.key)).collect();
+    let last_kept = removed.iter().rposition(|removed| !removed)?;
+
+    let mut edits = Vec::new();
+    let mut i = 0;
+    while i < members.len() {
+        if !removed[i] {
+            i += 1;
+            continue;
+        }
+        let run_start = i;
+        while i < members.len() && removed[i] {
+            i += 1;
+        }
+        // Take the separator after the run, or before it if the run ends the object
+        let range = match members.get(i) {
+            Some(next) => members[run_start].key_start..next.key_start,
+            None => members[run_start - 1].value_end..members[i - 1].value_end,
+        };
+        edits.push((range, String::new()));
+    }
+
+    let added: Vec<(&String, &Value)> = new
+        .iter()
+        .filter(|(key, _)| !old.contains_key(*key))
+        .collect();
+    if !added.is_empty() {
+        let first = &members[0];
+        let separator = match members.get(1) {
+            Some(second) => original[first.value_end..second.key_start].to_string(),
+            None => format!(",{}", &original[node.span.start + 1..first.key_start]),
+        };
+        let colon = &original[first.key_end..first.value_start];
+        let member_indent = line_indent(original, first.key_start);
+
+        let mut text = String::new();
+        for (key, value) in added {
+            text.push_str(&separator);
+            text.push_str(&serde_json::to_string(key).ok()?);
+            text.push_str(colon);
+            text.push_str(&render(value, indent, member_indent)?);
+        }
+        let at = members[last_kept].value_end;
+        edits.push((at..at, text));
+    }
+    Some(edits)
+}
+
+/// Serialize a value starting on a line indented by `line_indent`
+fn render(value: &Value, indent: Option<&str>, line_indent: &str) -> Option<String> {
+    let text = match indent {
+        Some(unit) => {
+            let mut output = Vec::new();
+            let formatter = PrettyFormatter::with_indent(unit.as_bytes());
+            let mut serializer = serde_json::Serializer::with_formatter(&mut output, formatter);
+            value.serialize(&mut serializer).ok()?;
+            String::from_utf8(output).ok()?
+        }
+        None => serde_json::to_string(value).ok()?,
+    };
+    Some(text.replace('\n', &format!("\n{}", line_indent)))
+}
+
+/// The indentation unit of a pretty-printed text, or `None` if it is compact
+fn indent_unit(text: &str) -> Option<&str> {
+    text.trim_end().lines().skip(1).find_map(|line| {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        (!indent.is_empty()).then_some(indent)
+    })
+}
+
+/// The whitespace at the start of the line containing `pos`
+fn line_indent(text: &str, pos: usize) -> &str {
+    let line_start = text[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let line = &text[line_start..pos];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    fn full_after(original: &str, new: &Value) -> String {
+        let old: Value = serde_json::from_str(original).unwrap();
+        let formatter = FullAfterFormatter::new(Some(original.to_string()));
+        let output = formatter.format(&diff(&old, new)).unwrap();
+        assert_eq!(&serde_json::from_str::<Value>(&output).unwrap(), new);
+        output
+    }
+
+    #[test]
+    fn test_keeps_untouched_layout() {
+        let original =
+            "{\n  \"keep\": [1,2,  3],\n  \"old\": true,\n  \"n\": {\"a\": 1, \"b\": 2}\n}\n";
+        let new = json!({"keep": [1, 2, 3], "n": {"a": 1, "b": 3, "c": [4]}, "added": {"x": 1}});
+        assert_eq!(
+            full_after(original, &new),
+            "{\n  \"keep\": [1,2,  3],\n  \"n\": {\"a\": 1, \"b\": 3, \"c\": [\n    4\n  ]},\n  \"added\": {\n    \"x\": 1\n  }\n}"
+        );
+    }
+
+    #[test]
+    fn test_removes_members_at_either_end() {
+        let original = "{\"a\": 1, \"b\": 2, \"c\": 3, \"d\": 4}";
+        assert_eq!(
+            full_after(original, &json!({"b": 2, "c": 3})),
+            "{\"b\": 2, \"c\": 3}"
+        );
+        assert_eq!(
+            full_after(original, &json!({"a": 1, "c": 3, "e": 5})),
+            "{\"a\": 1, \"c\": 3, \"e\": 5}"
+        );
+        assert_eq!(full_after(original, &json!({})), "{}");
+    }
+
+    #[test]
+    fn test_rerenders_changed_arrays() {
+        let original = "[\n\t{\"id\": 1},\n\t{\"id\": 2}\n]";
+        assert_eq!(
+            full_after(original, &json!([{"id": 1}])),
+            "[\n\t{\n\t\t\"id\": 1\n\t}\n]"
+        );
+        assert_eq!(
+            full_after(original, &json!([{"id": 1}, {"id": 3}])),
+            "[\n\t{\"id\": 1},\n\t{\"id\": 3}\n]"
+        );
+    }
+
+    #[test]
+    fn test_falls_back_to_pretty_printing() {
+        let new = json!({"a": 2});
+        let changes = diff(&json!({"a": 1}), &new);
+
+        // The text is not the document the changes came from
+        let formatter = FullAfterFormatter::new(Some("{\"a\": 5}".to_string()));
+        assert_eq!(formatter.format(&changes).unwrap(), "{\n  \"a\": 2\n}");
+        let formatter = FullAfterFormatter::new(None);
+        assert_eq!(formatter.format(&changes).unwrap(), "{\n  \"a\": 2\n}");
+    }
+}
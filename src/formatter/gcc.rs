@@ -0,0 +1,151 @@
+use crate::error::FormatError;
+use crate::formatter::{description, Formatter};
+use crate::types::{Change, ChangeKind, Changes};
+use std::io::Write;
+
+/// Formatter for compiler-style diagnostics, one line per change
+///
+/// Each line reads `file:line:column: kind: message`, the layout Vim and
+/// Emacs quickfix lists and editor problem matchers understand. The position
+/// comes from the change's `newLocation` annotation, or `oldLocation` for a
+/// removed value (see [`Changes::annotate_locations`]); a change without one
+/// is reported as `file: kind: message` against the second input. A
+/// `description` annotation (see [`Changes::annotate_descriptions`]) replaces
+/// the message.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::formatter::{Formatter, GccFormatter};
+/// use rjd::{diff, SourceText};
+///
+/// let old_text = "{\"port\": 80}";
+/// let new_text = "{\n  \"port\": 8080\n}";
+/// let mut changes = diff(
+///     &serde_json::from_str(old_text).unwrap(),
+///     &serde_json::from_str(new_text).unwrap(),
+/// );
+/// changes.annotate_locations(None, Some(SourceText { name: "new.json", text: new_text }));
+///
+/// let output = GccFormatter::new(None).format(&changes).unwrap();
+/// assert_eq!(output, "new.json:2:11: modified: $.port changed from 80 to 8080");
+/// ```
+pub struct GccFormatter {
+    artifact: Option<String>,
+}
+
+impl GccFormatter {
+    /// Create a formatter; `artifact` names the second input in lines
+    /// without a location (default `-`)
+    pub fn new(artifact: Option<String>) -> Self {
+        Self { artifact }
+    }
+
+    fn line(&self, change: &Change, changes: &Changes) -> String {
+        let field = match change.kind() {
+            ChangeKind::Removed => "oldLocation",
+            ChangeKind::Added | ChangeKind::Modified => "newLocation",
+        };
+        let location = changes
+            .annotations
+            .get(change.path())
+            .and_then(|annotations| annotations.get(field));
+        let position = match location {
+            Some(location) => format!(
+                "{}:{}:{}",
+                location["file"].as_str().unwrap_or("-"),
+                location["line"],
+                location["column"]
+            ),
+            None => self.artifact.clone().unwrap_or_else(|| "-".to_string()),
+        };
+
+        let path = change.path().to_jsonpath();
+        let (kind, message) = match change {
+            Change::Added { value, .. } => {
+                ("added", format!("{} added with value {}", path, value))
+            }
+            Change::Removed { value, .. } => {
+                ("removed", format!("{} removed (was {})", path, value))
+            }
+            Change::Modified {
+                old_value,
+                new_value,
+                ..
+            } => (
+                "modified",
+                format!("{} changed from {} to {}", path, old_value, new_value),
+            ),
+        };
+        let message = description(change, changes).map_or(message, String::from);
+        format!("{}: {}: {}", position, kind, message)
+    }
+}
+
+impl Formatter for GccFormatter {
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+        let lines: Vec<String> = changes
+            .iter()
+            .map(|change| self.line(change, changes))
+            .collect();
+        Ok(lines.join("\n"))
+    }
+
+    fn write_to(&self, changes: &Changes, writer: &mut dyn Write) -> Result<(), FormatError> {
+        // No changes print nothing, not an empty line
+        for change in changes {
+            writeln!(writer, "{}", self.line(change, changes))?;
+        }
+        Ok(())
+    }
+
+    fn content_type(&self) -> &'static str {
+        "text/plain; charset=utf-8"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff, SourceText};
+
+    #[test]
+    fn test_gcc_lines() {
+        let old_text = "{\n  \"gone\": true,\n  \"port\": 80\n}";
+        let new_text = "{\"port\": 8080, \"name\": \"app\"}";
+        let mut changes = diff(
+            &serde_json::from_str(old_text).unwrap(),
+            &serde_json::from_str(new_text).unwrap(),
+        );
+        let formatter = GccFormatter::new(Some("new.json".to_string()));
+        assert_eq!(
+            formatter.format(&changes).unwrap(),
+            "new.json: added: $.name added with value \"app\"\n\
+             new.json: removed: $.gone removed (was true)\n\
+             new.json: modified: $.port changed from 80 to 8080"
+        );
+
+        changes.annotate_locations(
+            Some(SourceText {
+                name: "old.json",
+                text: old_text,
+            }),
+            Some(SourceText {
+                name: "new.json",
+                text: new_text,
+            }),
+        );
+        let mut output = Vec::new();
+        formatter.write_to(&changes, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "new.json:1:24: added: $.name added with value \"app\"\n\
+             old.json:2:11: removed: $.gone removed (was true)\n\
+             new.json:1:10: modified: $.port changed from 80 to 8080\n"
+        );
+
+        let mut output = Vec::new();
+        formatter.write_to(&Changes::new(), &mut output).unwrap();
+        assert!(output.is_empty());
+    }
+}
@@ -1,7 +1,11 @@
-use crate::formatter::{sort_json_value, Formatter};
+use crate::error::FormatError;
+use crate::formatter::{sort_json_value, write_pretty_array, Formatter};
+use crate::json_path::{JsonPath, PathSegment};
 use crate::types::{Change, Changes};
 use serde::Serialize;
 use serde_json::Value;
+use std::collections::HashSet;
+use std::io::Write;
 
 /// Represents a JSON Patch operation according to RFC 6902
 #[derive(Debug, Clone, Serialize)]
@@ -36,38 +40,84 @@ impl Default for JsonPatchFormatter {
     }
 }
 
-impl Formatter for JsonPatchFormatter {
-    fn format(&self, changes: &Changes) -> Result<String, Box<dyn std::error::Error>> {
+impl JsonPatchFormatter {
+    /// Convert changes into patch operations: added, then removed, then modified
+    ///
+    /// The operations apply in sequence, so removals run from the last path
+    /// to the first and never shift an index a later removal refers to;
+    /// additions run in document order and append to arrays in turn. The
+    /// diff records added and removed subtrees leaf by leaf; when the
+    /// changes carry their documents, the leaves of an object member or array
+    /// element missing from one side become one operation on that member or
+    /// element, since its leaves cannot be added or removed one at a time.
+    ///
+    /// Below an array whose elements were paired other than by position (see
+    /// [`Changes::paired_arrays`]), only elements paired with the one at their
+    /// own index keep their changes; the others are replaced, added or
+    /// removed whole by index, so every operation uses the same indices.
+    fn operations(changes: &Changes) -> Vec<JsonPatchOperation> {
+        let rearranged = rearranged_arrays(changes);
+        let rewritten = |path: &JsonPath| rearranged.iter().any(|array| array.rewrites(path));
         let mut operations = Vec::new();
+        let mut seen = HashSet::new();
 
-        // Process added changes -> "add" operations
         for change in &changes.added {
             if let Change::Added { path, value } = change {
+                if rewritten(path) {
+                    continue;
+                }
+                let (path, value) =
+                    whole_subtree(path, value, changes.before.as_ref(), changes.after.as_ref());
+                if seen.insert(path.clone()) {
+                    operations.push(JsonPatchOperation {
+                        op: "add".to_string(),
+                        path: path.to_json_pointer(),
+                        value: Some(value),
+                    });
+                }
+            }
+        }
+        for array in &rearranged {
+            for index in array.old.len()..array.new.len() {
                 operations.push(JsonPatchOperation {
                     op: "add".to_string(),
-                    path: path.to_json_pointer(),
-                    value: Some(value.clone()),
+                    path: array.element(index).to_json_pointer(),
+                    value: Some(array.new[index].clone()),
                 });
             }
         }
 
-        // Process removed changes -> "remove" operations
+        let mut removed: Vec<JsonPath> = Vec::new();
         for change in &changes.removed {
-            if let Change::Removed { path, .. } = change {
-                operations.push(JsonPatchOperation {
-                    op: "remove".to_string(),
-                    path: path.to_json_pointer(),
-                    value: None,
-                });
+            if let Change::Removed { path, value } = change {
+                if rewritten(path) {
+                    continue;
+                }
+                let (path, _) =
+                    whole_subtree(path, value, changes.after.as_ref(), changes.before.as_ref());
+                if seen.insert(path.clone()) {
+                    removed.push(path);
+                }
             }
         }
+        for array in &rearranged {
+            removed.extend((array.new.len()..array.old.len()).map(|index| array.element(index)));
+        }
+        removed.sort_by(|a, b| b.cmp(a));
+        operations.extend(removed.into_iter().map(|path| JsonPatchOperation {
+            op: "remove".to_string(),
+            path: path.to_json_pointer(),
+            value: None,
+        }));
 
-        // Process modified changes -> "replace" operations
         for change in &changes.modified {
             if let Change::Modified {
                 path, new_value, ..
             } = change
             {
+                if rewritten(path) {
+                    continue;
+                }
                 operations.push(JsonPatchOperation {
                     op: "replace".to_string(),
                     path: path.to_json_pointer(),
@@ -75,6 +125,123 @@ impl Formatter for JsonPatchFormatter {
                 });
             }
         }
+        for array in &rearranged {
+            for (index, (old, new)) in array.old.iter().zip(array.new).enumerate() {
+                if !array.in_place[index] && old != new {
+                    operations.push(JsonPatchOperation {
+                        op: "replace".to_string(),
+                        path: array.element(index).to_json_pointer(),
+                        value: Some(new.clone()),
+                    });
+                }
+            }
+        }
+
+        operations
+    }
+}
+
+/// An array whose elements were paired other than by position, patched by index
+struct RearrangedArray<'a> {
+    path: JsonPath,
+    old: &'a [Value],
+    new: &'a [Value],
+    /// Whether the element at each index of both arrays was paired with the
+    /// one at the same index of the other
+    in_place: Vec<bool>,
+}
+
+impl RearrangedArray<'_> {
+    fn element(&self, index: usize) -> JsonPath {
+        let mut path = self.path.clone();
+        path.push(PathSegment::Index(index));
+        path
+    }
+
+    /// Whether the change at `path` lies in an element patched whole
+    fn rewrites(&self, path: &JsonPath) -> bool {
+        if path.len() <= self.path.len() || !path.matches_prefix(&self.path) {
+            return false;
+        }
+        match path.segments()[self.path.len()] {
+            PathSegment::Index(index) => !self.in_place.get(index).copied().unwrap_or(false),
+            PathSegment::Key(_) => true,
+        }
+    }
+}
+
+/// The paired arrays with changes below them, outermost first
+///
+/// An array inside an element patched whole is left out, and so is every
+/// array when the changes do not carry their documents.
+fn rearranged_arrays(changes: &Changes) -> Vec<RearrangedArray<'_>> {
+    let (Some(before), Some(after)) = (changes.before.as_ref(), changes.after.as_ref()) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<&JsonPath> = changes.paired_arrays.keys().collect();
+    paths.sort_by_key(|path| path.len());
+
+    let mut arrays: Vec<RearrangedArray> = Vec::new();
+    for path in paths {
+        let changed_below = changes
+            .iter()
+            .any(|change| change.path().len() > path.len() && change.path().matches_prefix(path));
+        if !changed_below || arrays.iter().any(|array| array.rewrites(path)) {
+            continue;
+        }
+        let (Some(Value::Array(old)), Some(Value::Array(new))) =
+            (path.resolve(before), path.resolve(after))
+        else {
+            continue;
+        };
+        let mut in_place = vec![false; old.len().min(new.len())];
+        for &(i, j) in &changes.paired_arrays[path] {
+            if i == j && i < in_place.len() {
+                in_place[i] = true;
+            }
+        }
+        arrays.push(RearrangedArray {
+            path: path.clone(),
+            old,
+            new,
+            in_place,
+        });
+    }
+    arrays
+}
+
+/// The JSON Patch [`JsonPatchFormatter`] writes for `changes`, as a value
+pub(crate) fn changes_to_patch(changes: &Changes) -> Value {
+    let operations = JsonPatchFormatter::operations(changes);
+    serde_json::to_value(operations).unwrap_or_else(|_| Value::Array(Vec::new()))
+}
+
+/// The outermost part of `path` missing from the `absent` document, with its value
+///
+/// `value` is the value at `path` in the `present` document. Without the
+/// documents, or when every ancestor of `path` exists on both sides, `path`
+/// and `value` are returned as they are.
+fn whole_subtree(
+    path: &JsonPath,
+    value: &Value,
+    absent: Option<&Value>,
+    present: Option<&Value>,
+) -> (JsonPath, Value) {
+    let (Some(absent), Some(present)) = (absent, present) else {
+        return (path.clone(), value.clone());
+    };
+    let missing = (1..path.len())
+        .filter_map(|n| path.prefix(n))
+        .find(|prefix| prefix.resolve(absent).is_none());
+    match missing.and_then(|prefix| Some((prefix.resolve(present)?.clone(), prefix))) {
+        Some((whole, prefix)) => (prefix, whole),
+        None => (path.clone(), value.clone()),
+    }
+}
+
+impl Formatter for JsonPatchFormatter {
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+        let operations = Self::operations(changes);
 
         // Serialize the array of operations
         let json = if self.pretty {
@@ -92,6 +259,32 @@ impl Formatter for JsonPatchFormatter {
             Ok(json)
         }
     }
+
+    fn write_to(&self, changes: &Changes, writer: &mut dyn Write) -> Result<(), FormatError> {
+        if !self.pretty && !self.sort {
+            writeln!(writer, "{}", self.format(changes)?)?;
+            return Ok(());
+        }
+
+        let values = Self::operations(changes).into_iter().map(|operation| {
+            let value = serde_json::to_value(operation).unwrap_or(Value::Null);
+            if self.sort {
+                sort_json_value(&value)
+            } else {
+                value
+            }
+        });
+        write_pretty_array(writer, values, 0)?;
+        writeln!(writer)?;
+
+        Ok(())
+    }
+
+    /// Removed subtrees and rearranged arrays are patched whole from the
+    /// first document
+    fn uses_before(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
@@ -396,4 +589,107 @@ mod tests {
         let nested_keys: Vec<&str> = value_obj.keys().map(|s| s.as_str()).collect();
         assert_eq!(nested_keys, vec!["a_key", "z_key"]);
     }
+
+    #[test]
+    fn test_write_to_matches_format() {
+        let mut changes = Changes::new();
+        changes.push(Change::Added {
+            path: "obj".parse().unwrap(),
+            value: serde_json::json!({"z": 1, "a": 2}),
+        });
+        changes.push(Change::Removed {
+            path: "phone".parse().unwrap(),
+            value: Value::String("555-1234".to_string()),
+        });
+
+        for formatter in [
+            JsonPatchFormatter::new(false),
+            JsonPatchFormatter::new(true),
+        ] {
+            let mut out = Vec::new();
+            formatter.write_to(&changes, &mut out).unwrap();
+            let expected = format!("{}\n", formatter.format(&changes).unwrap());
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
+        }
+    }
+
+    fn patch(old: Value, new: Value) -> Value {
+        let mut changes = crate::diff(&old, &new);
+        changes.before = Some(old);
+        let result = JsonPatchFormatter::new(false).format(&changes).unwrap();
+        serde_json::from_str(&result).unwrap()
+    }
+
+    #[test]
+    fn test_format_array_removals_last_first() {
+        use serde_json::json;
+
+        let ops = patch(json!({"a": [1, 2, 3, 4]}), json!({"a": [1, 2]}));
+        assert_eq!(
+            ops,
+            json!([{"op": "remove", "path": "/a/3"}, {"op": "remove", "path": "/a/2"}])
+        );
+        let ops = patch(json!({"a": [1]}), json!({"a": [1, 2, 3]}));
+        assert_eq!(
+            ops,
+            json!([
+                {"op": "add", "path": "/a/1", "value": 2},
+                {"op": "add", "path": "/a/2", "value": 3}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_format_whole_subtrees() {
+        use serde_json::json;
+
+        let old = json!({"keep": {"x": 1}, "gone": [1, {"y": 2}], "list": [{"a": 1}, {"b": 2}]});
+        let new = json!({"keep": {"x": 1, "z": [3]}, "list": [{"a": 1}], "new": {"n": [1]}});
+        assert_eq!(
+            patch(old, new),
+            json!([
+                {"op": "add", "path": "/keep/z", "value": [3]},
+                {"op": "add", "path": "/new", "value": {"n": [1]}},
+                {"op": "remove", "path": "/list/1"},
+                {"op": "remove", "path": "/gone"}
+            ])
+        );
+
+        // Without the documents, each leaf is an operation of its own
+        let mut changes = Changes::new();
+        changes.push(Change::Added {
+            path: "new.n[0]".parse().unwrap(),
+            value: json!(1),
+        });
+        let result = JsonPatchFormatter::new(false).format(&changes).unwrap();
+        let ops: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(ops[0]["path"], "/new/n/0");
+    }
+
+    #[test]
+    fn test_format_paired_arrays_by_index() {
+        use crate::{diff_with_options, ArrayDiff, DiffOptions};
+        use serde_json::json;
+
+        let options = DiffOptions {
+            array_diff: ArrayDiff::Keyed("id".to_string()),
+            ..DiffOptions::default()
+        };
+        let old = json!({"a": [{"id": 1, "n": 1}, {"id": 2, "n": 2}, {"id": 3, "n": 3}]});
+        let new = json!({"a": [{"id": 1, "n": 5}, {"id": 3, "n": 3}]});
+        let mut changes = diff_with_options(&old, &new, &options);
+        changes.before = Some(old.clone());
+        let result = JsonPatchFormatter::new(false).format(&changes).unwrap();
+
+        // The element paired in place keeps its change; the shifted one is
+        // replaced whole, and the last old element removed by its index
+        assert_eq!(
+            serde_json::from_str::<Value>(&result).unwrap(),
+            json!([
+                {"op": "remove", "path": "/a/2"},
+                {"op": "replace", "path": "/a/0/n", "value": 5},
+                {"op": "replace", "path": "/a/1", "value": {"id": 3, "n": 3}}
+            ])
+        );
+    }
 }
@@ -0,0 +1,197 @@
+use crate::error::FormatError;
+use crate::formatter::{sort_json_value, Formatter};
+use crate::types::{Change, ChangeKind, Changes};
+use serde_json::{Map, Value};
+use std::io::Write;
+
+/// Formatter for JSON Lines output, one change per line
+///
+/// Each line is a compact JSON object with the change's `kind` (`added`,
+/// `removed` or `modified`) followed by the fields of its entry in the
+/// changes format: `path`, `value` or `oldValue` and `newValue`, and any
+/// annotations. Where a partial diff stopped and any warnings follow as lines
+/// of kind `truncated` (with a `reason`) and `warning` (with a `message`).
+/// Lines are written one at a time, so `jq` or a log pipeline can consume
+/// them as they arrive; no changes produce no output.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::diff;
+/// use rjd::formatter::{Formatter, JsonlFormatter};
+/// use serde_json::json;
+///
+/// let changes = diff(&json!({"a": 1, "b": 2}), &json!({"a": 3}));
+/// let output = JsonlFormatter::new(false).format(&changes).unwrap();
+/// assert_eq!(
+///     output,
+///     "{\"kind\":\"removed\",\"path\":\"b\",\"value\":2}\n\
+///      {\"kind\":\"modified\",\"path\":\"a\",\"oldValue\":1,\"newValue\":3}"
+/// );
+/// ```
+pub struct JsonlFormatter {
+    sort: bool,
+}
+
+impl JsonlFormatter {
+    /// Create a new JsonlFormatter
+    pub fn new(sort: bool) -> Self {
+        Self { sort }
+    }
+
+    /// The lines of the output, without line endings
+    fn lines<'a>(&'a self, changes: &'a Changes) -> impl Iterator<Item = Value> + 'a {
+        let entries = changes.iter().map(|change| entry(change, changes));
+        let truncations = changes.truncated.iter().map(|truncation| {
+            let mut line = Map::new();
+            line.insert("kind".to_string(), Value::from("truncated"));
+            line.insert(
+                "path".to_string(),
+                Value::String(truncation.path.to_string()),
+            );
+            line.insert(
+                "reason".to_string(),
+                Value::String(truncation.reason.to_string()),
+            );
+            Value::Object(line)
+        });
+        let warnings = changes.warnings.iter().map(|warning| {
+            let mut line = Map::new();
+            line.insert("kind".to_string(), Value::from("warning"));
+            line.insert("path".to_string(), Value::String(warning.path.to_string()));
+            line.insert(
+                "message".to_string(),
+                Value::String(warning.kind.to_string()),
+            );
+            Value::Object(line)
+        });
+        entries.chain(truncations).chain(warnings).map(|line| {
+            if self.sort {
+                sort_json_value(&line)
+            } else {
+                line
+            }
+        })
+    }
+}
+
+impl Default for JsonlFormatter {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+/// The line for a change: its kind, its fields and its annotations
+fn entry(change: &Change, changes: &Changes) -> Value {
+    let kind = match change.kind() {
+        ChangeKind::Added => "added",
+        ChangeKind::Removed => "removed",
+        ChangeKind::Modified => "modified",
+    };
+    let mut line = Map::new();
+    line.insert("kind".to_string(), Value::from(kind));
+    if let Ok(Value::Object(fields)) = serde_json::to_value(change) {
+        line.extend(fields);
+    }
+    if let Some(annotations) = changes.annotations.get(change.path()) {
+        line.extend(annotations.clone());
+    }
+    Value::Object(line)
+}
+
+impl Formatter for JsonlFormatter {
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+        let lines = self
+            .lines(changes)
+            .map(|line| serde_json::to_string(&line))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(lines.join("\n"))
+    }
+
+    fn write_to(&self, changes: &Changes, writer: &mut dyn Write) -> Result<(), FormatError> {
+        for line in self.lines(changes) {
+            serde_json::to_writer(&mut *writer, &line)?;
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    fn content_type(&self) -> &'static str {
+        "application/x-ndjson"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use crate::types::{Truncation, TruncationReason, Warning, WarningKind};
+    use serde_json::json;
+
+    fn parse_lines(output: &str) -> Vec<Value> {
+        output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_one_line_per_change() {
+        let mut changes = diff(
+            &json!({"a": 1, "b": [true]}),
+            &json!({"a": 2, "b": [], "c": {"d": null}}),
+        );
+        changes
+            .annotations
+            .entry("a".parse().unwrap())
+            .or_default()
+            .insert("severity".to_string(), json!("warn"));
+
+        let output = JsonlFormatter::new(false).format(&changes).unwrap();
+        assert_eq!(
+            parse_lines(&output),
+            [
+                json!({"kind": "added", "path": "c.d", "value": null}),
+                json!({"kind": "removed", "path": "b[0]", "value": true}),
+                json!({"kind": "modified", "path": "a", "oldValue": 1, "newValue": 2, "severity": "warn"}),
+            ]
+        );
+
+        let mut streamed = Vec::new();
+        JsonlFormatter::new(false)
+            .write_to(&changes, &mut streamed)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(streamed).unwrap(),
+            format!("{}\n", output)
+        );
+    }
+
+    #[test]
+    fn test_truncations_warnings_and_sorting() {
+        let mut changes = diff(&json!({"z": 1}), &json!({"z": 2}));
+        changes.truncated.push(Truncation {
+            path: "x".parse().unwrap(),
+            reason: TruncationReason::MaxChanges(1),
+        });
+        changes.warnings.push(Warning {
+            path: "y".parse().unwrap(),
+            kind: WarningKind::PrecisionLoss,
+        });
+
+        let output = JsonlFormatter::new(true).format(&changes).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            r#"{"kind":"modified","newValue":2,"oldValue":1,"path":"z"}"#
+        );
+        assert_eq!(parse_lines(&output)[1]["kind"], "truncated");
+        assert_eq!(parse_lines(&output)[2]["kind"], "warning");
+
+        let mut empty = Vec::new();
+        JsonlFormatter::new(false)
+            .write_to(&Changes::new(), &mut empty)
+            .unwrap();
+        assert!(empty.is_empty());
+    }
+}
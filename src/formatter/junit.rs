@@ -0,0 +1,166 @@
+use crate::error::FormatError;
+use crate::formatter::{describe_change, description, Formatter};
+use crate::types::{Change, ChangeKind, Changes};
+use std::fmt::Write;
+
+/// Formatter for JUnit XML output, understood by CI test-report UIs
+///
+/// Each change is a failed test case named after the changed value's
+/// JSONPath, with the change kind as its class name. Identical documents
+/// produce a suite with a single passing test case for the root (`$`), so CI
+/// systems show a green report instead of an empty one.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::formatter::{Formatter, JunitFormatter};
+/// use rjd::diff;
+/// use serde_json::json;
+///
+/// let changes = diff(&json!({"port": 80}), &json!({"port": 8080}));
+/// let xml = JunitFormatter::new(None).format(&changes).unwrap();
+/// assert!(xml.contains(r#"<testcase name="$.port" classname="modified">"#));
+/// assert!(xml.contains(r#"failures="1""#));
+/// ```
+pub struct JunitFormatter {
+    suite: Option<String>,
+}
+
+impl JunitFormatter {
+    /// Create a formatter; `suite` names the test suite (default `rjd`)
+    pub fn new(suite: Option<String>) -> Self {
+        Self { suite }
+    }
+}
+
+impl Formatter for JunitFormatter {
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+        let failures = changes.len();
+        let tests = failures.max(1);
+        let suite = escape(self.suite.as_deref().unwrap_or("rjd"));
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        writeln!(
+            xml,
+            r#"<testsuites name="rjd" tests="{tests}" failures="{failures}" errors="0">"#
+        )?;
+        writeln!(
+            xml,
+            r#"  <testsuite name="{suite}" tests="{tests}" failures="{failures}" errors="0" skipped="0">"#
+        )?;
+        if changes.is_empty() {
+            writeln!(xml, r#"    <testcase name="$" classname="{suite}"/>"#)?;
+        }
+        for change in changes {
+            write_case(&mut xml, change, description(change, changes))?;
+        }
+        writeln!(xml, "  </testsuite>")?;
+        write!(xml, "</testsuites>")?;
+        Ok(xml)
+    }
+
+    fn content_type(&self) -> &'static str {
+        "application/xml"
+    }
+}
+
+/// Write a failing test case for `change`, with `description` as its message
+/// if it has one
+fn write_case(xml: &mut String, change: &Change, description: Option<&str>) -> std::fmt::Result {
+    let kind = match change.kind() {
+        ChangeKind::Added => "added",
+        ChangeKind::Removed => "removed",
+        ChangeKind::Modified => "modified",
+    };
+    let details = match change {
+        Change::Added { value, .. } => format!("new: {}", value),
+        Change::Removed { value, .. } => format!("old: {}", value),
+        Change::Modified {
+            old_value,
+            new_value,
+            ..
+        } => format!("old: {}\nnew: {}", old_value, new_value),
+    };
+
+    writeln!(
+        xml,
+        r#"    <testcase name="{}" classname="{}">"#,
+        escape(&change.path().to_jsonpath()),
+        kind
+    )?;
+    writeln!(
+        xml,
+        r#"      <failure type="{}" message="{}">{}</failure>"#,
+        kind,
+        escape(&description.map_or_else(|| describe_change(change), String::from)),
+        escape(&details)
+    )?;
+    writeln!(xml, "    </testcase>")
+}
+
+/// Escape text for XML content and attributes
+///
+/// Characters XML 1.0 cannot represent at all (most control characters) are
+/// replaced with U+FFFD; they can only come from object keys, since values
+/// are written as JSON text.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\t' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 || c == '\u{FFFE}' || c == '\u{FFFF}' => {
+                escaped.push('\u{FFFD}')
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    #[test]
+    fn test_junit_failures() {
+        let old = json!({"name": "app", "port": 80, "<tag>": "a & b"});
+        let new = json!({"name": "app", "port": 8080, "debug": true});
+        let xml = JunitFormatter::new(Some("new.json".to_string()))
+            .format(&diff(&old, &new))
+            .unwrap();
+
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+        assert!(xml.contains(
+            r#"<testsuite name="new.json" tests="3" failures="3" errors="0" skipped="0">"#
+        ));
+        assert!(xml.contains(r#"<testcase name="$.debug" classname="added">"#));
+        assert!(xml.contains(
+            r#"<failure type="modified" message="Modified $.port: 80 -&gt; 8080">old: 80&#10;new: 8080</failure>"#
+        ));
+        assert!(xml.contains(r#"<testcase name="$[&apos;&lt;tag&gt;&apos;]" classname="removed">"#));
+        assert!(xml.contains("old: &quot;a &amp; b&quot;"));
+        assert!(xml.ends_with("</testsuites>"));
+    }
+
+    #[test]
+    fn test_junit_passing_suite() {
+        let xml = JunitFormatter::new(None).format(&Changes::new()).unwrap();
+        assert!(xml.contains(r#"<testsuites name="rjd" tests="1" failures="0" errors="0">"#));
+        assert!(xml.contains(r#"<testcase name="$" classname="rjd"/>"#));
+        assert!(!xml.contains("<failure"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a<b>&\"c'"), "a&lt;b&gt;&amp;&quot;c&apos;");
+        assert_eq!(escape("x\u{1}y\tz"), "x\u{FFFD}y\tz");
+    }
+}
@@ -0,0 +1,120 @@
+use crate::datetime::format_timestamp;
+use crate::error::FormatError;
+use crate::formatter::Formatter;
+use crate::types::Changes;
+use serde_json::{json, Map, Value};
+#[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds since the Unix epoch
+///
+/// `wasm32-unknown-unknown` has no system clock, so the WebAssembly build
+/// asks JavaScript for the time.
+fn now_seconds() -> i64 {
+    #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+    return (js_sys::Date::now() / 1000.0) as i64;
+
+    #[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+/// Formatter decorator that wraps JSON output in a self-describing envelope
+///
+/// The output becomes `{"metadata": {...}, "result": <inner output>}`, where
+/// the metadata holds the tool name and version, the UTC time the output was
+/// generated, the caller's fields (e.g. inputs and options) and the change
+/// counts. Formats whose output is not JSON cannot be wrapped.
+pub(crate) struct MetadataFormatter {
+    pub(crate) inner: Box<dyn Formatter>,
+    pub(crate) fields: Map<String, Value>,
+}
+
+impl MetadataFormatter {
+    fn metadata(&self, changes: &Changes) -> Value {
+        let seconds = now_seconds();
+
+        let mut metadata = Map::new();
+        metadata.insert(
+            "tool".to_string(),
+            json!({"name": "rjd", "version": env!("CARGO_PKG_VERSION")}),
+        );
+        metadata.insert(
+            "generatedAt".to_string(),
+            Value::String(format_timestamp(seconds)),
+        );
+        metadata.extend(self.fields.clone());
+        metadata.insert(
+            "changes".to_string(),
+            json!({
+                "added": changes.added.len(),
+                "removed": changes.removed.len(),
+                "modified": changes.modified.len(),
+                "total": changes.len()
+            }),
+        );
+        Value::Object(metadata)
+    }
+}
+
+impl Formatter for MetadataFormatter {
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+        let result: Value = serde_json::from_str(&self.inner.format(changes)?).map_err(|_| {
+            FormatError::Unsupported {
+                message: "the metadata envelope needs a JSON output format".to_string(),
+            }
+        })?;
+        let envelope = json!({
+            "metadata": self.metadata(changes),
+            "result": result
+        });
+        Ok(serde_json::to_string_pretty(&envelope)?)
+    }
+
+    fn uses_before(&self) -> bool {
+        self.inner.uses_before()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use crate::formatter::{JsonPatchFormatter, JunitFormatter};
+
+    #[test]
+    fn test_envelope() {
+        let mut fields = Map::new();
+        fields.insert(
+            "inputs".to_string(),
+            json!({"old": "a.json", "new": "b.json"}),
+        );
+        let formatter = MetadataFormatter {
+            inner: Box::new(JsonPatchFormatter::new(false)),
+            fields,
+        };
+        let changes = diff(&json!({"a": 1, "b": 2}), &json!({"a": 2, "c": 3}));
+        let output: Value = serde_json::from_str(&formatter.format(&changes).unwrap()).unwrap();
+
+        let metadata = &output["metadata"];
+        assert_eq!(metadata["tool"]["name"], "rjd");
+        assert_eq!(metadata["inputs"]["new"], "b.json");
+        assert_eq!(
+            metadata["changes"],
+            json!({"added": 1, "removed": 1, "modified": 1, "total": 3})
+        );
+        let generated_at = metadata["generatedAt"].as_str().unwrap();
+        assert!(crate::datetime::parse_timestamp(generated_at).is_some());
+        assert_eq!(output["result"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_envelope_needs_json_output() {
+        let formatter = MetadataFormatter {
+            inner: Box::new(JunitFormatter::new(None)),
+            fields: Map::new(),
+        };
+        assert!(formatter.format(&Changes::new()).is_err());
+    }
+}
@@ -5,30 +5,171 @@
 //! object with added, removed, and modified arrays.
 
 mod after;
+mod annotated;
+mod before;
 mod changes;
+mod context;
+mod full_after;
+mod gcc;
 mod json_patch;
+mod jsonl;
+mod junit;
+mod metadata;
+mod msgpack;
 mod path_filter;
 pub mod path_parser;
+mod registry;
+mod sarif;
+pub mod schema;
+mod summary;
+#[cfg(feature = "template")]
+mod template;
+mod truncate;
 mod util;
 
 pub use after::AfterFormatter;
+pub use annotated::AnnotatedFormatter;
+pub use before::BeforeFormatter;
 pub use changes::ChangesFormatter;
+pub use full_after::FullAfterFormatter;
+pub use gcc::GccFormatter;
+pub(crate) use json_patch::changes_to_patch;
 pub use json_patch::JsonPatchFormatter;
+pub use jsonl::JsonlFormatter;
+pub use junit::JunitFormatter;
+pub use msgpack::MsgpackFormatter;
+pub use registry::{FormatterFactory, FormatterRegistry};
+pub use sarif::SarifFormatter;
+pub use summary::{SummaryFormatter, DEFAULT_TOP_K};
+#[cfg(feature = "template")]
+pub use template::TemplateFormatter;
+pub use truncate::truncate_value;
 pub use util::sort_json_value;
+use util::{describe_change, description, write_pretty_array};
 
-use crate::error::FormatterError;
+pub use crate::error::{FormatError, FormatterError};
+use serde_json::{Map, Value};
+
+/// Current version of the "changes" output format
+///
+/// Version 2 adds a top-level `formatVersion` field. Version 1 is the original
+/// layout with only the `added`, `removed`, and `modified` arrays.
+pub const CHANGES_FORMAT_VERSION: u32 = 2;
+
+/// Names of the built-in output formats, for error messages
+#[cfg(feature = "template")]
+pub(crate) const VALID_FORMATS: &str =
+    "changes, after, before, full-after, annotated, rfc6902, sarif, junit, gcc, summary, jsonl, msgpack, template";
+#[cfg(not(feature = "template"))]
+pub(crate) const VALID_FORMATS: &str =
+    "changes, after, before, full-after, annotated, rfc6902, sarif, junit, gcc, summary, jsonl, msgpack";
+
+/// Options controlling how formatters render their output
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::formatter::{create_formatter_with_options, FormatterOptions};
+///
+/// // Emit the legacy (version 1) changes layout
+/// let options = FormatterOptions {
+///     format_version: 1,
+///     ..FormatterOptions::default()
+/// };
+/// let formatter = create_formatter_with_options("changes", &options).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatterOptions {
+    /// Sort keys alphabetically in JSON output
+    pub sort: bool,
+    /// Version of the "changes" output format (1 or 2)
+    pub format_version: u32,
+    /// Number of entries in each "largest changes" list of the summary format
+    pub top_k: usize,
+    /// Truncate reported values whose JSON text is longer than this
+    pub max_value_length: Option<usize>,
+    /// Levels of unchanged surrounding keys to show with each change (0 = none)
+    pub context: usize,
+    /// Group changes under their ancestors this many path segments deep
+    /// (changes format only; see [`group_changes`](crate::group_changes))
+    pub group_depth: Option<usize>,
+    /// Text of the first input, whose layout the full-after format keeps
+    pub original: Option<String>,
+    /// URI of the second input when it is a file: the location of SARIF
+    /// results and the name of the JUnit test suite
+    pub artifact: Option<String>,
+    /// Wrap the output in `{"metadata": ..., "result": ...}`, with these
+    /// fields added to the metadata (JSON output formats only)
+    pub metadata: Option<Map<String, Value>>,
+    /// Source text of the template the template format renders
+    pub template: Option<String>,
+}
+
+impl FormatterOptions {
+    /// Create options with the given sort flag and defaults for everything else
+    pub fn new(sort: bool) -> Self {
+        Self {
+            sort,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for FormatterOptions {
+    fn default() -> Self {
+        Self {
+            sort: false,
+            format_version: CHANGES_FORMAT_VERSION,
+            top_k: DEFAULT_TOP_K,
+            max_value_length: None,
+            context: 0,
+            group_depth: None,
+            original: None,
+            artifact: None,
+            metadata: None,
+            template: None,
+        }
+    }
+}
 
 /// Trait for formatting diff results
 pub trait Formatter {
     /// Format the changes and return a string representation
-    fn format(&self, changes: &crate::types::Changes)
-        -> Result<String, Box<dyn std::error::Error>>;
+    fn format(&self, changes: &crate::types::Changes) -> Result<String, FormatError>;
+
+    /// Write the formatted changes, followed by a newline, to `writer`
+    ///
+    /// Formatters that can render changes one at a time override this so
+    /// output reaches the writer incrementally. The default writes the result
+    /// of [`Formatter::format`] in one go.
+    fn write_to(
+        &self,
+        changes: &crate::types::Changes,
+        writer: &mut dyn std::io::Write,
+    ) -> Result<(), FormatError> {
+        writeln!(writer, "{}", self.format(changes)?)?;
+        Ok(())
+    }
+
+    /// Whether the output depends on [`Changes::before`](crate::Changes::before)
+    ///
+    /// The diff functions leave the first document out of their result, so
+    /// callers copy it in for formatters that read it. The default is `false`.
+    fn uses_before(&self) -> bool {
+        false
+    }
+
+    /// The media type of the output, sent as `Content-Type` when the report
+    /// is uploaded. The default is `application/json`.
+    fn content_type(&self) -> &'static str {
+        "application/json"
+    }
 }
 
 /// Factory function to create a formatter based on output format string
 ///
 /// # Arguments
-/// * `format_str` - One of "changes", "after", or "rfc6902"
+/// * `format_str` - The name of a built-in format, e.g. "changes", "after", or "rfc6902"
 /// * `sort` - Whether to sort keys in JSON output
 ///
 /// # Returns
@@ -36,20 +177,27 @@ pub trait Formatter {
 /// * `Err(FormatterError)` - If the format string is invalid
 ///
 /// # Errors
-/// Returns an error if format_str is not one of: "changes", "after", or "rfc6902"
+/// Returns an error if format_str is not a built-in format
 pub fn create_formatter(
     format_str: &str,
     sort: bool,
 ) -> Result<Box<dyn Formatter>, FormatterError> {
-    match format_str {
-        "changes" => Ok(Box::new(ChangesFormatter::new(sort))),
-        "after" => Ok(Box::new(AfterFormatter::new(sort))),
-        "rfc6902" => Ok(Box::new(JsonPatchFormatter::new(sort))),
-        _ => Err(FormatterError::UnknownFormat {
-            format: format_str.to_string(),
-            valid: "changes, after, rfc6902".to_string(),
-        }),
-    }
+    create_formatter_with_options(format_str, &FormatterOptions::new(sort))
+}
+
+/// Factory function to create a formatter with explicit output options
+///
+/// Only built-in formats are available; use a [`FormatterRegistry`] to
+/// create custom formats.
+///
+/// # Errors
+/// Returns an error if format_str is not a known format, or if the requested
+/// changes format version is not supported.
+pub fn create_formatter_with_options(
+    format_str: &str,
+    options: &FormatterOptions,
+) -> Result<Box<dyn Formatter>, FormatterError> {
+    FormatterRegistry::new().create(format_str, options)
 }
 
 #[cfg(test)]
@@ -67,6 +215,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_content_types() {
+        let content_type = |format: &str| {
+            create_formatter_with_options(
+                format,
+                &FormatterOptions {
+                    max_value_length: Some(10),
+                    ..FormatterOptions::new(false)
+                },
+            )
+            .unwrap()
+            .content_type()
+        };
+        assert_eq!(content_type("changes"), "application/json");
+        assert_eq!(content_type("jsonl"), "application/x-ndjson");
+        assert_eq!(content_type("sarif"), "application/sarif+json");
+        assert_eq!(content_type("msgpack"), "application/msgpack");
+        // Decorators report the media type of the format they wrap
+        assert_eq!(content_type("junit"), "application/xml");
+        assert_eq!(content_type("gcc"), "text/plain; charset=utf-8");
+    }
+
     #[test]
     fn test_create_formatter_invalid_format() {
         let result = create_formatter("invalid", false);
@@ -94,6 +264,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_create_formatter_with_unsupported_version() {
+        let options = FormatterOptions {
+            format_version: 7,
+            ..FormatterOptions::default()
+        };
+        let result = create_formatter_with_options("changes", &options);
+        assert!(matches!(
+            result,
+            Err(FormatterError::UnsupportedFormatVersion { version: 7, .. })
+        ));
+    }
+
     #[test]
     fn test_create_formatter_json_format() {
         // Test with "json" which is a common mistake
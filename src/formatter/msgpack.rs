@@ -0,0 +1,80 @@
+use crate::error::FormatError;
+use crate::formatter::Formatter;
+use crate::types::Changes;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::io::Write;
+
+/// Formatter for MessagePack output
+///
+/// Writes the changes as a binary change set ([`Changes::to_msgpack`]),
+/// without the documents, which [`Changes::from_msgpack`] reads back. The
+/// output is the raw bytes with no trailing newline; since
+/// [`Formatter::format`] returns text, it returns them base64-encoded.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::formatter::{Formatter, MsgpackFormatter};
+/// use rjd::{diff, Changes};
+/// use serde_json::json;
+///
+/// let changes = diff(&json!({"a": 1}), &json!({"a": 2}));
+/// let mut packed = Vec::new();
+/// MsgpackFormatter::new().write_to(&changes, &mut packed).unwrap();
+/// assert_eq!(Changes::from_msgpack(&packed).unwrap().modified, changes.modified);
+/// ```
+pub struct MsgpackFormatter;
+
+impl MsgpackFormatter {
+    /// Create a new MsgpackFormatter
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for MsgpackFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Formatter for MsgpackFormatter {
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+        Ok(STANDARD.encode(changes.to_msgpack(false)))
+    }
+
+    fn write_to(&self, changes: &Changes, writer: &mut dyn Write) -> Result<(), FormatError> {
+        writer.write_all(&changes.to_msgpack(false))?;
+        Ok(())
+    }
+
+    fn content_type(&self) -> &'static str {
+        "application/msgpack"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_is_base64_of_output() {
+        let mut changes = diff(&json!({"a": [1, 2]}), &json!({"a": [1], "b": "x"}));
+        changes.before = Some(json!({"a": [1, 2]}));
+
+        let mut packed = Vec::new();
+        MsgpackFormatter::new()
+            .write_to(&changes, &mut packed)
+            .unwrap();
+        let text = MsgpackFormatter::new().format(&changes).unwrap();
+        assert_eq!(STANDARD.decode(text).unwrap(), packed);
+
+        let loaded = Changes::from_msgpack(&packed).unwrap();
+        assert_eq!(loaded.added, changes.added);
+        assert_eq!(loaded.removed, changes.removed);
+        assert_eq!(loaded.before, None);
+    }
+}
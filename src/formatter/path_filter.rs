@@ -1,13 +1,224 @@
-//! Path filtering and manipulation utilities
+//! Filtering a document down to the parts that changed
 //!
-//! This module provides utilities for working with JSON paths.
-//!
-//! **Note**: After optimization in Task 6, the legacy path manipulation functions
-//! have been removed in favor of PathParser and single-pass traversal.
+//! Shared by the "after" and "before" formatters, which show the changed
+//! parts of the second and first document respectively.
+
+use crate::formatter::context::{context_for, merge_context};
+use crate::json_path::{JsonPath, PathSegment};
+use crate::types::{Change, Changes};
+use serde_json::{Map, Value};
+
+use std::collections::HashSet;
+
+/// Keep only the parts of `document` at or around the `changed` paths
+///
+/// Arrays on the way to a change are kept whole, and a changed container is
+/// kept with all its children. With `context` levels, unchanged keys around
+/// every change in `changes` are added as well. Returns an empty object if
+/// nothing changed.
+pub(crate) fn filter_document<'a>(
+    document: &Value,
+    changed: impl IntoIterator<Item = &'a Change>,
+    changes: &Changes,
+    context: usize,
+) -> Value {
+    // Build a set of all changed paths for O(1) comparison
+    let changed_paths_segments: HashSet<Vec<PathSegment>> = changed
+        .into_iter()
+        .map(|change| change.path().segments().to_vec())
+        .collect();
+
+    // Use single-pass traversal
+    let root_path = JsonPath::new();
+    let mut filtered =
+        collect_and_filter_single_pass(document, &root_path, &changed_paths_segments)
+            .unwrap_or(Value::Object(Map::new()));
+
+    if context > 0 {
+        for change in changes {
+            if let Some((path, levels)) = context_for(document, change.path(), context) {
+                add_context(&mut filtered, document, path.segments(), &levels);
+            }
+        }
+    }
+    filtered
+}
+
+/// Check if a path or any of its descendants are in the changed paths set
+///
+/// This function determines whether a JSON node should be included in the filtered
+/// output by checking if either the exact path matches or if any descendant paths
+/// start with this path as a prefix.
+fn path_or_descendants_changed(path: &JsonPath, changed_paths: &HashSet<Vec<PathSegment>>) -> bool {
+    // Check exact match
+    if changed_paths.contains(path.segments()) {
+        return true;
+    }
+
+    // Check if any changed path is a descendant of this path
+    // A path is a descendant if it starts with all segments of this path
+    for changed in changed_paths {
+        if changed.len() >= path.len() && changed[..path.len()] == path.segments()[..] {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Collect and filter in a single pass through the value tree
+///
+/// This optimized function traverses the JSON value exactly once, building the
+/// filtered output directly during traversal. It includes a node if:
+/// - The node's path is in changed_paths, OR
+/// - Any descendant of the node is in changed_paths
+///
+/// When an object/array is directly in changed_paths, ALL its children are included
+/// (not just changed ones). This matches the old behavior where the entire structure
+/// is preserved when the parent is marked as changed.
+///
+/// This eliminates the need for separate path collection and value building phases.
+fn collect_and_filter_single_pass(
+    value: &Value,
+    current_path: &JsonPath,
+    changed_paths: &HashSet<Vec<PathSegment>>,
+) -> Option<Value> {
+    match value {
+        Value::Object(map) => {
+            // Check if this object or any of its descendants are changed
+            let object_or_descendants_changed =
+                path_or_descendants_changed(current_path, changed_paths);
+            let object_directly_changed = changed_paths.contains(current_path.segments());
+
+            if object_or_descendants_changed {
+                let mut filtered_map = Map::new();
+
+                for (key, child_value) in map {
+                    // Build path for this child
+                    let mut child_path = current_path.clone();
+                    child_path.push(PathSegment::Key(key.clone()));
+
+                    // Check if this child path or any descendants are changed
+                    let child_or_descendants_changed =
+                        path_or_descendants_changed(&child_path, changed_paths);
+
+                    if child_or_descendants_changed || object_directly_changed {
+                        // If parent object is directly changed, include all children without filtering
+                        // Otherwise, recurse normally to filter
+                        if object_directly_changed {
+                            // Include child as-is (don't filter further)
+                            filtered_map.insert(key.clone(), child_value.clone());
+                        } else {
+                            // Recurse into child with filtering
+                            if let Some(filtered_child) = collect_and_filter_single_pass(
+                                child_value,
+                                &child_path,
+                                changed_paths,
+                            ) {
+                                filtered_map.insert(key.clone(), filtered_child);
+                            }
+                        }
+                    }
+                }
+
+                Some(Value::Object(filtered_map))
+            } else {
+                None
+            }
+        }
+        Value::Array(arr) => {
+            // Check if this array or any of its elements/descendants are changed
+            let array_or_descendants_changed =
+                path_or_descendants_changed(current_path, changed_paths);
+            let array_directly_changed = changed_paths.contains(current_path.segments());
+
+            if array_or_descendants_changed {
+                // Include ALL elements of the array
+                // This matches the old behavior where the entire array is shown
+                let mut filtered_arr = Vec::new();
+
+                for (i, child_value) in arr.iter().enumerate() {
+                    if array_directly_changed {
+                        // Include element as-is (don't filter further)
+                        filtered_arr.push(child_value.clone());
+                    } else {
+                        // Recursively filter child elements
+                        let mut child_path = current_path.clone();
+                        child_path.push(PathSegment::Index(i));
+
+                        if let Some(filtered_child) =
+                            collect_and_filter_single_pass(child_value, &child_path, changed_paths)
+                        {
+                            filtered_arr.push(filtered_child);
+                        } else {
+                            // Primitive value not in changed paths, but descendant changed
+                            // Include it anyway since we're in an array that has changed descendants
+                            filtered_arr.push(child_value.clone());
+                        }
+                    }
+                }
+
+                Some(Value::Array(filtered_arr))
+            } else {
+                None
+            }
+        }
+        // Primitive values are included if path is in changed_paths
+        _ => {
+            if changed_paths.contains(current_path.segments()) {
+                Some(value.clone())
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Merge `context` into `target` at `path`, following the shape of `document`
+///
+/// Objects missing from `target` along the way are created empty; missing
+/// arrays are copied whole, as the filtered output always shows arrays in full.
+fn add_context(target: &mut Value, document: &Value, path: &[PathSegment], context: &Value) {
+    let Some((segment, rest)) = path.split_first() else {
+        merge_context(target, context);
+        return;
+    };
+    match (segment, target, document) {
+        (PathSegment::Key(key), Value::Object(target), Value::Object(document)) => {
+            let Some(child) = document.get(key) else {
+                return;
+            };
+            let entry = target.entry(key.clone()).or_insert_with(|| match child {
+                Value::Array(_) => child.clone(),
+                _ => Value::Object(Map::new()),
+            });
+            add_context(entry, child, rest, context);
+        }
+        (PathSegment::Index(index), Value::Array(target), Value::Array(document)) => {
+            if let (Some(entry), Some(child)) = (target.get_mut(*index), document.get(*index)) {
+                add_context(entry, child, rest, context);
+            }
+        }
+        _ => {}
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    // Tests removed along with unused functions
-    // PathParser is now tested in path_parser.rs
-    // AfterFormatter is tested in after.rs
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_filter_document() {
+        let document = json!({"a": {"b": 1, "c": 2}, "list": [1, {"x": 1, "y": 2}], "d": 3});
+        let changes: Changes = crate::diff(
+            &json!({"a": {"b": 0, "c": 2}, "list": [1, {"x": 1, "y": 0}], "d": 3}),
+            &document,
+        );
+        assert_eq!(
+            filter_document(&document, changes.iter(), &changes, 0),
+            json!({"a": {"b": 1}, "list": [1, {"y": 2}]})
+        );
+        assert_eq!(filter_document(&document, [], &changes, 0), json!({}));
+    }
 }
@@ -0,0 +1,262 @@
+use crate::error::FormatterError;
+use crate::formatter::metadata::MetadataFormatter;
+use crate::formatter::truncate::TruncatingFormatter;
+use crate::formatter::{
+    AfterFormatter, AnnotatedFormatter, BeforeFormatter, ChangesFormatter, Formatter,
+    FormatterOptions, FullAfterFormatter, GccFormatter, JsonPatchFormatter, JsonlFormatter,
+    JunitFormatter, MsgpackFormatter, SarifFormatter, SummaryFormatter,
+};
+
+/// Function that builds a formatter from the output options
+pub type FormatterFactory =
+    dyn Fn(&FormatterOptions) -> Result<Box<dyn Formatter>, FormatterError> + Send + Sync;
+
+/// Output formats by name
+///
+/// [`FormatterRegistry::new`] contains the built-in formats; library users
+/// can [`register`](FormatterRegistry::register) their own [`Formatter`]
+/// implementations next to them.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::formatter::{FormatError, Formatter, FormatterOptions, FormatterRegistry};
+/// use rjd::{diff, Changes};
+/// use serde_json::json;
+///
+/// struct CountFormatter;
+///
+/// impl Formatter for CountFormatter {
+///     fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+///         Ok(changes.len().to_string())
+///     }
+/// }
+///
+/// let mut registry = FormatterRegistry::new();
+/// registry.register("count", |_| Ok(Box::new(CountFormatter)));
+///
+/// let formatter = registry.create("count", &FormatterOptions::default()).unwrap();
+/// let changes = diff(&json!({"a": 1}), &json!({"a": 2, "b": 3}));
+/// assert_eq!(formatter.format(&changes).unwrap(), "2");
+/// ```
+pub struct FormatterRegistry {
+    /// Factories in registration order
+    factories: Vec<(String, Box<FormatterFactory>)>,
+}
+
+impl FormatterRegistry {
+    /// Create a registry with the built-in formats
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry
+            .register("changes", |options| {
+                Ok(Box::new(
+                    ChangesFormatter::with_version(options.sort, options.format_version)?
+                        .with_context(options.context)
+                        .with_grouping(options.group_depth),
+                ))
+            })
+            .register("after", |options| {
+                Ok(Box::new(
+                    AfterFormatter::new(options.sort).with_context(options.context),
+                ))
+            })
+            .register("before", |options| {
+                Ok(Box::new(
+                    BeforeFormatter::new(options.sort).with_context(options.context),
+                ))
+            })
+            .register("full-after", |options| {
+                Ok(Box::new(FullAfterFormatter::new(options.original.clone())))
+            })
+            .register("annotated", |options| {
+                Ok(Box::new(AnnotatedFormatter::new(options.sort)))
+            })
+            .register("rfc6902", |options| {
+                Ok(Box::new(JsonPatchFormatter::new(options.sort)))
+            })
+            .register("sarif", |options| {
+                Ok(Box::new(SarifFormatter::new(
+                    options.sort,
+                    options.artifact.clone(),
+                )))
+            })
+            .register("junit", |options| {
+                Ok(Box::new(JunitFormatter::new(options.artifact.clone())))
+            })
+            .register("gcc", |options| {
+                Ok(Box::new(GccFormatter::new(options.artifact.clone())))
+            })
+            .register("summary", |options| {
+                Ok(Box::new(SummaryFormatter::new(options.sort, options.top_k)))
+            })
+            .register("jsonl", |options| {
+                Ok(Box::new(JsonlFormatter::new(options.sort)))
+            })
+            .register("msgpack", |_| Ok(Box::new(MsgpackFormatter::new())));
+        #[cfg(feature = "template")]
+        registry.register("template", |options| {
+            let template = options
+                .template
+                .as_deref()
+                .ok_or(FormatterError::MissingTemplate)?;
+            Ok(Box::new(crate::formatter::TemplateFormatter::new(
+                template,
+            )?))
+        });
+        registry
+    }
+
+    /// Create a registry without any formats
+    pub fn empty() -> Self {
+        Self {
+            factories: Vec::new(),
+        }
+    }
+
+    /// Register a format, replacing any format already registered under `name`
+    pub fn register<F>(&mut self, name: impl Into<String>, factory: F) -> &mut Self
+    where
+        F: Fn(&FormatterOptions) -> Result<Box<dyn Formatter>, FormatterError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let name = name.into();
+        let factory: Box<FormatterFactory> = Box::new(factory);
+        match self.factories.iter_mut().find(|(known, _)| *known == name) {
+            Some(entry) => entry.1 = factory,
+            None => self.factories.push((name, factory)),
+        }
+        self
+    }
+
+    /// Check whether a format is registered under `name`
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.iter().any(|(known, _)| known == name)
+    }
+
+    /// Names of the registered formats, in registration order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Create the formatter registered under `name`
+    ///
+    /// The `max_value_length` and `metadata` options apply to every format:
+    /// the formatter is wrapped to truncate values and to add the metadata
+    /// envelope.
+    ///
+    /// # Errors
+    /// Returns an error if no format is registered under `name`, or if the
+    /// format's factory rejects the options.
+    pub fn create(
+        &self,
+        name: &str,
+        options: &FormatterOptions,
+    ) -> Result<Box<dyn Formatter>, FormatterError> {
+        let Some((_, factory)) = self.factories.iter().find(|(known, _)| known == name) else {
+            return Err(FormatterError::UnknownFormat {
+                format: name.to_string(),
+                valid: self.names().collect::<Vec<_>>().join(", "),
+            });
+        };
+        let formatter = factory(options)?;
+
+        let formatter: Box<dyn Formatter> = match options.max_value_length {
+            Some(max_length) => Box::new(TruncatingFormatter {
+                inner: formatter,
+                max_length,
+            }),
+            None => formatter,
+        };
+        Ok(match &options.metadata {
+            Some(fields) => Box::new(MetadataFormatter {
+                inner: formatter,
+                fields: fields.clone(),
+            }),
+            None => formatter,
+        })
+    }
+}
+
+impl Default for FormatterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::FormatError;
+    use crate::formatter::VALID_FORMATS;
+    use crate::types::Changes;
+
+    struct NameFormatter(&'static str);
+
+    impl Formatter for NameFormatter {
+        fn format(&self, _changes: &Changes) -> Result<String, FormatError> {
+            Ok(format!("\"{}\"", self.0))
+        }
+    }
+
+    #[test]
+    fn test_builtin_formats() {
+        let registry = FormatterRegistry::new();
+        assert_eq!(
+            registry.names().collect::<Vec<_>>().join(", "),
+            VALID_FORMATS
+        );
+        assert!(registry.contains("rfc6902"));
+        assert!(!FormatterRegistry::empty().contains("changes"));
+    }
+
+    #[test]
+    fn test_register_and_replace() {
+        let mut registry = FormatterRegistry::new();
+        registry
+            .register("custom", |_| Ok(Box::new(NameFormatter("first"))))
+            .register("custom", |_| Ok(Box::new(NameFormatter("second"))));
+        assert_eq!(registry.names().filter(|n| *n == "custom").count(), 1);
+        assert_eq!(registry.names().last(), Some("custom"));
+
+        let options = FormatterOptions::default();
+        let output = registry
+            .create("custom", &options)
+            .unwrap()
+            .format(&Changes::new())
+            .unwrap();
+        assert_eq!(output, "\"second\"");
+    }
+
+    #[test]
+    fn test_custom_formats_are_wrapped() {
+        let mut registry = FormatterRegistry::empty();
+        registry.register("name", |_| Ok(Box::new(NameFormatter("name"))));
+        let options = FormatterOptions {
+            metadata: Some(serde_json::Map::new()),
+            ..FormatterOptions::default()
+        };
+        let output = registry
+            .create("name", &options)
+            .unwrap()
+            .format(&Changes::new())
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["result"], "name");
+    }
+
+    #[test]
+    fn test_unknown_format_lists_registered_names() {
+        let mut registry = FormatterRegistry::empty();
+        registry.register("only", |_| Ok(Box::new(NameFormatter("only"))));
+        match registry.create("changes", &FormatterOptions::default()) {
+            Err(FormatterError::UnknownFormat { format, valid }) => {
+                assert_eq!(format, "changes");
+                assert_eq!(valid, "only");
+            }
+            _ => panic!("Expected UnknownFormat error"),
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use crate::error::FormatError;
+use crate::formatter::{describe_change, description, sort_json_value, Formatter};
+use crate::types::{Change, ChangeKind, Changes};
+use serde_json::{json, Value};
+
+/// URI of the SARIF 2.1.0 JSON schema
+pub(crate) const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Formatter for SARIF 2.1.0 output, understood by code-scanning UIs
+///
+/// Each change becomes a `warning` result whose rule is the change kind
+/// (`added`, `removed` or `modified`); a change with a `severity` annotation
+/// (see [`Rules`](crate::rules::Rules)) becomes a `note`, `warning` or
+/// `error` result instead, and a change marked `unexpected` (see
+/// [`Changes::mark_unexpected`]) an `error` result. The changed value's JSONPath is the
+/// result's logical location; when the second input is a file, it is the
+/// physical location, so CI annotations point at that file. A change with a
+/// `newLocation` or `oldLocation` annotation (see
+/// [`Changes::annotate_locations`]) points at that file, line and column
+/// instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::formatter::{Formatter, SarifFormatter};
+/// use rjd::diff;
+/// use serde_json::json;
+///
+/// let changes = diff(&json!({"port": 80}), &json!({"port": 8080}));
+/// let formatter = SarifFormatter::new(false, Some("config.json".to_string()));
+/// let sarif: serde_json::Value = serde_json::from_str(&formatter.format(&changes).unwrap()).unwrap();
+///
+/// let result = &sarif["runs"][0]["results"][0];
+/// assert_eq!(result["ruleId"], "modified");
+/// assert_eq!(result["locations"][0]["logicalLocations"][0]["fullyQualifiedName"], "$.port");
+/// ```
+pub struct SarifFormatter {
+    sort: bool,
+    artifact: Option<String>,
+}
+
+impl SarifFormatter {
+    /// Create a formatter; `artifact` is the URI of the second input, if it is a file
+    pub fn new(sort: bool, artifact: Option<String>) -> Self {
+        Self { sort, artifact }
+    }
+
+    fn result(&self, change: &Change, changes: &Changes) -> Value {
+        let path = change.path();
+        let mut location = json!({
+            "logicalLocations": [{
+                "fullyQualifiedName": path.to_jsonpath(),
+                "kind": "member"
+            }]
+        });
+        let source = changes.annotations.get(path).and_then(|annotations| {
+            annotations
+                .get("newLocation")
+                .or_else(|| annotations.get("oldLocation"))
+        });
+        if let Some(source) = source {
+            location["physicalLocation"] = json!({
+                "artifactLocation": { "uri": source["file"] },
+                "region": { "startLine": source["line"], "startColumn": source["column"] }
+            });
+        } else if let Some(uri) = &self.artifact {
+            location["physicalLocation"] = json!({ "artifactLocation": { "uri": uri } });
+        }
+
+        json!({
+            "ruleId": rule_id(change.kind()),
+            "level": level(change, changes),
+            "message": {
+                "text": description(change, changes).map_or_else(|| describe_change(change), String::from)
+            },
+            "locations": [location]
+        })
+    }
+}
+
+/// SARIF level of a change: `error` if it is unexpected, otherwise from its
+/// `severity` annotation, if it has one
+fn level(change: &Change, changes: &Changes) -> &'static str {
+    let annotations = changes.annotations.get(change.path());
+    if annotations.and_then(|annotations| annotations.get("unexpected")) == Some(&Value::Bool(true))
+    {
+        return "error";
+    }
+    let severity = annotations
+        .and_then(|annotations| annotations.get("severity"))
+        .and_then(Value::as_str);
+    match severity {
+        Some("info") => "note",
+        Some("error") => "error",
+        _ => "warning",
+    }
+}
+
+fn rule_id(kind: ChangeKind) -> &'static str {
+    match kind {
+        ChangeKind::Added => "added",
+        ChangeKind::Removed => "removed",
+        ChangeKind::Modified => "modified",
+    }
+}
+
+fn rule(kind: ChangeKind, description: &str) -> Value {
+    json!({
+        "id": rule_id(kind),
+        "shortDescription": { "text": description },
+        "defaultConfiguration": { "level": "warning" }
+    })
+}
+
+impl Formatter for SarifFormatter {
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+        let results: Vec<Value> = changes
+            .iter()
+            .map(|change| self.result(change, changes))
+            .collect();
+        let log = json!({
+            "$schema": SARIF_SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "rjd",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": "https://github.com/darkcodi/rjd",
+                        "rules": [
+                            rule(ChangeKind::Added, "A value was added"),
+                            rule(ChangeKind::Removed, "A value was removed"),
+                            rule(ChangeKind::Modified, "A value was modified")
+                        ]
+                    }
+                },
+                "results": results
+            }]
+        });
+
+        let log = if self.sort {
+            sort_json_value(&log)
+        } else {
+            log
+        };
+        Ok(serde_json::to_string_pretty(&log)?)
+    }
+
+    fn content_type(&self) -> &'static str {
+        "application/sarif+json"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+
+    fn sarif(formatter: &SarifFormatter, changes: &Changes) -> Value {
+        serde_json::from_str(&formatter.format(changes).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_sarif_results() {
+        let old = json!({"name": "app", "tags": ["a"], "port": 80});
+        let new = json!({"name": "app", "tags": [], "port": 8080, "debug": true});
+        let log = sarif(
+            &SarifFormatter::new(false, Some("new.json".to_string())),
+            &diff(&old, &new),
+        );
+
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "rjd");
+        let results = run["results"].as_array().unwrap();
+        let summary: Vec<(&str, &str)> = results
+            .iter()
+            .map(|r| {
+                (
+                    r["ruleId"].as_str().unwrap(),
+                    r["locations"][0]["logicalLocations"][0]["fullyQualifiedName"]
+                        .as_str()
+                        .unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("added", "$.debug"),
+                ("removed", "$.tags[0]"),
+                ("modified", "$.port")
+            ]
+        );
+        assert_eq!(results[2]["message"]["text"], "Modified $.port: 80 -> 8080");
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "new.json"
+        );
+    }
+
+    #[test]
+    fn test_sarif_without_changes_or_artifact() {
+        let log = sarif(&SarifFormatter::new(false, None), &Changes::new());
+        assert_eq!(log["runs"][0]["results"], json!([]));
+
+        let changes = diff(&json!(1), &json!(2));
+        let log = sarif(&SarifFormatter::new(true, None), &changes);
+        let location = &log["runs"][0]["results"][0]["locations"][0];
+        assert!(location.get("physicalLocation").is_none());
+        assert_eq!(location["logicalLocations"][0]["fullyQualifiedName"], "$");
+    }
+
+    #[test]
+    fn test_sarif_regions_from_locations() {
+        let old_text = "{\"port\": 80, \"gone\": 1}";
+        let new_text = "{\n  \"port\": 8080\n}";
+        let mut changes = diff(
+            &serde_json::from_str(old_text).unwrap(),
+            &serde_json::from_str(new_text).unwrap(),
+        );
+        changes.annotate_locations(
+            Some(crate::SourceText {
+                name: "old.json",
+                text: old_text,
+            }),
+            Some(crate::SourceText {
+                name: "new.json",
+                text: new_text,
+            }),
+        );
+        let log = sarif(
+            &SarifFormatter::new(false, Some("new.json".to_string())),
+            &changes,
+        );
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        let physical = |i: usize| &results[i]["locations"][0]["physicalLocation"];
+
+        // The removed value is found in the first input only
+        assert_eq!(physical(0)["artifactLocation"]["uri"], "old.json");
+        assert_eq!(
+            physical(0)["region"],
+            json!({"startLine": 1, "startColumn": 22})
+        );
+        assert_eq!(physical(1)["artifactLocation"]["uri"], "new.json");
+        assert_eq!(
+            physical(1)["region"],
+            json!({"startLine": 2, "startColumn": 11})
+        );
+    }
+
+    #[test]
+    fn test_sarif_levels_from_severity() {
+        let rules = crate::rules::Rules::from_json(
+            r#"{"rules": [{"path": "/a", "severity": "error"}, {"path": "/b", "severity": "info"}]}"#,
+        )
+        .unwrap();
+        let mut changes = diff(
+            &json!({"a": 1, "b": 1, "c": 1}),
+            &json!({"a": 2, "b": 2, "c": 2}),
+        );
+        let plain = sarif(&SarifFormatter::new(false, None), &changes);
+        assert_eq!(plain["runs"][0]["results"][0]["level"], "warning");
+
+        rules.apply(&mut changes);
+        let log = sarif(&SarifFormatter::new(false, None), &changes);
+        let levels: Vec<&str> = log["runs"][0]["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["level"].as_str().unwrap())
+            .collect();
+        assert_eq!(levels, ["error", "note", "note"]);
+
+        changes.mark_unexpected(&["/a".to_string(), "/b".to_string()]);
+        let log = sarif(&SarifFormatter::new(false, None), &changes);
+        assert_eq!(log["runs"][0]["results"][2]["level"], "error");
+        assert_eq!(log["runs"][0]["results"][1]["level"], "note");
+    }
+}
@@ -0,0 +1,377 @@
+//! JSON Schemas describing the output formats
+//!
+//! The schemas follow JSON Schema draft 2020-12 and can be printed with
+//! `rjd schema --format <format>`, so consumers can validate rjd output in CI.
+
+use crate::error::FormatterError;
+use crate::formatter::sarif::SARIF_SCHEMA;
+use crate::formatter::{CHANGES_FORMAT_VERSION, VALID_FORMATS};
+use serde_json::{json, Value};
+
+const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Return the JSON Schema for an output format
+///
+/// `version` selects the "changes" format version and is ignored by the other
+/// formats.
+///
+/// # Errors
+/// Returns an error if the format is unknown or the version is not supported.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::formatter::schema::output_schema;
+///
+/// let schema = output_schema("changes", 2).unwrap();
+/// assert_eq!(schema["properties"]["formatVersion"]["const"], 2);
+/// ```
+pub fn output_schema(format: &str, version: u32) -> Result<Value, FormatterError> {
+    match format {
+        "changes" => changes_schema(version),
+        "rfc6902" => Ok(rfc6902_schema()),
+        "after" => Ok(after_schema()),
+        "before" => Ok(before_schema()),
+        "full-after" => Ok(full_after_schema()),
+        "annotated" => Ok(annotated_schema()),
+        "sarif" => Ok(sarif_schema()),
+        "junit" | "gcc" | "jsonl" | "msgpack" | "template" => Err(FormatterError::NoSchema {
+            format: format.to_string(),
+        }),
+        "summary" => Ok(summary_schema()),
+        _ => Err(FormatterError::UnknownFormat {
+            format: format.to_string(),
+            valid: VALID_FORMATS.to_string(),
+        }),
+    }
+}
+
+fn changes_schema(version: u32) -> Result<Value, FormatterError> {
+    let defs = json!({
+        "path": {
+            "type": "string",
+            "description": "Dot-notation path to the changed value, e.g. \"users[0].name\""
+        },
+        "context": {
+            "type": "object",
+            "description": "Unchanged surroundings of the change, present with --context",
+            "required": ["path", "value"],
+            "properties": {
+                "path": { "$ref": "#/$defs/path" },
+                "value": { "description": "The ancestor's value in the second input" }
+            }
+        },
+        "schemaPath": {
+            "type": "string",
+            "description": "Location of the changed value's subschema, present with --schema"
+        },
+        "compatibility": {
+            "enum": ["breaking", "compatible"],
+            "description": "Whether the change can break consumers, present with --schema"
+        },
+        "reason": {
+            "type": "string",
+            "description": "Why a change is breaking, present with --schema"
+        },
+        "severity": {
+            "enum": ["info", "warn", "error"],
+            "description": "Severity assigned by the --rules file"
+        },
+        "stringDiff": {
+            "type": "array",
+            "description": "Runs of text kept, deleted and inserted in a modified string, present with --string-diff",
+            "items": {
+                "type": "object",
+                "required": ["op", "text"],
+                "properties": {
+                    "op": { "enum": ["equal", "delete", "insert"] },
+                    "text": { "type": "string" }
+                }
+            }
+        },
+        "location": {
+            "type": "object",
+            "description": "Where the value is written in an input file, present with --with-locations",
+            "required": ["file", "line", "column", "offset"],
+            "properties": {
+                "file": { "type": "string" },
+                "line": { "type": "integer", "minimum": 1 },
+                "column": { "type": "integer", "minimum": 1, "description": "Column in characters" },
+                "offset": { "type": "integer", "minimum": 0, "description": "Offset in bytes" }
+            }
+        },
+        "type": {
+            "enum": ["null", "boolean", "number", "string", "array", "object"],
+            "description": "A JSON type, present as oldType and newType with --types-only or --verbose-changes"
+        },
+        "description": {
+            "type": "string",
+            "description": "A human-readable description of the change, present with --describe or --describe-template"
+        },
+        "valueSize": {
+            "type": "integer",
+            "minimum": 0,
+            "description": "Length in bytes of the value's compact JSON text (of the larger value of a modification), present with --verbose-changes"
+        },
+        "unexpected": {
+            "const": true,
+            "description": "Marks a change outside the --expect-json allowlist"
+        },
+        "valueChange": {
+            "type": "object",
+            "required": ["path", "value"],
+            "properties": {
+                "path": { "$ref": "#/$defs/path" },
+                "value": { "description": "The added or removed value" },
+                "context": { "$ref": "#/$defs/context" },
+                "schemaPath": { "$ref": "#/$defs/schemaPath" },
+                "compatibility": { "$ref": "#/$defs/compatibility" },
+                "reason": { "$ref": "#/$defs/reason" },
+                "severity": { "$ref": "#/$defs/severity" },
+                "unexpected": { "$ref": "#/$defs/unexpected" },
+                "oldType": { "$ref": "#/$defs/type" },
+                "newType": { "$ref": "#/$defs/type" },
+                "valueSize": { "$ref": "#/$defs/valueSize" },
+                "description": { "$ref": "#/$defs/description" },
+                "oldLocation": { "$ref": "#/$defs/location" },
+                "newLocation": { "$ref": "#/$defs/location" }
+            }
+        },
+        "modifiedChange": {
+            "type": "object",
+            "required": ["path", "oldValue", "newValue"],
+            "properties": {
+                "path": { "$ref": "#/$defs/path" },
+                "oldValue": { "description": "The value in the first input" },
+                "newValue": { "description": "The value in the second input" },
+                "context": { "$ref": "#/$defs/context" },
+                "schemaPath": { "$ref": "#/$defs/schemaPath" },
+                "compatibility": { "$ref": "#/$defs/compatibility" },
+                "reason": { "$ref": "#/$defs/reason" },
+                "severity": { "$ref": "#/$defs/severity" },
+                "unexpected": { "$ref": "#/$defs/unexpected" },
+                "oldType": { "$ref": "#/$defs/type" },
+                "newType": { "$ref": "#/$defs/type" },
+                "valueSize": { "$ref": "#/$defs/valueSize" },
+                "description": { "$ref": "#/$defs/description" },
+                "stringDiff": { "$ref": "#/$defs/stringDiff" },
+                "oldLocation": { "$ref": "#/$defs/location" },
+                "newLocation": { "$ref": "#/$defs/location" }
+            }
+        },
+        "group": {
+            "type": "object",
+            "description": "Changes under a common ancestor path; empty fields are left out",
+            "required": ["path"],
+            "properties": {
+                "path": { "$ref": "#/$defs/path" },
+                "added": { "type": "array", "items": { "$ref": "#/$defs/valueChange" } },
+                "removed": { "type": "array", "items": { "$ref": "#/$defs/valueChange" } },
+                "modified": { "type": "array", "items": { "$ref": "#/$defs/modifiedChange" } },
+                "groups": { "type": "array", "items": { "$ref": "#/$defs/group" } }
+            }
+        }
+    });
+    let changes = json!({
+        "added": { "type": "array", "items": { "$ref": "#/$defs/valueChange" } },
+        "removed": { "type": "array", "items": { "$ref": "#/$defs/valueChange" } },
+        "modified": { "type": "array", "items": { "$ref": "#/$defs/modifiedChange" } },
+        "groups": {
+            "type": "array",
+            "description": "Changes below the root grouped by subtree, present with --group-by-path-depth (the change arrays then hold only changes to the whole document)",
+            "items": { "$ref": "#/$defs/group" }
+        },
+        "truncated": {
+            "type": "array",
+            "description": "Where a partial diff stopped because it hit a limit; absent for complete diffs",
+            "items": {
+                "type": "object",
+                "required": ["path", "reason"],
+                "properties": {
+                    "path": { "$ref": "#/$defs/path" },
+                    "reason": { "type": "string" }
+                }
+            }
+        },
+        "warnings": {
+            "type": "array",
+            "description": "Comparisons whose result may be misleading; absent when there are none",
+            "items": {
+                "type": "object",
+                "required": ["path", "message"],
+                "properties": {
+                    "path": { "$ref": "#/$defs/path" },
+                    "message": { "type": "string" }
+                }
+            }
+        }
+    });
+
+    match version {
+        1 => Ok(json!({
+            "$schema": SCHEMA_DIALECT,
+            "$id": "https://github.com/darkcodi/rjd/schemas/changes-v1.json",
+            "title": "rjd changes output (format version 1)",
+            "type": "object",
+            "required": ["added", "removed", "modified"],
+            "properties": changes,
+            "$defs": defs
+        })),
+        2 => {
+            let mut properties = json!({
+                "formatVersion": {
+                    "const": 2,
+                    "description": "Version of the changes output format"
+                }
+            });
+            if let (Some(target), Value::Object(fields)) = (properties.as_object_mut(), changes) {
+                target.extend(fields);
+            }
+            Ok(json!({
+                "$schema": SCHEMA_DIALECT,
+                "$id": "https://github.com/darkcodi/rjd/schemas/changes-v2.json",
+                "title": "rjd changes output (format version 2)",
+                "description": "Consumers should ignore unknown properties; additions within a format version are backwards compatible",
+                "type": "object",
+                "required": ["formatVersion", "added", "removed", "modified"],
+                "properties": properties,
+                "$defs": defs
+            }))
+        }
+        _ => Err(FormatterError::UnsupportedFormatVersion {
+            version,
+            supported: format!("1, {}", CHANGES_FORMAT_VERSION),
+        }),
+    }
+}
+
+fn rfc6902_schema() -> Value {
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "$id": "https://github.com/darkcodi/rjd/schemas/rfc6902.json",
+        "title": "rjd rfc6902 output (JSON Patch)",
+        "type": "array",
+        "items": {
+            "type": "object",
+            "required": ["op", "path"],
+            "properties": {
+                "op": { "enum": ["add", "remove", "replace"] },
+                "path": { "type": "string", "description": "JSON Pointer (RFC 6901)" },
+                "value": {}
+            }
+        }
+    })
+}
+
+fn after_schema() -> Value {
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "$id": "https://github.com/darkcodi/rjd/schemas/after.json",
+        "title": "rjd after output",
+        "description": "The changed properties of the second input, nested as in the original document"
+    })
+}
+
+fn before_schema() -> Value {
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "$id": "https://github.com/darkcodi/rjd/schemas/before.json",
+        "title": "rjd before output",
+        "description": "The removed and modified properties of the first input, nested as in the original document"
+    })
+}
+
+fn full_after_schema() -> Value {
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "$id": "https://github.com/darkcodi/rjd/schemas/full-after.json",
+        "title": "rjd full-after output",
+        "description": "The complete second input, laid out like the first wherever it is unchanged"
+    })
+}
+
+fn annotated_schema() -> Value {
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "$id": "https://github.com/darkcodi/rjd/schemas/annotated.json",
+        "title": "rjd annotated output",
+        "description": "The complete second input with json-diff style markers: `key__added` and `key__deleted` members, `{\"__old\", \"__new\"}` for modified values and `[marker, value]` pairs in changed arrays"
+    })
+}
+
+fn sarif_schema() -> Value {
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "$id": "https://github.com/darkcodi/rjd/schemas/sarif.json",
+        "title": "rjd sarif output",
+        "description": "A SARIF 2.1.0 log with one result per change",
+        "allOf": [{ "$ref": SARIF_SCHEMA }]
+    })
+}
+
+fn summary_schema() -> Value {
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "$id": "https://github.com/darkcodi/rjd/schemas/summary.json",
+        "title": "rjd summary output",
+        "type": "object",
+        "required": ["added", "removed", "modified", "total", "largestByBytes", "largestBySubtree"],
+        "properties": {
+            "added": { "type": "integer", "minimum": 0 },
+            "removed": { "type": "integer", "minimum": 0 },
+            "modified": { "type": "integer", "minimum": 0 },
+            "total": { "type": "integer", "minimum": 0 },
+            "largestByBytes": { "type": "array", "items": { "$ref": "#/$defs/changeSize" } },
+            "largestBySubtree": { "type": "array", "items": { "$ref": "#/$defs/changeSize" } }
+        },
+        "$defs": {
+            "changeSize": {
+                "type": "object",
+                "required": ["path", "kind", "oldBytes", "newBytes", "byteDelta", "subtreeSize"],
+                "properties": {
+                    "path": { "type": "string" },
+                    "kind": { "enum": ["added", "removed", "modified"] },
+                    "oldBytes": { "type": "integer", "minimum": 0 },
+                    "newBytes": { "type": "integer", "minimum": 0 },
+                    "byteDelta": { "type": "integer" },
+                    "subtreeSize": { "type": "integer", "minimum": 0 }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_schema_versions() {
+        let v2 = output_schema("changes", 2).unwrap();
+        assert_eq!(v2["properties"]["formatVersion"]["const"], 2);
+        assert!(v2["required"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("formatVersion")));
+
+        let v1 = output_schema("changes", 1).unwrap();
+        assert!(v1["properties"].get("formatVersion").is_none());
+        assert!(v1["properties"]["modified"].is_object());
+    }
+
+    #[test]
+    fn test_schema_errors() {
+        assert!(matches!(
+            output_schema("junit", 2),
+            Err(FormatterError::NoSchema { .. })
+        ));
+        assert!(matches!(
+            output_schema("changes", 9),
+            Err(FormatterError::UnsupportedFormatVersion { version: 9, .. })
+        ));
+        assert!(matches!(
+            output_schema("yaml", 2),
+            Err(FormatterError::UnknownFormat { .. })
+        ));
+    }
+}
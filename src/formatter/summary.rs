@@ -0,0 +1,177 @@
+use crate::error::FormatError;
+use crate::formatter::{sort_json_value, Formatter};
+use crate::types::{subtree_size, Change, Changes};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Default number of entries in each "largest" list
+pub const DEFAULT_TOP_K: usize = 5;
+
+/// Formatter for the "summary" output format
+///
+/// Outputs change counts plus the top-K changes by serialized byte-size delta
+/// and by subtree size, so the biggest changes stand out in large diffs.
+pub struct SummaryFormatter {
+    sort: bool,
+    top_k: usize,
+}
+
+/// Size information for a single change
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangeSize {
+    path: String,
+    kind: &'static str,
+    old_bytes: usize,
+    new_bytes: usize,
+    byte_delta: i64,
+    subtree_size: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Summary {
+    added: usize,
+    removed: usize,
+    modified: usize,
+    total: usize,
+    largest_by_bytes: Vec<ChangeSize>,
+    largest_by_subtree: Vec<ChangeSize>,
+}
+
+impl SummaryFormatter {
+    /// Create a new SummaryFormatter listing the top `top_k` changes
+    pub fn new(sort: bool, top_k: usize) -> Self {
+        Self { sort, top_k }
+    }
+}
+
+impl Default for SummaryFormatter {
+    fn default() -> Self {
+        Self::new(false, DEFAULT_TOP_K)
+    }
+}
+
+fn byte_size(value: &Value) -> usize {
+    serde_json::to_string(value).map(|s| s.len()).unwrap_or(0)
+}
+
+fn change_size(change: &Change) -> ChangeSize {
+    let (kind, old, new) = match change {
+        Change::Added { value, .. } => ("added", None, Some(value)),
+        Change::Removed { value, .. } => ("removed", Some(value), None),
+        Change::Modified {
+            old_value,
+            new_value,
+            ..
+        } => ("modified", Some(old_value), Some(new_value)),
+    };
+    let old_bytes = old.map(byte_size).unwrap_or(0);
+    let new_bytes = new.map(byte_size).unwrap_or(0);
+    let subtree = old
+        .map(subtree_size)
+        .unwrap_or(0)
+        .max(new.map(subtree_size).unwrap_or(0));
+
+    ChangeSize {
+        path: change.path().to_string(),
+        kind,
+        old_bytes,
+        new_bytes,
+        byte_delta: new_bytes as i64 - old_bytes as i64,
+        subtree_size: subtree,
+    }
+}
+
+impl Formatter for SummaryFormatter {
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+        let sizes: Vec<ChangeSize> = changes
+            .added
+            .iter()
+            .chain(&changes.removed)
+            .chain(&changes.modified)
+            .map(change_size)
+            .collect();
+
+        // Stable sorts keep the added/removed/modified order for ties
+        let mut by_bytes = sizes.clone();
+        by_bytes.sort_by_key(|size| std::cmp::Reverse(size.byte_delta.unsigned_abs()));
+        by_bytes.truncate(self.top_k);
+
+        let mut by_subtree = sizes;
+        by_subtree.sort_by_key(|size| std::cmp::Reverse(size.subtree_size));
+        by_subtree.truncate(self.top_k);
+
+        let summary = Summary {
+            added: changes.added.len(),
+            removed: changes.removed.len(),
+            modified: changes.modified.len(),
+            total: changes.added.len() + changes.removed.len() + changes.modified.len(),
+            largest_by_bytes: by_bytes,
+            largest_by_subtree: by_subtree,
+        };
+
+        let json = serde_json::to_value(&summary)?;
+        let json = if self.sort {
+            sort_json_value(&json)
+        } else {
+            json
+        };
+        Ok(serde_json::to_string_pretty(&json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_changes() -> Changes {
+        let mut changes = Changes::new();
+        changes.push(Change::Modified {
+            path: "version".parse().unwrap(),
+            old_value: json!(1),
+            new_value: json!(2),
+        });
+        changes.push(Change::Modified {
+            path: "dataset".parse().unwrap(),
+            old_value: json!({"rows": [1, 2, 3]}),
+            new_value: json!({"rows": ["a long string value", 2, 3, 4, 5]}),
+        });
+        changes.push(Change::Added {
+            path: "flags".parse().unwrap(),
+            value: json!([true, false, true, false, true, false, true, false]),
+        });
+        changes
+    }
+
+    #[test]
+    fn test_summary_counts_and_rankings() {
+        let formatter = SummaryFormatter::new(false, 2);
+        let result = formatter.format(&sample_changes()).unwrap();
+        let parsed: Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(parsed["total"], 3);
+        assert_eq!(parsed["added"], 1);
+        assert_eq!(parsed["modified"], 2);
+
+        let by_bytes = parsed["largestByBytes"].as_array().unwrap();
+        assert_eq!(by_bytes.len(), 2);
+        assert_eq!(by_bytes[0]["path"], "flags");
+        assert_eq!(by_bytes[1]["path"], "dataset");
+
+        let by_subtree = parsed["largestBySubtree"].as_array().unwrap();
+        assert_eq!(by_subtree[0]["path"], "flags");
+        assert_eq!(by_subtree[0]["subtreeSize"], 9);
+        assert_eq!(by_subtree[1]["path"], "dataset");
+        assert_eq!(by_subtree[1]["subtreeSize"], 7);
+    }
+
+    #[test]
+    fn test_summary_empty_changes() {
+        let result = SummaryFormatter::default().format(&Changes::new()).unwrap();
+        let parsed: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(parsed["total"], 0);
+        assert!(parsed["largestByBytes"].as_array().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+use crate::error::{FormatError, FormatterError};
+use crate::formatter::{ChangesFormatter, Formatter};
+use crate::types::Changes;
+use serde_json::Value;
+use std::error::Error;
+use std::io::Write;
+use tera::{Context, Tera};
+
+/// Name the template is registered under in its Tera instance
+const TEMPLATE_NAME: &str = "report";
+
+/// Formatter rendering the changes through a user-supplied Tera template
+///
+/// The template sees the object the changes format writes (`added`,
+/// `removed` and `modified` arrays of change entries, with `path`, `value`
+/// or `oldValue`/`newValue` and any annotations such as `description`, plus
+/// `truncated` and `warnings` when present), and in addition:
+///
+/// - `changes`: every entry in one array (added, then removed, then
+///   modified), each with a `kind` field
+/// - `total`: the number of changes
+///
+/// Output is not escaped, and is written exactly as rendered: no newline is
+/// added. See the [Tera documentation](https://keats.github.io/tera/docs/)
+/// for the template syntax.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::diff;
+/// use rjd::formatter::{Formatter, TemplateFormatter};
+/// use serde_json::json;
+///
+/// let formatter = TemplateFormatter::new(
+///     "{{ total }} change(s){% for c in changes %}\n- {{ c.kind }} {{ c.path }}{% endfor %}",
+/// )
+/// .unwrap();
+/// let changes = diff(&json!({"a": 1, "b": 2}), &json!({"a": 3}));
+/// assert_eq!(
+///     formatter.format(&changes).unwrap(),
+///     "2 change(s)\n- removed b\n- modified a"
+/// );
+/// ```
+pub struct TemplateFormatter {
+    tera: Tera,
+}
+
+impl TemplateFormatter {
+    /// Create a formatter from the source text of a template
+    ///
+    /// # Errors
+    /// Returns an error if the template does not parse.
+    pub fn new(template: &str) -> Result<Self, FormatterError> {
+        let mut tera = Tera::default();
+        tera.add_raw_template(TEMPLATE_NAME, template)
+            .map_err(|e| FormatterError::InvalidTemplate {
+                message: error_chain(&e),
+            })?;
+        Ok(Self { tera })
+    }
+
+    /// The variables the template is rendered with
+    fn context(changes: &Changes) -> Result<Context, FormatError> {
+        let report = ChangesFormatter::new(false).format(changes)?;
+        let mut report: Value = serde_json::from_str(&report)?;
+
+        let mut all = Vec::new();
+        for kind in ["added", "removed", "modified"] {
+            let entries = report[kind].as_array().cloned().unwrap_or_default();
+            for mut entry in entries {
+                entry["kind"] = Value::from(kind);
+                all.push(entry);
+            }
+        }
+        report["changes"] = Value::Array(all);
+        report["total"] = Value::from(changes.len());
+
+        Context::from_value(report).map_err(|e| FormatError::Custom(error_chain(&e).into()))
+    }
+}
+
+impl Formatter for TemplateFormatter {
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+        self.tera
+            .render(TEMPLATE_NAME, &Self::context(changes)?)
+            .map_err(|e| FormatError::Custom(error_chain(&e).into()))
+    }
+
+    fn write_to(&self, changes: &Changes, writer: &mut dyn Write) -> Result<(), FormatError> {
+        // The template decides where lines end
+        write!(writer, "{}", self.format(changes)?)?;
+        Ok(())
+    }
+
+    fn content_type(&self) -> &'static str {
+        "text/plain; charset=utf-8"
+    }
+}
+
+/// A Tera error with its causes, which say what went wrong and where
+fn error_chain(error: &tera::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    #[test]
+    fn test_template_variables() {
+        let mut changes = diff(
+            &json!({"name": "a", "tags": ["x"]}),
+            &json!({"name": "b", "tags": [], "id": 1}),
+        );
+        changes.annotate_descriptions(None);
+        let formatter = TemplateFormatter::new(
+            "{{ added | length }}/{{ removed | length }}/{{ modified | length }} of {{ total }}\n\
+             {% for c in changes %}{{ c.kind }}: {{ c.description }}\n{% endfor %}\
+             {{ modified.0.oldValue }} -> {{ modified.0.newValue }}",
+        )
+        .unwrap();
+        assert_eq!(
+            formatter.format(&changes).unwrap(),
+            "1/1/1 of 3\n\
+             added: `id` 1 added\n\
+             removed: `tags[0]` 'x' removed\n\
+             modified: `name` changed from 'a' to 'b'\n\
+             a -> b"
+        );
+    }
+
+    #[test]
+    fn test_template_errors() {
+        let error = TemplateFormatter::new("{% for c in changes %}")
+            .err()
+            .unwrap();
+        assert!(matches!(error, FormatterError::InvalidTemplate { .. }));
+
+        let formatter = TemplateFormatter::new("{{ missing.field }}").unwrap();
+        let error = formatter.format(&Changes::new()).unwrap_err();
+        assert!(error.to_string().contains("missing.field"), "{}", error);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::error::FormatError;
+use crate::formatter::Formatter;
+use crate::types::{Change, Changes};
+use serde_json::{Map, Value};
+use std::io::Write;
+
+/// Marker appended to truncated values
+const ELLIPSIS: &str = "…";
+
+/// Shorten a value so its JSON text is roughly at most `max_length` bytes
+///
+/// Long strings keep their first `max_length` characters followed by an
+/// ellipsis and the original length. Arrays and objects keep leading entries
+/// (each truncated in turn, and always at least one) while they fit, followed
+/// by a marker entry with the number of omitted entries and the original size
+/// in bytes.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::formatter::truncate_value;
+/// use serde_json::json;
+///
+/// let value = truncate_value(&json!("abcdefghij"), 4);
+/// assert_eq!(value, json!("abcd… (10 chars)"));
+/// ```
+pub fn truncate_value(value: &Value, max_length: usize) -> Value {
+    match value {
+        Value::String(s) => {
+            let length = s.chars().count();
+            if length <= max_length {
+                return value.clone();
+            }
+            let prefix: String = s.chars().take(max_length).collect();
+            Value::String(format!("{}{} ({} chars)", prefix, ELLIPSIS, length))
+        }
+        Value::Array(items) => {
+            let total = json_size(value);
+            if total <= max_length {
+                return value.clone();
+            }
+            let mut kept = Vec::new();
+            let mut used = 0;
+            for item in items {
+                let item = truncate_value(item, max_length);
+                used += json_size(&item) + 1;
+                if used > max_length && !kept.is_empty() {
+                    break;
+                }
+                kept.push(item);
+            }
+            let omitted = items.len() - kept.len();
+            kept.push(Value::String(format!(
+                "{} {} more items ({} bytes total)",
+                ELLIPSIS, omitted, total
+            )));
+            Value::Array(kept)
+        }
+        Value::Object(map) => {
+            let total = json_size(value);
+            if total <= max_length {
+                return value.clone();
+            }
+            let mut kept = Map::new();
+            let mut used = 0;
+            for (key, child) in map {
+                let child = truncate_value(child, max_length);
+                used += key.len() + 3 + json_size(&child) + 1;
+                if used > max_length && !kept.is_empty() {
+                    break;
+                }
+                kept.insert(key.clone(), child);
+            }
+            let omitted = map.len() - kept.len();
+            kept.insert(
+                ELLIPSIS.to_string(),
+                Value::String(format!("{} more keys ({} bytes total)", omitted, total)),
+            );
+            Value::Object(kept)
+        }
+        _ => value.clone(),
+    }
+}
+
+fn json_size(value: &Value) -> usize {
+    serde_json::to_string(value).map(|s| s.len()).unwrap_or(0)
+}
+
+/// Copy of `changes` with every reported value truncated
+fn truncate_changes(changes: &Changes, max_length: usize) -> Changes {
+    let truncate = |change: &Change| match change {
+        Change::Added { path, value } => Change::Added {
+            path: path.clone(),
+            value: truncate_value(value, max_length),
+        },
+        Change::Removed { path, value } => Change::Removed {
+            path: path.clone(),
+            value: truncate_value(value, max_length),
+        },
+        Change::Modified {
+            path,
+            old_value,
+            new_value,
+        } => Change::Modified {
+            path: path.clone(),
+            old_value: truncate_value(old_value, max_length),
+            new_value: truncate_value(new_value, max_length),
+        },
+    };
+
+    let mut truncated = changes.clone();
+    truncated.added = changes.added.iter().map(truncate).collect();
+    truncated.removed = changes.removed.iter().map(truncate).collect();
+    truncated.modified = changes.modified.iter().map(truncate).collect();
+
+    // Truncate the changed values inside the "before" and "after" documents as well
+    if let Some(before) = truncated.before.as_mut() {
+        for change in changes.removed.iter().chain(&changes.modified) {
+            if let Some(target) = change.path().resolve_mut(before) {
+                *target = truncate_value(target, max_length);
+            }
+        }
+    }
+    if let Some(after) = truncated.after.as_mut() {
+        for change in changes.added.iter().chain(&changes.modified) {
+            if let Some(target) = change.path().resolve_mut(after) {
+                *target = truncate_value(target, max_length);
+            }
+        }
+    }
+
+    truncated
+}
+
+/// Formatter decorator that truncates large values before formatting
+pub(crate) struct TruncatingFormatter {
+    pub(crate) inner: Box<dyn Formatter>,
+    pub(crate) max_length: usize,
+}
+
+impl Formatter for TruncatingFormatter {
+    fn format(&self, changes: &Changes) -> Result<String, FormatError> {
+        self.inner
+            .format(&truncate_changes(changes, self.max_length))
+    }
+
+    fn write_to(&self, changes: &Changes, writer: &mut dyn Write) -> Result<(), FormatError> {
+        self.inner
+            .write_to(&truncate_changes(changes, self.max_length), writer)
+    }
+
+    fn uses_before(&self) -> bool {
+        self.inner.uses_before()
+    }
+
+    fn content_type(&self) -> &'static str {
+        self.inner.content_type()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_truncate_small_values_unchanged() {
+        let value = json!({"a": [1, 2], "b": "short"});
+        assert_eq!(truncate_value(&value, 100), value);
+        assert_eq!(truncate_value(&json!(123456789), 2), json!(123456789));
+    }
+
+    #[test]
+    fn test_truncate_containers_keep_leading_entries() {
+        let array = json!([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        let truncated = truncate_value(&array, 6);
+        let items = truncated.as_array().unwrap();
+        assert_eq!(&items[..3], &[json!(1), json!(2), json!(3)]);
+        assert_eq!(items[3], json!("… 7 more items (22 bytes total)"));
+
+        let object = json!({"name": "x".repeat(50), "id": 1});
+        let truncated = truncate_value(&object, 10);
+        assert_eq!(
+            truncated["name"],
+            json!(format!("{}… (50 chars)", "x".repeat(10)))
+        );
+        assert_eq!(truncated["…"], json!("1 more keys (68 bytes total)"));
+    }
+
+    #[test]
+    fn test_truncate_changes_updates_documents() {
+        let mut changes = Changes::new();
+        changes.push(Change::Modified {
+            path: "blob".parse().unwrap(),
+            old_value: json!("a".repeat(20)),
+            new_value: json!("b".repeat(20)),
+        });
+        changes.before = Some(json!({"blob": "a".repeat(20), "id": 1}));
+        changes.after = Some(json!({"blob": "b".repeat(20), "id": 1}));
+
+        let truncated = truncate_changes(&changes, 3);
+        match &truncated.modified[0] {
+            Change::Modified {
+                old_value,
+                new_value,
+                ..
+            } => {
+                assert_eq!(old_value, &json!("aaa… (20 chars)"));
+                assert_eq!(new_value, &json!("bbb… (20 chars)"));
+            }
+            other => panic!("unexpected change: {:?}", other),
+        }
+        assert_eq!(
+            truncated.before,
+            Some(json!({"blob": "aaa… (20 chars)", "id": 1}))
+        );
+        assert_eq!(
+            truncated.after,
+            Some(json!({"blob": "bbb… (20 chars)", "id": 1}))
+        );
+    }
+}
@@ -1,4 +1,7 @@
+use crate::error::FormatError;
+use crate::types::{Change, Changes};
 use serde_json::Value;
+use std::io::Write;
 
 /// Recursively sort a JSON value's keys alphabetically
 ///
@@ -20,6 +23,64 @@ pub fn sort_json_value(value: &Value) -> Value {
     }
 }
 
+/// The `description` annotation of a change, if it has one
+pub(crate) fn description<'a>(change: &Change, changes: &'a Changes) -> Option<&'a str> {
+    changes
+        .annotations
+        .get(change.path())
+        .and_then(|annotations| annotations.get("description"))
+        .and_then(Value::as_str)
+}
+
+/// One-line description of a change for report formats, e.g. `Modified $.port: 80 -> 8080`
+pub(crate) fn describe_change(change: &Change) -> String {
+    let path = change.path().to_jsonpath();
+    match change {
+        Change::Added { value, .. } => format!("Added {} at {}", value, path),
+        Change::Removed { value, .. } => format!("Removed {} at {}", value, path),
+        Change::Modified {
+            old_value,
+            new_value,
+            ..
+        } => format!("Modified {}: {} -> {}", path, old_value, new_value),
+    }
+}
+
+/// Write a JSON array one element at a time
+///
+/// The layout matches `serde_json::to_string_pretty` for an array nested
+/// `indent` levels deep, so streamed output is byte-identical to the
+/// buffered output. No trailing newline is written.
+pub(crate) fn write_pretty_array<I>(
+    writer: &mut dyn Write,
+    items: I,
+    indent: usize,
+) -> Result<(), FormatError>
+where
+    I: IntoIterator<Item = Value>,
+{
+    let outer = "  ".repeat(indent);
+    let inner = "  ".repeat(indent + 1);
+    let mut empty = true;
+
+    write!(writer, "[")?;
+    for item in items {
+        let separator = if empty { "" } else { "," };
+        let rendered = serde_json::to_string_pretty(&item)?;
+        // Serialized JSON never contains raw newlines inside strings
+        let rendered = rendered.replace('\n', &format!("\n{}", inner));
+        write!(writer, "{}\n{}{}", separator, inner, rendered)?;
+        empty = false;
+    }
+    if empty {
+        write!(writer, "]")?;
+    } else {
+        write!(writer, "\n{}]", outer)?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -122,4 +183,22 @@ mod tests {
         assert_eq!(sort_json_value(&Value::Bool(true)), true);
         assert_eq!(sort_json_value(&Value::Null), Value::Null);
     }
+
+    #[test]
+    fn test_write_pretty_array_matches_serde_layout() {
+        let items = vec![
+            serde_json::json!({"a": [1, {"b": "x\ny"}], "c": {}}),
+            serde_json::json!([]),
+            serde_json::json!(3),
+        ];
+
+        let mut out = Vec::new();
+        write_pretty_array(&mut out, items.clone(), 0).unwrap();
+        let expected = serde_json::to_string_pretty(&Value::Array(items)).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+
+        let mut out = Vec::new();
+        write_pretty_array(&mut out, Vec::new(), 1).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "[]");
+    }
 }
@@ -0,0 +1,341 @@
+//! Detection of formatting-only differences between JSON texts
+//!
+//! Two documents can be structurally equal while their text differs in key
+//! order, whitespace, number spelling (`1e2` vs `100.0`) or string escaping
+//! (`"\u0041"` vs `"A"`). These differences are invisible to [`crate::diff`].
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::diff::numbers_equal;
+use crate::error::RjdError;
+use crate::json_path::JsonPath;
+use crate::path::{join_array_path, join_path};
+
+/// Formatting-only differences between two JSON texts
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FormattingDifferences {
+    /// Objects whose keys appear in a different order
+    pub key_order: Vec<JsonPath>,
+    /// Numbers that are equal but spelled differently
+    pub number_format: Vec<JsonPath>,
+    /// Strings that are equal but escaped differently
+    pub string_escapes: Vec<JsonPath>,
+    /// Whether whitespace between tokens differs
+    pub whitespace: bool,
+}
+
+impl FormattingDifferences {
+    /// Check if the texts are formatted identically
+    pub fn is_empty(&self) -> bool {
+        self.key_order.is_empty()
+            && self.number_format.is_empty()
+            && self.string_escapes.is_empty()
+            && !self.whitespace
+    }
+}
+
+impl fmt::Display for FormattingDifferences {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sections = [
+            ("Key order differs at", &self.key_order),
+            ("Number formatting differs at", &self.number_format),
+            ("String escaping differs at", &self.string_escapes),
+        ];
+        for (title, paths) in sections {
+            if paths.is_empty() {
+                continue;
+            }
+            writeln!(f, "{}:", title)?;
+            for path in paths {
+                let pointer = path.to_json_pointer();
+                writeln!(f, "  {}", if pointer.is_empty() { "/" } else { &pointer })?;
+            }
+        }
+        if self.whitespace {
+            writeln!(f, "Whitespace differs")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare how two JSON texts are formatted
+///
+/// Only differences between values that are equal are reported, so the
+/// result is meaningful for documents that [`crate::diff`] considers equal.
+///
+/// # Errors
+/// Returns an error if either text is not valid JSON.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::formatting_differences;
+///
+/// let report = formatting_differences(r#"{"a": 1e2, "b": 2}"#, r#"{"b":2,"a":100.0}"#).unwrap();
+/// assert_eq!(report.key_order.len(), 1);
+/// assert_eq!(report.number_format[0].to_json_pointer(), "/a");
+/// assert!(report.whitespace);
+/// ```
+pub fn formatting_differences(
+    old_text: &str,
+    new_text: &str,
+) -> Result<FormattingDifferences, RjdError> {
+    let old = Layout::scan(old_text)?;
+    let new = Layout::scan(new_text)?;
+
+    let mut result = FormattingDifferences {
+        whitespace: old.whitespace != new.whitespace,
+        ..FormattingDifferences::default()
+    };
+
+    let new_keys: HashMap<&JsonPath, &Vec<String>> = new
+        .key_orders
+        .iter()
+        .map(|(path, keys)| (path, keys))
+        .collect();
+    for (path, keys) in &old.key_orders {
+        if let Some(other) = new_keys.get(path) {
+            let mut sorted = keys.clone();
+            let mut other_sorted = (*other).clone();
+            sorted.sort();
+            other_sorted.sort();
+            if keys != *other && sorted == other_sorted {
+                result.key_order.push(path.clone());
+            }
+        }
+    }
+
+    let new_scalars: HashMap<&JsonPath, &str> = new
+        .scalars
+        .iter()
+        .map(|(path, raw, _)| (path, raw.as_str()))
+        .collect();
+    for (path, raw, is_number) in &old.scalars {
+        let Some(other) = new_scalars.get(path) else {
+            continue;
+        };
+        if raw == other || !same_value(raw, other) {
+            continue;
+        }
+        if *is_number {
+            result.number_format.push(path.clone());
+        } else {
+            result.string_escapes.push(path.clone());
+        }
+    }
+
+    Ok(result)
+}
+
+fn same_value(a: &str, b: &str) -> bool {
+    match (
+        serde_json::from_str::<Value>(a),
+        serde_json::from_str::<Value>(b),
+    ) {
+        // Numbers are equal by value, as with `--coerce-numbers`
+        (Ok(Value::Number(a)), Ok(Value::Number(b))) => numbers_equal(&a, &b),
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Raw lexical layout of a JSON text
+#[derive(Default)]
+struct Layout {
+    /// Raw number and string lexemes by path: (path, raw text, is_number)
+    scalars: Vec<(JsonPath, String, bool)>,
+    /// Key order of every object
+    key_orders: Vec<(JsonPath, Vec<String>)>,
+    /// Whitespace runs between tokens
+    whitespace: Vec<String>,
+}
+
+impl Layout {
+    fn scan(text: &str) -> Result<Self, RjdError> {
+        let mut scanner = Scanner {
+            text,
+            pos: 0,
+            layout: Layout::default(),
+        };
+        scanner
+            .value(&JsonPath::new())
+            .and_then(|_| {
+                scanner.whitespace();
+                if scanner.pos == text.len() {
+                    Ok(())
+                } else {
+                    Err(scanner.error("trailing characters"))
+                }
+            })
+            .map_err(|message| RjdError::Internal { message })?;
+        Ok(scanner.layout)
+    }
+}
+
+struct Scanner<'a> {
+    text: &'a str,
+    pos: usize,
+    layout: Layout,
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("Failed to scan JSON at byte {}: {}", self.pos, message)
+    }
+
+    fn whitespace(&mut self) {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+        self.layout
+            .whitespace
+            .push(self.text[start..self.pos].to_string());
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        self.whitespace();
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", byte as char)))
+        }
+    }
+
+    fn string(&mut self) -> Result<&'a str, String> {
+        let start = self.pos;
+        self.pos += 1;
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            match byte {
+                b'\\' => self.pos += 1,
+                b'"' => return Ok(&self.text[start..self.pos]),
+                _ => {}
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn value(&mut self, path: &JsonPath) -> Result<(), String> {
+        self.whitespace();
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut keys = Vec::new();
+                self.whitespace();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        self.whitespace();
+                        if self.peek() != Some(b'"') {
+                            return Err(self.error("expected object key"));
+                        }
+                        let raw = self.string()?;
+                        let key: String =
+                            serde_json::from_str(raw).map_err(|e| self.error(&e.to_string()))?;
+                        self.expect(b':')?;
+                        self.value(&join_path(path, &key))?;
+                        keys.push(key);
+                        self.whitespace();
+                        match self.peek() {
+                            Some(b',') => self.pos += 1,
+                            Some(b'}') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => return Err(self.error("expected ',' or '}'")),
+                        }
+                    }
+                }
+                self.layout.key_orders.push((path.clone(), keys));
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                self.whitespace();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                } else {
+                    let mut index = 0;
+                    loop {
+                        self.value(&join_array_path(path, index))?;
+                        index += 1;
+                        self.whitespace();
+                        match self.peek() {
+                            Some(b',') => self.pos += 1,
+                            Some(b']') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => return Err(self.error("expected ',' or ']'")),
+                        }
+                    }
+                }
+            }
+            Some(b'"') => {
+                let raw = self.string()?;
+                self.layout
+                    .scalars
+                    .push((path.clone(), raw.to_string(), false));
+            }
+            Some(_) => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(b) if !b",]} \t\n\r".contains(&b)) {
+                    self.pos += 1;
+                }
+                let raw = &self.text[start..self.pos];
+                if raw.starts_with(|c: char| c == '-' || c.is_ascii_digit()) {
+                    self.layout
+                        .scalars
+                        .push((path.clone(), raw.to_string(), true));
+                } else if !matches!(raw, "true" | "false" | "null") {
+                    return Err(self.error("invalid literal"));
+                }
+            }
+            None => return Err(self.error("unexpected end of input")),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_texts_have_no_differences() {
+        let text = r#"{"a": [1, "x"], "b": {"c": null}}"#;
+        assert!(formatting_differences(text, text).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_detects_each_kind_of_difference() {
+        let old = r#"{"n": {"x": 1, "y": 2}, "s": "\u0041", "f": 1.50, "i": 1}"#;
+        let new = r#"{"n": {"y": 2, "x": 1}, "s": "A", "f": 1.5, "i": 1.0}"#;
+        let report = formatting_differences(old, new).unwrap();
+
+        assert_eq!(report.key_order, vec!["n".parse::<JsonPath>().unwrap()]);
+        assert_eq!(
+            report.string_escapes,
+            vec!["s".parse::<JsonPath>().unwrap()]
+        );
+        assert_eq!(
+            report.number_format,
+            vec!["f".parse::<JsonPath>().unwrap(), "i".parse().unwrap()]
+        );
+        assert!(!report.whitespace);
+    }
+
+    #[test]
+    fn test_invalid_json_is_an_error() {
+        assert!(formatting_differences("{", "{}").is_err());
+    }
+}
@@ -0,0 +1,156 @@
+//! Grouping changes by subtree
+//!
+//! Wide documents produce long flat change lists where related changes (all
+//! fields of `users[3]`, say) are scattered among unrelated ones.
+//! [`group_changes`] builds a tree of [`ChangeGroup`]s instead: every change
+//! sits in the group of its ancestor at a chosen depth, nested under the
+//! groups of that ancestor's own ancestors.
+
+use crate::json_path::JsonPath;
+use crate::types::{Change, Changes};
+
+/// Changes under one path, with nested groups for deeper subtrees
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeGroup<'a> {
+    /// Common ancestor of everything in the group
+    pub path: JsonPath,
+    /// Changes that belong directly to this group
+    pub changes: Vec<&'a Change>,
+    /// Groups of deeper paths, in the order their first change was found
+    pub groups: Vec<ChangeGroup<'a>>,
+}
+
+impl<'a> ChangeGroup<'a> {
+    fn new(path: JsonPath) -> Self {
+        Self {
+            path,
+            changes: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    /// Number of changes in this group and all nested groups
+    pub fn len(&self) -> usize {
+        self.changes.len() + self.groups.iter().map(ChangeGroup::len).sum::<usize>()
+    }
+
+    /// Check whether the group holds no changes at all
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Add a change to the group at `path` below this one, creating groups on the way
+    fn insert(&mut self, path: &JsonPath, change: &'a Change) {
+        let mut group = self;
+        for n in 1..=path.len() {
+            let prefix = path.prefix(n).expect("prefix within the path");
+            let index = match group.groups.iter().position(|g| g.path == prefix) {
+                Some(index) => index,
+                None => {
+                    group.groups.push(ChangeGroup::new(prefix));
+                    group.groups.len() - 1
+                }
+            };
+            group = &mut group.groups[index];
+        }
+        group.changes.push(change);
+    }
+
+    /// Merge every group that holds no changes of its own and a single
+    /// nested group into that nested group, so `users` → `users[3]` with
+    /// nothing else under `users` becomes just `users[3]`
+    fn compact(&mut self) {
+        for group in &mut self.groups {
+            group.compact();
+            while group.changes.is_empty() && group.groups.len() == 1 {
+                *group = group.groups.remove(0);
+            }
+        }
+    }
+}
+
+/// Group changes under their ancestors up to `depth` path segments deep
+///
+/// Each change goes in the group of its path's first `depth` segments (or its
+/// whole path, if shorter), and that group is nested under the groups of its
+/// shorter prefixes. Chains of groups without changes of their own are then
+/// collapsed into their only nested group. The returned root group has the
+/// empty path and holds changes to the whole document, if any.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::{diff, group_changes};
+/// use serde_json::json;
+///
+/// let old = json!({"users": [{"name": "a", "age": 1}, {"name": "b"}], "v": 1});
+/// let new = json!({"users": [{"name": "A", "age": 2}, {"name": "b"}], "v": 2});
+/// let changes = diff(&old, &new);
+///
+/// let root = group_changes(&changes, 2);
+/// let paths: Vec<String> = root.groups.iter().map(|g| g.path.to_string()).collect();
+/// assert_eq!(paths, ["users[0]", "v"]);
+/// assert_eq!(root.groups[0].changes.len(), 2);
+/// ```
+pub fn group_changes(changes: &Changes, depth: usize) -> ChangeGroup<'_> {
+    let mut root = ChangeGroup::new(JsonPath::new());
+    for change in changes.iter() {
+        let path = change.path();
+        let group_path = path.prefix(depth).unwrap_or_else(|| path.clone());
+        root.insert(&group_path, change);
+    }
+    root.compact();
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    fn shape(group: &ChangeGroup) -> serde_json::Value {
+        json!({
+            "path": group.path.to_string(),
+            "changes": group.changes.iter().map(|c| c.path().to_string()).collect::<Vec<_>>(),
+            "groups": group.groups.iter().map(shape).collect::<Vec<_>>(),
+        })
+    }
+
+    #[test]
+    fn test_group_changes() {
+        let old = json!({
+            "users": [{"name": "a", "tags": [1]}, {"name": "b"}, {"name": "c"}],
+            "meta": {"build": {"id": 1}}
+        });
+        let new = json!({
+            "users": [{"name": "A", "tags": [1, 2]}, {"name": "b"}, {"name": "C"}],
+            "meta": {"build": {"id": 2}},
+            "extra": true
+        });
+        let changes = diff(&old, &new);
+        let root = group_changes(&changes, 2);
+
+        assert_eq!(root.len(), changes.len());
+        assert_eq!(
+            shape(&root),
+            json!({"path": "", "changes": [], "groups": [
+                {"path": "users", "changes": [], "groups": [
+                    {"path": "users[0]", "changes": ["users[0].tags[1]", "users[0].name"], "groups": []},
+                    {"path": "users[2]", "changes": ["users[2].name"], "groups": []}
+                ]},
+                {"path": "extra", "changes": ["extra"], "groups": []},
+                {"path": "meta.build", "changes": ["meta.build.id"], "groups": []}
+            ]})
+        );
+    }
+
+    #[test]
+    fn test_group_root_change() {
+        let changes = diff(&json!(1), &json!("one"));
+        let root = group_changes(&changes, 3);
+        assert_eq!(root.changes.len(), 1);
+        assert!(root.groups.is_empty());
+        assert!(group_changes(&diff(&json!({}), &json!({})), 1).is_empty());
+    }
+}
@@ -0,0 +1,90 @@
+//! Usage examples shown by `rjd --help`, `rjd help-full` and the man page
+
+/// A command line and what it does
+pub struct Example {
+    pub command: &'static str,
+    pub description: &'static str,
+}
+
+/// Examples in the order they are shown
+pub const EXAMPLES: &[Example] = &[
+    Example {
+        command: "rjd old.json new.json",
+        description: "List added, removed and modified values",
+    },
+    Example {
+        command: "rjd old.json new.json --format rfc6902 -o patch.json",
+        description: "Write an RFC 6902 JSON Patch to a file",
+    },
+    Example {
+        command: "rjd old.json new.json --reverse --format rfc6902 -o rollback.json",
+        description: "Write the patch that turns the new document back into the old one",
+    },
+    Example {
+        command: "rjd old.json new.json --ignore '$.users[*].token' --ignore '/metadata/**'",
+        description: "Ignore volatile values",
+    },
+    Example {
+        command: "curl -s https://api.example.com/v1 | rjd expected.json --stdin",
+        description: "Compare a live response against a stored document",
+    },
+    Example {
+        command: "rjd old.json new.json --require-paths allowed.json --max-drift 5%",
+        description: "Fail a CI job (exit code 2) on unexpected or excessive changes",
+    },
+    Example {
+        command: "rjd assert old.json new.json --expect /version=2.0.0",
+        description: "Check that only the version changed, and to 2.0.0",
+    },
+    Example {
+        command: "rjd matrix envs/*.json --details pairs/",
+        description: "Count the changes between every pair of environment configs",
+    },
+    Example {
+        command: "rjd rev HEAD~1:config.json HEAD:config.json",
+        description: "Diff a file at two git revisions",
+    },
+    Example {
+        command: "rjd patch-in-place config.json --with patch.json --backup",
+        description: "Apply a JSON Patch to a file atomically",
+    },
+    Example {
+        command: "rjd patch invert deploy.patch.json config.json > rollback.patch.json",
+        description: "Write the patch that undoes a JSON Patch",
+    },
+];
+
+/// The examples as plain text, for `--help`
+pub fn examples_text() -> String {
+    let mut text = String::from("Examples:\n");
+    for example in EXAMPLES {
+        text.push_str(&format!(
+            "  # {}\n  {}\n\n",
+            example.description, example.command
+        ));
+    }
+    text.trim_end().to_string()
+}
+
+/// The examples as a roff man page section
+pub fn examples_roff() -> String {
+    let mut roff = String::from(".SH EXAMPLES\n");
+    for example in EXAMPLES {
+        roff.push_str(&format!(
+            ".TP\n\\fB{}\\fR\n{}\n",
+            escape_roff(example.command),
+            escape_roff(example.description)
+        ));
+    }
+    roff
+}
+
+/// Escape text for use on a roff text line
+fn escape_roff(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with(['.', '\'']) {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
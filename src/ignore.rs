@@ -1,13 +1,19 @@
+#[cfg(not(target_arch = "wasm32"))]
 use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
 
 use serde_json::Value;
 
 use crate::error::RjdError;
+use crate::json_path::{JsonPath, PathSegment};
+use crate::query::Query;
+use crate::types::split_subtree;
 
 /// Extract paths from a JSON object recursively.
 /// For each key with a truthy value, adds the path /prefix/key.
 /// Only adds leaf paths (doesn't add intermediate parent paths).
+#[cfg(not(target_arch = "wasm32"))]
 fn extract_paths_from_value(value: &Value, prefix: &str, paths: &mut Vec<String>) {
     if let Some(obj) = value.as_object() {
         for (key, val) in obj {
@@ -37,8 +43,12 @@ fn extract_paths_from_value(value: &Value, prefix: &str, paths: &mut Vec<String>
 
 /// Load ignore patterns from a JSON file.
 /// The file can contain either:
-/// - A JSON array of strings: ["/user/id", "/config/password"]
-/// - A JSON object with truthy values: {"user": {"id": true}, "tags": true}
+/// - A JSON array of JSON Pointers or JSONPath queries: ["/user/id", "$..password"]
+/// - A JSON object with truthy values: {"user": {"id": true}, "tags": {"**": true}}
+///
+/// Each pattern matches exactly one path unless it ends in `/**`, as
+/// `/tags/**` does above, which also matches everything below it.
+#[cfg(not(target_arch = "wasm32"))]
 pub fn load_ignore_patterns(path: &Path) -> Result<Vec<String>, RjdError> {
     // Check if file exists
     if !path.exists() {
@@ -84,16 +94,8 @@ pub fn load_ignore_patterns(path: &Path) -> Result<Vec<String>, RjdError> {
                 }
             })?;
 
-        // Validate that paths start with / (JSON Pointer format)
         for pattern in &patterns {
-            if !pattern.starts_with('/') {
-                return Err(RjdError::Internal {
-                    message: format!(
-                        "Ignore pattern '{}' must start with '/' (JSON Pointer format)",
-                        pattern
-                    ),
-                });
-            }
+            validate_pattern(pattern)?;
         }
 
         return Ok(patterns);
@@ -117,7 +119,38 @@ pub fn load_ignore_patterns(path: &Path) -> Result<Vec<String>, RjdError> {
     })
 }
 
+/// Check that a pattern is a JSON Pointer (`/user/id`), dot notation
+/// (`user.id`) or a JSONPath query (`$.users[*].token`), any of them ending in
+/// `/**` or `.**` for a subtree
+pub fn validate_pattern(pattern: &str) -> Result<(), RjdError> {
+    let (base, _) = split_subtree(pattern);
+    if pattern.starts_with('$') {
+        return Query::parse(base)
+            .map(|_| ())
+            .map_err(|e| RjdError::Internal {
+                message: format!("Invalid JSONPath pattern '{}': {}", pattern, e),
+            });
+    }
+    let path = JsonPath::parse_any(base).map_err(|e| RjdError::Internal {
+        message: format!(
+            "Invalid ignore pattern '{}': {} (expected a JSON Pointer like /user/id, dot notation like user.id or a JSONPath query)",
+            pattern, e
+        ),
+    })?;
+    let subtree = PathSegment::Key("**".to_string());
+    if path.segments().contains(&subtree) {
+        return Err(RjdError::Internal {
+            message: format!(
+                "Ignore pattern '{}' may only use '**' as its last token (e.g. /user/**)",
+                pattern
+            ),
+        });
+    }
+    Ok(())
+}
+
 /// Load and combine ignore patterns from multiple JSON files
+#[cfg(not(target_arch = "wasm32"))]
 pub fn load_all_ignore_patterns(paths: &[String]) -> Result<Vec<String>, RjdError> {
     let mut all_patterns = Vec::new();
 
@@ -188,16 +221,61 @@ mod tests {
     }
 
     #[test]
-    fn test_load_invalid_pattern_missing_slash() {
+    fn test_load_dot_notation_and_invalid_patterns() {
         let temp_file = NamedTempFile::new().unwrap();
         let file_path = temp_file.path().to_path_buf();
         drop(temp_file);
-        std::fs::write(&file_path, r#"["user/id", "/config/password"]"#).unwrap();
+        std::fs::write(&file_path, r#"["user.id", "/config/password"]"#).unwrap();
+        assert_eq!(
+            load_ignore_patterns(&file_path).unwrap(),
+            vec!["user.id", "/config/password"]
+        );
 
+        std::fs::write(&file_path, r#"["user[id]", "/config/password"]"#).unwrap();
         let result = load_ignore_patterns(&file_path);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_load_jsonpath_patterns() {
+        let temp_file = NamedTempFile::new().unwrap();
+        std::fs::write(temp_file.path(), r#"["$.users[*].token", "/id"]"#).unwrap();
+        let patterns = load_ignore_patterns(temp_file.path()).unwrap();
+        assert_eq!(patterns, vec!["$.users[*].token", "/id"]);
+
+        std::fs::write(temp_file.path(), r#"["$.users[x]"]"#).unwrap();
+        let err = load_ignore_patterns(temp_file.path()).unwrap_err();
+        assert!(err.to_string().contains("Invalid JSONPath pattern"));
+    }
+
+    #[test]
+    fn test_validate_subtree_patterns() {
+        assert!(validate_pattern("/user/**").is_ok());
+        assert!(validate_pattern("/**").is_ok());
+        assert!(validate_pattern("/user/**x").is_ok());
+        let err = validate_pattern("/user/**/id").unwrap_err();
+        assert!(err.to_string().contains("'**' as its last token"));
+    }
+
+    #[test]
+    fn test_validate_dot_notation_patterns() {
+        for valid in [
+            "user.id",
+            "users[0].email",
+            "user.**",
+            "/user/id",
+            "$..etag",
+        ] {
+            assert!(validate_pattern(valid).is_ok(), "{}", valid);
+        }
+        assert!(validate_pattern("user.**.id").is_err());
+        let err = validate_pattern("items[x]").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Invalid ignore pattern 'items[x]'"));
+        assert!(validate_pattern("/a~2").is_err());
+    }
+
     #[test]
     fn test_load_nonexistent_file() {
         let result = load_ignore_patterns(Path::new("/nonexistent/paths.json"));
@@ -0,0 +1,307 @@
+//! Loading of the two documents to compare
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use rjd::{
+    decode_document, decode_xml, estimated_memory, extract_json_span, extract_json_with_config,
+    find_duplicate_keys, load_json_input_and_text_with_config_policy_and_inline,
+    parse_json_with_config, read_file_bytes_with_config_and_policy,
+    read_input_text_with_config_policy_and_inline, read_stdin_bytes, read_stdin_text,
+    resolve_file_refs, split_json_pair, InputFormat, JsonPath, LoadConfig, RjdError, SymlinkPolicy,
+    XmlConvention,
+};
+
+use crate::cli::{Args, DuplicateKeyPolicy, OutputFormat};
+
+/// A loaded input document
+pub struct Input {
+    /// Parsed document
+    pub value: Value,
+    /// Raw text of the document, kept only when an option needs it
+    pub text: Option<String>,
+    /// Paths of repeated object keys, found only with `--strict-parse=warn`
+    pub duplicates: Vec<JsonPath>,
+}
+
+/// Load the old and new documents selected by the command-line arguments
+pub fn load_inputs(
+    args: &Args,
+    config: &LoadConfig,
+    policy: SymlinkPolicy,
+) -> Result<(Input, Input), RjdError> {
+    if let Some(budget) = args.max_memory {
+        check_file_sizes(args, budget)?;
+    }
+    let (mut old, mut new) = load_documents(args, config, policy)?;
+    if let Some(budget) = args.max_memory {
+        let needed = estimated_memory(&old.value) + estimated_memory(&new.value);
+        check_budget("the parsed inputs", needed as u64, budget)?;
+    }
+    unwrap_inputs(args, &mut old, &mut new)?;
+    Ok((old, new))
+}
+
+/// Refuse input files whose text alone exceeds the `--max-memory` budget
+fn check_file_sizes(args: &Args, budget: u64) -> Result<(), RjdError> {
+    if args.inline {
+        return Ok(());
+    }
+    let new = if args.stdin {
+        None
+    } else {
+        args.file2.as_ref()
+    };
+    let size = [args.file1.as_ref(), new]
+        .into_iter()
+        .flatten()
+        .filter_map(|input| std::fs::metadata(input).ok().filter(|m| m.is_file()))
+        .map(|metadata| metadata.len())
+        .sum();
+    check_budget("the input files", size, budget)
+}
+
+fn check_budget(what: &str, needed: u64, limit: u64) -> Result<(), RjdError> {
+    if needed > limit {
+        return Err(RjdError::MemoryBudgetExceeded {
+            what: what.to_string(),
+            needed,
+            limit,
+        });
+    }
+    Ok(())
+}
+
+/// Load the old and new documents as they are given
+fn load_documents(
+    args: &Args,
+    config: &LoadConfig,
+    policy: SymlinkPolicy,
+) -> Result<(Input, Input), RjdError> {
+    let Some(file1) = args.file1.as_deref() else {
+        // Both documents come from a single stdin stream
+        return load_stdin_pair(args, config, policy).map_err(|e| RjdError::Internal {
+            message: format!("Failed to load from stdin: {}", e),
+        });
+    };
+
+    let old = load_input(file1, args, config, policy).map_err(|e| RjdError::Internal {
+        message: format!("Failed to load '{}': {}", file1, e),
+    })?;
+
+    let new = if args.stdin {
+        load_stdin(args, config, policy).map_err(|e| RjdError::Internal {
+            message: format!("Failed to load from stdin: {}", e),
+        })?
+    } else {
+        let file2 = args
+            .file2
+            .as_deref()
+            .expect("file2 is required when --stdin is not used");
+        load_input(file2, args, config, policy).map_err(|e| RjdError::Internal {
+            message: format!("Failed to load '{}': {}", file2, e),
+        })?
+    };
+
+    Ok((old, new))
+}
+
+/// Replace each input with the value selected by `--unwrap`, in the order given
+fn unwrap_inputs(args: &Args, old: &mut Input, new: &mut Input) -> Result<(), RjdError> {
+    for unwrap in &args.unwrap {
+        for (selected, input, which) in [
+            (unwrap.old, &mut *old, "old"),
+            (unwrap.new, &mut *new, "new"),
+        ] {
+            if !selected {
+                continue;
+            }
+            let value =
+                input
+                    .value
+                    .pointer_mut(&unwrap.pointer)
+                    .ok_or_else(|| RjdError::InvalidArgs {
+                        message: format!(
+                            "--unwrap: no value at '{}' in the {} input",
+                            unwrap.pointer, which
+                        ),
+                    })?;
+            input.value = value.take();
+            // The raw text describes the whole document, not the unwrapped value
+            input.text = None;
+            if let Ok(prefix) = JsonPath::from_pointer(&unwrap.pointer) {
+                input.duplicates = input
+                    .duplicates
+                    .iter()
+                    .filter(|path| path.matches_prefix(&prefix))
+                    .map(|path| JsonPath::from_segments(path.segments()[prefix.len()..].to_vec()))
+                    .collect();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether any option needs the raw text of the inputs
+fn keep_text(args: &Args) -> bool {
+    args.report_format_only
+        || args.with_locations
+        || matches!(args.format, OutputFormat::FullAfter | OutputFormat::Gcc)
+}
+
+/// Whether the raw text must be read, if only to check it while loading
+fn read_text(args: &Args) -> bool {
+    keep_text(args) || args.strict_parse.is_some()
+}
+
+/// The format a non-JSON input is decoded from, or `None` for JSON text
+///
+/// `--input-format` applies to every input; without it, a file's extension
+/// decides. `input` is `None` for stdin.
+fn decoded_format(input: Option<&str>, args: &Args) -> Option<InputFormat> {
+    let format = match (args.input_format, input) {
+        (Some(format), _) => format,
+        (None, Some(input)) if !args.inline && !args.extract_json && Path::new(input).is_file() => {
+            InputFormat::from_path(Path::new(input))?
+        }
+        _ => return None,
+    };
+    (format != InputFormat::Json).then_some(format)
+}
+
+/// Decode a non-JSON input, converting XML with the conventions in `args`
+fn decode_input(
+    bytes: &[u8],
+    format: InputFormat,
+    source_name: &str,
+    args: &Args,
+    config: &LoadConfig,
+) -> Result<Value, RjdError> {
+    if format != InputFormat::Xml {
+        return decode_document(bytes, format, source_name, config);
+    }
+    let convention = XmlConvention {
+        attribute_prefix: args.xml_attribute_prefix.clone(),
+        text_key: args.xml_text_key.clone(),
+    };
+    decode_xml(bytes, &convention, source_name, config)
+}
+
+/// Load one input, decoding a non-JSON document or extracting an embedded JSON document,
+/// and resolving `$ref`s if requested
+fn load_input(
+    input: &str,
+    args: &Args,
+    config: &LoadConfig,
+    policy: SymlinkPolicy,
+) -> Result<Input, RjdError> {
+    let (value, text) = if let Some(format) = decoded_format(Some(input), args) {
+        let bytes = read_file_bytes_with_config_and_policy(&PathBuf::from(input), config, policy)?;
+        (decode_input(&bytes, format, input, args, config)?, None)
+    } else if args.extract_json {
+        let text =
+            read_input_text_with_config_policy_and_inline(input, config, policy, args.inline)?;
+        let value = extract_json_with_config(&text, args.nth as usize, input, config)?;
+        let document =
+            extract_json_span(&text, args.nth as usize).map(|(span, _)| text[span].to_string());
+        (value, document)
+    } else {
+        let (value, text) = load_json_input_and_text_with_config_policy_and_inline(
+            input,
+            config,
+            policy,
+            args.inline,
+        )?;
+        (value, read_text(args).then_some(text))
+    };
+
+    // References in files are relative to the file; inline JSON uses the working directory
+    let path = Path::new(input);
+    let base_dir = if !args.inline && path.is_file() {
+        path.parent().unwrap_or(Path::new("."))
+    } else {
+        Path::new(".")
+    };
+    finish(value, text, base_dir, args, config, policy)
+}
+
+/// Load the second input from stdin, extracting an embedded JSON document and resolving `$ref`s if requested
+fn load_stdin(args: &Args, config: &LoadConfig, policy: SymlinkPolicy) -> Result<Input, RjdError> {
+    if let Some(format) = decoded_format(None, args) {
+        let value = decode_input(&read_stdin_bytes()?, format, "stdin", args, config)?;
+        return finish(value, None, Path::new("."), args, config, policy);
+    }
+    let text = read_stdin_text()?;
+    let (value, text) = if args.extract_json {
+        let value = extract_json_with_config(&text, args.nth as usize, "stdin", config)?;
+        let document =
+            extract_json_span(&text, args.nth as usize).map(|(span, _)| text[span].to_string());
+        (value, document)
+    } else {
+        (parse_json_with_config(&text, config)?, Some(text))
+    };
+
+    finish(value, text, Path::new("."), args, config, policy)
+}
+
+/// Load both documents from one stdin stream
+fn load_stdin_pair(
+    args: &Args,
+    config: &LoadConfig,
+    policy: SymlinkPolicy,
+) -> Result<(Input, Input), RjdError> {
+    let text = read_stdin_text()?;
+    let (first, second) = split_json_pair(&text, args.stdin_separator.as_deref())?;
+
+    let parse = |document: &str, which: &str| {
+        let value = parse_json_with_config(document, config).map_err(|e| RjdError::Internal {
+            message: format!("Failed to parse {} JSON document: {}", which, e),
+        })?;
+        finish(
+            value,
+            Some(document.to_string()),
+            Path::new("."),
+            args,
+            config,
+            policy,
+        )
+    };
+    Ok((parse(first, "first")?, parse(second, "second")?))
+}
+
+/// Check for repeated keys, apply `$ref` resolution and drop the raw text unless it is needed
+fn finish(
+    value: Value,
+    text: Option<String>,
+    base_dir: &Path,
+    args: &Args,
+    config: &LoadConfig,
+    policy: SymlinkPolicy,
+) -> Result<Input, RjdError> {
+    let value = if args.resolve_refs {
+        resolve_file_refs(&value, base_dir, config, policy)?
+    } else {
+        value
+    };
+    let duplicates = match (&text, args.strict_parse) {
+        (Some(text), Some(policy)) => {
+            let duplicates = find_duplicate_keys(text).map_err(|e| RjdError::Internal {
+                message: e.to_string(),
+            })?;
+            if policy == DuplicateKeyPolicy::Error && !duplicates.is_empty() {
+                return Err(RjdError::DuplicateKeys {
+                    paths: duplicates.iter().map(JsonPath::to_string).collect(),
+                });
+            }
+            duplicates
+        }
+        _ => Vec::new(),
+    };
+    let text = text.filter(|_| keep_text(args));
+
+    Ok(Input {
+        value,
+        text,
+        duplicates,
+    })
+}
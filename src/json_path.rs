@@ -21,10 +21,24 @@
 //! let path = JsonPath::from_str("users[0].email").unwrap();
 //! assert_eq!(path.to_string(), "users[0].email");
 //!
-//! // Convert to JSON Pointer (RFC 6901)
+//! // Convert to JSON Pointer (RFC 6901) and JSONPath
 //! assert_eq!(path.to_json_pointer(), "/users/0/email");
+//! assert_eq!(path.to_jsonpath(), "$.users[0].email");
+//!
+//! // And back
+//! assert_eq!(JsonPath::from_pointer("/users/0/email").unwrap(), path);
+//!
+//! // Or accept either notation, as the command line does
+//! assert_eq!(JsonPath::parse_any("/users/0/email").unwrap(), path);
 //! ```
+//!
+//! A JSONPath query without wildcards converts back with
+//! [`Query::to_path`](crate::query::Query::to_path). A [`RelativePointer`]
+//! (`1/name`) refers to a location relative to another path instead of the
+//! root.
 
+use serde_json::{Map, Value};
+use std::borrow::Cow;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::str::FromStr;
@@ -32,7 +46,7 @@ use std::str::FromStr;
 /// A single segment in a JSON path
 ///
 /// Represents either an object property key or an array index.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum PathSegment {
     /// Object property key (e.g., "user" in "user.name")
     Key(String),
@@ -59,7 +73,7 @@ impl Hash for PathSegment {
 ///
 /// Represents a path to a location in a JSON value using dot notation.
 /// Paths are composed of segments that can be either object keys or array indices.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct JsonPath {
     /// The segments that make up this path
     segments: Vec<PathSegment>,
@@ -145,6 +159,94 @@ impl JsonPath {
         })
     }
 
+    /// Look up the value at this path
+    ///
+    /// Keys only match object members and indices only match array elements.
+    /// The empty path resolves to `value` itself.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rjd::json_path::JsonPath;
+    /// use serde_json::json;
+    ///
+    /// let doc = json!({"users": [{"email": "a@example.com"}]});
+    /// let path: JsonPath = "users[0].email".parse().unwrap();
+    /// assert_eq!(path.resolve(&doc), Some(&json!("a@example.com")));
+    /// assert_eq!("users[1]".parse::<JsonPath>().unwrap().resolve(&doc), None);
+    /// ```
+    pub fn resolve<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |current, segment| match (segment, current) {
+                (PathSegment::Key(key), Value::Object(map)) => map.get(key),
+                (PathSegment::Index(index), Value::Array(items)) => items.get(*index),
+                _ => None,
+            })
+    }
+
+    /// Look up the value at this path for modification
+    ///
+    /// See [`JsonPath::resolve`].
+    pub fn resolve_mut<'a>(&self, value: &'a mut Value) -> Option<&'a mut Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |current, segment| match (segment, current) {
+                (PathSegment::Key(key), Value::Object(map)) => map.get_mut(key),
+                (PathSegment::Index(index), Value::Array(items)) => items.get_mut(*index),
+                _ => None,
+            })
+    }
+
+    /// Store `new_value` at this path, creating missing containers on the way
+    ///
+    /// Missing keys are added to objects, arrays are padded with `null` up to
+    /// the index, and values of the wrong type along the path are replaced by
+    /// an empty object or array. The empty path replaces `root`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rjd::json_path::JsonPath;
+    /// use serde_json::{json, Value};
+    ///
+    /// let mut doc = Value::Null;
+    /// let path: JsonPath = "users[1].email".parse().unwrap();
+    /// path.set(&mut doc, json!("b@example.com"));
+    /// assert_eq!(doc, json!({"users": [null, {"email": "b@example.com"}]}));
+    /// ```
+    pub fn set(&self, root: &mut Value, new_value: Value) {
+        let mut current = root;
+        for segment in &self.segments {
+            current = match segment {
+                PathSegment::Key(key) => {
+                    if !current.is_object() {
+                        *current = Value::Object(Map::new());
+                    }
+                    match current {
+                        Value::Object(map) => map.entry(key.clone()).or_insert(Value::Null),
+                        _ => unreachable!("replaced by an object above"),
+                    }
+                }
+                PathSegment::Index(index) => {
+                    if !current.is_array() {
+                        *current = Value::Array(Vec::new());
+                    }
+                    match current {
+                        Value::Array(items) => {
+                            if items.len() <= *index {
+                                items.resize(*index + 1, Value::Null);
+                            }
+                            &mut items[*index]
+                        }
+                        _ => unreachable!("replaced by an array above"),
+                    }
+                }
+            };
+        }
+        *current = new_value;
+    }
+
     /// Convert this path to JSON Pointer format (RFC 6901)
     ///
     /// JSON Pointer uses a slash-separated path with special encoding:
@@ -181,6 +283,144 @@ impl JsonPath {
         }
         result
     }
+
+    /// Parse a JSON Pointer (RFC 6901) such as `/users/0/email`
+    ///
+    /// `~1` and `~0` are decoded to `/` and `~`. Tokens that are array
+    /// indices (`0`, or digits without a leading zero) become
+    /// [`PathSegment::Index`]; a pointer alone cannot tell them apart from
+    /// numeric object keys. The empty pointer is the root.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rjd::json_path::JsonPath;
+    ///
+    /// let path = JsonPath::from_pointer("/users/0/a~1b").unwrap();
+    /// assert_eq!(path.to_string(), "users[0].a/b");
+    /// assert_eq!(path.to_json_pointer(), "/users/0/a~1b");
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the pointer is not empty and does not start with
+    /// `/`, or contains a `~` not followed by `0` or `1`.
+    pub fn from_pointer(pointer: &str) -> Result<Self, ParseError> {
+        if pointer.is_empty() {
+            return Ok(Self::new());
+        }
+        let Some(tokens) = pointer.strip_prefix('/') else {
+            return Err(ParseError::InvalidPointer(pointer.to_string()));
+        };
+
+        let mut segments = Vec::new();
+        for token in tokens.split('/') {
+            let is_index = !token.is_empty()
+                && token.bytes().all(|b| b.is_ascii_digit())
+                && (token == "0" || !token.starts_with('0'));
+            if let Some(index) = is_index.then(|| token.parse().ok()).flatten() {
+                segments.push(PathSegment::Index(index));
+                continue;
+            }
+
+            let mut key = String::with_capacity(token.len());
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                if c != '~' {
+                    key.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('0') => key.push('~'),
+                    Some('1') => key.push('/'),
+                    _ => return Err(ParseError::InvalidPointer(pointer.to_string())),
+                }
+            }
+            segments.push(PathSegment::Key(key));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Parse a JSON Pointer or dot notation, telling them apart by a leading `/`
+    ///
+    /// Text starting with `/` is read as a JSON Pointer (see
+    /// [`from_pointer`](Self::from_pointer)), anything else as dot notation.
+    /// The empty string is the root either way. A dot-notation path whose
+    /// first key starts with `/` has to be written as a pointer instead
+    /// (`/~1key`).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rjd::json_path::JsonPath;
+    ///
+    /// let dotted = JsonPath::parse_any("users[0].email").unwrap();
+    /// let pointer = JsonPath::parse_any("/users/0/email").unwrap();
+    /// assert_eq!(dotted, pointer);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns the error of the notation the text was read as.
+    pub fn parse_any(text: &str) -> Result<Self, ParseError> {
+        if text.starts_with('/') {
+            Self::from_pointer(text)
+        } else {
+            text.parse()
+        }
+    }
+
+    /// Convert this path to a JSONPath expression such as `$.users[0].email`
+    ///
+    /// Keys that are not plain identifiers use bracket notation
+    /// (`$['a.b']`), so the result always parses back with
+    /// [`Query::parse`](crate::query::Query::parse) and selects this path.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rjd::json_path::{JsonPath, PathSegment};
+    ///
+    /// let path = JsonPath::from_segments(vec![
+    ///     PathSegment::Key("users".to_string()),
+    ///     PathSegment::Index(0),
+    ///     PathSegment::Key("e-mail".to_string()),
+    /// ]);
+    /// assert_eq!(path.to_jsonpath(), "$.users[0]['e-mail']");
+    /// assert_eq!(JsonPath::new().to_jsonpath(), "$");
+    /// ```
+    pub fn to_jsonpath(&self) -> String {
+        let mut result = String::from("$");
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Key(key) if is_identifier(key) => {
+                    result.push('.');
+                    result.push_str(key);
+                }
+                PathSegment::Key(key) => {
+                    result.push_str("['");
+                    for c in key.chars() {
+                        if matches!(c, '\\' | '\'') {
+                            result.push('\\');
+                        }
+                        result.push(c);
+                    }
+                    result.push_str("']");
+                }
+                PathSegment::Index(i) => {
+                    result.push_str(&format!("[{}]", i));
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Whether a key can be written after a dot in JSONPath
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
 }
 
 impl Default for JsonPath {
@@ -219,6 +459,37 @@ impl fmt::Display for JsonPath {
     }
 }
 
+/// Serializes as a dot notation string, e.g. `"users[0].email"`
+impl serde::Serialize for JsonPath {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserializes from a dot notation string
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::json_path::JsonPath;
+///
+/// let path: JsonPath = serde_json::from_str("\"users[0].email\"").unwrap();
+/// assert_eq!(serde_json::to_string(&path).unwrap(), "\"users[0].email\"");
+/// ```
+impl<'de> serde::Deserialize<'de> for JsonPath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let path = String::deserialize(deserializer)?;
+        path.parse()
+            .map_err(|e| serde::de::Error::custom(format!("invalid path '{}': {}", path, e)))
+    }
+}
+
 /// Error type for path parsing failures
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
 pub enum ParseError {
@@ -233,6 +504,12 @@ pub enum ParseError {
 
     #[error("Unexpected character '{0}' at position {1}")]
     UnexpectedCharacter(char, usize),
+
+    #[error("Invalid JSON Pointer '{0}': expected '/'-separated tokens with '~0'/'~1' escapes")]
+    InvalidPointer(String),
+
+    #[error("Invalid Relative JSON Pointer '{0}': expected a level count like 1, 0+1 or 2-1 followed by a JSON Pointer or '#'")]
+    InvalidRelativePointer(String),
 }
 
 /// Parse dot notation to create a JsonPath
@@ -344,6 +621,146 @@ impl FromStr for JsonPath {
     }
 }
 
+/// A Relative JSON Pointer such as `1/name`, `0+1` or `2#`
+///
+/// A relative pointer is resolved from a location in a document rather
+/// than from its root: it goes up a number of levels, optionally moves to
+/// a sibling array element (`+N`/`-N`), and then either follows a JSON
+/// Pointer or, with `#`, yields the key or index of the location it reached.
+/// See the Relative JSON Pointer draft for the full semantics.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::json_path::{JsonPath, RelativePointer};
+/// use serde_json::json;
+///
+/// let doc = json!({"users": [{"id": 7, "name": "ann"}, {"id": 8, "name": "bob"}]});
+/// let anchor: JsonPath = "users[0].id".parse().unwrap();
+///
+/// let sibling: RelativePointer = "1/name".parse().unwrap();
+/// assert_eq!(sibling.locate(&anchor).unwrap().to_string(), "users[0].name");
+/// assert_eq!(*sibling.resolve(&anchor, &doc).unwrap(), json!("ann"));
+///
+/// let next_user: RelativePointer = "1+1/id".parse().unwrap();
+/// assert_eq!(*next_user.resolve(&anchor, &doc).unwrap(), json!(8));
+///
+/// let index: RelativePointer = "1#".parse().unwrap();
+/// assert_eq!(*index.resolve(&anchor, &doc).unwrap(), json!(0));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RelativePointer {
+    /// Number of levels to go up from the anchor
+    up: usize,
+    /// Offset applied to the array index reached by going up
+    index_offset: Option<isize>,
+    /// Pointer followed from there, or `None` for `#`
+    pointer: Option<JsonPath>,
+}
+
+impl RelativePointer {
+    /// Number of levels the pointer goes up from its anchor
+    pub fn up(&self) -> usize {
+        self.up
+    }
+
+    /// Whether the pointer asks for a key or index (`#`) rather than a value
+    pub fn is_name(&self) -> bool {
+        self.pointer.is_none()
+    }
+
+    /// The absolute path this pointer refers to from `anchor`
+    ///
+    /// For a `#` pointer this is the location whose key or index is asked
+    /// for. Returns `None` if the pointer goes above the root, applies an
+    /// index offset to a location that is not an array element or moves
+    /// before the first element, or asks for the name of the root.
+    pub fn locate(&self, anchor: &JsonPath) -> Option<JsonPath> {
+        let depth = anchor.len().checked_sub(self.up)?;
+        let mut segments = anchor.segments()[..depth].to_vec();
+        if let Some(offset) = self.index_offset {
+            let Some(PathSegment::Index(index)) = segments.last_mut() else {
+                return None;
+            };
+            *index = index.checked_add_signed(offset)?;
+        }
+        match &self.pointer {
+            Some(pointer) => segments.extend(pointer.segments().iter().cloned()),
+            None if segments.is_empty() => return None,
+            None => {}
+        }
+        Some(JsonPath::from_segments(segments))
+    }
+
+    /// Evaluate the pointer from `anchor` in `document`
+    ///
+    /// Returns the value it refers to, or for a `#` pointer the key (as a
+    /// string) or array index (as a number) of the location it reaches.
+    /// Returns `None` if that location does not exist in `document`.
+    pub fn resolve<'a>(&self, anchor: &JsonPath, document: &'a Value) -> Option<Cow<'a, Value>> {
+        let path = self.locate(anchor)?;
+        let value = path.resolve(document)?;
+        if self.pointer.is_some() {
+            return Some(Cow::Borrowed(value));
+        }
+        match path.segments().last()? {
+            PathSegment::Key(key) => Some(Cow::Owned(Value::String(key.clone()))),
+            PathSegment::Index(index) => Some(Cow::Owned(Value::from(*index))),
+        }
+    }
+}
+
+impl fmt::Display for RelativePointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.up)?;
+        if let Some(offset) = self.index_offset {
+            write!(f, "{:+}", offset)?;
+        }
+        match &self.pointer {
+            Some(pointer) => f.write_str(&pointer.to_json_pointer()),
+            None => f.write_str("#"),
+        }
+    }
+}
+
+/// Parse a Relative JSON Pointer: a level count, an optional index
+/// offset, and a JSON Pointer or `#`
+impl FromStr for RelativePointer {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseError::InvalidRelativePointer(s.to_string());
+        // A non-negative integer without leading zeros, and the rest of the text
+        let integer = |text: &str| -> Option<(usize, usize)> {
+            let digits = text.bytes().take_while(u8::is_ascii_digit).count();
+            if digits == 0 || (digits > 1 && text.starts_with('0')) {
+                return None;
+            }
+            Some((text[..digits].parse().ok()?, digits))
+        };
+
+        let (up, digits) = integer(s).ok_or_else(invalid)?;
+        let mut rest = &s[digits..];
+        let mut index_offset = None;
+        if let Some(sign @ ('+' | '-')) = rest.chars().next() {
+            let (offset, digits) = integer(&rest[1..]).ok_or_else(invalid)?;
+            let offset = isize::try_from(offset).map_err(|_| invalid())?;
+            index_offset = Some(if sign == '-' { -offset } else { offset });
+            rest = &rest[1 + digits..];
+        }
+        let pointer = if rest == "#" {
+            None
+        } else {
+            Some(JsonPath::from_pointer(rest).map_err(|_| invalid())?)
+        };
+        Ok(Self {
+            up,
+            index_offset,
+            pointer,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -520,4 +937,171 @@ mod tests {
         assert!(set.contains(&path1));
         assert!(set.contains(&path3));
     }
+
+    #[test]
+    fn test_serde_uses_dot_notation() {
+        let path = JsonPath::from_str("users[0].email").unwrap();
+        let json = serde_json::to_value(&path).unwrap();
+        assert_eq!(json, serde_json::json!("users[0].email"));
+        assert_eq!(serde_json::from_value::<JsonPath>(json).unwrap(), path);
+
+        let err = serde_json::from_value::<JsonPath>(serde_json::json!("items[x]")).unwrap_err();
+        assert!(err.to_string().contains("invalid path 'items[x]'"));
+    }
+
+    #[test]
+    fn test_pointer_and_jsonpath_conversions() {
+        use crate::query::Query;
+
+        let path = JsonPath::from_segments(vec![
+            PathSegment::Key("a/b~c".to_string()),
+            PathSegment::Index(10),
+            PathSegment::Key("it's".to_string()),
+            PathSegment::Key("007".to_string()),
+            PathSegment::Key(String::new()),
+            PathSegment::Key("_ok1".to_string()),
+        ]);
+        assert_eq!(path.to_json_pointer(), "/a~1b~0c/10/it's/007//_ok1");
+        assert_eq!(
+            JsonPath::from_pointer(&path.to_json_pointer()),
+            Ok(path.clone())
+        );
+        assert_eq!(
+            path.to_jsonpath(),
+            r"$['a/b~c'][10]['it\'s']['007']['']._ok1"
+        );
+        let query = Query::parse(&path.to_jsonpath()).unwrap();
+        assert!(query.matches(&path));
+        assert_eq!(query.to_path(), Some(path));
+
+        assert_eq!(JsonPath::from_pointer(""), Ok(JsonPath::new()));
+        assert_eq!(
+            JsonPath::from_pointer("/0/1").unwrap().segments(),
+            &[PathSegment::Index(0), PathSegment::Index(1)]
+        );
+        for invalid in ["a/b", "/a~2", "/a~"] {
+            assert_eq!(
+                JsonPath::from_pointer(invalid),
+                Err(ParseError::InvalidPointer(invalid.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_relative_pointer_parsing() {
+        for text in ["0", "1/name", "2#", "0+1", "3-2/a~1b/0", "10/"] {
+            let pointer: RelativePointer = text.parse().unwrap();
+            assert_eq!(pointer.to_string(), text);
+        }
+        let pointer: RelativePointer = "2-1#".parse().unwrap();
+        assert_eq!(pointer.up(), 2);
+        assert!(pointer.is_name());
+
+        for invalid in [
+            "", "#", "/a", "01", "1+", "1+01", "1a", "1#/a", "0/a~2", "-1",
+        ] {
+            assert_eq!(
+                invalid.parse::<RelativePointer>(),
+                Err(ParseError::InvalidRelativePointer(invalid.to_string())),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_relative_pointer_resolution() {
+        // The examples of the Relative JSON Pointer draft
+        let doc = serde_json::json!({
+            "foo": ["bar", "baz", "biz"],
+            "highly": {"nested": {"objects": true}}
+        });
+        let resolve = |anchor: &str, pointer: &str| {
+            let anchor = JsonPath::from_pointer(anchor).unwrap();
+            let pointer: RelativePointer = pointer.parse().unwrap();
+            pointer.resolve(&anchor, &doc).map(Cow::into_owned)
+        };
+
+        assert_eq!(resolve("/foo/1", "0"), Some("baz".into()));
+        assert_eq!(resolve("/foo/1", "1/0"), Some("bar".into()));
+        assert_eq!(resolve("/foo/1", "0-1"), Some("bar".into()));
+        assert_eq!(
+            resolve("/foo/1", "2/highly/nested/objects"),
+            Some(true.into())
+        );
+        assert_eq!(resolve("/foo/1", "0#"), Some(1.into()));
+        assert_eq!(resolve("/foo/1", "0+1#"), Some(2.into()));
+        assert_eq!(resolve("/foo/1", "1#"), Some("foo".into()));
+        assert_eq!(resolve("/highly/nested", "0/objects"), Some(true.into()));
+        assert_eq!(
+            resolve("/highly/nested", "1/nested/objects"),
+            Some(true.into())
+        );
+        assert_eq!(resolve("/highly/nested", "2/foo/0"), Some("bar".into()));
+        assert_eq!(resolve("/highly/nested", "0#"), Some("nested".into()));
+        assert_eq!(resolve("/highly/nested", "1#"), Some("highly".into()));
+
+        // Above the root, past the array, offsets on keys and the root's name
+        assert_eq!(resolve("/foo/1", "3"), None);
+        assert_eq!(resolve("/foo/1", "0+2"), None);
+        assert_eq!(resolve("/foo/1", "0-2"), None);
+        assert_eq!(resolve("/highly/nested", "0+1"), None);
+        assert_eq!(resolve("/foo/1", "2#"), None);
+        assert_eq!(
+            "0+1/x"
+                .parse::<RelativePointer>()
+                .unwrap()
+                .locate(&"a[0]".parse().unwrap())
+                .unwrap()
+                .to_string(),
+            "a[1].x"
+        );
+    }
+
+    #[test]
+    fn test_parse_any() {
+        let expected: JsonPath = "users[0].a/b".parse().unwrap();
+        assert_eq!(JsonPath::parse_any("users[0].a/b"), Ok(expected.clone()));
+        assert_eq!(JsonPath::parse_any("/users/0/a~1b"), Ok(expected.clone()));
+        assert_eq!(
+            JsonPath::parse_any(&expected.to_json_pointer()),
+            JsonPath::parse_any(&expected.to_string())
+        );
+        assert_eq!(JsonPath::parse_any(""), Ok(JsonPath::new()));
+        assert_eq!(
+            JsonPath::parse_any("/a~2"),
+            Err(ParseError::InvalidPointer("/a~2".to_string()))
+        );
+        assert!(JsonPath::parse_any("items[x]").is_err());
+    }
+
+    #[test]
+    fn test_resolve_and_set() {
+        let mut doc = serde_json::json!({"a": {"list": [1, {"b": true}]}, "0": "key"});
+        let path = JsonPath::from_str("a.list[1].b").unwrap();
+        assert_eq!(path.resolve(&doc), Some(&Value::Bool(true)));
+        assert_eq!(JsonPath::new().resolve(&doc), Some(&doc));
+        // Indices never match object keys
+        let index = JsonPath::from_segments(vec![PathSegment::Index(0)]);
+        assert_eq!(index.resolve(&doc), None);
+
+        *path.resolve_mut(&mut doc).unwrap() = Value::Bool(false);
+        assert_eq!(doc["a"]["list"][1]["b"], Value::Bool(false));
+
+        JsonPath::from_str("a.list[3]")
+            .unwrap()
+            .set(&mut doc, Value::from(4));
+        assert_eq!(
+            doc["a"]["list"],
+            serde_json::json!([1, {"b": false}, null, 4])
+        );
+
+        JsonPath::from_str("0.x")
+            .unwrap()
+            .set(&mut doc, Value::from(1));
+        assert_eq!(doc["0"], serde_json::json!({"x": 1}));
+
+        JsonPath::new().set(&mut doc, Value::Null);
+        assert_eq!(doc, Value::Null);
+    }
 }
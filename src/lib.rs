@@ -2,24 +2,98 @@
 //!
 //! This library provides JSON comparison and diff functionality.
 
-pub use diff::diff;
+pub use canonical::{canonicalize, jcs_normalize, to_jcs_string};
+pub use decode::{decode_document, InputFormat};
+pub use describe::{describe, DescriptionTemplate};
+#[cfg(feature = "parallel")]
+pub use diff::diff_parallel;
+pub use diff::{
+    describe_changes, diff, diff_borrowed, diff_borrowed_with_options, diff_serialize,
+    diff_with_cancellation, diff_with_options, traverse, ArrayDiff, CancellationToken, ChangeRef,
+    ChangesRef, Comparator, DiffOptions, SimilarityThreshold, ValueComparator, ValueVisitor,
+};
+pub use drift::Drift;
 pub use error::RjdError;
-pub use formatter::create_formatter;
+pub use extract::{extract_json, extract_json_span, extract_json_with_config};
+pub use formatter::{
+    create_formatter, create_formatter_with_options, FormatterOptions, FormatterRegistry,
+};
+pub use formatting::{formatting_differences, FormattingDifferences};
+pub use grouping::{group_changes, ChangeGroup};
+pub use ignore::validate_pattern;
+#[cfg(not(target_arch = "wasm32"))]
 pub use ignore::{load_all_ignore_patterns, load_ignore_patterns};
-pub use json_path::{JsonPath, ParseError, PathSegment};
+pub use json_path::{JsonPath, ParseError, PathSegment, RelativePointer};
 pub use loader::{
-    load_json_file, load_json_file_with_config, load_json_file_with_config_and_policy,
-    load_json_input, load_json_input_with_config, load_json_input_with_config_and_policy,
-    load_json_input_with_config_policy_and_inline, load_json_stdin, load_json_stdin_with_config,
+    find_duplicate_keys, parse_json_pair_with_config, parse_json_with_config, split_json_pair,
     LoadConfig, SymlinkPolicy,
 };
-pub use types::{Change, Changes};
+#[cfg(not(target_arch = "wasm32"))]
+pub use loader::{
+    load_json_file, load_json_file_with_config, load_json_file_with_config_and_policy,
+    load_json_input, load_json_input_and_text_with_config_policy_and_inline,
+    load_json_input_with_config, load_json_input_with_config_and_policy,
+    load_json_input_with_config_policy_and_inline, load_json_stdin,
+    load_json_stdin_pair_with_config, load_json_stdin_with_config,
+    read_file_bytes_with_config_and_policy, read_file_with_config_and_policy,
+    read_input_text_with_config_policy_and_inline, read_stdin_bytes, read_stdin_text,
+};
+pub use location::{locate_paths, SourceLocation, SourceText};
+pub use order::ChangeOrder;
+pub use patch::{
+    apply_patch, compose_patches, invert_patch, minimize_patch, simplify_patch, PatchError,
+};
+pub use path_map::{PathSubstitution, PathTransformer};
+#[cfg(not(target_arch = "wasm32"))]
+pub use refs::resolve_file_refs;
+pub use stats::{estimated_memory, profile, Profile};
+pub use types::{
+    Change, ChangeKind, Changes, PathCoverage, Truncation, TruncationReason, Warning, WarningKind,
+};
+pub use xml::{decode_xml, XmlConvention};
 
+pub mod binary;
+#[cfg(any(feature = "ffi", feature = "wasm"))]
+mod bindings;
+mod canonical;
+mod change_set;
+pub mod color;
+mod datetime;
+mod decimal;
+mod decode;
+mod describe;
 mod diff;
+mod drift;
 mod error;
+mod extract;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod formatter;
+mod formatting;
+mod grouping;
 pub mod ignore;
 pub mod json_path;
 mod loader;
+mod location;
+mod order;
+mod patch;
 mod path;
+mod path_map;
+pub mod query;
+mod reconstruct;
+#[cfg(feature = "analytics")]
+mod records;
+#[cfg(not(target_arch = "wasm32"))]
+mod refs;
+mod reverse;
+pub mod rules;
+mod sample;
+pub mod schema;
+mod stats;
+pub mod string_diff;
+#[cfg(feature = "test-helpers")]
+pub mod test_helpers;
 pub mod types;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod xml;
@@ -1,8 +1,15 @@
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
 use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
 use std::fs;
-use std::path::PathBuf;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
 
 use crate::error::RjdError;
+use crate::json_path::JsonPath;
+use crate::path::{join_array_path, join_path};
 
 /// Symlink following policy
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -14,7 +21,7 @@ pub enum SymlinkPolicy {
 }
 
 /// Check if JSON value exceeds depth limit
-fn check_json_depth(value: &Value, max_depth: usize) -> Result<(), usize> {
+pub(crate) fn check_json_depth(value: &Value, max_depth: usize) -> Result<(), usize> {
     fn check_depth(value: &Value, current_depth: usize, max_depth: usize) -> Result<(), usize> {
         if current_depth > max_depth {
             return Err(current_depth);
@@ -41,7 +48,10 @@ fn check_json_depth(value: &Value, max_depth: usize) -> Result<(), usize> {
 }
 
 /// Parse JSON string with depth limit
-fn parse_json_with_depth_limit(content: &str, max_depth: usize) -> Result<Value, String> {
+pub(crate) fn parse_json_with_depth_limit(
+    content: &str,
+    max_depth: usize,
+) -> Result<Value, String> {
     // First parse the JSON normally
     let value: Value =
         serde_json::from_str(content).map_err(|e| format!("Failed to parse JSON: {}", e))?;
@@ -156,21 +166,65 @@ impl LoadConfig {
 }
 
 /// Load and parse a JSON file
+#[cfg(not(target_arch = "wasm32"))]
 pub fn load_json_file(path: &PathBuf) -> Result<Value, RjdError> {
     load_json_file_with_config(path, &LoadConfig::default())
 }
 
 /// Load and parse a JSON file with resource limits
+#[cfg(not(target_arch = "wasm32"))]
 pub fn load_json_file_with_config(path: &PathBuf, config: &LoadConfig) -> Result<Value, RjdError> {
     load_json_file_with_config_and_policy(path, config, SymlinkPolicy::Reject)
 }
 
 /// Load and parse a JSON file with resource limits and symlink policy
+#[cfg(not(target_arch = "wasm32"))]
 pub fn load_json_file_with_config_and_policy(
     path: &PathBuf,
     config: &LoadConfig,
     policy: SymlinkPolicy,
 ) -> Result<Value, RjdError> {
+    let content = read_file_with_config_and_policy(path, config, policy)?;
+    parse_json_file_content(path, &content, config)
+}
+
+/// Parse the content of a JSON file with depth checking
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_json_file_content(
+    path: &Path,
+    content: &str,
+    config: &LoadConfig,
+) -> Result<Value, RjdError> {
+    parse_json_with_depth_limit(content, config.max_json_depth).map_err(|msg| {
+        // Convert string error to serde_json::Error for consistency
+        RjdError::JsonParse {
+            path: path.to_path_buf(),
+            source: serde_json::Error::io(std::io::Error::other(msg)),
+        }
+    })
+}
+
+/// Read a file as text, applying the size limit and symlink policy
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_file_with_config_and_policy(
+    path: &PathBuf,
+    config: &LoadConfig,
+    policy: SymlinkPolicy,
+) -> Result<String, RjdError> {
+    let bytes = read_file_bytes_with_config_and_policy(path, config, policy)?;
+    String::from_utf8(bytes).map_err(|e| RjdError::FileRead {
+        path: path.clone(),
+        source: std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+    })
+}
+
+/// Read a file's bytes, applying the size limit and symlink policy
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_file_bytes_with_config_and_policy(
+    path: &PathBuf,
+    config: &LoadConfig,
+    policy: SymlinkPolicy,
+) -> Result<Vec<u8>, RjdError> {
     // Check if file exists
     if !path.exists() {
         return Err(RjdError::FileRead {
@@ -203,7 +257,7 @@ pub fn load_json_file_with_config_and_policy(
                 })?;
 
                 // Use canonicalized path for subsequent checks
-                return load_json_file_with_config_and_policy(&canonical, config, policy);
+                return read_file_bytes_with_config_and_policy(&canonical, config, policy);
             }
         }
     }
@@ -235,36 +289,46 @@ pub fn load_json_file_with_config_and_policy(
     }
 
     // Read file contents
-    let content = fs::read_to_string(path).map_err(|source| RjdError::FileRead {
+    fs::read(path).map_err(|source| RjdError::FileRead {
         path: path.clone(),
         source,
-    })?;
-
-    // Parse JSON with depth checking
-    let value = parse_json_with_depth_limit(&content, config.max_json_depth).map_err(|msg| {
-        // Convert string error to serde_json::Error for consistency
-        RjdError::JsonParse {
-            path: path.clone(),
-            source: serde_json::Error::io(std::io::Error::other(msg)),
-        }
-    })?;
+    })
+}
 
-    Ok(value)
+/// Read the raw text of an input that is either a file path or inline text
+///
+/// Existing paths are read as files (unless `force_inline` is set); anything
+/// else is returned unchanged as inline text.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_input_text_with_config_policy_and_inline(
+    input: &str,
+    config: &LoadConfig,
+    policy: SymlinkPolicy,
+    force_inline: bool,
+) -> Result<String, RjdError> {
+    let path = PathBuf::from(input);
+    if !force_inline && path.exists() {
+        return read_file_with_config_and_policy(&path, config, policy);
+    }
+    Ok(input.to_string())
 }
 
 /// Load JSON from either a file path or an inline JSON string
 /// The function will try to parse the input as JSON first (only objects/arrays),
 /// and if that fails, it will try to load it as a file path.
+#[cfg(not(target_arch = "wasm32"))]
 pub fn load_json_input(input: &str) -> Result<Value, RjdError> {
     load_json_input_with_config(input, &LoadConfig::default())
 }
 
 /// Load JSON from either a file path or an inline JSON string with resource limits
+#[cfg(not(target_arch = "wasm32"))]
 pub fn load_json_input_with_config(input: &str, config: &LoadConfig) -> Result<Value, RjdError> {
     load_json_input_with_config_and_policy(input, config, SymlinkPolicy::Reject)
 }
 
 /// Load JSON from either a file path or an inline JSON string with resource limits and symlink policy
+#[cfg(not(target_arch = "wasm32"))]
 pub fn load_json_input_with_config_and_policy(
     input: &str,
     config: &LoadConfig,
@@ -274,53 +338,89 @@ pub fn load_json_input_with_config_and_policy(
 }
 
 /// Load JSON with resource limits, symlink policy, and inline flag
+#[cfg(not(target_arch = "wasm32"))]
 pub fn load_json_input_with_config_policy_and_inline(
     input: &str,
     config: &LoadConfig,
     policy: SymlinkPolicy,
     force_inline: bool,
 ) -> Result<Value, RjdError> {
+    load_json_input_and_text_with_config_policy_and_inline(input, config, policy, force_inline)
+        .map(|(value, _)| value)
+}
+
+/// Load JSON like [`load_json_input_with_config_policy_and_inline`], along with the text it was parsed from
+///
+/// A file is read only once, so the value and the text always agree.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_json_input_and_text_with_config_policy_and_inline(
+    input: &str,
+    config: &LoadConfig,
+    policy: SymlinkPolicy,
+    force_inline: bool,
+) -> Result<(Value, String), RjdError> {
     let trimmed = input.trim();
+    let invalid = |_| RjdError::InvalidInput {
+        input: input.to_string(),
+    };
 
     // If force_inline is true, parse as JSON only
     if force_inline {
-        return serde_json::from_str(input).map_err(|_| RjdError::InvalidInput {
+        let value = serde_json::from_str(input).map_err(|_| RjdError::InvalidInput {
             input: input.to_string(),
-        });
+        })?;
+        return Ok((value, input.to_string()));
     }
 
     // If input starts with '{' or '[', it's definitely inline JSON
     if trimmed.starts_with('{') || trimmed.starts_with('[') {
-        return parse_json_with_depth_limit(input, config.max_json_depth).map_err(|_msg| {
-            RjdError::InvalidInput {
-                input: input.to_string(),
-            }
-        });
+        let value = parse_json_with_depth_limit(input, config.max_json_depth).map_err(invalid)?;
+        return Ok((value, input.to_string()));
     }
 
     // Otherwise, try file path first, then inline JSON
     let path = PathBuf::from(input);
     if path.exists() {
-        return load_json_file_with_config_and_policy(&path, config, policy);
+        let content = read_file_with_config_and_policy(&path, config, policy)?;
+        let value = parse_json_file_content(&path, &content, config)?;
+        return Ok((value, content));
     }
 
     // Fall back to inline JSON
-    parse_json_with_depth_limit(input, config.max_json_depth).map_err(|_| RjdError::InvalidInput {
-        input: input.to_string(),
-    })
+    let value = parse_json_with_depth_limit(input, config.max_json_depth).map_err(invalid)?;
+    Ok((value, input.to_string()))
 }
 
 /// Load JSON from stdin
+#[cfg(not(target_arch = "wasm32"))]
 pub fn load_json_stdin() -> Result<Value, RjdError> {
     load_json_stdin_with_config(&LoadConfig::default())
 }
 
+/// Read all of stdin as text
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_stdin_text() -> Result<String, RjdError> {
+    std::io::read_to_string(std::io::stdin()).map_err(|source| RjdError::Internal {
+        message: format!("Failed to read from stdin: {}", source),
+    })
+}
+
+/// Read all of stdin as bytes
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_stdin_bytes() -> Result<Vec<u8>, RjdError> {
+    let mut bytes = Vec::new();
+    std::io::Read::read_to_end(&mut std::io::stdin(), &mut bytes).map_err(|source| {
+        RjdError::Internal {
+            message: format!("Failed to read from stdin: {}", source),
+        }
+    })?;
+    Ok(bytes)
+}
+
 /// Load JSON from stdin with resource limits
+#[cfg(not(target_arch = "wasm32"))]
 pub fn load_json_stdin_with_config(config: &LoadConfig) -> Result<Value, RjdError> {
-    let content =
-        std::io::read_to_string(std::io::stdin()).map_err(|source| RjdError::Internal {
-            message: format!("Failed to read from stdin: {}", source),
-        })?;
+    let content = read_stdin_text()?;
 
     // Parse JSON with depth checking
     parse_json_with_depth_limit(&content, config.max_json_depth).map_err(|msg| RjdError::Internal {
@@ -328,6 +428,210 @@ pub fn load_json_stdin_with_config(config: &LoadConfig) -> Result<Value, RjdErro
     })
 }
 
+/// Load two JSON documents from a single stdin stream
+///
+/// See [`parse_json_pair_with_config`] for how the stream is split.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_json_stdin_pair_with_config(
+    separator: Option<&str>,
+    config: &LoadConfig,
+) -> Result<(Value, Value), RjdError> {
+    let content = read_stdin_text()?;
+
+    parse_json_pair_with_config(&content, separator, config)
+}
+
+/// Parse two JSON documents from one text stream
+///
+/// With a separator, the text is split at the first line that consists of the
+/// separator (surrounding whitespace is ignored). Without one, the text must
+/// contain exactly two concatenated JSON values, e.g. `{"a":1} {"a":2}`.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::{parse_json_pair_with_config, LoadConfig};
+///
+/// let config = LoadConfig::default();
+/// let (old, new) =
+///     parse_json_pair_with_config("{\"a\": 1}\n---\n{\"a\": 2}", Some("---"), &config).unwrap();
+/// assert_eq!(old["a"], 1);
+/// assert_eq!(new["a"], 2);
+/// ```
+pub fn parse_json_pair_with_config(
+    content: &str,
+    separator: Option<&str>,
+    config: &LoadConfig,
+) -> Result<(Value, Value), RjdError> {
+    let (first, second) = split_json_pair(content, separator)?;
+    let parse = |text: &str, which: &str| {
+        parse_json_with_depth_limit(text, config.max_json_depth).map_err(|msg| RjdError::Internal {
+            message: format!(
+                "Failed to parse {} JSON document from stdin: {}",
+                which, msg
+            ),
+        })
+    };
+
+    Ok((parse(first, "first")?, parse(second, "second")?))
+}
+
+/// Split a text stream into the raw text of two JSON documents
+///
+/// Uses the same rules as [`parse_json_pair_with_config`], but returns the
+/// text of each document instead of parsing it.
+pub fn split_json_pair<'a>(
+    content: &'a str,
+    separator: Option<&str>,
+) -> Result<(&'a str, &'a str), RjdError> {
+    if let Some(separator) = separator {
+        let mut offset = 0;
+        for line in content.split_inclusive('\n') {
+            if line.trim() == separator {
+                return Ok((&content[..offset], &content[offset + line.len()..]));
+            }
+            offset += line.len();
+        }
+        return Err(RjdError::Internal {
+            message: format!("Separator '{}' not found in stdin", separator),
+        });
+    }
+
+    let mut stream =
+        serde_json::Deserializer::from_str(content).into_iter::<serde::de::IgnoredAny>();
+    let mut next_end = |which: &str| -> Result<usize, RjdError> {
+        stream
+            .next()
+            .ok_or_else(|| RjdError::Internal {
+                message: format!("Missing {} JSON document in stdin", which),
+            })?
+            .map_err(|e| RjdError::Internal {
+                message: format!("Failed to parse {} JSON document from stdin: {}", which, e),
+            })?;
+        Ok(stream.byte_offset())
+    };
+    let first_end = next_end("first")?;
+    let second_end = next_end("second")?;
+    if !content[second_end..].trim().is_empty() {
+        return Err(RjdError::Internal {
+            message: "Expected exactly two JSON documents in stdin".to_string(),
+        });
+    }
+
+    Ok((&content[..first_end], &content[first_end..second_end]))
+}
+
+/// Parse JSON text with resource limits
+pub fn parse_json_with_config(content: &str, config: &LoadConfig) -> Result<Value, RjdError> {
+    parse_json_with_depth_limit(content, config.max_json_depth)
+        .map_err(|message| RjdError::Internal { message })
+}
+
+/// Find object members whose key appears earlier in the same object
+///
+/// `serde_json` silently keeps the last value of a repeated key, so a
+/// document can say something different from what is compared. Returns the
+/// path of every repetition, in document order.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::find_duplicate_keys;
+///
+/// let duplicates = find_duplicate_keys(r#"{"a": 1, "b": [{"c": 1, "c": 2}], "a": 3}"#).unwrap();
+/// let paths: Vec<String> = duplicates.iter().map(|path| path.to_string()).collect();
+/// assert_eq!(paths, ["b[0].c", "a"]);
+/// ```
+///
+/// # Errors
+/// Returns an error if the text is not a single JSON value.
+pub fn find_duplicate_keys(content: &str) -> Result<Vec<JsonPath>, serde_json::Error> {
+    let mut duplicates = Vec::new();
+    let mut deserializer = serde_json::Deserializer::from_str(content);
+    DuplicateKeySeed {
+        path: JsonPath::new(),
+        duplicates: &mut duplicates,
+    }
+    .deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(duplicates)
+}
+
+/// Deserializer seed walking a value and recording repeated keys below `path`
+struct DuplicateKeySeed<'a> {
+    path: JsonPath,
+    duplicates: &'a mut Vec<JsonPath>,
+}
+
+impl<'de> DeserializeSeed<'de> for DuplicateKeySeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DuplicateKeySeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a JSON value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let mut index = 0;
+        while seq
+            .next_element_seed(DuplicateKeySeed {
+                path: join_array_path(&self.path, index),
+                duplicates: &mut *self.duplicates,
+            })?
+            .is_some()
+        {
+            index += 1;
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut seen = HashSet::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let path = join_path(&self.path, &key);
+            if !seen.insert(key) {
+                self.duplicates.push(path.clone());
+            }
+            map.next_value_seed(DuplicateKeySeed {
+                path,
+                duplicates: &mut *self.duplicates,
+            })?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -704,4 +1008,53 @@ mod tests {
         // The behavior may vary by system
         assert!(result.is_err() || link1.canonicalize().is_err());
     }
+
+    #[test]
+    fn test_parse_pair_with_separator() {
+        let config = LoadConfig::default();
+        let content = "{\"a\": 1}\n---\n{\"a\": 2}\n";
+        let (old, new) = parse_json_pair_with_config(content, Some("---"), &config).unwrap();
+        assert_eq!(old, serde_json::json!({"a": 1}));
+        assert_eq!(new, serde_json::json!({"a": 2}));
+
+        let result = parse_json_pair_with_config("{}\n{}", Some("---"), &config);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_pair_concatenated() {
+        let config = LoadConfig::default();
+        let (old, new) = parse_json_pair_with_config("{\"a\":1}{\"a\":2}", None, &config).unwrap();
+        assert_eq!(old["a"], 1);
+        assert_eq!(new["a"], 2);
+
+        assert!(parse_json_pair_with_config("{}", None, &config).is_err());
+        assert!(parse_json_pair_with_config("{} {} {}", None, &config).is_err());
+
+        let (first, second) = split_json_pair(" {\"a\": 1}\n[2] \n", None).unwrap();
+        assert_eq!(first, " {\"a\": 1}");
+        assert_eq!(second, "\n[2]");
+    }
+
+    #[test]
+    fn test_find_duplicate_keys() {
+        assert!(find_duplicate_keys(r#"{"a": {"b": 1}, "c": [{"b": 2}]}"#)
+            .unwrap()
+            .is_empty());
+
+        let duplicates =
+            find_duplicate_keys(r#"[{"k": 1, "k": 2, "k": 3}, {"x": {"y": 0, "y": 1}}]"#).unwrap();
+        let paths: Vec<String> = duplicates.iter().map(JsonPath::to_string).collect();
+        assert_eq!(paths, ["[0].k", "[0].k", "[1].x.y"]);
+
+        // Escaped and literal spellings of a key are the same key
+        assert_eq!(
+            find_duplicate_keys(r#"{"\u0061": 1, "a": 2}"#)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(find_duplicate_keys(r#"{"a": 1"#).is_err());
+        assert!(find_duplicate_keys("1 2").is_err());
+    }
 }
@@ -0,0 +1,423 @@
+//! Source locations of changed values
+//!
+//! A parsed [`Value`] no longer knows where its parts were written. This
+//! module scans the original JSON text again to find where the value at a
+//! path starts, so that a change can point at a line and column of an input
+//! file (`rjd --with-locations`). The scanner also tells the full-after
+//! format which byte ranges to rewrite.
+
+use crate::json_path::{JsonPath, PathSegment};
+use crate::types::{ChangeKind, Changes};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// Where a value starts in a JSON text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// Byte offset from the start of the text
+    pub offset: usize,
+    /// Line number, starting at 1
+    pub line: usize,
+    /// Column in characters, starting at 1
+    pub column: usize,
+}
+
+impl SourceLocation {
+    /// The location as reported in a change's annotations
+    fn to_json(self, file: &str) -> Value {
+        json!({
+            "file": file,
+            "line": self.line,
+            "column": self.column,
+            "offset": self.offset
+        })
+    }
+}
+
+/// A JSON text and the name its locations are reported under
+#[derive(Debug, Clone, Copy)]
+pub struct SourceText<'a> {
+    /// Usually the path of the file the text was read from
+    pub name: &'a str,
+    /// The JSON text
+    pub text: &'a str,
+}
+
+/// Find where the values at `paths` start in `text`
+///
+/// Paths that do not exist in the text are left out, and so is every path if
+/// the text is not valid JSON.
+///
+/// # Example
+/// ```
+/// use rjd::{locate_paths, JsonPath};
+///
+/// let text = "{\n  \"name\": \"app\",\n  \"tags\": [\"a\", \"b\"]\n}";
+/// let path: JsonPath = "tags[1]".parse().unwrap();
+/// let locations = locate_paths(text, [&path]);
+///
+/// assert_eq!(locations[&path].line, 3);
+/// assert_eq!(locations[&path].column, 17);
+/// ```
+pub fn locate_paths<'p>(
+    text: &str,
+    paths: impl IntoIterator<Item = &'p JsonPath>,
+) -> HashMap<JsonPath, SourceLocation> {
+    let requested: HashSet<&JsonPath> = paths.into_iter().collect();
+    let mut wanted = HashMap::new();
+    for path in &requested {
+        for n in 0..path.len() {
+            let prefix = JsonPath::from_segments(path.segments()[..n].to_vec());
+            wanted.insert(prefix, Want::Members);
+        }
+        wanted.entry((*path).clone()).or_insert(Want::Span);
+    }
+    let Some(nodes) = Scanner::scan(text, &wanted) else {
+        return HashMap::new();
+    };
+
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    nodes
+        .into_iter()
+        .filter(|(path, _)| requested.contains(path))
+        .map(|(path, node)| {
+            let offset = node.span.start;
+            let line = line_starts.partition_point(|&start| start <= offset);
+            let column = text[line_starts[line - 1]..offset].chars().count() + 1;
+            let location = SourceLocation {
+                offset,
+                line,
+                column,
+            };
+            (path, location)
+        })
+        .collect()
+}
+
+impl Changes {
+    /// Annotate each change with where its values are written in the inputs
+    ///
+    /// A removed value gets an `oldLocation` annotation, an added value a
+    /// `newLocation` one and a modified value both, each holding the `file`
+    /// (the [`SourceText::name`]), `line`, `column` and byte `offset` of the
+    /// value. Values that are not found in the text, e.g. because it is not
+    /// the document that was diffed, are not annotated.
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::{diff, SourceText};
+    ///
+    /// let old_text = "{\"port\": 80}";
+    /// let new_text = "{\n  \"port\": 8080\n}";
+    /// let mut changes = diff(
+    ///     &serde_json::from_str(old_text).unwrap(),
+    ///     &serde_json::from_str(new_text).unwrap(),
+    /// );
+    /// changes.annotate_locations(
+    ///     Some(SourceText { name: "old.json", text: old_text }),
+    ///     Some(SourceText { name: "new.json", text: new_text }),
+    /// );
+    ///
+    /// let location = &changes.annotations[changes.modified[0].path()]["newLocation"];
+    /// assert_eq!(location["file"], "new.json");
+    /// assert_eq!(location["line"], 2);
+    /// ```
+    pub fn annotate_locations(&mut self, old: Option<SourceText>, new: Option<SourceText>) {
+        let sides = [
+            (old, "oldLocation", ChangeKind::Added),
+            (new, "newLocation", ChangeKind::Removed),
+        ];
+        for (source, field, missing) in sides {
+            let Some(source) = source else {
+                continue;
+            };
+            let paths: Vec<JsonPath> = self
+                .iter()
+                .filter(|change| change.kind() != missing)
+                .map(|change| change.path().clone())
+                .collect();
+            let locations = locate_paths(source.text, &paths);
+            for path in paths {
+                if let Some(location) = locations.get(&path) {
+                    self.annotations
+                        .entry(path)
+                        .or_default()
+                        .insert(field.to_string(), location.to_json(source.name));
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Want {
+    /// Only the value's byte range
+    Span,
+    /// The byte range and the layout of the members (containers are entered)
+    Members,
+}
+
+/// Where a value sits in the original text
+pub(crate) struct Node {
+    pub(crate) span: Range<usize>,
+    pub(crate) members: Vec<Member>,
+}
+
+/// Where an object member sits in the original text
+pub(crate) struct Member {
+    pub(crate) key: String,
+    pub(crate) key_start: usize,
+    pub(crate) key_end: usize,
+    pub(crate) value_start: usize,
+    pub(crate) value_end: usize,
+}
+
+/// Locates values of a valid JSON text by path
+pub(crate) struct Scanner<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    wanted: &'a HashMap<JsonPath, Want>,
+    nodes: HashMap<JsonPath, Node>,
+}
+
+impl<'a> Scanner<'a> {
+    pub(crate) fn scan(
+        text: &'a str,
+        wanted: &'a HashMap<JsonPath, Want>,
+    ) -> Option<HashMap<JsonPath, Node>> {
+        let mut scanner = Scanner {
+            text,
+            bytes: text.as_bytes(),
+            pos: 0,
+            wanted,
+            nodes: HashMap::new(),
+        };
+        scanner.value(&mut Vec::new())?;
+        Some(scanner.nodes)
+    }
+
+    fn value(&mut self, segments: &mut Vec<PathSegment>) -> Option<()> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let path = JsonPath::from_segments(segments.clone());
+        let members = match self.wanted.get(&path) {
+            None => return self.skip_value(),
+            Some(Want::Span) => {
+                self.skip_value()?;
+                Vec::new()
+            }
+            Some(Want::Members) => match self.bytes.get(self.pos)? {
+                b'{' => self.object(segments)?,
+                b'[' => {
+                    self.array(segments)?;
+                    Vec::new()
+                }
+                _ => {
+                    self.skip_value()?;
+                    Vec::new()
+                }
+            },
+        };
+        self.nodes.insert(
+            path,
+            Node {
+                span: start..self.pos,
+                members,
+            },
+        );
+        Some(())
+    }
+
+    fn object(&mut self, segments: &mut Vec<PathSegment>) -> Option<Vec<Member>> {
+        self.pos += 1;
+        let mut members = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.bytes.get(self.pos)? {
+                b'}' => {
+                    self.pos += 1;
+                    return Some(members);
+                }
+                b',' => self.pos += 1,
+                b'"' => {
+                    let key_start = self.pos;
+                    self.skip_string()?;
+                    let key_end = self.pos;
+                    let key: String = serde_json::from_str(&self.text[key_start..key_end]).ok()?;
+                    self.skip_whitespace();
+                    if self.bytes.get(self.pos)? != &b':' {
+                        return None;
+                    }
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    let value_start = self.pos;
+                    segments.push(PathSegment::Key(key.clone()));
+                    self.value(segments)?;
+                    segments.pop();
+                    members.push(Member {
+                        key,
+                        key_start,
+                        key_end,
+                        value_start,
+                        value_end: self.pos,
+                    });
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn array(&mut self, segments: &mut Vec<PathSegment>) -> Option<()> {
+        self.pos += 1;
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            match self.bytes.get(self.pos)? {
+                b']' => {
+                    self.pos += 1;
+                    return Some(());
+                }
+                b',' => self.pos += 1,
+                _ => {
+                    segments.push(PathSegment::Index(index));
+                    self.value(segments)?;
+                    segments.pop();
+                    index += 1;
+                }
+            }
+        }
+    }
+
+    fn skip_value(&mut self) -> Option<()> {
+        match self.bytes.get(self.pos)? {
+            b'"' => self.skip_string(),
+            b'{' | b'[' => {
+                let mut depth = 0usize;
+                loop {
+                    match self.bytes.get(self.pos)? {
+                        b'"' => {
+                            self.skip_string()?;
+                            continue;
+                        }
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                self.pos += 1;
+                                return Some(());
+                            }
+                        }
+                        _ => {}
+                    }
+                    self.pos += 1;
+                }
+            }
+            _ => {
+                while let Some(b) = self.bytes.get(self.pos) {
+                    if matches!(b, b',' | b'}' | b']') || b.is_ascii_whitespace() {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                Some(())
+            }
+        }
+    }
+
+    fn skip_string(&mut self) -> Option<()> {
+        self.pos += 1;
+        loop {
+            match self.bytes.get(self.pos)? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Some(());
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+
+    fn path(text: &str) -> JsonPath {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_locate_paths() {
+        let text = "{\n  \"a\": {\"b\": [1, \n    2]},\n  \"é\": \"ü\", \"c\": null\n}\n";
+        let paths = [
+            path("a.b[1]"),
+            path("a"),
+            path("c"),
+            path(""),
+            path("missing"),
+        ];
+        let locations = locate_paths(text, &paths);
+
+        let position = |p: &str| {
+            let location = locations[&path(p)];
+            (location.line, location.column)
+        };
+        assert_eq!(position("a.b[1]"), (3, 5));
+        assert_eq!(position("a"), (2, 8));
+        assert_eq!(position(""), (1, 1));
+        // Columns count characters, offsets count bytes
+        assert_eq!(position("c"), (4, 18));
+        assert_eq!(locations[&path("c")].offset, text.find("null").unwrap());
+        assert!(!locations.contains_key(&path("missing")));
+
+        assert!(locate_paths("{", &paths).is_empty());
+    }
+
+    #[test]
+    fn test_annotate_locations() {
+        let old_text = "{\"gone\": 1, \"same\": 2, \"port\": 80}";
+        let new_text = "{\n  \"same\": 2,\n  \"port\": 8080,\n  \"new\": true\n}";
+        let mut changes = diff(
+            &serde_json::from_str(old_text).unwrap(),
+            &serde_json::from_str(new_text).unwrap(),
+        );
+        changes.annotate_locations(
+            Some(SourceText {
+                name: "old.json",
+                text: old_text,
+            }),
+            Some(SourceText {
+                name: "new.json",
+                text: new_text,
+            }),
+        );
+
+        let annotations = |p: &str| &changes.annotations[&path(p)];
+        assert_eq!(
+            annotations("gone")["oldLocation"],
+            json!({"file": "old.json", "line": 1, "column": 10, "offset": 9})
+        );
+        assert!(annotations("gone").get("newLocation").is_none());
+        assert_eq!(annotations("new")["newLocation"]["line"], 4);
+        assert!(annotations("new").get("oldLocation").is_none());
+        assert_eq!(annotations("port")["oldLocation"]["column"], 32);
+        assert_eq!(annotations("port")["newLocation"]["line"], 3);
+        assert!(!changes.annotations.contains_key(&path("same")));
+    }
+}
@@ -1,32 +1,52 @@
-use clap::Parser;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use std::path::Path;
 use std::process;
+use std::thread;
 
 mod cli;
+mod commands;
+mod config;
+mod help;
+mod input;
+mod output;
 
 // Import from library crate
-use rjd::create_formatter;
-use rjd::diff;
-use rjd::load_all_ignore_patterns;
-use rjd::RjdError;
+use rjd::color::{should_colorize, ColorWriter};
+use rjd::create_formatter_with_options;
+use rjd::formatting_differences;
+use rjd::rules::{Rules, Severity};
+use rjd::schema::{diff_with_schema_cancellation, Schema};
+use rjd::string_diff::annotate_string_diffs;
 use rjd::{
-    load_json_input_with_config_policy_and_inline, load_json_stdin_with_config, LoadConfig,
-    SymlinkPolicy,
+    apply_patch, create_formatter, diff_with_cancellation, diff_with_options, jcs_normalize,
+    load_json_file_with_config, CancellationToken, Changes, DiffOptions, JsonPath, PathSegment,
+    SourceText,
 };
+use rjd::{load_all_ignore_patterns, validate_pattern};
+use rjd::{FormatterOptions, RjdError, TruncationReason, Warning, WarningKind};
+use rjd::{LoadConfig, SymlinkPolicy};
+
+/// Printed instead of the formatted output by `--print-empty-message`
+const NO_DIFFERENCES_MESSAGE: &str = "No differences found.";
 
 fn main() {
     if let Err(err) = run() {
         eprintln!("Error: {}", err);
-        process::exit(1);
+        process::exit(err.exit_code());
     }
 }
 
 fn run() -> Result<(), RjdError> {
     // Parse command-line arguments
-    let args = cli::Args::parse();
+    let args = cli::Args::parse_command_line()?;
 
     // Validate arguments
     args.validate()?;
 
+    if let Some(command) = &args.command {
+        return commands::run(command);
+    }
     // Create LoadConfig from environment variables and merge with CLI flags
     let config = LoadConfig::from_env().merge_with_cli(args.max_file_size, args.max_depth);
 
@@ -42,54 +62,529 @@ fn run() -> Result<(), RjdError> {
     };
 
     // Load and parse JSON from either files or inline strings
-    let old_json = load_json_input_with_config_policy_and_inline(
-        &args.file1,
-        &config,
-        symlink_policy,
-        args.inline,
-    )
-    .map_err(|e| RjdError::Internal {
-        message: format!("Failed to load '{}': {}", args.file1, e),
-    })?;
-
-    let new_json = if args.stdin {
-        load_json_stdin_with_config(&config).map_err(|e| RjdError::Internal {
-            message: format!("Failed to load from stdin: {}", e),
-        })?
-    } else {
-        let file2 = args
-            .file2
-            .expect("file2 is required when --stdin is not used");
-        load_json_input_with_config_policy_and_inline(&file2, &config, symlink_policy, args.inline)
-            .map_err(|e| RjdError::Internal {
-                message: format!("Failed to load '{}': {}", file2, e),
-            })?
-    };
+    let (mut old_input, mut new_input) = input::load_inputs(&args, &config, symlink_policy)?;
+    if let Some(path) = &args.fill_defaults {
+        let schema = Schema::new(load_json_file_with_config(path, &config)?);
+        old_input.value = schema.fill_defaults(&old_input.value);
+        new_input.value = schema.fill_defaults(&new_input.value);
+    }
+    if args.canonical {
+        old_input.value = jcs_normalize(&old_input.value);
+        new_input.value = jcs_normalize(&new_input.value);
+    }
+    let (old_json, new_json) = (&old_input.value, &new_input.value);
 
     // Compute diff
-    let mut changes = diff(&old_json, &new_json);
+    let strategy_patterns = args.array_strategy.iter().map(|(pattern, _)| pattern);
+    for pattern in args.binary_paths.iter().chain(strategy_patterns) {
+        validate_pattern(pattern)?;
+    }
+    let diff_options = DiffOptions {
+        max_depth: args.max_diff_depth,
+        max_changes: args.max_changes,
+        ignore_key_case: args.ignore_key_case,
+        sort_keys: args.sort_keys,
+        normalize_dates: args.normalize_dates,
+        date_tolerance: args.date_tolerance.unwrap_or_default(),
+        coerce_numbers: args.coerce_numbers,
+        coerce_number_strings: args.coerce_number_strings,
+        null_as_absent: args.null_as_absent,
+        empty_as_absent: args.empty_as_absent,
+        structure_only: args.structure_only,
+        array_diff: args.array_diff(),
+        array_strategies: args.array_strategy.clone(),
+        array_sets: args.array_sets,
+        parse_embedded_json: args.parse_embedded_json,
+        binary_paths: args.binary_paths.clone(),
+        detect_binary: args.detect_binary,
+        decode_binary_json: args.decode_binary_json,
+        parallel: args.parallel,
+        comparator: None,
+    };
+    let schema = match &args.schema {
+        Some(path) => Some(Schema::new(load_json_file_with_config(path, &config)?)),
+        None => None,
+    };
+    let token = CancellationToken::new();
+    if let Some(limit) = args.timeout {
+        let timer = token.clone();
+        thread::spawn(move || {
+            thread::sleep(limit);
+            timer.cancel();
+        });
+    }
+    let mut changes = match &schema {
+        Some(schema) => {
+            diff_with_schema_cancellation(old_json, new_json, schema, &diff_options, &token)
+        }
+        None => diff_with_cancellation(old_json, new_json, &diff_options, &token),
+    };
+    if changes.is_cancelled() {
+        return Err(RjdError::Timeout {
+            limit: args.timeout.unwrap_or_default(),
+        });
+    }
+
+    // Keys repeated in the inputs (with --strict-parse=warn) are reported as warnings
+    for (input, which) in [(&old_input, "old"), (&new_input, "new")] {
+        for path in &input.duplicates {
+            changes.warnings.push(Warning {
+                path: path.clone(),
+                kind: WarningKind::DuplicateKey(which.to_string()),
+            });
+        }
+    }
+
+    // Only some reports read the first document, so it is copied for them
+    // alone; reversed, it becomes the second document every format reads
+    let uses_before = create_formatter(&args.format.to_string(), false)
+        .is_ok_and(|formatter| formatter.uses_before());
+    if uses_before || args.reverse || args.verify || args.save_changes.is_some() {
+        changes.before = Some(old_input.value.clone());
+    }
+
+    // The way back: the changes and the inputs swap sides
+    if args.reverse {
+        changes.invert();
+        std::mem::swap(&mut old_input, &mut new_input);
+    }
+
+    // A partial result is still reported, but say where and why it stopped
+    // (depth-limited subtrees were asked for and are only marked in the output)
+    let stopped_early = changes
+        .truncated
+        .iter()
+        .filter(|t| !matches!(t.reason, TruncationReason::MaxDepth(_)));
+    for truncation in stopped_early {
+        eprintln!(
+            "Warning: diff truncated at '{}': {}",
+            truncation.path, truncation.reason
+        );
+    }
+
+    if args.verify {
+        verify_patch(&changes, &old_input.value, &new_input.value)?;
+    }
+
+    // The gcc format reports positions, which it takes from the locations;
+    // they are looked up before --map-path rewrites the paths
+    if args.with_locations || args.format == cli::OutputFormat::Gcc {
+        let (old_arg, new_arg) = report_inputs(&args);
+        let (old_name, new_name) = (input_name(old_arg), input_name(new_arg));
+        changes.annotate_locations(
+            old_input.text.as_deref().map(|text| SourceText {
+                name: &old_name,
+                text,
+            }),
+            new_input.text.as_deref().map(|text| SourceText {
+                name: &new_name,
+                text,
+            }),
+        );
+    }
+    for substitution in &args.map_path {
+        changes.map_paths(substitution);
+    }
 
     // Load and apply ignore patterns if specified
-    if !args.ignore_json.is_empty() {
-        let patterns =
+    let mut ignore_patterns = Vec::new();
+    if !args.ignore_json.is_empty() || !args.ignore.is_empty() {
+        ignore_patterns =
             load_all_ignore_patterns(&args.ignore_json).map_err(|e| RjdError::Internal {
                 message: e.to_string(),
             })?;
-        changes = changes.filter_ignore_patterns(&patterns);
+        for pattern in &args.ignore {
+            validate_pattern(pattern)?;
+            ignore_patterns.push(pattern.clone());
+        }
+        changes = changes.filter_ignore_patterns(&ignore_patterns);
     }
 
+    if args.types_only {
+        changes = changes.type_changes();
+    }
+    if args.verbose_changes {
+        changes.annotate_value_metadata();
+    }
+    if args.describe || args.describe_template.is_some() {
+        changes.annotate_descriptions(args.describe_template.as_ref());
+    }
+    if let Some(granularity) = args.string_diff {
+        annotate_string_diffs(&mut changes, granularity);
+    }
+    if let Some(order) = args.order {
+        changes.sort_by_order(order.into());
+    }
+
+    // Assign severities; the --fail-on check runs once the output is written
+    let mut highest_severity = None;
+    if let Some(path) = &args.rules {
+        let text = std::fs::read_to_string(path).map_err(|source| RjdError::FileRead {
+            path: path.clone(),
+            source,
+        })?;
+        let rules = Rules::from_json(&text).map_err(|e| RjdError::Config {
+            path: path.clone(),
+            message: e.to_string(),
+        })?;
+        highest_severity = rules.apply(&mut changes);
+    }
+
+    // Mark changes outside the expected paths; the check runs once the output is written
+    let mut unexpected = Vec::new();
+    if !args.expect_json.is_empty() {
+        let expected =
+            load_all_ignore_patterns(&args.expect_json).map_err(|e| RjdError::Internal {
+                message: e.to_string(),
+            })?;
+        unexpected = changes.mark_unexpected(&expected);
+    }
+
+    if let Some(destination) = &args.save_changes {
+        let mut sink = destination.open(output::JSON)?;
+        writeln!(sink, "{}", changes.to_json(true)).map_err(|e| RjdError::Output {
+            destination: destination.to_string(),
+            message: e.to_string(),
+        })?;
+        sink.finish()?;
+    }
+
+    // Suspicious comparisons are reported on stderr (and in the changes format)
+    for warning in &changes.warnings {
+        eprintln!("Warning: at '{}': {}", warning.path, warning.kind);
+    }
+
+    // Sample the reported changes; the policy checks below still see every change
+    let seed = args.sample.map(|_| args.seed.unwrap_or_else(random_seed));
+    let sampled;
+    let report = match (args.sample, seed) {
+        (Some(count), Some(seed)) => {
+            sampled = changes.sample(count, seed);
+            eprintln!(
+                "Reporting {} of {} changes (--sample {} --seed {})",
+                sampled.len(),
+                changes.len(),
+                count,
+                seed
+            );
+            &sampled
+        }
+        _ => &changes,
+    };
+
     // Format and output results
     let format_str = args.format.to_string();
-    let formatter = create_formatter(&format_str, args.sort).map_err(|e| RjdError::Formatter {
-        message: e.to_string(),
-    })?;
-    let output = formatter
-        .format(&changes)
-        .map_err(|e| RjdError::Formatter {
-            message: e.to_string(),
+    let formatter_options = FormatterOptions {
+        sort: args.sort,
+        format_version: args.format_version,
+        top_k: args.top,
+        max_value_length: args.max_value_length,
+        context: args.context,
+        group_depth: args.group_by_path_depth.map(|depth| depth as usize),
+        original: old_input.text.clone(),
+        artifact: report_inputs(&args)
+            .1
+            .filter(|file| Path::new(file).is_file())
+            .cloned(),
+        metadata: args
+            .with_metadata
+            .then(|| envelope_fields(&args, &diff_options, &ignore_patterns, seed)),
+        template: match &args.template {
+            Some(path) => {
+                Some(
+                    std::fs::read_to_string(path).map_err(|source| RjdError::FileRead {
+                        path: path.clone(),
+                        source,
+                    })?,
+                )
+            }
+            None => None,
+        },
+    };
+    let formatter =
+        create_formatter_with_options(&format_str, &formatter_options).map_err(|e| {
+            RjdError::Formatter {
+                message: e.to_string(),
+            }
         })?;
 
-    println!("{}", output);
+    let empty_output = if args.print_empty_message {
+        Some(NO_DIFFERENCES_MESSAGE)
+    } else {
+        args.empty_output.as_deref()
+    };
+    let content_type = if report.is_empty() && empty_output.is_some() {
+        "text/plain; charset=utf-8"
+    } else {
+        formatter.content_type()
+    };
+
+    // Stream output to the destination; a closed pipe (e.g. `rjd ... | head`) is not an error
+    let mut sink = args.output.open(content_type)?;
+    // Binary output is never colored
+    let colorize = args.format != cli::OutputFormat::Msgpack
+        && should_colorize(args.color.into(), sink.is_terminal(), |name| {
+            std::env::var(name).ok()
+        });
+    let result = if let (true, Some(text)) = (report.is_empty(), empty_output) {
+        // Replace the format's empty structure; an empty text prints nothing
+        if text.is_empty() {
+            Ok(())
+        } else {
+            writeln!(sink, "{}", text).map_err(Into::into)
+        }
+    } else if colorize {
+        let mut out = ColorWriter::new(&mut sink);
+        formatter
+            .write_to(report, &mut out)
+            .and_then(|()| Ok(out.flush()?))
+    } else {
+        formatter.write_to(report, &mut sink)
+    };
+    if let Err(e) = result {
+        if !e.is_broken_pipe() {
+            return Err(RjdError::Formatter {
+                message: e.to_string(),
+            });
+        }
+    }
+    sink.finish()?;
+
+    // Structurally equal inputs may still differ in formatting, and numbers
+    // that only differ in spelling count as formatting for this verdict
+    let numerically_equal = || {
+        let options = DiffOptions {
+            coerce_numbers: true,
+            ..diff_options.clone()
+        };
+        diff_with_options(&old_input.value, &new_input.value, &options).is_empty()
+    };
+    if args.report_format_only && (changes.is_empty() || numerically_equal()) {
+        if let (Some(old_text), Some(new_text)) = (&old_input.text, &new_input.text) {
+            let report = formatting_differences(old_text, new_text)?;
+            if !report.is_empty() {
+                eprint!(
+                    "Inputs are structurally equal but formatted differently.\n{}",
+                    report
+                );
+            }
+        }
+    }
+
+    if !unexpected.is_empty() {
+        let paths: Vec<String> = unexpected
+            .iter()
+            .map(|path| format!("  {}", path.to_json_pointer()))
+            .collect();
+        return Err(RjdError::PolicyViolation {
+            message: format!(
+                "Unexpected changes outside --expect-json:\n{}",
+                paths.join("\n")
+            ),
+        });
+    }
+
+    // Enforce allowed/required path policies after the output is printed
+    if !args.require_paths.is_empty() || !args.must_change.is_empty() {
+        let load_patterns = |files: &[String]| {
+            if files.is_empty() {
+                return Ok(Vec::new());
+            }
+            load_all_ignore_patterns(files).map_err(|e| RjdError::Internal {
+                message: e.to_string(),
+            })
+        };
+        let allowed = load_patterns(&args.require_paths)?;
+        let required = load_patterns(&args.must_change)?;
+
+        let mut coverage = changes.path_coverage(&allowed, &required);
+        if args.require_paths.is_empty() {
+            // Without --require-paths every change is allowed
+            coverage.outside.clear();
+        }
+
+        if !coverage.is_satisfied() {
+            return Err(RjdError::PolicyViolation {
+                message: coverage.to_string().trim_end().to_string(),
+            });
+        }
+    }
+
+    if let (Some(threshold), Some(highest)) = (args.fail_on, highest_severity) {
+        if highest >= threshold {
+            let failing: Vec<String> = changes
+                .iter()
+                .filter_map(|change| {
+                    let severity = changes.annotations.get(change.path())?.get("severity")?;
+                    let severity: Severity = severity.as_str()?.parse().ok()?;
+                    (severity >= threshold)
+                        .then(|| format!("  {}: {}", severity, change.path().to_json_pointer()))
+                })
+                .collect();
+            return Err(RjdError::PolicyViolation {
+                message: format!(
+                    "Changes with severity {} or higher:\n{}",
+                    threshold,
+                    failing.join("\n")
+                ),
+            });
+        }
+    }
+
+    if let Some(max_drift) = args.max_drift {
+        let drift = changes.drift(&old_input.value);
+        if drift.percent() > max_drift {
+            return Err(RjdError::PolicyViolation {
+                message: format!(
+                    "Drift of {:.1}% ({} of {} values changed) exceeds --max-drift {}%",
+                    drift.percent(),
+                    drift.changed,
+                    drift.total,
+                    max_drift
+                ),
+            });
+        }
+    }
 
     Ok(())
 }
+
+/// The second input's argument, or `None` when it is read from stdin
+fn second_input(args: &cli::Args) -> Option<&String> {
+    if args.stdin {
+        None
+    } else {
+        args.file2.as_ref()
+    }
+}
+
+/// Check that the RFC 6902 patch of `changes` turns `old` into `new` (--verify)
+fn verify_patch(changes: &Changes, old: &Value, new: &Value) -> Result<(), RjdError> {
+    let failed = |message: String| RjdError::PatchVerification { message };
+    let formatter = create_formatter("rfc6902", false).map_err(|e| failed(e.to_string()))?;
+    let patch = formatter
+        .format(changes)
+        .map_err(|e| failed(e.to_string()))?;
+    let patch: Value = serde_json::from_str(&patch).map_err(|e| failed(e.to_string()))?;
+    let patched = apply_patch(old, &patch).map_err(|e| failed(e.to_string()))?;
+
+    if patched == *new {
+        return Ok(());
+    }
+    let path = first_difference(&patched, new, JsonPath::new());
+    Err(failed(format!(
+        "the patched first input differs from the second at '{}'",
+        path.to_json_pointer()
+    )))
+}
+
+/// The path of the first place where `a` and `b` differ, which must not be equal
+fn first_difference(a: &Value, b: &Value, path: JsonPath) -> JsonPath {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) if a.len() == b.len() => {
+            for (key, value) in a {
+                let Some(other) = b.get(key) else { break };
+                if value != other {
+                    let mut child = path.clone();
+                    child.push(PathSegment::Key(key.clone()));
+                    return first_difference(value, other, child);
+                }
+            }
+            path
+        }
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            match a.iter().zip(b).position(|(x, y)| x != y) {
+                Some(i) => {
+                    let mut child = path.clone();
+                    child.push(PathSegment::Index(i));
+                    first_difference(&a[i], &b[i], child)
+                }
+                None => path,
+            }
+        }
+        _ => path,
+    }
+}
+
+/// The arguments of the inputs reported as old and new (swapped by --reverse)
+fn report_inputs(args: &cli::Args) -> (Option<&String>, Option<&String>) {
+    let (old, new) = (args.file1.as_ref(), second_input(args));
+    if args.reverse {
+        (new, old)
+    } else {
+        (old, new)
+    }
+}
+
+/// How an input is named in reports: its file path, `<inline>` or `<stdin>`
+fn input_name(input: Option<&String>) -> String {
+    match input {
+        Some(file) if Path::new(file).is_file() => file.clone(),
+        Some(_) => "<inline>".to_string(),
+        None => "<stdin>".to_string(),
+    }
+}
+
+/// Inputs and options recorded in the `--with-metadata` envelope, with the
+/// `--sample` seed actually used
+fn envelope_fields(
+    args: &cli::Args,
+    options: &DiffOptions,
+    ignore_patterns: &[String],
+    seed: Option<u64>,
+) -> Map<String, Value> {
+    let describe_input = |input: Option<&String>| Value::String(input_name(input));
+    let (old_input, new_input) = report_inputs(args);
+
+    let mut fields = Map::new();
+    fields.insert(
+        "inputs".to_string(),
+        json!({
+            "old": describe_input(old_input),
+            "new": describe_input(new_input)
+        }),
+    );
+    fields.insert(
+        "options".to_string(),
+        json!({
+            "format": args.format.to_string(),
+            "mapPath": args.map_path.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "ignore": ignore_patterns,
+            "arrays": options.array_diff.to_string(),
+            "arrayStrategies": options
+                .array_strategies
+                .iter()
+                .map(|(pattern, strategy)| (pattern.clone(), Value::String(strategy.to_string())))
+                .collect::<Map<_, _>>(),
+            "maxDiffDepth": options.max_depth,
+            "maxChanges": options.max_changes,
+            "ignoreKeyCase": options.ignore_key_case,
+            "sortKeys": options.sort_keys,
+            "normalizeDates": options.normalize_dates,
+            "dateToleranceMs": options.date_tolerance.as_millis() as u64,
+            "coerceNumbers": options.coerce_numbers,
+            "coerceNumberStrings": options.coerce_number_strings,
+            "nullAsAbsent": options.null_as_absent,
+            "emptyAsAbsent": options.empty_as_absent,
+            "canonical": args.canonical,
+            "reverse": args.reverse,
+            "structureOnly": options.structure_only,
+            "arraySets": options.array_sets,
+            "parseEmbeddedJson": options.parse_embedded_json,
+            "binaryPaths": options.binary_paths,
+            "detectBinary": options.detect_binary,
+            "decodeBinaryJson": options.decode_binary_json,
+            "sample": args.sample,
+            "seed": seed
+        }),
+    );
+    fields
+}
+
+/// Seed for `--sample` when none is given
+fn random_seed() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    nanos ^ u64::from(process::id()).rotate_left(32)
+}
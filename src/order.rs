@@ -0,0 +1,138 @@
+//! Ordering of reported changes
+//!
+//! The engine reports the changes of each kind in document order: keys of the
+//! old object in order, then keys only found in the new one, and array
+//! elements by index (or in key order with
+//! [`DiffOptions::sort_keys`](crate::DiffOptions::sort_keys)). [`Changes::sort_by_order`] reorders them, and every
+//! formatter follows the new order.
+
+use crate::types::{subtree_size, Change, Changes};
+use serde_json::Value;
+use std::cmp::Reverse;
+
+/// How to order the changes of each kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeOrder {
+    /// By path; keys compare as strings and array indices as numbers
+    Path,
+    /// By the JSON type of the changed value (null, boolean, number, string,
+    /// array, object), then by path
+    Type,
+    /// Most disruptive first: type changes, then larger subtrees, then by path
+    Severity,
+}
+
+/// Position of a value's JSON type in [`ChangeOrder::Type`]
+fn type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    }
+}
+
+/// Type ranks of the values a change replaces and introduces
+fn type_key(change: &Change) -> (u8, u8) {
+    match change {
+        Change::Added { value, .. } | Change::Removed { value, .. } => {
+            (type_rank(value), type_rank(value))
+        }
+        Change::Modified {
+            old_value,
+            new_value,
+            ..
+        } => (type_rank(old_value), type_rank(new_value)),
+    }
+}
+
+/// Whether a change alters a value's type, and how many values it touches
+fn severity(change: &Change) -> (bool, usize) {
+    match change {
+        Change::Added { value, .. } | Change::Removed { value, .. } => (false, subtree_size(value)),
+        Change::Modified {
+            old_value,
+            new_value,
+            ..
+        } => (
+            change.type_change().is_some(),
+            subtree_size(old_value).max(subtree_size(new_value)),
+        ),
+    }
+}
+
+impl Changes {
+    /// Reorder the changes of each kind
+    ///
+    /// Changes stay grouped by kind (added, removed, modified); the order
+    /// applies within each group. Sorting is stable, so changes that compare
+    /// equal keep their document order.
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::{diff, ChangeOrder};
+    /// use serde_json::json;
+    ///
+    /// let mut changes = diff(&json!({"b": 1, "a": [1], "c": 1}), &json!({"b": 2, "a": [2], "c": "1"}));
+    /// changes.sort_by_order(ChangeOrder::Path);
+    /// let paths: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+    /// assert_eq!(paths, ["a[0]", "b", "c"]);
+    ///
+    /// changes.sort_by_order(ChangeOrder::Severity);
+    /// assert_eq!(changes.modified[0].path().to_string(), "c");
+    /// ```
+    pub fn sort_by_order(&mut self, order: ChangeOrder) {
+        for list in [&mut self.added, &mut self.removed, &mut self.modified] {
+            match order {
+                ChangeOrder::Path => list.sort_by(|a, b| a.path().cmp(b.path())),
+                ChangeOrder::Type => {
+                    list.sort_by(|a, b| (type_key(a), a.path()).cmp(&(type_key(b), b.path())))
+                }
+                ChangeOrder::Severity => list.sort_by(|a, b| {
+                    (Reverse(severity(a)), a.path()).cmp(&(Reverse(severity(b)), b.path()))
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    fn paths(changes: &Changes) -> Vec<String> {
+        changes.paths().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_engine_reports_document_order() {
+        let old = json!({"z": 1, "a": 1, "gone": 1, "m": [1, 2]});
+        let new = json!({"z": 2, "m": [1, 3], "a": 2, "new": 1});
+        let changes = diff(&old, &new);
+        assert_eq!(paths(&changes), ["new", "gone", "z", "a", "m[1]"]);
+        assert_eq!(paths(&diff(&old, &new)), paths(&changes));
+    }
+
+    #[test]
+    fn test_sort_by_order() {
+        let old =
+            json!({"items": [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], "s": "x", "n": 1, "o": {"k": 1}});
+        let new =
+            json!({"items": [0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1], "s": "y", "n": "1", "o": [1, 2]});
+        let mut changes = diff(&old, &new);
+
+        changes.sort_by_order(ChangeOrder::Path);
+        // Array indices compare as numbers, not as text
+        assert_eq!(paths(&changes), ["items[2]", "items[10]", "n", "o", "s"]);
+
+        changes.sort_by_order(ChangeOrder::Type);
+        assert_eq!(paths(&changes), ["items[2]", "items[10]", "n", "s", "o"]);
+
+        changes.sort_by_order(ChangeOrder::Severity);
+        assert_eq!(paths(&changes), ["o", "n", "items[2]", "items[10]", "s"]);
+    }
+}
@@ -0,0 +1,274 @@
+//! Output destinations for the diff report
+//!
+//! `--output` accepts `-` (stdout, the default), a file path or `file:` URL,
+//! an `http://`/`https://` URL (with the `http` feature) or an `s3://` URL
+//! (with the `s3` feature). Files are written to a temporary file beside the
+//! target and renamed over it once the report is complete; remote destinations
+//! buffer the report and upload it once it is complete.
+
+use crate::cli::OutputFormat;
+use rjd::RjdError;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Media type of JSON documents, for reports not written by a formatter
+pub const JSON: &str = "application/json";
+
+/// Where the report is written
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Destination {
+    #[default]
+    Stdout,
+    File(PathBuf),
+    Http(String),
+    S3(String),
+}
+
+impl FromStr for Destination {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        if spec == "-" {
+            return Ok(Destination::Stdout);
+        }
+        if let Some(path) = spec.strip_prefix("file://").or(spec.strip_prefix("file:")) {
+            return Ok(Destination::File(PathBuf::from(path)));
+        }
+        if spec.starts_with("http://") || spec.starts_with("https://") {
+            return Ok(Destination::Http(spec.to_string()));
+        }
+        if spec.starts_with("s3://") {
+            return Ok(Destination::S3(spec.to_string()));
+        }
+        if let Some((scheme, _)) = spec.split_once("://") {
+            return Err(format!(
+                "unsupported output scheme '{}://' (expected a path, file:, http(s):// or s3://)",
+                scheme
+            ));
+        }
+        Ok(Destination::File(PathBuf::from(spec)))
+    }
+}
+
+impl fmt::Display for Destination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Destination::Stdout => write!(f, "stdout"),
+            Destination::File(path) => write!(f, "{}", path.display()),
+            Destination::Http(url) | Destination::S3(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+impl Destination {
+    /// Open a sink writing to this destination
+    ///
+    /// `content_type` is the media type of the report, sent along when it is
+    /// uploaded (e.g. [`Formatter::content_type`](rjd::formatter::Formatter::content_type)).
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be created or the destination needs
+    /// a feature this binary was built without.
+    pub fn open(&self, content_type: &'static str) -> Result<Box<dyn Sink>, RjdError> {
+        match self {
+            Destination::Stdout => Ok(Box::new(StdoutSink(io::stdout().lock()))),
+            Destination::File(path) => {
+                let temp = temp_path(path);
+                let file = File::create(&temp).map_err(|e| self.error(e))?;
+                Ok(Box::new(FileSink {
+                    writer: BufWriter::new(file),
+                    temp,
+                    target: path.clone(),
+                    destination: self.clone(),
+                }))
+            }
+            Destination::Http(_) if !cfg!(feature = "http") => {
+                Err(self.error("rjd was built without the `http` feature"))
+            }
+            Destination::S3(_) if !cfg!(feature = "s3") => {
+                Err(self.error("rjd was built without the `s3` feature"))
+            }
+            Destination::Http(_) | Destination::S3(_) => Ok(Box::new(UploadSink {
+                buffer: Vec::new(),
+                destination: self.clone(),
+                content_type,
+            })),
+        }
+    }
+
+    /// The output format implied by a file destination's extension
+    pub fn inferred_format(&self) -> Option<OutputFormat> {
+        let Destination::File(path) = self else {
+            return None;
+        };
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "sarif" => Some(OutputFormat::Sarif),
+            "xml" => Some(OutputFormat::Junit),
+            "jsonl" => Some(OutputFormat::Jsonl),
+            "msgpack" => Some(OutputFormat::Msgpack),
+            _ => None,
+        }
+    }
+
+    fn error(&self, message: impl fmt::Display) -> RjdError {
+        RjdError::Output {
+            destination: self.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// A writer for the report that must be finished once the report is complete
+pub trait Sink: Write {
+    /// Whether the sink is an interactive terminal (for `--color auto`)
+    fn is_terminal(&self) -> bool {
+        false
+    }
+
+    /// Flush the report to its destination
+    fn finish(self: Box<Self>) -> Result<(), RjdError>;
+}
+
+struct StdoutSink(io::StdoutLock<'static>);
+
+impl Write for StdoutSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Sink for StdoutSink {
+    fn is_terminal(&self) -> bool {
+        self.0.is_terminal()
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), RjdError> {
+        // A closed pipe (e.g. `rjd ... | head`) is not an error
+        match self.0.flush() {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(RjdError::Output {
+                destination: Destination::Stdout.to_string(),
+                message: e.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Name of the temporary file written before it is renamed to `target`
+pub fn temp_path(target: &Path) -> PathBuf {
+    let file_name = target.file_name().unwrap_or_default().to_string_lossy();
+    target.with_file_name(format!(".{}.rjd-{}.tmp", file_name, std::process::id()))
+}
+
+/// Writes to a temporary file that replaces the target when finished
+struct FileSink {
+    writer: BufWriter<File>,
+    temp: PathBuf,
+    target: PathBuf,
+    destination: Destination,
+}
+
+impl Write for FileSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl Sink for FileSink {
+    fn finish(mut self: Box<Self>) -> Result<(), RjdError> {
+        let result = self
+            .writer
+            .flush()
+            .and_then(|()| self.writer.get_ref().sync_all())
+            .and_then(|()| {
+                // Keep the permissions of a file being replaced
+                if let Ok(metadata) = fs::metadata(&self.target) {
+                    fs::set_permissions(&self.temp, metadata.permissions())?;
+                }
+                fs::rename(&self.temp, &self.target)
+            });
+        result.map_err(|e| self.destination.error(e))
+    }
+}
+
+impl Drop for FileSink {
+    fn drop(&mut self) {
+        // Left over only if the report was not finished
+        let _ = fs::remove_file(&self.temp);
+    }
+}
+
+/// Buffers the report and uploads it to a remote destination when finished
+struct UploadSink {
+    buffer: Vec<u8>,
+    destination: Destination,
+    /// Read only by the uploads this build supports
+    #[cfg_attr(not(any(feature = "http", feature = "s3")), allow(dead_code))]
+    content_type: &'static str,
+}
+
+impl Write for UploadSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Sink for UploadSink {
+    fn finish(self: Box<Self>) -> Result<(), RjdError> {
+        let result = match &self.destination {
+            #[cfg(feature = "http")]
+            Destination::Http(url) => post(url, &self.buffer, self.content_type),
+            #[cfg(feature = "s3")]
+            Destination::S3(url) => upload_s3(url, &self.buffer, self.content_type),
+            _ => Err("destination does not support uploads".to_string()),
+        };
+        result.map_err(|message| self.destination.error(message))
+    }
+}
+
+/// POST the report with its media type
+#[cfg(feature = "http")]
+fn post(url: &str, body: &[u8], content_type: &str) -> Result<(), String> {
+    ureq::post(url)
+        .set("Content-Type", content_type)
+        .send_bytes(body)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Upload the report with `aws s3 cp`, which picks up the usual AWS credentials
+#[cfg(feature = "s3")]
+fn upload_s3(url: &str, body: &[u8], content_type: &str) -> Result<(), String> {
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new("aws")
+        .args(["s3", "cp", "-", url, "--content-type", content_type])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run `aws s3 cp`: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(body).map_err(|e| e.to_string())?;
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("`aws s3 cp` exited with {}", status))
+    }
+}
@@ -0,0 +1,748 @@
+//! Applying and combining RFC 6902 JSON Patch documents
+//!
+//! Supports the `add`, `remove`, `replace` and `test` operations, which is
+//! enough to check that rjd's `rfc6902` output turns the old document into
+//! the new one. Patches can also be inverted ([`invert_patch`]), composed
+//! ([`compose_patches`]), simplified ([`simplify_patch`]) and minimized for a
+//! document ([`minimize_patch`]), so pipelines of patches can be maintained
+//! with the same tool.
+
+use crate::formatter::changes_to_patch;
+use serde_json::{json, Value};
+
+/// Error applying a JSON Patch
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PatchError {
+    #[error("Invalid patch operation #{index}: {message}")]
+    InvalidOperation { index: usize, message: String },
+
+    #[error("Patch operation #{index} ({op}) failed: no target at '{path}'")]
+    PathNotFound {
+        index: usize,
+        op: String,
+        path: String,
+    },
+
+    #[error("Patch operation #{index} (test) failed at '{path}'")]
+    TestFailed { index: usize, path: String },
+}
+
+/// A supported patch operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Add,
+    Remove,
+    Replace,
+    Test,
+}
+
+impl Op {
+    fn as_str(self) -> &'static str {
+        match self {
+            Op::Add => "add",
+            Op::Remove => "remove",
+            Op::Replace => "replace",
+            Op::Test => "test",
+        }
+    }
+}
+
+/// A parsed patch operation
+#[derive(Debug, Clone, PartialEq)]
+struct Operation {
+    op: Op,
+    /// JSON Pointer of the target
+    path: String,
+    /// The value of `add`, `replace` and `test` operations
+    value: Option<Value>,
+}
+
+impl Operation {
+    fn new(op: Op, path: &str, value: Option<Value>) -> Self {
+        Self {
+            op,
+            path: path.to_string(),
+            value,
+        }
+    }
+
+    /// Parse the operation at position `index` of a patch
+    fn parse(operation: &Value, index: usize) -> Result<Self, PatchError> {
+        let invalid = |message: &str| PatchError::InvalidOperation {
+            index,
+            message: message.to_string(),
+        };
+        let op = operation
+            .get("op")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid("missing 'op'"))?;
+        let path = operation
+            .get("path")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid("missing 'path'"))?;
+        let op = match op {
+            "add" => Op::Add,
+            "remove" => Op::Remove,
+            "replace" => Op::Replace,
+            "test" => Op::Test,
+            other => return Err(invalid(&format!("unsupported op '{}'", other))),
+        };
+        let value = match op {
+            Op::Remove => None,
+            _ => Some(
+                operation
+                    .get("value")
+                    .cloned()
+                    .ok_or_else(|| invalid("missing 'value'"))?,
+            ),
+        };
+        Ok(Self::new(op, path, value))
+    }
+
+    fn to_value(&self) -> Value {
+        let mut operation = json!({"op": self.op.as_str(), "path": self.path});
+        if let Some(value) = &self.value {
+            operation["value"] = value.clone();
+        }
+        operation
+    }
+}
+
+/// The operations of a patch, which must be an array
+fn patch_operations(patch: &Value) -> Result<&Vec<Value>, PatchError> {
+    patch
+        .as_array()
+        .ok_or_else(|| PatchError::InvalidOperation {
+            index: 0,
+            message: "a patch must be an array of operations".to_string(),
+        })
+}
+
+fn to_patch(operations: &[Operation]) -> Value {
+    Value::Array(operations.iter().map(Operation::to_value).collect())
+}
+
+/// Apply a JSON Patch (an array of operations) to a document
+///
+/// The document is left unchanged if any operation fails.
+///
+/// # Errors
+/// Returns an error for malformed operations, unsupported operations, missing
+/// targets, or failed `test` operations.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::{apply_patch, create_formatter, diff};
+/// use serde_json::json;
+///
+/// let old = json!({"name": "John", "age": 30});
+/// let new = json!({"name": "Jane", "age": 30, "email": "j@example.com"});
+///
+/// let patch = create_formatter("rfc6902", false).unwrap().format(&diff(&old, &new)).unwrap();
+/// let patch: serde_json::Value = serde_json::from_str(&patch).unwrap();
+/// assert_eq!(apply_patch(&old, &patch).unwrap(), new);
+/// ```
+pub fn apply_patch(document: &Value, patch: &Value) -> Result<Value, PatchError> {
+    let mut result = document.clone();
+    for (index, operation) in patch_operations(patch)?.iter().enumerate() {
+        apply_operation(&mut result, &Operation::parse(operation, index)?, index)?;
+    }
+    Ok(result)
+}
+
+fn apply_operation(
+    document: &mut Value,
+    operation: &Operation,
+    index: usize,
+) -> Result<(), PatchError> {
+    let path = operation.path.as_str();
+    let not_found = || PatchError::PathNotFound {
+        index,
+        op: operation.op.as_str().to_string(),
+        path: path.to_string(),
+    };
+    let value = || operation.value.clone().unwrap_or_default();
+
+    match operation.op {
+        Op::Add => add(document, path, value()).ok_or_else(not_found),
+        Op::Remove => remove(document, path).map(|_| ()).ok_or_else(not_found),
+        Op::Replace => {
+            let target = document.pointer_mut(path).ok_or_else(not_found)?;
+            *target = value();
+            Ok(())
+        }
+        Op::Test => match document.pointer(path) {
+            Some(actual) if Some(actual) == operation.value.as_ref() => Ok(()),
+            _ => Err(PatchError::TestFailed {
+                index,
+                path: path.to_string(),
+            }),
+        },
+    }
+}
+
+/// The patch that undoes `patch` on `document`
+///
+/// Applying `patch` to `document` and then the result of this function
+/// gives `document` back. The document is needed because a patch does not
+/// record the values it removes or replaces. `test` operations are kept, in
+/// reverse order, since they hold at the same points when undoing.
+///
+/// # Errors
+/// Returns an error if `patch` does not apply to `document`.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::{apply_patch, invert_patch};
+/// use serde_json::json;
+///
+/// let document = json!({"name": "John", "tags": ["a"]});
+/// let patch = json!([
+///     {"op": "replace", "path": "/name", "value": "Jane"},
+///     {"op": "add", "path": "/tags/-", "value": "b"}
+/// ]);
+/// let inverse = invert_patch(&document, &patch).unwrap();
+/// assert_eq!(
+///     inverse,
+///     json!([
+///         {"op": "remove", "path": "/tags/1"},
+///         {"op": "replace", "path": "/name", "value": "John"}
+///     ])
+/// );
+/// let patched = apply_patch(&document, &patch).unwrap();
+/// assert_eq!(apply_patch(&patched, &inverse).unwrap(), document);
+/// ```
+pub fn invert_patch(document: &Value, patch: &Value) -> Result<Value, PatchError> {
+    let mut current = document.clone();
+    let mut inverse = Vec::new();
+    for (index, operation) in patch_operations(patch)?.iter().enumerate() {
+        let operation = Operation::parse(operation, index)?;
+        let undo = undo_operation(&current, &operation);
+        apply_operation(&mut current, &operation, index)?;
+        // Every operation that applies can be undone
+        inverse.extend(undo);
+    }
+    inverse.reverse();
+    Ok(to_patch(&inverse))
+}
+
+/// The operation undoing `operation` on `document`, if it applies there
+fn undo_operation(document: &Value, operation: &Operation) -> Option<Operation> {
+    let path = operation.path.as_str();
+    let old = || document.pointer(path).cloned();
+    match operation.op {
+        Op::Add if path.is_empty() => Some(Operation::new(Op::Replace, path, old())),
+        Op::Add => {
+            let (parent, token) = split_pointer(path)?;
+            match document.pointer(parent)? {
+                // Adding an existing member replaces it
+                Value::Object(map) => Some(match map.get(&token) {
+                    Some(old) => Operation::new(Op::Replace, path, Some(old.clone())),
+                    None => Operation::new(Op::Remove, path, None),
+                }),
+                Value::Array(items) => {
+                    let index = if token == "-" {
+                        items.len()
+                    } else {
+                        array_index(&token)?
+                    };
+                    let path = format!("{}/{}", parent, index);
+                    Some(Operation::new(Op::Remove, &path, None))
+                }
+                _ => None,
+            }
+        }
+        Op::Remove => Some(Operation::new(Op::Add, path, Some(old()?))),
+        Op::Replace => Some(Operation::new(Op::Replace, path, Some(old()?))),
+        Op::Test => Some(operation.clone()),
+    }
+}
+
+/// One patch with the effect of applying `patches` in order
+///
+/// The operations are concatenated and then simplified as by
+/// [`simplify_patch`]. Composing does not need a document, so operations
+/// whose interaction depends on it (such as two insertions into the same
+/// array) are kept as they are.
+///
+/// # Errors
+/// Returns an error if a patch is not an array of supported operations.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::compose_patches;
+/// use serde_json::json;
+///
+/// let first = json!([{"op": "add", "path": "/db", "value": {"host": "a"}}]);
+/// let second = json!([
+///     {"op": "replace", "path": "/db/host", "value": "b"},
+///     {"op": "add", "path": "/db/port", "value": 5432}
+/// ]);
+/// assert_eq!(
+///     compose_patches(&[first, second]).unwrap(),
+///     json!([{"op": "add", "path": "/db", "value": {"host": "b", "port": 5432}}])
+/// );
+/// ```
+pub fn compose_patches(patches: &[Value]) -> Result<Value, PatchError> {
+    let mut composed = Vec::new();
+    for patch in patches {
+        for (index, operation) in patch_operations(patch)?.iter().enumerate() {
+            let operation = Operation::parse(operation, index)?;
+            let kept = merge_into(&mut composed, operation);
+            composed.extend(kept);
+        }
+    }
+    Ok(to_patch(&composed))
+}
+
+/// An equivalent patch with redundant operations merged or dropped
+///
+/// Without a document to look at, only rewrites that hold for every
+/// document the patch applies to are made:
+///
+/// - operations on the same path are merged (`replace` then `replace`
+///   becomes one `replace`, `remove` then `add` a `replace`, and so on);
+/// - an operation inside a value that an earlier `add` or `replace` wrote is
+///   folded into that value;
+/// - operations inside a value that a later `replace` or `remove`
+///   overwrites are dropped.
+///
+/// An operation is only moved past operations on unrelated paths, and paths
+/// that differ at an array index (or a token that may be one) are treated
+/// as related, since insertions and removals shift the elements after them.
+/// `test` operations are never merged or moved.
+///
+/// # Errors
+/// Returns an error if `patch` is not an array of supported operations.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::simplify_patch;
+/// use serde_json::json;
+///
+/// let patch = json!([
+///     {"op": "replace", "path": "/name", "value": "Jane"},
+///     {"op": "replace", "path": "/age", "value": 31},
+///     {"op": "replace", "path": "/name", "value": "Joan"}
+/// ]);
+/// assert_eq!(
+///     simplify_patch(&patch).unwrap(),
+///     json!([
+///         {"op": "replace", "path": "/name", "value": "Joan"},
+///         {"op": "replace", "path": "/age", "value": 31}
+///     ])
+/// );
+/// ```
+pub fn simplify_patch(patch: &Value) -> Result<Value, PatchError> {
+    compose_patches(std::slice::from_ref(patch))
+}
+
+/// How the target of one operation relates to that of another
+enum Relation {
+    /// The same location
+    Same,
+    /// The first target contains the second, at this pointer within it
+    Contains(String),
+    /// The second target contains the first
+    Within,
+    /// Separate locations, neither of which can shift the other
+    Unrelated,
+    /// Separate locations in the same array (or possibly so)
+    Siblings,
+}
+
+/// Whether a pointer token may be an array index
+fn may_be_index(token: &str) -> bool {
+    token == "-" || (!token.is_empty() && token.bytes().all(|b| b.is_ascii_digit()))
+}
+
+fn relation(first: &str, second: &str) -> Relation {
+    let first: Vec<&str> = first.split('/').skip(1).collect();
+    let second: Vec<&str> = second.split('/').skip(1).collect();
+    let common = first
+        .iter()
+        .zip(&second)
+        .take_while(|(a, b)| a == b)
+        .count();
+    match (common == first.len(), common == second.len()) {
+        (true, true) => Relation::Same,
+        (true, false) => Relation::Contains(
+            second[common..]
+                .iter()
+                .map(|token| format!("/{}", token))
+                .collect(),
+        ),
+        (false, true) => Relation::Within,
+        _ if may_be_index(first[common]) || may_be_index(second[common]) => Relation::Siblings,
+        _ => Relation::Unrelated,
+    }
+}
+
+/// Fold `next` into the operations before it where that keeps their effect
+///
+/// Returns `next` if it has to stay a separate operation at the end.
+fn merge_into(operations: &mut Vec<Operation>, next: Operation) -> Option<Operation> {
+    let mut position = operations.len();
+    while position > 0 {
+        position -= 1;
+        let earlier = &mut operations[position];
+        if earlier.op == Op::Test || next.op == Op::Test {
+            match relation(&earlier.path, &next.path) {
+                Relation::Unrelated => continue,
+                _ => return Some(next),
+            }
+        }
+        match relation(&earlier.path, &next.path) {
+            Relation::Unrelated => continue,
+            Relation::Same => match merge_same(earlier, &next) {
+                Some(merged) => {
+                    *earlier = merged;
+                    return None;
+                }
+                None => return Some(next),
+            },
+            Relation::Contains(inner) if earlier.op != Op::Remove => {
+                // Apply `next` to the value `earlier` writes
+                let mut value = earlier.value.clone().unwrap_or_default();
+                let inner = Operation::new(next.op, &inner, next.value.clone());
+                if apply_operation(&mut value, &inner, 0).is_err() {
+                    return Some(next);
+                }
+                earlier.value = Some(value);
+                return None;
+            }
+            Relation::Within if matches!(next.op, Op::Replace | Op::Remove) => {
+                // `next` overwrites whatever `earlier` did
+                operations.remove(position);
+            }
+            _ => return Some(next),
+        }
+    }
+    Some(next)
+}
+
+/// One operation with the effect of `earlier` then `next` on the same path
+///
+/// Returns `None` when they have to stay separate.
+fn merge_same(earlier: &Operation, next: &Operation) -> Option<Operation> {
+    let path = earlier.path.as_str();
+    // `add` sets an object member but inserts into an array
+    let member = !split_pointer(path).is_some_and(|(_, token)| may_be_index(&token));
+    let value = || next.value.clone();
+    match (earlier.op, next.op) {
+        (Op::Add | Op::Replace, Op::Replace) => Some(Operation::new(earlier.op, path, value())),
+        (Op::Add | Op::Replace, Op::Add) if member => {
+            Some(Operation::new(earlier.op, path, value()))
+        }
+        (Op::Replace, Op::Remove) => Some(Operation::new(Op::Remove, path, None)),
+        (Op::Remove, Op::Add) => Some(Operation::new(Op::Replace, path, value())),
+        _ => None,
+    }
+}
+
+/// The shortest patch found with the same effect as `patch` on `document`
+///
+/// Compares [`simplify_patch`] of `patch` with the patch derived from
+/// diffing `document` against the patched result, and returns whichever has
+/// fewer operations. The latter has no `test` operations.
+///
+/// # Errors
+/// Returns an error if `patch` does not apply to `document`.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::minimize_patch;
+/// use serde_json::json;
+///
+/// let document = json!({"a": 1, "b": 2});
+/// let patch = json!([
+///     {"op": "remove", "path": "/a"},
+///     {"op": "add", "path": "/c", "value": 3},
+///     {"op": "remove", "path": "/c"},
+///     {"op": "add", "path": "/a", "value": 1}
+/// ]);
+/// assert_eq!(minimize_patch(&document, &patch).unwrap(), json!([]));
+/// ```
+pub fn minimize_patch(document: &Value, patch: &Value) -> Result<Value, PatchError> {
+    let target = apply_patch(document, patch)?;
+    let simplified = simplify_patch(patch)?;
+
+    let mut changes = crate::diff(document, &target);
+    changes.before = Some(document.clone());
+    let diffed = changes_to_patch(&changes);
+    let diffed_len = diffed.as_array().map_or(0, Vec::len);
+
+    match simplified.as_array() {
+        Some(simplified_ops) if simplified_ops.len() < diffed_len => Ok(simplified),
+        _ => Ok(diffed),
+    }
+}
+
+/// Split a JSON Pointer into its parent pointer and unescaped last token
+fn split_pointer(path: &str) -> Option<(&str, String)> {
+    let (parent, last) = path.rsplit_once('/')?;
+    Some((parent, last.replace("~1", "/").replace("~0", "~")))
+}
+
+/// Parse an array index token, rejecting leading zeros as RFC 6901 requires
+fn array_index(token: &str) -> Option<usize> {
+    if token.len() > 1 && token.starts_with('0') {
+        return None;
+    }
+    token.parse().ok()
+}
+
+fn add(document: &mut Value, path: &str, value: Value) -> Option<()> {
+    if path.is_empty() {
+        *document = value;
+        return Some(());
+    }
+    let (parent, token) = split_pointer(path)?;
+    match document.pointer_mut(parent)? {
+        Value::Object(map) => {
+            map.insert(token, value);
+        }
+        Value::Array(items) => {
+            let index = if token == "-" {
+                items.len()
+            } else {
+                array_index(&token)?
+            };
+            if index > items.len() {
+                return None;
+            }
+            items.insert(index, value);
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+fn remove(document: &mut Value, path: &str) -> Option<Value> {
+    let (parent, token) = split_pointer(path)?;
+    match document.pointer_mut(parent)? {
+        Value::Object(map) => map.shift_remove(&token),
+        Value::Array(items) => {
+            let index = array_index(&token)?;
+            (index < items.len()).then(|| items.remove(index))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_operations() {
+        let document = json!({"a": {"b": 1}, "list": [1, 2, 3], "x/y": 0});
+        let patch = json!([
+            {"op": "add", "path": "/a/c", "value": 2},
+            {"op": "add", "path": "/list/1", "value": 9},
+            {"op": "add", "path": "/list/-", "value": 4},
+            {"op": "remove", "path": "/list/0"},
+            {"op": "replace", "path": "/x~1y", "value": 1},
+            {"op": "test", "path": "/a/b", "value": 1}
+        ]);
+        assert_eq!(
+            apply_patch(&document, &patch).unwrap(),
+            json!({"a": {"b": 1, "c": 2}, "list": [9, 2, 3, 4], "x/y": 1})
+        );
+    }
+
+    #[test]
+    fn test_apply_errors() {
+        let document = json!({"list": [1]});
+        assert!(matches!(
+            apply_patch(&document, &json!([{"op": "remove", "path": "/list/5"}])),
+            Err(PatchError::PathNotFound { index: 0, .. })
+        ));
+        assert!(matches!(
+            apply_patch(
+                &document,
+                &json!([{"op": "add", "path": "/missing/a", "value": 1}])
+            ),
+            Err(PatchError::PathNotFound { .. })
+        ));
+        assert!(matches!(
+            apply_patch(
+                &document,
+                &json!([{"op": "test", "path": "/list/0", "value": 2}])
+            ),
+            Err(PatchError::TestFailed { .. })
+        ));
+        assert!(matches!(
+            apply_patch(
+                &document,
+                &json!([{"op": "move", "path": "/a", "from": "/b"}])
+            ),
+            Err(PatchError::InvalidOperation { .. })
+        ));
+        assert!(apply_patch(&document, &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_invert_patch() {
+        let document = json!({"a": {"b": 1}, "list": [1, 2], "gone": true});
+        let patch = json!([
+            {"op": "add", "path": "/a/b", "value": 2},
+            {"op": "add", "path": "/a/c", "value": 3},
+            {"op": "add", "path": "/list/0", "value": 0},
+            {"op": "add", "path": "/list/-", "value": 9},
+            {"op": "remove", "path": "/gone"},
+            {"op": "test", "path": "/list/1", "value": 1},
+            {"op": "replace", "path": "/list/1", "value": 10}
+        ]);
+        let patched = apply_patch(&document, &patch).unwrap();
+        let inverse = invert_patch(&document, &patch).unwrap();
+        assert_eq!(
+            inverse,
+            json!([
+                {"op": "replace", "path": "/list/1", "value": 1},
+                {"op": "test", "path": "/list/1", "value": 1},
+                {"op": "add", "path": "/gone", "value": true},
+                {"op": "remove", "path": "/list/3"},
+                {"op": "remove", "path": "/list/0"},
+                {"op": "remove", "path": "/a/c"},
+                {"op": "replace", "path": "/a/b", "value": 1}
+            ])
+        );
+        assert_eq!(apply_patch(&patched, &inverse).unwrap(), document);
+
+        // Replacing the whole document
+        let patch = json!([{"op": "add", "path": "", "value": [1]}]);
+        let inverse = invert_patch(&document, &patch).unwrap();
+        assert_eq!(apply_patch(&json!([1]), &inverse).unwrap(), document);
+
+        assert!(matches!(
+            invert_patch(&document, &json!([{"op": "remove", "path": "/missing"}])),
+            Err(PatchError::PathNotFound { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_simplify_patch() {
+        let simplify = |patch: Value| simplify_patch(&patch).unwrap();
+
+        // Same path: remove then add is a replace, replace then remove a remove
+        assert_eq!(
+            simplify(json!([
+                {"op": "remove", "path": "/a"},
+                {"op": "add", "path": "/a", "value": 1},
+                {"op": "replace", "path": "/b", "value": 1},
+                {"op": "remove", "path": "/b"}
+            ])),
+            json!([
+                {"op": "replace", "path": "/a", "value": 1},
+                {"op": "remove", "path": "/b"}
+            ])
+        );
+
+        // Changes inside a removed value are dropped
+        assert_eq!(
+            simplify(json!([
+                {"op": "replace", "path": "/db/host", "value": "b"},
+                {"op": "add", "path": "/db/port", "value": 1},
+                {"op": "remove", "path": "/db"}
+            ])),
+            json!([{"op": "remove", "path": "/db"}])
+        );
+
+        // Two insertions at one index, and paths at different indices of an
+        // array, are left alone
+        let inserts = json!([
+            {"op": "add", "path": "/list/0", "value": 1},
+            {"op": "add", "path": "/list/0", "value": 2}
+        ]);
+        assert_eq!(simplify(inserts.clone()), inserts);
+        let shifted = json!([
+            {"op": "replace", "path": "/list/1", "value": 1},
+            {"op": "remove", "path": "/list/0"},
+            {"op": "replace", "path": "/list/1", "value": 2}
+        ]);
+        assert_eq!(simplify(shifted.clone()), shifted);
+
+        // Tests are never moved past related operations
+        let tested = json!([
+            {"op": "replace", "path": "/a", "value": 1},
+            {"op": "test", "path": "/a", "value": 1},
+            {"op": "replace", "path": "/a", "value": 2}
+        ]);
+        assert_eq!(simplify(tested.clone()), tested);
+    }
+
+    #[test]
+    fn test_compose_patches() {
+        let document = json!({"users": [{"name": "ann"}], "version": 1});
+        let first = json!([
+            {"op": "add", "path": "/users/-", "value": {"name": "bob"}},
+            {"op": "replace", "path": "/version", "value": 2}
+        ]);
+        let second = json!([
+            {"op": "add", "path": "/users/1/admin", "value": true},
+            {"op": "replace", "path": "/version", "value": 3}
+        ]);
+        let composed = compose_patches(&[first.clone(), second.clone()]).unwrap();
+        assert_eq!(
+            composed,
+            json!([
+                {"op": "add", "path": "/users/-", "value": {"name": "bob"}},
+                {"op": "replace", "path": "/version", "value": 3},
+                {"op": "add", "path": "/users/1/admin", "value": true}
+            ])
+        );
+        let sequential = apply_patch(&apply_patch(&document, &first).unwrap(), &second);
+        assert_eq!(apply_patch(&document, &composed), sequential);
+
+        let err = compose_patches(&[first, json!([{"op": "move", "path": "/a"}])]).unwrap_err();
+        assert!(err.to_string().contains("operation #0"));
+    }
+
+    #[test]
+    fn test_minimize_patch() {
+        let document = json!({"list": [1, 2, 3, 4], "a": 1});
+        // The diff of the result is shorter
+        let patch = json!([
+            {"op": "replace", "path": "/a", "value": 2},
+            {"op": "replace", "path": "/a", "value": 1},
+            {"op": "remove", "path": "/list/3"},
+            {"op": "remove", "path": "/list/2"},
+            {"op": "test", "path": "/list", "value": [1, 2]}
+        ]);
+        let minimized = minimize_patch(&document, &patch).unwrap();
+        assert_eq!(
+            minimized,
+            json!([
+                {"op": "remove", "path": "/list/3"},
+                {"op": "remove", "path": "/list/2"}
+            ])
+        );
+        assert_eq!(
+            apply_patch(&document, &minimized),
+            apply_patch(&document, &patch)
+        );
+
+        // Removing the first element shifts every later one; the patch is shorter
+        let patch = json!([{"op": "remove", "path": "/list/0"}]);
+        assert_eq!(minimize_patch(&document, &patch).unwrap(), patch);
+
+        // A new subtree is added whole
+        let patch = json!([
+            {"op": "add", "path": "/o", "value": {"x": [1, 2]}},
+            {"op": "test", "path": "/a", "value": 1}
+        ]);
+        assert_eq!(
+            minimize_patch(&document, &patch).unwrap(),
+            json!([{"op": "add", "path": "/o", "value": {"x": [1, 2]}}])
+        );
+
+        assert!(minimize_patch(&document, &json!([{"op": "remove", "path": "/b"}])).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! Rewriting of change paths
+//!
+//! A [`PathTransformer`] maps each reported path to another one, for example
+//! to strip an environment-specific prefix so that two deployments report the
+//! same paths, or to name array elements by an identity key instead of their
+//! index. [`Changes::map_paths`] applies it to changes, warnings, truncations
+//! and annotations alike; ignore patterns and formatters then see only the
+//! rewritten paths.
+
+use crate::json_path::JsonPath;
+use crate::types::{Change, Changes};
+use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+
+/// Rewrites the paths of reported changes
+///
+/// Any `Fn(&JsonPath) -> JsonPath` closure is a transformer.
+///
+/// # Examples
+///
+/// Name the elements of `users` by their `id` rather than their index:
+///
+/// ```rust
+/// use rjd::{diff, JsonPath, PathSegment};
+/// use serde_json::json;
+///
+/// let old = json!({"users": [{"id": "ann", "age": 30}, {"id": "bob", "age": 40}]});
+/// let new = json!({"users": [{"id": "ann", "age": 31}, {"id": "bob", "age": 40}]});
+///
+/// let by_id = |path: &JsonPath| {
+///     let mut segments = path.segments().to_vec();
+///     if let [PathSegment::Key(users), PathSegment::Index(i), ..] = segments.as_slice() {
+///         if let Some(id) = old[users.as_str()][*i]["id"].as_str() {
+///             segments[1] = PathSegment::Key(id.to_string());
+///         }
+///     }
+///     JsonPath::from_segments(segments)
+/// };
+///
+/// let mut changes = diff(&old, &new);
+/// changes.map_paths(&by_id);
+/// assert_eq!(changes.modified[0].path().to_string(), "users.ann.age");
+/// ```
+pub trait PathTransformer {
+    /// The path to report in place of `path`
+    fn transform(&self, path: &JsonPath) -> JsonPath;
+}
+
+impl<F: Fn(&JsonPath) -> JsonPath> PathTransformer for F {
+    fn transform(&self, path: &JsonPath) -> JsonPath {
+        self(path)
+    }
+}
+
+/// A sed-style substitution on the JSON Pointer of a path
+///
+/// Written `s<d>PATTERN<d>REPLACEMENT<d>[g]`, where the delimiter `<d>` is
+/// the character after `s` (`s#^/env/[^/]+##` strips a leading
+/// `/env/<name>`). `PATTERN` is a regular expression matched against the
+/// pointer (`/users/0/name`); `REPLACEMENT` may refer to groups as `$1` or
+/// `${name}`. Only the first match is replaced unless the `g` flag is given.
+/// A backslash before the delimiter makes it literal.
+///
+/// The result is read back as a JSON Pointer; a leading `/` is added if the
+/// substitution removed it, and a path whose result is not a valid pointer is
+/// left unchanged.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::{JsonPath, PathSubstitution, PathTransformer};
+///
+/// let strip_env: PathSubstitution = "s#^/env/[^/]+##".parse().unwrap();
+/// let path = JsonPath::from_pointer("/env/prod/db/host").unwrap();
+/// assert_eq!(strip_env.transform(&path).to_json_pointer(), "/db/host");
+/// ```
+#[derive(Debug, Clone)]
+pub struct PathSubstitution {
+    expression: String,
+    pattern: Regex,
+    replacement: String,
+    global: bool,
+}
+
+impl PathTransformer for PathSubstitution {
+    fn transform(&self, path: &JsonPath) -> JsonPath {
+        let pointer = path.to_json_pointer();
+        let limit = if self.global { 0 } else { 1 };
+        let replaced = self
+            .pattern
+            .replacen(&pointer, limit, self.replacement.as_str());
+        if replaced == pointer {
+            return path.clone();
+        }
+        let replaced = if replaced.is_empty() || replaced.starts_with('/') {
+            replaced.into_owned()
+        } else {
+            format!("/{}", replaced)
+        };
+        JsonPath::from_pointer(&replaced).unwrap_or_else(|_| path.clone())
+    }
+}
+
+impl fmt::Display for PathSubstitution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl FromStr for PathSubstitution {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let expected = || {
+            format!(
+                "invalid path substitution '{}' (expected s#PATTERN#REPLACEMENT#)",
+                text
+            )
+        };
+        let mut chars = text.chars();
+        if chars.next() != Some('s') {
+            return Err(expected());
+        }
+        let delimiter = chars.next().filter(|c| !c.is_alphanumeric() && *c != '\\');
+        let delimiter = delimiter.ok_or_else(expected)?;
+
+        // Split on unescaped delimiters, unescaping escaped ones
+        let mut parts = vec![String::new()];
+        while let Some(c) = chars.next() {
+            if c == '\\' && chars.clone().next() == Some(delimiter) {
+                parts.last_mut().unwrap().push(chars.next().unwrap());
+            } else if c == delimiter {
+                parts.push(String::new());
+            } else {
+                parts.last_mut().unwrap().push(c);
+            }
+        }
+        let [pattern, replacement, flags] =
+            <[String; 3]>::try_from(parts).map_err(|_| expected())?;
+        let global = match flags.as_str() {
+            "" => false,
+            "g" => true,
+            _ => {
+                return Err(format!(
+                    "unknown flags '{}' in '{}' (expected g)",
+                    flags, text
+                ))
+            }
+        };
+        let pattern =
+            Regex::new(&pattern).map_err(|e| format!("invalid pattern in '{}': {}", text, e))?;
+
+        Ok(Self {
+            expression: text.to_string(),
+            pattern,
+            replacement,
+            global,
+        })
+    }
+}
+
+impl Changes {
+    /// Rewrite every path with `transformer`
+    ///
+    /// Changes keep their order and kind. Changes that end up at the same
+    /// path are all kept; their annotations are merged.
+    pub fn map_paths<T: PathTransformer + ?Sized>(&mut self, transformer: &T) {
+        let changes = self
+            .added
+            .iter_mut()
+            .chain(&mut self.removed)
+            .chain(&mut self.modified);
+        for change in changes {
+            let (Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Modified { path, .. }) = change;
+            *path = transformer.transform(path);
+        }
+        for warning in &mut self.warnings {
+            warning.path = transformer.transform(&warning.path);
+        }
+        for truncation in &mut self.truncated {
+            truncation.path = transformer.transform(&truncation.path);
+        }
+
+        let annotations = std::mem::take(&mut self.annotations);
+        for (path, fields) in annotations {
+            self.annotations
+                .entry(transformer.transform(&path))
+                .or_default()
+                .extend(fields);
+        }
+        self.paired_arrays = std::mem::take(&mut self.paired_arrays)
+            .into_iter()
+            .map(|(path, pairs)| (transformer.transform(&path), pairs))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    fn pointer(substitution: &str, path: &str) -> String {
+        let substitution: PathSubstitution = substitution.parse().unwrap();
+        let path = JsonPath::from_pointer(path).unwrap();
+        substitution.transform(&path).to_json_pointer()
+    }
+
+    #[test]
+    fn test_substitution() {
+        assert_eq!(pointer("s#^/env/[^/]+##", "/env/prod/db/port"), "/db/port");
+        assert_eq!(pointer("s#^/env/[^/]+##", "/other/env/x"), "/other/env/x");
+        assert_eq!(pointer("s#^/env/[^/]+##", "/env/prod"), "");
+        assert_eq!(pointer("s|/([0-9]+)|/item$1|", "/a/0/b/1"), "/a/item0/b/1");
+        assert_eq!(
+            pointer("s|/([0-9]+)|/item$1|g", "/a/0/b/1"),
+            "/a/item0/b/item1"
+        );
+        // A removed leading slash is restored; an invalid pointer keeps the path
+        assert_eq!(pointer("s#^/a/##", "/a/b"), "/b");
+        assert_eq!(pointer("s#b#~#", "/a/b"), "/a/b");
+        assert_eq!(pointer(r"s#a\#b#c#", "/a#b"), "/c");
+
+        for invalid in ["", "x#a#b#", "s#a#b", "s#a#b#x", "s#(#b#", "sa"] {
+            assert!(invalid.parse::<PathSubstitution>().is_err(), "{}", invalid);
+        }
+        let substitution: PathSubstitution = "s#a#b#g".parse().unwrap();
+        assert_eq!(substitution.to_string(), "s#a#b#g");
+    }
+
+    #[test]
+    fn test_map_paths() {
+        let old = json!({"env": {"prod": {"a": 1, "b": 1}}});
+        let new = json!({"env": {"prod": {"a": 2, "c": 1}}});
+        let mut changes = diff(&old, &new);
+        changes
+            .annotations
+            .entry("env.prod.a".parse().unwrap())
+            .or_default()
+            .insert("note".to_string(), json!(true));
+
+        let substitution: PathSubstitution = "s#^/env/[^/]+##".parse().unwrap();
+        changes.map_paths(&substitution);
+        let paths: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(paths, vec!["c", "b", "a"]);
+        assert_eq!(changes.annotations[&"a".parse().unwrap()]["note"], true);
+        assert_eq!(changes.annotations.len(), 1);
+    }
+}
@@ -0,0 +1,354 @@
+//! JSONPath query expressions for filters
+//!
+//! Ignore and path-policy patterns starting with `$` are JSONPath queries,
+//! compiled into matchers over [`JsonPath`] segments. Supported syntax:
+//!
+//! - `$` - the root
+//! - `.name`, `['name']`, `["name"]` - an object member
+//! - `[0]` - an array element
+//! - `.*`, `[*]` - any member or element
+//! - `..name`, `..*`, `..[0]` - recursive descent: the step at any depth below
+//!
+//! # Example
+//!
+//! ```rust
+//! use rjd::query::Query;
+//!
+//! let query = Query::parse("$.users[*].sessionToken").unwrap();
+//! assert!(query.matches(&"users[3].sessionToken".parse().unwrap()));
+//! assert!(!query.matches(&"users[3].name".parse().unwrap()));
+//!
+//! let anywhere = Query::parse("$..password").unwrap();
+//! assert!(anywhere.matches(&"db.primary.password".parse().unwrap()));
+//! ```
+
+use crate::json_path::{JsonPath, PathSegment};
+use std::fmt;
+use std::str::FromStr;
+
+/// A compiled JSONPath query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    source: String,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Key(String),
+    Index(usize),
+    Wildcard,
+    /// Skip zero or more segments before the next step
+    Descend,
+}
+
+/// Error type for JSONPath query parsing failures
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum QueryError {
+    #[error("JSONPath query must start with '$'")]
+    MissingRoot,
+
+    #[error("Unexpected end of JSONPath query")]
+    UnexpectedEnd,
+
+    #[error("Unexpected character '{found}' at position {position} in JSONPath query")]
+    UnexpectedCharacter { found: char, position: usize },
+}
+
+impl Query {
+    /// Compile a JSONPath expression
+    ///
+    /// # Errors
+    /// Returns an error if the expression does not start with `$` or is
+    /// malformed.
+    pub fn parse(expression: &str) -> Result<Self, QueryError> {
+        Parser::new(expression).parse()
+    }
+
+    /// Check whether `path` is matched by the query
+    pub fn matches(&self, path: &JsonPath) -> bool {
+        match_steps(&self.steps, path.segments())
+    }
+
+    /// Check whether `path` or one of its ancestors is matched by the query
+    pub fn matches_path_or_ancestor(&self, path: &JsonPath) -> bool {
+        let segments = path.segments();
+        (0..=segments.len()).any(|len| match_steps(&self.steps, &segments[..len]))
+    }
+
+    /// The single path this query selects, if it has no wildcards or
+    /// recursive descent
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rjd::query::Query;
+    ///
+    /// let query = Query::parse("$.users[0]['e-mail']").unwrap();
+    /// assert_eq!(query.to_path().unwrap().to_json_pointer(), "/users/0/e-mail");
+    /// assert!(Query::parse("$.users[*]").unwrap().to_path().is_none());
+    /// ```
+    pub fn to_path(&self) -> Option<JsonPath> {
+        self.steps
+            .iter()
+            .map(|step| match step {
+                Step::Key(key) => Some(PathSegment::Key(key.clone())),
+                Step::Index(index) => Some(PathSegment::Index(*index)),
+                Step::Wildcard | Step::Descend => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(JsonPath::from_segments)
+    }
+
+    /// Check whether the query could match `path` or a path below it
+    pub fn could_match_below(&self, path: &JsonPath) -> bool {
+        could_extend(&self.steps, path.segments())
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn match_steps(steps: &[Step], segments: &[PathSegment]) -> bool {
+    match steps.split_first() {
+        None => segments.is_empty(),
+        Some((Step::Descend, rest)) => {
+            (0..=segments.len()).any(|skip| match_steps(rest, &segments[skip..]))
+        }
+        Some((step, rest)) => match segments.split_first() {
+            Some((segment, remaining)) => {
+                step_matches(step, segment) && match_steps(rest, remaining)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Whether `segments` is a prefix of some path the steps match
+fn could_extend(steps: &[Step], segments: &[PathSegment]) -> bool {
+    match (steps.split_first(), segments.split_first()) {
+        (_, None) => true,
+        (None, Some(_)) => false,
+        (Some((Step::Descend, _)), Some(_)) => true,
+        (Some((step, rest)), Some((segment, remaining))) => {
+            step_matches(step, segment) && could_extend(rest, remaining)
+        }
+    }
+}
+
+fn step_matches(step: &Step, segment: &PathSegment) -> bool {
+    match (step, segment) {
+        (Step::Wildcard, _) => true,
+        (Step::Key(key), PathSegment::Key(segment)) => key == segment,
+        (Step::Index(index), PathSegment::Index(segment)) => index == segment,
+        _ => false,
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            chars: source.chars().collect(),
+            position: 0,
+        }
+    }
+
+    fn parse(mut self) -> Result<Query, QueryError> {
+        if self.next() != Some('$') {
+            return Err(QueryError::MissingRoot);
+        }
+
+        let mut steps = Vec::new();
+        while let Some(c) = self.next() {
+            match c {
+                '.' => {
+                    if self.peek() == Some('.') {
+                        self.position += 1;
+                        steps.push(Step::Descend);
+                        if self.peek() == Some('[') {
+                            continue;
+                        }
+                    }
+                    steps.push(self.member()?);
+                }
+                '[' => steps.push(self.bracket()?),
+                found => return Err(self.unexpected(found)),
+            }
+        }
+        if steps.last() == Some(&Step::Descend) {
+            return Err(QueryError::UnexpectedEnd);
+        }
+
+        Ok(Query {
+            source: self.source.to_string(),
+            steps,
+        })
+    }
+
+    /// A member name or `*` after a dot
+    fn member(&mut self) -> Result<Step, QueryError> {
+        if self.peek() == Some('*') {
+            self.position += 1;
+            return Ok(Step::Wildcard);
+        }
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if c == '.' || c == '[' {
+                break;
+            }
+            self.position += 1;
+        }
+        if self.position == start {
+            return match self.peek() {
+                Some(found) => Err(self.unexpected(found)),
+                None => Err(QueryError::UnexpectedEnd),
+            };
+        }
+        Ok(Step::Key(self.chars[start..self.position].iter().collect()))
+    }
+
+    /// The contents of `[...]` after the opening bracket
+    fn bracket(&mut self) -> Result<Step, QueryError> {
+        let step = match self.next().ok_or(QueryError::UnexpectedEnd)? {
+            '*' => Step::Wildcard,
+            quote @ ('\'' | '"') => {
+                let mut key = String::new();
+                loop {
+                    match self.next().ok_or(QueryError::UnexpectedEnd)? {
+                        '\\' => key.push(self.next().ok_or(QueryError::UnexpectedEnd)?),
+                        c if c == quote => break,
+                        c => key.push(c),
+                    }
+                }
+                Step::Key(key)
+            }
+            c if c.is_ascii_digit() => {
+                let start = self.position - 1;
+                while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    self.position += 1;
+                }
+                let digits: String = self.chars[start..self.position].iter().collect();
+                let index = digits
+                    .parse()
+                    .map_err(|_| QueryError::UnexpectedCharacter {
+                        found: c,
+                        position: start,
+                    })?;
+                Step::Index(index)
+            }
+            found => return Err(self.unexpected(found)),
+        };
+        match self.next() {
+            Some(']') => Ok(step),
+            Some(found) => Err(self.unexpected(found)),
+            None => Err(QueryError::UnexpectedEnd),
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.get(self.position).copied();
+        self.position += 1;
+        c
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    /// Error for the character just consumed
+    fn unexpected(&self, found: char) -> QueryError {
+        QueryError::UnexpectedCharacter {
+            found,
+            position: self.position.saturating_sub(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(s: &str) -> JsonPath {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_match_steps() {
+        let query = Query::parse("$.store.book[*]['title']").unwrap();
+        assert!(query.matches(&path("store.book[2].title")));
+        assert!(!query.matches(&path("store.book[2]")));
+        assert!(!query.matches(&path("store.book[2].title.x")));
+        assert!(query.matches_path_or_ancestor(&path("store.book[2].title.x")));
+
+        let index = Query::parse("$.items[1]").unwrap();
+        assert!(index.matches(&path("items[1]")));
+        assert!(!index.matches(&path("items[10]")));
+
+        assert!(Query::parse("$").unwrap().matches(&JsonPath::new()));
+        assert!(Query::parse("$[\"a.b\"]")
+            .unwrap()
+            .matches(&JsonPath::from_segments(vec![PathSegment::Key(
+                "a.b".to_string()
+            )])));
+    }
+
+    #[test]
+    fn test_recursive_descent() {
+        let query = Query::parse("$..password").unwrap();
+        assert!(query.matches(&path("password")));
+        assert!(query.matches(&path("a.b[0].password")));
+        assert!(!query.matches(&path("a.password.x")));
+
+        let nested = Query::parse("$.users..[0]").unwrap();
+        assert!(nested.matches(&path("users[0]")));
+        assert!(nested.matches(&path("users.x.list[0]")));
+        assert!(!nested.matches(&path("admins[0]")));
+
+        assert!(Query::parse("$..*").unwrap().matches(&path("a.b")));
+    }
+
+    #[test]
+    fn test_could_match_below() {
+        let query = Query::parse("$.users[*].token").unwrap();
+        assert!(query.could_match_below(&path("users")));
+        assert!(query.could_match_below(&path("users[0].token")));
+        assert!(!query.could_match_below(&path("admins")));
+        assert!(Query::parse("$..token")
+            .unwrap()
+            .could_match_below(&path("anything")));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Query::parse("users"), Err(QueryError::MissingRoot));
+        assert_eq!(Query::parse("$.a["), Err(QueryError::UnexpectedEnd));
+        assert_eq!(Query::parse("$.."), Err(QueryError::UnexpectedEnd));
+        assert_eq!(Query::parse("$."), Err(QueryError::UnexpectedEnd));
+        assert_eq!(
+            Query::parse("$.a[x]"),
+            Err(QueryError::UnexpectedCharacter {
+                found: 'x',
+                position: 4
+            })
+        );
+        assert!(Query::parse("$.a[1").is_err());
+    }
+}
@@ -0,0 +1,146 @@
+//! Rebuilding the old document from the new one and its changes
+
+use crate::json_path::{JsonPath, PathSegment};
+use crate::types::{Change, Changes};
+use serde_json::Value;
+
+impl Changes {
+    /// Rebuild the old document from the new document and these changes
+    ///
+    /// Added values are removed, modified values get their old value back and
+    /// removed values are re-inserted. This lets a history store keep only the
+    /// latest document plus the diffs.
+    ///
+    /// Added subtrees are recorded leaf by leaf, so a container that only
+    /// holds added values is left behind empty: `{}` and `{"x": {}}` both
+    /// change into `{"x": {"y": 1}}` with the same changes. Removed object keys
+    /// are re-added at the end of their object. A truncated diff
+    /// (see [`Changes::truncated`]) cannot be fully reversed either.
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::diff;
+    /// use serde_json::json;
+    ///
+    /// let old = json!({"name": "John", "tags": ["a", "b"], "age": 30});
+    /// let new = json!({"name": "Jane", "tags": ["a"], "email": "j@example.com"});
+    /// let changes = diff(&old, &new);
+    /// assert_eq!(changes.reconstruct_before(&new), old);
+    /// ```
+    pub fn reconstruct_before(&self, after: &Value) -> Value {
+        let mut before = after.clone();
+
+        // Undo additions deepest and last first, so array indices stay valid
+        let mut added: Vec<&JsonPath> = self.added.iter().map(Change::path).collect();
+        added.sort_by(|a, b| compare_paths(b, a));
+        for path in added {
+            remove_at(&mut before, path);
+        }
+
+        for change in &self.modified {
+            if let Change::Modified {
+                path, old_value, ..
+            } = change
+            {
+                path.set(&mut before, old_value.clone());
+            }
+        }
+
+        // Re-insert removals in document order, so arrays are refilled in sequence
+        let mut removed: Vec<&Change> = self.removed.iter().collect();
+        removed.sort_by(|a, b| compare_paths(a.path(), b.path()));
+        for change in removed {
+            if let Change::Removed { path, value } = change {
+                insert_at(&mut before, path, value.clone());
+            }
+        }
+
+        before
+    }
+}
+
+/// Order paths segment by segment, with array indices compared numerically
+fn compare_paths(a: &JsonPath, b: &JsonPath) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    for (x, y) in a.segments().iter().zip(b.segments()) {
+        let ordering = match (x, y) {
+            (PathSegment::Index(x), PathSegment::Index(y)) => x.cmp(y),
+            (PathSegment::Key(x), PathSegment::Key(y)) => x.cmp(y),
+            (PathSegment::Key(_), PathSegment::Index(_)) => Ordering::Less,
+            (PathSegment::Index(_), PathSegment::Key(_)) => Ordering::Greater,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// Remove the value at `path`, shifting later array elements down
+fn remove_at(root: &mut Value, path: &JsonPath) {
+    let Some((last, parent)) = path.segments().split_last() else {
+        *root = Value::Null;
+        return;
+    };
+    let parent = JsonPath::from_segments(parent.to_vec());
+    match (parent.resolve_mut(root), last) {
+        (Some(Value::Object(map)), PathSegment::Key(key)) => {
+            map.shift_remove(key);
+        }
+        (Some(Value::Array(items)), PathSegment::Index(index)) if *index < items.len() => {
+            items.remove(*index);
+        }
+        _ => {}
+    }
+}
+
+/// Insert `value` at `path`, shifting later array elements up
+fn insert_at(root: &mut Value, path: &JsonPath, value: Value) {
+    if let Some((PathSegment::Index(index), parent)) = path.segments().split_last() {
+        let parent = JsonPath::from_segments(parent.to_vec());
+        if let Some(Value::Array(items)) = parent.resolve_mut(root) {
+            if *index <= items.len() {
+                items.insert(*index, value);
+                return;
+            }
+        }
+    }
+    path.set(root, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff;
+    use serde_json::json;
+
+    #[test]
+    fn test_reconstruct_before_round_trips() {
+        let cases = [
+            (json!({"a": 1}), json!({"a": 2})),
+            (json!({"list": [1, 2, 3, 4]}), json!({"list": [9]})),
+            (json!({"list": [1]}), json!({"list": [1, 2, 3]})),
+            (
+                json!({"user": {"name": "A", "roles": [{"id": 1}, {"id": 2}]}}),
+                json!({"user": {"roles": [{"id": 1, "admin": true}]}, "v": 2}),
+            ),
+            (json!("old"), json!({"replaced": true})),
+            (json!([[1, 2], [3]]), json!([[1]])),
+        ];
+        for (old, new) in cases {
+            let changes = diff(&old, &new);
+            assert_eq!(changes.reconstruct_before(&new), old, "{} -> {}", old, new);
+        }
+    }
+
+    #[test]
+    fn test_reconstruct_before_appends_removed_keys() {
+        let old = json!({"a": 1, "b": 2, "c": 3});
+        let new = json!({"a": 1, "c": 3});
+        let before = diff(&old, &new).reconstruct_before(&new);
+        let keys: Vec<&String> = before.as_object().unwrap().keys().collect();
+        // Removed keys are re-appended; the values round-trip but not the order
+        assert_eq!(keys, ["a", "c", "b"]);
+        assert_eq!(before, old);
+    }
+}
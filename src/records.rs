@@ -0,0 +1,852 @@
+//! Avro and Parquet record files
+//!
+//! Data pipelines write records to Avro object container files and Parquet
+//! files. These decoders read every record of such a file into a JSON array
+//! of objects, one per record, so pipeline output can be diffed like JSON;
+//! use `--unwrap /N` (or a JSON Pointer in the library) to compare a single
+//! record. They are meant for small record sets: the whole file is decoded
+//! into memory, within the `--max-file-size` and `--max-depth` limits: the
+//! decompressed data may not exceed the file size limit, and a file cannot
+//! claim more items than its bytes can hold.
+//!
+//! Bytes and fixed values become base64 strings and non-finite floats become
+//! `null`. Avro logical types are shown as their underlying values (e.g. a
+//! `timestamp-millis` as a number); Parquet dates, timestamps and decimals
+//! are shown as strings.
+
+use crate::decode::DecodeError;
+use crate::loader::LoadConfig;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+use std::io::Read;
+
+/// First bytes of an Avro object container file
+const AVRO_MAGIC: &[u8] = b"Obj\x01";
+
+/// Decode the records of an Avro object container file
+///
+/// The `null`, `deflate` and `snappy` codecs are supported. Decoding fails
+/// once values nest deeper than `config.max_json_depth`, or the decompressed
+/// blocks exceed `config.max_file_size`.
+pub(crate) fn decode_avro(bytes: &[u8], config: &LoadConfig) -> Result<Value, DecodeError> {
+    let mut limits = Limits::new(config);
+    let mut header = AvroReader::new(bytes);
+    if header.take(AVRO_MAGIC.len())? != AVRO_MAGIC {
+        return Err("not an Avro object container file".to_string().into());
+    }
+    let mut metadata = HashMap::new();
+    header.blocks(1, &mut limits, |reader, _| {
+        let key = reader.string()?;
+        metadata.insert(key, reader.bytes()?.to_vec());
+        Ok(())
+    })?;
+    let sync = header.take(16)?;
+
+    let schema = metadata
+        .get("avro.schema")
+        .ok_or_else(|| "missing avro.schema in the file header".to_string())?;
+    let schema: Value =
+        serde_json::from_slice(schema).map_err(|e| format!("invalid schema: {}", e))?;
+    let mut names = HashMap::new();
+    let schema = Schema::parse(&schema, None, &mut names)?;
+    let sizes = min_sizes(&names)?;
+    let types = Types {
+        names: &names,
+        sizes: &sizes,
+    };
+    let codec = match metadata.get("avro.codec") {
+        Some(codec) => String::from_utf8_lossy(codec).into_owned(),
+        None => "null".to_string(),
+    };
+
+    let mut records = Vec::new();
+    let mut reader = header;
+    while !reader.is_empty() {
+        let count = reader.count()? as u64;
+        let size = reader.count()?;
+        let block = reader.take(size)?;
+        if reader.take(16)? != sync {
+            return Err(format!("sync marker mismatch at offset {}", reader.pos - 16).into());
+        }
+        let data = decompress(&codec, block, limits.data_left)?;
+        limits.data_left -= data.len() as u64;
+        let mut block_reader = AvroReader::new(&data);
+        block_reader.check_count(count, schema.min_size(&sizes), &mut limits)?;
+        for _ in 0..count {
+            records.push(block_reader.value(&schema, types, 1, &mut limits)?);
+        }
+    }
+    Ok(Value::Array(records))
+}
+
+/// Limits on what a file may make the decoder build
+struct Limits {
+    max_depth: usize,
+    /// Decompressed bytes the remaining blocks may hold
+    data_left: u64,
+    /// Items that read no bytes (such as `null`s) that may still be built;
+    /// the file size limit, counted in values
+    empty_items_left: u64,
+}
+
+impl Limits {
+    fn new(config: &LoadConfig) -> Self {
+        Self {
+            max_depth: config.max_json_depth,
+            data_left: config.max_file_size,
+            empty_items_left: config.max_file_size / std::mem::size_of::<Value>() as u64,
+        }
+    }
+}
+
+/// The named types of a schema, and the least number of bytes each is encoded in
+#[derive(Clone, Copy)]
+struct Types<'a> {
+    names: &'a HashMap<String, Schema>,
+    sizes: &'a HashMap<String, usize>,
+}
+
+/// The data of a block written with `codec`, at most `limit` bytes of it
+fn decompress(codec: &str, block: &[u8], limit: u64) -> Result<Vec<u8>, String> {
+    let too_large = || format!("decompressed data exceeds the limit of {} bytes", limit);
+    let data = match codec {
+        "null" => block.to_vec(),
+        "deflate" => {
+            let mut data = Vec::new();
+            flate2::read::DeflateDecoder::new(block)
+                .take(limit.saturating_add(1))
+                .read_to_end(&mut data)
+                .map_err(|e| format!("invalid deflate block: {}", e))?;
+            data
+        }
+        "snappy" => {
+            // The block ends with a CRC-32 of the uncompressed data
+            let compressed = block
+                .len()
+                .checked_sub(4)
+                .map(|end| &block[..end])
+                .ok_or("truncated snappy block")?;
+            let len = snap::raw::decompress_len(compressed)
+                .map_err(|e| format!("invalid snappy block: {}", e))?;
+            if len as u64 > limit {
+                return Err(too_large());
+            }
+            snap::raw::Decoder::new()
+                .decompress_vec(compressed)
+                .map_err(|e| format!("invalid snappy block: {}", e))?
+        }
+        other => return Err(format!("unsupported Avro codec '{}'", other)),
+    };
+    if data.len() as u64 > limit {
+        return Err(too_large());
+    }
+    Ok(data)
+}
+
+/// The least number of bytes each named type is encoded in
+///
+/// A record that always contains itself could never be encoded; it is
+/// rejected, since reading it would recurse without consuming any input.
+fn min_sizes(names: &HashMap<String, Schema>) -> Result<HashMap<String, usize>, String> {
+    fn size_of(
+        name: &str,
+        names: &HashMap<String, Schema>,
+        sizes: &mut HashMap<String, usize>,
+        visiting: &mut Vec<String>,
+    ) -> Result<usize, String> {
+        if let Some(&size) = sizes.get(name) {
+            return Ok(size);
+        }
+        if visiting.iter().any(|visited| visited == name) {
+            return Err(format!("type '{}' contains itself and can never end", name));
+        }
+        let schema = names
+            .get(name)
+            .ok_or_else(|| format!("unknown type '{}'", name))?;
+        visiting.push(name.to_string());
+        let mut named = Vec::new();
+        schema.required_names(&mut named);
+        for inner in named {
+            size_of(inner, names, sizes, visiting)?;
+        }
+        visiting.pop();
+        let size = schema.min_size(sizes);
+        sizes.insert(name.to_string(), size);
+        Ok(size)
+    }
+
+    let mut sizes = HashMap::new();
+    for name in names.keys() {
+        size_of(name, names, &mut sizes, &mut Vec::new())?;
+    }
+    Ok(sizes)
+}
+
+/// An Avro schema
+#[derive(Debug, Clone)]
+enum Schema {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    Record(Vec<(String, Schema)>),
+    Enum(Vec<String>),
+    Array(Box<Schema>),
+    Map(Box<Schema>),
+    Union(Vec<Schema>),
+    Fixed(usize),
+    /// A reference to a named record, enum or fixed type, by full name
+    Named(String),
+}
+
+impl Schema {
+    /// Parse a schema, registering its named types in `names`
+    fn parse(
+        schema: &Value,
+        namespace: Option<&str>,
+        names: &mut HashMap<String, Schema>,
+    ) -> Result<Self, String> {
+        let object = match schema {
+            Value::String(name) => return Ok(Self::primitive(name, namespace)),
+            Value::Array(branches) => {
+                return branches
+                    .iter()
+                    .map(|branch| Self::parse(branch, namespace, names))
+                    .collect::<Result<_, _>>()
+                    .map(Schema::Union)
+            }
+            Value::Object(object) => object,
+            other => return Err(format!("invalid schema {}", other)),
+        };
+        let kind = object
+            .get("type")
+            .ok_or_else(|| format!("schema without a type: {}", schema))?;
+        let Some(kind) = kind.as_str() else {
+            // {"type": {...}} wraps another schema
+            return Self::parse(kind, namespace, names);
+        };
+
+        let full_name = || -> Result<String, String> {
+            let name = object
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or_else(|| format!("{} schema without a name", kind))?;
+            let namespace = object
+                .get("namespace")
+                .and_then(Value::as_str)
+                .or(namespace);
+            Ok(qualify(name, namespace))
+        };
+        let parsed = match kind {
+            "record" | "error" => {
+                let name = full_name()?;
+                let inner = name.rsplit_once('.').map(|(namespace, _)| namespace);
+                let fields = object
+                    .get("fields")
+                    .and_then(Value::as_array)
+                    .ok_or_else(|| format!("record {} without fields", name))?;
+                let fields = fields
+                    .iter()
+                    .map(|field| {
+                        let field_name = field
+                            .get("name")
+                            .and_then(Value::as_str)
+                            .ok_or_else(|| format!("field of {} without a name", name))?;
+                        let field_type = field.get("type").ok_or_else(|| {
+                            format!("field {}.{} without a type", name, field_name)
+                        })?;
+                        Ok((
+                            field_name.to_string(),
+                            Self::parse(field_type, inner, names)?,
+                        ))
+                    })
+                    .collect::<Result<_, String>>()?;
+                names.insert(name.clone(), Schema::Record(fields));
+                Schema::Named(name)
+            }
+            "enum" => {
+                let name = full_name()?;
+                let symbols = object
+                    .get("symbols")
+                    .and_then(Value::as_array)
+                    .ok_or_else(|| format!("enum {} without symbols", name))?
+                    .iter()
+                    .map(|symbol| symbol.as_str().unwrap_or_default().to_string())
+                    .collect();
+                names.insert(name.clone(), Schema::Enum(symbols));
+                Schema::Named(name)
+            }
+            "fixed" => {
+                let name = full_name()?;
+                let size = object
+                    .get("size")
+                    .and_then(Value::as_u64)
+                    .ok_or_else(|| format!("fixed {} without a size", name))?;
+                names.insert(name.clone(), Schema::Fixed(size as usize));
+                Schema::Named(name)
+            }
+            "array" => {
+                let items = object.get("items").ok_or("array schema without items")?;
+                Schema::Array(Box::new(Self::parse(items, namespace, names)?))
+            }
+            "map" => {
+                let values = object.get("values").ok_or("map schema without values")?;
+                Schema::Map(Box::new(Self::parse(values, namespace, names)?))
+            }
+            // Primitive types, possibly with a logical type
+            name => Self::primitive(name, namespace),
+        };
+        Ok(parsed)
+    }
+
+    /// The named types every value of this schema contains
+    ///
+    /// Types reached only through an array, map or union are left out, since
+    /// an empty array or map, or another branch, ends the value there.
+    fn required_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Schema::Record(fields) => {
+                for (_, field) in fields {
+                    field.required_names(names);
+                }
+            }
+            Schema::Named(name) => names.push(name),
+            _ => {}
+        }
+    }
+
+    /// The least number of bytes a value of this schema is encoded in, given
+    /// those of the named types
+    fn min_size(&self, sizes: &HashMap<String, usize>) -> usize {
+        match self {
+            Schema::Null => 0,
+            Schema::Float => 4,
+            Schema::Double => 8,
+            Schema::Fixed(size) => *size,
+            Schema::Record(fields) => fields.iter().fold(0, |total, (_, field)| {
+                total.saturating_add(field.min_size(sizes))
+            }),
+            Schema::Named(name) => sizes.get(name).copied().unwrap_or(0),
+            // A varint, a length, a block count or a branch index
+            _ => 1,
+        }
+    }
+
+    fn primitive(name: &str, namespace: Option<&str>) -> Self {
+        match name {
+            "null" => Schema::Null,
+            "boolean" => Schema::Boolean,
+            "int" => Schema::Int,
+            "long" => Schema::Long,
+            "float" => Schema::Float,
+            "double" => Schema::Double,
+            "bytes" => Schema::Bytes,
+            "string" => Schema::String,
+            name => Schema::Named(qualify(name, namespace)),
+        }
+    }
+}
+
+/// The full name of a type named `name` in `namespace`
+fn qualify(name: &str, namespace: Option<&str>) -> String {
+    match namespace {
+        Some(namespace) if !name.contains('.') && !namespace.is_empty() => {
+            format!("{}.{}", namespace, name)
+        }
+        _ => name.to_string(),
+    }
+}
+
+/// Reader for the Avro binary encoding
+struct AvroReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> AvroReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| format!("unexpected end of data at offset {}", self.pos))?;
+        let taken = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(taken)
+    }
+
+    /// A zig-zag encoded variable-length integer
+    fn long(&mut self) -> Result<i64, String> {
+        let offset = self.pos;
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok((value >> 1) as i64 ^ -((value & 1) as i64));
+            }
+        }
+        Err(format!("integer too long at offset {}", offset))
+    }
+
+    /// A long that must not be negative, such as a length
+    fn count(&mut self) -> Result<usize, String> {
+        let offset = self.pos;
+        usize::try_from(self.long()?).map_err(|_| format!("negative length at offset {}", offset))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.count()?;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let offset = self.pos;
+        String::from_utf8(self.bytes()?.to_vec())
+            .map_err(|_| format!("invalid UTF-8 at offset {}", offset))
+    }
+
+    /// Check that `count` items of at least `item_size` bytes each fit in the
+    /// bytes left, or in the budget of items that read nothing
+    fn check_count(&self, count: u64, item_size: usize, limits: &mut Limits) -> Result<(), String> {
+        let fits = match (self.bytes.len() - self.pos).checked_div(item_size) {
+            Some(items) => count <= items as u64,
+            None => {
+                let left = limits.empty_items_left.checked_sub(count);
+                limits.empty_items_left = left.unwrap_or(0);
+                left.is_some()
+            }
+        };
+        if fits {
+            Ok(())
+        } else {
+            Err(format!(
+                "count of {} items at offset {} exceeds what the data can hold",
+                count, self.pos
+            ))
+        }
+    }
+
+    /// Read the items of a block-encoded array or map, each encoded in at
+    /// least `item_size` bytes
+    fn blocks(
+        &mut self,
+        item_size: usize,
+        limits: &mut Limits,
+        mut item: impl FnMut(&mut Self, &mut Limits) -> Result<(), DecodeError>,
+    ) -> Result<(), DecodeError> {
+        loop {
+            let count = match self.long()? {
+                0 => return Ok(()),
+                // A negative count is followed by the block's size in bytes
+                count if count < 0 => {
+                    self.long()?;
+                    count.unsigned_abs()
+                }
+                count => count as u64,
+            };
+            self.check_count(count, item_size, limits)?;
+            for _ in 0..count {
+                item(self, limits)?;
+            }
+        }
+    }
+
+    /// A value of `schema`, nested `depth` levels deep
+    fn value(
+        &mut self,
+        schema: &Schema,
+        types: Types,
+        depth: usize,
+        limits: &mut Limits,
+    ) -> Result<Value, DecodeError> {
+        let nests = matches!(
+            schema,
+            Schema::Record(_) | Schema::Array(_) | Schema::Map(_)
+        );
+        if nests && depth > limits.max_depth {
+            return Err(DecodeError::TooDeep(depth));
+        }
+        Ok(match schema {
+            Schema::Null => Value::Null,
+            Schema::Boolean => Value::Bool(self.take(1)?[0] != 0),
+            Schema::Int | Schema::Long => Value::from(self.long()?),
+            Schema::Float => {
+                let n = f32::from_le_bytes(self.take(4)?.try_into().expect("took 4 bytes"));
+                float(f64::from(n))
+            }
+            Schema::Double => float(f64::from_le_bytes(
+                self.take(8)?.try_into().expect("took 8 bytes"),
+            )),
+            Schema::Bytes => Value::String(STANDARD.encode(self.bytes()?)),
+            Schema::String => Value::String(self.string()?),
+            Schema::Fixed(size) => Value::String(STANDARD.encode(self.take(*size)?)),
+            Schema::Record(fields) => {
+                let mut record = Map::new();
+                for (name, field) in fields {
+                    record.insert(name.clone(), self.value(field, types, depth + 1, limits)?);
+                }
+                Value::Object(record)
+            }
+            Schema::Enum(symbols) => {
+                let offset = self.pos;
+                let index = self.count()?;
+                let symbol = symbols.get(index).ok_or_else(|| {
+                    format!("enum index {} out of range at offset {}", index, offset)
+                })?;
+                Value::String(symbol.clone())
+            }
+            Schema::Array(items) => {
+                let mut values = Vec::new();
+                self.blocks(items.min_size(types.sizes), limits, |reader, limits| {
+                    values.push(reader.value(items, types, depth + 1, limits)?);
+                    Ok(())
+                })?;
+                Value::Array(values)
+            }
+            Schema::Map(values) => {
+                let mut map = Map::new();
+                let item_size = values.min_size(types.sizes).saturating_add(1);
+                self.blocks(item_size, limits, |reader, limits| {
+                    let key = reader.string()?;
+                    map.insert(key, reader.value(values, types, depth + 1, limits)?);
+                    Ok(())
+                })?;
+                Value::Object(map)
+            }
+            Schema::Union(branches) => {
+                let offset = self.pos;
+                let index = self.count()?;
+                let branch = branches.get(index).ok_or_else(|| {
+                    format!("union branch {} out of range at offset {}", index, offset)
+                })?;
+                self.value(branch, types, depth, limits)?
+            }
+            Schema::Named(name) => {
+                let schema = types
+                    .names
+                    .get(name)
+                    .ok_or_else(|| format!("unknown type '{}'", name))?;
+                self.value(schema, types, depth, limits)?
+            }
+        })
+    }
+}
+
+fn float(n: f64) -> Value {
+    Number::from_f64(n).map_or(Value::Null, Value::Number)
+}
+
+/// Decode the rows of a Parquet file
+pub(crate) fn decode_parquet(bytes: &[u8]) -> Result<Value, String> {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let reader = SerializedFileReader::new(bytes::Bytes::copy_from_slice(bytes))
+        .map_err(|e| e.to_string())?;
+    let rows = reader.get_row_iter(None).map_err(|e| e.to_string())?;
+    rows.map(|row| {
+        row.map(|row| row.to_json_value())
+            .map_err(|e| e.to_string())
+    })
+    .collect::<Result<_, _>>()
+    .map(Value::Array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Avro encoding of a long
+    fn long(n: i64) -> Vec<u8> {
+        let mut zigzag = ((n << 1) ^ (n >> 63)) as u64;
+        let mut bytes = Vec::new();
+        loop {
+            let byte = (zigzag & 0x7F) as u8;
+            zigzag >>= 7;
+            if zigzag == 0 {
+                bytes.push(byte);
+                return bytes;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+
+    fn string(text: &str) -> Vec<u8> {
+        let mut bytes = long(text.len() as i64);
+        bytes.extend(text.as_bytes());
+        bytes
+    }
+
+    /// An object container file holding one block of `count` records
+    fn container(schema: &Value, codec: &str, count: i64, data: &[u8]) -> Vec<u8> {
+        let sync = [7u8; 16];
+        let mut file = AVRO_MAGIC.to_vec();
+        file.extend(long(2));
+        file.extend(string("avro.schema"));
+        file.extend(string(&schema.to_string()));
+        file.extend(string("avro.codec"));
+        file.extend(string(codec));
+        file.extend(long(0));
+        file.extend(sync);
+        file.extend(long(count));
+        file.extend(long(data.len() as i64));
+        file.extend(data);
+        file.extend(sync);
+        file
+    }
+
+    fn schema() -> Value {
+        json!({
+            "type": "record",
+            "name": "User",
+            "namespace": "example",
+            "fields": [
+                {"name": "id", "type": "long"},
+                {"name": "email", "type": ["null", "string"]},
+                {"name": "score", "type": "double"},
+                {"name": "tags", "type": {"type": "array", "items": "string"}},
+                {"name": "attrs", "type": {"type": "map", "values": "int"}},
+                {"name": "status", "type": {"type": "enum", "name": "Status", "symbols": ["ACTIVE", "GONE"]}},
+                {"name": "hash", "type": {"type": "fixed", "name": "Hash", "size": 2}},
+                {"name": "manager", "type": ["null", "User"]},
+                {"name": "created", "type": {"type": "long", "logicalType": "timestamp-millis"}}
+            ]
+        })
+    }
+
+    fn decode(file: &[u8]) -> Result<Value, DecodeError> {
+        decode_avro(file, &LoadConfig::default())
+    }
+
+    fn invalid(message: &str) -> DecodeError {
+        DecodeError::Invalid(message.to_string())
+    }
+
+    fn records() -> Vec<u8> {
+        let mut data = Vec::new();
+        // id 1, email "a@x", score 1.5, tags ["x"], attrs {"k": -1}, GONE, "hi", no manager
+        data.extend(long(1));
+        data.extend(long(1));
+        data.extend(string("a@x"));
+        data.extend(1.5f64.to_le_bytes());
+        data.extend(long(1));
+        data.extend(string("x"));
+        data.extend(long(0));
+        // A block with a negative count and a byte size
+        data.extend(long(-1));
+        data.extend(long(2));
+        data.extend(string("k"));
+        data.extend(long(-1));
+        data.extend(long(0));
+        data.extend(long(1));
+        data.extend(b"hi");
+        data.extend(long(0));
+        data.extend(long(1_700_000_000_000));
+        // id -2, no email, NaN score, no tags or attrs, ACTIVE, a manager with id 1
+        data.extend(long(-2));
+        data.extend(long(0));
+        data.extend(f64::NAN.to_le_bytes());
+        data.extend(long(0));
+        data.extend(long(0));
+        data.extend(long(0));
+        data.extend(b"\x00\xff");
+        data.extend(long(1));
+        data.extend(long(1));
+        data.extend(long(0));
+        data.extend(0f64.to_le_bytes());
+        data.extend([0, 0, 0, 0, 0, 0]);
+        data.extend(long(0));
+        data.extend(long(0));
+        data
+    }
+
+    #[test]
+    fn test_decode_avro() {
+        let expected = json!([
+            {
+                "id": 1, "email": "a@x", "score": 1.5, "tags": ["x"], "attrs": {"k": -1},
+                "status": "GONE", "hash": "aGk=", "manager": null, "created": 1700000000000i64
+            },
+            {
+                "id": -2, "email": null, "score": null, "tags": [], "attrs": {},
+                "status": "ACTIVE", "hash": "AP8=",
+                "manager": {
+                    "id": 1, "email": null, "score": 0.0, "tags": [], "attrs": {},
+                    "status": "ACTIVE", "hash": "AAA=", "manager": null, "created": 0
+                },
+                "created": 0
+            }
+        ]);
+        let file = container(&schema(), "null", 2, &records());
+        assert_eq!(decode(&file).unwrap(), expected);
+
+        let mut compressed = Vec::new();
+        flate2::read::DeflateEncoder::new(&records()[..], flate2::Compression::default())
+            .read_to_end(&mut compressed)
+            .unwrap();
+        let file = container(&schema(), "deflate", 2, &compressed);
+        assert_eq!(decode(&file).unwrap(), expected);
+
+        let mut compressed = snap::raw::Encoder::new().compress_vec(&records()).unwrap();
+        compressed.extend([0; 4]);
+        let file = container(&schema(), "snappy", 2, &compressed);
+        assert_eq!(decode(&file).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_decode_avro_errors() {
+        assert!(decode(b"{\"a\": 1}").is_err());
+
+        let file = container(&schema(), "zstandard", 2, &records());
+        assert_eq!(
+            decode(&file).unwrap_err(),
+            invalid("unsupported Avro codec 'zstandard'")
+        );
+
+        let mut file = container(&schema(), "null", 2, &records());
+        let last = file.len() - 1;
+        file[last] = 0;
+        assert!(matches!(
+            decode(&file).unwrap_err(),
+            DecodeError::Invalid(message) if message.starts_with("sync marker mismatch")
+        ));
+
+        // More records than the block holds
+        let file = container(&schema(), "null", 3, &records());
+        assert!(decode(&file).is_err());
+
+        let file = container(
+            &json!({"type": "record", "name": "R", "fields": [{"name": "x", "type": "Missing"}]}),
+            "null",
+            1,
+            &[],
+        );
+        assert_eq!(
+            decode(&file).unwrap_err(),
+            invalid("unknown type 'Missing'")
+        );
+    }
+
+    #[test]
+    fn test_decode_avro_limits() {
+        // A record that contains itself would be read forever without using any bytes
+        let file = container(
+            &json!({"type": "record", "name": "R", "fields": [{"name": "r", "type": "R"}]}),
+            "null",
+            1,
+            &[],
+        );
+        assert_eq!(
+            decode(&file).unwrap_err(),
+            invalid("type 'R' contains itself and can never end")
+        );
+
+        // One optional self-reference per level, nested past the depth limit
+        let list = json!({
+            "type": "record",
+            "name": "List",
+            "fields": [{"name": "next", "type": ["null", "List"]}]
+        });
+        let file = container(&list, "null", 1, &[&[2u8; 20][..], &[0]].concat());
+        let config = LoadConfig::with_limits(u64::MAX, 10);
+        assert_eq!(
+            decode_avro(&file, &config).unwrap_err(),
+            DecodeError::TooDeep(11)
+        );
+        let config = LoadConfig::with_limits(u64::MAX, 21);
+        assert!(decode_avro(&file, &config).is_ok());
+
+        // An array claiming 2^40 nulls, which take no bytes
+        let nulls = json!({"type": "array", "items": "null"});
+        let file = container(&nulls, "null", 1, &[long(1 << 40), long(0)].concat());
+        assert!(matches!(
+            decode(&file).unwrap_err(),
+            DecodeError::Invalid(message) if message.starts_with("count of 1099511627776 items")
+        ));
+        let file = container(&nulls, "null", 1, &[long(3), long(0)].concat());
+        assert_eq!(decode(&file).unwrap(), json!([[null, null, null]]));
+
+        // More records than the block has bytes for
+        let file = container(&json!("long"), "null", 1 << 40, &long(1));
+        assert!(matches!(
+            decode(&file).unwrap_err(),
+            DecodeError::Invalid(message) if message.starts_with("count of 1099511627776 items")
+        ));
+
+        // A block that decompresses past the file size limit
+        let data = vec![0u8; 4096];
+        let mut compressed = Vec::new();
+        flate2::read::DeflateEncoder::new(&data[..], flate2::Compression::default())
+            .read_to_end(&mut compressed)
+            .unwrap();
+        let file = container(&json!("null"), "deflate", 1, &compressed);
+        let config = LoadConfig::with_limits(1024, 100);
+        assert_eq!(
+            decode_avro(&file, &config).unwrap_err(),
+            invalid("decompressed data exceeds the limit of 1024 bytes")
+        );
+        let mut compressed = snap::raw::Encoder::new().compress_vec(&data).unwrap();
+        compressed.extend([0; 4]);
+        let file = container(&json!("null"), "snappy", 1, &compressed);
+        assert_eq!(
+            decode_avro(&file, &config).unwrap_err(),
+            invalid("decompressed data exceeds the limit of 1024 bytes")
+        );
+    }
+
+    #[test]
+    fn test_decode_parquet() {
+        use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::parser::parse_message_type;
+        use std::sync::Arc;
+
+        let schema =
+            parse_message_type("message user { required int64 id; optional binary name (UTF8); }")
+                .unwrap();
+        let mut file = Vec::new();
+        let mut writer = SerializedFileWriter::new(
+            &mut file,
+            Arc::new(schema),
+            Arc::new(WriterProperties::builder().build()),
+        )
+        .unwrap();
+        let mut row_group = writer.next_row_group().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&[1, 2], None, None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&[ByteArray::from("ann")], Some(&[1, 0]), None)
+            .unwrap();
+        column.close().unwrap();
+        row_group.close().unwrap();
+        writer.close().unwrap();
+
+        assert_eq!(
+            decode_parquet(&file).unwrap(),
+            json!([{"id": 1, "name": "ann"}, {"id": 2, "name": null}])
+        );
+        assert!(decode_parquet(b"PAR1 not really").is_err());
+    }
+}
@@ -0,0 +1,243 @@
+//! Resolution of `$ref` references to other JSON files
+//!
+//! Documents split across files (e.g. `{"$ref": "common.json#/defaults"}`) are
+//! inlined before diffing, so content that merely moved between files is not
+//! reported as a removal in one place and an addition in another.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::error::RjdError;
+use crate::loader::{load_json_file_with_config_and_policy, LoadConfig, SymlinkPolicy};
+
+/// Inline all `$ref` references to other files in `value`
+///
+/// Relative paths are resolved against `base_dir`, and references inside a
+/// referenced file against that file's directory. Every referenced file must
+/// lie under `base_dir`, so absolute paths and `..` cannot reach elsewhere. A
+/// `#/json/pointer` suffix selects part of the referenced document. Local
+/// references (`#/...`) and URLs are left untouched. Sibling keys next to
+/// `$ref` are merged over the referenced object.
+///
+/// # Errors
+/// Returns an error if a referenced file cannot be loaded or lies outside
+/// `base_dir`, a pointer does not exist, or references form a cycle.
+pub fn resolve_file_refs(
+    value: &Value,
+    base_dir: &Path,
+    config: &LoadConfig,
+    policy: SymlinkPolicy,
+) -> Result<Value, RjdError> {
+    let root = base_dir
+        .canonicalize()
+        .map_err(|source| RjdError::FileRead {
+            path: base_dir.to_path_buf(),
+            source,
+        })?;
+    let mut resolver = RefResolver {
+        root,
+        config,
+        policy,
+        stack: Vec::new(),
+        documents: HashMap::new(),
+    };
+    resolver.resolve(value, base_dir)
+}
+
+struct RefResolver<'a> {
+    /// Directory every referenced file must lie under
+    root: PathBuf,
+    config: &'a LoadConfig,
+    policy: SymlinkPolicy,
+    /// References currently being resolved, for cycle detection
+    stack: Vec<String>,
+    /// Loaded documents by canonical path
+    documents: HashMap<PathBuf, Value>,
+}
+
+impl RefResolver<'_> {
+    fn resolve(&mut self, value: &Value, base_dir: &Path) -> Result<Value, RjdError> {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    if let Some((file, pointer)) = split_file_ref(reference) {
+                        let mut resolved = self.resolve_ref(reference, file, pointer, base_dir)?;
+                        if let Value::Object(target) = &mut resolved {
+                            for (key, sibling) in map.iter().filter(|(key, _)| *key != "$ref") {
+                                target.insert(key.clone(), self.resolve(sibling, base_dir)?);
+                            }
+                        }
+                        return Ok(resolved);
+                    }
+                }
+
+                let mut resolved = serde_json::Map::new();
+                for (key, child) in map {
+                    resolved.insert(key.clone(), self.resolve(child, base_dir)?);
+                }
+                Ok(Value::Object(resolved))
+            }
+            Value::Array(items) => items
+                .iter()
+                .map(|item| self.resolve(item, base_dir))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Array),
+            _ => Ok(value.clone()),
+        }
+    }
+
+    fn resolve_ref(
+        &mut self,
+        reference: &str,
+        file: &str,
+        pointer: &str,
+        base_dir: &Path,
+    ) -> Result<Value, RjdError> {
+        let error = |message: String| RjdError::RefResolution {
+            reference: reference.to_string(),
+            message,
+        };
+
+        let path = base_dir.join(file);
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| error(format!("{}: {}", path.display(), e)))?;
+        if !canonical.starts_with(&self.root) {
+            return Err(RjdError::RefOutsideRoot {
+                reference: reference.to_string(),
+                root: self.root.clone(),
+            });
+        }
+
+        let key = format!("{}#{}", canonical.display(), pointer);
+        if self.stack.contains(&key) {
+            return Err(error("circular reference".to_string()));
+        }
+
+        if !self.documents.contains_key(&canonical) {
+            let document = load_json_file_with_config_and_policy(&path, self.config, self.policy)?;
+            self.documents.insert(canonical.clone(), document);
+        }
+        let target = self.documents[&canonical]
+            .pointer(pointer)
+            .cloned()
+            .ok_or_else(|| error(format!("pointer '{}' not found", pointer)))?;
+
+        let file_dir = canonical.parent().unwrap_or(Path::new(".")).to_path_buf();
+        self.stack.push(key);
+        let resolved = self.resolve(&target, &file_dir);
+        self.stack.pop();
+        resolved
+    }
+}
+
+/// Split a reference into file and JSON pointer parts, if it points to a file
+fn split_file_ref(reference: &str) -> Option<(&str, &str)> {
+    if reference.starts_with('#') || reference.contains("://") {
+        return None;
+    }
+    Some(reference.split_once('#').unwrap_or((reference, "")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_resolve_nested_file_refs() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("shared")).unwrap();
+        std::fs::write(
+            dir.path().join("shared/common.json"),
+            r#"{"defaults": {"retries": 3, "backoff": {"$ref": "backoff.json"}}}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("shared/backoff.json"), r#"{"ms": 100}"#).unwrap();
+
+        let value = json!({
+            "service": {"$ref": "shared/common.json#/defaults", "name": "api"},
+            "local": {"$ref": "#/definitions/x"}
+        });
+        let resolved = resolve_file_refs(
+            &value,
+            dir.path(),
+            &LoadConfig::default(),
+            SymlinkPolicy::Reject,
+        )
+        .unwrap();
+
+        assert_eq!(
+            resolved,
+            json!({
+                "service": {"retries": 3, "backoff": {"ms": 100}, "name": "api"},
+                "local": {"$ref": "#/definitions/x"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_resolve_detects_cycles_and_missing_pointers() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.json"), r#"{"$ref": "b.json"}"#).unwrap();
+        std::fs::write(dir.path().join("b.json"), r#"{"$ref": "a.json"}"#).unwrap();
+        let config = LoadConfig::default();
+
+        let cyclic = resolve_file_refs(
+            &json!({"$ref": "a.json"}),
+            dir.path(),
+            &config,
+            SymlinkPolicy::Reject,
+        );
+        assert!(matches!(cyclic, Err(RjdError::RefResolution { .. })));
+
+        std::fs::write(dir.path().join("c.json"), r#"{"x": 1}"#).unwrap();
+        let missing = resolve_file_refs(
+            &json!({"$ref": "c.json#/y"}),
+            dir.path(),
+            &config,
+            SymlinkPolicy::Reject,
+        );
+        assert!(matches!(missing, Err(RjdError::RefResolution { .. })));
+    }
+
+    #[test]
+    fn test_resolve_rejects_files_outside_base_dir() {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path().join("base");
+        std::fs::create_dir_all(base.join("nested")).unwrap();
+        std::fs::write(dir.path().join("secret.json"), r#"{"key": "x"}"#).unwrap();
+        std::fs::write(
+            base.join("nested/up.json"),
+            r#"{"$ref": "../../secret.json"}"#,
+        )
+        .unwrap();
+        let config = LoadConfig::default();
+        let resolve =
+            |value: Value| resolve_file_refs(&value, &base, &config, SymlinkPolicy::Reject);
+
+        let absolute = dir.path().join("secret.json");
+        for reference in [
+            "../secret.json".to_string(),
+            absolute.to_str().unwrap().to_string(),
+            "nested/up.json".to_string(),
+        ] {
+            let error = resolve(json!({"$ref": reference})).unwrap_err();
+            assert!(
+                matches!(error, RjdError::RefOutsideRoot { .. }),
+                "{}",
+                reference
+            );
+        }
+
+        // `..` that stays inside the base directory is fine
+        std::fs::write(base.join("inner.json"), r#"{"ok": true}"#).unwrap();
+        assert_eq!(
+            resolve(json!({"$ref": "nested/../inner.json"})).unwrap(),
+            json!({"ok": true})
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Reversing a diff
+//!
+//! [`Changes::invert`] turns the changes from one document to another into
+//! the changes back, without the documents, so a saved change set can be
+//! rendered as a rollback.
+
+use crate::types::{Change, Changes, WarningKind};
+use serde_json::Value;
+
+/// Annotations holding one side of a change, and their counterparts
+const SIDED_ANNOTATIONS: &[(&str, &str)] = &[
+    ("oldLocation", "newLocation"),
+    ("oldType", "newType"),
+    ("removedElements", "addedElements"),
+];
+
+impl Changes {
+    /// Turn these changes into the changes from the second document back to the first
+    ///
+    /// Added values become removed ones and vice versa, modified values swap
+    /// their old and new values, and the documents swap places (set
+    /// [`Changes::before`] first, or the result has no second document).
+    /// Annotations describing one side (locations, types, array set elements
+    /// and string edits) and warnings naming an input are swapped too.
+    /// Judgements of the forward change, such as `severity` or schema
+    /// `compatibility`, are kept as they are.
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::diff;
+    /// use serde_json::json;
+    ///
+    /// let old = json!({"name": "John", "phone": "555"});
+    /// let new = json!({"name": "Jane", "email": "j@example.com"});
+    /// let mut changes = diff(&old, &new);
+    /// changes.before = Some(old.clone());
+    /// changes.invert();
+    ///
+    /// let mut expected = diff(&new, &old);
+    /// expected.before = Some(new);
+    /// assert_eq!(changes, expected);
+    /// ```
+    pub fn invert(&mut self) {
+        let added = std::mem::take(&mut self.added);
+        let removed = std::mem::take(&mut self.removed);
+        self.added = removed.into_iter().map(invert_change).collect();
+        self.removed = added.into_iter().map(invert_change).collect();
+        self.modified = std::mem::take(&mut self.modified)
+            .into_iter()
+            .map(invert_change)
+            .collect();
+        std::mem::swap(&mut self.before, &mut self.after);
+        for pairs in self.paired_arrays.values_mut() {
+            for (old, new) in pairs.iter_mut() {
+                std::mem::swap(old, new);
+            }
+        }
+
+        for fields in self.annotations.values_mut() {
+            for (old, new) in SIDED_ANNOTATIONS {
+                let old_value = fields.remove(*old);
+                if let Some(value) = fields.remove(*new) {
+                    fields.insert(old.to_string(), value);
+                }
+                if let Some(value) = old_value {
+                    fields.insert(new.to_string(), value);
+                }
+            }
+            if let Some(Value::Array(edits)) = fields.get_mut("stringDiff") {
+                for edit in edits {
+                    let op = match edit.get("op").and_then(Value::as_str) {
+                        Some("insert") => "delete",
+                        Some("delete") => "insert",
+                        _ => continue,
+                    };
+                    edit["op"] = Value::from(op);
+                }
+            }
+        }
+
+        for warning in &mut self.warnings {
+            match &mut warning.kind {
+                WarningKind::PositionalArray { old_len, new_len } => {
+                    std::mem::swap(old_len, new_len)
+                }
+                WarningKind::DuplicateKey(input) => {
+                    *input = match input.as_str() {
+                        "old" => "new".to_string(),
+                        "new" => "old".to_string(),
+                        other => other.to_string(),
+                    }
+                }
+                WarningKind::PrecisionLoss | WarningKind::AmbiguousKey(_) => {}
+            }
+        }
+    }
+}
+
+fn invert_change(change: Change) -> Change {
+    match change {
+        Change::Added { path, value } => Change::Removed { path, value },
+        Change::Removed { path, value } => Change::Added { path, value },
+        Change::Modified {
+            path,
+            old_value,
+            new_value,
+        } => Change::Modified {
+            path,
+            old_value: new_value,
+            new_value: old_value,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use crate::string_diff::{annotate_string_diffs, Granularity};
+    use crate::types::Warning;
+    use serde_json::json;
+
+    #[test]
+    fn test_invert_matches_swapped_diff() {
+        let old = json!({"a": [1, 2, 3], "b": {"c": 1}, "d": "x"});
+        let new = json!({"a": [1, 5], "b": {"e": 2}, "d": 4, "f": null});
+        let mut changes = diff(&old, &new);
+        changes.before = Some(old.clone());
+        changes.after = Some(new.clone());
+        changes.invert();
+
+        let mut expected = diff(&new, &old);
+        expected.before = Some(new);
+        expected.after = Some(old);
+        assert_eq!(changes, expected);
+    }
+
+    #[test]
+    fn test_invert_annotations_and_warnings() {
+        let mut changes = diff(&json!({"s": "abc"}), &json!({"s": "abd"}));
+        annotate_string_diffs(&mut changes, Granularity::Char);
+        let path = changes.modified[0].path().clone();
+        let fields = changes.annotations.get_mut(&path).unwrap();
+        fields.insert("oldLocation".to_string(), json!({"line": 1}));
+        fields.insert("severity".to_string(), json!("warn"));
+        changes.warnings.push(Warning {
+            path: path.clone(),
+            kind: WarningKind::DuplicateKey("old".to_string()),
+        });
+
+        changes.invert();
+        let fields = &changes.annotations[&path];
+        assert_eq!(fields["newLocation"], json!({"line": 1}));
+        assert!(fields.get("oldLocation").is_none());
+        assert_eq!(fields["severity"], "warn");
+        assert_eq!(
+            fields["stringDiff"],
+            json!([{"op": "equal", "text": "ab"}, {"op": "insert", "text": "c"}, {"op": "delete", "text": "d"}])
+        );
+        assert_eq!(
+            changes.warnings[0].kind,
+            WarningKind::DuplicateKey("new".to_string())
+        );
+    }
+}
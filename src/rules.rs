@@ -0,0 +1,283 @@
+//! Severity rules for changes
+//!
+//! A rules file assigns a severity to each change from its path and kind,
+//! e.g. to fail a CI job only when critical settings drift:
+//!
+//! ```json
+//! {
+//!   "default": "info",
+//!   "rules": [
+//!     { "path": "/database/**", "kinds": ["removed", "modified"], "severity": "error" },
+//!     { "path": "$.services[*].replicas", "severity": "warn" }
+//!   ]
+//! }
+//! ```
+//!
+//! Paths use the ignore pattern syntax: a JSON Pointer, dot-notation path or
+//! `$` JSONPath query matches exactly the values it selects, and a trailing
+//! `/**` (`/database/**`) adds everything below them. A rule without `kinds` applies to every
+//! kind of change. The first matching rule wins; changes no rule matches get
+//! the default severity (`info` if not given).
+
+use crate::error::RjdError;
+use crate::ignore::validate_pattern;
+use crate::types::{Change, ChangeKind, Changes, PatternMatcher};
+use serde::de::Error as _;
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+/// How serious a change is, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warn,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warn => write!(f, "warn"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "info" => Ok(Severity::Info),
+            "warn" => Ok(Severity::Warn),
+            "error" => Ok(Severity::Error),
+            _ => Err(format!(
+                "unknown severity '{}' (expected info, warn or error)",
+                text
+            )),
+        }
+    }
+}
+
+/// A rule assigning a severity to matching changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    /// Ignore-style pattern of the paths the rule applies to
+    pub path: String,
+    /// Kinds of change the rule applies to (empty for all)
+    pub kinds: Vec<ChangeKind>,
+    /// Severity of matching changes
+    pub severity: Severity,
+}
+
+/// An ordered list of severity rules
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::rules::{Rules, Severity};
+/// use rjd::diff;
+/// use serde_json::json;
+///
+/// let rules = Rules::from_json(
+///     r#"{"rules": [{"path": "/db/**", "kinds": ["removed"], "severity": "error"}]}"#,
+/// )
+/// .unwrap();
+/// let mut changes = diff(&json!({"db": {"host": "x"}, "n": 1}), &json!({"db": {}, "n": 2}));
+///
+/// assert_eq!(rules.apply(&mut changes), Some(Severity::Error));
+/// assert_eq!(changes.annotations[changes.removed[0].path()]["severity"], "error");
+/// assert_eq!(changes.annotations[changes.modified[0].path()]["severity"], "info");
+/// ```
+#[derive(Clone)]
+pub struct Rules {
+    rules: Vec<(Rule, PatternMatcher)>,
+    default: Severity,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    default: Option<Severity>,
+    #[serde(default)]
+    rules: Vec<RuleEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleEntry {
+    path: String,
+    #[serde(default)]
+    kinds: Vec<KindName>,
+    severity: Severity,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum KindName {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl Rules {
+    /// Create rules, checking that their path patterns are valid
+    ///
+    /// # Errors
+    /// Returns an error if a rule's path is not a valid ignore pattern.
+    pub fn new(rules: Vec<Rule>, default: Severity) -> Result<Self, RjdError> {
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                validate_pattern(&rule.path)?;
+                let matcher = PatternMatcher::new(std::slice::from_ref(&rule.path));
+                Ok((rule, matcher))
+            })
+            .collect::<Result<_, RjdError>>()?;
+        Ok(Self { rules, default })
+    }
+
+    /// Read rules from the JSON text of a rules file
+    ///
+    /// # Errors
+    /// Returns an error if the text is not a valid rules file.
+    pub fn from_json(text: &str) -> Result<Self, serde_json::Error> {
+        let file: RulesFile = serde_json::from_str(text)?;
+        let rules = file
+            .rules
+            .into_iter()
+            .map(|entry| Rule {
+                path: entry.path,
+                kinds: entry
+                    .kinds
+                    .into_iter()
+                    .map(|kind| match kind {
+                        KindName::Added => ChangeKind::Added,
+                        KindName::Removed => ChangeKind::Removed,
+                        KindName::Modified => ChangeKind::Modified,
+                    })
+                    .collect(),
+                severity: entry.severity,
+            })
+            .collect();
+        Self::new(rules, file.default.unwrap_or(Severity::Info))
+            .map_err(|e| serde_json::Error::custom(e.to_string()))
+    }
+
+    /// The severity of a change: that of the first matching rule, or the default
+    pub fn severity(&self, change: &Change) -> Severity {
+        self.rules
+            .iter()
+            .find(|(rule, matcher)| {
+                (rule.kinds.is_empty() || rule.kinds.contains(&change.kind()))
+                    && matcher.matches(change.path())
+            })
+            .map_or(self.default, |(rule, _)| rule.severity)
+    }
+
+    /// Annotate every change with its `severity` and return the highest one
+    ///
+    /// Returns `None` if there are no changes.
+    pub fn apply(&self, changes: &mut Changes) -> Option<Severity> {
+        let severities: Vec<_> = changes
+            .iter()
+            .map(|change| (change.path().clone(), self.severity(change)))
+            .collect();
+        let highest = severities.iter().map(|(_, severity)| *severity).max();
+        for (path, severity) in severities {
+            changes
+                .annotations
+                .entry(path)
+                .or_default()
+                .insert("severity".to_string(), Value::String(severity.to_string()));
+        }
+        highest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    fn severities(rules: &Rules, changes: &Changes) -> Vec<(String, Severity)> {
+        changes
+            .iter()
+            .map(|change| (change.path().to_string(), rules.severity(change)))
+            .collect()
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rules = Rules::from_json(
+            r#"{
+                "default": "warn",
+                "rules": [
+                    {"path": "/db/timeout", "severity": "info"},
+                    {"path": "/db/**", "severity": "error"},
+                    {"path": "$.services[*].replicas", "kinds": ["modified"], "severity": "warn"},
+                    {"path": "/services/**", "severity": "info"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let old = json!({"db": {"host": "a", "timeout": 5}, "services": [{"replicas": 1}], "x": 1});
+        let new = json!({"db": {"host": "b", "timeout": 6}, "services": [{"replicas": 2, "y": 1}], "x": 2});
+        let changes = diff(&old, &new);
+
+        assert_eq!(
+            severities(&rules, &changes),
+            [
+                ("services[0].y".to_string(), Severity::Info),
+                ("db.host".to_string(), Severity::Error),
+                ("db.timeout".to_string(), Severity::Info),
+                ("services[0].replicas".to_string(), Severity::Warn),
+                ("x".to_string(), Severity::Warn),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_annotates_changes() {
+        let rules = Rules::new(Vec::new(), Severity::Info).unwrap();
+        let mut changes = diff(&json!({"a": 1}), &json!({"a": 2}));
+        changes
+            .annotations
+            .entry("a".parse().unwrap())
+            .or_default()
+            .insert("compatibility".to_string(), json!("compatible"));
+
+        assert_eq!(rules.apply(&mut changes), Some(Severity::Info));
+        let annotations = &changes.annotations[&"a".parse().unwrap()];
+        assert_eq!(annotations["severity"], "info");
+        assert_eq!(annotations["compatibility"], "compatible");
+        assert_eq!(rules.apply(&mut Changes::new()), None);
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(
+            Rules::from_json(r#"{"rules": [{"path": "db[x]", "severity": "error"}]}"#).is_err()
+        );
+        assert!(Rules::from_json(r#"{"rules": [{"path": "/db", "severity": "fatal"}]}"#).is_err());
+        assert!(Rules::from_json(r#"{"rule": []}"#).is_err());
+        assert!(Rules::from_json(
+            r#"{"rules": [{"path": "/db", "kinds": ["moved"], "severity": "info"}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_severity_order_and_parsing() {
+        assert!(Severity::Info < Severity::Warn && Severity::Warn < Severity::Error);
+        assert_eq!("warn".parse::<Severity>(), Ok(Severity::Warn));
+        assert!("fatal".parse::<Severity>().is_err());
+        assert_eq!(Severity::Error.to_string(), "error");
+    }
+}
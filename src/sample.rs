@@ -0,0 +1,117 @@
+//! Deterministic sampling of changes
+//!
+//! Sampling uses a small seeded generator (SplitMix64) so that the same seed
+//! always selects the same changes, on every platform and rjd version.
+
+use crate::types::Changes;
+
+/// SplitMix64 pseudo-random number generator
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound` (bound > 0)
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+impl Changes {
+    /// Keep a pseudo-random subset of at most `count` changes
+    ///
+    /// The selection depends only on `seed`, the number of changes and their
+    /// order, so a report can be reproduced by sampling again with the same
+    /// seed. Kept changes stay in their original order.
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::diff;
+    /// use serde_json::json;
+    ///
+    /// let changes = diff(&json!({"a": 1, "b": 2, "c": 3}), &json!({"a": 4, "b": 5, "c": 6}));
+    /// let sample = changes.sample(2, 42);
+    /// assert_eq!(sample.len(), 2);
+    /// assert_eq!(sample, changes.sample(2, 42));
+    /// ```
+    pub fn sample(&self, count: usize, seed: u64) -> Changes {
+        let total = self.len();
+        if count >= total {
+            return self.clone();
+        }
+
+        // Partial Fisher-Yates shuffle over the change indices
+        let mut rng = SplitMix64(seed);
+        let mut indices: Vec<usize> = (0..total).collect();
+        for i in 0..count {
+            let j = i + rng.below(total - i);
+            indices.swap(i, j);
+        }
+        let mut selected = vec![false; total];
+        for &index in &indices[..count] {
+            selected[index] = true;
+        }
+
+        let mut sampled = Changes::new();
+        for (change, keep) in self.iter().zip(selected) {
+            if keep {
+                sampled.push(change.clone());
+            }
+        }
+        sampled.before = self.before.clone();
+        sampled.after = self.after.clone();
+        sampled.truncated = self.truncated.clone();
+        sampled.warnings = self.warnings.clone();
+        sampled.annotations = self.annotations.clone();
+        sampled.paired_arrays = self.paired_arrays.clone();
+        sampled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{Change, Changes};
+    use serde_json::json;
+
+    fn numbered(count: usize) -> Changes {
+        let mut changes = Changes::new();
+        for i in 0..count {
+            changes.push(Change::Added {
+                path: format!("k{}", i).parse().unwrap(),
+                value: json!(i),
+            });
+        }
+        changes
+    }
+
+    #[test]
+    fn test_sample_is_deterministic_and_ordered() {
+        let changes = numbered(100);
+        let first = changes.sample(10, 7);
+        assert_eq!(first.len(), 10);
+        assert_eq!(first, changes.sample(10, 7));
+        assert_ne!(first, changes.sample(10, 8));
+
+        let values: Vec<u64> = first
+            .iter()
+            .map(|c| match c {
+                Change::Added { value, .. } => value.as_u64().unwrap(),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert!(values.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_sample_larger_than_changes_keeps_all() {
+        let changes = numbered(3);
+        assert_eq!(changes.sample(5, 1), changes);
+        assert!(changes.sample(0, 1).is_empty());
+    }
+}
@@ -0,0 +1,815 @@
+//! Schema-aware diffing
+//!
+//! A JSON Schema describing the compared documents guides the diff:
+//!
+//! - arrays whose items have an identity key are matched by that key instead
+//!   of by position, so reordering is not a change and an insertion does not
+//!   mark every later element as changed
+//! - every change is classified as breaking or compatible
+//! - every change is annotated with the location of its subschema
+//!
+//! The identity key of an array's items is given by the `x-rjd-key` keyword
+//! on the array schema or its `items` schema; without it, an `id` property
+//! that the items require is used. Only local `$ref`s (`#/...`) are followed.
+
+use crate::diff::{diff_with_cancellation, diff_with_options, CancellationToken, DiffOptions};
+use crate::json_path::{JsonPath, PathSegment};
+use crate::types::{type_name, Change, Changes, TruncationReason};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Keyword naming the property that identifies the items of an array
+pub const IDENTITY_KEYWORD: &str = "x-rjd-key";
+
+/// Maximum number of `$ref`s followed in a row, to stop reference cycles
+const MAX_REF_DEPTH: usize = 32;
+
+/// Whether a change can break consumers of the document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Compatibility {
+    Compatible,
+    /// The change breaks consumers, for the given reason
+    Breaking(String),
+}
+
+/// A JSON Schema describing the compared documents
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    root: Value,
+}
+
+impl Schema {
+    /// Create a schema from its JSON document
+    pub fn new(root: Value) -> Self {
+        Self { root }
+    }
+
+    /// The subschema describing the value at `path`
+    ///
+    /// Returns the subschema and its location as a URI fragment, e.g.
+    /// `#/properties/users/items`. Object members are looked up in
+    /// `properties` and then `additionalProperties`; array elements in
+    /// `prefixItems` and then `items`. `allOf`, `anyOf` and `oneOf` branches
+    /// are searched in order when the schema itself does not describe the
+    /// member.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rjd::schema::Schema;
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::new(json!({
+    ///     "properties": {"users": {"type": "array", "items": {"$ref": "#/$defs/user"}}},
+    ///     "$defs": {"user": {"properties": {"name": {"type": "string"}}}}
+    /// }));
+    /// let (location, subschema) = schema.resolve(&"users[0].name".parse().unwrap()).unwrap();
+    /// assert_eq!(location, "#/$defs/user/properties/name");
+    /// assert_eq!(subschema["type"], "string");
+    /// ```
+    pub fn resolve(&self, path: &JsonPath) -> Option<(String, &Value)> {
+        let mut current = ("#".to_string(), &self.root);
+        for segment in path.segments() {
+            let (location, schema) = self.dereference(current.0, current.1);
+            current = self.child(location, schema, segment)?;
+        }
+        Some(self.dereference(current.0, current.1))
+    }
+
+    /// The identity key of the items of the array at `path`, if it has one
+    pub fn identity_key(&self, path: &JsonPath) -> Option<&str> {
+        let (location, schema) = self.resolve(path)?;
+        if let Some(key) = schema.get(IDENTITY_KEYWORD).and_then(Value::as_str) {
+            return Some(key);
+        }
+        let items = schema.get("items").filter(|items| items.is_object())?;
+        let (_, items) = self.dereference(format!("{}/items", location), items);
+        if let Some(key) = items.get(IDENTITY_KEYWORD).and_then(Value::as_str) {
+            return Some(key);
+        }
+        required(items).any(|name| name == "id").then_some("id")
+    }
+
+    /// Classify a change as breaking or compatible
+    ///
+    /// A change is breaking if it removes a required property, changes the
+    /// type of a value, or leaves a value that does not have the type its
+    /// schema declares.
+    pub fn classify(&self, change: &Change) -> Compatibility {
+        let path = change.path();
+        match change {
+            Change::Removed { .. } => {
+                if let Some(PathSegment::Key(key)) = path.segments().last() {
+                    let parent =
+                        JsonPath::from_segments(path.segments()[..path.len() - 1].to_vec());
+                    if self.is_required(&parent, key) {
+                        return Compatibility::Breaking(format!(
+                            "required property '{}' removed",
+                            key
+                        ));
+                    }
+                }
+                Compatibility::Compatible
+            }
+            Change::Modified {
+                old_value,
+                new_value,
+                ..
+            } if type_name(old_value) != type_name(new_value) => Compatibility::Breaking(format!(
+                "type changed from {} to {}",
+                type_name(old_value),
+                type_name(new_value)
+            )),
+            Change::Added { value, .. }
+            | Change::Modified {
+                new_value: value, ..
+            } => match self
+                .resolve(path)
+                .and_then(|(_, schema)| schema.get("type"))
+            {
+                Some(expected) if !matches_type(value, expected) => Compatibility::Breaking(
+                    format!("value does not match the schema type {}", expected),
+                ),
+                _ => Compatibility::Compatible,
+            },
+        }
+    }
+
+    /// Fill in the defaults the schema declares for missing properties
+    ///
+    /// Every object gets each property it lacks whose subschema has a
+    /// `default`, taken from the object schema's `properties` and from those
+    /// of its `allOf` branches (`anyOf` and `oneOf` branches may not apply,
+    /// so their defaults are not used). Defaults are filled in recursively,
+    /// including inside inserted default values, so a document can be
+    /// compared by its effective configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rjd::schema::Schema;
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::new(json!({
+    ///     "properties": {
+    ///         "port": {"type": "integer", "default": 80},
+    ///         "tls": {"type": "object", "default": {}, "properties": {"enabled": {"default": false}}}
+    ///     }
+    /// }));
+    /// let filled = schema.fill_defaults(&json!({"port": 8080}));
+    /// assert_eq!(filled, json!({"port": 8080, "tls": {"enabled": false}}));
+    /// ```
+    pub fn fill_defaults(&self, value: &Value) -> Value {
+        let mut filled = value.clone();
+        self.fill("#".to_string(), &self.root, &mut filled);
+        filled
+    }
+
+    fn fill(&self, location: String, schema: &Value, value: &mut Value) {
+        let (location, schema) = self.dereference(location, schema);
+        match value {
+            Value::Object(map) => {
+                let branches = schema.get("allOf").and_then(Value::as_array);
+                let object_schemas = std::iter::once(schema).chain(
+                    branches
+                        .into_iter()
+                        .flatten()
+                        .map(|branch| self.dereference(location.clone(), branch).1),
+                );
+                for object_schema in object_schemas {
+                    let properties = object_schema.get("properties").and_then(Value::as_object);
+                    for (key, property) in properties.into_iter().flatten() {
+                        if map.contains_key(key) {
+                            continue;
+                        }
+                        let (_, property) = self.dereference(location.clone(), property);
+                        if let Some(default) = property.get("default") {
+                            map.insert(key.clone(), default.clone());
+                        }
+                    }
+                }
+                for (key, member) in map.iter_mut() {
+                    let segment = PathSegment::Key(key.clone());
+                    if let Some((child_location, child)) =
+                        self.child(location.clone(), schema, &segment)
+                    {
+                        self.fill(child_location, child, member);
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter_mut().enumerate() {
+                    let segment = PathSegment::Index(index);
+                    if let Some((child_location, child)) =
+                        self.child(location.clone(), schema, &segment)
+                    {
+                        self.fill(child_location, child, item);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether the object at `path` requires the property `key`
+    ///
+    /// Requirements of `allOf` branches count; those of `anyOf` and `oneOf`
+    /// branches do not, as another branch may allow the property's absence.
+    fn is_required(&self, path: &JsonPath, key: &str) -> bool {
+        let Some((location, schema)) = self.resolve(path) else {
+            return false;
+        };
+        let branches = schema.get("allOf").and_then(Value::as_array);
+        required(schema).any(|name| name == key)
+            || branches.into_iter().flatten().any(|branch| {
+                let (_, branch) = self.dereference(location.clone(), branch);
+                required(branch).any(|name| name == key)
+            })
+    }
+
+    /// Follow local `$ref`s from `schema`
+    fn dereference<'a>(
+        &'a self,
+        mut location: String,
+        mut schema: &'a Value,
+    ) -> (String, &'a Value) {
+        for _ in 0..MAX_REF_DEPTH {
+            let Some(reference) = schema.get("$ref").and_then(Value::as_str) else {
+                break;
+            };
+            let Some(target) = reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.root.pointer(pointer))
+            else {
+                break;
+            };
+            location = reference.to_string();
+            schema = target;
+        }
+        (location, schema)
+    }
+
+    /// The subschema of one member or element of a value described by `schema`
+    fn child<'a>(
+        &'a self,
+        location: String,
+        schema: &'a Value,
+        segment: &PathSegment,
+    ) -> Option<(String, &'a Value)> {
+        let direct = match segment {
+            PathSegment::Key(key) => schema
+                .get("properties")
+                .and_then(|properties| properties.get(key))
+                .map(|s| (format!("{}/properties/{}", location, escape(key)), s))
+                .or_else(|| {
+                    schema
+                        .get("additionalProperties")
+                        .filter(|s| s.is_object())
+                        .map(|s| (format!("{}/additionalProperties", location), s))
+                }),
+            PathSegment::Index(index) => schema
+                .get("prefixItems")
+                .and_then(|items| items.get(index))
+                .map(|s| (format!("{}/prefixItems/{}", location, index), s))
+                .or_else(|| match schema.get("items") {
+                    Some(Value::Array(items)) => items
+                        .get(*index)
+                        .map(|s| (format!("{}/items/{}", location, index), s)),
+                    Some(items @ Value::Object(_)) => Some((format!("{}/items", location), items)),
+                    _ => None,
+                }),
+        };
+        if direct.is_some() {
+            return direct;
+        }
+
+        for keyword in ["allOf", "anyOf", "oneOf"] {
+            let Some(branches) = schema.get(keyword).and_then(Value::as_array) else {
+                continue;
+            };
+            for (n, branch) in branches.iter().enumerate() {
+                let (branch_location, branch) =
+                    self.dereference(format!("{}/{}/{}", location, keyword, n), branch);
+                if let Some(found) = self.child(branch_location, branch, segment) {
+                    return Some(found);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Diff two documents, guided by a JSON Schema
+///
+/// Works like [`diff_with_options`], but matches the elements of arrays with
+/// an identity key (see [`Schema::identity_key`]) by that key: an element
+/// whose key is only in the first array is removed, one only in the second
+/// added, and elements with the same key are compared with each other,
+/// reported under their index in the second array. Removed elements are
+/// reported after the end of the second array. Arrays whose elements are
+/// not all objects with distinct keys are compared by position.
+///
+/// Each change is annotated (see [`Changes::annotations`]) with its
+/// `compatibility` (`"breaking"` or `"compatible"`), the `reason` for a
+/// breaking change and, if the schema describes it, the `schemaPath` of the
+/// changed value.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::schema::{diff_with_schema, Schema};
+/// use rjd::DiffOptions;
+/// use serde_json::json;
+///
+/// let schema = Schema::new(json!({
+///     "type": "array",
+///     "items": {"required": ["id"], "properties": {"id": {}, "port": {"type": "integer"}}}
+/// }));
+/// let old = json!([{"id": "a", "port": 80}, {"id": "b", "port": 81}]);
+/// let new = json!([{"id": "b", "port": 81}, {"id": "a", "port": "80"}]);
+/// let changes = diff_with_schema(&old, &new, &schema, &DiffOptions::default());
+///
+/// assert_eq!(changes.len(), 1);
+/// let change = &changes.modified[0];
+/// assert_eq!(change.path().to_string(), "[1].port");
+/// assert_eq!(changes.annotations[change.path()]["compatibility"], "breaking");
+/// assert_eq!(changes.annotations[change.path()]["schemaPath"], "#/items/properties/port");
+/// ```
+pub fn diff_with_schema(
+    old: &Value,
+    new: &Value,
+    schema: &Schema,
+    options: &DiffOptions,
+) -> Changes {
+    schema_diff(old, new, schema, options, None)
+}
+
+/// Schema-aware diff that stops early once `token` is cancelled
+///
+/// This is [`diff_with_schema`] as
+/// [`diff_with_cancellation`](crate::diff_with_cancellation) is to
+/// [`diff_with_options`](crate::diff_with_options): a cancelled diff returns
+/// the changes found so far, marked with a
+/// [`TruncationReason::Cancelled`] truncation.
+pub fn diff_with_schema_cancellation(
+    old: &Value,
+    new: &Value,
+    schema: &Schema,
+    options: &DiffOptions,
+    token: &CancellationToken,
+) -> Changes {
+    schema_diff(old, new, schema, options, Some(token))
+}
+
+fn schema_diff(
+    old: &Value,
+    new: &Value,
+    schema: &Schema,
+    options: &DiffOptions,
+    cancel: Option<&CancellationToken>,
+) -> Changes {
+    let mut changes = diff_at(schema, old, new, &JsonPath::new(), options, cancel);
+
+    let mut annotations = HashMap::new();
+    for change in changes.iter() {
+        let mut fields = Map::new();
+        if let Some((location, _)) = schema.resolve(change.path()) {
+            fields.insert("schemaPath".to_string(), Value::String(location));
+        }
+        match schema.classify(change) {
+            Compatibility::Compatible => {
+                fields.insert("compatibility".to_string(), "compatible".into());
+            }
+            Compatibility::Breaking(reason) => {
+                fields.insert("compatibility".to_string(), "breaking".into());
+                fields.insert("reason".to_string(), Value::String(reason));
+            }
+        }
+        annotations.insert(change.path().clone(), fields);
+    }
+    changes.annotations = annotations;
+    changes
+}
+
+/// Diff two values found at `base`, with paths relative to the whole document
+fn diff_at(
+    schema: &Schema,
+    old: &Value,
+    new: &Value,
+    base: &JsonPath,
+    options: &DiffOptions,
+    cancel: Option<&CancellationToken>,
+) -> Changes {
+    let mut changes = match cancel {
+        Some(token) => diff_with_cancellation(old, new, options, token),
+        None => diff_with_options(old, new, options),
+    };
+    if !base.is_empty() {
+        prefix_paths(&mut changes, base);
+    }
+
+    let mut keyed = Vec::new();
+    find_keyed_arrays(schema, old, new, base.clone(), &mut keyed);
+    for (path, key, old_items, new_items) in keyed {
+        drop_below(&mut changes, &path);
+
+        let old_index: HashMap<String, usize> = old_items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (item[key].to_string(), i))
+            .collect();
+        let mut matched = vec![false; old_items.len()];
+        let mut pairs = Vec::new();
+        for (j, item) in new_items.iter().enumerate() {
+            let element = child(&path, PathSegment::Index(j));
+            match old_index.get(&item[key].to_string()) {
+                Some(&i) => {
+                    matched[i] = true;
+                    pairs.push((i, j));
+                    let nested = diff_at(schema, &old_items[i], item, &element, options, cancel);
+                    for change in nested.iter() {
+                        changes.push(change.clone());
+                    }
+                    for truncation in nested.truncated {
+                        if truncation.reason != TruncationReason::Cancelled
+                            || !changes.is_cancelled()
+                        {
+                            changes.truncated.push(truncation);
+                        }
+                    }
+                    changes.warnings.extend(nested.warnings);
+                    changes.paired_arrays.extend(nested.paired_arrays);
+                }
+                None => changes.push(Change::Added {
+                    path: element,
+                    value: item.clone(),
+                }),
+            }
+        }
+        let unmatched = old_items.iter().enumerate().filter(|&(i, _)| !matched[i]);
+        for (k, (_, item)) in unmatched.enumerate() {
+            changes.push(Change::Removed {
+                path: child(&path, PathSegment::Index(new_items.len() + k)),
+                value: item.clone(),
+            });
+        }
+        if old_items != new_items {
+            changes.paired_arrays.insert(path, pairs);
+        }
+    }
+    changes
+}
+
+/// An array matched by key: its path, the key and the two arrays' items
+type KeyedArray<'a> = (JsonPath, &'a str, &'a [Value], &'a [Value]);
+
+/// Find the outermost arrays at the same place in both values that can be
+/// matched by an identity key
+fn find_keyed_arrays<'a>(
+    schema: &'a Schema,
+    old: &'a Value,
+    new: &'a Value,
+    path: JsonPath,
+    found: &mut Vec<KeyedArray<'a>>,
+) {
+    match (old, new) {
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            if let Some(key) = schema.identity_key(&path) {
+                if has_distinct_keys(old_items, key) && has_distinct_keys(new_items, key) {
+                    found.push((path, key, old_items, new_items));
+                    return;
+                }
+            }
+            for (i, (old_item, new_item)) in old_items.iter().zip(new_items).enumerate() {
+                find_keyed_arrays(
+                    schema,
+                    old_item,
+                    new_item,
+                    child(&path, PathSegment::Index(i)),
+                    found,
+                );
+            }
+        }
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            for (key, new_value) in new_map {
+                if let Some(old_value) = old_map.get(key) {
+                    let member = child(&path, PathSegment::Key(key.clone()));
+                    find_keyed_arrays(schema, old_value, new_value, member, found);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether every item is an object with `key`, and no two share its value
+fn has_distinct_keys(items: &[Value], key: &str) -> bool {
+    let mut seen = std::collections::HashSet::new();
+    items.iter().all(|item| {
+        item.get(key)
+            .is_some_and(|value| seen.insert(value.to_string()))
+    })
+}
+
+/// Prefix every path in `changes` with `base`
+fn prefix_paths(changes: &mut Changes, base: &JsonPath) {
+    let join = |path: &JsonPath| {
+        JsonPath::from_segments(
+            base.segments()
+                .iter()
+                .chain(path.segments())
+                .cloned()
+                .collect(),
+        )
+    };
+    for list in [
+        &mut changes.added,
+        &mut changes.removed,
+        &mut changes.modified,
+    ] {
+        for change in list.iter_mut() {
+            match change {
+                Change::Added { path, .. }
+                | Change::Removed { path, .. }
+                | Change::Modified { path, .. } => *path = join(path),
+            }
+        }
+    }
+    for truncation in &mut changes.truncated {
+        truncation.path = join(&truncation.path);
+    }
+    for warning in &mut changes.warnings {
+        warning.path = join(&warning.path);
+    }
+    changes.paired_arrays = std::mem::take(&mut changes.paired_arrays)
+        .into_iter()
+        .map(|(path, pairs)| (join(&path), pairs))
+        .collect();
+}
+
+/// Remove everything reported at or below `path`
+fn drop_below(changes: &mut Changes, path: &JsonPath) {
+    for list in [
+        &mut changes.added,
+        &mut changes.removed,
+        &mut changes.modified,
+    ] {
+        list.retain(|change| !change.path().matches_prefix(path));
+    }
+    changes.truncated.retain(|t| !t.path.matches_prefix(path));
+    changes.warnings.retain(|w| !w.path.matches_prefix(path));
+    changes
+        .paired_arrays
+        .retain(|array, _| !array.matches_prefix(path));
+}
+
+fn child(path: &JsonPath, segment: PathSegment) -> JsonPath {
+    let mut child = path.clone();
+    child.push(segment);
+    child
+}
+
+/// The names listed in a schema's `required` keyword
+fn required(schema: &Value) -> impl Iterator<Item = &str> {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+}
+
+/// Escape a key for use in a JSON Pointer
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Whether a value has the type (or one of the types) a schema declares
+fn matches_type(value: &Value, expected: &Value) -> bool {
+    let matches = |name: &str| match name {
+        "integer" => value
+            .as_f64()
+            .is_some_and(|n| value.is_i64() || value.is_u64() || n.fract() == 0.0),
+        name => type_name(value) == name,
+    };
+    match expected {
+        Value::String(name) => matches(name),
+        Value::Array(names) => names.iter().filter_map(Value::as_str).any(matches),
+        // Not a valid type declaration; do not judge the value by it
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    fn path(text: &str) -> JsonPath {
+        text.parse().unwrap()
+    }
+
+    fn schema() -> Schema {
+        Schema::new(json!({
+            "type": "object",
+            "required": ["name", "services"],
+            "properties": {
+                "name": {"type": "string"},
+                "services": {
+                    "type": "array",
+                    "x-rjd-key": "name",
+                    "items": {"$ref": "#/$defs/service"}
+                },
+                "tags": {"type": "array", "items": {"type": "string"}}
+            },
+            "additionalProperties": {"type": "number"},
+            "$defs": {
+                "service": {
+                    "type": "object",
+                    "required": ["name", "port"],
+                    "properties": {
+                        "name": {"type": "string"},
+                        "port": {"type": "integer"},
+                        "hosts": {
+                            "type": "array",
+                            "items": {"required": ["id"], "properties": {"id": {}}}
+                        }
+                    }
+                }
+            }
+        }))
+    }
+
+    #[test]
+    fn test_fill_defaults() {
+        let schema = Schema::new(json!({
+            "allOf": [{"properties": {"region": {"default": "eu"}}}],
+            "anyOf": [{"properties": {"mode": {"default": "fast"}}}],
+            "properties": {
+                "port": {"default": 80},
+                "servers": {"type": "array", "items": {"$ref": "#/$defs/server"}}
+            },
+            "$defs": {
+                "server": {"properties": {"weight": {"default": 1}, "host": {"type": "string"}}}
+            }
+        }));
+        let filled = schema.fill_defaults(&json!({
+            "port": 8080,
+            "servers": [{"host": "a"}, {"host": "b", "weight": 5}]
+        }));
+        assert_eq!(
+            filled,
+            json!({
+                "port": 8080,
+                "servers": [{"host": "a", "weight": 1}, {"host": "b", "weight": 5}],
+                "region": "eu"
+            })
+        );
+
+        // Documents with every default filled in have no differences
+        let old = json!({"servers": [{"host": "a", "weight": 1}]});
+        let new = json!({"port": 80, "servers": [{"host": "a"}]});
+        assert!(diff(&schema.fill_defaults(&old), &schema.fill_defaults(&new)).is_empty());
+        assert_eq!(schema.fill_defaults(&json!("text")), json!("text"));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let schema = schema();
+        let location = |text: &str| schema.resolve(&path(text)).map(|(location, _)| location);
+
+        assert_eq!(location("name").as_deref(), Some("#/properties/name"));
+        assert_eq!(location("services[3]").as_deref(), Some("#/$defs/service"));
+        assert_eq!(
+            location("services[0].port").as_deref(),
+            Some("#/$defs/service/properties/port")
+        );
+        assert_eq!(location("extra").as_deref(), Some("#/additionalProperties"));
+        assert_eq!(location("tags[0].x"), None);
+        assert_eq!(
+            schema
+                .resolve(&JsonPath::new())
+                .map(|(location, _)| location)
+                .as_deref(),
+            Some("#")
+        );
+    }
+
+    #[test]
+    fn test_resolve_through_composition() {
+        let schema = Schema::new(json!({
+            "allOf": [{"properties": {"a": {"type": "string"}}}, {"$ref": "#/$defs/b"}],
+            "$defs": {"b": {"properties": {"b": {"prefixItems": [{"type": "null"}]}}}}
+        }));
+        let location = |text: &str| schema.resolve(&path(text)).map(|(location, _)| location);
+        assert_eq!(location("a").as_deref(), Some("#/allOf/0/properties/a"));
+        assert_eq!(
+            location("b[0]").as_deref(),
+            Some("#/$defs/b/properties/b/prefixItems/0")
+        );
+    }
+
+    #[test]
+    fn test_reference_cycle_terminates() {
+        let schema =
+            Schema::new(json!({"$defs": {"a": {"$ref": "#/$defs/a"}}, "$ref": "#/$defs/a"}));
+        assert!(schema.resolve(&path("x")).is_none());
+    }
+
+    #[test]
+    fn test_identity_keys() {
+        let schema = schema();
+        assert_eq!(schema.identity_key(&path("services")), Some("name"));
+        assert_eq!(schema.identity_key(&path("services[0].hosts")), Some("id"));
+        assert_eq!(schema.identity_key(&path("tags")), None);
+    }
+
+    #[test]
+    fn test_classify() {
+        let schema = schema();
+        let removed = Change::Removed {
+            path: path("services[0].port"),
+            value: json!(80),
+        };
+        assert_eq!(
+            schema.classify(&removed),
+            Compatibility::Breaking("required property 'port' removed".to_string())
+        );
+        let optional = Change::Removed {
+            path: path("tags"),
+            value: json!([]),
+        };
+        assert_eq!(schema.classify(&optional), Compatibility::Compatible);
+
+        let retyped = Change::Modified {
+            path: path("extra"),
+            old_value: json!(1),
+            new_value: json!("1"),
+        };
+        assert_eq!(
+            schema.classify(&retyped),
+            Compatibility::Breaking("type changed from number to string".to_string())
+        );
+        let invalid = Change::Added {
+            path: path("services[0].port"),
+            value: json!(80.5),
+        };
+        assert!(matches!(
+            schema.classify(&invalid),
+            Compatibility::Breaking(_)
+        ));
+        let valid = Change::Modified {
+            path: path("services[0].port"),
+            old_value: json!(80),
+            new_value: json!(8080),
+        };
+        assert_eq!(schema.classify(&valid), Compatibility::Compatible);
+    }
+
+    #[test]
+    fn test_diff_matches_array_items_by_key() {
+        let old = json!({
+            "name": "app",
+            "services": [
+                {"name": "web", "port": 80, "hosts": [{"id": 1, "ip": "a"}, {"id": 2, "ip": "b"}]},
+                {"name": "db", "port": 5432},
+                {"name": "cache", "port": 6379}
+            ]
+        });
+        let new = json!({
+            "name": "app",
+            "services": [
+                {"name": "queue", "port": 5672},
+                {"name": "db", "port": 5433},
+                {"name": "web", "port": 80, "hosts": [{"id": 2, "ip": "c"}, {"id": 1, "ip": "a"}]}
+            ]
+        });
+        let changes = diff_with_schema(&old, &new, &schema(), &DiffOptions::default());
+
+        let paths = |list: &[Change]| -> Vec<String> {
+            list.iter().map(|c| c.path().to_string()).collect()
+        };
+        assert_eq!(paths(&changes.added), ["services[0]"]);
+        assert_eq!(paths(&changes.removed), ["services[3]"]);
+        assert_eq!(
+            paths(&changes.modified),
+            ["services[1].port", "services[2].hosts[0].ip"]
+        );
+        assert_eq!(changes.after.as_ref(), Some(&new));
+
+        let removed = &changes.annotations[&path("services[3]")];
+        assert_eq!(removed["compatibility"], "compatible");
+        assert_eq!(removed["schemaPath"], "#/$defs/service");
+    }
+
+    #[test]
+    fn test_diff_falls_back_to_positions() {
+        // Duplicate keys cannot be matched
+        let old = json!({"services": [{"name": "a", "port": 1}, {"name": "a", "port": 2}]});
+        let new = json!({"services": [{"name": "a", "port": 2}, {"name": "a", "port": 2}]});
+        let changes = diff_with_schema(&old, &new, &schema(), &DiffOptions::default());
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes.modified[0].path().to_string(), "services[0].port");
+    }
+}
@@ -0,0 +1,271 @@
+//! Profiling the size and shape of a document
+
+use crate::diff::{traverse, ValueVisitor};
+use crate::json_path::JsonPath;
+use crate::path::{join_array_path, join_path};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Size and shape of a JSON document, from [`profile`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Profile {
+    /// Number of values, containers included
+    pub values: usize,
+    /// Number of object members
+    pub keys: usize,
+    /// Number of values at each depth (the root is at depth 0)
+    pub depths: Vec<usize>,
+    /// Number of values of each JSON type
+    pub types: BTreeMap<&'static str, usize>,
+    /// The object with the most members, and how many it has
+    pub widest_object: Option<(JsonPath, usize)>,
+    /// The longest array, and its length
+    pub longest_array: Option<(JsonPath, usize)>,
+    /// The containers below the root holding the most values, largest first,
+    /// with the number of values in each
+    pub largest: Vec<(JsonPath, usize)>,
+}
+
+impl Profile {
+    /// Depth of the deepest value
+    pub fn max_depth(&self) -> usize {
+        self.depths.len().saturating_sub(1)
+    }
+}
+
+/// Profile a document, listing its `top` largest subtrees
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::profile;
+/// use serde_json::json;
+///
+/// let profile = profile(&json!({"users": [{"id": 1}, {"id": 2}], "n": null}), 1);
+///
+/// assert_eq!(profile.values, 7);
+/// assert_eq!(profile.keys, 4);
+/// assert_eq!(profile.depths, [1, 2, 2, 2]);
+/// assert_eq!(profile.types["object"], 3);
+/// assert_eq!(profile.largest, [("users".parse().unwrap(), 5)]);
+/// ```
+pub fn profile(value: &Value, top: usize) -> Profile {
+    let mut visitor = ProfileVisitor {
+        profile: Profile::default(),
+        top,
+    };
+    traverse(None, Some(value), &JsonPath::new(), &mut visitor);
+
+    let mut profile = visitor.profile;
+    sort_largest(&mut profile.largest, top);
+    profile
+}
+
+/// Approximate number of bytes a parsed document occupies in memory
+///
+/// Counts each value, the text of strings, numbers and keys, and the
+/// storage of arrays and objects. Allocator overhead is not included, so
+/// the real footprint is somewhat larger.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::estimated_memory;
+/// use serde_json::json;
+///
+/// let small = estimated_memory(&json!({"a": 1}));
+/// let large = estimated_memory(&json!({"a": 1, "b": "x".repeat(1000)}));
+/// assert!(large > small + 1000);
+/// ```
+pub fn estimated_memory(value: &Value) -> usize {
+    size_of::<Value>() + heap_memory(value)
+}
+
+/// Bytes a value owns beyond its own `Value`
+fn heap_memory(value: &Value) -> usize {
+    match value {
+        Value::Null | Value::Bool(_) => 0,
+        // Numbers hold their text only with arbitrary precision
+        #[cfg(feature = "arbitrary-precision")]
+        Value::Number(number) => number.as_str().len(),
+        #[cfg(not(feature = "arbitrary-precision"))]
+        Value::Number(_) => 0,
+        Value::String(text) => text.capacity(),
+        Value::Array(items) => {
+            items.capacity() * size_of::<Value>() + items.iter().map(heap_memory).sum::<usize>()
+        }
+        Value::Object(map) => {
+            // Each entry stores its key, value and hash, plus an index slot
+            let entry = size_of::<String>() + size_of::<Value>() + 2 * size_of::<usize>();
+            map.iter()
+                .map(|(key, item)| entry + key.capacity() + heap_memory(item))
+                .sum()
+        }
+    }
+}
+
+/// Keep the `top` largest subtrees, largest first (ties in document order)
+fn sort_largest(largest: &mut Vec<(JsonPath, usize)>, top: usize) {
+    largest.sort_by(|(_, a), (_, b)| b.cmp(a));
+    largest.truncate(top);
+}
+
+/// Visitor recording each value of a single document (the "new" side)
+struct ProfileVisitor {
+    profile: Profile,
+    top: usize,
+}
+
+impl ProfileVisitor {
+    fn record(&mut self, path: &JsonPath, type_name: &'static str) {
+        let profile = &mut self.profile;
+        profile.values += 1;
+        if profile.depths.len() <= path.len() {
+            profile.depths.resize(path.len() + 1, 0);
+        }
+        profile.depths[path.len()] += 1;
+        *profile.types.entry(type_name).or_default() += 1;
+    }
+
+    /// Record a container after its children, with the values it holds
+    fn record_subtree(&mut self, path: &JsonPath, values_before: usize) {
+        if path.is_empty() || self.top == 0 {
+            return;
+        }
+        let size = self.profile.values - values_before;
+        self.profile.largest.push((path.clone(), size));
+        // Bound memory on huge documents; the final sort picks the top ones
+        if self.profile.largest.len() > self.top.saturating_mul(2).max(64) {
+            sort_largest(&mut self.profile.largest, self.top);
+        }
+    }
+}
+
+/// Replace `current` when `len` is greater than its recorded size
+fn keep_max(current: &mut Option<(JsonPath, usize)>, path: &JsonPath, len: usize) {
+    if current.as_ref().is_none_or(|(_, max)| len > *max) {
+        *current = Some((path.clone(), len));
+    }
+}
+
+impl ValueVisitor for ProfileVisitor {
+    type Output = ();
+
+    fn visit_null(&mut self, path: &JsonPath, _: Option<&Value>, new: Option<&Value>) {
+        if new.is_some() {
+            self.record(path, "null");
+        }
+    }
+
+    fn visit_bool(&mut self, path: &JsonPath, _: Option<&bool>, new: Option<&bool>) {
+        if new.is_some() {
+            self.record(path, "boolean");
+        }
+    }
+
+    fn visit_number(&mut self, path: &JsonPath, _: Option<&Value>, new: Option<&Value>) {
+        if new.is_some() {
+            self.record(path, "number");
+        }
+    }
+
+    fn visit_string(&mut self, path: &JsonPath, _: Option<&String>, new: Option<&String>) {
+        if new.is_some() {
+            self.record(path, "string");
+        }
+    }
+
+    fn visit_array(&mut self, path: &JsonPath, _: Option<&Vec<Value>>, new: Option<&Vec<Value>>) {
+        let Some(items) = new else { return };
+        let before = self.profile.values;
+        self.record(path, "array");
+        keep_max(&mut self.profile.longest_array, path, items.len());
+        for (i, item) in items.iter().enumerate() {
+            traverse(None, Some(item), &join_array_path(path, i), self);
+        }
+        self.record_subtree(path, before);
+    }
+
+    fn visit_object(
+        &mut self,
+        path: &JsonPath,
+        _: Option<&Map<String, Value>>,
+        new: Option<&Map<String, Value>>,
+    ) {
+        let Some(map) = new else { return };
+        let before = self.profile.values;
+        self.record(path, "object");
+        self.profile.keys += map.len();
+        keep_max(&mut self.profile.widest_object, path, map.len());
+        for (key, item) in map {
+            traverse(None, Some(item), &join_path(path, key), self);
+        }
+        self.record_subtree(path, before);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_profile_shape() {
+        let doc = json!({
+            "a": {"x": 1, "y": 2, "z": 3},
+            "b": [1, [true, false, null, "s"]],
+            "c": "text"
+        });
+        let profile = profile(&doc, 2);
+
+        assert_eq!(profile.values, 13);
+        assert_eq!(profile.keys, 6);
+        assert_eq!(profile.depths, [1, 3, 5, 4]);
+        assert_eq!(profile.max_depth(), 3);
+        assert_eq!(
+            profile.types.into_iter().collect::<Vec<_>>(),
+            [
+                ("array", 2),
+                ("boolean", 2),
+                ("null", 1),
+                ("number", 4),
+                ("object", 2),
+                ("string", 2)
+            ]
+        );
+        assert_eq!(profile.widest_object, Some((JsonPath::new(), 3)));
+        assert_eq!(profile.longest_array, Some(("b[1]".parse().unwrap(), 4)));
+        assert_eq!(
+            profile.largest,
+            [("b".parse().unwrap(), 7), ("b[1]".parse().unwrap(), 5)]
+        );
+    }
+
+    #[test]
+    fn test_estimated_memory() {
+        let value = size_of::<Value>();
+        assert_eq!(estimated_memory(&json!(null)), value);
+        assert_eq!(estimated_memory(&json!("abc")), value + 3);
+        assert_eq!(estimated_memory(&json!([true, false])), 3 * value);
+
+        // Memory grows with the document
+        let doc = |n: usize| json!((0..n).map(|i| json!({"id": i})).collect::<Vec<_>>());
+        let (small, large) = (estimated_memory(&doc(10)), estimated_memory(&doc(1000)));
+        assert!(large > 90 * small);
+    }
+
+    #[test]
+    fn test_profile_scalars_and_many_subtrees() {
+        let profile = profile(&json!(5), 3);
+        assert_eq!((profile.values, profile.max_depth()), (1, 0));
+        assert!(profile.largest.is_empty());
+        assert!(profile.widest_object.is_none());
+
+        let items: Vec<Value> = (0..200).map(|i| json!(vec![0; i % 7])).collect();
+        let largest = super::profile(&Value::Array(items), 2).largest;
+        assert_eq!(
+            largest,
+            [("[6]".parse().unwrap(), 7), ("[13]".parse().unwrap(), 7)]
+        );
+    }
+}
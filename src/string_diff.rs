@@ -0,0 +1,328 @@
+//! Differences inside modified string values
+//!
+//! [`diff_strings`] splits two strings into words or characters and lists
+//! the runs kept, deleted and inserted, so a long modified string can be
+//! shown with only the changed part highlighted.
+
+use crate::types::{Change, Changes};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+/// Comparisons above this many token pairs fall back to replacing the whole
+/// differing middle of the strings
+const MAX_TABLE_CELLS: usize = 1_000_000;
+
+/// The unit strings are compared in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Granularity {
+    /// Runs of letters and digits, runs of whitespace and single other characters
+    #[default]
+    Word,
+    /// Single characters
+    Char,
+}
+
+impl fmt::Display for Granularity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Granularity::Word => write!(f, "word"),
+            Granularity::Char => write!(f, "char"),
+        }
+    }
+}
+
+impl FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "word" => Ok(Granularity::Word),
+            "char" => Ok(Granularity::Char),
+            _ => Err(format!(
+                "unknown granularity '{}' (expected word or char)",
+                text
+            )),
+        }
+    }
+}
+
+/// A run of text kept, deleted from the old string or inserted into the new one
+///
+/// Serializes as `{"op": "equal" | "delete" | "insert", "text": ...}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "op", content = "text", rename_all = "lowercase")]
+pub enum Edit {
+    Equal(String),
+    Delete(String),
+    Insert(String),
+}
+
+/// The edits turning `old` into `new`
+///
+/// Joining the `Equal` and `Delete` texts gives `old`; joining the `Equal`
+/// and `Insert` texts gives `new`. Adjacent edits of the same kind are merged.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::string_diff::{diff_strings, Edit, Granularity};
+///
+/// let edits = diff_strings("the quick fox", "the slow fox", Granularity::Word);
+/// assert_eq!(
+///     edits,
+///     [
+///         Edit::Equal("the ".to_string()),
+///         Edit::Delete("quick".to_string()),
+///         Edit::Insert("slow".to_string()),
+///         Edit::Equal(" fox".to_string()),
+///     ]
+/// );
+/// ```
+pub fn diff_strings(old: &str, new: &str, granularity: Granularity) -> Vec<Edit> {
+    let old = tokenize(old, granularity);
+    let new = tokenize(new, granularity);
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut edits = Edits::default();
+    for token in &old[..prefix] {
+        edits.push(Edit::Equal(token.to_string()));
+    }
+    middle_edits(
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+        &mut edits,
+    );
+    for token in &old[old.len() - suffix..] {
+        edits.push(Edit::Equal(token.to_string()));
+    }
+    edits.0
+}
+
+/// Annotate every modified string with its `stringDiff` edits
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::string_diff::{annotate_string_diffs, Granularity};
+/// use rjd::diff;
+/// use serde_json::json;
+///
+/// let mut changes = diff(&json!({"s": "abc"}), &json!({"s": "abd"}));
+/// annotate_string_diffs(&mut changes, Granularity::Char);
+///
+/// assert_eq!(
+///     changes.annotations[changes.modified[0].path()]["stringDiff"],
+///     json!([{"op": "equal", "text": "ab"}, {"op": "delete", "text": "c"}, {"op": "insert", "text": "d"}])
+/// );
+/// ```
+pub fn annotate_string_diffs(changes: &mut Changes, granularity: Granularity) {
+    let diffs: Vec<_> = changes
+        .modified
+        .iter()
+        .filter_map(|change| match change {
+            Change::Modified {
+                path,
+                old_value: Value::String(old),
+                new_value: Value::String(new),
+            } => Some((path.clone(), diff_strings(old, new, granularity))),
+            _ => None,
+        })
+        .collect();
+    for (path, edits) in diffs {
+        let edits = serde_json::to_value(edits).unwrap_or_default();
+        changes
+            .annotations
+            .entry(path)
+            .or_default()
+            .insert("stringDiff".to_string(), edits);
+    }
+}
+
+/// Split text into tokens that join back into it
+fn tokenize(text: &str, granularity: Granularity) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        if granularity == Granularity::Word && (c.is_alphanumeric() || c.is_whitespace()) {
+            let word = c.is_alphanumeric();
+            while let Some(&(_, next)) = chars.peek() {
+                if next.is_alphanumeric() != word || next.is_whitespace() == word {
+                    break;
+                }
+                chars.next();
+            }
+        }
+        let end = chars.peek().map_or(text.len(), |&(i, _)| i);
+        tokens.push(&text[start..end]);
+        start = end;
+    }
+    tokens
+}
+
+/// Edits for the differing middle of the strings, from a longest common subsequence
+fn middle_edits(old: &[&str], new: &[&str], edits: &mut Edits) {
+    if old.len().saturating_mul(new.len()) > MAX_TABLE_CELLS {
+        edits.push(Edit::Delete(old.concat()));
+        edits.push(Edit::Insert(new.concat()));
+        return;
+    }
+
+    // lengths[i][j]: length of the common subsequence of old[i..] and new[j..]
+    let width = new.len() + 1;
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            edits.push(Edit::Equal(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            edits.push(Edit::Delete(old[i].to_string()));
+            i += 1;
+        } else {
+            edits.push(Edit::Insert(new[j].to_string()));
+            j += 1;
+        }
+    }
+    for token in &old[i..] {
+        edits.push(Edit::Delete(token.to_string()));
+    }
+    for token in &new[j..] {
+        edits.push(Edit::Insert(token.to_string()));
+    }
+}
+
+/// Edits with adjacent runs of the same kind merged
+#[derive(Default)]
+struct Edits(Vec<Edit>);
+
+impl Edits {
+    fn push(&mut self, edit: Edit) {
+        let (Edit::Equal(text) | Edit::Delete(text) | Edit::Insert(text)) = &edit;
+        if text.is_empty() {
+            return;
+        }
+        match (self.0.last_mut(), edit) {
+            (Some(Edit::Equal(last)), Edit::Equal(text))
+            | (Some(Edit::Delete(last)), Edit::Delete(text))
+            | (Some(Edit::Insert(last)), Edit::Insert(text)) => last.push_str(&text),
+            (_, edit) => self.0.push(edit),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    fn join(edits: &[Edit], keep: fn(&Edit) -> Option<&String>) -> String {
+        edits.iter().filter_map(keep).map(String::as_str).collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("hello,  wörld 42!", Granularity::Word),
+            ["hello", ",", "  ", "wörld", " ", "42", "!"]
+        );
+        assert_eq!(tokenize("añb", Granularity::Char), ["a", "ñ", "b"]);
+        assert!(tokenize("", Granularity::Word).is_empty());
+    }
+
+    #[test]
+    fn test_edits_rebuild_both_strings() {
+        let old = "The quick brown fox jumps over the lazy dog";
+        let new = "A quick red fox jumped over the dog!";
+        for granularity in [Granularity::Word, Granularity::Char] {
+            let edits = diff_strings(old, new, granularity);
+            let old_text = join(&edits, |e| match e {
+                Edit::Equal(t) | Edit::Delete(t) => Some(t),
+                Edit::Insert(_) => None,
+            });
+            let new_text = join(&edits, |e| match e {
+                Edit::Equal(t) | Edit::Insert(t) => Some(t),
+                Edit::Delete(_) => None,
+            });
+            assert_eq!((old_text.as_str(), new_text.as_str()), (old, new));
+        }
+    }
+
+    #[test]
+    fn test_char_diff_and_edge_cases() {
+        assert_eq!(
+            diff_strings("kitten", "sitting", Granularity::Char),
+            [
+                Edit::Delete("k".to_string()),
+                Edit::Insert("s".to_string()),
+                Edit::Equal("itt".to_string()),
+                Edit::Delete("e".to_string()),
+                Edit::Insert("i".to_string()),
+                Edit::Equal("n".to_string()),
+                Edit::Insert("g".to_string()),
+            ]
+        );
+        assert_eq!(
+            diff_strings("", "new", Granularity::Word),
+            [Edit::Insert("new".to_string())]
+        );
+        assert!(diff_strings("", "", Granularity::Char).is_empty());
+    }
+
+    #[test]
+    fn test_large_strings_replace_the_middle() {
+        let old = format!("start {}end", "a ".repeat(2000));
+        let new = format!("start {}end", "b ".repeat(2000));
+        let edits = diff_strings(&old, &new, Granularity::Word);
+        assert_eq!(edits.len(), 4);
+        assert_eq!(edits[0], Edit::Equal("start ".to_string()));
+        assert_eq!(edits[3], Edit::Equal(" end".to_string()));
+    }
+
+    #[test]
+    fn test_annotate_only_string_modifications() {
+        let mut changes = diff(
+            &json!({"s": "a b", "n": 1, "t": "x"}),
+            &json!({"s": "a c", "n": 2, "t": 3}),
+        );
+        annotate_string_diffs(&mut changes, Granularity::Word);
+
+        assert_eq!(changes.annotations.len(), 1);
+        assert_eq!(
+            changes.annotations[&"s".parse().unwrap()]["stringDiff"],
+            json!([
+                {"op": "equal", "text": "a "},
+                {"op": "delete", "text": "b"},
+                {"op": "insert", "text": "c"}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_granularity_parsing() {
+        assert_eq!("char".parse::<Granularity>(), Ok(Granularity::Char));
+        assert!("line".parse::<Granularity>().is_err());
+        assert_eq!(Granularity::Word.to_string(), "word");
+    }
+}
@@ -0,0 +1,218 @@
+//! Assertion macros for tests in downstream crates (`test-helpers` feature)
+//!
+//! [`assert_no_diff!`](crate::assert_no_diff) and
+//! [`assert_json_matches!`](crate::assert_json_matches) compare any two
+//! `Serialize` values by their JSON form and panic with a report of the
+//! changes, colored when stderr is a terminal (see
+//! [`should_colorize`](crate::color::should_colorize)). Both accept
+//! `ignore = [...]` patterns in the syntax of `--ignore`, for fields such as
+//! timestamps and generated IDs.
+//!
+//! ```rust
+//! use rjd::{assert_json_matches, assert_no_diff};
+//! use serde_json::json;
+//!
+//! let response = json!({"id": 7, "name": "api", "createdAt": "2024-05-01T10:00:00Z"});
+//!
+//! assert_no_diff!(
+//!     response,
+//!     json!({"id": 7, "name": "api", "createdAt": "today"}),
+//!     ignore = ["/createdAt"]
+//! );
+//! // Members the expected value leaves out are not checked
+//! assert_json_matches!(response, json!({"name": "api"}));
+//! ```
+
+use crate::color::{should_colorize, ColorChoice};
+use crate::{diff_serialize, validate_pattern, Change, Changes};
+use serde::Serialize;
+use std::io::IsTerminal;
+
+const ADDED: &str = "\x1b[32m";
+const REMOVED: &str = "\x1b[31m";
+const MODIFIED: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// Compare two values for the assertion macros, returning the panic message
+/// if they differ
+///
+/// Changes at `ignore` patterns are dropped. With `allow_additions`, members
+/// and elements only `actual` has are allowed too.
+#[doc(hidden)]
+pub fn mismatch<T, U>(
+    actual: &T,
+    expected: &U,
+    ignore: &[&str],
+    allow_additions: bool,
+) -> Option<String>
+where
+    T: Serialize + ?Sized,
+    U: Serialize + ?Sized,
+{
+    let patterns: Vec<String> = ignore.iter().map(|pattern| pattern.to_string()).collect();
+    for pattern in &patterns {
+        if let Err(error) = validate_pattern(pattern) {
+            return Some(format!("invalid ignore pattern: {}", error));
+        }
+    }
+    let mut changes = match diff_serialize(expected, actual) {
+        Ok(changes) => changes,
+        Err(error) => return Some(error.to_string()),
+    };
+    if !patterns.is_empty() {
+        changes = changes.filter_ignore_patterns(&patterns);
+    }
+    if allow_additions {
+        changes.added.clear();
+    }
+    if changes.is_empty() {
+        return None;
+    }
+    let color = should_colorize(ColorChoice::Auto, std::io::stderr().is_terminal(), |name| {
+        std::env::var(name).ok()
+    });
+    Some(report(&changes, color))
+}
+
+/// List the changes from the expected value to the actual one
+fn report(changes: &Changes, color: bool) -> String {
+    let paint = |code: &str, text: String| {
+        if color {
+            format!("{}{}{}", code, text, RESET)
+        } else {
+            text
+        }
+    };
+    let mut lines = vec![format!(
+        "JSON values differ ({} {}; - expected, + actual):",
+        changes.len(),
+        if changes.len() == 1 {
+            "change"
+        } else {
+            "changes"
+        }
+    )];
+    for change in changes.iter() {
+        lines.push(match change {
+            Change::Added { path, value } => paint(ADDED, format!("  + {}: {}", path, value)),
+            Change::Removed { path, value } => paint(REMOVED, format!("  - {}: {}", path, value)),
+            Change::Modified {
+                path,
+                old_value,
+                new_value,
+            } => format!(
+                "  {} {}\n      {}\n      {}",
+                paint(MODIFIED, "~".to_string()),
+                path,
+                paint(REMOVED, format!("- {}", old_value)),
+                paint(ADDED, format!("+ {}", new_value))
+            ),
+        });
+    }
+    lines.join("\n")
+}
+
+/// Assert that two serializable values have the same JSON form
+///
+/// The first value is the actual one and the second the expected one. On
+/// failure the panic message lists the changes between them; changes at
+/// `ignore = [...]` patterns are allowed.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use serde_json::json;
+///
+/// // JSON values differ (1 change; - expected, + actual):
+/// //   ~ port
+/// //       - 80
+/// //       + 8080
+/// rjd::assert_no_diff!(json!({"port": 8080}), json!({"port": 80}));
+/// ```
+#[macro_export]
+macro_rules! assert_no_diff {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::assert_no_diff!($actual, $expected, ignore = [])
+    };
+    ($actual:expr, $expected:expr, ignore = [$($pattern:expr),* $(,)?] $(,)?) => {
+        if let Some(report) =
+            $crate::test_helpers::mismatch(&$actual, &$expected, &[$($pattern),*], false)
+        {
+            panic!("{}", report);
+        }
+    };
+}
+
+/// Assert that a value contains everything an expected value holds
+///
+/// Like [`assert_no_diff!`](crate::assert_no_diff), but members and array
+/// elements that only the actual value has are allowed, so the expected value
+/// can list just the fields a test cares about.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+///
+/// let user = json!({"id": 1, "name": "Ada", "roles": ["admin", "dev"]});
+/// rjd::assert_json_matches!(user, json!({"name": "Ada", "roles": ["admin"]}));
+/// ```
+#[macro_export]
+macro_rules! assert_json_matches {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::assert_json_matches!($actual, $expected, ignore = [])
+    };
+    ($actual:expr, $expected:expr, ignore = [$($pattern:expr),* $(,)?] $(,)?) => {
+        if let Some(report) =
+            $crate::test_helpers::mismatch(&$actual, &$expected, &[$($pattern),*], true)
+        {
+            panic!("{}", report);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+    use serde_json::json;
+
+    #[test]
+    fn test_report() {
+        let changes = diff(&json!({"a": 1, "b": true}), &json!({"a": 2, "c": [null]}));
+        assert_eq!(
+            report(&changes, false),
+            "JSON values differ (3 changes; - expected, + actual):\n  + c[0]: null\n  - b: true\n  ~ a\n      - 1\n      + 2"
+        );
+        assert!(report(&changes, true).contains("\x1b[32m  + c[0]: null\x1b[0m"));
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let actual = json!({"id": 7, "tags": ["a", "b"], "at": 1});
+        assert!(mismatch(
+            &actual,
+            &json!({"id": 7, "tags": ["a", "b"], "at": 2}),
+            &["/at"],
+            false
+        )
+        .is_none());
+        assert!(mismatch(&actual, &json!({"tags": ["a"]}), &[], true).is_none());
+        assert!(mismatch(&actual, &json!({"tags": ["a"]}), &[], false).is_some());
+        assert!(mismatch(&actual, &json!({"id": 8}), &[], true).is_some());
+        assert_eq!(
+            mismatch(&actual, &actual, &["at[x]"], false).unwrap(),
+            "invalid ignore pattern: Internal error: Invalid ignore pattern 'at[x]': Invalid array index at position 3: expected digit, found 'x' (expected a JSON Pointer like /user/id, dot notation like user.id or a JSONPath query)"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "JSON values differ (1 change")]
+    fn test_assert_json_matches_panics() {
+        crate::assert_json_matches!(
+            json!({"id": 1}),
+            json!({"id": 1, "name": "x"}),
+            ignore = ["/id"]
+        );
+    }
+}
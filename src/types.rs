@@ -1,7 +1,8 @@
-use crate::json_path::JsonPath;
+use crate::json_path::{JsonPath, PathSegment};
+use crate::query::Query;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde_json::Value;
-use std::collections::HashSet;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
 
 /// Represents a change to a JSON value
 ///
@@ -71,6 +72,14 @@ pub enum Change {
     },
 }
 
+/// The kind of a [`Change`], without its path or values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
 impl Change {
     /// Get the path for this change
     pub fn path(&self) -> &JsonPath {
@@ -80,9 +89,54 @@ impl Change {
             Change::Modified { path, .. } => path,
         }
     }
+
+    /// Get the kind of this change
+    pub fn kind(&self) -> ChangeKind {
+        match self {
+            Change::Added { .. } => ChangeKind::Added,
+            Change::Removed { .. } => ChangeKind::Removed,
+            Change::Modified { .. } => ChangeKind::Modified,
+        }
+    }
+
+    /// The old and new JSON type names of a modification that changes the
+    /// value's type, e.g. `("string", "number")`
+    pub fn type_change(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Change::Modified {
+                old_value,
+                new_value,
+                ..
+            } if type_name(old_value) != type_name(new_value) => {
+                Some((type_name(old_value), type_name(new_value)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// JSON Schema type name of a value (integers are numbers)
+pub(crate) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Number of JSON values in a subtree, counting the root
+pub(crate) fn subtree_size(value: &Value) -> usize {
+    match value {
+        Value::Object(map) => 1 + map.values().map(subtree_size).sum::<usize>(),
+        Value::Array(arr) => 1 + arr.iter().map(subtree_size).sum::<usize>(),
+        _ => 1,
+    }
 }
 
-/// Custom serialization for Change that converts JsonPath to String for JSON output
+/// Custom serialization for Change with camelCase value fields and a dot notation path
 impl Serialize for Change {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -93,13 +147,13 @@ impl Serialize for Change {
         match self {
             Change::Added { path, value } => {
                 let mut map = serializer.serialize_map(Some(2))?;
-                map.serialize_entry("path", &path.to_string())?;
+                map.serialize_entry("path", path)?;
                 map.serialize_entry("value", value)?;
                 map.end()
             }
             Change::Removed { path, value } => {
                 let mut map = serializer.serialize_map(Some(2))?;
-                map.serialize_entry("path", &path.to_string())?;
+                map.serialize_entry("path", path)?;
                 map.serialize_entry("value", value)?;
                 map.end()
             }
@@ -109,7 +163,7 @@ impl Serialize for Change {
                 new_value,
             } => {
                 let mut map = serializer.serialize_map(Some(3))?;
-                map.serialize_entry("path", &path.to_string())?;
+                map.serialize_entry("path", path)?;
                 map.serialize_entry("oldValue", old_value)?;
                 map.serialize_entry("newValue", new_value)?;
                 map.end()
@@ -118,7 +172,7 @@ impl Serialize for Change {
     }
 }
 
-/// Custom deserialization for Change that converts String to JsonPath
+/// Custom deserialization for Change that infers the variant from its fields
 impl<'de> Deserialize<'de> for Change {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -148,10 +202,7 @@ impl<'de> Deserialize<'de> for Change {
                 while let Some(key) = map.next_key::<String>()? {
                     match key.as_str() {
                         "path" => {
-                            let path_str: String = map.next_value()?;
-                            path = Some(path_str.parse::<JsonPath>().map_err(|_| {
-                                serde::de::Error::custom(format!("invalid path: {}", path_str))
-                            })?);
+                            path = Some(map.next_value::<JsonPath>()?);
                         }
                         "value" => {
                             value = Some(map.next_value()?);
@@ -201,8 +252,135 @@ pub struct Changes {
     pub added: Vec<Change>,
     pub removed: Vec<Change>,
     pub modified: Vec<Change>,
+    /// The first document, used by formatters that render the "before" state
+    ///
+    /// The diff functions leave it unset rather than copy the whole first
+    /// document; set it for the formatters whose
+    /// [`uses_before`](crate::formatter::Formatter::uses_before) is true.
+    #[serde(skip)]
+    pub before: Option<Value>,
+    /// The second document, used by formatters that render the "after" state
     #[serde(skip)]
     pub after: Option<Value>,
+    /// Places where the diff stopped early, making the result partial
+    #[serde(skip)]
+    pub truncated: Vec<Truncation>,
+    /// Comparisons whose result may be misleading
+    #[serde(skip)]
+    pub warnings: Vec<Warning>,
+    /// Extra fields reported with the change at a path, e.g. its schema
+    /// compatibility (see [`diff_with_schema`](crate::schema::diff_with_schema))
+    #[serde(skip)]
+    pub annotations: HashMap<JsonPath, Map<String, Value>>,
+    /// Arrays whose elements were paired by an [`ArrayDiff`](crate::ArrayDiff)
+    /// strategy other than position, with the (first index, second index)
+    /// pairs of the elements compared with each other
+    ///
+    /// Changes below such an array use one index space: paired and added
+    /// elements are reported under their index in the second document, and
+    /// removed ones after its last element, in their order in the first.
+    #[serde(skip)]
+    pub paired_arrays: HashMap<JsonPath, Vec<(usize, usize)>>,
+}
+
+/// A place where the diff stopped early because it hit a limit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Truncation {
+    /// Path at which the diff stopped
+    pub path: JsonPath,
+    /// The limit that was hit
+    pub reason: TruncationReason,
+}
+
+/// Limits that can cut a diff short
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TruncationReason {
+    /// Differences below the maximum diff depth were reported as one change
+    /// of the whole subtree
+    MaxDepth(usize),
+    /// The maximum number of changes was recorded; later differences were
+    /// not examined
+    MaxChanges(usize),
+    /// The diff was cancelled through its
+    /// [`CancellationToken`](crate::CancellationToken); later differences
+    /// were not examined
+    Cancelled,
+}
+
+impl std::fmt::Display for TruncationReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TruncationReason::MaxDepth(depth) => write!(
+                f,
+                "max diff depth {} reached; subtree compared as a whole",
+                depth
+            ),
+            TruncationReason::MaxChanges(limit) => write!(
+                f,
+                "change limit {} reached; remaining differences not examined",
+                limit
+            ),
+            TruncationReason::Cancelled => {
+                write!(f, "diff cancelled; remaining differences not examined")
+            }
+        }
+    }
+}
+
+/// A comparison the diff made that may not mean what it seems
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// Path of the compared value
+    pub path: JsonPath,
+    /// What makes the comparison suspicious
+    pub kind: WarningKind,
+}
+
+/// Correctness hazards the diff can detect
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarningKind {
+    /// Large arrays of different lengths were compared index by index, so an
+    /// insertion or removal shows up as a change to every later element
+    PositionalArray { old_len: usize, new_len: usize },
+    /// A number outside the range where floats are exact was compared;
+    /// distinct numbers in the source text may have parsed to the same value
+    ///
+    /// Not produced with the `arbitrary-precision` feature, which compares
+    /// numbers exactly.
+    PrecisionLoss,
+    /// A changed object key contains `.`, `[` or `]`, so its dot notation
+    /// path is ambiguous (use the `rfc6902` format for exact paths)
+    AmbiguousKey(String),
+    /// The object member appears more than once in the named input ("old"
+    /// or "new"); only the last value was compared
+    DuplicateKey(String),
+}
+
+impl std::fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WarningKind::PositionalArray { old_len, new_len } => write!(
+                f,
+                "arrays of {} and {} elements compared by position; \
+                 an insertion or removal marks every later element as changed",
+                old_len, new_len
+            ),
+            WarningKind::PrecisionLoss => write!(
+                f,
+                "number exceeds the range of exact floats and may have lost precision"
+            ),
+            WarningKind::AmbiguousKey(key) => write!(
+                f,
+                "key {:?} contains path syntax, so the dot notation path is ambiguous",
+                key
+            ),
+            WarningKind::DuplicateKey(input) => write!(
+                f,
+                "key appears more than once in the {} input; only the last value was compared",
+                input
+            ),
+        }
+    }
 }
 
 impl Changes {
@@ -212,7 +390,12 @@ impl Changes {
             added: Vec::new(),
             removed: Vec::new(),
             modified: Vec::new(),
+            before: None,
             after: None,
+            truncated: Vec::new(),
+            warnings: Vec::new(),
+            annotations: HashMap::new(),
+            paired_arrays: HashMap::new(),
         }
     }
 
@@ -230,6 +413,287 @@ impl Changes {
         self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
     }
 
+    /// Check whether the diff stopped early because it hit a limit
+    pub fn is_truncated(&self) -> bool {
+        !self.truncated.is_empty()
+    }
+
+    /// Check whether the diff stopped early because it was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.truncated
+            .iter()
+            .any(|truncation| truncation.reason == TruncationReason::Cancelled)
+    }
+
+    /// Total number of changes of all kinds
+    pub fn len(&self) -> usize {
+        self.added.len() + self.removed.len() + self.modified.len()
+    }
+
+    /// Iterate over all changes in the order: added, removed, modified
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::{diff, ChangeKind};
+    /// use serde_json::json;
+    ///
+    /// let changes = diff(&json!({"a": 1, "b": 2}), &json!({"a": 2, "c": 3}));
+    /// let kinds: Vec<ChangeKind> = changes.iter().map(|c| c.kind()).collect();
+    /// assert_eq!(
+    ///     kinds,
+    ///     [ChangeKind::Added, ChangeKind::Removed, ChangeKind::Modified]
+    /// );
+    /// ```
+    pub fn iter(&self) -> Iter<'_> {
+        self.added
+            .iter()
+            .chain(self.removed.iter())
+            .chain(self.modified.iter())
+    }
+
+    /// Iterate over the paths of all changes, in the same order as [`Changes::iter`]
+    pub fn paths(&self) -> impl Iterator<Item = &JsonPath> {
+        self.iter().map(Change::path)
+    }
+
+    /// Get the change recorded at exactly `path`, if any
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::{diff, Change, JsonPath};
+    /// use serde_json::json;
+    ///
+    /// let changes = diff(&json!({"user": {"age": 25}}), &json!({"user": {"age": 26}}));
+    /// let path: JsonPath = "user.age".parse().unwrap();
+    /// assert!(matches!(changes.get(&path), Some(Change::Modified { .. })));
+    /// assert!(changes.contains_path(&path));
+    /// ```
+    pub fn get(&self, path: &JsonPath) -> Option<&Change> {
+        self.iter().find(|change| change.path() == path)
+    }
+
+    /// Check whether a change was recorded at exactly `path`
+    pub fn contains_path(&self, path: &JsonPath) -> bool {
+        self.get(path).is_some()
+    }
+
+    /// Get the changes of one kind
+    pub fn by_kind(&self, kind: ChangeKind) -> &[Change] {
+        match kind {
+            ChangeKind::Added => &self.added,
+            ChangeKind::Removed => &self.removed,
+            ChangeKind::Modified => &self.modified,
+        }
+    }
+
+    /// Changes recorded identically (same path, kind and values) in both sets
+    ///
+    /// Changes are matched by path, so a path changed differently in the two
+    /// sets (e.g. to different new values) is in neither intersection but in
+    /// both [differences](Changes::difference). The annotations of kept
+    /// changes are kept; the documents, truncations and warnings are not.
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::diff;
+    /// use serde_json::json;
+    ///
+    /// let last_week = diff(&json!({"a": 1, "b": 1}), &json!({"a": 2, "b": 2}));
+    /// let today = diff(&json!({"a": 1, "b": 1}), &json!({"a": 2, "b": 3}));
+    /// assert_eq!(today.intersection(&last_week).len(), 1);
+    /// assert_eq!(today.difference(&last_week).paths().next().unwrap().to_string(), "b");
+    /// ```
+    pub fn intersection(&self, other: &Changes) -> Self {
+        let other = other.by_path();
+        self.select(|change| other.get(change.path()) == Some(&change))
+    }
+
+    /// Changes not recorded identically in `other`
+    ///
+    /// See [`Changes::intersection`] for how changes are matched.
+    pub fn difference(&self, other: &Changes) -> Self {
+        let other = other.by_path();
+        self.select(|change| other.get(change.path()) != Some(&change))
+    }
+
+    fn by_path(&self) -> HashMap<&JsonPath, &Change> {
+        self.iter().map(|change| (change.path(), change)).collect()
+    }
+
+    fn select(&self, keep: impl Fn(&Change) -> bool) -> Self {
+        let mut selected = Changes::new();
+        for change in self.iter().filter(|change| keep(change)) {
+            if let Some(annotations) = self.annotations.get(change.path()) {
+                selected
+                    .annotations
+                    .insert(change.path().clone(), annotations.clone());
+            }
+            selected.push(change.clone());
+        }
+        selected
+    }
+
+    /// Keep only the modifications that change a value's JSON type
+    ///
+    /// Each kept change is annotated with its `oldType` and `newType`.
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::diff;
+    /// use serde_json::json;
+    ///
+    /// let changes = diff(&json!({"id": 1, "name": "a"}), &json!({"id": "1", "name": "b"}));
+    /// let type_changes = changes.type_changes();
+    ///
+    /// assert_eq!(type_changes.len(), 1);
+    /// assert_eq!(type_changes.annotations[type_changes.modified[0].path()]["newType"], "string");
+    /// ```
+    pub fn type_changes(&self) -> Self {
+        let mut changes = self.select(|change| change.type_change().is_some());
+        for change in &changes.modified {
+            if let Some((old_type, new_type)) = change.type_change() {
+                let annotations = changes
+                    .annotations
+                    .entry(change.path().clone())
+                    .or_default();
+                annotations.insert("oldType".to_string(), Value::from(old_type));
+                annotations.insert("newType".to_string(), Value::from(new_type));
+            }
+        }
+        changes.before = self.before.clone();
+        changes.after = self.after.clone();
+        changes.truncated = self.truncated.clone();
+        changes.warnings = self.warnings.clone();
+        changes.paired_arrays = self.paired_arrays.clone();
+        changes
+    }
+
+    /// Annotate every change with the types and size of its values
+    ///
+    /// Added and modified values get `newType`, removed and modified values
+    /// `oldType`, and every change a `valueSize`: the length in bytes of the
+    /// value's compact JSON text (for a modification, of the larger value).
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::diff;
+    /// use serde_json::json;
+    ///
+    /// let mut changes = diff(&json!({"id": 1}), &json!({"id": "10"}));
+    /// changes.annotate_value_metadata();
+    ///
+    /// let fields = &changes.annotations[changes.modified[0].path()];
+    /// assert_eq!(fields["oldType"], "number");
+    /// assert_eq!(fields["newType"], "string");
+    /// assert_eq!(fields["valueSize"], 4);
+    /// ```
+    pub fn annotate_value_metadata(&mut self) {
+        let size = |value: &Value| serde_json::to_string(value).map_or(0, |text| text.len());
+        for change in self.added.iter().chain(&self.removed).chain(&self.modified) {
+            let (old, new) = match change {
+                Change::Added { value, .. } => (None, Some(value)),
+                Change::Removed { value, .. } => (Some(value), None),
+                Change::Modified {
+                    old_value,
+                    new_value,
+                    ..
+                } => (Some(old_value), Some(new_value)),
+            };
+            let annotations = self.annotations.entry(change.path().clone()).or_default();
+            if let Some(old) = old {
+                annotations.insert("oldType".to_string(), Value::from(type_name(old)));
+            }
+            if let Some(new) = new {
+                annotations.insert("newType".to_string(), Value::from(type_name(new)));
+            }
+            let value_size = old.into_iter().chain(new).map(size).max().unwrap_or(0);
+            annotations.insert("valueSize".to_string(), Value::from(value_size));
+        }
+    }
+
+    /// Collapse overlapping changes, as left by merging or filtering results
+    ///
+    /// Changes below an added or removed path are dropped, since the value
+    /// recorded at that path already holds them, and of several changes of
+    /// one kind at the same path only the first is kept. A removal and an
+    /// addition at the same path become a single modification (or nothing, if
+    /// the values are equal). Annotations of dropped paths are dropped too.
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::{Change, Changes};
+    /// use serde_json::json;
+    ///
+    /// let mut changes = Changes::new();
+    /// changes.push(Change::Added { path: "user".parse().unwrap(), value: json!({"id": 2}) });
+    /// changes.push(Change::Added { path: "user.id".parse().unwrap(), value: json!(2) });
+    /// changes.push(Change::Removed { path: "user".parse().unwrap(), value: json!({"id": 1}) });
+    /// changes.normalize();
+    ///
+    /// assert_eq!(changes.len(), 1);
+    /// assert_eq!(
+    ///     changes.modified[0],
+    ///     Change::Modified {
+    ///         path: "user".parse().unwrap(),
+    ///         old_value: json!({"id": 1}),
+    ///         new_value: json!({"id": 2}),
+    ///     }
+    /// );
+    /// ```
+    pub fn normalize(&mut self) {
+        let roots: HashSet<JsonPath> = self
+            .added
+            .iter()
+            .chain(&self.removed)
+            .map(|change| change.path().clone())
+            .collect();
+        let mut seen = HashSet::new();
+        let mut keep = |change: &Change| {
+            let path = change.path();
+            let subsumed = (1..path.len())
+                .filter_map(|n| path.prefix(n))
+                .any(|ancestor| roots.contains(&ancestor));
+            !subsumed && seen.insert((change.kind(), path.clone()))
+        };
+        self.added.retain(&mut keep);
+        self.removed.retain(&mut keep);
+        self.modified.retain(&mut keep);
+
+        let removed_at: HashMap<JsonPath, usize> = self
+            .removed
+            .iter()
+            .enumerate()
+            .map(|(index, change)| (change.path().clone(), index))
+            .collect();
+        let mut merged = vec![false; self.removed.len()];
+        let mut replaced = Vec::new();
+        self.added
+            .retain(|change| match removed_at.get(change.path()) {
+                Some(&index) => {
+                    merged[index] = true;
+                    if let (Change::Removed { value: old, .. }, Change::Added { path, value }) =
+                        (&self.removed[index], change)
+                    {
+                        if old != value {
+                            replaced.push(Change::Modified {
+                                path: path.clone(),
+                                old_value: old.clone(),
+                                new_value: value.clone(),
+                            });
+                        }
+                    }
+                    false
+                }
+                None => true,
+            });
+        let mut merged = merged.into_iter();
+        self.removed.retain(|_| !merged.next().unwrap_or(false));
+        self.modified.extend(replaced);
+
+        let paths: HashSet<JsonPath> = self.paths().cloned().collect();
+        self.annotations.retain(|path, _| paths.contains(path));
+    }
+
     /// Filter out changes that match any of the ignore patterns
     pub fn filter_ignore_patterns(&self, patterns: &[String]) -> Self {
         let matcher = PatternMatcher::new(patterns);
@@ -253,7 +717,17 @@ impl Changes {
                 .filter(|c| !should_ignore_change(c, &matcher))
                 .cloned()
                 .collect(),
+            before: self.before.clone(),
             after: self.after.clone(),
+            truncated: self.truncated.clone(),
+            warnings: self
+                .warnings
+                .iter()
+                .filter(|w| !matcher.matches(&w.path))
+                .cloned()
+                .collect(),
+            annotations: self.annotations.clone(),
+            paired_arrays: self.paired_arrays.clone(),
         }
     }
 
@@ -308,75 +782,266 @@ impl Changes {
                     .filter(move |c| !should_ignore_change(c, &matcher_modified)),
             )
     }
-}
 
-/// Pattern matcher that pre-computes all possible pattern prefixes for O(1) lookup
-#[derive(Clone)]
-struct PatternMatcher {
-    /// All possible prefixes for O(1) lookup
-    /// Example: Pattern "user.profile" stores {"user", "user.profile"}
-    prefixes: HashSet<String>,
+    /// Check whether every change falls within the allowed path patterns
+    ///
+    /// Patterns use the same syntax as ignore patterns: a change is within the
+    /// allowed set when a pattern matches its path, so `/config/**` allows any
+    /// change below `config` while `/config` allows only `config` itself.
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::diff;
+    /// use serde_json::json;
+    ///
+    /// let old = json!({"version": "1.0", "name": "app"});
+    /// let new = json!({"version": "1.1", "name": "app"});
+    /// let changes = diff(&old, &new);
+    ///
+    /// assert!(changes.is_subset_of_paths(&["/version".to_string()]));
+    /// assert!(!changes.is_subset_of_paths(&["/name".to_string()]));
+    /// ```
+    pub fn is_subset_of_paths(&self, patterns: &[String]) -> bool {
+        let matcher = PatternMatcher::new(patterns);
+        self.iter().all(|c| should_ignore_change(c, &matcher))
+    }
+
+    /// Mark the changes outside an allowlist of expected paths
+    ///
+    /// Patterns use the ignore pattern syntax. Each change no pattern matches
+    /// gets an `unexpected: true` annotation; their paths are returned.
+    ///
+    /// # Example
+    /// ```
+    /// use rjd::diff;
+    /// use serde_json::json;
+    ///
+    /// let mut changes = diff(&json!({"version": 1, "owner": "a"}), &json!({"version": 2, "owner": "b"}));
+    /// let unexpected = changes.mark_unexpected(&["/version".to_string()]);
+    ///
+    /// assert_eq!(unexpected, vec!["owner".parse().unwrap()]);
+    /// assert_eq!(changes.annotations[&unexpected[0]]["unexpected"], true);
+    /// ```
+    pub fn mark_unexpected(&mut self, expected: &[String]) -> Vec<JsonPath> {
+        let matcher = PatternMatcher::new(expected);
+        let unexpected: Vec<JsonPath> = self
+            .iter()
+            .filter(|c| !should_ignore_change(c, &matcher))
+            .map(|c| c.path().clone())
+            .collect();
+        for path in &unexpected {
+            self.annotations
+                .entry(path.clone())
+                .or_default()
+                .insert("unexpected".to_string(), Value::Bool(true));
+        }
+        unexpected
+    }
+
+    /// Build a coverage report against an allowed path set and required paths
+    ///
+    /// `allowed` lists the patterns changes may fall under; `required` lists the
+    /// patterns that must have changed. A required pattern is satisfied when a
+    /// change was recorded at or above that path, or anywhere below it for a
+    /// subtree pattern (`/config/**`).
+    pub fn path_coverage(&self, allowed: &[String], required: &[String]) -> PathCoverage {
+        let matcher = PatternMatcher::new(allowed);
+        let outside = self
+            .iter()
+            .filter(|c| !should_ignore_change(c, &matcher))
+            .map(|c| c.path().clone())
+            .collect();
+
+        let missing = required
+            .iter()
+            .filter(|pattern| {
+                if pattern.starts_with('$') {
+                    let (base, subtree) = split_subtree(pattern);
+                    let Ok(query) = Query::parse(base) else {
+                        return true;
+                    };
+                    return !self.iter().any(|c| {
+                        query.could_match_below(c.path())
+                            || (subtree && query.matches_path_or_ancestor(c.path()))
+                    });
+                }
+                let Some(required) = PathPattern::parse(pattern) else {
+                    return true;
+                };
+                !self.iter().any(|c| {
+                    let tokens = path_tokens(c.path());
+                    required.tokens.starts_with(&tokens)
+                        || (required.subtree && tokens.starts_with(&required.tokens))
+                })
+            })
+            .cloned()
+            .collect();
+
+        PathCoverage { outside, missing }
+    }
 }
 
-impl PatternMatcher {
-    /// Create a new PatternMatcher by parsing patterns and storing them
-    fn new(patterns: &[String]) -> Self {
-        let mut prefixes = HashSet::new();
+/// Iterator over all changes, returned by [`Changes::iter`]
+pub type Iter<'a> = std::iter::Chain<
+    std::iter::Chain<std::slice::Iter<'a, Change>, std::slice::Iter<'a, Change>>,
+    std::slice::Iter<'a, Change>,
+>;
 
-        for pattern_str in patterns {
-            // Convert JSON Pointer to dot notation if needed
-            let dot_notation = if pattern_str.starts_with('/') {
-                json_pointer_to_dot_notation(pattern_str)
-            } else {
-                pattern_str.clone()
-            };
+impl<'a> IntoIterator for &'a Changes {
+    type Item = &'a Change;
+    type IntoIter = Iter<'a>;
 
-            // Store the full pattern string
-            prefixes.insert(dot_notation);
-        }
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Result of checking changes against allowed and required path sets
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PathCoverage {
+    /// Paths of changes that fall outside the allowed path set
+    pub outside: Vec<JsonPath>,
+    /// Required patterns for which no change was recorded
+    pub missing: Vec<String>,
+}
 
-        Self { prefixes }
+impl PathCoverage {
+    /// Check if all changes are allowed and all required paths changed
+    pub fn is_satisfied(&self) -> bool {
+        self.outside.is_empty() && self.missing.is_empty()
     }
+}
 
-    /// Check if a path should be ignored (matches any pattern prefix)
-    fn should_ignore(&self, path: &JsonPath) -> bool {
-        // Check if any prefix of this path matches a pattern in our set
-        // This implements the same logic as before: a path is ignored if
-        // any pattern matches exactly or is a prefix of the path
-        for i in 1..=path.len() {
-            if let Some(prefix) = path.prefix(i) {
-                let prefix_str = prefix.to_string();
-                // Check if this prefix is in our pattern set
-                if self.prefixes.contains(&prefix_str) {
-                    return true;
-                }
+impl std::fmt::Display for PathCoverage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.outside.is_empty() {
+            writeln!(f, "Changes outside the allowed paths:")?;
+            for path in &self.outside {
+                writeln!(f, "  {}", path.to_json_pointer())?;
+            }
+        }
+        if !self.missing.is_empty() {
+            writeln!(f, "Required paths without changes:")?;
+            for pattern in &self.missing {
+                writeln!(f, "  {}", pattern)?;
             }
         }
-        false
+        Ok(())
+    }
+}
+
+/// Split the trailing `**` token off an ignore-style pattern
+///
+/// Returns the pattern without it and whether it was there: `/user/**`,
+/// `user.**` and `$.users[*].**` cover the selected values and everything
+/// below them, while every other pattern matches the selected values only.
+pub(crate) fn split_subtree(pattern: &str) -> (&str, bool) {
+    match pattern
+        .strip_suffix("/**")
+        .or_else(|| pattern.strip_suffix(".**"))
+    {
+        Some(base) => (base, true),
+        None => (pattern, false),
     }
 }
 
-/// Convert a JSON Pointer path to dot notation
-/// Example: "/user/id/0/name" -> "user.id[0].name"
-fn json_pointer_to_dot_notation(ptr: &str) -> String {
-    let mut result = String::new();
-    let parts: Vec<&str> = ptr.split('/').filter(|s| !s.is_empty()).collect();
+/// An ignore-style pattern (JSON Pointer or dot notation) split into path tokens
+///
+/// A trailing `**` token (`/user/**`, `user.**`) makes the pattern cover the
+/// path and everything below it; otherwise only the exact path matches.
+struct PathPattern {
+    tokens: Vec<String>,
+    subtree: bool,
+}
+
+impl PathPattern {
+    fn parse(pattern: &str) -> Option<Self> {
+        let (base, subtree) = split_subtree(pattern);
+        let path = JsonPath::parse_any(base).ok()?;
+        Some(Self {
+            tokens: path_tokens(&path),
+            subtree,
+        })
+    }
+}
+
+/// The JSON Pointer reference tokens of a path, unescaped
+///
+/// Indices and keys compare as the same token, so `/items/0` matches both an
+/// array element and an object key `"0"`.
+fn path_tokens(path: &JsonPath) -> Vec<String> {
+    path.segments()
+        .iter()
+        .map(|segment| match segment {
+            PathSegment::Key(key) => key.clone(),
+            PathSegment::Index(index) => index.to_string(),
+        })
+        .collect()
+}
+
+/// Pattern matcher comparing parsed path segments for O(depth) lookup
+#[derive(Clone)]
+pub(crate) struct PatternMatcher {
+    /// Token lists of patterns matching one path only
+    /// Example: Pattern "/user" stores ["user"] and matches neither "user.id" nor "username"
+    exact: HashSet<Vec<String>>,
+    /// Token lists of subtree patterns, matched against every prefix of a path
+    /// Example: Pattern "/user/**" stores ["user"] and matches "user" and "user.id"
+    subtrees: HashSet<Vec<String>>,
+    /// Compiled JSONPath patterns (those starting with `$`), with whether
+    /// they end in `.**` and so also match below the selected values
+    queries: Vec<(Query, bool)>,
+}
+
+impl PatternMatcher {
+    /// Create a new PatternMatcher by parsing patterns and storing them
+    pub(crate) fn new(patterns: &[String]) -> Self {
+        let mut exact = HashSet::new();
+        let mut subtrees = HashSet::new();
+        let mut queries = Vec::new();
+
+        for pattern_str in patterns {
+            // Invalid queries are rejected when patterns are loaded
+            if pattern_str.starts_with('$') {
+                let (base, subtree) = split_subtree(pattern_str);
+                queries.extend(Query::parse(base).ok().map(|query| (query, subtree)));
+                continue;
+            }
 
-    for (i, part) in parts.iter().enumerate() {
-        if i > 0 {
-            result.push('.');
+            match PathPattern::parse(pattern_str) {
+                Some(pattern) if pattern.subtree => subtrees.insert(pattern.tokens),
+                Some(pattern) => exact.insert(pattern.tokens),
+                None => false,
+            };
         }
-        // Check if part is a numeric array index
-        if part.chars().next().is_some_and(|c| c.is_ascii_digit()) {
-            result.push('[');
-            result.push_str(part);
-            result.push(']');
-        } else {
-            result.push_str(part);
+
+        Self {
+            exact,
+            subtrees,
+            queries,
         }
     }
 
-    result
+    /// Check if a path matches any pattern
+    ///
+    /// Pointers, dot paths and JSONPath queries all follow one rule: they
+    /// match the paths they select, and with a trailing `**` token every
+    /// descendant of those paths too.
+    pub(crate) fn matches(&self, path: &JsonPath) -> bool {
+        let tokens = path_tokens(path);
+        if self.exact.contains(&tokens)
+            || (0..=tokens.len()).any(|n| self.subtrees.contains(&tokens[..n]))
+        {
+            return true;
+        }
+        self.queries.iter().any(|(query, subtree)| {
+            if *subtree {
+                query.matches_path_or_ancestor(path)
+            } else {
+                query.matches(path)
+            }
+        })
+    }
 }
 
 /// Check if a change should be ignored using the pattern matcher
@@ -387,7 +1052,7 @@ fn should_ignore_change(change: &Change, matcher: &PatternMatcher) -> bool {
         Change::Modified { path, .. } => path,
     };
 
-    matcher.should_ignore(path)
+    matcher.matches(path)
 }
 
 impl Default for Changes {
@@ -399,6 +1064,7 @@ impl Default for Changes {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::diff;
     use serde_json::json;
 
     #[test]
@@ -408,13 +1074,34 @@ mod tests {
 
         // Test that converted patterns match dot notation paths
         let user_id_path: JsonPath = "user.id".parse().unwrap();
-        assert!(matcher.should_ignore(&user_id_path));
+        assert!(matcher.matches(&user_id_path));
 
         let tags_path: JsonPath = "tags".parse().unwrap();
-        assert!(matcher.should_ignore(&tags_path));
+        assert!(matcher.matches(&tags_path));
 
         let user_name_path: JsonPath = "user.name".parse().unwrap();
-        assert!(!matcher.should_ignore(&user_name_path));
+        assert!(!matcher.matches(&user_name_path));
+    }
+
+    #[test]
+    fn test_pattern_matching_exact_and_subtree() {
+        let matcher = PatternMatcher::new(&["/user".to_string(), "/items/**".to_string()]);
+        let matches = |path: &str| matcher.matches(&path.parse().unwrap());
+
+        // An exact pattern matches neither descendants nor keys sharing its prefix
+        assert!(matches("user"));
+        assert!(!matches("user.id"));
+        assert!(!matches("username"));
+
+        // A subtree pattern matches the path and everything below it
+        assert!(matches("items"));
+        assert!(matches("items[0]"));
+        assert!(matches("items[0].name"));
+        assert!(!matches("items_count"));
+
+        // Dot notation takes the same `**` suffix, and `/**` covers the whole document
+        assert!(PatternMatcher::new(&["user.**".to_string()]).matches(&"user.id".parse().unwrap()));
+        assert!(PatternMatcher::new(&["/**".to_string()]).matches(&"a.b".parse().unwrap()));
     }
 
     #[test]
@@ -587,4 +1274,296 @@ mod tests {
         assert!(matches!(filtered[1], Change::Removed { .. }));
         assert!(matches!(filtered[2], Change::Modified { .. }));
     }
+
+    #[test]
+    fn test_is_subset_of_paths() {
+        let mut changes = Changes::new();
+        changes.push(Change::Modified {
+            path: "config.version".parse().unwrap(),
+            old_value: json!("1.0"),
+            new_value: json!("1.1"),
+        });
+        changes.push(Change::Added {
+            path: "config.features[0]".parse().unwrap(),
+            value: json!("beta"),
+        });
+
+        assert!(changes.is_subset_of_paths(&["/config/**".to_string()]));
+        assert!(!changes.is_subset_of_paths(&["/config".to_string()]));
+        assert!(changes.is_subset_of_paths(&[
+            "/config/version".to_string(),
+            "/config/features/**".to_string()
+        ]));
+        assert!(!changes.is_subset_of_paths(&["/config/version".to_string()]));
+        assert!(Changes::new().is_subset_of_paths(&[]));
+    }
+
+    #[test]
+    fn test_path_coverage_reports_outside_and_missing() {
+        let mut changes = Changes::new();
+        changes.push(Change::Modified {
+            path: "version".parse().unwrap(),
+            old_value: json!("1.0"),
+            new_value: json!("1.1"),
+        });
+        changes.push(Change::Removed {
+            path: "owner".parse().unwrap(),
+            value: json!("alice"),
+        });
+
+        let coverage = changes.path_coverage(
+            &["/version".to_string()],
+            &["/version".to_string(), "/changelog".to_string()],
+        );
+
+        assert!(!coverage.is_satisfied());
+        assert_eq!(coverage.outside, vec!["owner".parse::<JsonPath>().unwrap()]);
+        assert_eq!(coverage.missing, vec!["/changelog".to_string()]);
+
+        let report = coverage.to_string();
+        assert!(report.contains("/owner"));
+        assert!(report.contains("/changelog"));
+    }
+
+    #[test]
+    fn test_path_coverage_required_satisfied_by_ancestor_change() {
+        let mut changes = Changes::new();
+        changes.push(Change::Added {
+            path: "release".parse().unwrap(),
+            value: json!({"notes": "initial"}),
+        });
+
+        let coverage =
+            changes.path_coverage(&["/release".to_string()], &["/release/notes".to_string()]);
+        assert!(coverage.is_satisfied());
+    }
+
+    #[test]
+    fn test_type_changes() {
+        let mut changes = diff(
+            &json!({"a": "1", "b": {"x": 1}, "c": 1, "d": null}),
+            &json!({"a": 1, "b": [1], "c": 1.5, "e": true}),
+        );
+        changes
+            .annotations
+            .entry("a".parse().unwrap())
+            .or_default()
+            .insert("severity".to_string(), json!("warn"));
+
+        let type_changes = changes.type_changes();
+
+        let paths: Vec<String> = type_changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(paths, vec!["a", "b"]);
+        let a = &type_changes.annotations[&"a".parse().unwrap()];
+        assert_eq!(a["oldType"], "string");
+        assert_eq!(a["newType"], "number");
+        assert_eq!(a["severity"], "warn");
+        assert_eq!(type_changes.after, changes.after);
+    }
+
+    #[test]
+    fn test_annotate_value_metadata() {
+        let mut changes = diff(
+            &json!({"a": "abc", "b": [1, 2], "c": null}),
+            &json!({"a": "abcdef", "b": {"x": 1}, "d": true}),
+        );
+        changes.annotate_value_metadata();
+
+        let fields = |path: &str| &changes.annotations[&path.parse::<JsonPath>().unwrap()];
+        assert_eq!(fields("a")["oldType"], "string");
+        assert_eq!(fields("a")["newType"], "string");
+        assert_eq!(fields("a")["valueSize"], 8);
+        assert_eq!(fields("b")["oldType"], "array");
+        assert_eq!(fields("b")["newType"], "object");
+        assert_eq!(fields("b")["valueSize"], 7);
+        assert_eq!(fields("d")["newType"], "boolean");
+        assert!(fields("d").get("oldType").is_none());
+        assert_eq!(fields("d")["valueSize"], 4);
+        assert_eq!(fields("c")["oldType"], "null");
+        assert!(fields("c").get("newType").is_none());
+    }
+
+    #[test]
+    fn test_mark_unexpected() {
+        let mut changes = diff(
+            &json!({"config": {"a": 1}, "owner": "x", "version": 1}),
+            &json!({"config": {"a": 2}, "owner": "y", "version": 2}),
+        );
+        changes
+            .annotations
+            .entry("owner".parse().unwrap())
+            .or_default()
+            .insert("severity".to_string(), json!("warn"));
+
+        let unexpected =
+            changes.mark_unexpected(&["/config/**".to_string(), "/version".to_string()]);
+
+        assert_eq!(unexpected, vec!["owner".parse::<JsonPath>().unwrap()]);
+        let owner = &changes.annotations[&"owner".parse().unwrap()];
+        assert_eq!(owner["unexpected"], true);
+        assert_eq!(owner["severity"], "warn");
+        assert!(!changes
+            .annotations
+            .contains_key(&"version".parse().unwrap()));
+        assert!(Changes::new().mark_unexpected(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_path_coverage_required_subtree() {
+        let mut changes = Changes::new();
+        changes.push(Change::Modified {
+            path: "release.notes".parse().unwrap(),
+            old_value: json!("a"),
+            new_value: json!("b"),
+        });
+
+        let coverage = changes.path_coverage(
+            &["/release/**".to_string()],
+            &["/release/**".to_string(), "/release".to_string()],
+        );
+        assert!(coverage.outside.is_empty());
+        assert_eq!(coverage.missing, vec!["/release".to_string()]);
+    }
+
+    #[test]
+    fn test_changes_accessors() {
+        let mut changes = Changes::new();
+        changes.push(Change::Modified {
+            path: "user.age".parse().unwrap(),
+            old_value: json!(25),
+            new_value: json!(26),
+        });
+        changes.push(Change::Added {
+            path: "user.email".parse().unwrap(),
+            value: json!("a@example.com"),
+        });
+
+        assert_eq!(changes.len(), 2);
+        let paths: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(paths, ["user.email", "user.age"]);
+        assert_eq!((&changes).into_iter().count(), 2);
+
+        let age: JsonPath = "user.age".parse().unwrap();
+        assert_eq!(
+            changes.get(&age).map(Change::kind),
+            Some(ChangeKind::Modified)
+        );
+        assert!(!changes.contains_path(&"user".parse().unwrap()));
+
+        assert_eq!(changes.by_kind(ChangeKind::Added).len(), 1);
+        assert!(changes.by_kind(ChangeKind::Removed).is_empty());
+    }
+
+    #[test]
+    fn test_intersection_and_difference() {
+        let first = diff(
+            &json!({"a": 1, "b": 1, "c": 1, "gone": 0}),
+            &json!({"a": 2, "b": 2, "c": 1, "new": 1}),
+        );
+        let second = diff(
+            &json!({"a": 1, "b": 1, "c": 1, "gone": 0}),
+            &json!({"a": 2, "b": 3, "c": 2}),
+        );
+
+        let paths =
+            |changes: Changes| -> Vec<String> { changes.paths().map(|p| p.to_string()).collect() };
+        assert_eq!(paths(first.intersection(&second)), ["gone", "a"]);
+        assert_eq!(paths(second.intersection(&first)), ["gone", "a"]);
+        assert_eq!(paths(first.difference(&second)), ["new", "b"]);
+        assert_eq!(paths(second.difference(&first)), ["b", "c"]);
+        assert!(first.intersection(&second).after.is_none());
+        assert!(first.difference(&first).is_empty());
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mut changes = Changes::new();
+        changes.push(Change::Removed {
+            path: "a".parse().unwrap(),
+            value: json!({"b": 1, "c": [1]}),
+        });
+        changes.push(Change::Removed {
+            path: "a.c[0]".parse().unwrap(),
+            value: json!(1),
+        });
+        changes.push(Change::Modified {
+            path: "a.b".parse().unwrap(),
+            old_value: json!(1),
+            new_value: json!(2),
+        });
+        changes.push(Change::Removed {
+            path: "same".parse().unwrap(),
+            value: json!(1),
+        });
+        changes.push(Change::Added {
+            path: "same".parse().unwrap(),
+            value: json!(1),
+        });
+        changes.push(Change::Added {
+            path: "x".parse().unwrap(),
+            value: json!(true),
+        });
+        changes.push(Change::Added {
+            path: "x".parse().unwrap(),
+            value: json!(true),
+        });
+        changes
+            .annotations
+            .insert("a.b".parse().unwrap(), Map::new());
+        changes.normalize();
+
+        let paths: Vec<String> = changes.paths().map(|p| p.to_string()).collect();
+        assert_eq!(paths, vec!["x", "a"]);
+        assert!(changes.annotations.is_empty());
+
+        // The engine's own output is already normal
+        let diffed = diff(
+            &json!({"a": {"b": 1}, "c": [1, 2]}),
+            &json!({"c": [1], "d": {"e": 1}}),
+        );
+        let mut normalized = diffed.clone();
+        normalized.normalize();
+        assert_eq!(normalized, diffed);
+    }
+
+    #[test]
+    fn test_jsonpath_patterns() {
+        let mut changes = Changes::new();
+        for path in [
+            "users[0].token",
+            "users[1].token",
+            "users[1].name",
+            "db.password",
+        ] {
+            changes.push(Change::Modified {
+                path: path.parse().unwrap(),
+                old_value: json!(1),
+                new_value: json!(2),
+            });
+        }
+
+        let filtered = changes
+            .filter_ignore_patterns(&["$.users[*].token".to_string(), "$..password".to_string()]);
+        let paths: Vec<String> = filtered.paths().map(|p| p.to_string()).collect();
+        assert_eq!(paths, vec!["users[1].name"]);
+
+        // Like pointers, a query matches the selected values only unless it ends in `.**`
+        assert_eq!(
+            changes
+                .filter_ignore_patterns(&["$.users".to_string(), "$.db.**".to_string()])
+                .len(),
+            3
+        );
+
+        let coverage = changes.path_coverage(
+            &["$.users.**".to_string(), "$.db.**".to_string()],
+            &[
+                "$.users[*].name".to_string(),
+                "$.missing".to_string(),
+                "$.db.password.hash".to_string(),
+            ],
+        );
+        assert!(coverage.outside.is_empty());
+        assert_eq!(coverage.missing, vec!["$.missing"]);
+    }
 }
@@ -0,0 +1,65 @@
+//! JavaScript interface (`wasm` feature)
+//!
+//! Built for `wasm32-unknown-unknown` (e.g. with `wasm-pack build --
+//! --no-default-features --features wasm`), the crate exports functions a
+//! browser-based diff viewer can call directly. They take the same options
+//! document as the C interface; `optionsSchema()` describes it.
+//!
+//! ```js
+//! import { diff } from "rjd";
+//!
+//! const changes = diff({ a: 1 }, { a: 2 }, { ignore: ["/updatedAt"] });
+//! changes.modified[0].path; // "a"
+//! ```
+
+use crate::bindings::{diff_report, parse_document, parse_options, OPTIONS_SCHEMA};
+use js_sys::JSON;
+use wasm_bindgen::prelude::*;
+
+/// Diff two JavaScript values
+///
+/// `options` is an options object or `undefined`. Reports in a JSON format
+/// (the default "changes" format, "rfc6902", ...) are returned as values and
+/// other formats as strings. Numbers are JavaScript numbers, which hold
+/// integers exactly only up to 2^53; [`diff_text`] reads 64-bit integers
+/// exactly, and any number when built with the `arbitrary-precision` feature.
+#[wasm_bindgen(js_name = diff)]
+pub fn diff_values(old: JsValue, new: JsValue, options: JsValue) -> Result<JsValue, JsError> {
+    let old = parse_document(&stringify(&old, "old")?, "old")?;
+    let new = parse_document(&stringify(&new, "new")?, "new")?;
+    let options = if options.is_undefined() || options.is_null() {
+        parse_options(None)?
+    } else {
+        parse_options(Some(&stringify(&options, "options")?))?
+    };
+    let report = diff_report(&old, &new, &options)?;
+    Ok(JSON::parse(&report).unwrap_or_else(|_| JsValue::from_str(&report)))
+}
+
+/// Diff two documents given as JSON text and return the report as text
+///
+/// Numbers are compared as 64-bit integers or floats, as in the CLI; only
+/// when built with the `arbitrary-precision` feature are they compared
+/// exactly as written. `options` is the options document as JSON text, or
+/// `undefined`.
+#[wasm_bindgen(js_name = diffText)]
+pub fn diff_text(old: &str, new: &str, options: Option<String>) -> Result<String, JsError> {
+    let old = parse_document(old, "old")?;
+    let new = parse_document(new, "new")?;
+    let options = parse_options(options.as_deref())?;
+    Ok(diff_report(&old, &new, &options)?)
+}
+
+/// The JSON Schema of the options document, as JSON text
+#[wasm_bindgen(js_name = optionsSchema)]
+pub fn options_schema() -> String {
+    OPTIONS_SCHEMA.to_string()
+}
+
+/// Serialize a JavaScript value to JSON text
+fn stringify(value: &JsValue, name: &str) -> Result<String, JsError> {
+    JSON::stringify(value)
+        .ok()
+        .and_then(|text| text.as_string())
+        .ok_or_else(|| JsError::new(&format!("{} cannot be serialized as JSON", name)))
+}
@@ -0,0 +1,372 @@
+//! XML documents as JSON
+//!
+//! [`decode_xml`] converts an XML document to a canonical JSON value, so XML
+//! configs can be compared structurally:
+//!
+//! - the document is an object with the root element under its name
+//! - an element with neither attributes nor child elements is its text, or
+//!   `null` when empty
+//! - any other element is an object holding its attributes (names prefixed
+//!   with `@`), then its child elements by name, then its text (under
+//!   `#text`); a name that occurs more than once holds an array, in document
+//!   order
+//!
+//! Attribute values and text are kept as strings. Text is trimmed, and the
+//! pieces of mixed content are joined with a space. Names keep their
+//! namespace prefix (`soap:Body`); namespace declarations, comments and
+//! processing instructions are dropped.
+
+use roxmltree::{Document, Node, ParsingOptions};
+use serde_json::{Map, Value};
+
+use crate::decode::{checked, DecodeError, InputFormat};
+use crate::error::RjdError;
+use crate::loader::LoadConfig;
+
+/// How attributes and text appear in the JSON an XML document converts to
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::XmlConvention;
+///
+/// let convention = XmlConvention::default();
+/// assert_eq!(convention.attribute_prefix, "@");
+/// assert_eq!(convention.text_key, "#text");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlConvention {
+    /// Prefix of attribute names, to tell them from child elements
+    /// (default: `@`)
+    pub attribute_prefix: String,
+    /// Key of an element's text when it also has attributes or child
+    /// elements (default: `#text`)
+    pub text_key: String,
+}
+
+impl Default for XmlConvention {
+    fn default() -> Self {
+        Self {
+            attribute_prefix: "@".to_string(),
+            text_key: "#text".to_string(),
+        }
+    }
+}
+
+/// Convert an XML document to JSON following `convention`
+///
+/// `source_name` names the input in error messages. The document must be
+/// UTF-8 and, once converted, no deeper than the configured JSON depth limit.
+/// Internal DTD entities are expanded; external ones are not loaded.
+///
+/// # Errors
+/// Returns an error if the bytes are not a well-formed XML document, or if
+/// the result is nested too deeply.
+///
+/// # Examples
+///
+/// ```rust
+/// use rjd::{decode_xml, LoadConfig, XmlConvention};
+/// use serde_json::json;
+///
+/// let xml = br#"<server port="80"><name>web</name><alias>a</alias><alias>b</alias></server>"#;
+/// let value = decode_xml(xml, &XmlConvention::default(), "inline", &LoadConfig::default()).unwrap();
+/// assert_eq!(
+///     value,
+///     json!({"server": {"@port": "80", "name": "web", "alias": ["a", "b"]}})
+/// );
+/// ```
+pub fn decode_xml(
+    bytes: &[u8],
+    convention: &XmlConvention,
+    source_name: &str,
+    config: &LoadConfig,
+) -> Result<Value, RjdError> {
+    checked(
+        xml_to_json(bytes, convention, config.max_json_depth),
+        InputFormat::Xml,
+        source_name,
+        config,
+    )
+}
+
+/// Convert an XML document to JSON, stopping at elements nested past
+/// `max_depth`
+pub(crate) fn xml_to_json(
+    bytes: &[u8],
+    convention: &XmlConvention,
+    max_depth: usize,
+) -> Result<Value, DecodeError> {
+    let text =
+        std::str::from_utf8(bytes).map_err(|e| format!("the document is not UTF-8: {}", e))?;
+    // The parser recurses once per element, so refuse deep documents before it sees them
+    check_nesting(text, max_depth)?;
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let document = Document::parse_with_options(text, options).map_err(|e| e.to_string())?;
+
+    let root = document.root_element();
+    let mut value = Map::new();
+    // The root element is a member of the document object
+    value.insert(
+        qualified_name(root),
+        element(root, convention, 2, max_depth)?,
+    );
+    Ok(Value::Object(value))
+}
+
+/// Fail if elements in `text` nest deeper than `max_depth`, counting the
+/// document object as the first level
+///
+/// Only tags are counted; comments, CDATA sections, processing instructions
+/// and the DTD are skipped. Elements that entity references expand to are
+/// left to [`element`].
+fn check_nesting(text: &str, max_depth: usize) -> Result<(), DecodeError> {
+    let bytes = text.as_bytes();
+    let find = |from: usize, end: &str| {
+        text[from..]
+            .find(end)
+            .map_or(bytes.len(), |at| from + at + end.len())
+    };
+    let mut depth: usize = 1;
+    let mut pos = 0;
+    while let Some(at) = text[pos..].find('<') {
+        let start = pos + at;
+        let rest = &text[start..];
+        pos = if rest.starts_with("<!--") {
+            find(start, "-->")
+        } else if rest.starts_with("<![CDATA[") {
+            find(start, "]]>")
+        } else if rest.starts_with("<?") {
+            find(start, "?>")
+        } else if rest.starts_with("<!") {
+            markup_end(bytes, start)
+        } else if rest.starts_with("</") {
+            depth = depth.saturating_sub(1);
+            find(start, ">")
+        } else {
+            let end = markup_end(bytes, start);
+            if !bytes[..end].ends_with(b"/>") {
+                depth += 1;
+                if depth > max_depth {
+                    return Err(DecodeError::TooDeep(depth));
+                }
+            }
+            end
+        };
+    }
+    Ok(())
+}
+
+/// The position after the tag or declaration starting at `start`, skipping
+/// quoted values, and comments in a bracketed internal subset
+fn markup_end(bytes: &[u8], start: usize) -> usize {
+    let mut subset = false;
+    let mut pos = start + 1;
+    while let Some(&byte) = bytes.get(pos) {
+        pos += 1;
+        match byte {
+            b'"' | b'\'' => {
+                pos = bytes[pos..]
+                    .iter()
+                    .position(|&b| b == byte)
+                    .map_or(bytes.len(), |at| pos + at + 1);
+            }
+            b'<' if subset && bytes[pos..].starts_with(b"!--") => {
+                pos = bytes[pos..]
+                    .windows(3)
+                    .position(|end| end == b"-->")
+                    .map_or(bytes.len(), |at| pos + at + 3);
+            }
+            b'[' => subset = true,
+            b']' => subset = false,
+            b'>' if !subset => return pos,
+            _ => {}
+        }
+    }
+    bytes.len()
+}
+
+/// The JSON for an element's attributes, children and text, at `depth` in
+/// the document
+fn element(
+    node: Node,
+    convention: &XmlConvention,
+    depth: usize,
+    max_depth: usize,
+) -> Result<Value, DecodeError> {
+    if depth > max_depth {
+        return Err(DecodeError::TooDeep(depth));
+    }
+    let text = node
+        .children()
+        .filter(Node::is_text)
+        .filter_map(|child| child.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let has_children = node.children().any(|child| child.is_element());
+    if node.attributes().len() == 0 && !has_children {
+        return Ok(if text.is_empty() {
+            Value::Null
+        } else {
+            Value::String(text)
+        });
+    }
+
+    let mut object = Map::new();
+    for attribute in node.attributes() {
+        let name = match attribute
+            .namespace()
+            .and_then(|uri| node.lookup_prefix(uri))
+        {
+            Some(prefix) if !prefix.is_empty() => format!("{}:{}", prefix, attribute.name()),
+            _ => attribute.name().to_string(),
+        };
+        insert(
+            &mut object,
+            format!("{}{}", convention.attribute_prefix, name),
+            Value::String(attribute.value().to_string()),
+        );
+    }
+    for child in node.children().filter(Node::is_element) {
+        insert(
+            &mut object,
+            qualified_name(child),
+            element(child, convention, depth + 1, max_depth)?,
+        );
+    }
+    if !text.is_empty() {
+        insert(
+            &mut object,
+            convention.text_key.clone(),
+            Value::String(text),
+        );
+    }
+    Ok(Value::Object(object))
+}
+
+/// Add a member, collecting the values of a repeated name in an array
+fn insert(object: &mut Map<String, Value>, name: String, value: Value) {
+    match object.get_mut(&name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            object.insert(name, value);
+        }
+    }
+}
+
+/// An element's name with its namespace prefix, if it has one
+fn qualified_name(node: Node) -> String {
+    let name = node.tag_name();
+    match name.namespace().and_then(|uri| node.lookup_prefix(uri)) {
+        Some(prefix) if !prefix.is_empty() => format!("{}:{}", prefix, name.name()),
+        _ => name.name().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn decode(xml: &str, convention: &XmlConvention) -> Result<Value, RjdError> {
+        decode_xml(
+            xml.as_bytes(),
+            convention,
+            "test.xml",
+            &LoadConfig::default(),
+        )
+    }
+
+    #[test]
+    fn test_xml_conventions() {
+        let xml = r#"<?xml version="1.0"?>
+            <!-- deployment -->
+            <config xmlns="urn:app" xmlns:x="urn:ext" version="2">
+                <empty/>
+                <server id="a">web <![CDATA[<1>]]> <port>80</port> tail</server>
+                <server id="b"/>
+                <x:flag x:on="yes">true</x:flag>
+            </config>"#;
+        assert_eq!(
+            decode(xml, &XmlConvention::default()).unwrap(),
+            json!({"config": {
+                "@version": "2",
+                "empty": null,
+                "server": [
+                    {"@id": "a", "port": "80", "#text": "web <1> tail"},
+                    {"@id": "b"}
+                ],
+                "x:flag": {"@x:on": "yes", "#text": "true"}
+            }})
+        );
+
+        let convention = XmlConvention {
+            attribute_prefix: "-".to_string(),
+            text_key: "$".to_string(),
+        };
+        assert_eq!(
+            decode(r#"<a b="1">c</a>"#, &convention).unwrap(),
+            json!({"a": {"-b": "1", "$": "c"}})
+        );
+    }
+
+    #[test]
+    fn test_xml_entities_and_errors() {
+        let xml = r#"<!DOCTYPE a [<!ENTITY who "world">]><a>hello &who; &amp; &#33;</a>"#;
+        assert_eq!(
+            decode(xml, &XmlConvention::default()).unwrap(),
+            json!({"a": "hello world & !"})
+        );
+
+        let error = decode("<a><b></a>", &XmlConvention::default()).unwrap_err();
+        assert!(matches!(
+            error,
+            RjdError::Decode {
+                format: InputFormat::Xml,
+                ..
+            }
+        ));
+        assert!(decode_xml(
+            b"<a>\xff</a>",
+            &XmlConvention::default(),
+            "t",
+            &LoadConfig::default()
+        )
+        .is_err());
+
+        let config = LoadConfig::with_limits(1024, 2);
+        let error = decode_xml(
+            b"<a><b><c/></b></a>",
+            &XmlConvention::default(),
+            "t",
+            &config,
+        )
+        .unwrap_err();
+        assert!(!matches!(error, RjdError::Decode { .. }));
+
+        // Markup that is not an element does not count towards the depth
+        let xml = r#"<!DOCTYPE a [<!-- it's --><!ENTITY e "<b>">]><?pi <x>?><a k="c>"><!-- <d> --><![CDATA[<e>]]><b/></a>"#;
+        let config = LoadConfig::with_limits(1024, 3);
+        assert!(decode_xml(xml.as_bytes(), &XmlConvention::default(), "t", &config).is_ok());
+
+        // Deep enough to overflow the stack if converted without a limit
+        let xml = format!("{}{}", "<a>".repeat(50_000), "</a>".repeat(50_000));
+        let error = decode(&xml, &XmlConvention::default()).unwrap_err();
+        assert!(matches!(
+            error,
+            RjdError::JsonDepthExceeded {
+                depth: 1001,
+                limit: 1000
+            }
+        ));
+    }
+}
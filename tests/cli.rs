@@ -148,6 +148,32 @@ fn test_stdin_flag() {
     assert!(stdout.contains("modified"));
 }
 
+#[test]
+fn test_stdin_pair_with_separator() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg("--stdin")
+        .arg("--stdin-separator")
+        .arg("---")
+        .write_stdin("{\"a\": 1}\n---\n{\"a\": 2}\n");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stdout["modified"][0]["newValue"], 2);
+}
+
+#[test]
+fn test_stdin_pair_concatenated() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg("--stdin")
+        .write_stdin(r#"{"a": 1} {"a": 1, "b": true}"#);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stdout["added"][0]["path"], "b");
+}
+
 #[test]
 fn test_ignore_json_option() {
     let dir = TempDir::new().unwrap();
@@ -176,7 +202,7 @@ fn test_ignore_json_option() {
 fn test_ignore_json_multiple_patterns() {
     let dir = TempDir::new().unwrap();
     let ignore_file = dir.path().join("ignore.json");
-    fs::write(&ignore_file, r#"["/user/id", "/tags"]"#).unwrap();
+    fs::write(&ignore_file, r#"["/user/id", "/tags/**"]"#).unwrap();
 
     #[allow(deprecated)]
     let mut cmd = Command::cargo_bin("rjd").unwrap();
@@ -241,7 +267,7 @@ fn test_ignore_json_object_format() {
         r#"{"user": {"id": 1, "name": "John"}, "tags": ["a", "b"], "age": 30}"#,
     )
     .unwrap();
-    fs::write(&file2, r#"{"user": {"id": true}, "tags": true}"#).unwrap();
+    fs::write(&file2, r#"{"user": {"id": true}, "tags": {"**": true}}"#).unwrap();
 
     #[allow(deprecated)]
     let mut cmd = Command::cargo_bin("rjd").unwrap();
@@ -265,8 +291,8 @@ fn test_ignore_json_invalid_path() {
     let dir = TempDir::new().unwrap();
     let ignore_file = dir.path().join("ignore.json");
 
-    // Write an array with paths that don't start with /
-    fs::write(&ignore_file, r#"["user/id", "/valid/path"]"#).unwrap();
+    // Write an array with a path that is neither a valid pointer nor dot notation
+    fs::write(&ignore_file, r#"["user[id]", "/valid/path"]"#).unwrap();
 
     #[allow(deprecated)]
     let mut cmd = Command::cargo_bin("rjd").unwrap();
@@ -468,3 +494,3007 @@ fn test_error_message_clarity() {
         stderr.contains("file") || stderr.contains("not found") || stderr.contains("No such file")
     );
 }
+
+#[test]
+fn test_require_paths_allows_changes_within_set() {
+    let dir = TempDir::new().unwrap();
+    let allowed = dir.path().join("allowed.json");
+    fs::write(&allowed, r#"["/version"]"#).unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"version": "1.0", "name": "app"}"#)
+        .arg(r#"{"version": "1.1", "name": "app"}"#)
+        .arg("--require-paths")
+        .arg(&allowed);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_require_paths_rejects_changes_outside_set() {
+    let dir = TempDir::new().unwrap();
+    let allowed = dir.path().join("allowed.json");
+    fs::write(&allowed, r#"["/version"]"#).unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"version": "1.0", "name": "app"}"#)
+        .arg(r#"{"version": "1.1", "name": "other"}"#)
+        .arg("--require-paths")
+        .arg(&allowed);
+    let output = cmd.output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    // The diff is still printed before the policy failure
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("modified"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("/name"));
+}
+
+#[test]
+fn test_must_change_reports_missing_paths() {
+    let dir = TempDir::new().unwrap();
+    let required = dir.path().join("required.json");
+    fs::write(&required, r#"["/changelog"]"#).unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"version": "1.0"}"#)
+        .arg(r#"{"version": "1.1"}"#)
+        .arg("--must-change")
+        .arg(&required);
+    let output = cmd.output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("/changelog"));
+}
+
+#[test]
+fn test_changes_output_includes_format_version() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"a": 1}"#).arg(r#"{"a": 2}"#);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stdout["formatVersion"], 2);
+}
+
+#[test]
+fn test_format_version_1_omits_version_field() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"a": 1}"#)
+        .arg(r#"{"a": 2}"#)
+        .arg("--format-version")
+        .arg("1");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("formatVersion"));
+}
+
+#[test]
+fn test_schema_subcommand() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg("schema").arg("--format").arg("changes");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["properties"]["formatVersion"]["const"], 2);
+}
+
+#[test]
+fn test_extract_json_from_log_files() {
+    let dir = TempDir::new().unwrap();
+    let file1 = dir.path().join("before.log");
+    let file2 = dir.path().join("after.log");
+    fs::write(&file1, "[INFO] boot\n[INFO] config={\"port\": 80} loaded\n").unwrap();
+    fs::write(
+        &file2,
+        "[INFO] boot\n[INFO] config={\"port\": 8080} loaded\n",
+    )
+    .unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(&file1).arg(&file2).arg("--extract-json");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stdout["modified"][0]["path"], "port");
+    assert_eq!(stdout["modified"][0]["newValue"], 8080);
+}
+
+#[test]
+fn test_extract_json_nth_document() {
+    let dir = TempDir::new().unwrap();
+    let file1 = dir.path().join("a.txt");
+    let file2 = dir.path().join("b.txt");
+    fs::write(&file1, r#"first {"x": 1} second {"y": 1}"#).unwrap();
+    fs::write(&file2, r#"first {"x": 1} second {"y": 2}"#).unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(&file1)
+        .arg(&file2)
+        .arg("--extract-json")
+        .arg("--nth")
+        .arg("2");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stdout["modified"][0]["path"], "y");
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_parallel_flag_matches_sequential_output() {
+    let old = r#"{"a": {"x": 1}, "b": [1, 2], "c": "gone"}"#;
+    let new = r#"{"a": {"x": 2}, "b": [1], "d": true}"#;
+
+    #[allow(deprecated)]
+    let sequential = Command::cargo_bin("rjd")
+        .unwrap()
+        .arg(old)
+        .arg(new)
+        .output()
+        .unwrap();
+    #[allow(deprecated)]
+    let parallel = Command::cargo_bin("rjd")
+        .unwrap()
+        .arg(old)
+        .arg(new)
+        .arg("--parallel")
+        .output()
+        .unwrap();
+    assert!(parallel.status.success());
+    assert_eq!(parallel.stdout, sequential.stdout);
+}
+
+#[test]
+fn test_output_format_summary() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"dataset": [1, 2, 3], "v": 1}"#)
+        .arg(r#"{"dataset": [1, 2, 3, 4, 5, 6, 7, 8], "v": 2}"#)
+        .arg("--format")
+        .arg("summary")
+        .arg("--top")
+        .arg("1");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stdout["total"], 6);
+    assert_eq!(stdout["largestByBytes"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_resolve_refs_ignores_content_moved_between_files() {
+    let dir = TempDir::new().unwrap();
+    // Old layout: everything inline. New layout: limits moved to a shared file.
+    let old = dir.path().join("old.json");
+    let new = dir.path().join("new.json");
+    fs::write(&old, r#"{"name": "svc", "limits": {"cpu": 2}}"#).unwrap();
+    fs::write(
+        &new,
+        r#"{"name": "svc", "limits": {"$ref": "shared.json#/limits"}}"#,
+    )
+    .unwrap();
+    fs::write(dir.path().join("shared.json"), r#"{"limits": {"cpu": 2}}"#).unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(&old).arg(&new).arg("--resolve-refs");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(stdout["added"].as_array().unwrap().is_empty());
+    assert!(stdout["removed"].as_array().unwrap().is_empty());
+    assert!(stdout["modified"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_report_format_only_differences() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"a": 1e2, "b": true}"#)
+        .arg(r#"{"b":true,"a":100.0}"#)
+        .arg("--report-format-only");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Key order differs"));
+    assert!(stderr.contains("Number formatting differs"));
+    assert!(stderr.contains("/a"));
+    assert!(stderr.contains("Whitespace differs"));
+}
+
+#[test]
+fn test_report_format_only_keeps_number_changes() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"a": 1, "b": true}"#)
+        .arg(r#"{"a": 1.0, "b": true}"#)
+        .arg("--report-format-only");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stdout["modified"][0]["path"], "a");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Number formatting differs at:\n  /a"));
+}
+
+#[test]
+fn test_max_diff_depth_reports_subtree() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"a": {"b": {"c": 1, "d": 1}}}"#)
+        .arg(r#"{"a": {"b": {"c": 2, "d": 2}}}"#)
+        .arg("--max-diff-depth")
+        .arg("1");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let modified = stdout["modified"].as_array().unwrap();
+    assert_eq!(modified.len(), 1);
+    assert_eq!(modified[0]["path"], "a");
+}
+
+#[test]
+fn test_color_always_and_env_conventions() {
+    #[allow(deprecated)]
+    let colored = Command::cargo_bin("rjd")
+        .unwrap()
+        .arg(r#"{"a": 1}"#)
+        .arg(r#"{"a": 2}"#)
+        .arg("--color")
+        .arg("always")
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&colored.stdout).contains("\x1b["));
+
+    #[allow(deprecated)]
+    let forced = Command::cargo_bin("rjd")
+        .unwrap()
+        .arg(r#"{"a": 1}"#)
+        .arg(r#"{"a": 2}"#)
+        .env("FORCE_COLOR", "1")
+        .env_remove("NO_COLOR")
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&forced.stdout).contains("\x1b["));
+
+    #[allow(deprecated)]
+    let disabled = Command::cargo_bin("rjd")
+        .unwrap()
+        .arg(r#"{"a": 1}"#)
+        .arg(r#"{"a": 2}"#)
+        .env("FORCE_COLOR", "1")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(!String::from_utf8_lossy(&disabled.stdout).contains("\x1b["));
+}
+
+#[test]
+fn test_max_value_length_truncates_output() {
+    let blob = "x".repeat(1000);
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"blob": "a"}"#)
+        .arg(format!(r#"{{"blob": "{}"}}"#, blob))
+        .arg("--max-value-length")
+        .arg("10");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        stdout["modified"][0]["newValue"],
+        format!("{}… (1000 chars)", "x".repeat(10))
+    );
+}
+
+#[test]
+fn test_context_includes_surrounding_keys() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"user": {"name": "Alice", "age": 25}}"#)
+        .arg(r#"{"user": {"name": "Alice", "age": 26}}"#)
+        .arg("--context")
+        .arg("1");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stdout["modified"][0]["context"]["path"], "user");
+    assert_eq!(
+        stdout["modified"][0]["context"]["value"],
+        serde_json::json!({"name": "Alice", "age": 26})
+    );
+}
+
+#[test]
+fn test_output_writes_report_to_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("report.json");
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"a": 1}"#)
+        .arg(r#"{"a": 2}"#)
+        .arg("--output")
+        .arg(format!("file:{}", path.display()));
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(report["modified"][0]["newValue"], 2);
+}
+
+#[test]
+fn test_output_rejects_unknown_scheme() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"a": 1}"#)
+        .arg(r#"{"a": 2}"#)
+        .arg("--output")
+        .arg("ftp://example.com/report.json");
+    let output = cmd.output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unsupported output scheme"));
+}
+
+#[cfg(not(feature = "http"))]
+#[test]
+fn test_output_http_requires_feature() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"a": 1}"#)
+        .arg(r#"{"a": 2}"#)
+        .arg("--output")
+        .arg("https://example.com/hook");
+    let output = cmd.output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("`http` feature"));
+}
+
+#[cfg(not(feature = "parallel"))]
+#[test]
+fn test_parallel_requires_feature() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.args([r#"{"a": 1}"#, r#"{"a": 2}"#, "--parallel"]);
+    let output = cmd.output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("`parallel` feature"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn test_output_http_sends_format_content_type() {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    // Accept one request and return its Content-Type header and body
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let (mut content_type, mut length) = (String::new(), 0);
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(": ") {
+                match name.to_ascii_lowercase().as_str() {
+                    "content-type" => content_type = value.to_string(),
+                    "content-length" => length = value.parse().unwrap(),
+                    _ => {}
+                }
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        (content_type, String::from_utf8(body).unwrap())
+    });
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.args([
+        r#"{"a": 1}"#,
+        r#"{"a": 2}"#,
+        "--format",
+        "junit",
+        "--output",
+        &url,
+    ]);
+    let output = cmd.output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let (content_type, body) = server.join().unwrap();
+    assert_eq!(content_type, "application/xml");
+    assert!(body.starts_with("<?xml"));
+}
+
+#[test]
+fn test_sample_with_seed_is_reproducible() {
+    let old = r#"{"a": 1, "b": 2, "c": 3, "d": 4, "e": 5}"#;
+    let new = r#"{"a": 10, "b": 20, "c": 30, "d": 40, "e": 50}"#;
+    let run = |seed: &str| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        cmd.arg(old)
+            .arg(new)
+            .args(["--sample", "2", "--seed", seed]);
+        cmd.output().unwrap()
+    };
+
+    let first = run("42");
+    assert!(first.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&first.stdout).unwrap();
+    assert_eq!(report["modified"].as_array().unwrap().len(), 2);
+    assert!(String::from_utf8_lossy(&first.stderr).contains("--sample 2 --seed 42"));
+    assert_eq!(run("42").stdout, first.stdout);
+}
+
+#[test]
+fn test_sample_seed_recorded_in_metadata() {
+    let old = r#"{"a": 1, "b": 2, "c": 3, "d": 4, "e": 5}"#;
+    let new = r#"{"a": 10, "b": 20, "c": 30, "d": 40, "e": 50}"#;
+    let run = |extra: &[&str]| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        cmd.arg(old)
+            .arg(new)
+            .args(["--sample", "2", "--with-metadata"])
+            .args(extra);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    // Without --seed, the random seed is recorded and reproduces the sample
+    let first = run(&[]);
+    let seed = first["metadata"]["options"]["seed"].as_u64().unwrap();
+    let again = run(&["--seed", &seed.to_string()]);
+    assert_eq!(again["metadata"]["options"]["seed"], seed);
+    assert_eq!(again["result"], first["result"]);
+}
+
+#[test]
+fn test_max_changes_reports_partial_result() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"a": 1, "b": 1, "c": 1}"#)
+        .arg(r#"{"a": 2, "b": 2, "c": 2}"#)
+        .arg("--max-changes")
+        .arg("1");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stdout["modified"].as_array().unwrap().len(), 1);
+    assert_eq!(stdout["truncated"][0]["path"], "b");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Warning: diff truncated at 'b'"));
+}
+
+#[test]
+fn test_empty_output_controls() {
+    let run = |extra: &[&str]| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        cmd.arg(r#"{"a": 1}"#).arg(r#"{"a": 1}"#).args(extra);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert!(run(&[]).contains("\"added\": []"));
+    assert_eq!(run(&["--print-empty-message"]), "No differences found.\n");
+    assert_eq!(run(&["--empty-output", "{}"]), "{}\n");
+    assert_eq!(run(&["--empty-output", ""]), "");
+    assert_eq!(
+        run(&["--format", "rfc6902", "--empty-output", "null"]),
+        "null\n"
+    );
+
+    // Non-empty results are unaffected
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"a": 1}"#)
+        .arg(r#"{"a": 2}"#)
+        .arg("--print-empty-message");
+    let stdout = String::from_utf8(cmd.output().unwrap().stdout).unwrap();
+    assert!(stdout.contains("\"modified\""));
+}
+
+#[test]
+fn test_ignore_jsonpath_query() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(
+        r#"{"users": [{"name": "a", "sessionToken": "x"}, {"name": "b", "sessionToken": "y"}]}"#,
+    )
+    .arg(r#"{"users": [{"name": "a", "sessionToken": "z"}, {"name": "c", "sessionToken": "w"}]}"#)
+    .arg("--ignore")
+    .arg("$.users[*].sessionToken");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let modified = stdout["modified"].as_array().unwrap();
+    assert_eq!(modified.len(), 1);
+    assert_eq!(modified[0]["path"], "users[1].name");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"a": 1}"#)
+        .arg(r#"{"a": 2}"#)
+        .arg("--ignore")
+        .arg("$.a[");
+    let output = cmd.output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid JSONPath pattern"));
+}
+
+#[test]
+fn test_ignore_key_case_flag() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"Name": "x", "Count": 1}"#)
+        .arg(r#"{"name": "x", "count": 2}"#)
+        .arg("--ignore-key-case");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(stdout["added"].as_array().unwrap().is_empty());
+    assert!(stdout["removed"].as_array().unwrap().is_empty());
+    assert_eq!(stdout["modified"][0]["path"], "count");
+}
+
+#[test]
+fn test_normalize_dates_flags() {
+    let old = r#"{"created": "2024-01-01T00:00:00Z", "seen": "2024-01-01T10:00:00Z"}"#;
+    let new = r#"{"created": "2024-01-01T00:00:00+00:00", "seen": "2024-01-01T10:00:03Z"}"#;
+    let modified_paths = |args: &[&str]| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        cmd.arg(old).arg(new).args(args);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        stdout["modified"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["path"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(modified_paths(&[]), vec!["created", "seen"]);
+    assert_eq!(modified_paths(&["--normalize-dates"]), vec!["seen"]);
+    assert!(modified_paths(&["--normalize-dates", "--date-tolerance", "5s"]).is_empty());
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(old)
+        .arg(new)
+        .args(["--normalize-dates", "--date-tolerance", "5 days"]);
+    let output = cmd.output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown unit"));
+}
+
+#[test]
+fn test_coerce_number_flags() {
+    let run = |args: &[&str]| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        cmd.arg(r#"{"a": 1, "b": "2"}"#)
+            .arg(r#"{"a": 1.0, "b": 2}"#)
+            .args(args);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        stdout["modified"].as_array().unwrap().len()
+    };
+
+    assert_eq!(run(&[]), 2);
+    assert_eq!(run(&["--coerce-numbers"]), 1);
+    assert_eq!(run(&["--coerce-number-strings"]), 0);
+}
+
+#[test]
+fn test_unwrap_roots() {
+    let old = r#"{"data": {"id": 1, "name": "a"}, "meta": 1}"#;
+    let new = r#"{"result": {"payload": {"id": 1, "name": "b"}}, "meta": 2}"#;
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(old)
+        .arg(new)
+        .args(["--unwrap", "old:/data", "--unwrap", "new:/result/payload"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        stdout["modified"],
+        json!([{"path": "name", "oldValue": "a", "newValue": "b"}])
+    );
+    assert!(stdout["added"].as_array().unwrap().is_empty());
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(old)
+        .arg(new)
+        .args(["--unwrap", "old:data", "--unwrap", "new:result.payload"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stdout["modified"][0]["path"], "name");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(old).arg(new).args(["--unwrap", "/meta"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stdout["modified"][0]["path"], "");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(old).arg(new).args(["--unwrap", "new:/data"]);
+    let output = cmd.output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("no value at '/data' in the new input")
+    );
+}
+
+#[test]
+fn test_null_as_absent_flag() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"a": null, "b": 1}"#)
+        .arg(r#"{"b": 2, "c": null}"#)
+        .arg("--null-as-absent");
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(stdout["added"].as_array().unwrap().is_empty());
+    assert!(stdout["removed"].as_array().unwrap().is_empty());
+    assert_eq!(stdout["modified"][0]["path"], "b");
+}
+
+#[test]
+fn test_warnings_for_ambiguous_keys() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"a.b": 1}"#).arg(r#"{"a.b": 2}"#);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Warning: at 'a.b'"), "{}", stderr);
+    let stdout: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stdout["warnings"][0]["path"], "a.b");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(r#"{"a.b": 1}"#)
+        .arg(r#"{"a.b": 2}"#)
+        .args(["--ignore", "$['a.b']"]);
+    let output = cmd.output().unwrap();
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_max_drift_gate() {
+    let run = |max_drift: &str| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        cmd.arg(r#"{"a": 1, "b": 2, "c": 3, "d": 4}"#)
+            .arg(r#"{"a": 1, "b": 2, "c": 3, "d": 5}"#)
+            .args(["--max-drift", max_drift]);
+        cmd.output().unwrap()
+    };
+
+    assert!(run("25%").status.success());
+    let output = run("10%");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Drift of 25.0% (1 of 4 values changed)")
+    );
+    assert!(!output.stdout.is_empty(), "the report is still printed");
+    assert!(String::from_utf8_lossy(&run("150%").stderr).contains("expected a percentage"));
+}
+
+#[test]
+fn test_empty_as_absent_flag() {
+    let run = |args: &[&str]| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        cmd.arg(r#"{"tags": [], "meta": {}, "n": 1}"#)
+            .arg(r#"{"meta": [], "n": 1}"#)
+            .args(args)
+            .args(["--format", "rfc6902"]);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    assert_eq!(
+        run(&[]),
+        json!([
+            {"op": "remove", "path": "/tags"},
+            {"op": "replace", "path": "/meta", "value": []}
+        ])
+    );
+    assert_eq!(run(&["--empty-as-absent"]), json!([]));
+}
+
+#[test]
+fn test_patch_in_place() {
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join("config.json");
+    let patch = dir.path().join("patch.json");
+    let original = "{\n    \"name\": \"app\",\n    \"port\": 80,\n    \"tags\": [\"a\"]\n}\n";
+    fs::write(&target, original).unwrap();
+    fs::write(
+        &patch,
+        r#"[{"op": "replace", "path": "/port", "value": 8080}, {"op": "add", "path": "/debug", "value": true}]"#,
+    )
+    .unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg("patch-in-place")
+        .arg(&target)
+        .arg("--with")
+        .arg(&patch)
+        .arg("--backup");
+    let output = cmd.output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        fs::read_to_string(&target).unwrap(),
+        "{\n    \"name\": \"app\",\n    \"port\": 8080,\n    \"tags\": [\"a\"],\n    \"debug\": true\n}\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("config.json.bak")).unwrap(),
+        original
+    );
+
+    // A patch that does not apply leaves the file alone
+    let patched = fs::read_to_string(&target).unwrap();
+    fs::write(&patch, r#"[{"op": "remove", "path": "/missing"}]"#).unwrap();
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg("patch-in-place")
+        .arg(&target)
+        .arg("--with")
+        .arg(&patch);
+    let output = cmd.output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(fs::read_to_string(&target).unwrap(), patched);
+    assert_eq!(
+        fs::read_dir(dir.path()).unwrap().count(),
+        3,
+        "no temporary file is left"
+    );
+}
+
+#[test]
+fn test_full_after_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let old = dir.path().join("old.json");
+    let new = dir.path().join("new.json");
+    fs::write(
+        &old,
+        "{\n    \"name\": \"app\",\n    \"tags\": [\"a\", \"b\"],\n    \"port\": 80,\n    \"debug\": false\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        &new,
+        r#"{"name": "app", "tags": ["a", "b"], "port": 8080, "limits": {"cpu": 2}}"#,
+    )
+    .unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(&old)
+        .arg(&new)
+        .args(["--format", "full-after"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\n    \"name\": \"app\",\n    \"tags\": [\"a\", \"b\"],\n    \"port\": 8080,\n    \"limits\": {\n        \"cpu\": 2\n    }\n}\n"
+    );
+}
+
+#[test]
+fn test_sarif_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let old = dir.path().join("old.json");
+    let new = dir.path().join("new.json");
+    fs::write(&old, r#"{"port": 80, "debug": true}"#).unwrap();
+    fs::write(&new, r#"{"port": 8080}"#).unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(&old)
+        .arg(&new)
+        .args(["--format", "sarif"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let log: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let results = log["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["ruleId"], "removed");
+    assert_eq!(
+        results[0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+        new.to_str().unwrap()
+    );
+
+    // Inline JSON has no file to point at
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args(["{\"a\": 1}", "{\"a\": 2}", "--format", "sarif"])
+        .output()
+        .unwrap();
+    let log: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let location = &log["runs"][0]["results"][0]["locations"][0];
+    assert!(location.get("physicalLocation").is_none());
+    assert_eq!(location["logicalLocations"][0]["fullyQualifiedName"], "$.a");
+}
+
+#[test]
+fn test_junit_format() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([
+            r#"{"a": 1, "b": 2}"#,
+            r#"{"a": 1, "b": 3}"#,
+            "--format",
+            "junit",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(r#"tests="1" failures="1""#));
+    assert!(stdout.contains(r#"<testcase name="$.b" classname="modified">"#));
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([r#"{"a": 1}"#, r#"{"a": 1}"#, "--format", "junit"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(r#"failures="0""#));
+    assert!(stdout.contains(r#"<testcase name="$" classname="rjd"/>"#));
+}
+
+#[test]
+fn test_before_format() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([
+            r#"{"name": "app", "port": 80, "debug": true}"#,
+            r#"{"name": "app", "port": 8080, "tls": true}"#,
+            "--format",
+            "before",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let before: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(before, json!({"port": 80, "debug": true}));
+}
+
+#[test]
+fn test_annotated_format() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([
+            r#"{"port": 80, "debug": true, "tags": ["a"]}"#,
+            r#"{"port": 8080, "tags": ["a", "b"]}"#,
+            "--format",
+            "annotated",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let annotated: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        annotated,
+        json!({
+            "port": {"__old": 80, "__new": 8080},
+            "tags": [[" ", "a"], ["+", "b"]],
+            "debug__deleted": true
+        })
+    );
+}
+
+#[test]
+fn test_with_metadata_envelope() {
+    let dir = tempfile::tempdir().unwrap();
+    let old = dir.path().join("old.json");
+    fs::write(&old, r#"{"a": 1, "token": "x"}"#).unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(&old)
+        .arg(r#"{"a": 2, "token": "y"}"#)
+        .args([
+            "--with-metadata",
+            "--ignore",
+            "/token",
+            "--format",
+            "rfc6902",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let envelope: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let metadata = &envelope["metadata"];
+    assert_eq!(metadata["tool"]["name"], "rjd");
+    assert_eq!(metadata["inputs"]["old"], old.to_str().unwrap());
+    assert_eq!(metadata["inputs"]["new"], "<inline>");
+    assert_eq!(metadata["options"]["ignore"], json!(["/token"]));
+    assert_eq!(metadata["options"]["format"], "rfc6902");
+    assert_eq!(metadata["changes"]["total"], 1);
+    assert!(metadata["generatedAt"].as_str().unwrap().ends_with('Z'));
+    assert_eq!(
+        envelope["result"],
+        json!([{"op": "replace", "path": "/a", "value": 2}])
+    );
+
+    // Non-JSON formats cannot be wrapped
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args(["1", "2", "--with-metadata", "--format", "junit"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("JSON output format"));
+}
+
+#[test]
+fn test_output_file_infers_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let report = dir.path().join("report.sarif");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args(["{\"a\": 1}", "{\"a\": 2}", "-o"])
+        .arg(&report)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let log: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(log["version"], "2.1.0");
+
+    // An explicit --format wins, and the file is replaced without leftovers
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([
+            "{\"a\": 1}",
+            "{\"a\": 2}",
+            "--format",
+            "rfc6902",
+            "--output",
+        ])
+        .arg(&report)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let patch: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(patch, json!([{"op": "replace", "path": "/a", "value": 2}]));
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_config_file_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join(".rjd.toml"),
+        "format = \"rfc6902\"\nignore = [\"/b\"]\nsort = true\n",
+    )
+    .unwrap();
+    let nested = dir.path().join("nested");
+    fs::create_dir(&nested).unwrap();
+    let (old, new) = (r#"{"a": 1, "b": 1}"#, r#"{"a": 2, "b": 2}"#);
+
+    let run = |extra: &[&str]| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        let output = cmd
+            .current_dir(&nested)
+            .args([old, new])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    // Found in a parent directory
+    assert_eq!(
+        run(&[]),
+        json!([{"op": "replace", "path": "/a", "value": 2}])
+    );
+    // Command-line options win
+    assert_eq!(
+        run(&["--format", "changes"])["modified"]
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(
+        run(&["--ignore", "/a"]),
+        json!([{"op": "replace", "path": "/b", "value": 2}])
+    );
+    assert_eq!(
+        run(&["--no-config"])["modified"].as_array().unwrap().len(),
+        2
+    );
+
+    // A JSON config with an unknown option is an error
+    fs::write(nested.join(".rjdrc.json"), r#"{"formatt": "after"}"#).unwrap();
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd.current_dir(&nested).args([old, new]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown option 'formatt'"));
+
+    // Options that read or write files or change limits are refused
+    for option in [
+        r#"{"output": "report.json"}"#,
+        r#"{"save-changes": "changes.json"}"#,
+        r#"{"follow-symlinks": true}"#,
+        r#"{"max-file-size": 1}"#,
+        r#"{"stdin": true}"#,
+        r#"{"inline": true}"#,
+        r#"{"no-config": true}"#,
+    ] {
+        fs::write(nested.join(".rjdrc.json"), option).unwrap();
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        let output = cmd.current_dir(&nested).args([old, new]).output().unwrap();
+        assert_eq!(output.status.code(), Some(1), "{}", option);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be set in a config file"));
+    }
+    assert!(!nested.join("report.json").exists());
+}
+
+#[test]
+fn test_rev_subcommand() {
+    let dir = tempfile::tempdir().unwrap();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .current_dir(dir.path())
+            .args(["-c", "user.name=rjd", "-c", "user.email=rjd@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    };
+    git(&["init", "-q"]);
+    let file = dir.path().join("config.json");
+    fs::write(&file, r#"{"port": 80}"#).unwrap();
+    git(&["add", "config.json"]);
+    git(&["commit", "-q", "-m", "first"]);
+    fs::write(&file, r#"{"port": 8080}"#).unwrap();
+    git(&["commit", "-q", "-am", "second"]);
+    fs::write(&file, r#"{"port": 8080, "debug": true}"#).unwrap();
+
+    let rev = |old: &str, new: &str| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        cmd.current_dir(dir.path())
+            .args(["rev", old, new, "--format", "rfc6902"])
+            .output()
+            .unwrap()
+    };
+
+    let output = rev("HEAD~1:config.json", "HEAD:config.json");
+    assert!(output.status.success());
+    let patch: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        patch,
+        json!([{"op": "replace", "path": "/port", "value": 8080}])
+    );
+
+    let output = rev("HEAD:./config.json", "config.json");
+    let patch: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        patch,
+        json!([{"op": "add", "path": "/debug", "value": true}])
+    );
+
+    let output = rev("HEAD~5:config.json", "HEAD:config.json");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("from git"));
+}
+
+#[test]
+fn test_help_full_and_manpage() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd.arg("--help").output().unwrap();
+    let help = String::from_utf8(output.stdout).unwrap();
+    assert!(help.contains("Examples:"));
+    assert!(help.contains("rjd rev HEAD~1:config.json HEAD:config.json"));
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd.arg("help-full").output().unwrap();
+    assert!(output.status.success());
+    let help = String::from_utf8(output.stdout).unwrap();
+    assert!(help.contains("Examples:"));
+    assert!(help.contains("rjd patch-in-place"));
+    assert!(help.contains("--manpage"));
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd.args(["help-full", "--manpage"]).output().unwrap();
+    assert!(output.status.success());
+    let manpage = String::from_utf8(output.stdout).unwrap();
+    assert!(manpage.contains(".TH rjd 1"));
+    assert!(manpage.contains(".SH EXAMPLES"));
+    assert!(manpage.contains("\\fBrjd rev HEAD~1:config.json HEAD:config.json\\fR"));
+    assert!(!manpage.contains(".SH EXTRA"));
+}
+
+#[test]
+fn test_save_changes_and_render() {
+    let dir = TempDir::new().unwrap();
+    let saved = dir.path().join("run.changes.json");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([
+            r#"{"a": 1, "b": {"c": null}}"#,
+            r#"{"a": 2, "b": {}}"#,
+            "--save-changes",
+        ])
+        .arg(&saved)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let direct: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let render = |args: &[&str]| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        let output = cmd.arg("render").arg(&saved).args(args).output().unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+    assert_eq!(render(&[]), direct);
+    assert_eq!(
+        render(&["--format", "annotated"]),
+        json!({"a": {"__old": 1, "__new": 2}, "b": {"c__deleted": null}})
+    );
+    assert_eq!(
+        render(&["--format", "rfc6902"]),
+        json!([{"op": "remove", "path": "/b/c"}, {"op": "replace", "path": "/a", "value": 2}])
+    );
+
+    // The changes format is not a change set
+    let report = dir.path().join("report.json");
+    fs::write(&report, serde_json::to_string(&direct).unwrap()).unwrap();
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd.arg("render").arg(&report).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("changeSetVersion"));
+}
+
+#[test]
+fn test_compare_changes_subcommand() {
+    let dir = TempDir::new().unwrap();
+    let save = |name: &str, new: &str| {
+        let path = dir.path().join(name);
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        let output = cmd
+            .args([r#"{"a": 1, "b": 1, "c": 1}"#, new, "--save-changes"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        path
+    };
+    let first = save("first.json", r#"{"a": 2, "b": 2, "c": 1}"#);
+    let second = save("second.json", r#"{"a": 2, "b": 3, "c": 2}"#);
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg("compare-changes")
+        .arg(&first)
+        .arg(&second)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report["common"]["modified"],
+        json!([{"path": "a", "oldValue": 1, "newValue": 2}])
+    );
+    assert_eq!(
+        report["onlyInFirst"]["modified"],
+        json!([{"path": "b", "oldValue": 1, "newValue": 2}])
+    );
+    assert_eq!(
+        report["summary"],
+        json!({"common": 1, "onlyInFirst": 1, "onlyInSecond": 2})
+    );
+}
+
+#[test]
+fn test_schema_aware_diff() {
+    let dir = TempDir::new().unwrap();
+    let schema = dir.path().join("schema.json");
+    fs::write(
+        &schema,
+        r#"{
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": {"type": "string"},
+                "items": {
+                    "type": "array",
+                    "items": {"required": ["id"], "properties": {"v": {"type": "integer"}}}
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([
+            r#"{"name": "a", "items": [{"id": 1, "v": 1}, {"id": 2, "v": 2}]}"#,
+            r#"{"items": [{"id": 2, "v": "2"}, {"id": 1, "v": 1}, {"id": 3, "v": 3}]}"#,
+            "--schema",
+        ])
+        .arg(&schema)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report["added"],
+        json!([{
+            "path": "items[2]",
+            "value": {"id": 3, "v": 3},
+            "schemaPath": "#/properties/items/items",
+            "compatibility": "compatible"
+        }])
+    );
+    assert_eq!(report["removed"][0]["compatibility"], "breaking");
+    assert_eq!(
+        report["removed"][0]["reason"],
+        "required property 'name' removed"
+    );
+    assert_eq!(
+        report["modified"],
+        json!([{
+            "path": "items[0].v",
+            "oldValue": 2,
+            "newValue": "2",
+            "schemaPath": "#/properties/items/items/properties/v",
+            "compatibility": "breaking",
+            "reason": "type changed from number to string"
+        }])
+    );
+}
+
+#[test]
+fn test_rules_and_fail_on() {
+    let dir = TempDir::new().unwrap();
+    let rules = dir.path().join("rules.json");
+    fs::write(
+        &rules,
+        r#"{"default": "info", "rules": [{"path": "/db/**", "kinds": ["removed"], "severity": "error"}]}"#,
+    )
+    .unwrap();
+    let old = r#"{"db": {"host": "a"}, "n": 1}"#;
+
+    let run = |new: &str, extra: &[&str]| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        cmd.args([old, new, "--rules"])
+            .arg(&rules)
+            .args(extra)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(r#"{"db": {}, "n": 2}"#, &[]);
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["removed"][0]["severity"], "error");
+    assert_eq!(report["modified"][0]["severity"], "info");
+
+    let output = run(r#"{"db": {}, "n": 2}"#, &["--fail-on", "error"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(!output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Changes with severity error or higher"));
+    assert!(stderr.contains("error: /db/host"));
+
+    let output = run(r#"{"db": {"host": "b"}, "n": 2}"#, &["--fail-on", "error"]);
+    assert!(output.status.success());
+
+    // --fail-on needs rules
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd.args([old, old, "--fail-on", "warn"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--rules"));
+}
+
+#[test]
+fn test_ignore_exact_and_subtree_patterns() {
+    let old = r#"{"user": {"id": 1}, "username": "a"}"#;
+    let new = r#"{"user": {"id": 2}, "username": "b"}"#;
+    let modified_paths = |pattern: &str| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        let output = cmd.args([old, new, "--ignore", pattern]).output().unwrap();
+        assert!(output.status.success());
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        report["modified"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|change| change["path"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(modified_paths("/user"), ["user.id", "username"]);
+    assert_eq!(modified_paths("/user/**"), ["username"]);
+    // Dot notation works the same way
+    assert_eq!(modified_paths("user.id"), ["username"]);
+    assert_eq!(modified_paths("user.**"), ["username"]);
+    // And so do JSONPath queries
+    assert_eq!(modified_paths("$.user"), ["user.id", "username"]);
+    assert_eq!(modified_paths("$.user.**"), ["username"]);
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([old, new, "--ignore", "/user/**/id"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("last token"));
+}
+
+#[test]
+fn test_expect_json_allowlist() {
+    let dir = TempDir::new().unwrap();
+    let expected = dir.path().join("expected.json");
+    fs::write(&expected, r#"["/version", "/config/**"]"#).unwrap();
+    let old = r#"{"version": 1, "config": {"a": 1}, "owner": "x"}"#;
+
+    let run = |new: &str| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        cmd.args([old, new, "--expect-json"])
+            .arg(&expected)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(r#"{"version": 2, "config": {"a": 2, "b": 1}, "owner": "x"}"#);
+    assert!(output.status.success());
+
+    let output = run(r#"{"version": 2, "config": {"a": 1}, "owner": "y"}"#);
+    assert_eq!(output.status.code(), Some(2));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let modified = report["modified"].as_array().unwrap();
+    let owner = modified.iter().find(|c| c["path"] == "owner").unwrap();
+    assert_eq!(owner["unexpected"], true);
+    let version = modified.iter().find(|c| c["path"] == "version").unwrap();
+    assert!(version.get("unexpected").is_none());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Unexpected changes outside --expect-json:\n  /owner"));
+}
+
+#[test]
+fn test_assert_subcommand() {
+    let dir = TempDir::new().unwrap();
+    let old = dir.path().join("old.json");
+    let new = dir.path().join("new.json");
+    fs::write(
+        &old,
+        r#"{"version": "1.0.0", "replicas": 2, "name": "app"}"#,
+    )
+    .unwrap();
+    fs::write(
+        &new,
+        r#"{"version": "2.0.0", "replicas": 3, "name": "app"}"#,
+    )
+    .unwrap();
+
+    let run = |expect: &[&str]| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        cmd.arg("assert").arg(&old).arg(&new);
+        for expectation in expect {
+            cmd.args(["--expect", expectation]);
+        }
+        let output = cmd.output().unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        (output.status.code(), report)
+    };
+
+    let (code, report) = run(&["/version=2.0.0", "/replicas=3"]);
+    assert_eq!(code, Some(0));
+    assert_eq!(report["passed"], true);
+    assert_eq!(report["expectations"][1]["newValue"], 3);
+
+    // Dot notation is reported as the equivalent pointer
+    let (code, report) = run(&["version=2.0.0", "replicas=3"]);
+    assert_eq!(code, Some(0));
+    assert_eq!(report["expectations"][1]["path"], "/replicas");
+
+    let (code, report) = run(&["/version=2.0.1", "/name=app"]);
+    assert_eq!(code, Some(2));
+    assert_eq!(report["passed"], false);
+    assert_eq!(
+        report["expectations"][0]["reason"],
+        "new value differs from the expected value"
+    );
+    assert_eq!(report["expectations"][1]["reason"], "value did not change");
+    assert_eq!(report["unexpected"], json!(["/replicas"]));
+}
+
+#[test]
+fn test_structure_only_flag() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(r#"{"name": "a", "port": 80, "tags": ["x"]}"#)
+        .arg(r#"{"name": "b", "port": "80", "tags": ["y"], "debug": true}"#)
+        .arg("--structure-only")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["added"][0]["path"], "debug");
+    assert_eq!(report["modified"].as_array().unwrap().len(), 1);
+    assert_eq!(report["modified"][0]["path"], "port");
+}
+
+#[test]
+fn test_types_only_flag() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(r#"{"id": 1, "name": "a", "tags": {"x": 1}}"#)
+        .arg(r#"{"id": "1", "name": "b", "tags": ["x"], "new": 1}"#)
+        .arg("--types-only")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["added"], json!([]));
+    let modified = report["modified"].as_array().unwrap();
+    assert_eq!(modified.len(), 2);
+    assert_eq!(modified[0]["path"], "id");
+    assert_eq!(modified[0]["oldType"], "number");
+    assert_eq!(modified[0]["newType"], "string");
+    assert_eq!(modified[1]["newType"], "array");
+}
+
+#[test]
+fn test_string_diff_flag() {
+    let run = |flag: &str| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        let output = cmd
+            .args([flag, r#"{"s": "red car"}"#, r#"{"s": "red cat"}"#])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        report["modified"][0]["stringDiff"].clone()
+    };
+
+    assert_eq!(
+        run("--string-diff"),
+        json!([
+            {"op": "equal", "text": "red "},
+            {"op": "delete", "text": "car"},
+            {"op": "insert", "text": "cat"}
+        ])
+    );
+    assert_eq!(
+        run("--string-diff=char"),
+        json!([
+            {"op": "equal", "text": "red ca"},
+            {"op": "delete", "text": "r"},
+            {"op": "insert", "text": "t"}
+        ])
+    );
+}
+
+#[test]
+fn test_parse_embedded_json_flag() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(r#"{"settings": "{\"port\": 80, \"debug\": false}"}"#)
+        .arg(r#"{"settings": "{\"debug\": false, \"port\": 81}"}"#)
+        .arg("--parse-embedded-json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let modified = report["modified"].as_array().unwrap();
+    assert_eq!(modified.len(), 1);
+    assert_eq!(modified[0]["path"], "settings.port");
+    assert_eq!(modified[0]["newValue"], 81);
+}
+
+#[test]
+fn test_binary_paths_flag() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(r#"{"cert": "aGVsbG8="}"#)
+        .arg(r#"{"cert": "aGVsbE8="}"#)
+        .args(["--binary-paths", "/cert"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let change = &report["modified"][0];
+    assert_eq!(change["oldValue"]["$binary"]["bytes"], 5);
+    assert_eq!(
+        change["oldValue"]["$binary"]["sha256"],
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    );
+    assert_ne!(change["newValue"], change["oldValue"]);
+}
+
+#[test]
+fn test_stats_subcommand() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("doc.json");
+    fs::write(
+        &file,
+        r#"{"users": [{"id": 1}, {"id": 2, "tags": []}], "n": null}"#,
+    )
+    .unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg("stats")
+        .arg(&file)
+        .args(["--top", "2"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["values"], 8);
+    assert_eq!(report["keys"], 5);
+    assert_eq!(report["maxDepth"], 3);
+    assert_eq!(report["depths"], json!([1, 2, 2, 3]));
+    assert_eq!(report["types"]["object"], 3);
+    assert_eq!(report["widestObject"], json!({"path": "", "keys": 2}));
+    assert_eq!(
+        report["longestArray"],
+        json!({"path": "/users", "length": 2})
+    );
+    assert_eq!(
+        report["largestSubtrees"],
+        json!([{"path": "/users", "values": 6}, {"path": "/users/1", "values": 3}])
+    );
+}
+
+#[test]
+fn test_fmt_subcommand() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("doc.json");
+    fs::write(&file, r#"{"b": 1.0, "a": {"z": 1e21, "y": [2.50, -0.0]}}"#).unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd.arg("fmt").arg(&file).output().unwrap();
+    assert!(output.status.success());
+    #[cfg(not(feature = "arbitrary-precision"))]
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\n  \"a\": {\n    \"y\": [\n      2.5,\n      0\n    ],\n    \"z\": 1e21\n  },\n  \"b\": 1\n}\n"
+    );
+    // Numbers are respelled as ECMAScript writes them
+    #[cfg(feature = "arbitrary-precision")]
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\n  \"a\": {\n    \"y\": [\n      2.5,\n      0\n    ],\n    \"z\": 1e+21\n  },\n  \"b\": 1\n}\n"
+    );
+
+    // The alias writes the JCS form in place
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg("canonicalize")
+        .arg(&file)
+        .arg("--jcs")
+        .arg("-o")
+        .arg(&file)
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "{\"a\":{\"y\":[2.5,0],\"z\":1e+21},\"b\":1}\n"
+    );
+}
+
+#[test]
+fn test_canonical_flag() {
+    let dir = TempDir::new().unwrap();
+    let old = dir.path().join("old.json");
+    let new = dir.path().join("new.json");
+    fs::write(&old, r#"{"price": 1.50, "caf\u00e9": true, "n": 100}"#).unwrap();
+    fs::write(&new, r#"{"n": 1e2, "cafe\u0301": true, "price": 1.5}"#).unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd.arg(&old).arg(&new).output().unwrap();
+    let changes: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(changes["added"].as_array().unwrap().len(), 1);
+    assert_eq!(changes["removed"].as_array().unwrap().len(), 1);
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd.arg(&old).arg(&new).arg("--canonical").output().unwrap();
+    assert!(output.status.success());
+    let changes: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        changes,
+        json!({"formatVersion": 2, "added": [], "removed": [], "modified": []})
+    );
+}
+
+#[test]
+fn test_strict_parse_duplicate_keys() {
+    let dir = TempDir::new().unwrap();
+    let old = dir.path().join("old.json");
+    let new = dir.path().join("new.json");
+    fs::write(&old, r#"{"a": 1, "b": {"c": 1, "c": 2}}"#).unwrap();
+    fs::write(&new, r#"{"a": 1, "b": {"c": 3}}"#).unwrap();
+
+    // Without --strict-parse the last value wins silently
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd.arg(&old).arg(&new).output().unwrap();
+    let changes: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(changes["modified"][0]["oldValue"], 2);
+    assert!(changes.get("warnings").is_none());
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(&old)
+        .arg(&new)
+        .arg("--strict-parse")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Duplicate object keys at: b.c"));
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(&old)
+        .arg(&new)
+        .arg("--strict-parse=warn")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let changes: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(changes["modified"][0]["newValue"], 3);
+    assert_eq!(changes["warnings"][0]["path"], "b.c");
+    assert!(changes["warnings"][0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("old input"));
+}
+
+#[test]
+#[cfg(not(feature = "arbitrary-precision"))]
+fn test_numbers_compared_as_floats() {
+    let old = r#"{"id": 123456789012345678901234567890, "price": 1.10}"#;
+    let new = r#"{"id": 123456789012345678901234567891, "price": 1.1}"#;
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd.arg(old).arg(new).output().unwrap();
+    assert!(output.status.success());
+    let changes: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(changes["modified"], json!([]));
+    assert_eq!(changes["warnings"][0]["path"], "id");
+}
+
+#[test]
+#[cfg(feature = "arbitrary-precision")]
+fn test_numbers_compared_exactly() {
+    let old = r#"{"id": 123456789012345678901234567890, "price": 1.10}"#;
+    let new = r#"{"id": 123456789012345678901234567891, "price": 1.1}"#;
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd.arg(old).arg(new).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    // Values are printed exactly as written
+    assert!(stdout.contains("\"oldValue\": 123456789012345678901234567890"));
+    assert!(stdout.contains("\"oldValue\": 1.10"));
+    let changes: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(changes["modified"].as_array().unwrap().len(), 2);
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(old)
+        .arg(new)
+        .arg("--coerce-numbers")
+        .output()
+        .unwrap();
+    let changes: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(changes["modified"].as_array().unwrap().len(), 1);
+    assert_eq!(changes["modified"][0]["path"], "id");
+}
+
+#[test]
+fn test_max_memory_budget() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("big.json");
+    let items: Vec<serde_json::Value> = (0..2000)
+        .map(|i| json!({"id": i, "name": format!("user{}", i)}))
+        .collect();
+    fs::write(&file, serde_json::Value::Array(items).to_string()).unwrap();
+    let file_size = fs::metadata(&file).unwrap().len();
+
+    // Rejected before reading: two copies of the text exceed the budget
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(&file)
+        .arg(&file)
+        .args(["--max-memory", &file_size.to_string()])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Memory budget exceeded"));
+    assert!(stderr.contains("the input files"));
+
+    // Rejected once parsed: the documents take more memory than their text
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(&file)
+        .arg(&file)
+        .args(["--max-memory", &(3 * file_size).to_string()])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("the parsed inputs"));
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(&file)
+        .arg(&file)
+        .args(["--max-memory", "64M"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_timeout_aborts_diff() {
+    let temp_dir = TempDir::new().unwrap();
+    let old_path = temp_dir.path().join("old.json");
+    let new_path = temp_dir.path().join("new.json");
+    let old: Vec<usize> = (0..500_000).collect();
+    let new: Vec<usize> = (1..500_001).collect();
+    fs::write(&old_path, serde_json::to_string(&old).unwrap()).unwrap();
+    fs::write(&new_path, serde_json::to_string(&new).unwrap()).unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(&old_path)
+        .arg(&new_path)
+        .arg("--timeout")
+        .arg("1ms")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Diff timed out after 1ms"), "{}", stderr);
+
+    let small_old = temp_dir.path().join("small_old.json");
+    let small_new = temp_dir.path().join("small_new.json");
+    fs::write(&small_old, json!({"a": 1}).to_string()).unwrap();
+    fs::write(&small_new, json!({"a": 2}).to_string()).unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(&small_old)
+        .arg(&small_new)
+        .arg("--timeout")
+        .arg("1m")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["modified"][0]["path"], "a");
+}
+
+#[test]
+fn test_snapshot_subcommand() {
+    let temp_dir = TempDir::new().unwrap();
+    let golden = temp_dir.path().join("golden").join("response.json");
+    let live = temp_dir.path().join("live.json");
+    fs::write(&live, json!({"status": "ok", "count": 1}).to_string()).unwrap();
+
+    let snapshot = |update: bool| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        cmd.arg("snapshot");
+        if update {
+            cmd.arg("--update");
+        }
+        cmd.arg(&golden).arg("--").arg(&live).output().unwrap()
+    };
+
+    // A missing snapshot fails until it is created with --update
+    let output = snapshot(false);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("run with --update to create it"));
+    assert!(snapshot(true).status.success());
+    let stored: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&golden).unwrap()).unwrap();
+    assert_eq!(stored, json!({"status": "ok", "count": 1}));
+    assert!(snapshot(false).status.success());
+
+    // A changed document fails and prints the diff, until accepted
+    fs::write(&live, json!({"status": "ok", "count": 2}).to_string()).unwrap();
+    let output = snapshot(false);
+    assert_eq!(output.status.code(), Some(2));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["modified"][0]["path"], "count");
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("1 changes; run with --update to accept them"));
+
+    assert!(snapshot(true).status.success());
+    let stored: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&golden).unwrap()).unwrap();
+    assert_eq!(stored["count"], 2);
+    assert!(snapshot(false).status.success());
+}
+
+#[test]
+fn test_group_by_path_depth() {
+    let temp_dir = TempDir::new().unwrap();
+    let old_path = temp_dir.path().join("old.json");
+    let new_path = temp_dir.path().join("new.json");
+    fs::write(
+        &old_path,
+        json!({"users": [{"id": 1, "name": "a"}, {"id": 2, "name": "b"}], "version": 1})
+            .to_string(),
+    )
+    .unwrap();
+    fs::write(
+        &new_path,
+        json!({"users": [{"id": 1, "name": "A"}, {"id": 2, "name": "B", "admin": true}], "version": 2}).to_string(),
+    )
+    .unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(&old_path)
+        .arg(&new_path)
+        .arg("--group-by-path-depth")
+        .arg("2")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        result,
+        json!({
+            "formatVersion": 2,
+            "added": [],
+            "removed": [],
+            "modified": [],
+            "groups": [
+                {"path": "users", "groups": [
+                    {"path": "users[1]",
+                     "added": [{"path": "users[1].admin", "value": true}],
+                     "modified": [{"path": "users[1].name", "oldValue": "b", "newValue": "B"}]},
+                    {"path": "users[0]",
+                     "modified": [{"path": "users[0].name", "oldValue": "a", "newValue": "A"}]}
+                ]},
+                {"path": "version", "modified": [{"path": "version", "oldValue": 1, "newValue": 2}]}
+            ]
+        })
+    );
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(&old_path)
+        .arg(&new_path)
+        .arg("--group-by-path-depth")
+        .arg("0")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_order_entries() {
+    let temp_dir = TempDir::new().unwrap();
+    let old = temp_dir.path().join("old.json");
+    let new = temp_dir.path().join("new.json");
+    fs::write(&old, json!({"b": 1, "a": [1, 2], "c": "x"}).to_string()).unwrap();
+    fs::write(&new, json!({"b": 2, "a": [1, 3], "c": 1}).to_string()).unwrap();
+
+    let paths = |order: &str, format: &str| -> Vec<String> {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        let output = cmd
+            .args([&old, &new])
+            .args(["--order", order, "--format", format])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let entries = report
+            .get("modified")
+            .unwrap_or(&report)
+            .as_array()
+            .unwrap();
+        entries
+            .iter()
+            .map(|entry| entry["path"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    assert_eq!(paths("path", "changes"), ["a[1]", "b", "c"]);
+    assert_eq!(paths("path", "rfc6902"), ["/a/1", "/b", "/c"]);
+    assert_eq!(paths("severity", "changes"), ["c", "a[1]", "b"]);
+}
+
+#[test]
+fn test_sort_keys_traversal() {
+    let temp_dir = TempDir::new().unwrap();
+    let old = temp_dir.path().join("old.json");
+    let new = temp_dir.path().join("new.json");
+    fs::write(&old, r#"{"z": 1, "a": 1}"#).unwrap();
+    fs::write(&new, r#"{"a": 2, "z": 2}"#).unwrap();
+
+    for (flag, expected) in [(None, ["z", "a"]), (Some("--sort-keys"), ["a", "z"])] {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        cmd.args([&old, &new]);
+        if let Some(flag) = flag {
+            cmd.arg(flag);
+        }
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(report["modified"][0]["path"], expected[0]);
+        assert_eq!(report["modified"][1]["path"], expected[1]);
+    }
+}
+
+#[test]
+fn test_with_locations() {
+    let temp_dir = TempDir::new().unwrap();
+    let old = temp_dir.path().join("old.json");
+    let new = temp_dir.path().join("new.json");
+    fs::write(&old, "{\n  \"name\": \"app\",\n  \"port\": 80\n}\n").unwrap();
+    fs::write(&new, "{\"name\": \"app\", \"port\": 8080, \"debug\": true}").unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([&old, &new])
+        .arg("--with-locations")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let modified = &report["modified"][0];
+    assert_eq!(modified["oldLocation"]["file"], old.to_str().unwrap());
+    assert_eq!(modified["oldLocation"]["line"], 3);
+    assert_eq!(modified["oldLocation"]["column"], 11);
+    assert_eq!(modified["newLocation"]["line"], 1);
+    assert_eq!(modified["newLocation"]["column"], 25);
+    let added = &report["added"][0];
+    assert_eq!(added["newLocation"]["column"], 40);
+    assert!(added.get("oldLocation").is_none());
+}
+
+#[test]
+fn test_gcc_format() {
+    let temp_dir = TempDir::new().unwrap();
+    let old = temp_dir.path().join("old.json");
+    let new = temp_dir.path().join("new.json");
+    fs::write(&old, "{\"port\": 80, \"debug\": true}").unwrap();
+    fs::write(&new, "{\n  \"port\": 8080\n}\n").unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([&old, &new])
+        .args(["--format", "gcc"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout,
+        format!(
+            "{}:1:23: removed: $.debug removed (was true)\n{}:2:11: modified: $.port changed from 80 to 8080\n",
+            old.display(),
+            new.display()
+        )
+    );
+}
+
+#[test]
+fn test_fill_defaults() {
+    let temp_dir = TempDir::new().unwrap();
+    let schema = temp_dir.path().join("schema.json");
+    let old = temp_dir.path().join("old.json");
+    let new = temp_dir.path().join("new.json");
+    fs::write(
+        &schema,
+        json!({"properties": {"port": {"default": 80}, "debug": {"default": false}}}).to_string(),
+    )
+    .unwrap();
+    fs::write(&old, json!({"name": "app"}).to_string()).unwrap();
+    fs::write(
+        &new,
+        json!({"name": "app", "port": 80, "debug": true}).to_string(),
+    )
+    .unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([&old, &new])
+        .arg("--fill-defaults")
+        .arg(&schema)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["added"], json!([]));
+    assert_eq!(
+        report["modified"],
+        json!([{"path": "debug", "oldValue": false, "newValue": true}])
+    );
+}
+
+#[test]
+fn test_map_path() {
+    let temp_dir = TempDir::new().unwrap();
+    let old = temp_dir.path().join("old.json");
+    let new = temp_dir.path().join("new.json");
+    fs::write(
+        &old,
+        json!({"env": {"prod": {"db": {"port": 5432}, "seen": 1}}}).to_string(),
+    )
+    .unwrap();
+    fs::write(
+        &new,
+        json!({"env": {"prod": {"db": {"port": 6432}, "seen": 2}}}).to_string(),
+    )
+    .unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([&old, &new])
+        .args(["--map-path", "s#^/env/[^/]+##", "--ignore", "/seen"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["modified"].as_array().unwrap().len(), 1);
+    assert_eq!(report["modified"][0]["path"], "db.port");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.args([&old, &new])
+        .args(["--map-path", "s#^/env#"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("invalid path substitution"));
+}
+
+#[test]
+fn test_fanout_subcommand() {
+    let dir = TempDir::new().unwrap();
+    let baseline = dir.path().join("baseline.json");
+    fs::write(
+        &baseline,
+        json!({"replicas": 3, "debug": false, "region": "eu"}).to_string(),
+    )
+    .unwrap();
+    let targets: Vec<_> = [
+        json!({"replicas": 1, "debug": false, "region": "eu"}),
+        json!({"replicas": 1, "debug": true, "region": "eu"}),
+        json!({"replicas": 1, "debug": false, "region": "us"}),
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, content)| {
+        let path = dir.path().join(format!("env{}.json", i));
+        fs::write(&path, content.to_string()).unwrap();
+        path
+    })
+    .collect();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg("fanout")
+        .arg(&baseline)
+        .args(&targets)
+        .args(["--threshold", "1", "--save-changes"])
+        .arg(dir.path().join("audit"));
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let reports = report["targets"].as_array().unwrap();
+    assert_eq!(reports.len(), 3);
+    assert_eq!(reports[1]["total"], 2);
+    assert_eq!(
+        reports[1]["changes"]["modified"].as_array().unwrap().len(),
+        2
+    );
+
+    let summary = &report["summary"];
+    assert_eq!(summary["targets"], 3);
+    let paths = summary["paths"].as_array().unwrap();
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0]["path"], "replicas");
+    assert_eq!(paths[0]["count"], 3);
+
+    // Each change set renders like any saved diff
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg("render")
+        .arg(dir.path().join("audit").join("env2.changes.json"))
+        .args(["--format", "rfc6902"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let patch: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(patch.as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_matrix_subcommand() {
+    let dir = TempDir::new().unwrap();
+    let envs = [
+        json!({"replicas": 3, "debug": false}),
+        json!({"replicas": 3, "debug": false}),
+        json!({"replicas": 1, "debug": true, "trace": true}),
+    ];
+    let files: Vec<_> = envs
+        .iter()
+        .enumerate()
+        .map(|(i, content)| {
+            let path = dir.path().join(format!("env{}.json", i));
+            fs::write(&path, content.to_string()).unwrap();
+            path
+        })
+        .collect();
+    let details = dir.path().join("pairs");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg("matrix")
+        .args(&files)
+        .arg("--details")
+        .arg(&details)
+        .args(["--format", "rfc6902"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(report["documents"].as_array().unwrap().len(), 3);
+    assert_eq!(report["matrix"], json!([[0, 0, 3], [0, 0, 3], [3, 3, 0]]));
+    let pairs = report["pairs"].as_array().unwrap();
+    assert_eq!(pairs.len(), 3);
+    assert_eq!(pairs[1]["added"], 1);
+    assert_eq!(pairs[1]["modified"], 2);
+
+    let patch: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(details.join("0-2.json")).unwrap()).unwrap();
+    assert_eq!(patch.as_array().unwrap().len(), 3);
+    assert!(pairs[1]["details"].as_str().unwrap().ends_with("0-2.json"));
+}
+
+#[test]
+fn test_array_sets() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([
+            r#"{"tags": ["a", "b"]}"#,
+            r#"{"tags": ["c", "a"]}"#,
+            "--array-sets",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let modified = report["modified"].as_array().unwrap();
+    assert_eq!(modified.len(), 1);
+    assert_eq!(modified[0]["path"], "tags");
+    assert_eq!(modified[0]["addedElements"], json!(["c"]));
+    assert_eq!(modified[0]["removedElements"], json!(["b"]));
+    assert_eq!(modified[0]["common"], json!(["a"]));
+}
+
+#[test]
+fn test_array_diff_similarity() {
+    let old = json!({"services": [
+        {"name": "api", "port": 80, "replicas": 2},
+        {"name": "db", "port": 5432, "replicas": 1}
+    ]});
+    let new = json!({"services": [
+        {"name": "cache", "port": 6379, "replicas": 1},
+        {"name": "api", "port": 8080, "replicas": 2},
+        {"name": "db", "port": 5432, "replicas": 1}
+    ]});
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([old.to_string(), new.to_string()])
+        .args([
+            "--array-diff",
+            "similarity",
+            "--similarity-threshold",
+            "0.6",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    // The new service is added; the shifted one only changed its port
+    assert_eq!(report["added"].as_array().unwrap().len(), 3);
+    assert_eq!(report["added"][0]["path"], "services[0].name");
+    assert_eq!(report["modified"].as_array().unwrap().len(), 1);
+    assert_eq!(report["modified"][0]["path"], "services[1].port");
+    assert!(report["removed"].as_array().unwrap().is_empty());
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.args([old.to_string(), new.to_string()])
+        .args([
+            "--array-diff",
+            "similarity",
+            "--similarity-threshold",
+            "1.5",
+        ])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_array_diff_similarity_describes_each_change() {
+    // The removed element and the modified one were both at index 1
+    let old = json!([{"id": 1, "v": "a"}, {"id": 2, "v": "b"}, {"id": 3, "v": "c"}]);
+    let new = json!([{"id": 3, "v": "c"}, {"id": 1, "v": "x"}]);
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([old.to_string(), new.to_string()])
+        .args([
+            "--array-diff",
+            "similarity",
+            "--similarity-threshold",
+            "0.5",
+            "--describe",
+            "--verbose-changes",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report["modified"],
+        json!([{
+            "path": "[1].v",
+            "oldValue": "a",
+            "newValue": "x",
+            "oldType": "string",
+            "newType": "string",
+            "valueSize": 3,
+            "description": "`[1].v` changed from 'a' to 'x'"
+        }])
+    );
+    let removed = report["removed"].as_array().unwrap();
+    assert_eq!(removed.len(), 2);
+    assert_eq!(removed[0]["path"], "[2].id");
+    assert_eq!(removed[0]["description"], "`[2].id` 2 removed");
+    assert_eq!(removed[1]["path"], "[2].v");
+    assert!(removed[1].get("newType").is_none());
+}
+
+#[test]
+fn test_array_strategy() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join(".rjd.toml"),
+        "[array-strategy]\n\"/users\" = { strategy = \"keyed\", key = \"id\" }\n\"/tags\" = \"unordered\"\n",
+    )
+    .unwrap();
+    let old = json!({"users": [{"id": 1, "on": true}, {"id": 2, "on": true}], "tags": ["a", "b"]});
+    let new = json!({"users": [{"id": 2, "on": true}, {"id": 1, "on": false}], "tags": ["b", "a"]});
+
+    let run = |extra: &[&str]| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        let output = cmd
+            .current_dir(dir.path())
+            .args([old.to_string(), new.to_string()])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    // From the config file
+    let report = run(&[]);
+    assert_eq!(report["modified"].as_array().unwrap().len(), 1);
+    assert_eq!(report["modified"][0]["path"], "users[1].on");
+
+    // The command line replaces the config file's strategies
+    let report = run(&["--array-strategy", "/users=keyed:id"]);
+    assert_eq!(report["modified"].as_array().unwrap().len(), 3);
+    let report = run(&["--no-config", "--array-strategy", "$..tags=unordered"]);
+    assert_eq!(report["modified"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn test_patch_subcommands() {
+    let dir = TempDir::new().unwrap();
+    let document = dir.path().join("config.json");
+    let first = dir.path().join("first.json");
+    let second = dir.path().join("second.json");
+    fs::write(&document, r#"{"version": 1, "hosts": ["a"]}"#).unwrap();
+    fs::write(
+        &first,
+        json!([
+            {"op": "replace", "path": "/version", "value": 2},
+            {"op": "add", "path": "/hosts/-", "value": "b"}
+        ])
+        .to_string(),
+    )
+    .unwrap();
+    fs::write(
+        &second,
+        json!([
+            {"op": "replace", "path": "/version", "value": 1},
+            {"op": "remove", "path": "/hosts/1"}
+        ])
+        .to_string(),
+    )
+    .unwrap();
+
+    let run = |args: &[&std::ffi::OsStr]| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        let output = cmd.arg("patch").args(args).output().unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    assert_eq!(
+        run(&["invert".as_ref(), first.as_os_str(), document.as_os_str()]),
+        json!([
+            {"op": "remove", "path": "/hosts/1"},
+            {"op": "replace", "path": "/version", "value": 1}
+        ])
+    );
+    assert_eq!(
+        run(&["compose".as_ref(), first.as_os_str(), second.as_os_str()]),
+        json!([
+            {"op": "replace", "path": "/version", "value": 1},
+            {"op": "add", "path": "/hosts/-", "value": "b"},
+            {"op": "remove", "path": "/hosts/1"}
+        ])
+    );
+
+    // The second patch undoes the first, so together they do nothing
+    let combined = dir.path().join("combined.json");
+    fs::write(
+        &combined,
+        run(&["compose".as_ref(), first.as_os_str(), second.as_os_str()]).to_string(),
+    )
+    .unwrap();
+    assert_eq!(
+        run(&[
+            "minimize".as_ref(),
+            combined.as_os_str(),
+            document.as_os_str()
+        ]),
+        json!([])
+    );
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args(["patch", "invert"])
+        .arg(&second)
+        .arg(&document)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to invert"));
+}
+
+#[test]
+fn test_reverse_flag() {
+    let dir = TempDir::new().unwrap();
+    let saved = dir.path().join("run.changes.json");
+    let old = r#"{"a": 1, "b": {"c": null}}"#;
+    let new = r#"{"a": 2, "b": {}, "d": [1]}"#;
+    let run = |args: &[&str]| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        let output = cmd.args(args).output().unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    // The inverted diff is the diff the other way round
+    assert_eq!(run(&[old, new, "--reverse"]), run(&[new, old]));
+    assert_eq!(
+        run(&[old, new, "--reverse", "--format", "rfc6902"]),
+        run(&[new, old, "--format", "rfc6902"])
+    );
+
+    // A saved change set renders as its rollback
+    let saved_arg = saved.to_str().unwrap();
+    run(&[old, new, "--save-changes", saved_arg]);
+    assert_eq!(run(&["render", saved_arg, "--reverse"]), run(&[new, old]));
+}
+
+#[test]
+fn test_verify_flag() {
+    let old = r#"{"a": 1, "b": {"c": true, "d": "x"}, "e": null}"#;
+    let new = r#"{"a": "1", "b": {"c": false, "f": 2}}"#;
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd.args([old, new]).output().unwrap();
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let verified = cmd.args([old, new, "--verify"]).output().unwrap();
+    assert!(verified.status.success());
+    assert_eq!(verified.stdout, output.stdout);
+
+    // Array elements and whole subtrees removed or added
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([
+            r#"{"a": [1, 2, 3], "b": [{"c": 1}]}"#,
+            r#"{"a": [1], "d": {"e": [2]}}"#,
+        ])
+        .args(["--verify", "--format", "rfc6902"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Unreported differences leave the patch short of the second input
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([r#"{"a": 1, "b": 1}"#, r#"{"a": 2, "b": 2}"#])
+        .args(["--verify", "--structure-only"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Patch verification failed"), "{}", stderr);
+    assert!(stderr.contains("second at '/a'"), "{}", stderr);
+}
+
+#[test]
+fn test_verbose_changes_flag() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([
+            r#"{"id": 1, "tags": ["a"]}"#,
+            r#"{"id": "1", "name": "ann"}"#,
+        ])
+        .arg("--verbose-changes")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report["added"][0],
+        json!({"path": "name", "value": "ann", "newType": "string", "valueSize": 5})
+    );
+    assert_eq!(
+        report["removed"][0],
+        json!({"path": "tags[0]", "value": "a", "oldType": "string", "valueSize": 3})
+    );
+    assert_eq!(report["modified"][0]["oldType"], "number");
+    assert_eq!(report["modified"][0]["newType"], "string");
+    assert_eq!(report["modified"][0]["valueSize"], 3);
+}
+
+#[test]
+fn test_describe_flags() {
+    let run = |args: &[&str]| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        let output = cmd
+            .args([
+                r#"{"user": {"age": 30}, "tags": ["a", "beta"]}"#,
+                r#"{"user": {"age": 31}, "tags": ["a"]}"#,
+            ])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let report: serde_json::Value = serde_json::from_str(&run(&["--describe"])).unwrap();
+    assert_eq!(
+        report["removed"][0]["description"],
+        "`tags[1]` 'beta' removed"
+    );
+    assert_eq!(
+        report["modified"][0]["description"],
+        "`user.age` changed from 30 to 31"
+    );
+
+    let gcc = run(&[
+        "--describe-template",
+        "{kind} {pointer} ({value})",
+        "--format",
+        "gcc",
+    ]);
+    assert_eq!(
+        gcc,
+        "<inline>:1:37: removed: removed /tags/1 ('beta')\n<inline>:1:18: modified: modified /user/age (31)\n"
+    );
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args(["{}", "{}", "--describe-template", "{size}"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown placeholder '{size}'"));
+}
+
+#[test]
+#[cfg(feature = "template")]
+fn test_template_format() {
+    let dir = TempDir::new().unwrap();
+    let template = dir.path().join("report.tera");
+    fs::write(
+        &template,
+        "{{ total }} change(s)\n{% for c in changes %}{{ c.kind }} {{ c.path }}: {{ c.description }}\n{% endfor %}",
+    )
+    .unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args([
+            r#"{"a": 1, "b": true}"#,
+            r#"{"a": 2, "c": null}"#,
+            "--describe",
+        ])
+        .arg("--template")
+        .arg(&template)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "3 change(s)\n\
+         added c: `c` null added\n\
+         removed b: `b` true removed\n\
+         modified a: `a` changed from 1 to 2\n"
+    );
+
+    // The format and the template go together
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args(["{}", "{}", "--format", "template"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--template FILE"));
+
+    // Templates that do not parse are reported as such
+    fs::write(&template, "{% if %}").unwrap();
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .args(["{}", "{}", "--template"])
+        .arg(&template)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid template"));
+}
+
+#[test]
+fn test_jsonl_format() {
+    let temp = TempDir::new().unwrap();
+    let old = temp.path().join("old.json");
+    let new = temp.path().join("new.json");
+    fs::write(&old, json!({"port": 80, "tags": ["a"]}).to_string()).unwrap();
+    fs::write(
+        &new,
+        json!({"port": 8080, "tags": [], "debug": true}).to_string(),
+    )
+    .unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(&old)
+        .arg(&new)
+        .arg("--format")
+        .arg("jsonl")
+        .assert()
+        .success()
+        .stdout(
+            "{\"kind\":\"added\",\"path\":\"debug\",\"value\":true}\n\
+             {\"kind\":\"removed\",\"path\":\"tags[0]\",\"value\":\"a\"}\n\
+             {\"kind\":\"modified\",\"path\":\"port\",\"oldValue\":80,\"newValue\":8080}\n",
+        );
+
+    // The format follows a .jsonl output file, and no changes write nothing
+    let out = temp.path().join("changes.jsonl");
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(&old)
+        .arg(&old)
+        .arg("--output")
+        .arg(&out)
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&out).unwrap(), "");
+}
+
+#[test]
+fn test_msgpack_format() {
+    let temp = TempDir::new().unwrap();
+    let old = temp.path().join("old.json");
+    let new = temp.path().join("new.json");
+    fs::write(&old, json!({"port": 80, "tags": ["a"]}).to_string()).unwrap();
+    fs::write(&new, json!({"port": 8080, "tags": []}).to_string()).unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(&old)
+        .arg(&new)
+        .arg("--format")
+        .arg("msgpack")
+        .arg("--color")
+        .arg("always")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let changes = rjd::Changes::from_msgpack(&output.stdout).unwrap();
+    let expected = rjd::diff(
+        &json!({"port": 80, "tags": ["a"]}),
+        &json!({"port": 8080, "tags": []}),
+    );
+    assert_eq!(changes.removed, expected.removed);
+    assert_eq!(changes.modified, expected.modified);
+
+    // The format follows a .msgpack output file
+    let out = temp.path().join("scan.msgpack");
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(&old)
+        .arg(&new)
+        .arg("-o")
+        .arg(&out)
+        .assert()
+        .success();
+    assert_eq!(fs::read(&out).unwrap(), output.stdout);
+}
+
+#[test]
+fn test_binary_input_formats() {
+    let temp = TempDir::new().unwrap();
+    // {"name": "ann", "n": 1} and {"name": "bob", "n": 1} in each format
+    let old_msgpack = temp.path().join("old.msgpack");
+    fs::write(&old_msgpack, b"\x82\xa4name\xa3ann\xa1n\x01").unwrap();
+    let new_cbor = temp.path().join("new.cbor");
+    fs::write(&new_cbor, b"\xa2\x64name\x63bob\x61n\x01").unwrap();
+    let new_bson = temp.path().join("new.bson");
+    fs::write(
+        &new_bson,
+        b"\x1a\x00\x00\x00\x02name\x00\x04\x00\x00\x00bob\x00\x10n\x00\x01\x00\x00\x00\x00",
+    )
+    .unwrap();
+    let expected = json!([{"op": "replace", "path": "/name", "value": "bob"}]);
+
+    for new in [&new_cbor, &new_bson] {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        let output = cmd
+            .arg(&old_msgpack)
+            .arg(new)
+            .args(["--format", "rfc6902"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let patch: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(patch, expected);
+    }
+
+    // Binary stdin needs --input-format, which applies to both inputs
+    let old_bson = temp.path().join("old.data");
+    fs::write(
+        &old_bson,
+        b"\x1a\x00\x00\x00\x02name\x00\x04\x00\x00\x00ann\x00\x10n\x00\x01\x00\x00\x00\x00",
+    )
+    .unwrap();
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(&old_bson)
+        .args(["--stdin", "--input-format", "bson", "--format", "rfc6902"])
+        .write_stdin(fs::read(&new_bson).unwrap())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let patch: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(patch, expected);
+
+    // Corrupt documents and conflicting options are reported
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(&old_msgpack)
+        .arg(&old_bson)
+        .args(["--input-format", "cbor"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Failed to decode cbor from"));
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(&old_msgpack)
+        .arg(&new_cbor)
+        .args(["--input-format", "msgpack", "--extract-json"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "--input-format msgpack cannot be used with --extract-json",
+        ));
+}
+
+#[test]
+fn test_deeply_nested_bson_input() {
+    // {"a": {"a": ... {}}} nested 200,000 levels deep
+    let depth = 200_000;
+    let mut bytes = Vec::new();
+    for level in 0..depth {
+        let len = 5 + 8 * (depth - level) as i32;
+        bytes.extend(len.to_le_bytes());
+        bytes.extend(b"\x03a\x00");
+    }
+    bytes.extend([5, 0, 0, 0, 0]);
+    bytes.extend(std::iter::repeat_n(0, depth));
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("deep.bson");
+    fs::write(&file, bytes).unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(&file)
+        .arg(&file)
+        .args(["--input-format", "bson"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "JSON depth exceeded: depth 1001 exceeds limit 1000",
+        ));
+}
+
+#[test]
+fn test_xml_input() {
+    let temp_dir = TempDir::new().unwrap();
+    let file1 = temp_dir.path().join("old.xml");
+    let file2 = temp_dir.path().join("new.xml");
+    fs::write(
+        &file1,
+        r#"<config version="1"><server>a</server><port>80</port></config>"#,
+    )
+    .unwrap();
+    fs::write(
+        &file2,
+        r#"<config version="2"><server>a</server><server>b</server><port>80</port></config>"#,
+    )
+    .unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    let output = cmd
+        .arg(&file1)
+        .arg(&file2)
+        .args(["--format", "rfc6902", "--xml-attribute-prefix", "_"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let patch: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        patch,
+        json!([
+            {"op": "replace", "path": "/config/_version", "value": "2"},
+            {"op": "replace", "path": "/config/server", "value": ["a", "b"]}
+        ])
+    );
+
+    // Not well-formed
+    fs::write(&file2, "<config>").unwrap();
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg(&file1)
+        .arg(&file2)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Failed to decode xml"));
+}
+
+#[test]
+fn test_chain_subcommand() {
+    let dir = TempDir::new().unwrap();
+    let versions = [
+        r#"{"version": 1, "debug": true}"#,
+        r#"{"version": 2, "debug": true}"#,
+        r#"{"version": 3, "name": "app"}"#,
+    ];
+    let files: Vec<_> = versions
+        .iter()
+        .enumerate()
+        .map(|(i, content)| {
+            let path = dir.path().join(format!("v{}.json", i + 1));
+            fs::write(&path, content).unwrap();
+            path
+        })
+        .collect();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg("chain").args(&files).args(["--format", "rfc6902"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let steps = report["steps"].as_array().unwrap();
+    assert_eq!(steps.len(), 2);
+    assert!(steps[0]["from"].as_str().unwrap().ends_with("v1.json"));
+    assert_eq!(
+        steps[0]["changes"],
+        serde_json::json!([{"op": "replace", "path": "/version", "value": 2}])
+    );
+    assert_eq!(steps[1]["changes"].as_array().unwrap().len(), 3);
+
+    let summary = &report["summary"];
+    assert_eq!(summary["versions"], 3);
+    assert_eq!(summary["total"], 4);
+    assert_eq!(
+        summary["paths"][0],
+        serde_json::json!({"path": "version", "steps": 2})
+    );
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("rjd").unwrap();
+    cmd.arg("chain").arg(&files[0]);
+    assert!(!cmd.output().unwrap().status.success());
+}
+
+#[test]
+fn test_fuzz_corpus_export() {
+    let dir = TempDir::new().unwrap();
+    let corpus = dir.path().join("corpus");
+    let export = || {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("rjd").unwrap();
+        cmd.args(["fuzz-corpus", "export", r#"{"a": [1, 2]}"#, r#"{"a": [2]}"#])
+            .arg("--dir")
+            .arg(&corpus);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    };
+
+    let path = export();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "{\"a\":[1,2]}\n---\n{\"a\":[2]}\n"
+    );
+    assert_eq!(export(), path);
+    assert_eq!(fs::read_dir(&corpus).unwrap().count(), 1);
+}
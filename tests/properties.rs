@@ -0,0 +1,218 @@
+//! Property tests for diff, patch and reconstruction round-trips
+
+use proptest::prelude::*;
+use rjd::schema::{diff_with_schema, Schema};
+use rjd::{
+    apply_patch, create_formatter, diff, diff_borrowed, diff_with_options, ArrayDiff, Changes,
+    DiffOptions, SimilarityThreshold,
+};
+use serde_json::{json, Map, Value};
+
+fn arb_scalar() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i32>().prop_map(|n| json!(n)),
+        "[a-z~/]{0,6}".prop_map(Value::String),
+    ]
+}
+
+fn arb_json() -> impl Strategy<Value = Value> {
+    arb_scalar().prop_recursive(3, 32, 5, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..5).prop_map(Value::Array),
+            prop::collection::btree_map("[a-z~/]{1,4}", inner, 0..5)
+                .prop_map(|map| Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+/// An object holding a shuffled array of records with distinct `id`s
+fn arb_records() -> impl Strategy<Value = Value> {
+    let records = prop::collection::btree_map(0u8..6, arb_json(), 0..5)
+        .prop_map(|records| {
+            records
+                .into_iter()
+                .map(|(id, n)| json!({"id": id, "n": n}))
+                .collect::<Vec<_>>()
+        })
+        .prop_shuffle();
+    (records, arb_scalar()).prop_map(|(items, other)| json!({"items": items, "other": other}))
+}
+
+/// Sort the elements of every array, for comparing documents up to their order
+fn sorted_arrays(value: &Value) -> Value {
+    match value {
+        Value::Array(items) => {
+            let mut items: Vec<Value> = items.iter().map(sorted_arrays).collect();
+            items.sort_by_key(|item| item.to_string());
+            Value::Array(items)
+        }
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), sorted_arrays(value)))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+/// Deterministically derive a new document from `old` using `decisions`
+///
+/// The diff reports added or removed subtrees leaf by leaf, which
+/// `reconstruct_before` cannot tell from emptied containers, so mutations keep
+/// array lengths and only add or remove scalar members. Values of any shape
+/// may be replaced by scalars, and nested values are mutated recursively.
+fn mutate(old: &Value, decisions: &mut impl Iterator<Item = u8>) -> Value {
+    fn next(decisions: &mut impl Iterator<Item = u8>) -> u8 {
+        decisions.next().unwrap_or(0)
+    }
+    match old {
+        Value::Object(map) => {
+            let mut new = Map::new();
+            for (key, value) in map {
+                match next(decisions) % 6 {
+                    0 if !value.is_object() && !value.is_array() => {}
+                    1 => {
+                        new.insert(key.clone(), scalar(next(decisions)));
+                    }
+                    _ => {
+                        new.insert(key.clone(), mutate(value, decisions));
+                    }
+                }
+            }
+            if next(decisions).is_multiple_of(3) {
+                let key = format!("added{}", next(decisions));
+                new.insert(key, scalar(next(decisions)));
+            }
+            Value::Object(new)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| match next(decisions) % 3 {
+                    0 => scalar(next(decisions)),
+                    _ => mutate(item, decisions),
+                })
+                .collect(),
+        ),
+        _ if next(decisions).is_multiple_of(4) => scalar(next(decisions)),
+        _ => old.clone(),
+    }
+}
+
+fn scalar(seed: u8) -> Value {
+    match seed % 4 {
+        0 => Value::Null,
+        1 => Value::Bool(seed % 8 == 1),
+        2 => json!(seed),
+        _ => Value::String(format!("s{}", seed)),
+    }
+}
+
+fn rfc6902_patch(old: &Value, new: &Value) -> Value {
+    let mut changes = diff(old, new);
+    changes.before = Some(old.clone());
+    patch_of(&changes)
+}
+
+fn patch_of(changes: &Changes) -> Value {
+    let output = create_formatter("rfc6902", false)
+        .unwrap()
+        .format(changes)
+        .unwrap();
+    serde_json::from_str(&output).unwrap()
+}
+
+/// The changes between two documents under each way of pairing array elements
+fn paired_diffs(old: &Value, new: &Value) -> Vec<(String, Changes)> {
+    let strategies = [
+        ArrayDiff::Unordered,
+        ArrayDiff::Similarity(SimilarityThreshold::default()),
+        ArrayDiff::Similarity(SimilarityThreshold::new(0.3).unwrap()),
+        ArrayDiff::Keyed("id".to_string()),
+    ];
+    let mut diffs: Vec<(String, Changes)> = strategies
+        .into_iter()
+        .map(|strategy| {
+            let options = DiffOptions {
+                array_diff: strategy.clone(),
+                ..DiffOptions::default()
+            };
+            (strategy.to_string(), diff_with_options(old, new, &options))
+        })
+        .collect();
+    let schema = Schema::new(json!({
+        "type": "object",
+        "properties": {"items": {"type": "array", "x-rjd-key": "id"}}
+    }));
+    diffs.push((
+        "schema".to_string(),
+        diff_with_schema(old, new, &schema, &DiffOptions::default()),
+    ));
+    // The patch of a rearranged array is built from both documents
+    for (_, changes) in &mut diffs {
+        changes.before = Some(old.clone());
+    }
+    diffs
+}
+
+proptest! {
+    #[test]
+    fn prop_diff_of_identical_documents_is_empty(doc in arb_json()) {
+        prop_assert!(diff(&doc, &doc).is_empty());
+    }
+
+    #[test]
+    fn prop_patch_turns_old_into_new(
+        old in arb_json(),
+        decisions in prop::collection::vec(any::<u8>(), 64),
+    ) {
+        let new = mutate(&old, &mut decisions.into_iter());
+        let patch = rfc6902_patch(&old, &new);
+        let patched = apply_patch(&old, &patch);
+        prop_assert_eq!(patched, Ok(new));
+    }
+
+    #[test]
+    fn prop_patch_turns_any_document_into_another(old in arb_json(), new in arb_json()) {
+        let patch = rfc6902_patch(&old, &new);
+        prop_assert_eq!(apply_patch(&old, &patch), Ok(new));
+    }
+
+    // Elements paired other than by position are patched by index; only a
+    // reordering no change is reported for is left out of the patch
+    #[test]
+    fn prop_patch_applies_under_each_array_strategy(
+        (old, new) in prop_oneof![(arb_json(), arb_json()), (arb_records(), arb_records())],
+    ) {
+        for (strategy, changes) in paired_diffs(&old, &new) {
+            let patched = apply_patch(&old, &patch_of(&changes));
+            prop_assert!(patched.is_ok(), "{}: {:?}", strategy, patched);
+            let patched = patched.unwrap();
+            prop_assert_eq!(sorted_arrays(&patched), sorted_arrays(&new), "{}", strategy);
+        }
+    }
+
+    #[test]
+    fn prop_reconstruct_before_restores_old(
+        old in arb_json(),
+        decisions in prop::collection::vec(any::<u8>(), 64),
+    ) {
+        let new = mutate(&old, &mut decisions.into_iter());
+        prop_assert_eq!(diff(&old, &new).reconstruct_before(&new), old);
+    }
+
+    #[test]
+    fn prop_diff_borrowed_matches_diff(old in arb_json(), new in arb_json()) {
+        prop_assert_eq!(diff_borrowed(&old, &new).to_owned_changes(), diff(&old, &new));
+    }
+
+    #[test]
+    fn prop_diff_never_panics_on_unrelated_documents(old in arb_json(), new in arb_json()) {
+        let changes = diff(&old, &new);
+        for format in ["changes", "after", "rfc6902", "summary"] {
+            prop_assert!(create_formatter(format, true).unwrap().format(&changes).is_ok());
+        }
+    }
+}